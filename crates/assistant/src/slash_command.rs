@@ -67,11 +67,7 @@ impl SlashCommandCompletionProvider {
             .command_names()
             .into_iter()
             .enumerate()
-            .map(|(ix, def)| StringMatchCandidate {
-                id: ix,
-                string: def.to_string(),
-                char_bag: def.as_ref().into(),
-            })
+            .map(|(ix, def)| StringMatchCandidate::new(ix, def.to_string()))
             .collect::<Vec<_>>();
         let commands = self.commands.clone();
         let command_name = command_name.to_string();
@@ -42,6 +42,8 @@ impl FileSlashCommand {
                             path: full_path.into(),
                             path_prefix: path_prefix.clone(),
                             distance_to_relative_ancestor: 0,
+                            proximity_bonus: 0.0,
+                            is_ignored: false,
                         })
                     })
                     .collect(),
@@ -70,6 +72,7 @@ impl FileSlashCommand {
                     query.as_str(),
                     None,
                     false,
+                    true,
                     100,
                     &cancellation_flag,
                     executor,
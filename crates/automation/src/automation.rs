@@ -0,0 +1,506 @@
+//! Drives a workspace programmatically, the way a user would: opening files, typing, running
+//! actions and reading back buffers and selections.
+//!
+//! Tests use [`Automation`] directly. Out-of-process harnesses can use the same operations over a
+//! Unix socket with a JSON protocol, which Zed serves when `ZED_AUTOMATION_SOCKET` is set to the
+//! path of the socket. See [`serve`].
+
+use std::{ops::Range, path::PathBuf};
+
+use anyhow::{Context as _, Result};
+use editor::Editor;
+use gpui::{AppContext, AsyncAppContext, ViewContext, WindowHandle};
+use language::Point;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use workspace::{SaveIntent, Workspace};
+
+/// Runs operations on a workspace window, and on the editor that's active in it.
+#[derive(Clone)]
+pub struct Automation {
+    workspace: WindowHandle<Workspace>,
+    cx: AsyncAppContext,
+}
+
+impl Automation {
+    pub fn new(workspace: WindowHandle<Workspace>, cx: AsyncAppContext) -> Self {
+        Self { workspace, cx }
+    }
+
+    /// Opens the file at the given absolute path, and focuses it.
+    pub async fn open(&self, abs_path: impl Into<PathBuf>) -> Result<()> {
+        let mut cx = self.cx.clone();
+        let abs_path = abs_path.into();
+        let item = self
+            .workspace
+            .update(&mut cx, |workspace, cx| {
+                workspace.open_abs_path(abs_path, true, cx)
+            })?
+            .await?;
+        self.workspace
+            .update(&mut cx, |_, cx| cx.focus(&item.focus_handle(cx)))
+    }
+
+    /// Returns the text of the active editor, or of the editor that the active item shows, such
+    /// as the results of a project search.
+    pub async fn active_buffer_text(&self) -> Result<String> {
+        self.update_active_editor(|editor, cx| editor.text(cx))
+    }
+
+    /// Types the given text into the active editor at its selections.
+    pub async fn type_text(&self, text: &str) -> Result<()> {
+        self.update_active_editor(|editor, cx| editor.handle_input(text, cx))
+    }
+
+    /// Dispatches the action with the given name, such as `editor::SelectAll`, to the focused
+    /// view, as if its key binding was pressed.
+    pub async fn dispatch_action(&self, name: &str, args: Option<Value>) -> Result<()> {
+        let mut cx = self.cx.clone();
+        self.workspace.update(&mut cx, |_, cx| {
+            let action = cx.build_action(name, args)?;
+            cx.dispatch_action(action);
+            anyhow::Ok(())
+        })?
+    }
+
+    /// Returns the selections of the active editor, in the order they appear in the buffer.
+    pub async fn selections(&self) -> Result<Vec<Range<Point>>> {
+        self.update_active_editor(|editor, cx| {
+            editor
+                .selections
+                .all::<Point>(cx)
+                .into_iter()
+                .map(|selection| selection.range())
+                .collect()
+        })
+    }
+
+    /// Replaces the selections of the active editor.
+    pub async fn set_selections(&self, ranges: Vec<Range<Point>>) -> Result<()> {
+        self.update_active_editor(|editor, cx| {
+            editor.change_selections(None, cx, |selections| selections.select_ranges(ranges))
+        })
+    }
+
+    /// Saves the active item.
+    pub async fn save(&self) -> Result<()> {
+        let mut cx = self.cx.clone();
+        self.workspace
+            .update(&mut cx, |workspace, cx| {
+                workspace.save_active_item(SaveIntent::Save, cx)
+            })?
+            .await
+    }
+
+    fn update_active_editor<R>(
+        &self,
+        update: impl FnOnce(&mut Editor, &mut ViewContext<Editor>) -> R,
+    ) -> Result<R> {
+        let mut cx = self.cx.clone();
+        self.workspace.update(&mut cx, |workspace, cx| {
+            let editor = workspace
+                .active_item(cx)
+                .and_then(|item| item.act_as::<Editor>(cx))
+                .context("the active item isn't an editor")?;
+            anyhow::Ok(editor.update(cx, update))
+        })?
+    }
+
+    /// Handles a request of the JSON protocol, and returns the response to send back.
+    pub async fn handle_message(&self, message: &str) -> String {
+        let response = match serde_json::from_str::<Message>(message) {
+            Ok(message) => Response::new(Some(message.id), self.handle(message.request).await),
+            Err(error) => Response::new(None, Err(error).context("invalid request")),
+        };
+        serde_json::to_string(&response).unwrap_or_default()
+    }
+
+    async fn handle(&self, request: Request) -> Result<Value> {
+        match request {
+            Request::Open { path } => self.open(path).await?,
+            Request::ActiveBufferText => return Ok(self.active_buffer_text().await?.into()),
+            Request::TypeText { text } => self.type_text(&text).await?,
+            Request::DispatchAction { name, args } => self.dispatch_action(&name, args).await?,
+            Request::Selections => {
+                let selections = self
+                    .selections()
+                    .await?
+                    .into_iter()
+                    .map(SelectionRange::from)
+                    .collect::<Vec<_>>();
+                return Ok(serde_json::to_value(selections)?);
+            }
+            Request::SetSelections { selections } => {
+                let ranges = selections.into_iter().map(Range::from).collect();
+                self.set_selections(ranges).await?
+            }
+            Request::Save => self.save().await?,
+        }
+        Ok(Value::Null)
+    }
+}
+
+/// A request of the JSON protocol, such as `{"id": 1, "method": "open", "path": "/a.txt"}`.
+#[derive(Debug, Deserialize)]
+struct Message {
+    id: u64,
+    #[serde(flatten)]
+    request: Request,
+}
+
+/// The operations of [`Automation`], with their parameters as fields next to the method.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+enum Request {
+    Open {
+        path: PathBuf,
+    },
+    ActiveBufferText,
+    TypeText {
+        text: String,
+    },
+    DispatchAction {
+        name: String,
+        #[serde(default)]
+        args: Option<Value>,
+    },
+    Selections,
+    SetSelections {
+        selections: Vec<SelectionRange>,
+    },
+    Save,
+}
+
+/// The response to a request, with the request's `id` and either its `result` or an `error`.
+/// The `id` is null when the request couldn't be parsed.
+#[derive(Debug, Serialize)]
+struct Response {
+    id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl Response {
+    fn new(id: Option<u64>, result: Result<Value>) -> Self {
+        match result {
+            Ok(result) => Self {
+                id,
+                result: Some(result),
+                error: None,
+            },
+            Err(error) => Self {
+                id,
+                result: None,
+                error: Some(format!("{error:#}")),
+            },
+        }
+    }
+}
+
+/// A selection in the JSON protocol, with `[row, column]` positions, both zero-based.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct SelectionRange {
+    start: (u32, u32),
+    end: (u32, u32),
+}
+
+impl From<Range<Point>> for SelectionRange {
+    fn from(range: Range<Point>) -> Self {
+        Self {
+            start: (range.start.row, range.start.column),
+            end: (range.end.row, range.end.column),
+        }
+    }
+}
+
+impl From<SelectionRange> for Range<Point> {
+    fn from(range: SelectionRange) -> Self {
+        Point::new(range.start.0, range.start.1)..Point::new(range.end.0, range.end.1)
+    }
+}
+
+/// Serves the JSON protocol on a Unix socket at the given path, running each request on the
+/// active workspace window. Requests and responses are written one per line.
+#[cfg(unix)]
+pub fn serve(socket_path: PathBuf, cx: &mut AppContext) {
+    cx.spawn(|cx| accept_connections(socket_path, cx))
+        .detach_and_log_err(cx);
+}
+
+#[cfg(not(unix))]
+pub fn serve(_socket_path: PathBuf, _cx: &mut AppContext) {
+    log::error!("automation over a socket is only supported on Unix");
+}
+
+#[cfg(unix)]
+async fn accept_connections(socket_path: PathBuf, cx: AsyncAppContext) -> Result<()> {
+    use std::os::unix::fs::{FileTypeExt as _, PermissionsExt as _};
+    use util::ResultExt as _;
+
+    // Remove the socket if the process that served it has died, but never a file that isn't
+    // a socket.
+    if let Err(error) = std::os::unix::net::UnixStream::connect(&socket_path) {
+        let is_socket = std::fs::symlink_metadata(&socket_path)
+            .map_or(false, |metadata| metadata.file_type().is_socket());
+        if error.kind() == std::io::ErrorKind::ConnectionRefused && is_socket {
+            std::fs::remove_file(&socket_path)?;
+        }
+    }
+    let listener = smol::net::unix::UnixListener::bind(&socket_path)
+        .with_context(|| format!("binding the automation socket at {socket_path:?}"))?;
+    // Anyone who can connect can drive the editor, so only the user running Zed gets to.
+    std::fs::set_permissions(&socket_path, std::fs::Permissions::from_mode(0o600))?;
+    cx.update(|cx| {
+        let socket_path = socket_path.clone();
+        cx.on_app_quit(move |_| {
+            std::fs::remove_file(&socket_path).log_err();
+            async {}
+        })
+        .detach();
+    })?;
+    log::info!("serving automation requests at {socket_path:?}");
+    loop {
+        let (stream, _) = listener.accept().await?;
+        cx.spawn(|cx| handle_connection(stream, cx).log_err())
+            .detach();
+    }
+}
+
+#[cfg(unix)]
+async fn handle_connection(stream: smol::net::unix::UnixStream, cx: AsyncAppContext) -> Result<()> {
+    use smol::io::{AsyncBufReadExt as _, AsyncWriteExt as _, BufReader};
+    use smol::stream::StreamExt as _;
+
+    let mut lines = BufReader::new(stream.clone()).lines();
+    let mut writer = stream;
+    while let Some(line) = lines.next().await {
+        let line = line?;
+        let response = match active_workspace(&cx) {
+            Some(workspace) => {
+                Automation::new(workspace, cx.clone())
+                    .handle_message(&line)
+                    .await
+            }
+            None => serde_json::to_string(&Response::new(
+                None,
+                Err(anyhow::anyhow!("no workspace window is open")),
+            ))?,
+        };
+        writer.write_all(response.as_bytes()).await?;
+        writer.write_all(b"\n").await?;
+    }
+    Ok(())
+}
+
+fn active_workspace(cx: &AsyncAppContext) -> Option<WindowHandle<Workspace>> {
+    cx.update(|cx| {
+        cx.active_window()
+            .and_then(|window| window.downcast::<Workspace>())
+            .or_else(|| {
+                cx.windows()
+                    .into_iter()
+                    .find_map(|window| window.downcast::<Workspace>())
+            })
+    })
+    .ok()
+    .flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::TestAppContext;
+    use project::{FakeFs, Fs as _, Project};
+    use serde_json::json;
+    use std::{path::Path, sync::Arc};
+    use workspace::AppState;
+
+    fn init_test(cx: &mut TestAppContext) -> Arc<AppState> {
+        cx.update(|cx| {
+            let app_state = AppState::test(cx);
+            language::init(cx);
+            editor::init(cx);
+            Project::init_settings(cx);
+            app_state
+        })
+    }
+
+    async fn build_automation(fs: Arc<FakeFs>, cx: &mut TestAppContext) -> Automation {
+        let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+        let window = cx.add_window(|cx| Workspace::test_new(project, cx));
+        Automation::new(window, cx.to_async())
+    }
+
+    #[gpui::test]
+    async fn test_open_edit_and_save(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree("/dir", json!({ "a.txt": "one\nthree\n" }))
+            .await;
+        let automation = build_automation(fs.clone(), cx).await;
+
+        automation.open("/dir/a.txt").await.unwrap();
+        assert_eq!(
+            automation.active_buffer_text().await.unwrap(),
+            "one\nthree\n"
+        );
+
+        automation
+            .set_selections(vec![Point::new(1, 0)..Point::new(1, 0)])
+            .await
+            .unwrap();
+        automation.type_text("two\n").await.unwrap();
+        assert_eq!(
+            automation.active_buffer_text().await.unwrap(),
+            "one\ntwo\nthree\n"
+        );
+        assert_eq!(
+            automation.selections().await.unwrap(),
+            [Point::new(2, 0)..Point::new(2, 0)]
+        );
+
+        automation.save().await.unwrap();
+        cx.run_until_parked();
+        assert_eq!(
+            fs.load(Path::new("/dir/a.txt")).await.unwrap(),
+            "one\ntwo\nthree\n"
+        );
+    }
+
+    #[gpui::test]
+    async fn test_search_with_actions(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree("/dir", json!({ "a.txt": "let a = b + a;\n" }))
+            .await;
+        let automation = build_automation(fs, cx).await;
+        automation.open("/dir/a.txt").await.unwrap();
+        cx.run_until_parked();
+
+        automation
+            .set_selections(vec![Point::new(0, 4)..Point::new(0, 5)])
+            .await
+            .unwrap();
+        automation
+            .dispatch_action("editor::SelectAllMatches", None)
+            .await
+            .unwrap();
+        assert_eq!(
+            automation.selections().await.unwrap(),
+            [
+                Point::new(0, 4)..Point::new(0, 5),
+                Point::new(0, 12)..Point::new(0, 13)
+            ]
+        );
+
+        assert!(automation
+            .dispatch_action("editor::NoSuchAction", None)
+            .await
+            .is_err());
+    }
+
+    #[gpui::test]
+    async fn test_project_search(cx: &mut TestAppContext) {
+        init_test(cx);
+        cx.update(search::init);
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            "/dir",
+            json!({
+                "one.rs": "const ONE: usize = 1;",
+                "two.rs": "const TWO: usize = one::ONE + one::ONE;",
+                "three.rs": "const THREE: usize = 3;",
+            }),
+        )
+        .await;
+        let automation = build_automation(fs, cx).await;
+        automation
+            .workspace
+            .update(cx, |workspace, cx| {
+                workspace.active_pane().update(cx, |pane, cx| {
+                    let search_bar =
+                        cx.new_view(|_| search::project_search::ProjectSearchBar::new());
+                    pane.toolbar()
+                        .update(cx, |toolbar, cx| toolbar.add_item(search_bar, cx))
+                })
+            })
+            .unwrap();
+
+        automation.open("/dir/one.rs").await.unwrap();
+        automation
+            .set_selections(vec![Point::new(0, 6)..Point::new(0, 9)])
+            .await
+            .unwrap();
+        automation
+            .dispatch_action("pane::DeploySearch", None)
+            .await
+            .unwrap();
+        cx.run_until_parked();
+        automation
+            .dispatch_action("menu::Confirm", None)
+            .await
+            .unwrap();
+        cx.run_until_parked();
+
+        let results = automation.active_buffer_text().await.unwrap();
+        assert!(results.contains("const ONE: usize = 1;"), "{results}");
+        assert!(
+            results.contains("const TWO: usize = one::ONE + one::ONE;"),
+            "{results}"
+        );
+        assert!(!results.contains("THREE"), "{results}");
+    }
+
+    #[gpui::test]
+    async fn test_json_protocol(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree("/dir", json!({ "a.txt": "abc" })).await;
+        let automation = build_automation(fs, cx).await;
+
+        let response = automation
+            .handle_message(r#"{"id": 1, "method": "open", "path": "/dir/a.txt"}"#)
+            .await;
+        assert_eq!(response, r#"{"id":1,"result":null}"#);
+
+        let response = automation
+            .handle_message(
+                r#"{"id": 2, "method": "set_selections", "selections": [{"start": [0, 1], "end": [0, 2]}]}"#,
+            )
+            .await;
+        assert_eq!(response, r#"{"id":2,"result":null}"#);
+
+        let response = automation
+            .handle_message(r#"{"id": 3, "method": "type_text", "text": "-"}"#)
+            .await;
+        assert_eq!(response, r#"{"id":3,"result":null}"#);
+
+        let response = automation
+            .handle_message(r#"{"id": 4, "method": "active_buffer_text"}"#)
+            .await;
+        assert_eq!(response, r#"{"id":4,"result":"a-c"}"#);
+
+        let response = automation
+            .handle_message(r#"{"id": 5, "method": "selections"}"#)
+            .await;
+        assert_eq!(
+            response,
+            r#"{"id":5,"result":[{"start":[0,2],"end":[0,2]}]}"#
+        );
+
+        let response = automation
+            .handle_message(
+                r#"{"id": 6, "method": "dispatch_action", "name": "editor::NoSuchAction"}"#,
+            )
+            .await;
+        assert!(response.starts_with(r#"{"id":6,"error":"#), "{response}");
+
+        let response = automation.handle_message("not json").await;
+        assert!(
+            response.starts_with(r#"{"id":null,"error":"invalid request"#),
+            "{response}"
+        );
+    }
+}
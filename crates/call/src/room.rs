@@ -38,6 +38,10 @@ pub enum Event {
     RemoteAudioTracksChanged {
         participant_id: proto::PeerId,
     },
+    ParticipantLeft {
+        participant_id: proto::PeerId,
+        user: Arc<User>,
+    },
     RemoteProjectShared {
         owner: Arc<User>,
         project_id: u64,
@@ -907,6 +911,10 @@ impl Room {
                                     project_id: project.id,
                                 });
                             }
+                            cx.emit(Event::ParticipantLeft {
+                                participant_id: participant.peer_id,
+                                user: participant.user.clone(),
+                            });
                             false
                         }
                     });
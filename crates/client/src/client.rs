@@ -11,7 +11,7 @@ use async_tungstenite::tungstenite::{
     http::{Request, StatusCode},
 };
 use clock::SystemClock;
-use collections::HashMap;
+use collections::{HashMap, HashSet};
 use futures::{
     channel::oneshot, future::LocalBoxFuture, AsyncReadExt, FutureExt, SinkExt, Stream, StreamExt,
     TryFutureExt as _, TryStreamExt,
@@ -30,7 +30,7 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use settings::{Settings, SettingsSources};
 use std::fmt;
-use std::pin::Pin;
+use std::pin::{pin, Pin};
 use std::{
     any::TypeId,
     convert::TryFrom,
@@ -47,7 +47,7 @@ use std::{
 use telemetry::Telemetry;
 use thiserror::Error;
 use url::Url;
-use util::{ResultExt, TryFutureExt};
+use util::{timings, ResultExt, TryFutureExt};
 
 pub use rpc::*;
 pub use telemetry_events::Event;
@@ -305,6 +305,22 @@ struct ClientState {
                 ) -> LocalBoxFuture<'static, Result<()>>,
         >,
     >,
+    #[allow(clippy::type_complexity)]
+    global_message_handlers: HashMap<
+        TypeId,
+        Arc<
+            dyn Send
+                + Sync
+                + Fn(
+                    Box<dyn AnyTypedEnvelope>,
+                    &Arc<Client>,
+                    AsyncAppContext,
+                ) -> LocalBoxFuture<'static, Result<()>>,
+        >,
+    >,
+    /// The types of the messages that arrived without a handler, which are only warned about
+    /// the first time.
+    unhandled_message_types: HashSet<TypeId>,
 }
 
 enum WeakSubscriber {
@@ -367,6 +383,8 @@ impl Default for ClientState {
             entities_by_type_and_remote_id: Default::default(),
             entity_types_by_message_type: Default::default(),
             message_handlers: Default::default(),
+            global_message_handlers: Default::default(),
+            unhandled_message_types: Default::default(),
         }
     }
 }
@@ -396,6 +414,7 @@ impl Drop for Subscription {
                     let mut state = client.state.write();
                     let _ = state.entity_types_by_message_type.remove(id);
                     let _ = state.message_handlers.remove(id);
+                    let _ = state.global_message_handlers.remove(id);
                 }
             }
         }
@@ -457,6 +476,7 @@ impl<T: 'static> Drop for PendingEntitySubscription<T> {
 pub struct TelemetrySettings {
     pub diagnostics: bool,
     pub metrics: bool,
+    pub usage_events: bool,
 }
 
 /// Control what info is collected by Zed.
@@ -470,6 +490,11 @@ pub struct TelemetrySettingsContent {
     ///
     /// Default: true
     pub metrics: Option<bool>,
+    /// Send anonymous usage events, like which features are used, over the collaboration
+    /// connection.
+    ///
+    /// Default: false
+    pub usage_events: Option<bool>,
 }
 
 impl settings::Settings for TelemetrySettings {
@@ -490,6 +515,16 @@ impl settings::Settings for TelemetrySettings {
                 .as_ref()
                 .and_then(|v| v.metrics)
                 .unwrap_or(sources.default.metrics.ok_or_else(Self::missing_default)?),
+            usage_events: sources
+                .user
+                .as_ref()
+                .and_then(|v| v.usage_events)
+                .unwrap_or(
+                    sources
+                        .default
+                        .usage_events
+                        .ok_or_else(Self::missing_default)?,
+                ),
         })
     }
 }
@@ -515,7 +550,7 @@ impl Client {
                 Arc::new(KeychainCredentialsProvider)
             };
 
-        Arc::new(Self {
+        let this = Arc::new(Self {
             id: AtomicU64::new(0),
             peer: Peer::new(0),
             telemetry: Telemetry::new(clock, http.clone(), cx),
@@ -527,7 +562,9 @@ impl Client {
             authenticate: Default::default(),
             #[cfg(any(test, feature = "test-support"))]
             establish_connection: Default::default(),
-        })
+        });
+        this.telemetry.set_rpc_client(Arc::downgrade(&this));
+        this
     }
 
     pub fn production(cx: &mut AppContext) -> Arc<Self> {
@@ -557,6 +594,7 @@ impl Client {
         let mut state = self.state.write();
         state._reconnect_task.take();
         state.message_handlers.clear();
+        state.global_message_handlers.clear();
         state.models_by_message_type.clear();
         state.entities_by_type_and_remote_id.clear();
         state.entity_id_extractors.clear();
@@ -713,7 +751,43 @@ impl Client {
                 handler(subscriber, *envelope, client.clone(), cx).boxed_local()
             }),
         );
-        if prev_handler.is_some() {
+        if prev_handler.is_some() || state.global_message_handlers.contains_key(&message_type_id) {
+            let location = std::panic::Location::caller();
+            panic!(
+                "{}:{} registered handler for the same message {} twice",
+                location.file(),
+                location.line(),
+                std::any::type_name::<M>()
+            );
+        }
+
+        Subscription::Message {
+            client: Arc::downgrade(self),
+            id: message_type_id,
+        }
+    }
+
+    /// Handles the messages of the given type that the server sends on its own accord, and that
+    /// aren't meant for a particular model, such as a contact coming online. Handlers can be
+    /// added whether or not the client is connected. A message type has either a global handler
+    /// or handlers for models, and registering both for the same type panics.
+    #[track_caller]
+    pub fn add_global_message_handler<M, H, F>(self: &Arc<Self>, handler: H) -> Subscription
+    where
+        M: EnvelopedMessage,
+        H: 'static + Fn(TypedEnvelope<M>, Arc<Self>, AsyncAppContext) -> F + Send + Sync,
+        F: 'static + Future<Output = Result<()>>,
+    {
+        let message_type_id = TypeId::of::<M>();
+        let mut state = self.state.write();
+        let prev_handler = state.global_message_handlers.insert(
+            message_type_id,
+            Arc::new(move |envelope, client, cx| {
+                let envelope = envelope.into_any().downcast::<TypedEnvelope<M>>().unwrap();
+                handler(*envelope, client.clone(), cx).boxed_local()
+            }),
+        );
+        if prev_handler.is_some() || state.message_handlers.contains_key(&message_type_id) {
             let location = std::panic::Location::caller();
             panic!(
                 "{}:{} registered handler for the same message {} twice",
@@ -799,7 +873,7 @@ impl Client {
                 handler(handle, *envelope, client.clone(), cx).boxed_local()
             }),
         );
-        if prev_handler.is_some() {
+        if prev_handler.is_some() || state.global_message_handlers.contains_key(&message_type_id) {
             panic!("registered handler for the same message twice");
         }
     }
@@ -820,6 +894,55 @@ impl Client {
         })
     }
 
+    /// Adds a handler for a request that gets a stream of responses, which ends once the stream
+    /// returned by the handler does.
+    pub fn add_model_streaming_request_handler<M, E, H, F, S>(self: &Arc<Self>, handler: H)
+    where
+        M: EntityMessage + RequestMessage,
+        E: 'static,
+        H: 'static + Fn(Model<E>, TypedEnvelope<M>, Arc<Self>, AsyncAppContext) -> F + Send + Sync,
+        F: 'static + Future<Output = Result<S>>,
+        S: 'static + Stream<Item = Result<M::Response>>,
+    {
+        self.add_model_message_handler(move |entity, envelope, client, cx| {
+            Self::respond_to_streaming_request::<M, _, _>(
+                envelope.receipt(),
+                handler(entity, envelope, client.clone(), cx),
+                client,
+            )
+        })
+    }
+
+    async fn respond_to_streaming_request<T, F, S>(
+        receipt: Receipt<T>,
+        responses: F,
+        client: Arc<Self>,
+    ) -> Result<()>
+    where
+        T: RequestMessage,
+        F: Future<Output = Result<S>>,
+        S: Stream<Item = Result<T::Response>>,
+    {
+        let result = async {
+            let mut responses = pin!(responses.await?);
+            while let Some(response) = responses.next().await {
+                client.respond(receipt, response?)?;
+            }
+            anyhow::Ok(())
+        }
+        .await;
+        match result {
+            Ok(()) => {
+                client.end_stream(receipt)?;
+                Ok(())
+            }
+            Err(error) => {
+                client.respond_with_error(receipt, error.to_proto())?;
+                Err(error)
+            }
+        }
+    }
+
     async fn respond_to_request<T: RequestMessage, F: Future<Output = Result<T::Response>>>(
         receipt: Receipt<T>,
         response: F,
@@ -867,6 +990,8 @@ impl Client {
             }
             Status::UpgradeRequired => return Err(EstablishConnectionError::UpgradeRequired)?,
         };
+        let span = timings::start("connect to server");
+        let authenticate_span = span.child("authenticate");
         if was_disconnected {
             self.set_status(Status::Authenticating, cx);
         } else {
@@ -902,6 +1027,8 @@ impl Client {
         if let Credentials::User { user_id, .. } = &credentials {
             self.set_id(*user_id);
         }
+        authenticate_span.end();
+        let _connection_span = span.child("establish connection");
 
         if was_disconnected {
             self.set_status(Status::Connecting, cx);
@@ -1364,7 +1491,9 @@ impl Client {
         if let Status::Connected { connection_id, .. } = *self.status().borrow() {
             Ok(connection_id)
         } else {
-            Err(anyhow!("not connected"))
+            Err(ErrorCode::Disconnected
+                .message("not connected".into())
+                .anyhow())
         }
     }
 
@@ -1381,6 +1510,32 @@ impl Client {
             .map_ok(|envelope| envelope.payload)
     }
 
+    /// Like [`Self::request`], but fails if the response doesn't arrive within the given timeout.
+    pub fn request_with_timeout<T: RequestMessage>(
+        &self,
+        request: T,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<T::Response>> {
+        let client_id = self.id();
+        log::debug!(
+            "rpc request start. client_id:{}. name:{}",
+            client_id,
+            T::NAME
+        );
+        let response = self
+            .connection_id()
+            .map(|conn_id| self.peer.request_with_timeout(conn_id, request, timeout));
+        async move {
+            let response = response?.await;
+            log::debug!(
+                "rpc request finish. client_id:{}. name:{}",
+                client_id,
+                T::NAME
+            );
+            response
+        }
+    }
+
     pub fn request_stream<T: RequestMessage>(
         &self,
         request: T,
@@ -1459,6 +1614,11 @@ impl Client {
         self.peer.respond(receipt, response)
     }
 
+    fn end_stream<T: RequestMessage>(&self, receipt: Receipt<T>) -> Result<()> {
+        log::debug!("rpc end stream. client_id:{}. name:{}", self.id(), T::NAME);
+        self.peer.end_stream(receipt)
+    }
+
     fn respond_with_error<T: RequestMessage>(
         &self,
         receipt: Receipt<T>,
@@ -1515,22 +1675,40 @@ impl Client {
             }
         }
 
-        let subscriber = if let Some(subscriber) = subscriber {
-            subscriber
+        let handler = subscriber.and_then(|subscriber| {
+            Some((
+                subscriber,
+                state.message_handlers.get(&payload_type_id).cloned()?,
+            ))
+        });
+        let global_handler = if handler.is_none() {
+            state.global_message_handlers.get(&payload_type_id).cloned()
         } else {
-            log::info!("unhandled message {}", type_name);
-            self.peer.respond_with_unhandled_message(message).log_err();
-            return;
+            None
         };
-
-        let handler = state.message_handlers.get(&payload_type_id).cloned();
+        let first_unhandled = handler.is_none()
+            && global_handler.is_none()
+            && state.unhandled_message_types.insert(payload_type_id);
         // Dropping the state prevents deadlocks if the handler interacts with rpc::Client.
         // It also ensures we don't hold the lock while yielding back to the executor, as
         // that might cause the executor thread driving this future to block indefinitely.
         drop(state);
 
-        if let Some(handler) = handler {
-            let future = handler(subscriber, message, self, cx.clone());
+        let future = if let Some((subscriber, handler)) = handler {
+            Some(handler(subscriber, message, self, cx.clone()))
+        } else if let Some(handler) = global_handler {
+            Some(handler(message, self, cx.clone()))
+        } else {
+            if first_unhandled {
+                log::warn!("unhandled message {}", type_name);
+            } else {
+                log::debug!("unhandled message {}", type_name);
+            }
+            self.peer.respond_with_unhandled_message(message).log_err();
+            None
+        };
+
+        if let Some(future) = future {
             let client_id = self.id();
             log::debug!(
                 "rpc message received. client_id:{}, sender_id:{:?}, type:{}",
@@ -1560,9 +1738,6 @@ impl Client {
                     }
                 })
                 .detach();
-        } else {
-            log::info!("unhandled message {}", type_name);
-            self.peer.respond_with_unhandled_message(message).log_err();
         }
     }
 
@@ -1774,6 +1949,42 @@ mod tests {
         assert_eq!(server.auth_count(), 2); // Client re-authenticated due to an invalid token
     }
 
+    #[gpui::test]
+    async fn test_handlers_across_reconnection(cx: &mut TestAppContext) {
+        init_test(cx);
+        let user_id = 5;
+        let client = cx.update(|cx| {
+            Client::new(
+                Arc::new(FakeSystemClock::default()),
+                FakeHttpClient::with_404_response(),
+                cx,
+            )
+        });
+        let server = FakeServer::for_client(user_id, &client, cx).await;
+        let mut status = client.status();
+        let (done_tx, mut done_rx) = smol::channel::unbounded();
+        let _subscription =
+            client.add_global_message_handler(move |_: TypedEnvelope<proto::Ping>, _, _| {
+                done_tx.try_send(()).unwrap();
+                async { Ok(()) }
+            });
+
+        server.forbid_connections();
+        server.disconnect();
+        while !matches!(status.next().await, Some(Status::ReconnectionError { .. })) {}
+
+        // Requests fail right away while disconnected, with an error that tells them apart.
+        let error = client.request(proto::Ping {}).await.unwrap_err();
+        assert_eq!(error.error_code(), ErrorCode::Disconnected);
+
+        // Handlers keep receiving messages once reconnected.
+        server.allow_connections();
+        cx.executor().advance_clock(Duration::from_secs(10));
+        while !matches!(status.next().await, Some(Status::Connected { .. })) {}
+        server.send(proto::Ping {});
+        done_rx.next().await.unwrap();
+    }
+
     #[gpui::test(iterations = 10)]
     async fn test_connection_timeout(executor: BackgroundExecutor, cx: &mut TestAppContext) {
         init_test(cx);
@@ -1991,6 +2202,54 @@ mod tests {
         done_rx2.next().await.unwrap();
     }
 
+    #[gpui::test]
+    async fn test_global_message_handler(cx: &mut TestAppContext) {
+        init_test(cx);
+        let user_id = 5;
+        let client = cx.update(|cx| {
+            Client::new(
+                Arc::new(FakeSystemClock::default()),
+                FakeHttpClient::with_404_response(),
+                cx,
+            )
+        });
+
+        // Handlers can be added before connecting, as well as afterwards.
+        let (done_tx1, mut done_rx1) = smol::channel::unbounded();
+        let subscription1 =
+            client.add_global_message_handler(move |_: TypedEnvelope<proto::Ping>, _, _| {
+                done_tx1.try_send(()).unwrap();
+                async { Ok(()) }
+            });
+        let server = FakeServer::for_client(user_id, &client, cx).await;
+        let (done_tx2, mut done_rx2) = smol::channel::unbounded();
+        let _subscription2 = client.add_global_message_handler(
+            move |envelope: TypedEnvelope<proto::UpdateContacts>, _, _| {
+                done_tx2.try_send(envelope.payload.contacts.len()).unwrap();
+                async { Ok(()) }
+            },
+        );
+
+        server.send(proto::Ping {});
+        server.send(proto::UpdateContacts {
+            contacts: vec![Default::default()],
+            ..Default::default()
+        });
+        done_rx1.next().await.unwrap();
+        assert_eq!(done_rx2.next().await.unwrap(), 1);
+
+        // Messages without a handler are remembered, so that they only get warned about once.
+        drop(subscription1);
+        server.send(proto::Ping {});
+        cx.executor().run_until_parked();
+        assert!(done_rx1.try_recv().is_err());
+        assert!(client
+            .state
+            .read()
+            .unhandled_message_types
+            .contains(&TypeId::of::<proto::Ping>()));
+    }
+
     #[gpui::test]
     async fn test_dropping_subscription_in_handler(cx: &mut TestAppContext) {
         init_test(cx);
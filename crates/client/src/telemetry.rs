@@ -1,6 +1,6 @@
 mod event_coalescer;
 
-use crate::{ChannelId, TelemetrySettings};
+use crate::{ChannelId, Client, GlobalClient, TelemetrySettings};
 use chrono::{DateTime, Utc};
 use clock::SystemClock;
 use futures::Future;
@@ -9,20 +9,24 @@ use http::{self, HttpClient, HttpClientWithUrl, Method};
 use once_cell::sync::Lazy;
 use parking_lot::Mutex;
 use release_channel::ReleaseChannel;
+use rpc::proto;
 use settings::{Settings, SettingsStore};
 use sha2::{Digest, Sha256};
 use std::io::Write;
-use std::{env, mem, path::PathBuf, sync::Arc, time::Duration};
+use std::{
+    env, mem,
+    path::PathBuf,
+    sync::{Arc, Weak},
+    time::Duration,
+};
 use sysinfo::{CpuRefreshKind, Pid, ProcessRefreshKind, RefreshKind, System};
 use telemetry_events::{
     ActionEvent, AppEvent, AssistantEvent, AssistantKind, CallEvent, CpuEvent, EditEvent,
     EditorEvent, Event, EventRequestBody, EventWrapper, ExtensionEvent, InlineCompletionEvent,
-    MemoryEvent, SettingEvent,
+    MemoryEvent, SettingEvent, MAX_USAGE_EVENTS_PER_BATCH, USAGE_EVENT_PROPERTIES,
 };
 use tempfile::NamedTempFile;
-#[cfg(not(debug_assertions))]
-use util::ResultExt;
-use util::TryFutureExt;
+use util::{ResultExt, TryFutureExt};
 
 use self::event_coalescer::EventCoalescer;
 
@@ -47,6 +51,10 @@ struct TelemetryState {
     first_event_date_time: Option<DateTime<Utc>>,
     event_coalescer: EventCoalescer,
     max_queue_size: usize,
+    rpc_client: Weak<Client>,
+    usage_events_queue: Vec<proto::TelemetryEvent>,
+    flush_usage_events_task: Option<Task<()>>,
+    first_usage_event_date_time: Option<DateTime<Utc>>,
 
     os_name: String,
     app_version: String,
@@ -64,6 +72,11 @@ const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
 
 #[cfg(not(debug_assertions))]
 const FLUSH_INTERVAL: Duration = Duration::from_secs(60 * 5);
+
+/// Usage events beyond this many are dropped until the queue is flushed, so that the queue
+/// always fits in one batch.
+const MAX_USAGE_EVENTS_QUEUE_LEN: usize = MAX_USAGE_EVENTS_PER_BATCH;
+
 static ZED_CLIENT_CHECKSUM_SEED: Lazy<Option<Vec<u8>>> = Lazy::new(|| {
     option_env!("ZED_CLIENT_CHECKSUM_SEED")
         .map(|s| s.as_bytes().into())
@@ -180,6 +193,10 @@ impl Telemetry {
             first_event_date_time: None,
             event_coalescer: EventCoalescer::new(clock.clone()),
             max_queue_size: MAX_QUEUE_LEN,
+            rpc_client: Weak::new(),
+            usage_events_queue: Vec::new(),
+            flush_usage_events_task: None,
+            first_usage_event_date_time: None,
 
             os_version: None,
             os_name: os_name(),
@@ -206,6 +223,11 @@ impl Telemetry {
             move |cx| {
                 let mut state = state.lock();
                 state.settings = *TelemetrySettings::get_global(cx);
+                if !state.settings.usage_events {
+                    state.usage_events_queue.clear();
+                    state.flush_usage_events_task.take();
+                    state.first_usage_event_date_time = None;
+                }
             }
         })
         .detach();
@@ -242,6 +264,11 @@ impl Telemetry {
         Task::ready(())
     }
 
+    /// Sets the client whose connection usage events are sent over.
+    pub(crate) fn set_rpc_client(&self, client: Weak<Client>) {
+        self.state.lock().rpc_client = client;
+    }
+
     pub fn log_file_path(&self) -> Option<PathBuf> {
         Some(self.state.lock().log_file.as_ref()?.path().to_path_buf())
     }
@@ -450,6 +477,87 @@ impl Telemetry {
         self.report_event(event)
     }
 
+    /// Queues an anonymous usage event, if the user opted into sending them. Queued events
+    /// are sent in batches over the collaboration connection, or dropped when there is none.
+    ///
+    /// Only the properties listed in [`USAGE_EVENT_PROPERTIES`] are kept.
+    pub fn report(
+        self: &Arc<Self>,
+        event_name: &'static str,
+        properties: impl IntoIterator<Item = (&'static str, String)>,
+    ) {
+        let mut state = self.state.lock();
+
+        if !state.settings.usage_events {
+            return;
+        }
+
+        if state.usage_events_queue.len() >= MAX_USAGE_EVENTS_QUEUE_LEN {
+            log::debug!("usage events queue is full, dropping {event_name:?} event");
+            return;
+        }
+
+        let properties = properties
+            .into_iter()
+            .filter_map(|(key, value)| {
+                if USAGE_EVENT_PROPERTIES.contains(&key) {
+                    Some((key.to_string(), value))
+                } else {
+                    log::error!("dropping property {key:?} of usage event {event_name:?}");
+                    None
+                }
+            })
+            .collect();
+
+        let date_time = self.clock.utc_now();
+        let milliseconds_since_first_event = match state.first_usage_event_date_time {
+            Some(first_event_date_time) => {
+                date_time.timestamp_millis() - first_event_date_time.timestamp_millis()
+            }
+            None => {
+                state.first_usage_event_date_time = Some(date_time);
+                0
+            }
+        };
+
+        state.usage_events_queue.push(proto::TelemetryEvent {
+            name: event_name.to_string(),
+            milliseconds_since_first_event: milliseconds_since_first_event.max(0) as u64,
+            properties,
+        });
+
+        if state.flush_usage_events_task.is_none() {
+            let this = self.clone();
+            let executor = self.executor.clone();
+            state.flush_usage_events_task = Some(self.executor.spawn(async move {
+                executor.timer(FLUSH_INTERVAL).await;
+                this.flush_usage_events();
+            }));
+        }
+    }
+
+    /// Sends the queued usage events over the collaboration connection.
+    pub fn flush_usage_events(self: &Arc<Self>) {
+        let mut state = self.state.lock();
+        state.first_usage_event_date_time = None;
+        state.flush_usage_events_task.take();
+        let events = mem::take(&mut state.usage_events_queue);
+        let client = state.rpc_client.upgrade();
+        let enabled = state.settings.usage_events;
+        drop(state);
+
+        if events.is_empty() || !enabled {
+            return;
+        }
+
+        match client.filter(|client| client.status().borrow().is_connected()) {
+            Some(client) => {
+                client.send(proto::TelemetryBatch { events }).log_err();
+            }
+            None => log::debug!("dropping {} usage events while offline", events.len()),
+        }
+    }
+
     fn report_event(self: &Arc<Self>, event: Event) {
         let mut state = self.state.lock();
 
@@ -579,12 +687,24 @@ impl Telemetry {
     }
 }
 
+/// Reports a usage event through the global client, see [`Telemetry::report`].
+pub fn report(
+    event_name: &'static str,
+    properties: impl IntoIterator<Item = (&'static str, String)>,
+    cx: &AppContext,
+) {
+    if let Some(client) = cx.try_global::<GlobalClient>() {
+        client.0.telemetry().report(event_name, properties);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test::FakeServer;
     use chrono::TimeZone;
     use clock::FakeSystemClock;
-    use gpui::TestAppContext;
+    use gpui::{TestAppContext, UpdateGlobal as _};
     use http::FakeHttpClient;
 
     #[gpui::test]
@@ -720,6 +840,84 @@ mod tests {
         });
     }
 
+    #[gpui::test]
+    async fn test_disabling_usage_events_stops_sending(cx: &mut TestAppContext) {
+        init_test(cx);
+        let client = cx.update(|cx| {
+            crate::init_settings(cx);
+            Client::new(
+                Arc::new(FakeSystemClock::default()),
+                FakeHttpClient::with_404_response(),
+                cx,
+            )
+        });
+        let server = FakeServer::for_client(5, &client, cx).await;
+        let telemetry = client.telemetry().clone();
+
+        // Nothing is queued until the user opts in.
+        telemetry.report("open_panel", [("kind", "terminal".to_string())]);
+        assert!(telemetry.state.lock().usage_events_queue.is_empty());
+
+        set_usage_events(true, cx);
+        telemetry.report(
+            "open_panel",
+            [
+                ("kind", "terminal".to_string()),
+                ("path", "/home/user/secret.txt".to_string()),
+            ],
+        );
+        cx.executor().advance_clock(FLUSH_INTERVAL);
+        let batch = server
+            .receive::<proto::TelemetryBatch>()
+            .await
+            .unwrap()
+            .payload;
+        assert_eq!(batch.events.len(), 1);
+        assert_eq!(batch.events[0].name, "open_panel");
+        assert_eq!(
+            batch.events[0].properties.keys().collect::<Vec<_>>(),
+            ["kind"],
+            "properties outside of the allowlist are dropped"
+        );
+
+        // Disabling the setting drops what is queued, and nothing reported afterwards is sent.
+        telemetry.report("open_panel", [("kind", "project".to_string())]);
+        set_usage_events(false, cx);
+        assert!(telemetry.state.lock().usage_events_queue.is_empty());
+        assert!(telemetry.state.lock().flush_usage_events_task.is_none());
+        telemetry.report("open_panel", [("kind", "outline".to_string())]);
+        cx.executor().advance_clock(FLUSH_INTERVAL);
+        cx.executor().run_until_parked();
+
+        // The next batch the server gets only has the events reported after re-enabling.
+        set_usage_events(true, cx);
+        telemetry.report("close_panel", [("kind", "terminal".to_string())]);
+        cx.executor().advance_clock(FLUSH_INTERVAL);
+        let batch = server
+            .receive::<proto::TelemetryBatch>()
+            .await
+            .unwrap()
+            .payload;
+        assert_eq!(
+            batch
+                .events
+                .iter()
+                .map(|event| event.name.as_str())
+                .collect::<Vec<_>>(),
+            ["close_panel"]
+        );
+    }
+
+    fn set_usage_events(enabled: bool, cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            SettingsStore::update_global(cx, |store, cx| {
+                store.update_user_settings::<TelemetrySettings>(cx, |settings| {
+                    settings.usage_events = Some(enabled);
+                });
+            });
+        });
+    }
+
     // TODO:
     // Test settings
     // Update FakeHTTPClient to keep track of the number of requests and assert on it
@@ -2,13 +2,16 @@ use super::{proto, Client, Status, TypedEnvelope};
 use anyhow::{anyhow, Context, Result};
 use collections::{hash_map::Entry, HashMap, HashSet};
 use feature_flags::FeatureFlagAppExt;
-use futures::{channel::mpsc, Future, StreamExt};
+use futures::{channel::mpsc, future, stream, stream::FusedStream as _, Future, StreamExt};
 use gpui::{
     AppContext, AsyncAppContext, EventEmitter, Model, ModelContext, SharedString, SharedUri, Task,
     WeakModel,
 };
 use postage::{sink::Sink, watch};
-use rpc::proto::{RequestMessage, UsersResponse};
+use rpc::{
+    proto::{RequestMessage, UsersResponse},
+    subscribe_with_snapshot, SnapshotSubscription, SnapshotUpdate, SubscriptionMessage,
+};
 use std::sync::{Arc, Weak};
 use text::ReplicaId;
 use util::TryFutureExt as _;
@@ -134,6 +137,31 @@ enum UpdateContacts {
     Clear(postage::barrier::Sender),
 }
 
+impl SnapshotSubscription for UpdateContacts {
+    type Snapshot = Self;
+    type Delta = Self;
+
+    fn classify(self) -> SubscriptionMessage<Self, Self> {
+        match self {
+            UpdateContacts::Update(message) => match message.classify() {
+                SubscriptionMessage::Snapshot(message) => {
+                    SubscriptionMessage::Snapshot(UpdateContacts::Update(message))
+                }
+                SubscriptionMessage::Delta(message) => {
+                    SubscriptionMessage::Delta(UpdateContacts::Update(message))
+                }
+            },
+            // Clearing the contacts replaces them with nothing, like an empty snapshot.
+            UpdateContacts::Clear(barrier) => {
+                SubscriptionMessage::Snapshot(UpdateContacts::Clear(barrier))
+            }
+            UpdateContacts::Wait(barrier) => {
+                SubscriptionMessage::Delta(UpdateContacts::Wait(barrier))
+            }
+        }
+    }
+}
+
 impl UserStore {
     pub fn new(client: Arc<Client>, cx: &mut ModelContext<Self>) -> Self {
         let (mut current_user_tx, current_user_rx) = watch::channel();
@@ -156,13 +184,30 @@ impl UserStore {
             update_contacts_tx,
             _maintain_contacts: cx.spawn(|this, mut cx| async move {
                 let _subscriptions = rpc_subscriptions;
-                while let Some(message) = update_contacts_rx.next().await {
-                    if let Ok(task) =
-                        this.update(&mut cx, |this, cx| this.update_contacts(message, cx))
-                    {
-                        task.log_err().await;
-                    } else {
-                        break;
+                while !update_contacts_rx.is_terminated() {
+                    let (snapshot, updates) =
+                        match subscribe_with_snapshot(&mut update_contacts_rx).await {
+                            Ok(subscription) => subscription,
+                            Err(error) => {
+                                log::error!("error receiving contacts: {error:#}");
+                                continue;
+                            }
+                        };
+                    let mut messages =
+                        stream::once(future::ready(snapshot)).chain(updates.map(|update| {
+                            match update {
+                                SnapshotUpdate::Resync(message)
+                                | SnapshotUpdate::Delta(message) => message,
+                            }
+                        }));
+                    while let Some(message) = messages.next().await {
+                        if let Ok(task) =
+                            this.update(&mut cx, |this, cx| this.update_contacts(message, cx))
+                        {
+                            task.log_err().await;
+                        } else {
+                            return;
+                        }
                     }
                 }
             }),
@@ -350,6 +395,13 @@ impl UserStore {
                         HashSet::<u64>::from_iter(message.remove_outgoing_requests.iter().copied());
 
                     this.update(&mut cx, |this, cx| {
+                        // A snapshot replaces all of the contacts and requests
+                        if message.is_snapshot {
+                            this.contacts.clear();
+                            this.incoming_contact_requests.clear();
+                            this.outgoing_contact_requests.clear();
+                        }
+
                         // Remove contacts
                         this.contacts
                             .retain(|contact| !removed_contacts.contains(&contact.user.id));
@@ -777,3 +829,101 @@ impl Collaborator {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::FakeServer;
+    use clock::FakeSystemClock;
+    use gpui::TestAppContext;
+    use http::FakeHttpClient;
+    use settings::SettingsStore;
+
+    #[gpui::test]
+    async fn test_contacts_snapshot_and_deltas(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let settings_store = SettingsStore::test(cx);
+            cx.set_global(settings_store);
+            crate::init_settings(cx);
+        });
+        let client = cx.update(|cx| {
+            Client::new(
+                Arc::new(FakeSystemClock::default()),
+                FakeHttpClient::with_404_response(),
+                cx,
+            )
+        });
+        let server = FakeServer::for_client(5, &client, cx).await;
+        let user_store = server.build_user_store(client.clone(), cx).await;
+        let outgoing_requests = |cx: &mut TestAppContext| {
+            user_store.read_with(cx, |store, _| {
+                store
+                    .outgoing_contact_requests()
+                    .iter()
+                    .map(|user| user.github_login.clone())
+                    .collect::<Vec<_>>()
+            })
+        };
+
+        // A delta that arrives before the snapshot has nothing to apply to, and is dropped.
+        server.send(proto::UpdateContacts {
+            outgoing_requests: vec![6],
+            ..Default::default()
+        });
+        cx.executor().run_until_parked();
+        assert!(outgoing_requests(cx).is_empty());
+
+        server.send(proto::UpdateContacts {
+            outgoing_requests: vec![6],
+            is_snapshot: true,
+            ..Default::default()
+        });
+        respond_with_users(&server, &[6]).await;
+        cx.executor().run_until_parked();
+        assert_eq!(outgoing_requests(cx), ["user-6"]);
+
+        server.send(proto::UpdateContacts {
+            outgoing_requests: vec![7],
+            ..Default::default()
+        });
+        respond_with_users(&server, &[7]).await;
+        cx.executor().run_until_parked();
+        assert_eq!(outgoing_requests(cx), ["user-6", "user-7"]);
+
+        // When the backfill has a gap, a new snapshot replaces the contacts.
+        server.send(proto::UpdateContacts {
+            outgoing_requests: vec![8],
+            is_snapshot: true,
+            ..Default::default()
+        });
+        respond_with_users(&server, &[8]).await;
+        cx.executor().run_until_parked();
+        assert_eq!(outgoing_requests(cx), ["user-8"]);
+
+        server.send(proto::UpdateContacts {
+            remove_outgoing_requests: vec![8],
+            outgoing_requests: vec![6],
+            ..Default::default()
+        });
+        cx.executor().run_until_parked();
+        assert_eq!(outgoing_requests(cx), ["user-6"]);
+    }
+
+    async fn respond_with_users(server: &FakeServer, user_ids: &[u64]) {
+        let request = server.receive::<proto::GetUsers>().await.unwrap();
+        assert_eq!(request.payload.user_ids, user_ids);
+        server.respond(
+            request.receipt(),
+            proto::UsersResponse {
+                users: user_ids
+                    .iter()
+                    .map(|&id| proto::User {
+                        id,
+                        github_login: format!("user-{id}"),
+                        avatar_url: format!("http://avatar.com/user-{id}"),
+                    })
+                    .collect(),
+            },
+        );
+    }
+}
@@ -1,4 +1,5 @@
 mod system_clock;
+mod version_stream;
 
 use smallvec::SmallVec;
 use std::{
@@ -7,6 +8,7 @@ use std::{
 };
 
 pub use system_clock::*;
+pub use version_stream::*;
 
 /// A unique identifier for each distributed node.
 pub type ReplicaId = u16;
@@ -47,7 +49,17 @@ impl Global {
         }
     }
 
+    pub fn observe_all(&mut self, timestamps: impl IntoIterator<Item = Lamport>) {
+        for timestamp in timestamps {
+            self.observe(timestamp);
+        }
+    }
+
     pub fn join(&mut self, other: &Self) {
+        if self.0.is_empty() {
+            self.0.clone_from(&other.0);
+            return;
+        }
         if other.0.len() > self.0.len() {
             self.0.resize(other.0.len(), 0);
         }
@@ -134,6 +146,23 @@ impl Global {
         false
     }
 
+    /// Sets the entry of the timestamp's replica to its value, even if that is lower.
+    fn set(&mut self, timestamp: Lamport) {
+        let index = timestamp.replica_id as usize;
+        if index >= self.0.len() {
+            if timestamp.value == 0 {
+                return;
+            }
+            self.0.resize(index + 1, 0);
+        }
+        self.0[index] = timestamp.value;
+
+        // Keep the clock free of trailing zeros, so that equal versions compare equal.
+        while self.0.last() == Some(&0) {
+            self.0.pop();
+        }
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = Lamport> + '_ {
         self.0.iter().enumerate().map(|(replica_id, seq)| Lamport {
             replica_id: replica_id as ReplicaId,
@@ -145,9 +174,7 @@ impl Global {
 impl FromIterator<Lamport> for Global {
     fn from_iter<T: IntoIterator<Item = Lamport>>(locals: T) -> Self {
         let mut result = Self::new();
-        for local in locals {
-            result.observe(local);
-        }
+        result.observe_all(locals);
         result
     }
 }
@@ -185,14 +212,26 @@ impl Lamport {
         }
     }
 
+    /// Returns the current timestamp and advances the clock.
+    ///
+    /// Panics when the clock runs out of values rather than wrapping around, as two
+    /// operations with the same timestamp would corrupt every replica's history.
     pub fn tick(&mut self) -> Self {
         let timestamp = *self;
-        self.value += 1;
+        self.value = Self::next_value(self.value);
         timestamp
     }
 
+    /// Advances the clock past the given timestamp. Panics like [`Lamport::tick`] when the
+    /// clock runs out of values.
     pub fn observe(&mut self, timestamp: Self) {
-        self.value = cmp::max(self.value, timestamp.value) + 1;
+        self.value = Self::next_value(cmp::max(self.value, timestamp.value));
+    }
+
+    fn next_value(value: Seq) -> Seq {
+        value
+            .checked_add(1)
+            .unwrap_or_else(|| panic!("lamport clock overflowed at {value}"))
     }
 }
 
@@ -214,3 +253,42 @@ impl fmt::Debug for Global {
         write!(f, "}}")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lamport_clock_near_overflow() {
+        let mut clock = Lamport {
+            replica_id: 1,
+            value: Seq::MAX - 2,
+        };
+        assert_eq!(clock.tick().value, Seq::MAX - 2);
+        clock.observe(Lamport {
+            replica_id: 2,
+            value: Seq::MAX - 1,
+        });
+        assert_eq!(clock.value, Seq::MAX);
+    }
+
+    #[test]
+    #[should_panic(expected = "lamport clock overflowed")]
+    fn test_lamport_tick_overflow() {
+        let mut clock = Lamport {
+            replica_id: 1,
+            value: Seq::MAX,
+        };
+        clock.tick();
+    }
+
+    #[test]
+    #[should_panic(expected = "lamport clock overflowed")]
+    fn test_lamport_observe_overflow() {
+        let mut clock = Lamport::new(1);
+        clock.observe(Lamport {
+            replica_id: 2,
+            value: Seq::MAX,
+        });
+    }
+}
@@ -0,0 +1,362 @@
+//! Compact encoding of the versions attached to a stream of operations.
+//!
+//! Consecutive versions on a stream mostly differ in an entry or two, so a version is sent as
+//! the entries that changed since the previous version on the same stream. Every
+//! [`FULL_SYNC_INTERVAL`] versions, and the first time after [`VersionEncoder::reset`], a
+//! version is sent in full, which bounds how far back a decoder has to wait for.
+//!
+//! Replicas that are gone for good can be retired. Full versions leave out the entry of a
+//! retired replica while it's at the replica's final value, so that the ids of long-gone
+//! replicas stop being sent.
+
+use crate::{Global, Lamport, ReplicaId, Seq};
+use std::{
+    cmp,
+    collections::{btree_map, BTreeMap, BTreeSet},
+    mem,
+};
+
+/// How many versions an encoder sends from one full version to the next.
+pub const FULL_SYNC_INTERVAL: u32 = 32;
+
+/// A version as sent on a stream.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EncodedVersion {
+    /// The position of the version on its stream.
+    pub seq: u32,
+    /// Whether `entries` hold the whole version, rather than the entries that changed since
+    /// the version at `seq - 1`.
+    pub full: bool,
+    pub entries: Vec<Lamport>,
+    /// Replicas that were retired along with this version, at their final values.
+    pub retired: Vec<Lamport>,
+    /// How many replicas were retired up to and including this version. Only set on full
+    /// versions, as those can't be decoded before all of these retirements are known.
+    pub retired_count: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DecodedVersion {
+    Version(Global),
+    /// The version depends on versions that haven't been decoded yet.
+    Pending,
+    /// The version was decoded before.
+    Duplicate,
+}
+
+/// Encodes the versions sent on one stream.
+#[derive(Clone, Debug, Default)]
+pub struct VersionEncoder {
+    next_seq: u32,
+    previous: Option<Global>,
+    retired: BTreeMap<ReplicaId, Seq>,
+    unsent_retirements: Vec<Lamport>,
+}
+
+impl VersionEncoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn encode(&mut self, version: &Global) -> EncodedVersion {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+
+        let mut encoded = EncodedVersion {
+            seq,
+            retired: mem::take(&mut self.unsent_retirements),
+            ..Default::default()
+        };
+        match self
+            .previous
+            .as_ref()
+            .filter(|_| seq % FULL_SYNC_INTERVAL != 0)
+        {
+            Some(previous) => {
+                let len = cmp::max(previous.0.len(), version.0.len());
+                encoded.entries = (0..len as ReplicaId)
+                    .filter_map(|replica_id| {
+                        let value = version.get(replica_id);
+                        let changed = value != previous.get(replica_id);
+                        changed.then_some(Lamport { replica_id, value })
+                    })
+                    .collect();
+            }
+            None => {
+                let retired_len = self
+                    .retired
+                    .last_key_value()
+                    .map_or(0, |(replica_id, _)| *replica_id as usize + 1);
+                let len = cmp::max(version.0.len(), retired_len);
+                encoded.entries = (0..len as ReplicaId)
+                    .filter_map(|replica_id| {
+                        let value = version.get(replica_id);
+                        let implied_value = self.retired.get(&replica_id).copied();
+                        let implied = value == implied_value.unwrap_or(0);
+                        (!implied).then_some(Lamport { replica_id, value })
+                    })
+                    .collect();
+                encoded.full = true;
+                encoded.retired_count = self.retired.len() as u32;
+            }
+        }
+        self.previous = Some(version.clone());
+        encoded
+    }
+
+    /// Sends the next version in full, e.g. because the other side may have missed versions.
+    pub fn reset(&mut self) {
+        self.previous = None;
+    }
+
+    /// Stops sending the entry of the given replica in full versions while it's at
+    /// `final_value`. The replica must not make any more operations.
+    pub fn retire(&mut self, replica_id: ReplicaId, final_value: Seq) {
+        if let btree_map::Entry::Vacant(entry) = self.retired.entry(replica_id) {
+            entry.insert(final_value);
+            self.unsent_retirements.push(Lamport {
+                replica_id,
+                value: final_value,
+            });
+        }
+    }
+}
+
+/// Decodes the versions received on one stream, in whichever order they arrive.
+#[derive(Clone, Debug, Default)]
+pub struct VersionDecoder {
+    /// Decoded versions that the next version on the stream hasn't been decoded against yet.
+    versions: BTreeMap<u32, Global>,
+    /// Every version before this one has been decoded.
+    decoded_before: u32,
+    decoded: BTreeSet<u32>,
+    /// The final values of retired replicas, with the version they were retired along with.
+    retired: BTreeMap<ReplicaId, (Seq, u32)>,
+}
+
+impl VersionDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decodes the given version. A pending version should be decoded again once more
+    /// versions of the stream have arrived.
+    pub fn decode(&mut self, encoded: &EncodedVersion) -> DecodedVersion {
+        for retired in &encoded.retired {
+            self.retired
+                .entry(retired.replica_id)
+                .or_insert((retired.value, encoded.seq));
+        }
+        if self.is_decoded(encoded.seq) {
+            return DecodedVersion::Duplicate;
+        }
+
+        let mut version = if encoded.full {
+            let retired = self
+                .retired
+                .iter()
+                .filter(|(_, (_, seq))| *seq <= encoded.seq)
+                .map(|(replica_id, (value, _))| Lamport {
+                    replica_id: *replica_id,
+                    value: *value,
+                })
+                .collect::<Vec<_>>();
+            if retired.len() < encoded.retired_count as usize {
+                return DecodedVersion::Pending;
+            }
+
+            let mut version = Global::new();
+            for timestamp in retired {
+                version.set(timestamp);
+            }
+            version
+        } else {
+            let base = encoded
+                .seq
+                .checked_sub(1)
+                .and_then(|seq| self.versions.get(&seq));
+            let Some(base) = base else {
+                return DecodedVersion::Pending;
+            };
+            base.clone()
+        };
+        for entry in &encoded.entries {
+            version.set(*entry);
+        }
+
+        self.mark_decoded(encoded.seq, &version);
+        DecodedVersion::Version(version)
+    }
+
+    fn is_decoded(&self, seq: u32) -> bool {
+        seq < self.decoded_before || self.decoded.contains(&seq)
+    }
+
+    fn mark_decoded(&mut self, seq: u32, version: &Global) {
+        // Only the next version can be based on this one, and the previous version is no
+        // longer needed once this one is decoded.
+        if !self.is_decoded(seq + 1) {
+            self.versions.insert(seq, version.clone());
+        }
+        if let Some(previous_seq) = seq.checked_sub(1) {
+            self.versions.remove(&previous_seq);
+        }
+
+        self.decoded.insert(seq);
+        while self.decoded.remove(&self.decoded_before) {
+            self.decoded_before += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delta_encoding() {
+        let mut encoder = VersionEncoder::new();
+        let mut decoder = VersionDecoder::new();
+        let mut version = Global::new();
+        let mut encoded_versions = Vec::new();
+        for seq in 0..FULL_SYNC_INTERVAL + 2 {
+            version.observe(Lamport {
+                replica_id: (seq % 3) as ReplicaId,
+                value: seq + 1,
+            });
+            let encoded = encoder.encode(&version);
+            assert_eq!(
+                decoder.decode(&encoded),
+                DecodedVersion::Version(version.clone())
+            );
+            encoded_versions.push(encoded);
+        }
+
+        // After the first version, only the entry that changed is sent until the next full
+        // version.
+        assert!(encoded_versions[0].full);
+        assert_eq!(encoded_versions[0].entries.len(), 1);
+        assert!(encoded_versions[1..FULL_SYNC_INTERVAL as usize]
+            .iter()
+            .all(|encoded| !encoded.full && encoded.entries.len() == 1));
+        let full_version = &encoded_versions[FULL_SYNC_INTERVAL as usize];
+        assert!(full_version.full);
+        assert_eq!(full_version.entries.len(), 3);
+
+        // A version that goes back is still encoded exactly.
+        let older_version = Global::from_iter([Lamport {
+            replica_id: 1,
+            value: 2,
+        }]);
+        let encoded = encoder.encode(&older_version);
+        assert!(!encoded.full);
+        assert_eq!(
+            decoder.decode(&encoded),
+            DecodedVersion::Version(older_version)
+        );
+
+        encoder.reset();
+        assert!(encoder.encode(&version).full);
+    }
+
+    #[test]
+    fn test_out_of_order_decoding() {
+        let mut encoder = VersionEncoder::new();
+        let versions = (1..=3)
+            .map(|value| {
+                Global::from_iter([Lamport {
+                    replica_id: 0,
+                    value,
+                }])
+            })
+            .collect::<Vec<_>>();
+        let encoded = versions
+            .iter()
+            .map(|version| encoder.encode(version))
+            .collect::<Vec<_>>();
+
+        let mut decoder = VersionDecoder::new();
+        assert_eq!(decoder.decode(&encoded[2]), DecodedVersion::Pending);
+        assert_eq!(decoder.decode(&encoded[1]), DecodedVersion::Pending);
+
+        // Once the missing version arrives, the ones after it can be decoded.
+        assert_eq!(
+            decoder.decode(&encoded[0]),
+            DecodedVersion::Version(versions[0].clone())
+        );
+        assert_eq!(decoder.decode(&encoded[2]), DecodedVersion::Pending);
+        assert_eq!(
+            decoder.decode(&encoded[1]),
+            DecodedVersion::Version(versions[1].clone())
+        );
+        assert_eq!(
+            decoder.decode(&encoded[2]),
+            DecodedVersion::Version(versions[2].clone())
+        );
+        assert_eq!(decoder.decode(&encoded[0]), DecodedVersion::Duplicate);
+        assert_eq!(decoder.decode(&encoded[2]), DecodedVersion::Duplicate);
+        assert!(decoder.versions.len() <= 1);
+    }
+
+    #[test]
+    fn test_retired_replicas() {
+        let mut encoder = VersionEncoder::new();
+        let mut decoder = VersionDecoder::new();
+        let version = Global::from_iter([
+            Lamport {
+                replica_id: 0,
+                value: 5,
+            },
+            Lamport {
+                replica_id: 1,
+                value: 7,
+            },
+        ]);
+        encoder.retire(0, 5);
+        let retiring_version = encoder.encode(&version);
+        assert_eq!(
+            retiring_version.retired,
+            [Lamport {
+                replica_id: 0,
+                value: 5
+            }]
+        );
+
+        // Full versions leave out the retired replica when it's at its final value, and
+        // can't be decoded before the retirement is known.
+        encoder.reset();
+        let full_version = encoder.encode(&version);
+        assert_eq!(
+            full_version.entries,
+            [Lamport {
+                replica_id: 1,
+                value: 7
+            }]
+        );
+        assert_eq!(decoder.decode(&full_version), DecodedVersion::Pending);
+        assert_eq!(
+            decoder.decode(&retiring_version),
+            DecodedVersion::Version(version.clone())
+        );
+        assert_eq!(
+            decoder.decode(&full_version),
+            DecodedVersion::Version(version)
+        );
+
+        // A version that hasn't seen all operations of the retired replica still says so.
+        let older_version = Global::from_iter([Lamport {
+            replica_id: 1,
+            value: 8,
+        }]);
+        encoder.reset();
+        let encoded = encoder.encode(&older_version);
+        assert!(encoded.entries.contains(&Lamport {
+            replica_id: 0,
+            value: 0
+        }));
+        assert_eq!(
+            decoder.decode(&encoded),
+            DecodedVersion::Version(older_version)
+        );
+    }
+}
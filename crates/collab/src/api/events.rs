@@ -17,7 +17,7 @@ use std::sync::{Arc, OnceLock};
 use telemetry_events::{
     ActionEvent, AppEvent, AssistantEvent, CallEvent, CpuEvent, EditEvent, EditorEvent, Event,
     EventRequestBody, EventWrapper, ExtensionEvent, InlineCompletionEvent, MemoryEvent,
-    SettingEvent,
+    SettingEvent, MAX_USAGE_EVENTS_PER_BATCH, USAGE_EVENT_PROPERTIES,
 };
 use uuid::Uuid;
 
@@ -1229,6 +1229,68 @@ impl ActionEventRow {
     }
 }
 
+#[derive(Serialize, Debug, clickhouse::Row)]
+pub struct UsageEventRow {
+    time: i64,
+    name: String,
+    property_keys: Vec<String>,
+    property_values: Vec<String>,
+}
+
+/// Uploads the usage events that a client sent over its RPC connection. Unlike the events
+/// posted over HTTP, they carry nothing that identifies the installation or the user.
+///
+/// Batches larger than a client sends are rejected, and properties that clients don't attach
+/// are dropped, so that a modified client can't store anything else.
+pub async fn upload_usage_events(
+    batch: rpc::proto::TelemetryBatch,
+    clickhouse_client: &clickhouse::Client,
+) -> anyhow::Result<()> {
+    const USAGE_EVENTS_TABLE: &str = "usage_events";
+
+    if batch.events.len() > MAX_USAGE_EVENTS_PER_BATCH {
+        return Err(anyhow!(
+            "usage event batch of {} events exceeds the limit of {MAX_USAGE_EVENTS_PER_BATCH}",
+            batch.events.len()
+        ));
+    }
+
+    // Clients only send times relative to their first queued event, so anchor the
+    // most recent one to the time the batch arrived.
+    let received_at = chrono::Utc::now();
+    let last_event_offset = batch
+        .events
+        .iter()
+        .map(|event| event.milliseconds_since_first_event)
+        .max()
+        .unwrap_or_default();
+    let rows = batch
+        .events
+        .into_iter()
+        .map(|event| {
+            let time = received_at
+                - chrono::Duration::milliseconds(
+                    (last_event_offset - event.milliseconds_since_first_event) as i64,
+                );
+            let (property_keys, property_values) = event
+                .properties
+                .into_iter()
+                .filter(|(key, _)| USAGE_EVENT_PROPERTIES.contains(&key.as_str()))
+                .unzip();
+            UsageEventRow {
+                time: time.timestamp_millis(),
+                name: event.name,
+                property_keys,
+                property_values,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    ToUpload::upload_to_table(USAGE_EVENTS_TABLE, &rows, clickhouse_client)
+        .await
+        .with_context(|| format!("failed to upload to table '{USAGE_EVENTS_TABLE}'"))
+}
+
 pub fn calculate_json_checksum(app: Arc<AppState>, json: &impl AsRef<[u8]>) -> Option<Vec<u8>> {
     let Some(checksum_seed) = app.config.zed_client_checksum_seed.as_ref() else {
         return None;
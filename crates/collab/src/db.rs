@@ -734,6 +734,9 @@ pub struct RejoinedWorktree {
     pub settings_files: Vec<WorktreeSettingsFile>,
     pub scan_id: u64,
     pub completed_scan_id: u64,
+    /// Whether the entries are all of the worktree's entries rather than the ones changed
+    /// since the guest's last scan.
+    pub is_snapshot: bool,
 }
 
 pub struct LeftRoom {
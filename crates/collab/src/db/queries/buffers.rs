@@ -844,6 +844,7 @@ fn operation_from_storage(
             replica_id: row.replica_id as u32,
             lamport_timestamp: row.lamport_timestamp as u32,
             version,
+            encoded_version: None,
             counts: operation
                 .undo_counts
                 .iter()
@@ -868,6 +869,7 @@ fn operation_from_storage(
                 })
                 .collect(),
             new_text: operation.edit_texts,
+            encoded_version: None,
         })
     })
 }
@@ -826,6 +826,105 @@ impl Database {
         Ok((project, replica_id as ReplicaId))
     }
 
+    /// Returns all of the entries of a project's worktree, for a guest whose copy of them
+    /// stopped matching the host's.
+    pub async fn refresh_worktree(
+        &self,
+        project_id: ProjectId,
+        worktree_id: u64,
+        connection: ConnectionId,
+    ) -> Result<TransactionGuard<Worktree>> {
+        self.project_transaction(project_id, |tx| async move {
+            project_collaborator::Entity::find()
+                .filter(
+                    Condition::all()
+                        .add(project_collaborator::Column::ProjectId.eq(project_id))
+                        .add(project_collaborator::Column::ConnectionId.eq(connection.id as i32))
+                        .add(
+                            project_collaborator::Column::ConnectionServerId
+                                .eq(connection.owner_id as i32),
+                        ),
+                )
+                .one(&*tx)
+                .await?
+                .ok_or_else(|| anyhow!("no such project collaborator"))?;
+
+            let db_worktree = worktree::Entity::find()
+                .filter(
+                    Condition::all()
+                        .add(worktree::Column::ProjectId.eq(project_id))
+                        .add(worktree::Column::Id.eq(worktree_id as i64)),
+                )
+                .one(&*tx)
+                .await?
+                .ok_or_else(|| anyhow!("no such worktree"))?;
+            let mut worktree = Worktree {
+                id: db_worktree.id as u64,
+                abs_path: db_worktree.abs_path,
+                root_name: db_worktree.root_name,
+                visible: db_worktree.visible,
+                entries: Default::default(),
+                repository_entries: Default::default(),
+                diagnostic_summaries: Default::default(),
+                settings_files: Default::default(),
+                scan_id: db_worktree.scan_id as u64,
+                completed_scan_id: db_worktree.completed_scan_id as u64,
+            };
+
+            let mut db_entries = worktree_entry::Entity::find()
+                .filter(
+                    Condition::all()
+                        .add(worktree_entry::Column::ProjectId.eq(project_id))
+                        .add(worktree_entry::Column::WorktreeId.eq(worktree_id as i64))
+                        .add(worktree_entry::Column::IsDeleted.eq(false)),
+                )
+                .stream(&*tx)
+                .await?;
+            while let Some(db_entry) = db_entries.next().await {
+                let db_entry = db_entry?;
+                worktree.entries.push(proto::Entry {
+                    id: db_entry.id as u64,
+                    is_dir: db_entry.is_dir,
+                    path: db_entry.path,
+                    inode: db_entry.inode as u64,
+                    mtime: Some(proto::Timestamp {
+                        seconds: db_entry.mtime_seconds as u64,
+                        nanos: db_entry.mtime_nanos as u32,
+                    }),
+                    is_symlink: db_entry.is_symlink,
+                    is_ignored: db_entry.is_ignored,
+                    is_external: db_entry.is_external,
+                    git_status: db_entry.git_status.map(|status| status as i32),
+                });
+            }
+            drop(db_entries);
+
+            let mut db_repository_entries = worktree_repository::Entity::find()
+                .filter(
+                    Condition::all()
+                        .add(worktree_repository::Column::ProjectId.eq(project_id))
+                        .add(worktree_repository::Column::WorktreeId.eq(worktree_id as i64))
+                        .add(worktree_repository::Column::IsDeleted.eq(false)),
+                )
+                .stream(&*tx)
+                .await?;
+            while let Some(db_repository_entry) = db_repository_entries.next().await {
+                let db_repository_entry = db_repository_entry?;
+                worktree.repository_entries.insert(
+                    db_repository_entry.work_directory_id as u64,
+                    proto::RepositoryEntry {
+                        work_directory_id: db_repository_entry.work_directory_id as u64,
+                        branch: db_repository_entry.branch,
+                    },
+                );
+            }
+            drop(db_repository_entries);
+
+            Ok(worktree)
+        })
+        .await
+    }
+
     pub async fn leave_hosted_project(
         &self,
         project_id: ProjectId,
@@ -607,6 +607,16 @@ impl Database {
         let mut worktrees = Vec::new();
         let db_worktrees = project.find_related(worktree::Entity).all(tx).await?;
         for db_worktree in db_worktrees {
+            // If the guest has seen a later scan than the stored one, the host has shared the
+            // worktree again since, so the entries changed after the guest's scan would leave
+            // out the ones from before it. Send all of the entries then.
+            let backfill_since_scan_id = rejoined_project
+                .worktrees
+                .iter()
+                .find(|worktree| worktree.id == db_worktree.id as u64)
+                .map(|worktree| worktree.scan_id)
+                .filter(|scan_id| *scan_id <= db_worktree.scan_id as u64);
+
             let mut worktree = RejoinedWorktree {
                 id: db_worktree.id as u64,
                 abs_path: db_worktree.abs_path,
@@ -620,17 +630,13 @@ impl Database {
                 settings_files: Default::default(),
                 scan_id: db_worktree.scan_id as u64,
                 completed_scan_id: db_worktree.completed_scan_id as u64,
+                is_snapshot: backfill_since_scan_id.is_none(),
             };
 
-            let rejoined_worktree = rejoined_project
-                .worktrees
-                .iter()
-                .find(|worktree| worktree.id == db_worktree.id as u64);
-
             // File entries
             {
-                let entry_filter = if let Some(rejoined_worktree) = rejoined_worktree {
-                    worktree_entry::Column::ScanId.gt(rejoined_worktree.scan_id)
+                let entry_filter = if let Some(scan_id) = backfill_since_scan_id {
+                    worktree_entry::Column::ScanId.gt(scan_id)
                 } else {
                     worktree_entry::Column::IsDeleted.eq(false)
                 };
@@ -670,8 +676,8 @@ impl Database {
 
             // Repository Entries
             {
-                let repository_entry_filter = if let Some(rejoined_worktree) = rejoined_worktree {
-                    worktree_repository::Column::ScanId.gt(rejoined_worktree.scan_id)
+                let repository_entry_filter = if let Some(scan_id) = backfill_since_scan_id {
+                    worktree_repository::Column::ScanId.gt(scan_id)
                 } else {
                     worktree_repository::Column::IsDeleted.eq(false)
                 };
@@ -442,6 +442,7 @@ impl Server {
             .add_message_handler(user_message_handler(leave_project))
             .add_request_handler(update_project)
             .add_request_handler(update_worktree)
+            .add_request_handler(refresh_worktree)
             .add_message_handler(start_language_server)
             .add_message_handler(update_language_server)
             .add_message_handler(update_diagnostic_summary)
@@ -467,6 +468,9 @@ impl Server {
             .add_request_handler(user_handler(
                 forward_read_only_project_request::<proto::SearchProject>,
             ))
+            .add_streaming_request_handler(
+                forward_read_only_project_stream_request::<proto::StreamProjectSearch>,
+            )
             .add_request_handler(user_handler(
                 forward_read_only_project_request::<proto::GetDocumentHighlights>,
             ))
@@ -595,6 +599,12 @@ impl Server {
             .add_message_handler(user_message_handler(acknowledge_channel_message))
             .add_message_handler(user_message_handler(acknowledge_buffer_version))
             .add_request_handler(user_handler(get_supermaven_api_key))
+            .add_message_handler({
+                let clickhouse_client = app_state.clickhouse_client.clone();
+                user_message_handler(move |batch, session| {
+                    record_usage_events(batch, session, clickhouse_client.clone())
+                })
+            })
             .add_streaming_request_handler({
                 let app_state = app_state.clone();
                 move |request, response, session| {
@@ -757,6 +767,7 @@ impl Server {
                                                     remove_incoming_requests: Default::default(),
                                                     outgoing_requests: Default::default(),
                                                     remove_outgoing_requests: Default::default(),
+                                                    is_snapshot: false,
                                                 },
                                             )
                                             .trace_err();
@@ -1734,6 +1745,7 @@ fn notify_rejoined_projects(
                 is_last_update: worktree.completed_scan_id == worktree.scan_id,
                 updated_repositories: worktree.updated_repositories,
                 removed_repositories: worktree.removed_repositories,
+                is_snapshot: worktree.is_snapshot,
             };
             for update in proto::split_worktree_update(message, MAX_CHUNK_SIZE) {
                 session.peer.send(session.connection_id, update.clone())?;
@@ -2214,6 +2226,7 @@ fn join_project_internal(
             is_last_update: worktree.scan_id == worktree.completed_scan_id,
             updated_repositories: worktree.repository_entries.into_values().collect(),
             removed_repositories: Default::default(),
+            is_snapshot: true,
         };
         for update in proto::split_worktree_update(message, MAX_CHUNK_SIZE) {
             session.peer.send(session.connection_id, update.clone())?;
@@ -2775,6 +2788,50 @@ async fn update_worktree(
     Ok(())
 }
 
+/// Sends a guest a copy of all of a worktree's entries, after its copy stopped matching the
+/// host's.
+async fn refresh_worktree(
+    request: proto::RefreshWorktree,
+    response: Response<proto::RefreshWorktree>,
+    session: Session,
+) -> Result<()> {
+    #[cfg(any(test, feature = "test-support"))]
+    const MAX_CHUNK_SIZE: usize = 2;
+    #[cfg(not(any(test, feature = "test-support")))]
+    const MAX_CHUNK_SIZE: usize = 256;
+
+    let project_id = ProjectId::from_proto(request.project_id);
+    let mut worktree = session
+        .db()
+        .await
+        .refresh_worktree(project_id, request.worktree_id, session.connection_id)
+        .await?;
+    let message = proto::UpdateWorktree {
+        project_id: request.project_id,
+        worktree_id: worktree.id,
+        abs_path: worktree.abs_path.clone(),
+        root_name: worktree.root_name.clone(),
+        updated_entries: mem::take(&mut worktree.entries),
+        removed_entries: Default::default(),
+        scan_id: worktree.scan_id,
+        is_last_update: worktree.scan_id == worktree.completed_scan_id,
+        updated_repositories: mem::take(&mut worktree.repository_entries)
+            .into_values()
+            .collect(),
+        removed_repositories: Default::default(),
+        is_snapshot: true,
+    };
+    // The updates get sent before the transaction ends, so that none of the host's later ones
+    // reach the guest ahead of them.
+    for update in proto::split_worktree_update(message, MAX_CHUNK_SIZE) {
+        session.peer.send(session.connection_id, update)?;
+    }
+    drop(worktree);
+
+    response.send(proto::Ack {})?;
+    Ok(())
+}
+
 /// Updates other participants with changes to the diagnostics
 async fn update_diagnostic_summary(
     message: proto::UpdateDiagnosticSummary,
@@ -2893,6 +2950,33 @@ where
     Ok(())
 }
 
+/// Forwards a project request to the host, and streams its responses back to the guest.
+async fn forward_read_only_project_stream_request<T>(
+    request: T,
+    response: StreamingResponse<T>,
+    session: Session,
+) -> Result<()>
+where
+    T: EntityMessage + RequestMessage,
+{
+    let session = UserSession::new(session)
+        .ok_or_else(|| Error::Internal(anyhow!("must be a user to call {}", T::NAME)))?;
+    let project_id = ProjectId::from_proto(request.remote_entity_id());
+    let host_connection_id = session
+        .db()
+        .await
+        .host_for_read_only_project_request(project_id, session.connection_id, session.user_id())
+        .await?;
+    let mut responses = session
+        .peer
+        .forward_request_stream(session.connection_id, host_connection_id, request)
+        .await?;
+    while let Some(payload) = responses.next().await {
+        response.send(payload?)?;
+    }
+    Ok(())
+}
+
 /// forward a project request to the dev server. Only allowed
 /// if it's your dev server.
 async fn forward_project_request_for_owner<T>(
@@ -4336,6 +4420,18 @@ async fn update_channel_message(
     Ok(())
 }
 
+/// Store the usage events of a client that opted into sending them.
+async fn record_usage_events(
+    batch: proto::TelemetryBatch,
+    _session: UserSession,
+    clickhouse_client: Option<clickhouse::Client>,
+) -> Result<()> {
+    if let Some(clickhouse_client) = clickhouse_client {
+        crate::api::events::upload_usage_events(batch, &clickhouse_client).await?;
+    }
+    Ok(())
+}
+
 /// Mark a channel message as read
 async fn acknowledge_channel_message(
     request: proto::AckChannelMessage,
@@ -5123,7 +5219,10 @@ fn build_initial_contacts_update(
     contacts: Vec<db::Contact>,
     pool: &ConnectionPool,
 ) -> proto::UpdateContacts {
-    let mut update = proto::UpdateContacts::default();
+    let mut update = proto::UpdateContacts {
+        is_snapshot: true,
+        ..Default::default()
+    };
 
     for contact in contacts {
         match contact {
@@ -5244,6 +5343,7 @@ async fn update_user_contacts(user_id: UserId, session: &Session) -> Result<()>
                             remove_incoming_requests: Default::default(),
                             outgoing_requests: Default::default(),
                             remove_outgoing_requests: Default::default(),
+                            is_snapshot: false,
                         },
                     )
                     .trace_err();
@@ -1635,6 +1635,41 @@ async fn test_following_stops_on_unshare(cx_a: &mut TestAppContext, cx_b: &mut T
     })
 }
 
+#[gpui::test]
+async fn test_following_stops_when_leader_leaves(
+    cx_a: &mut TestAppContext,
+    cx_b: &mut TestAppContext,
+) {
+    let (_server, client_a, client_b, channel_id) = TestServer::start2(cx_a, cx_b).await;
+
+    let (workspace_a, cx_a) = client_a.build_test_workspace(cx_a).await;
+    client_a
+        .host_workspace(&workspace_a, channel_id, cx_a)
+        .await;
+    let (_workspace_b, cx_b) = client_b.join_workspace(channel_id, cx_b).await;
+
+    let peer_id_b = client_b.peer_id().unwrap();
+    workspace_a.update(cx_a, |workspace, cx| workspace.follow(peer_id_b, cx));
+    cx_a.run_until_parked();
+    workspace_a.update(cx_a, |workspace, _| {
+        assert_eq!(
+            workspace.leader_for_pane(workspace.active_pane()),
+            Some(peer_id_b)
+        );
+        assert!(workspace.notification_ids().is_empty());
+    });
+
+    // b leaves the call, which ends the following and tells a about it once.
+    cx_b.update(|cx| ActiveCall::global(cx).update(cx, |call, cx| call.hang_up(cx)))
+        .await
+        .unwrap();
+    cx_a.run_until_parked();
+    workspace_a.update(cx_a, |workspace, _| {
+        assert_eq!(workspace.leader_for_pane(workspace.active_pane()), None);
+        assert_eq!(workspace.notification_ids().len(), 1);
+    });
+}
+
 #[gpui::test]
 async fn test_following_into_excluded_file(
     mut cx_a: &mut TestAppContext,
@@ -29,6 +29,7 @@ use project::{
     SearchResult,
 };
 use rand::prelude::*;
+use rpc::{ErrorCode, ErrorExt as _};
 use serde_json::json;
 use settings::SettingsStore;
 use std::{
@@ -1436,6 +1437,129 @@ async fn test_unshare_project(
     });
 }
 
+#[gpui::test(iterations = 10)]
+async fn test_unsaved_edits_after_host_stops_sharing(
+    executor: BackgroundExecutor,
+    cx_a: &mut TestAppContext,
+    cx_b: &mut TestAppContext,
+) {
+    let mut server = TestServer::start(executor.clone()).await;
+    let client_a = server.create_client(cx_a, "user_a").await;
+    let client_b = server.create_client(cx_b, "user_b").await;
+    server
+        .create_room(&mut [(&client_a, cx_a), (&client_b, cx_b)])
+        .await;
+    let active_call_a = cx_a.read(ActiveCall::global);
+
+    client_a
+        .fs()
+        .insert_tree("/a", json!({ "a.txt": "a-contents" }))
+        .await;
+    client_a
+        .fs()
+        .insert_tree("/b", json!({ "b.txt": "b-contents" }))
+        .await;
+    client_b.fs().insert_tree("/local", json!({})).await;
+
+    let (project_a, worktree_a_id) = client_a.build_local_project("/a", cx_a).await;
+    let (worktree_b, _) = project_a
+        .update(cx_a, |project, cx| {
+            project.find_or_create_local_worktree("/b", true, cx)
+        })
+        .await
+        .unwrap();
+    let worktree_b_id = worktree_b.read_with(cx_a, |worktree, _| worktree.id());
+    let project_id = active_call_a
+        .update(cx_a, |call, cx| call.share_project(project_a.clone(), cx))
+        .await
+        .unwrap();
+    let project_b = client_b.build_dev_server_project(project_id, cx_b).await;
+    let (workspace_b, cx_b) = client_b.build_workspace(&project_b, cx_b);
+
+    let buffer_a = project_b
+        .update(cx_b, |project, cx| {
+            project.open_buffer((worktree_a_id, "a.txt"), cx)
+        })
+        .await
+        .unwrap();
+    let buffer_b = project_b
+        .update(cx_b, |project, cx| {
+            project.open_buffer((worktree_b_id, "b.txt"), cx)
+        })
+        .await
+        .unwrap();
+    buffer_b.update(cx_b, |buffer, cx| buffer.edit([(0..1, "B")], None, cx));
+    executor.run_until_parked();
+
+    // When the host stops sharing a folder, the guest's edits to its files are kept read-only,
+    // while the files of the other folders stay editable.
+    project_a.update(cx_a, |project, cx| {
+        project.remove_worktree(worktree_b_id, cx)
+    });
+    executor.run_until_parked();
+    buffer_b.read_with(cx_b, |buffer, _| {
+        assert!(buffer.read_only());
+        assert!(buffer.is_dirty());
+        assert_eq!(buffer.text(), "B-contents");
+    });
+    buffer_a.read_with(cx_b, |buffer, _| assert!(!buffer.read_only()));
+
+    // When the host stops sharing the project, all of its files are kept read-only.
+    buffer_a.update(cx_b, |buffer, cx| buffer.edit([(0..1, "A")], None, cx));
+    executor.run_until_parked();
+    project_a
+        .update(cx_a, |project, cx| project.unshare(cx))
+        .unwrap();
+    executor.run_until_parked();
+    assert!(project_b.read_with(cx_b, |project, _| project.is_disconnected()));
+    buffer_a.read_with(cx_b, |buffer, _| {
+        assert!(buffer.read_only());
+        assert!(buffer.is_dirty());
+        assert_eq!(buffer.text(), "A-contents");
+    });
+
+    // The guest can save its edits to its own file system.
+    let save = workspace_b.update(cx_b, |workspace, cx| {
+        workspace.save_local_copies(vec![buffer_a.clone(), buffer_b.clone()], cx)
+    });
+    executor.run_until_parked();
+    assert_eq!(cx_b.suggested_new_path_name().as_deref(), Some("a.txt"));
+    cx_b.simulate_new_path_selection(|_| Some("/local/a.txt".into()));
+    executor.run_until_parked();
+    assert_eq!(cx_b.suggested_new_path_name().as_deref(), Some("b.txt"));
+    cx_b.simulate_new_path_selection(|_| Some("/local/b.txt".into()));
+    save.await.unwrap();
+    assert_eq!(
+        client_b.fs().load("/local/a.txt".as_ref()).await.unwrap(),
+        "A-contents"
+    );
+    assert_eq!(
+        client_b.fs().load("/local/b.txt".as_ref()).await.unwrap(),
+        "B-contents"
+    );
+    assert_eq!(
+        client_a.fs().load("/a/a.txt".as_ref()).await.unwrap(),
+        "a-contents"
+    );
+
+    // When the host shares the project again, the guest joins it afresh.
+    let project_id = active_call_a
+        .update(cx_a, |call, cx| call.share_project(project_a.clone(), cx))
+        .await
+        .unwrap();
+    let project_b2 = client_b.build_dev_server_project(project_id, cx_b).await;
+    let buffer_a2 = project_b2
+        .update(cx_b, |project, cx| {
+            project.open_buffer((worktree_a_id, "a.txt"), cx)
+        })
+        .await
+        .unwrap();
+    buffer_a2.read_with(cx_b, |buffer, _| {
+        assert!(!buffer.read_only());
+        assert_eq!(buffer.text(), "a-contents");
+    });
+}
+
 #[gpui::test(iterations = 10)]
 async fn test_project_reconnect(
     executor: BackgroundExecutor,
@@ -1869,7 +1993,7 @@ async fn test_active_call_events(
     assert_eq!(mem::take(&mut *events_a.borrow_mut()), vec![]);
     assert_eq!(mem::take(&mut *events_b.borrow_mut()), vec![]);
 
-    // Unsharing a project should dispatch the RemoteProjectUnshared event.
+    // Leaving the room unshares the project and dispatches the ParticipantLeft event.
     active_call_a
         .update(cx_a, |call, cx| call.hang_up(cx))
         .await
@@ -1882,9 +2006,19 @@ async fn test_active_call_events(
     );
     assert_eq!(
         mem::take(&mut *events_b.borrow_mut()),
-        vec![room::Event::RemoteProjectUnshared {
-            project_id: project_a_id,
-        }]
+        vec![
+            room::Event::RemoteProjectUnshared {
+                project_id: project_a_id,
+            },
+            room::Event::ParticipantLeft {
+                participant_id: client_a.peer_id().unwrap(),
+                user: Arc::new(User {
+                    id: client_a.user_id().unwrap(),
+                    github_login: "user_a".to_string(),
+                    avatar_uri: "avatar_a".into(),
+                }),
+            }
+        ]
     );
 }
 
@@ -3080,6 +3214,20 @@ async fn test_fs_operations(
         );
     });
 
+    // Renaming over another entry fails with an error that the guest can tell apart.
+    let error = project_b
+        .update(cx_b, |project, cx| {
+            project.rename_entry(entry.id, Path::new("a.txt"), cx)
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(error.error_code(), ErrorCode::EntryAlreadyExists);
+    assert_eq!(error.error_tag("path"), Some("a.txt"));
+    assert_eq!(
+        client_a.fs().load("/dir/a.txt".as_ref()).await.unwrap(),
+        "a-contents"
+    );
+
     let dir_entry = project_b
         .update(cx_b, |project, cx| {
             project.create_entry((worktree_id, "DIR"), true, cx)
@@ -3274,6 +3422,152 @@ async fn test_fs_operations(
     });
 }
 
+#[gpui::test(iterations = 10)]
+async fn test_worktree_updates_from_host(
+    executor: BackgroundExecutor,
+    cx_a: &mut TestAppContext,
+    cx_b: &mut TestAppContext,
+) {
+    let mut server = TestServer::start(executor.clone()).await;
+    let client_a = server.create_client(cx_a, "user_a").await;
+    let client_b = server.create_client(cx_b, "user_b").await;
+    server
+        .create_room(&mut [(&client_a, cx_a), (&client_b, cx_b)])
+        .await;
+    let active_call_a = cx_a.read(ActiveCall::global);
+
+    client_a
+        .fs()
+        .insert_tree(
+            "/dir",
+            json!({
+                "a.txt": "a-contents",
+                "b.txt": "b-contents",
+                "sub": { "c.txt": "c-contents" },
+            }),
+        )
+        .await;
+    let (project_a, worktree_id) = client_a.build_local_project("/dir", cx_a).await;
+    let project_id = active_call_a
+        .update(cx_a, |call, cx| call.share_project(project_a.clone(), cx))
+        .await
+        .unwrap();
+    let project_b = client_b.build_dev_server_project(project_id, cx_b).await;
+    let buffer_b = project_b
+        .update(cx_b, |project, cx| {
+            project.open_buffer((worktree_id, "sub/c.txt"), cx)
+        })
+        .await
+        .unwrap();
+
+    let worktree_a = project_a.read_with(cx_a, |project, _| project.worktrees().next().unwrap());
+    let worktree_b = project_b.read_with(cx_b, |project, _| project.worktrees().next().unwrap());
+    let assert_entries_match = |cx_a: &mut TestAppContext, cx_b: &mut TestAppContext| {
+        let host_entries = worktree_a.read_with(cx_a, |worktree, _| {
+            worktree
+                .entries(false)
+                .map(|entry| (entry.id, entry.path.clone(), entry.is_dir()))
+                .collect::<Vec<_>>()
+        });
+        let guest_entries = worktree_b.read_with(cx_b, |worktree, _| {
+            worktree
+                .entries(false)
+                .map(|entry| (entry.id, entry.path.clone(), entry.is_dir()))
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(guest_entries, host_entries);
+        host_entries
+            .into_iter()
+            .map(|(_, path, _)| path.to_string_lossy().into_owned())
+            .collect::<Vec<_>>()
+    };
+
+    client_a.fs().insert_file("/dir/d.txt", "d".into()).await;
+    executor.run_until_parked();
+    assert_eq!(
+        assert_entries_match(cx_a, cx_b),
+        ["", "a.txt", "b.txt", "d.txt", "sub", "sub/c.txt"]
+    );
+
+    // Renaming a directory moves the guest's buffers of the files in it along with it.
+    client_a
+        .fs()
+        .rename(
+            "/dir/sub".as_ref(),
+            "/dir/moved".as_ref(),
+            Default::default(),
+        )
+        .await
+        .unwrap();
+    executor.run_until_parked();
+    assert_eq!(
+        assert_entries_match(cx_a, cx_b),
+        ["", "a.txt", "b.txt", "d.txt", "moved", "moved/c.txt"]
+    );
+    buffer_b.read_with(cx_b, |buffer, _| {
+        assert_eq!(buffer.file().unwrap().path().to_str(), Some("moved/c.txt"));
+        assert!(!buffer.file().unwrap().is_deleted());
+    });
+
+    client_a
+        .fs()
+        .remove_file("/dir/a.txt".as_ref(), Default::default())
+        .await
+        .unwrap();
+    client_a
+        .fs()
+        .remove_file("/dir/moved/c.txt".as_ref(), Default::default())
+        .await
+        .unwrap();
+    executor.run_until_parked();
+    assert_eq!(
+        assert_entries_match(cx_a, cx_b),
+        ["", "b.txt", "d.txt", "moved"]
+    );
+    buffer_b.read_with(cx_b, |buffer, _| {
+        assert!(buffer.file().unwrap().is_deleted());
+    });
+
+    // Make the guest's entries differ from the host's, like a missed update would. Once an
+    // update arrives out of order, they get replaced by a fresh copy of the host's.
+    let (scan_id, b_entry_id) = worktree_b.read_with(cx_b, |worktree, _| {
+        (
+            worktree.scan_id(),
+            worktree.entry_for_path("b.txt").unwrap().id,
+        )
+    });
+    worktree_b.update(cx_b, |worktree, _| {
+        let worktree = worktree.as_remote_mut().unwrap();
+        worktree.update_from_remote(rpc::proto::UpdateWorktree {
+            project_id,
+            worktree_id: worktree_id.to_proto(),
+            removed_entries: vec![b_entry_id.to_proto()],
+            scan_id: scan_id as u64,
+            is_last_update: true,
+            ..Default::default()
+        });
+    });
+    executor.run_until_parked();
+    worktree_b.read_with(cx_b, |worktree, _| {
+        assert!(worktree.entry_for_path("b.txt").is_none());
+    });
+    worktree_b.update(cx_b, |worktree, _| {
+        let worktree = worktree.as_remote_mut().unwrap();
+        worktree.update_from_remote(rpc::proto::UpdateWorktree {
+            project_id,
+            worktree_id: worktree_id.to_proto(),
+            scan_id: scan_id as u64 - 1,
+            is_last_update: true,
+            ..Default::default()
+        });
+    });
+    executor.run_until_parked();
+    assert_eq!(
+        assert_entries_match(cx_a, cx_b),
+        ["", "b.txt", "d.txt", "moved"]
+    );
+}
+
 #[gpui::test(iterations = 10)]
 async fn test_local_settings(
     executor: BackgroundExecutor,
@@ -3584,6 +3878,108 @@ async fn test_editing_while_guest_opens_buffer(
     buffer_b.read_with(cx_b, |buf, _| assert_eq!(buf.text(), text));
 }
 
+#[gpui::test(iterations = 10)]
+async fn test_guests_opening_buffer_concurrently(
+    executor: BackgroundExecutor,
+    cx_a: &mut TestAppContext,
+    cx_b: &mut TestAppContext,
+    cx_c: &mut TestAppContext,
+) {
+    let mut server = TestServer::start(executor.clone()).await;
+    let client_a = server.create_client(cx_a, "user_a").await;
+    let client_b = server.create_client(cx_b, "user_b").await;
+    let client_c = server.create_client(cx_c, "user_c").await;
+    server
+        .create_room(&mut [(&client_a, cx_a), (&client_b, cx_b), (&client_c, cx_c)])
+        .await;
+    let active_call_a = cx_a.read(ActiveCall::global);
+
+    client_a
+        .fs()
+        .insert_tree("/dir", json!({ "a.txt": "one\ntwo\n" }))
+        .await;
+    let (project_a, worktree_id) = client_a.build_local_project("/dir", cx_a).await;
+    let project_id = active_call_a
+        .update(cx_a, |call, cx| call.share_project(project_a.clone(), cx))
+        .await
+        .unwrap();
+    let project_b = client_b.build_dev_server_project(project_id, cx_b).await;
+    let project_c = client_c.build_dev_server_project(project_id, cx_c).await;
+
+    // Both guests open the same path at once, before the host has opened it, and get replicas
+    // of the same buffer.
+    let open_buffer_b = project_b.update(cx_b, |p, cx| p.open_buffer((worktree_id, "a.txt"), cx));
+    let open_buffer_c = project_c.update(cx_c, |p, cx| p.open_buffer((worktree_id, "a.txt"), cx));
+    let buffer_b = open_buffer_b.await.unwrap();
+    let buffer_c = open_buffer_c.await.unwrap();
+    let buffer_a = project_a
+        .update(cx_a, |p, cx| p.open_buffer((worktree_id, "a.txt"), cx))
+        .await
+        .unwrap();
+    let buffer_id = buffer_a.read_with(cx_a, |buffer, _| buffer.remote_id());
+    assert_eq!(
+        buffer_b.read_with(cx_b, |buffer, _| buffer.remote_id()),
+        buffer_id
+    );
+    assert_eq!(
+        buffer_c.read_with(cx_c, |buffer, _| buffer.remote_id()),
+        buffer_id
+    );
+    let replica_ids = HashSet::from_iter([
+        buffer_a.read_with(cx_a, |buffer, _| buffer.replica_id()),
+        buffer_b.read_with(cx_b, |buffer, _| buffer.replica_id()),
+        buffer_c.read_with(cx_c, |buffer, _| buffer.replica_id()),
+    ]);
+    assert_eq!(replica_ids.len(), 3);
+
+    // Concurrent edits of the guests reach everyone.
+    buffer_b.update(cx_b, |buffer, cx| buffer.edit([(0..0, "b ")], None, cx));
+    buffer_c.update(cx_c, |buffer, cx| buffer.edit([(7..7, " c")], None, cx));
+    executor.run_until_parked();
+    buffer_a.read_with(cx_a, |buffer, _| {
+        assert_eq!(buffer.text(), "b one\ntwo c\n")
+    });
+    buffer_b.read_with(cx_b, |buffer, _| {
+        assert_eq!(buffer.text(), "b one\ntwo c\n")
+    });
+    buffer_c.read_with(cx_c, |buffer, _| {
+        assert_eq!(buffer.text(), "b one\ntwo c\n")
+    });
+
+    // The host closing the buffer doesn't release it while it's shared with guests.
+    drop(buffer_a);
+    executor.run_until_parked();
+    buffer_b.update(cx_b, |buffer, cx| buffer.edit([(0..1, "B")], None, cx));
+    executor.run_until_parked();
+    buffer_c.read_with(cx_c, |buffer, _| {
+        assert_eq!(buffer.text(), "B one\ntwo c\n")
+    });
+
+    // A guest saves the buffer through the host, and everyone sees it saved.
+    project_c
+        .update(cx_c, |p, cx| p.save_buffer(buffer_c.clone(), cx))
+        .await
+        .unwrap();
+    executor.run_until_parked();
+    assert_eq!(
+        client_a.fs().load("/dir/a.txt".as_ref()).await.unwrap(),
+        "B one\ntwo c\n"
+    );
+    buffer_b.read_with(cx_b, |buffer, _| assert!(!buffer.is_dirty()));
+    buffer_c.read_with(cx_c, |buffer, _| assert!(!buffer.is_dirty()));
+
+    // Opening the path again as the host returns the buffer that the guests hold.
+    let buffer_a = project_a
+        .update(cx_a, |p, cx| p.open_buffer((worktree_id, "a.txt"), cx))
+        .await
+        .unwrap();
+    buffer_a.read_with(cx_a, |buffer, _| {
+        assert_eq!(buffer.remote_id(), buffer_id);
+        assert_eq!(buffer.text(), "B one\ntwo c\n");
+        assert!(!buffer.is_dirty());
+    });
+}
+
 #[gpui::test(iterations = 10)]
 async fn test_leaving_worktree_while_opening_buffer(
     executor: BackgroundExecutor,
@@ -4945,6 +5341,74 @@ async fn test_project_search(
     );
 }
 
+#[gpui::test(iterations = 10)]
+async fn test_cancel_project_search(
+    executor: BackgroundExecutor,
+    cx_a: &mut TestAppContext,
+    cx_b: &mut TestAppContext,
+) {
+    let mut server = TestServer::start(executor.clone()).await;
+    let client_a = server.create_client(cx_a, "user_a").await;
+    let client_b = server.create_client(cx_b, "user_b").await;
+    server
+        .create_room(&mut [(&client_a, cx_a), (&client_b, cx_b)])
+        .await;
+    let active_call_a = cx_a.read(ActiveCall::global);
+
+    client_a
+        .fs()
+        .insert_tree(
+            "/root",
+            json!({
+                "a": "hello world",
+                "b": "world of goo",
+                "c": "goodnight world",
+            }),
+        )
+        .await;
+    let (project_a, _) = client_a.build_local_project("/root", cx_a).await;
+    let project_id = active_call_a
+        .update(cx_a, |call, cx| call.share_project(project_a.clone(), cx))
+        .await
+        .unwrap();
+    let project_b = client_b.build_dev_server_project(project_id, cx_b).await;
+
+    // Dropping the results before the search is done stops it on the host.
+    let mut search_rx = project_b.update(cx_b, |project, cx| {
+        project.search(
+            SearchQuery::text("world", false, false, false, Vec::new(), Vec::new()).unwrap(),
+            cx,
+        )
+    });
+    assert!(matches!(
+        search_rx.next().await,
+        Some(SearchResult::Buffer { .. })
+    ));
+    drop(search_rx);
+    executor.run_until_parked();
+    project_a.read_with(cx_a, |project, _| {
+        assert_eq!(project.remote_search_count(), 0);
+    });
+
+    // Searches that ran to completion are gone from the host too.
+    let mut search_rx = project_b.update(cx_b, |project, cx| {
+        project.search(
+            SearchQuery::text("goo", false, false, false, Vec::new(), Vec::new()).unwrap(),
+            cx,
+        )
+    });
+    let mut result_count = 0;
+    while let Some(result) = search_rx.next().await {
+        assert!(matches!(result, SearchResult::Buffer { .. }));
+        result_count += 1;
+    }
+    assert_eq!(result_count, 2);
+    executor.run_until_parked();
+    project_a.read_with(cx_a, |project, _| {
+        assert_eq!(project.remote_search_count(), 0);
+    });
+}
+
 #[gpui::test(iterations = 10)]
 async fn test_document_highlights(
     executor: BackgroundExecutor,
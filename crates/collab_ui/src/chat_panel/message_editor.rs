@@ -376,11 +376,7 @@ impl MessageEditor {
 
         let candidates = names
             .into_iter()
-            .map(|user| StringMatchCandidate {
-                id: 0,
-                string: user.clone(),
-                char_bag: user.chars().collect(),
-            })
+            .map(|user| StringMatchCandidate::new(0, user.clone()))
             .collect::<Vec<_>>();
 
         Some((start_anchor, query, candidates))
@@ -396,11 +392,7 @@ impl MessageEditor {
             static ref EMOJI_FUZZY_MATCH_CANDIDATES: Vec<StringMatchCandidate> = {
                 let emojis = emojis::iter()
                     .flat_map(|s| s.shortcodes())
-                    .map(|emoji| StringMatchCandidate {
-                        id: 0,
-                        string: emoji.to_string(),
-                        char_bag: emoji.chars().collect(),
-                    })
+                    .map(|emoji| StringMatchCandidate::new(0, emoji.to_string()))
                     .collect::<Vec<_>>();
                 emojis
             };
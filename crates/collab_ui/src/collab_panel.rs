@@ -399,11 +399,8 @@ impl CollabPanel {
                 // Populate the active user.
                 if let Some(user) = user_store.current_user() {
                     self.match_candidates.clear();
-                    self.match_candidates.push(StringMatchCandidate {
-                        id: 0,
-                        string: user.github_login.clone(),
-                        char_bag: user.github_login.chars().collect(),
-                    });
+                    self.match_candidates
+                        .push(StringMatchCandidate::new(0, user.github_login.clone()));
                     let matches = executor.block(match_strings(
                         &self.match_candidates,
                         &query,
@@ -442,11 +439,10 @@ impl CollabPanel {
                 self.match_candidates.clear();
                 self.match_candidates
                     .extend(room.remote_participants().values().map(|participant| {
-                        StringMatchCandidate {
-                            id: participant.user.id as usize,
-                            string: participant.user.github_login.clone(),
-                            char_bag: participant.user.github_login.chars().collect(),
-                        }
+                        StringMatchCandidate::new(
+                            participant.user.id as usize,
+                            participant.user.github_login.clone(),
+                        )
                     }));
                 let mut matches = executor.block(match_strings(
                     &self.match_candidates,
@@ -496,10 +492,8 @@ impl CollabPanel {
                 self.match_candidates.clear();
                 self.match_candidates
                     .extend(room.pending_participants().iter().enumerate().map(
-                        |(id, participant)| StringMatchCandidate {
-                            id,
-                            string: participant.github_login.clone(),
-                            char_bag: participant.github_login.chars().collect(),
+                        |(id, participant)| {
+                            StringMatchCandidate::new(id, participant.github_login.clone())
                         },
                     ));
                 let matches = executor.block(match_strings(
@@ -531,10 +525,8 @@ impl CollabPanel {
                     channel_store
                         .ordered_channels()
                         .enumerate()
-                        .map(|(ix, (_, channel))| StringMatchCandidate {
-                            id: ix,
-                            string: channel.name.clone().into(),
-                            char_bag: channel.name.chars().collect(),
+                        .map(|(ix, (_, channel))| {
+                            StringMatchCandidate::new(ix, channel.name.clone().into())
                         }),
                 );
             let matches = executor.block(match_strings(
@@ -612,14 +604,11 @@ impl CollabPanel {
         let channel_invites = channel_store.channel_invitations();
         if !channel_invites.is_empty() {
             self.match_candidates.clear();
-            self.match_candidates
-                .extend(channel_invites.iter().enumerate().map(|(ix, channel)| {
-                    StringMatchCandidate {
-                        id: ix,
-                        string: channel.name.clone().into(),
-                        char_bag: channel.name.chars().collect(),
-                    }
-                }));
+            self.match_candidates.extend(
+                channel_invites.iter().enumerate().map(|(ix, channel)| {
+                    StringMatchCandidate::new(ix, channel.name.clone().into())
+                }),
+            );
             let matches = executor.block(match_strings(
                 &self.match_candidates,
                 &query,
@@ -649,17 +638,12 @@ impl CollabPanel {
         let incoming = user_store.incoming_contact_requests();
         if !incoming.is_empty() {
             self.match_candidates.clear();
-            self.match_candidates
-                .extend(
-                    incoming
-                        .iter()
-                        .enumerate()
-                        .map(|(ix, user)| StringMatchCandidate {
-                            id: ix,
-                            string: user.github_login.clone(),
-                            char_bag: user.github_login.chars().collect(),
-                        }),
-                );
+            self.match_candidates.extend(
+                incoming
+                    .iter()
+                    .enumerate()
+                    .map(|(ix, user)| StringMatchCandidate::new(ix, user.github_login.clone())),
+            );
             let matches = executor.block(match_strings(
                 &self.match_candidates,
                 &query,
@@ -678,17 +662,12 @@ impl CollabPanel {
         let outgoing = user_store.outgoing_contact_requests();
         if !outgoing.is_empty() {
             self.match_candidates.clear();
-            self.match_candidates
-                .extend(
-                    outgoing
-                        .iter()
-                        .enumerate()
-                        .map(|(ix, user)| StringMatchCandidate {
-                            id: ix,
-                            string: user.github_login.clone(),
-                            char_bag: user.github_login.chars().collect(),
-                        }),
-                );
+            self.match_candidates.extend(
+                outgoing
+                    .iter()
+                    .enumerate()
+                    .map(|(ix, user)| StringMatchCandidate::new(ix, user.github_login.clone())),
+            );
             let matches = executor.block(match_strings(
                 &self.match_candidates,
                 &query,
@@ -716,16 +695,9 @@ impl CollabPanel {
         if !contacts.is_empty() {
             self.match_candidates.clear();
             self.match_candidates
-                .extend(
-                    contacts
-                        .iter()
-                        .enumerate()
-                        .map(|(ix, contact)| StringMatchCandidate {
-                            id: ix,
-                            string: contact.user.github_login.clone(),
-                            char_bag: contact.user.github_login.chars().collect(),
-                        }),
-                );
+                .extend(contacts.iter().enumerate().map(|(ix, contact)| {
+                    StringMatchCandidate::new(ix, contact.user.github_login.clone())
+                }));
 
             let matches = executor.block(match_strings(
                 &self.match_candidates,
@@ -75,7 +75,7 @@ impl CommandPalette {
     ) -> Self {
         let filter = CommandPaletteFilter::try_global(cx);
 
-        let commands = cx
+        let mut commands = cx
             .available_actions()
             .into_iter()
             .filter_map(|action| {
@@ -84,11 +84,21 @@ impl CommandPalette {
                 }
 
                 Some(Command {
-                    name: humanize_action_name(action.name()),
+                    name: action
+                        .display_name()
+                        .map(ToString::to_string)
+                        .unwrap_or_else(|| humanize_action_name(action.name())),
                     action,
                 })
             })
-            .collect();
+            .collect::<Vec<_>>();
+        let hit_counts = cx.global::<HitCounts>();
+        commands.sort_by_key(|command| {
+            (
+                Reverse(hit_counts.0.get(&command.name).cloned()),
+                command.name.clone(),
+            )
+        });
 
         let delegate = CommandPaletteDelegate::new(
             cx.view().downgrade(),
@@ -118,7 +128,11 @@ impl Render for CommandPalette {
 
 pub struct CommandPaletteDelegate {
     command_palette: WeakView<CommandPalette>,
+    /// All the commands, the most used ones first.
     all_commands: Vec<Command>,
+    /// The candidates for the commands, which are kept for as long as the palette is open so
+    /// that they're only folded for matching once.
+    candidates: Arc<[StringMatchCandidate]>,
     commands: Vec<Command>,
     matches: Vec<StringMatch>,
     selected_ix: usize,
@@ -159,8 +173,14 @@ impl CommandPaletteDelegate {
         telemetry: Arc<Telemetry>,
         previous_focus_handle: FocusHandle,
     ) -> Self {
+        let candidates = commands
+            .iter()
+            .enumerate()
+            .map(|(ix, command)| StringMatchCandidate::new(ix, command.name.to_string()))
+            .collect();
         Self {
             command_palette,
+            candidates,
             all_commands: commands.clone(),
             matches: vec![],
             commands,
@@ -253,34 +273,17 @@ impl PickerDelegate for CommandPaletteDelegate {
     ) -> gpui::Task<()> {
         let (mut tx, mut rx) = postage::dispatch::channel(1);
         let task = cx.background_executor().spawn({
-            let mut commands = self.all_commands.clone();
-            let hit_counts = cx.global::<HitCounts>().clone();
+            let commands = self.all_commands.clone();
+            let candidates = self.candidates.clone();
             let executor = cx.background_executor().clone();
             let query = trim_consecutive_whitespaces(&query.as_str());
             async move {
-                commands.sort_by_key(|action| {
-                    (
-                        Reverse(hit_counts.0.get(&action.name).cloned()),
-                        action.name.clone(),
-                    )
-                });
-
-                let candidates = commands
-                    .iter()
-                    .enumerate()
-                    .map(|(ix, command)| StringMatchCandidate {
-                        id: ix,
-                        string: command.name.to_string(),
-                        char_bag: command.name.chars().collect(),
-                    })
-                    .collect::<Vec<_>>();
                 let matches = if query.is_empty() {
                     candidates
-                        .into_iter()
-                        .enumerate()
-                        .map(|(index, candidate)| StringMatch {
-                            candidate_id: index,
-                            string: candidate.string,
+                        .iter()
+                        .map(|candidate| StringMatch {
+                            candidate_id: candidate.id,
+                            string: candidate.string.clone(),
                             positions: Vec::new(),
                             score: 0.0,
                         })
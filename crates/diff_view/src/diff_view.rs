@@ -0,0 +1,713 @@
+mod line_diff;
+
+use std::{ops::Range, sync::Arc, time::Duration};
+
+use anyhow::{anyhow, Result};
+use collections::HashSet;
+use editor::Editor;
+use gpui::{
+    actions, uniform_list, AnyElement, AppContext, EventEmitter, FocusHandle, FocusableView,
+    InteractiveElement, IntoElement, Model, ParentElement, Render, Styled, Subscription, Task,
+    UniformListScrollHandle, View, ViewContext, VisualContext, WindowContext,
+};
+use language::{Buffer, BufferSnapshot, Event as BufferEvent, LineEnding, Point};
+use settings::Settings;
+use theme::ThemeSettings;
+use ui::{prelude::*, Tooltip};
+use workspace::{
+    item::{Item, ItemEvent, TabContentParams},
+    Pane, Workspace,
+};
+
+pub use line_diff::{DiffHunk, DiffLine, DiffLineKind, DiffRow, LineDiff, CONTEXT_LINES};
+
+actions!(
+    diff_view,
+    [
+        DiffWithSaved,
+        DiffWithOtherPane,
+        NextHunk,
+        PreviousHunk,
+        CopyHunkToOtherSide
+    ]
+);
+
+/// How long to wait after a side of a diff changes before computing the diff again.
+pub const RECALCULATE_DEBOUNCE: Duration = Duration::from_millis(250);
+
+pub fn init(cx: &mut AppContext) {
+    cx.observe_new_views(|workspace: &mut Workspace, _| {
+        workspace.register_action(DiffView::diff_with_saved);
+        workspace.register_action(DiffView::diff_with_other_pane);
+    })
+    .detach();
+}
+
+/// The text shown on one side of a diff.
+#[derive(Clone)]
+pub enum DiffSource {
+    /// The current text of a buffer, including its unsaved edits.
+    Buffer(Model<Buffer>),
+    /// The text of a buffer's file, as it was last saved.
+    SavedFile(Model<Buffer>),
+}
+
+impl DiffSource {
+    pub fn buffer(&self) -> &Model<Buffer> {
+        match self {
+            DiffSource::Buffer(buffer) | DiffSource::SavedFile(buffer) => buffer,
+        }
+    }
+
+    pub fn title(&self, cx: &AppContext) -> SharedString {
+        let name = self
+            .buffer()
+            .read(cx)
+            .file()
+            .map(|file| file.file_name(cx).to_string_lossy().into_owned())
+            .unwrap_or_else(|| "untitled".to_string());
+        match self {
+            DiffSource::Buffer(_) => name.into(),
+            DiffSource::SavedFile(_) => format!("{name} (saved)").into(),
+        }
+    }
+
+    /// Returns whether hunks can be copied into this side.
+    pub fn is_editable(&self, cx: &AppContext) -> bool {
+        match self {
+            DiffSource::Buffer(buffer) => !buffer.read(cx).read_only(),
+            DiffSource::SavedFile(_) => false,
+        }
+    }
+
+    /// Returns whether the given event of the buffer changes the text of this side.
+    fn is_changed_by(&self, event: &BufferEvent) -> bool {
+        match self {
+            DiffSource::Buffer(_) => matches!(event, BufferEvent::Edited | BufferEvent::Reloaded),
+            DiffSource::SavedFile(_) => matches!(
+                event,
+                BufferEvent::Saved | BufferEvent::Reloaded | BufferEvent::FileHandleChanged
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Side {
+    Old,
+    New,
+}
+
+struct DiffSide {
+    source: DiffSource,
+    /// The text as of the last diff, unless it changed since.
+    text: Option<Arc<str>>,
+    /// The buffer as of the last diff, for a side that shows a buffer. It's kept after the buffer
+    /// changes, to find where the rows of the diff went.
+    snapshot: Option<BufferSnapshot>,
+}
+
+impl DiffSide {
+    fn new(source: DiffSource) -> Self {
+        Self {
+            source,
+            text: None,
+            snapshot: None,
+        }
+    }
+
+    /// Loads the text of this side, unless it hasn't changed since the last diff.
+    fn load(&self, cx: &AppContext) -> Task<Result<(Arc<str>, Option<BufferSnapshot>)>> {
+        if let Some(text) = self.text.clone() {
+            return Task::ready(Ok((text, self.snapshot.clone())));
+        }
+        match &self.source {
+            DiffSource::Buffer(buffer) => {
+                let snapshot = buffer.read(cx).snapshot();
+                cx.background_executor()
+                    .spawn(async move { Ok((snapshot.text().into(), Some(snapshot))) })
+            }
+            DiffSource::SavedFile(buffer) => {
+                let Some(file) = buffer.read(cx).file().and_then(|file| file.as_local()) else {
+                    return Task::ready(Err(anyhow!(
+                        "{} has no saved file on this machine",
+                        self.source.title(cx)
+                    )));
+                };
+                let load = file.load(cx);
+                cx.background_executor().spawn(async move {
+                    let mut text = load.await?;
+                    LineEnding::normalize(&mut text);
+                    Ok((text.into(), None))
+                })
+            }
+        }
+    }
+}
+
+/// A read-only view of the lines that differ between two texts, such as a buffer and its saved
+/// file. It's computed again whenever either side changes.
+pub struct DiffView {
+    old: DiffSide,
+    new: DiffSide,
+    diff: Arc<LineDiff>,
+    rows: Vec<DiffRow>,
+    /// The folds of unchanged lines that were expanded, by the old row of their first line.
+    expanded_folds: HashSet<u32>,
+    selected_hunk: Option<usize>,
+    load_error: Option<SharedString>,
+    scroll_handle: UniformListScrollHandle,
+    focus_handle: FocusHandle,
+    recalculate_task: Option<Task<Result<()>>>,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl DiffView {
+    pub fn new(old: DiffSource, new: DiffSource, cx: &mut ViewContext<Self>) -> Self {
+        let subscriptions = [(Side::Old, &old), (Side::New, &new)]
+            .into_iter()
+            .map(|(side, source)| {
+                cx.subscribe(source.buffer(), move |this, _, event, cx| {
+                    this.on_buffer_event(side, event, cx)
+                })
+            })
+            .collect();
+        let mut this = Self {
+            old: DiffSide::new(old),
+            new: DiffSide::new(new),
+            diff: Default::default(),
+            rows: Vec::new(),
+            expanded_folds: HashSet::default(),
+            selected_hunk: None,
+            load_error: None,
+            scroll_handle: UniformListScrollHandle::new(),
+            focus_handle: cx.focus_handle(),
+            recalculate_task: None,
+            _subscriptions: subscriptions,
+        };
+        this.recalculate(false, cx);
+        this
+    }
+
+    /// Opens a diff between the active buffer and its saved file.
+    fn diff_with_saved(
+        workspace: &mut Workspace,
+        _: &DiffWithSaved,
+        cx: &mut ViewContext<Workspace>,
+    ) {
+        let Some(buffer) = active_buffer(workspace.active_pane(), cx) else {
+            return;
+        };
+        let view = cx.new_view(|cx| {
+            Self::new(
+                DiffSource::SavedFile(buffer.clone()),
+                DiffSource::Buffer(buffer),
+                cx,
+            )
+        });
+        workspace.add_item_to_active_pane(Box::new(view), None, cx);
+    }
+
+    /// Opens a diff from the active buffer of another pane to the active buffer.
+    fn diff_with_other_pane(
+        workspace: &mut Workspace,
+        _: &DiffWithOtherPane,
+        cx: &mut ViewContext<Workspace>,
+    ) {
+        let active_pane = workspace.active_pane().clone();
+        let Some(new_buffer) = active_buffer(&active_pane, cx) else {
+            return;
+        };
+        let Some(old_buffer) = workspace
+            .panes()
+            .iter()
+            .filter(|pane| **pane != active_pane)
+            .find_map(|pane| active_buffer(pane, cx))
+        else {
+            return;
+        };
+        let view = cx.new_view(|cx| {
+            Self::new(
+                DiffSource::Buffer(old_buffer),
+                DiffSource::Buffer(new_buffer),
+                cx,
+            )
+        });
+        workspace.add_item_to_active_pane(Box::new(view), None, cx);
+    }
+
+    pub fn diff(&self) -> &Arc<LineDiff> {
+        &self.diff
+    }
+
+    pub fn rows(&self) -> &[DiffRow] {
+        &self.rows
+    }
+
+    pub fn selected_hunk(&self) -> Option<usize> {
+        self.selected_hunk
+    }
+
+    fn side_mut(&mut self, side: Side) -> &mut DiffSide {
+        match side {
+            Side::Old => &mut self.old,
+            Side::New => &mut self.new,
+        }
+    }
+
+    fn on_buffer_event(&mut self, side: Side, event: &BufferEvent, cx: &mut ViewContext<Self>) {
+        if matches!(event, BufferEvent::FileHandleChanged) {
+            cx.emit(ItemEvent::UpdateTab);
+        }
+        let side = self.side_mut(side);
+        if side.source.is_changed_by(event) {
+            side.text = None;
+            self.recalculate(true, cx);
+        }
+    }
+
+    /// Computes the diff again in the background, reloading the sides that changed.
+    fn recalculate(&mut self, debounce: bool, cx: &mut ViewContext<Self>) {
+        self.recalculate_task = Some(cx.spawn(|this, mut cx| async move {
+            if debounce {
+                cx.background_executor().timer(RECALCULATE_DEBOUNCE).await;
+            }
+            let (old, new) =
+                this.update(&mut cx, |this, cx| (this.old.load(cx), this.new.load(cx)))?;
+            let loaded = async { anyhow::Ok((old.await?, new.await?)) }.await;
+            let ((old_text, old_snapshot), (new_text, new_snapshot)) = match loaded {
+                Ok(loaded) => loaded,
+                Err(error) => {
+                    return this.update(&mut cx, |this, cx| {
+                        this.load_error = Some(error.to_string().into());
+                        cx.notify();
+                    });
+                }
+            };
+
+            let diff = cx.background_executor().spawn({
+                let old_text = old_text.clone();
+                let new_text = new_text.clone();
+                async move { LineDiff::new(&old_text, &new_text) }
+            });
+            let diff = diff.await;
+            this.update(&mut cx, |this, cx| {
+                this.old.text = Some(old_text);
+                this.old.snapshot = old_snapshot;
+                this.new.text = Some(new_text);
+                this.new.snapshot = new_snapshot;
+                this.set_diff(diff, cx);
+            })
+        }));
+    }
+
+    fn set_diff(&mut self, diff: LineDiff, cx: &mut ViewContext<Self>) {
+        self.selected_hunk = self
+            .selected_hunk
+            .filter(|&ix| ix < diff.hunks.len())
+            .or_else(|| (!diff.hunks.is_empty()).then_some(0));
+        self.diff = Arc::new(diff);
+        self.rows = self.diff.rows(&self.expanded_folds);
+        self.load_error = None;
+        cx.notify();
+    }
+
+    fn expand_fold(&mut self, fold: Range<usize>, cx: &mut ViewContext<Self>) {
+        if let Some(old_row) = self.diff.lines[fold.start].old_row {
+            self.expanded_folds.insert(old_row);
+            self.rows = self.diff.rows(&self.expanded_folds);
+            cx.notify();
+        }
+    }
+
+    fn select_hunk(&mut self, ix: usize, cx: &mut ViewContext<Self>) {
+        let Some(hunk) = self.diff.hunks.get(ix) else {
+            return;
+        };
+        self.selected_hunk = Some(ix);
+        if let Some(row) = self
+            .rows
+            .iter()
+            .position(|row| *row == DiffRow::Line(hunk.line_ix))
+        {
+            self.scroll_handle.scroll_to_item(row);
+        }
+        cx.notify();
+    }
+
+    pub fn next_hunk(&mut self, _: &NextHunk, cx: &mut ViewContext<Self>) {
+        let count = self.diff.hunks.len();
+        if count > 0 {
+            let ix = self.selected_hunk.map_or(0, |ix| (ix + 1) % count);
+            self.select_hunk(ix, cx);
+        }
+    }
+
+    pub fn previous_hunk(&mut self, _: &PreviousHunk, cx: &mut ViewContext<Self>) {
+        let count = self.diff.hunks.len();
+        if count > 0 {
+            let ix = self
+                .selected_hunk
+                .map_or(count - 1, |ix| (ix + count - 1) % count);
+            self.select_hunk(ix, cx);
+        }
+    }
+
+    /// Returns the side that hunks get copied into: the new one if it's an editable buffer, or
+    /// else the old one.
+    fn editable_side(&self, cx: &AppContext) -> Option<Side> {
+        if self.new.source.is_editable(cx) {
+            Some(Side::New)
+        } else if self.old.source.is_editable(cx) {
+            Some(Side::Old)
+        } else {
+            None
+        }
+    }
+
+    /// Replaces the selected hunk's lines in the editable side with its lines in the other side.
+    pub fn copy_hunk_to_other_side(&mut self, _: &CopyHunkToOtherSide, cx: &mut ViewContext<Self>) {
+        let Some(hunk) = self
+            .selected_hunk
+            .and_then(|ix| self.diff.hunks.get(ix))
+            .cloned()
+        else {
+            return;
+        };
+        let (target, rows, text) = match self.editable_side(cx) {
+            Some(Side::New) => (
+                &self.new,
+                hunk.new_rows.clone(),
+                self.diff.hunk_text(&hunk, DiffLineKind::Removed),
+            ),
+            Some(Side::Old) => (
+                &self.old,
+                hunk.old_rows.clone(),
+                self.diff.hunk_text(&hunk, DiffLineKind::Added),
+            ),
+            None => return,
+        };
+        let Some(snapshot) = target.snapshot.as_ref() else {
+            return;
+        };
+        // The buffer may have been edited since the diff, so the rows are resolved in the
+        // snapshot that was diffed.
+        let offsets = row_offsets(snapshot, &rows);
+        let range = snapshot.anchor_before(offsets.start)..snapshot.anchor_before(offsets.end);
+        target.source.buffer().update(cx, |buffer, cx| {
+            buffer.edit([(range, text)], None, cx);
+        });
+    }
+
+    fn render_header(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let summary = match (&self.load_error, self.diff.hunks.len()) {
+            (Some(error), _) => error.clone(),
+            (None, 0) => "No changes".into(),
+            (None, 1) => "1 change".into(),
+            (None, count) => format!("{count} changes").into(),
+        };
+        let has_hunks = !self.diff.hunks.is_empty();
+        let can_copy = self.selected_hunk.is_some() && self.editable_side(cx).is_some();
+
+        h_flex()
+            .gap_2()
+            .px_2()
+            .py_1()
+            .border_b_1()
+            .border_color(cx.theme().colors().border)
+            .child(Label::new(self.old.source.title(cx)))
+            .child(Icon::new(IconName::ArrowRight).color(Color::Muted))
+            .child(Label::new(self.new.source.title(cx)))
+            .child(Label::new(summary).color(Color::Muted))
+            .child(div().flex_1())
+            .child(
+                IconButton::new("diff-previous-hunk", IconName::ChevronUp)
+                    .disabled(!has_hunks)
+                    .tooltip(|cx| Tooltip::for_action("Previous Change", &PreviousHunk, cx))
+                    .on_click(cx.listener(|this, _, cx| this.previous_hunk(&PreviousHunk, cx))),
+            )
+            .child(
+                IconButton::new("diff-next-hunk", IconName::ChevronDown)
+                    .disabled(!has_hunks)
+                    .tooltip(|cx| Tooltip::for_action("Next Change", &NextHunk, cx))
+                    .on_click(cx.listener(|this, _, cx| this.next_hunk(&NextHunk, cx))),
+            )
+            .child(
+                IconButton::new("diff-copy-hunk", IconName::Copy)
+                    .disabled(!can_copy)
+                    .tooltip(|cx| {
+                        Tooltip::for_action("Copy Change to Other Side", &CopyHunkToOtherSide, cx)
+                    })
+                    .on_click(cx.listener(|this, _, cx| {
+                        this.copy_hunk_to_other_side(&CopyHunkToOtherSide, cx)
+                    })),
+            )
+    }
+
+    fn render_rows(&mut self, range: Range<usize>, cx: &mut ViewContext<Self>) -> Vec<AnyElement> {
+        let selected_lines = self
+            .selected_hunk
+            .and_then(|ix| self.diff.hunks.get(ix))
+            .map(DiffHunk::line_ixs)
+            .unwrap_or_default();
+        range
+            .map(|ix| match self.rows[ix].clone() {
+                DiffRow::Line(line_ix) => {
+                    let line = &self.diff.lines[line_ix];
+                    render_line(ix, line, selected_lines.contains(&line_ix), cx)
+                }
+                DiffRow::Fold(fold) => h_flex()
+                    .id(ix)
+                    .w_full()
+                    .px_2()
+                    .cursor_pointer()
+                    .bg(cx.theme().colors().editor_subheader_background)
+                    .hover(|style| style.bg(cx.theme().colors().element_hover))
+                    .child(
+                        Label::new(format!("{} unchanged lines", fold.len())).color(Color::Muted),
+                    )
+                    .on_click(cx.listener(move |this, _, cx| this.expand_fold(fold.clone(), cx)))
+                    .into_any_element(),
+            })
+            .collect()
+    }
+}
+
+fn render_line(ix: usize, line: &DiffLine, selected: bool, cx: &WindowContext) -> AnyElement {
+    let status = cx.theme().status();
+    let (sign, background) = match line.kind {
+        DiffLineKind::Unchanged => (" ", None),
+        DiffLineKind::Removed => ("-", Some(status.deleted_background)),
+        DiffLineKind::Added => ("+", Some(status.created_background)),
+    };
+    let row_number = |row: Option<u32>| {
+        div()
+            .w_12()
+            .flex_none()
+            .text_color(cx.theme().colors().editor_line_number)
+            .children(row.map(|row| (row + 1).to_string()))
+    };
+
+    h_flex()
+        .id(ix)
+        .w_full()
+        .whitespace_nowrap()
+        .border_l_2()
+        .border_color(if selected {
+            cx.theme().colors().text_accent
+        } else {
+            gpui::transparent_black()
+        })
+        .when_some(background, |this, background| this.bg(background))
+        .child(row_number(line.old_row))
+        .child(row_number(line.new_row))
+        .child(div().w_4().flex_none().child(sign))
+        .child(line.text.clone())
+        .into_any_element()
+}
+
+/// Returns the offsets in a buffer of the given rows, where the rows past its end are at its end.
+fn row_offsets(snapshot: &BufferSnapshot, rows: &Range<u32>) -> Range<usize> {
+    let offset = |row: u32| {
+        if row > snapshot.max_point().row {
+            snapshot.len()
+        } else {
+            snapshot.point_to_offset(Point::new(row, 0))
+        }
+    };
+    offset(rows.start)..offset(rows.end)
+}
+
+fn active_buffer(pane: &View<Pane>, cx: &AppContext) -> Option<Model<Buffer>> {
+    pane.read(cx)
+        .active_item()?
+        .act_as::<Editor>(cx)?
+        .read(cx)
+        .buffer()
+        .read(cx)
+        .as_singleton()
+}
+
+impl EventEmitter<ItemEvent> for DiffView {}
+
+impl FocusableView for DiffView {
+    fn focus_handle(&self, _: &AppContext) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Item for DiffView {
+    type Event = ItemEvent;
+
+    fn tab_content(&self, params: TabContentParams, cx: &WindowContext) -> AnyElement {
+        let color = if params.selected {
+            Color::Default
+        } else {
+            Color::Muted
+        };
+        h_flex()
+            .gap_2()
+            .child(Icon::new(IconName::FileGit).color(color))
+            .child(Label::new(self.tab_content_text(cx).unwrap_or_default()).color(color))
+            .into_any_element()
+    }
+
+    fn tab_content_text(&self, cx: &AppContext) -> Option<SharedString> {
+        Some(
+            format!(
+                "{} ↔ {}",
+                self.old.source.title(cx),
+                self.new.source.title(cx)
+            )
+            .into(),
+        )
+    }
+
+    fn telemetry_event_text(&self) -> Option<&'static str> {
+        Some("diff view")
+    }
+
+    fn show_toolbar(&self) -> bool {
+        false
+    }
+
+    fn to_item_events(event: &Self::Event, mut f: impl FnMut(ItemEvent)) {
+        f(*event)
+    }
+}
+
+impl Render for DiffView {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let settings = ThemeSettings::get_global(cx);
+        let view = cx.view().clone();
+
+        v_flex()
+            .key_context("DiffView")
+            .track_focus(&self.focus_handle)
+            .on_action(cx.listener(Self::next_hunk))
+            .on_action(cx.listener(Self::previous_hunk))
+            .on_action(cx.listener(Self::copy_hunk_to_other_side))
+            .size_full()
+            .bg(cx.theme().colors().editor_background)
+            .child(self.render_header(cx))
+            .child(
+                uniform_list(view, "diff-rows", self.rows.len(), Self::render_rows)
+                    .flex_grow()
+                    .font_family(settings.buffer_font.family.clone())
+                    .text_size(settings.buffer_font_size(cx))
+                    .track_scroll(self.scroll_handle.clone()),
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fs::FakeFs;
+    use gpui::TestAppContext;
+    use project::Project;
+    use serde_json::json;
+    use settings::SettingsStore;
+
+    fn init_test(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let settings_store = SettingsStore::test(cx);
+            cx.set_global(settings_store);
+            theme::init(theme::LoadThemes::JustBase, cx);
+            language::init(cx);
+            Project::init_settings(cx);
+        });
+    }
+
+    fn hunk_rows(view: &View<DiffView>, cx: &mut gpui::VisualTestContext) -> Vec<[Range<u32>; 2]> {
+        view.update(cx, |view, _| {
+            view.diff()
+                .hunks
+                .iter()
+                .map(|hunk| [hunk.old_rows.clone(), hunk.new_rows.clone()])
+                .collect()
+        })
+    }
+
+    #[gpui::test]
+    async fn test_diff_between_buffers(cx: &mut TestAppContext) {
+        init_test(cx);
+        let old_buffer = cx.new_model(|cx| Buffer::local("one\ntwo\nthree\n", cx));
+        let new_buffer = cx.new_model(|cx| Buffer::local("one\n2\nthree\nfour\n", cx));
+        let (view, cx) = cx.add_window_view(|cx| {
+            DiffView::new(
+                DiffSource::Buffer(old_buffer.clone()),
+                DiffSource::Buffer(new_buffer.clone()),
+                cx,
+            )
+        });
+        cx.run_until_parked();
+        assert_eq!(hunk_rows(&view, cx), [[1..2, 1..2], [3..3, 3..4]]);
+
+        // Edits are diffed again once they stop for a while.
+        new_buffer.update(cx, |buffer, cx| buffer.edit([(12..17, "")], None, cx));
+        cx.run_until_parked();
+        assert_eq!(hunk_rows(&view, cx).len(), 2);
+        cx.executor().advance_clock(RECALCULATE_DEBOUNCE);
+        cx.run_until_parked();
+        assert_eq!(hunk_rows(&view, cx), [[1..2, 1..2]]);
+
+        // Copying a hunk replaces its lines in the new buffer, which makes the sides equal.
+        view.update(cx, |view, cx| {
+            view.previous_hunk(&PreviousHunk, cx);
+            assert_eq!(view.selected_hunk(), Some(0));
+            view.copy_hunk_to_other_side(&CopyHunkToOtherSide, cx);
+        });
+        assert_eq!(
+            new_buffer.read_with(cx, |buffer, _| buffer.text()),
+            "one\ntwo\nthree\n"
+        );
+        cx.executor().advance_clock(RECALCULATE_DEBOUNCE);
+        cx.run_until_parked();
+        assert!(hunk_rows(&view, cx).is_empty());
+        view.update(cx, |view, _| assert_eq!(view.selected_hunk(), None));
+    }
+
+    #[gpui::test]
+    async fn test_diff_with_saved_file(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree("/dir", json!({ "a.txt": "one\r\ntwo\r\nthree\r\n" }))
+            .await;
+        let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+        let buffer = project
+            .update(cx, |project, cx| {
+                project.open_local_buffer("/dir/a.txt", cx)
+            })
+            .await
+            .unwrap();
+        buffer.update(cx, |buffer, cx| buffer.edit([(4..7, "2")], None, cx));
+
+        let (view, cx) = cx.add_window_view(|cx| {
+            DiffView::new(
+                DiffSource::SavedFile(buffer.clone()),
+                DiffSource::Buffer(buffer.clone()),
+                cx,
+            )
+        });
+        cx.run_until_parked();
+        assert_eq!(hunk_rows(&view, cx), [[1..2, 1..2]]);
+        view.update(cx, |view, cx| {
+            let diff = view.diff();
+            assert_eq!(
+                diff.hunk_text(&diff.hunks[0], DiffLineKind::Removed),
+                "two\n"
+            );
+            assert_eq!(view.editable_side(cx), Some(Side::New));
+        });
+
+        // Once the edit is saved, there's nothing left to show.
+        project
+            .update(cx, |project, cx| project.save_buffer(buffer.clone(), cx))
+            .await
+            .unwrap();
+        cx.executor().advance_clock(RECALCULATE_DEBOUNCE);
+        cx.run_until_parked();
+        assert!(hunk_rows(&view, cx).is_empty());
+    }
+}
@@ -0,0 +1,304 @@
+use std::ops::Range;
+
+use collections::HashSet;
+use gpui::SharedString;
+use similar::{ChangeTag, TextDiff};
+
+/// How many unchanged lines are shown before and after each hunk. The unchanged lines further
+/// away are folded.
+pub const CONTEXT_LINES: usize = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiffLineKind {
+    Unchanged,
+    Removed,
+    Added,
+}
+
+/// A line of the old text, the new text, or both if it's unchanged.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+    /// The row of the line in the old text, unless it was added.
+    pub old_row: Option<u32>,
+    /// The row of the line in the new text, unless it was removed.
+    pub new_row: Option<u32>,
+    /// The text of the line, without its newline.
+    pub text: SharedString,
+    /// Whether the line ends with a newline, which only the last line of a text may not.
+    pub has_newline: bool,
+}
+
+/// Lines of the old text that were replaced by lines of the new text. Either range can be empty.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiffHunk {
+    pub old_rows: Range<u32>,
+    pub new_rows: Range<u32>,
+    /// The index of the first line of the hunk in [`LineDiff::lines`].
+    pub line_ix: usize,
+}
+
+impl DiffHunk {
+    /// Returns the indices of the hunk's lines in [`LineDiff::lines`].
+    pub fn line_ixs(&self) -> Range<usize> {
+        let len = self.old_rows.len() + self.new_rows.len();
+        self.line_ix..self.line_ix + len
+    }
+}
+
+/// What the diff view shows in a row.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DiffRow {
+    /// The line with the given index in [`LineDiff::lines`].
+    Line(usize),
+    /// Unchanged lines that are folded away, as a range of indices in [`LineDiff::lines`].
+    Fold(Range<usize>),
+}
+
+/// The lines of two texts interleaved: the unchanged lines once, and in each hunk the removed
+/// lines followed by the added ones.
+#[derive(Clone, Debug, Default)]
+pub struct LineDiff {
+    pub lines: Vec<DiffLine>,
+    pub hunks: Vec<DiffHunk>,
+}
+
+impl LineDiff {
+    pub fn new(old_text: &str, new_text: &str) -> Self {
+        let diff = TextDiff::from_lines(old_text, new_text);
+        let mut lines = Vec::<DiffLine>::new();
+        let mut hunks = Vec::<DiffHunk>::new();
+        let mut old_row = 0;
+        let mut new_row = 0;
+        for change in diff.iter_all_changes() {
+            let value = change.value();
+            let has_newline = value.ends_with('\n');
+            let text = SharedString::from(value.strip_suffix('\n').unwrap_or(value).to_string());
+            let kind = match change.tag() {
+                ChangeTag::Equal => DiffLineKind::Unchanged,
+                ChangeTag::Delete => DiffLineKind::Removed,
+                ChangeTag::Insert => DiffLineKind::Added,
+            };
+
+            if kind != DiffLineKind::Unchanged {
+                let continues_hunk = lines
+                    .last()
+                    .map_or(false, |line| line.kind != DiffLineKind::Unchanged);
+                if !continues_hunk {
+                    hunks.push(DiffHunk {
+                        old_rows: old_row..old_row,
+                        new_rows: new_row..new_row,
+                        line_ix: lines.len(),
+                    });
+                }
+            }
+            let hunk = hunks.last_mut();
+            let line = match kind {
+                DiffLineKind::Unchanged => {
+                    old_row += 1;
+                    new_row += 1;
+                    DiffLine {
+                        kind,
+                        old_row: Some(old_row - 1),
+                        new_row: Some(new_row - 1),
+                        text,
+                        has_newline,
+                    }
+                }
+                DiffLineKind::Removed => {
+                    old_row += 1;
+                    if let Some(hunk) = hunk {
+                        hunk.old_rows.end = old_row;
+                    }
+                    DiffLine {
+                        kind,
+                        old_row: Some(old_row - 1),
+                        new_row: None,
+                        text,
+                        has_newline,
+                    }
+                }
+                DiffLineKind::Added => {
+                    new_row += 1;
+                    if let Some(hunk) = hunk {
+                        hunk.new_rows.end = new_row;
+                    }
+                    DiffLine {
+                        kind,
+                        old_row: None,
+                        new_row: Some(new_row - 1),
+                        text,
+                        has_newline,
+                    }
+                }
+            };
+            lines.push(line);
+        }
+
+        Self { lines, hunks }
+    }
+
+    /// Returns the lines of a hunk of the given kind, which is either removed or added.
+    pub fn hunk_lines(
+        &self,
+        hunk: &DiffHunk,
+        kind: DiffLineKind,
+    ) -> impl Iterator<Item = &DiffLine> + '_ {
+        self.lines[hunk.line_ixs()]
+            .iter()
+            .filter(move |line| line.kind == kind)
+    }
+
+    /// Returns the text of the lines of a hunk of the given kind, including their newlines.
+    pub fn hunk_text(&self, hunk: &DiffHunk, kind: DiffLineKind) -> String {
+        let mut text = String::new();
+        for line in self.hunk_lines(hunk, kind) {
+            text.push_str(&line.text);
+            if line.has_newline {
+                text.push('\n');
+            }
+        }
+        text
+    }
+
+    /// Returns the rows to show, folding the unchanged lines that are more than
+    /// [`CONTEXT_LINES`] away from a hunk, unless their fold was expanded. Folds are identified
+    /// by the old row of their first line.
+    pub fn rows(&self, expanded_folds: &HashSet<u32>) -> Vec<DiffRow> {
+        let mut rows = Vec::new();
+        let mut ix = 0;
+        while ix < self.lines.len() {
+            if self.lines[ix].kind != DiffLineKind::Unchanged {
+                rows.push(DiffRow::Line(ix));
+                ix += 1;
+                continue;
+            }
+
+            let start = ix;
+            let end = self.lines[start..]
+                .iter()
+                .position(|line| line.kind != DiffLineKind::Unchanged)
+                .map_or(self.lines.len(), |len| start + len);
+            let fold_start = if start == 0 {
+                start
+            } else {
+                start + CONTEXT_LINES
+            };
+            let fold_end = if end == self.lines.len() {
+                end
+            } else {
+                end.saturating_sub(CONTEXT_LINES)
+            };
+            // Folding a single line would take as much room as showing it.
+            let fold = (fold_start + 1 < fold_end)
+                .then(|| fold_start..fold_end)
+                .filter(|fold| {
+                    let fold_id = self.lines[fold.start].old_row.unwrap_or_default();
+                    !expanded_folds.contains(&fold_id)
+                });
+            match fold {
+                Some(fold) => {
+                    rows.extend((start..fold.start).map(DiffRow::Line));
+                    rows.push(DiffRow::Fold(fold.clone()));
+                    rows.extend((fold.end..end).map(DiffRow::Line));
+                }
+                None => rows.extend((start..end).map(DiffRow::Line)),
+            }
+            ix = end;
+        }
+        rows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(diff: &LineDiff, rows: &[DiffRow]) -> Vec<String> {
+        rows.iter()
+            .map(|row| match row {
+                DiffRow::Line(ix) => {
+                    let line = &diff.lines[*ix];
+                    let sign = match line.kind {
+                        DiffLineKind::Unchanged => ' ',
+                        DiffLineKind::Removed => '-',
+                        DiffLineKind::Added => '+',
+                    };
+                    format!("{sign}{}", line.text)
+                }
+                DiffRow::Fold(range) => format!("…{}", range.len()),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_line_diff() {
+        let diff = LineDiff::new("a\nb\nc\nd\n", "a\nB\nc\nd\ne\n");
+        assert_eq!(
+            diff.hunks,
+            [
+                DiffHunk {
+                    old_rows: 1..2,
+                    new_rows: 1..2,
+                    line_ix: 1,
+                },
+                DiffHunk {
+                    old_rows: 4..4,
+                    new_rows: 4..5,
+                    line_ix: 5,
+                },
+            ]
+        );
+        assert_eq!(
+            render(&diff, &diff.rows(&HashSet::default())),
+            [" a", "-b", "+B", " c", " d", "+e"]
+        );
+        assert_eq!(
+            diff.lines[2],
+            DiffLine {
+                kind: DiffLineKind::Added,
+                old_row: None,
+                new_row: Some(1),
+                text: "B".into(),
+                has_newline: true,
+            }
+        );
+        assert_eq!(
+            diff.hunk_lines(&diff.hunks[0], DiffLineKind::Removed)
+                .map(|line| line.text.as_ref())
+                .collect::<Vec<_>>(),
+            ["b"]
+        );
+        assert_eq!(diff.hunk_text(&diff.hunks[1], DiffLineKind::Added), "e\n");
+
+        let diff = LineDiff::new("a\nb", "a\nc");
+        assert_eq!(diff.hunk_text(&diff.hunks[0], DiffLineKind::Removed), "b");
+
+        let diff = LineDiff::new("same\n", "same\n");
+        assert!(diff.hunks.is_empty());
+    }
+
+    #[test]
+    fn test_folded_context() {
+        let old_text = (0..20).map(|row| format!("{row}\n")).collect::<String>();
+        let new_text = old_text.replace("10\n", "ten\n");
+        let diff = LineDiff::new(&old_text, &new_text);
+
+        // Only the lines near the hunk are shown, with the others folded on either side.
+        let rows = diff.rows(&HashSet::default());
+        assert_eq!(
+            render(&diff, &rows),
+            ["…7", " 7", " 8", " 9", "-10", "+ten", " 11", " 12", " 13", "…6"]
+        );
+
+        // Expanding a fold shows its lines.
+        let DiffRow::Fold(fold) = &rows[0] else {
+            panic!("expected a fold");
+        };
+        let fold_id = diff.lines[fold.start].old_row.unwrap();
+        let rows = diff.rows(&HashSet::from_iter([fold_id]));
+        assert_eq!(rows.len(), 16);
+        assert_eq!(rows[..8], (0..8).map(DiffRow::Line).collect::<Vec<_>>());
+        assert_eq!(rows.last(), Some(&DiffRow::Fold(15..21)));
+    }
+}
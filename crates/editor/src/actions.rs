@@ -80,6 +80,12 @@ pub struct FoldAt {
     pub buffer_row: MultiBufferRow,
 }
 
+#[derive(PartialEq, Clone, Deserialize, Default)]
+pub struct FoldAtLevel {
+    /// How deeply nested the folded ranges are, starting at 1 for the outermost ones.
+    pub level: u32,
+}
+
 #[derive(PartialEq, Clone, Deserialize, Default)]
 pub struct UnfoldAt {
     pub buffer_row: MultiBufferRow,
@@ -108,6 +114,29 @@ pub struct SelectDownByLines {
     pub(super) lines: u32,
 }
 
+#[derive(PartialEq, Clone, Deserialize, Default)]
+pub struct SortLines {
+    #[serde(default)]
+    pub descending: bool,
+    #[serde(default)]
+    pub case_insensitive: bool,
+    /// Compare runs of digits by their numeric value, so that `item2` sorts before `item10`.
+    #[serde(default)]
+    pub natural: bool,
+}
+
+/// Sets a mark at the cursor, with the given letter or else the lowest unused number.
+#[derive(PartialEq, Clone, Deserialize, Default)]
+pub struct SetMark {
+    #[serde(default)]
+    pub letter: Option<char>,
+}
+
+#[derive(PartialEq, Clone, Deserialize, Default)]
+pub struct JumpToMark {
+    pub name: String,
+}
+
 #[derive(PartialEq, Clone, Deserialize, Default)]
 pub struct ExpandExcerpts {
     #[serde(default)]
@@ -135,6 +164,8 @@ impl_actions!(
         ExpandExcerptsUp,
         ExpandExcerptsDown,
         FoldAt,
+        FoldAtLevel,
+        JumpToMark,
         MoveDownByLines,
         MovePageDown,
         MovePageUp,
@@ -147,6 +178,8 @@ impl_actions!(
         SelectToBeginningOfLine,
         SelectToEndOfLine,
         SelectUpByLines,
+        SetMark,
+        SortLines,
         ToggleCodeActions,
         ToggleComments,
         UnfoldAt,
@@ -161,13 +194,17 @@ gpui::actions!(
         AcceptPartialInlineCompletion,
         AddSelectionAbove,
         AddSelectionBelow,
+        AddWordToDictionary,
         Backspace,
         Cancel,
+        ClearMarks,
         ConfirmRename,
         ContextMenuFirst,
         ContextMenuLast,
         ContextMenuNext,
         ContextMenuPrev,
+        ConvertIndentationToSpaces,
+        ConvertIndentationToTabs,
         ConvertToKebabCase,
         ConvertToLowerCamelCase,
         ConvertToLowerCase,
@@ -178,9 +215,11 @@ gpui::actions!(
         ConvertToUpperCase,
         Copy,
         CopyHighlightJson,
+        CopyFileLocation,
         CopyPath,
         CopyPermalinkToLine,
         CopyRelativePath,
+        CorrectSpelling,
         Cut,
         CutToEndOfLine,
         Delete,
@@ -194,10 +233,13 @@ gpui::actions!(
         DisplayCursorNames,
         DuplicateLineDown,
         DuplicateLineUp,
+        DuplicateSelection,
         ExpandAllHunkDiffs,
         ExpandMacroRecursively,
+        ExpandSelection,
         FindAllReferences,
         Fold,
+        FoldAll,
         FoldSelectedRanges,
         Format,
         GoToDefinition,
@@ -215,6 +257,7 @@ gpui::actions!(
         Hover,
         Indent,
         JoinLines,
+        JoinLinesWithoutSpace,
         LineDown,
         LineUp,
         MoveDown,
@@ -225,11 +268,13 @@ gpui::actions!(
         MoveToBeginning,
         MoveToEnclosingBracket,
         MoveToEnd,
+        MoveToEndOfBracketScope,
         MoveToEndOfParagraph,
         MoveToNextSubwordEnd,
         MoveToNextWordEnd,
         MoveToPreviousSubwordStart,
         MoveToPreviousWordStart,
+        MoveToStartOfBracketScope,
         MoveToStartOfParagraph,
         MoveUp,
         Newline,
@@ -239,12 +284,14 @@ gpui::actions!(
         NextScreen,
         OpenExcerpts,
         OpenExcerptsSplit,
+        OpenInDefaultApp,
         OpenPermalinkToLine,
         OpenUrl,
         Outdent,
         PageDown,
         PageUp,
         Paste,
+        PasteAndMatchIndentation,
         PreviousInlineCompletion,
         Redo,
         RedoSelection,
@@ -252,6 +299,7 @@ gpui::actions!(
         RestartLanguageServer,
         RevealInFinder,
         ReverseLines,
+        RevertFile,
         RevertSelectedHunks,
         ScrollCursorBottom,
         ScrollCursorCenter,
@@ -276,23 +324,27 @@ gpui::actions!(
         ShowCharacterPalette,
         ShowCompletions,
         ShowInlineCompletion,
+        ShrinkSelection,
         ShuffleLines,
         SortLinesCaseInsensitive,
         SortLinesCaseSensitive,
         SplitSelectionIntoLines,
         Tab,
         TabPrev,
+        ToggleBlockComments,
         ToggleGitBlame,
         ToggleGitBlameInline,
         ToggleHunkDiff,
         ToggleInlayHints,
         ToggleLineNumbers,
         ToggleIndentGuides,
+        ToggleReadOnly,
         ToggleSoftWrap,
         ToggleTabBar,
         Transpose,
         Undo,
         UndoSelection,
+        UnfoldAll,
         UnfoldLines,
         UniqueLinesCaseInsensitive,
         UniqueLinesCaseSensitive,
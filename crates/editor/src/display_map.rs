@@ -26,6 +26,7 @@ mod wrap_map;
 
 use crate::{
     hover_links::InlayHighlight, movement::TextLayoutDetails, EditorStyle, InlayId, RowExt,
+    MAX_LINE_LEN,
 };
 pub use block_map::{
     BlockBufferRows, BlockChunks as DisplayChunks, BlockContext, BlockDisposition, BlockId,
@@ -37,11 +38,13 @@ pub use flap_map::*;
 pub use fold_map::{Fold, FoldId, FoldPlaceholder, FoldPoint};
 use fold_map::{FoldMap, FoldSnapshot};
 use gpui::{
-    AnyElement, Font, HighlightStyle, LineLayout, Model, ModelContext, Pixels, UnderlineStyle,
+    AnyElement, AppContext, Font, HighlightStyle, LineLayout, Model, ModelContext, Pixels,
+    UnderlineStyle,
 };
 pub(crate) use inlay_map::Inlay;
 use inlay_map::{InlayMap, InlaySnapshot};
 pub use inlay_map::{InlayOffset, InlayPoint};
+use itertools::Either;
 use language::{
     language_settings::language_settings, ChunkRenderer, OffsetUtf16, Point,
     Subscription as BufferSubscription,
@@ -56,6 +59,7 @@ use std::{
     any::TypeId,
     borrow::Cow,
     fmt::Debug,
+    iter,
     num::NonZeroU32,
     ops::{Add, Range, Sub},
     sync::Arc,
@@ -108,6 +112,9 @@ pub struct DisplayMap {
     /// A container for explicitly foldable ranges, which supersede indentation based fold range suggestions.
     flap_map: FlapMap,
     fold_placeholder: FoldPlaceholder,
+    /// Lines with more characters than this are considered long, see
+    /// [`DisplaySnapshot::has_long_lines`].
+    long_line_threshold: usize,
     pub clip_at_line_ends: bool,
 }
 
@@ -155,6 +162,7 @@ impl DisplayMap {
             fold_placeholder,
             text_highlights: Default::default(),
             inlay_highlights: Default::default(),
+            long_line_threshold: usize::MAX,
             clip_at_line_ends: false,
         }
     }
@@ -183,6 +191,7 @@ impl DisplayMap {
             inlay_highlights: self.inlay_highlights.clone(),
             clip_at_line_ends: self.clip_at_line_ends,
             fold_placeholder: self.fold_placeholder.clone(),
+            long_line_threshold: self.long_line_threshold,
         }
     }
 
@@ -397,6 +406,28 @@ impl DisplayMap {
             .update(cx, |map, cx| map.set_wrap_width(width, cx))
     }
 
+    pub fn set_long_line_threshold(
+        &mut self,
+        long_line_threshold: usize,
+        cx: &mut ModelContext<Self>,
+    ) -> bool {
+        if long_line_threshold == self.long_line_threshold {
+            return false;
+        }
+
+        self.long_line_threshold = long_line_threshold;
+        self.wrap_map.update(cx, |map, cx| {
+            map.set_long_line_threshold(long_line_threshold, cx)
+        });
+        true
+    }
+
+    /// Returns whether the buffer has a line with more characters than the long line
+    /// threshold.
+    pub fn has_long_lines(&self, cx: &AppContext) -> bool {
+        has_long_lines(&self.buffer.read(cx).read(cx), self.long_line_threshold)
+    }
+
     pub(crate) fn current_inlays(&self) -> impl Iterator<Item = &Inlay> {
         self.inlay_map.current_inlays()
     }
@@ -481,6 +512,11 @@ pub struct DisplaySnapshot {
     inlay_highlights: InlayHighlights,
     clip_at_line_ends: bool,
     pub(crate) fold_placeholder: FoldPlaceholder,
+    long_line_threshold: usize,
+}
+
+fn has_long_lines(buffer: &MultiBufferSnapshot, long_line_threshold: usize) -> bool {
+    buffer.text_summary().longest_row_chars as usize > long_line_threshold
 }
 
 impl DisplaySnapshot {
@@ -493,6 +529,14 @@ impl DisplaySnapshot {
         self.buffer_snapshot.len() == 0
     }
 
+    /// Returns whether the buffer has a line with more characters than the long line
+    /// threshold. Such lines are only laid out in part, soft wrapped without being measured,
+    /// and turn off the highlighting of brackets and occurrences, so that they don't need to
+    /// be scanned in full whenever something changes.
+    pub fn has_long_lines(&self) -> bool {
+        has_long_lines(&self.buffer_snapshot, self.long_line_threshold)
+    }
+
     pub fn buffer_rows(
         &self,
         start_row: DisplayRow,
@@ -726,6 +770,51 @@ impl DisplaySnapshot {
         })
     }
 
+    /// Like [`Self::highlighted_chunks`], but leaves out the text of each row past
+    /// `max_line_len` bytes without reading it. Each row, including the last one, ends with a
+    /// newline.
+    pub fn truncated_highlighted_chunks<'a>(
+        &'a self,
+        display_rows: Range<DisplayRow>,
+        max_line_len: usize,
+        language_aware: bool,
+        editor_style: &'a EditorStyle,
+    ) -> impl Iterator<Item = HighlightedChunk<'a>> {
+        (display_rows.start.0..display_rows.end.0).flat_map(move |row| {
+            let row = DisplayRow(row);
+            let mut chunks =
+                self.highlighted_chunks(row..row.next_row(), language_aware, editor_style);
+            let mut len = 0;
+            let mut row_done = false;
+            iter::from_fn(move || {
+                if row_done {
+                    return None;
+                }
+                let mut chunk = chunks.next()?;
+                if let Some(newline_ix) = chunk.text.find('\n') {
+                    chunk.text = &chunk.text[..newline_ix];
+                    row_done = true;
+                }
+                if chunk.renderer.is_none() && len + chunk.text.len() > max_line_len {
+                    let mut truncated_len = max_line_len.saturating_sub(len);
+                    while !chunk.text.is_char_boundary(truncated_len) {
+                        truncated_len -= 1;
+                    }
+                    chunk.text = &chunk.text[..truncated_len];
+                    row_done = true;
+                }
+                len += chunk.text.len();
+                Some(chunk)
+            })
+            .chain([HighlightedChunk {
+                text: "\n",
+                style: None,
+                is_tab: false,
+                renderer: None,
+            }])
+        })
+    }
+
     pub fn layout_row(
         &self,
         display_row: DisplayRow,
@@ -742,7 +831,17 @@ impl DisplaySnapshot {
         let mut line = String::new();
 
         let range = display_row..display_row.next_row();
-        for chunk in self.highlighted_chunks(range, false, &editor_style) {
+        let chunks = if self.has_long_lines() {
+            Either::Left(self.truncated_highlighted_chunks(
+                range,
+                MAX_LINE_LEN,
+                false,
+                &editor_style,
+            ))
+        } else {
+            Either::Right(self.highlighted_chunks(range, false, &editor_style))
+        };
+        for chunk in chunks {
             line.push_str(chunk.text);
 
             let text_style = if let Some(style) = chunk.style {
@@ -1108,6 +1207,35 @@ impl ToDisplayPoint for Anchor {
     }
 }
 
+/// The kinds of characters that get drawn with a symbol when whitespace is shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvisibleKind {
+    Space,
+    /// A character that looks like a regular space but isn't one, such as a non-breaking
+    /// space (U+00A0) or a figure space (U+2007). They are rarely intentional in source code.
+    UnusualSpace,
+    /// A character that takes up no room at all, such as a zero-width space or a byte order mark.
+    ZeroWidth,
+}
+
+impl InvisibleKind {
+    /// Classifies a character of a text chunk. Tabs are reported by the tab map as chunks of
+    /// their own, so they aren't classified here.
+    pub fn for_char(c: char) -> Option<Self> {
+        match c {
+            ' ' | '\u{0B}' | '\u{0C}' | '\r' => Some(Self::Space),
+            '\u{00A0}'
+            | '\u{1680}'
+            | '\u{2000}'..='\u{200A}'
+            | '\u{202F}'
+            | '\u{205F}'
+            | '\u{3000}' => Some(Self::UnusualSpace),
+            '\u{200B}'..='\u{200D}' | '\u{2060}' | '\u{FEFF}' => Some(Self::ZeroWidth),
+            _ => None,
+        }
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
     use super::*;
@@ -1517,6 +1645,79 @@ pub mod tests {
         });
     }
 
+    #[gpui::test]
+    async fn test_long_lines(cx: &mut gpui::TestAppContext) {
+        cx.background_executor
+            .set_block_on_ticks(usize::MAX..=usize::MAX);
+        cx.update(|cx| init_test(cx, |_| {}));
+
+        let long_line = "one two three four five six seven eight nine ten";
+        let text = format!("{long_line}\nshort");
+        let buffer = cx.update(|cx| MultiBuffer::build_simple(&text, cx));
+        let map = cx.new_model(|cx| {
+            DisplayMap::new(
+                buffer.clone(),
+                font("Helvetica"),
+                px(12.0),
+                Some(px(64.)),
+                true,
+                1,
+                1,
+                0,
+                FoldPlaceholder::test(),
+                cx,
+            )
+        });
+
+        // Below the threshold, lines are wrapped at word boundaries.
+        let snapshot = map.update(cx, |map, cx| map.snapshot(cx));
+        assert!(!snapshot.has_long_lines());
+        let wrapped_text = snapshot.text();
+        let (wrapped_line, _) = wrapped_text.rsplit_once('\n').unwrap();
+        assert!(wrapped_line
+            .lines()
+            .all(|row| row.ends_with(' ') || long_line.ends_with(row)));
+
+        // Above it, they are cut into rows of the same length, regardless of their contents.
+        map.update(cx, |map, cx| map.set_long_line_threshold(20, cx));
+        let snapshot = map.update(cx, |map, cx| map.snapshot(cx));
+        assert!(snapshot.has_long_lines());
+        assert!(map.update(cx, |map, cx| map.has_long_lines(cx)));
+        let chunked_text = snapshot.text();
+        let (chunked_line, short_line) = chunked_text.rsplit_once('\n').unwrap();
+        assert_eq!(short_line, "short");
+        assert_eq!(chunked_line.replace('\n', ""), long_line);
+        let rows = chunked_line.lines().collect::<Vec<_>>();
+        assert!(rows.len() > 1);
+        let (last_row, full_rows) = rows.split_last().unwrap();
+        assert!(full_rows.iter().all(|row| row.len() == full_rows[0].len()));
+        assert!(last_row.len() <= full_rows[0].len());
+
+        // The truncated chunks of a long line stop at the given length.
+        let truncated_text = snapshot
+            .truncated_highlighted_chunks(
+                DisplayRow(0)..DisplayRow(2),
+                3,
+                false,
+                &Default::default(),
+            )
+            .map(|chunk| chunk.text)
+            .collect::<String>();
+        assert_eq!(
+            truncated_text,
+            format!("{}\n{}\n", &rows[0][..3], &rows[1][..3])
+        );
+
+        // Lines can still be edited, and shortening them restores the regular wrapping.
+        buffer.update(cx, |buffer, cx| {
+            let end = long_line.len();
+            buffer.edit([(10..end, "")], None, cx)
+        });
+        let snapshot = map.update(cx, |map, cx| map.snapshot(cx));
+        assert!(!snapshot.has_long_lines());
+        assert_eq!(snapshot.buffer_snapshot.text(), "one two th\nshort");
+    }
+
     #[gpui::test]
     fn test_text_chunks(cx: &mut gpui::AppContext) {
         init_test(cx, |_| {});
@@ -1679,6 +1880,37 @@ pub mod tests {
         );
     }
 
+    #[gpui::test]
+    async fn test_chunks_without_language(cx: &mut gpui::TestAppContext) {
+        let theme =
+            SyntaxTheme::new_test(vec![("mod.body", Hsla::red()), ("fn.name", Hsla::blue())]);
+        cx.update(|cx| init_test(cx, |_| {}));
+
+        // A buffer whose language isn't recognized is never parsed and yields no highlights.
+        let buffer = cx.update(|cx| MultiBuffer::build_simple("fn outer() {}\nmod module {}", cx));
+        buffer.update(cx, |buffer, cx| {
+            assert!(!buffer.as_singleton().unwrap().read(cx).is_parsing());
+        });
+        let map = cx.new_model(|cx| {
+            DisplayMap::new(
+                buffer,
+                font("Helvetica"),
+                px(14.0),
+                None,
+                true,
+                1,
+                1,
+                1,
+                FoldPlaceholder::test(),
+                cx,
+            )
+        });
+        assert_eq!(
+            cx.update(|cx| syntax_chunks(DisplayRow(0)..DisplayRow(2), &map, &theme, cx)),
+            vec![("fn outer() {}\nmod module {}".to_string(), None)]
+        );
+    }
+
     #[gpui::test]
     async fn test_chunks_with_soft_wrapping(cx: &mut gpui::TestAppContext) {
         use unindent::Unindent as _;
@@ -1860,6 +2092,25 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn test_invisible_kinds() {
+        assert_eq!(InvisibleKind::for_char(' '), Some(InvisibleKind::Space));
+        assert_eq!(InvisibleKind::for_char('a'), None);
+        assert_eq!(InvisibleKind::for_char('\n'), None);
+        assert_eq!(
+            InvisibleKind::for_char('\u{00A0}'),
+            Some(InvisibleKind::UnusualSpace)
+        );
+        assert_eq!(
+            InvisibleKind::for_char('\u{2007}'),
+            Some(InvisibleKind::UnusualSpace)
+        );
+        assert_eq!(
+            InvisibleKind::for_char('\u{FEFF}'),
+            Some(InvisibleKind::ZeroWidth)
+        );
+    }
+
     #[gpui::test]
     fn test_clip_point(cx: &mut gpui::AppContext) {
         init_test(cx, |_| {});
@@ -1990,10 +2241,10 @@ pub mod tests {
             )
         });
         let map = map.update(cx, |map, cx| map.snapshot(cx));
-        assert_eq!(map.text(), "✅       α\nβ   \n🏀β      γ");
+        assert_eq!(map.text(), "✅      α\nβ   \n🏀β     γ");
         assert_eq!(
             map.text_chunks(DisplayRow(0)).collect::<String>(),
-            "✅       α\nβ   \n🏀β      γ"
+            "✅      α\nβ   \n🏀β     γ"
         );
         assert_eq!(
             map.text_chunks(DisplayRow(1)).collect::<String>(),
@@ -2001,11 +2252,11 @@ pub mod tests {
         );
         assert_eq!(
             map.text_chunks(DisplayRow(2)).collect::<String>(),
-            "🏀β      γ"
+            "🏀β     γ"
         );
 
         let point = MultiBufferPoint::new(0, "✅\t\t".len() as u32);
-        let display_point = DisplayPoint::new(DisplayRow(0), "✅       ".len() as u32);
+        let display_point = DisplayPoint::new(DisplayRow(0), "✅      ".len() as u32);
         assert_eq!(point.to_display_point(&map), display_point);
         assert_eq!(display_point.to_point(&map), point);
 
@@ -2015,13 +2266,13 @@ pub mod tests {
         assert_eq!(display_point.to_point(&map), point,);
 
         let point = MultiBufferPoint::new(2, "🏀β\t\t".len() as u32);
-        let display_point = DisplayPoint::new(DisplayRow(2), "🏀β      ".len() as u32);
+        let display_point = DisplayPoint::new(DisplayRow(2), "🏀β     ".len() as u32);
         assert_eq!(point.to_display_point(&map), display_point);
         assert_eq!(display_point.to_point(&map), point,);
 
         // Display points inside of expanded tabs
         assert_eq!(
-            DisplayPoint::new(DisplayRow(0), "✅      ".len() as u32).to_point(&map),
+            DisplayPoint::new(DisplayRow(0), "✅     ".len() as u32).to_point(&map),
             MultiBufferPoint::new(0, "✅\t".len() as u32),
         );
         assert_eq!(
@@ -6,6 +6,7 @@ use language::{Chunk, Point};
 use multi_buffer::MultiBufferSnapshot;
 use std::{cmp, mem, num::NonZeroU32, ops::Range};
 use sum_tree::Bias;
+use text::cell_width;
 
 const MAX_EXPANSION_COLUMN: u32 = 256;
 
@@ -223,7 +224,7 @@ impl TabSnapshot {
         language_aware: bool,
         highlights: Highlights<'a>,
     ) -> TabChunks<'a> {
-        let (input_start, expanded_char_column, to_next_stop) =
+        let (input_start, expanded_cell_column, to_next_stop) =
             self.to_fold_point(range.start, Bias::Left);
         let input_column = input_start.column();
         let input_start = input_start.to_offset(&self.fold_snapshot);
@@ -244,7 +245,8 @@ impl TabSnapshot {
                 highlights,
             ),
             input_column,
-            column: expanded_char_column,
+            column: expanded_cell_column,
+            previous_char: None,
             max_expansion_column: self.max_expansion_column,
             output_position: range.start.0,
             max_output_position: range.end.0,
@@ -293,11 +295,11 @@ impl TabSnapshot {
     pub fn to_fold_point(&self, output: TabPoint, bias: Bias) -> (FoldPoint, u32, u32) {
         let chars = self.fold_snapshot.chars_at(FoldPoint::new(output.row(), 0));
         let expanded = output.column();
-        let (collapsed, expanded_char_column, to_next_stop) =
+        let (collapsed, expanded_cell_column, to_next_stop) =
             self.collapse_tabs(chars, expanded, bias);
         (
             FoldPoint::new(output.row(), collapsed),
-            expanded_char_column,
+            expanded_cell_column,
             to_next_stop,
         )
     }
@@ -319,23 +321,25 @@ impl TabSnapshot {
     fn expand_tabs(&self, chars: impl Iterator<Item = char>, column: u32) -> u32 {
         let tab_size = self.tab_size.get();
 
-        let mut expanded_chars = 0;
+        let mut expanded_cells = 0;
         let mut expanded_bytes = 0;
         let mut collapsed_bytes = 0;
+        let mut previous = None;
         let end_column = column.min(self.max_expansion_column);
         for c in chars {
             if collapsed_bytes >= end_column {
                 break;
             }
             if c == '\t' {
-                let tab_len = tab_size - expanded_chars % tab_size;
+                let tab_len = tab_size - expanded_cells % tab_size;
                 expanded_bytes += tab_len;
-                expanded_chars += tab_len;
+                expanded_cells += tab_len;
             } else {
                 expanded_bytes += c.len_utf8() as u32;
-                expanded_chars += 1;
+                expanded_cells += cell_width(c, previous);
             }
             collapsed_bytes += c.len_utf8() as u32;
+            previous = Some(c);
         }
         expanded_bytes + column.saturating_sub(collapsed_bytes)
     }
@@ -349,8 +353,9 @@ impl TabSnapshot {
         let tab_size = self.tab_size.get();
 
         let mut expanded_bytes = 0;
-        let mut expanded_chars = 0;
+        let mut expanded_cells = 0;
         let mut collapsed_bytes = 0;
+        let mut previous = None;
         for c in chars {
             if expanded_bytes >= column {
                 break;
@@ -360,31 +365,32 @@ impl TabSnapshot {
             }
 
             if c == '\t' {
-                let tab_len = tab_size - (expanded_chars % tab_size);
-                expanded_chars += tab_len;
+                let tab_len = tab_size - (expanded_cells % tab_size);
+                expanded_cells += tab_len;
                 expanded_bytes += tab_len;
                 if expanded_bytes > column {
-                    expanded_chars -= expanded_bytes - column;
+                    expanded_cells -= expanded_bytes - column;
                     return match bias {
-                        Bias::Left => (collapsed_bytes, expanded_chars, expanded_bytes - column),
-                        Bias::Right => (collapsed_bytes + 1, expanded_chars, 0),
+                        Bias::Left => (collapsed_bytes, expanded_cells, expanded_bytes - column),
+                        Bias::Right => (collapsed_bytes + 1, expanded_cells, 0),
                     };
                 }
             } else {
-                expanded_chars += 1;
+                let width = cell_width(c, previous);
+                expanded_cells += width;
                 expanded_bytes += c.len_utf8() as u32;
-            }
-
-            if expanded_bytes > column && matches!(bias, Bias::Left) {
-                expanded_chars -= 1;
-                break;
+                if expanded_bytes > column && matches!(bias, Bias::Left) {
+                    expanded_cells -= width;
+                    break;
+                }
             }
 
             collapsed_bytes += c.len_utf8() as u32;
+            previous = Some(c);
         }
         (
             collapsed_bytes + column.saturating_sub(expanded_bytes),
-            expanded_chars,
+            expanded_cells,
             0,
         )
     }
@@ -474,7 +480,10 @@ const SPACES: &str = "                ";
 pub struct TabChunks<'a> {
     fold_chunks: FoldChunks<'a>,
     chunk: Chunk<'a>,
+    /// The cells of the line that are displayed before the current position, which decide
+    /// where the next tab stop is.
     column: u32,
+    previous_char: Option<char>,
     max_expansion_column: u32,
     output_position: Point,
     input_column: u32,
@@ -524,6 +533,7 @@ impl<'a> Iterator for TabChunks<'a> {
                         );
                         len = next_output_position.column - self.output_position.column;
                         self.column += len;
+                        self.previous_char = Some(c);
                         self.input_column += 1;
                         self.output_position = next_output_position;
                         return Some(Chunk {
@@ -535,11 +545,13 @@ impl<'a> Iterator for TabChunks<'a> {
                 }
                 '\n' => {
                     self.column = 0;
+                    self.previous_char = None;
                     self.input_column = 0;
                     self.output_position += Point::new(1, 0);
                 }
                 _ => {
-                    self.column += 1;
+                    self.column += cell_width(c, self.previous_char);
+                    self.previous_char = Some(c);
                     if !self.inside_leading_tab {
                         self.input_column += c.len_utf8() as u32;
                     }
@@ -622,6 +634,65 @@ mod tests {
         }
     }
 
+    #[gpui::test]
+    fn test_wide_and_zero_width_chars(cx: &mut gpui::AppContext) {
+        // Tab stops are placed by the cells that the preceding text takes up: two for CJK
+        // characters and emoji sequences, and none for combining accents.
+        let family = "👨\u{200D}👩\u{200D}👧";
+        let clusters = [
+            ("中", "中"),
+            ("\t", "  "),
+            ("a\u{301}", "a\u{301}"),
+            ("\t", "   "),
+            (family, family),
+            ("\t", "  "),
+            ("x", "x"),
+        ];
+        let input = clusters.iter().map(|(input, _)| *input).collect::<String>();
+        let output = clusters
+            .iter()
+            .map(|(_, output)| *output)
+            .collect::<String>();
+
+        let buffer = MultiBuffer::build_simple(&input, cx);
+        let buffer_snapshot = buffer.read(cx).snapshot(cx);
+        let (_, inlay_snapshot) = InlayMap::new(buffer_snapshot.clone());
+        let (_, fold_snapshot) = FoldMap::new(inlay_snapshot);
+        let (_, tab_snapshot) = TabMap::new(fold_snapshot, 4.try_into().unwrap());
+        assert_eq!(tab_snapshot.text(), output);
+
+        let mut input_point = Point::zero();
+        let mut output_point = Point::zero();
+        for (input_cluster, output_cluster) in clusters {
+            assert_eq!(
+                tab_snapshot.to_tab_point(FoldPoint(input_point)),
+                TabPoint(output_point),
+                "to_tab_point({input_point:?})"
+            );
+            assert_eq!(
+                tab_snapshot
+                    .to_fold_point(TabPoint(output_point), Bias::Left)
+                    .0,
+                FoldPoint(input_point),
+                "to_fold_point({output_point:?})"
+            );
+            assert_eq!(
+                tab_snapshot
+                    .chunks(
+                        TabPoint(output_point)..tab_snapshot.max_point(),
+                        false,
+                        Highlights::default(),
+                    )
+                    .map(|c| c.text)
+                    .collect::<String>(),
+                &output[output_point.column as usize..],
+                "text from {output_point:?}"
+            );
+            input_point.column += input_cluster.len() as u32;
+            output_point.column += output_cluster.len() as u32;
+        }
+    }
+
     #[gpui::test]
     fn test_long_lines_with_character_spanning_max_expansion_column(cx: &mut gpui::AppContext) {
         let max_expansion_column = 8;
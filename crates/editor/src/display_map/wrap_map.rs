@@ -4,6 +4,7 @@ use super::{
     Highlights,
 };
 use gpui::{AppContext, Context, Font, LineWrapper, Model, ModelContext, Pixels, Task};
+use itertools::Either;
 use language::{Chunk, Point};
 use lazy_static::lazy_static;
 use multi_buffer::MultiBufferSnapshot;
@@ -24,6 +25,9 @@ pub struct WrapMap {
     interpolated_edits: Patch<u32>,
     edits_since_sync: Patch<u32>,
     wrap_width: Option<Pixels>,
+    /// Lines with more characters than this are wrapped every so many characters, rather
+    /// than by measuring them.
+    long_line_threshold: usize,
     background_task: Option<Task<()>>,
     font_with_size: (Font, Pixels),
 }
@@ -80,6 +84,7 @@ impl WrapMap {
             let mut this = Self {
                 font_with_size: (font, font_size),
                 wrap_width: None,
+                long_line_threshold: usize::MAX,
                 pending_edits: Default::default(),
                 interpolated_edits: Default::default(),
                 edits_since_sync: Default::default(),
@@ -149,6 +154,22 @@ impl WrapMap {
         true
     }
 
+    pub fn set_long_line_threshold(
+        &mut self,
+        long_line_threshold: usize,
+        cx: &mut ModelContext<Self>,
+    ) -> bool {
+        if long_line_threshold == self.long_line_threshold {
+            return false;
+        }
+
+        self.long_line_threshold = long_line_threshold;
+        if self.wrap_width.is_some() {
+            self.rewrap(cx);
+        }
+        true
+    }
+
     fn rewrap(&mut self, cx: &mut ModelContext<Self>) {
         self.background_task.take();
         self.interpolated_edits.clear();
@@ -159,6 +180,7 @@ impl WrapMap {
 
             let text_system = cx.text_system().clone();
             let (font, font_size) = self.font_with_size.clone();
+            let long_line_threshold = self.long_line_threshold;
             let task = cx.background_executor().spawn(async move {
                 let mut line_wrapper = text_system.line_wrapper(font, font_size);
                 let tab_snapshot = new_snapshot.tab_snapshot.clone();
@@ -171,6 +193,7 @@ impl WrapMap {
                             new: range.clone(),
                         }],
                         wrap_width,
+                        long_line_threshold,
                         &mut line_wrapper,
                     )
                     .await;
@@ -243,12 +266,19 @@ impl WrapMap {
                 let mut snapshot = self.snapshot.clone();
                 let text_system = cx.text_system().clone();
                 let (font, font_size) = self.font_with_size.clone();
+                let long_line_threshold = self.long_line_threshold;
                 let update_task = cx.background_executor().spawn(async move {
                     let mut edits = Patch::default();
                     let mut line_wrapper = text_system.line_wrapper(font, font_size);
                     for (tab_snapshot, tab_edits) in pending_edits {
                         let wrap_edits = snapshot
-                            .update(tab_snapshot, &tab_edits, wrap_width, &mut line_wrapper)
+                            .update(
+                                tab_snapshot,
+                                &tab_edits,
+                                wrap_width,
+                                long_line_threshold,
+                                &mut line_wrapper,
+                            )
                             .await;
                         edits = edits.compose(&wrap_edits);
                     }
@@ -390,6 +420,7 @@ impl WrapSnapshot {
         new_tab_snapshot: TabSnapshot,
         tab_edits: &[TabEdit],
         wrap_width: Pixels,
+        long_line_threshold: usize,
         line_wrapper: &mut LineWrapper,
     ) -> Patch<u32> {
         #[derive(Debug)]
@@ -466,8 +497,15 @@ impl WrapSnapshot {
                         break;
                     }
 
+                    let is_long_line = line.len() > long_line_threshold
+                        && line.chars().count() > long_line_threshold;
+                    let boundaries = if is_long_line {
+                        Either::Left(line_wrapper.chunk_line(&line, wrap_width))
+                    } else {
+                        Either::Right(line_wrapper.wrap_line(&line, wrap_width))
+                    };
                     let mut prev_boundary_ix = 0;
-                    for boundary in line_wrapper.wrap_line(&line, wrap_width) {
+                    for boundary in boundaries {
                         let wrapped = &line[prev_boundary_ix..boundary.ix];
                         push_isomorphic(&mut edit_transforms, TextSummary::from(wrapped));
                         edit_transforms.push(Transform::wrap(boundary.next_indent));
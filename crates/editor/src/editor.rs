@@ -29,13 +29,18 @@ mod inlay_hint_cache;
 mod inline_completion_provider;
 pub mod items;
 mod linked_editing_ranges;
+mod marks;
 mod mouse_context_menu;
 pub mod movement;
 mod persistence;
+mod recovery;
 mod rust_analyzer_ext;
+mod save_progress;
 pub mod scroll;
 mod selections_collection;
+mod spelling;
 pub mod tasks;
+mod word_highlights;
 
 #[cfg(test)]
 mod editor_tests;
@@ -54,7 +59,7 @@ use convert_case::{Case, Casing};
 use debounced_delay::DebouncedDelay;
 use display_map::*;
 pub use display_map::{DisplayPoint, FoldPlaceholder};
-pub use editor_settings::{CurrentLineHighlight, EditorSettings};
+pub use editor_settings::{CurrentLineHighlight, EditorSettings, ScrollBeyondLastLine};
 use element::LineWithInvisibles;
 pub use element::{
     CursorLayout, EditorElement, HighlightedRange, HighlightedRangeLine, PointForPosition,
@@ -82,13 +87,14 @@ pub use inline_completion_provider::*;
 pub use items::MAX_TAB_TITLE_LEN;
 use itertools::Itertools;
 use language::{
-    char_kind,
+    char_kind, is_word_boundary,
     language_settings::{self, all_language_settings, InlayHintSettings},
     markdown, point_from_lsp, AutoindentMode, BracketPair, Buffer, Capability, CharKind, CodeLabel,
     CursorShape, Diagnostic, Documentation, IndentKind, IndentSize, Language, OffsetRangeExt,
     Point, Selection, SelectionGoal, TransactionId,
 };
 use language::{BufferRow, Runnable, RunnableRange};
+pub use language::{Mark, MarkId, MarkName, Marks};
 use linked_editing_ranges::refresh_linked_ranges;
 use task::{ResolvedTask, TaskTemplate, TaskVariables};
 
@@ -103,12 +109,14 @@ pub use multi_buffer::{
 use multi_buffer::{ExpandExcerptDirection, MultiBufferPoint, MultiBufferRow, ToOffsetUtf16};
 use ordered_float::OrderedFloat;
 use parking_lot::{Mutex, RwLock};
+use persistence::DB;
 use project::project_settings::{GitGutterSetting, ProjectSettings};
 use project::{
-    CodeAction, Completion, FormatTrigger, Item, Location, Project, ProjectPath,
-    ProjectTransaction, TaskSourceKind, WorktreeId,
+    search::SearchQuery, CodeAction, Completion, FormatTrigger, Item, Location, Project,
+    ProjectPath, ProjectTransaction, TaskSourceKind, WorktreeId,
 };
 use rand::prelude::*;
+use recovery::BufferRecovery;
 use rpc::{proto::*, ErrorExt};
 use scroll::{Autoscroll, OngoingScroll, ScrollAnchor, ScrollManager, ScrollbarAutoHide};
 use selections_collection::{resolve_multiple, MutableSelectionsCollection, SelectionsCollection};
@@ -116,6 +124,8 @@ use serde::{Deserialize, Serialize};
 use settings::{update_settings_file, Settings, SettingsStore};
 use smallvec::SmallVec;
 use snippet::Snippet;
+use spell_check::UserDictionary;
+use spelling::Spelling;
 use std::{
     any::TypeId,
     borrow::Cow,
@@ -124,7 +134,7 @@ use std::{
     mem,
     num::NonZeroU32,
     ops::{ControlFlow, Deref, DerefMut, Not as _, Range, RangeInclusive},
-    path::Path,
+    path::{Path, PathBuf},
     rc::Rc,
     sync::Arc,
     time::{Duration, Instant},
@@ -140,11 +150,12 @@ use ui::{
     h_flex, prelude::*, ButtonSize, ButtonStyle, IconButton, IconName, IconSize, ListItem, Popover,
     Tooltip,
 };
-use util::{defer, maybe, post_inc, RangeExt, ResultExt, TryFutureExt};
+use util::{compare_natural, defer, maybe, post_inc, RangeExt, ResultExt, TryFutureExt};
+use word_highlights::refresh_word_highlights;
 use workspace::item::{ItemHandle, PreviewTabsSettings};
 use workspace::notifications::{DetachAndPromptErr, NotificationId};
 use workspace::{
-    searchable::SearchEvent, ItemNavHistory, SplitDirection, ViewId, Workspace, WorkspaceId,
+    searchable::SearchEvent, ItemId, ItemNavHistory, SplitDirection, ViewId, Workspace, WorkspaceId,
 };
 use workspace::{OpenInTerminal, OpenTerminal, TabBarSettings, Toast};
 
@@ -156,8 +167,13 @@ pub const MULTI_BUFFER_EXCERPT_FOOTER_HEIGHT: u8 = 1;
 pub const DEFAULT_MULTIBUFFER_CONTEXT: u32 = 2;
 const CURSOR_BLINK_INTERVAL: Duration = Duration::from_millis(500);
 const MAX_LINE_LEN: usize = 1024;
+const SERIALIZE_SELECTIONS_DEBOUNCE: Duration = Duration::from_millis(100);
 const MIN_NAVIGATION_HISTORY_ROW_DELTA: i64 = 10;
 const MAX_SELECTION_HISTORY_LEN: usize = 1024;
+/// The most occurrences that selecting all of them adds selections for.
+const MAX_SELECT_ALL_MATCHES: usize = 5000;
+/// The longest buffers that selecting all occurrences searches without leaving the main thread.
+const MAX_SYNC_SELECT_ALL_MATCHES_LEN: usize = 1024 * 1024;
 pub(crate) const CURSORS_VISIBLE_FOR: Duration = Duration::from_millis(2000);
 #[doc(hidden)]
 pub const CODE_ACTIONS_DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(250);
@@ -462,6 +478,7 @@ pub struct Editor {
     autoclose_regions: Vec<AutocloseRegion>,
     snippet_stack: InvalidationStack<SnippetState>,
     select_larger_syntax_node_stack: Vec<Box<[Selection<usize>]>>,
+    expand_selection_stack: Vec<Arc<[Selection<Anchor>]>>,
     ime_transaction: Option<TransactionId>,
     active_diagnostics: Option<ActiveDiagnosticGroup>,
     soft_wrap_mode_override: Option<language_settings::SoftWrap>,
@@ -497,6 +514,7 @@ pub struct Editor {
     available_code_actions: Option<(Location, Arc<[CodeAction]>)>,
     code_actions_task: Option<Task<()>>,
     document_highlights_task: Option<Task<()>>,
+    word_highlights_task: Option<Task<()>>,
     linked_editing_range_task: Option<Task<Option<()>>>,
     linked_edit_ranges: linked_editing_ranges::LinkedEditingRanges,
     pending_rename: Option<RenameState>,
@@ -548,6 +566,12 @@ pub struct Editor {
     tasks_update_task: Option<Task<()>>,
     previous_search_ranges: Option<Arc<[Range<Anchor>]>>,
     file_header_size: u8,
+    recovery: Option<Model<BufferRecovery>>,
+    serialize_selections_task: Option<Task<()>>,
+    spelling: Spelling,
+    /// Tells apart the untitled editors created with [`workspace::NewFile`] in the same
+    /// workspace, until they get saved.
+    untitled_number: Option<usize>,
 }
 
 #[derive(Clone)]
@@ -646,6 +670,8 @@ struct SelectionHistory {
     #[allow(clippy::type_complexity)]
     selections_by_transaction:
         HashMap<TransactionId, (Arc<[Selection<Anchor>]>, Option<Arc<[Selection<Anchor>]>>)>,
+    /// The transaction most recently started in this editor.
+    last_transaction_id: Option<TransactionId>,
     mode: SelectionHistoryMode,
     undo_stack: VecDeque<SelectionHistoryEntry>,
     redo_stack: VecDeque<SelectionHistoryEntry>,
@@ -659,6 +685,27 @@ impl SelectionHistory {
     ) {
         self.selections_by_transaction
             .insert(transaction_id, (selections, None));
+        self.last_transaction_id = Some(transaction_id);
+    }
+
+    fn last_transaction_end_selections(&self) -> Option<&Arc<[Selection<Anchor>]>> {
+        self.last_transaction_id
+            .and_then(|transaction_id| self.transaction(transaction_id))
+            .and_then(|(_, end_selections)| end_selections.as_ref())
+    }
+
+    /// Whether the last transaction started in this editor hasn't ended yet.
+    fn transaction_in_progress(&self) -> bool {
+        self.last_transaction_id.is_some() && self.last_transaction_end_selections().is_none()
+    }
+
+    /// Whether the selections moved since the end of the last transaction started in this
+    /// editor.
+    fn selections_moved_since_last_transaction(&self, selections: &[Selection<Anchor>]) -> bool {
+        self.last_transaction_end_selections()
+            .map_or(false, |end_selections| {
+                end_selections.as_ref() != selections
+            })
     }
 
     #[allow(clippy::type_complexity)]
@@ -1626,6 +1673,7 @@ impl Editor {
         clone.selections.clone_state(&self.selections);
         clone.scroll_manager.clone_state(&self.scroll_manager);
         clone.searchable = self.searchable;
+        clone.untitled_number = self.untitled_number;
         clone
     }
 
@@ -1672,7 +1720,7 @@ impl Editor {
         };
         let file_header_size = if show_excerpt_controls { 3 } else { 2 };
         let display_map = cx.new_model(|cx| {
-            DisplayMap::new(
+            let mut display_map = DisplayMap::new(
                 buffer.clone(),
                 style.font(),
                 font_size,
@@ -1683,7 +1731,10 @@ impl Editor {
                 MULTI_BUFFER_EXCERPT_FOOTER_HEIGHT,
                 fold_placeholder,
                 cx,
-            )
+            );
+            display_map
+                .set_long_line_threshold(EditorSettings::get_global(cx).long_line_threshold, cx);
+            display_map
         });
 
         let selections = SelectionsCollection::new(display_map.clone(), buffer.clone());
@@ -1756,6 +1807,7 @@ impl Editor {
             autoclose_regions: Default::default(),
             snippet_stack: Default::default(),
             select_larger_syntax_node_stack: Vec::new(),
+            expand_selection_stack: Vec::new(),
             ime_transaction: Default::default(),
             active_diagnostics: None,
             soft_wrap_mode_override,
@@ -1790,6 +1842,7 @@ impl Editor {
             available_code_actions: Default::default(),
             code_actions_task: Default::default(),
             document_highlights_task: Default::default(),
+            word_highlights_task: Default::default(),
             linked_editing_range_task: Default::default(),
             pending_rename: Default::default(),
             searchable: true,
@@ -1839,6 +1892,7 @@ impl Editor {
                 cx.observe(&display_map, Self::on_display_map_changed),
                 cx.observe(&blink_manager, |_, _, cx| cx.notify()),
                 cx.observe_global::<SettingsStore>(Self::settings_changed),
+                cx.observe_global::<UserDictionary>(Self::recheck_spelling),
                 observe_buffer_font_size_adjustment(cx, |_, cx| cx.notify()),
                 cx.observe_window_activation(|editor, cx| {
                     let active = cx.is_window_active();
@@ -1855,9 +1909,14 @@ impl Editor {
             tasks_update_task: None,
             linked_edit_ranges: Default::default(),
             previous_search_ranges: None,
+            recovery: None,
+            serialize_selections_task: None,
+            spelling: Spelling::default(),
+            untitled_number: None,
         };
         this.tasks_update_task = Some(this.refresh_runnables(cx));
         this._subscriptions.extend(project_subscriptions);
+        this.refresh_spelling(false, cx);
 
         this.end_selection(cx);
         this.scroll_manager.show_scrollbar(cx);
@@ -1940,10 +1999,13 @@ impl Editor {
         cx.spawn(|workspace, mut cx| async move {
             let buffer = create.await?;
             workspace.update(&mut cx, |workspace, cx| {
+                let untitled_number = Self::next_untitled_number(workspace, cx);
                 workspace.add_item_to_active_pane(
-                    Box::new(
-                        cx.new_view(|cx| Editor::for_buffer(buffer, Some(project.clone()), cx)),
-                    ),
+                    Box::new(cx.new_view(|cx| {
+                        let mut editor = Editor::for_buffer(buffer, Some(project.clone()), cx);
+                        editor.untitled_number = Some(untitled_number);
+                        editor
+                    })),
                     None,
                     cx,
                 )
@@ -1970,11 +2032,14 @@ impl Editor {
         cx.spawn(|workspace, mut cx| async move {
             let buffer = create.await?;
             workspace.update(&mut cx, move |workspace, cx| {
+                let untitled_number = Self::next_untitled_number(workspace, cx);
                 workspace.split_item(
                     direction,
-                    Box::new(
-                        cx.new_view(|cx| Editor::for_buffer(buffer, Some(project.clone()), cx)),
-                    ),
+                    Box::new(cx.new_view(|cx| {
+                        let mut editor = Editor::for_buffer(buffer, Some(project.clone()), cx);
+                        editor.untitled_number = Some(untitled_number);
+                        editor
+                    })),
                     cx,
                 )
             })?;
@@ -2006,7 +2071,31 @@ impl Editor {
     }
 
     pub fn title<'a>(&self, cx: &'a AppContext) -> Cow<'a, str> {
-        self.buffer().read(cx).title(cx)
+        match self.untitled_number(cx) {
+            Some(number) if number > 1 => format!("untitled-{number}").into(),
+            _ => self.buffer().read(cx).title(cx),
+        }
+    }
+
+    /// Returns the number that tells this editor apart from the workspace's other untitled
+    /// editors, if it is untitled.
+    fn untitled_number(&self, cx: &AppContext) -> Option<usize> {
+        let buffer = self.buffer.read(cx).as_singleton()?;
+        if buffer.read(cx).file().is_some() {
+            return None;
+        }
+        Some(self.untitled_number.unwrap_or(1))
+    }
+
+    /// Returns the smallest number that none of the workspace's untitled editors use.
+    fn next_untitled_number(workspace: &Workspace, cx: &AppContext) -> usize {
+        let used_numbers = workspace
+            .items_of_type::<Editor>(cx)
+            .filter_map(|editor| editor.read(cx).untitled_number(cx))
+            .collect::<HashSet<_>>();
+        (1..)
+            .find(|number| !used_numbers.contains(number))
+            .unwrap_or(1)
     }
 
     pub fn snapshot(&mut self, cx: &mut WindowContext) -> EditorSnapshot {
@@ -2174,6 +2263,113 @@ impl Editor {
         self.read_only = read_only;
     }
 
+    /// Makes the editor read-only, or writable again. Buffers that are read-only because of
+    /// the project, their worktree or their file's permissions stay that way, and the user is
+    /// told why.
+    pub fn toggle_read_only(&mut self, _: &ToggleReadOnly, cx: &mut ViewContext<Self>) {
+        if self.mode != EditorMode::Full {
+            return;
+        }
+        if !self.read_only(cx) {
+            self.read_only = true;
+            cx.notify();
+            return;
+        }
+
+        self.read_only = false;
+        cx.notify();
+        if !self.buffer.read(cx).read_only() {
+            return;
+        }
+
+        let buffer = self.buffer.read(cx).as_singleton();
+        let file = buffer
+            .as_ref()
+            .and_then(|buffer| project::File::from_dyn(buffer.read(cx).file()).cloned());
+        let (Some(buffer), Some(file), Some(project)) = (buffer, file, self.project.clone()) else {
+            self.show_read_only_toast(cx);
+            return;
+        };
+        if project.read(cx).is_read_only()
+            || project
+                .read(cx)
+                .is_worktree_read_only(file.worktree_id(cx), cx)
+        {
+            self.show_read_only_toast(cx);
+            return;
+        }
+
+        let fs = project.read(cx).fs().clone();
+        let abs_path = file.abs_path(cx);
+        cx.spawn(|editor, mut cx| async move {
+            let is_write_protected = fs
+                .metadata(&abs_path)
+                .await?
+                .map_or(false, |metadata| metadata.is_read_only);
+            editor.update(&mut cx, |editor, cx| {
+                if is_write_protected {
+                    editor.show_read_only_toast(cx);
+                } else {
+                    buffer.update(cx, |buffer, cx| {
+                        buffer.set_capability(Capability::ReadWrite, cx)
+                    });
+                }
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    /// Returns whether the editor is read-only, letting the user know why the edit they
+    /// attempted was rejected if it is.
+    fn reject_read_only_edit(&self, cx: &mut ViewContext<Self>) -> bool {
+        let read_only = self.read_only(cx);
+        if read_only && self.mode == EditorMode::Full {
+            self.show_read_only_toast(cx);
+        }
+        read_only
+    }
+
+    fn read_only_reason(&self, cx: &AppContext) -> &'static str {
+        if self.read_only {
+            return "This editor is read-only";
+        }
+        if let Some(project) = self.project.as_ref() {
+            if project.read(cx).is_read_only() {
+                return "This project was shared with you read-only";
+            }
+            let file = self
+                .buffer
+                .read(cx)
+                .as_singleton()
+                .and_then(|buffer| project::File::from_dyn(buffer.read(cx).file()).cloned());
+            if let Some(file) = file {
+                if project
+                    .read(cx)
+                    .is_worktree_read_only(file.worktree_id(cx), cx)
+                {
+                    return "This file belongs to a read-only worktree";
+                }
+                return "This file can't be edited, you don't have permission to write to it";
+            }
+        }
+        "This buffer is read-only"
+    }
+
+    fn show_read_only_toast(&self, cx: &mut ViewContext<Self>) {
+        let Some(workspace) = self.workspace() else {
+            return;
+        };
+        let message = self.read_only_reason(cx);
+        workspace.update(cx, |workspace, cx| {
+            struct ReadOnlyEditor;
+
+            workspace.show_toast(
+                Toast::new(NotificationId::unique::<ReadOnlyEditor>(), message),
+                cx,
+            )
+        });
+    }
+
     pub fn set_use_autoclose(&mut self, autoclose: bool) {
         self.use_autoclose = autoclose;
     }
@@ -2194,6 +2390,36 @@ impl Editor {
         self.use_modal_editing
     }
 
+    /// Returns the ids the state of this editor is stored under in the workspace database, if
+    /// it's an editor of a local file in a workspace.
+    fn serialization_ids(&self, cx: &ViewContext<Self>) -> Option<(ItemId, WorkspaceId)> {
+        let (_, workspace_id) = self.workspace.as_ref()?;
+        let buffer = self.buffer.read(cx).as_singleton()?;
+        buffer.read(cx).file()?.as_local()?;
+        Some((cx.view().item_id().as_u64() as ItemId, (*workspace_id)?))
+    }
+
+    /// Stores the selections of an editor of a file in the workspace database, a short while
+    /// after they changed, so that they get restored along with the editor.
+    pub(crate) fn serialize_selections(&mut self, cx: &mut ViewContext<Self>) {
+        let Some((item_id, workspace_id)) = self.serialization_ids(cx) else {
+            return;
+        };
+        let selections = self
+            .selections
+            .all::<usize>(cx)
+            .into_iter()
+            .map(|selection| (selection.start, selection.end))
+            .collect::<Vec<_>>();
+        let executor = cx.background_executor().clone();
+        self.serialize_selections_task = Some(cx.background_executor().spawn(async move {
+            executor.timer(SERIALIZE_SELECTIONS_DEBOUNCE).await;
+            DB.save_editor_selections(item_id, workspace_id, selections)
+                .await
+                .log_err();
+        }));
+    }
+
     fn selections_did_change(
         &mut self,
         local: bool,
@@ -2242,6 +2468,7 @@ impl Editor {
         self.select_next_state = None;
         self.select_prev_state = None;
         self.select_larger_syntax_node_stack.clear();
+        self.expand_selection_stack.clear();
         self.invalidate_autoclose_regions(&self.selections.disjoint_anchors(), buffer);
         self.snippet_stack
             .invalidate(&self.selections.disjoint_anchors(), buffer);
@@ -2320,11 +2547,13 @@ impl Editor {
             self.refresh_code_actions(cx);
             self.refresh_document_highlights(cx);
             refresh_matching_bracket_highlights(self, cx);
+            refresh_word_highlights(self, cx);
             self.discard_inline_completion(false, cx);
             linked_editing_ranges::refresh_linked_ranges(self, cx);
             if self.git_blame_inline_enabled {
                 self.start_inline_blame_timer(cx);
             }
+            self.serialize_selections(cx);
         }
 
         self.blink_manager.update(cx, BlinkManager::pause_blinking);
@@ -2373,7 +2602,7 @@ impl Editor {
         S: ToOffset,
         T: Into<Arc<str>>,
     {
-        if self.read_only(cx) {
+        if self.reject_read_only_edit(cx) {
             return;
         }
 
@@ -2387,7 +2616,7 @@ impl Editor {
         S: ToOffset,
         T: Into<Arc<str>>,
     {
-        if self.read_only(cx) {
+        if self.reject_read_only_edit(cx) {
             return;
         }
 
@@ -2406,7 +2635,7 @@ impl Editor {
         S: ToOffset,
         T: Into<Arc<str>>,
     {
-        if self.read_only(cx) {
+        if self.reject_read_only_edit(cx) {
             return;
         }
 
@@ -2716,7 +2945,9 @@ impl Editor {
         let selection_ranges = (start_row.0..=end_row.0)
             .map(DisplayRow)
             .filter_map(|row| {
-                if start_column <= display_map.line_len(row) && !display_map.is_block_line(row) {
+                if display_map.is_block_line(row) {
+                    None
+                } else if start_column <= display_map.line_len(row) {
                     let start = display_map
                         .clip_point(DisplayPoint::new(row, start_column), Bias::Left)
                         .to_point(display_map);
@@ -2729,7 +2960,11 @@ impl Editor {
                         Some(start..end)
                     }
                 } else {
-                    None
+                    // Lines that end before the block starts get a cursor at their end, so
+                    // that typing appends to every line in the block.
+                    let end =
+                        DisplayPoint::new(row, display_map.line_len(row)).to_point(display_map);
+                    Some(end..end)
                 }
             })
             .collect::<Vec<_>>();
@@ -2848,7 +3083,7 @@ impl Editor {
     pub fn handle_input(&mut self, text: &str, cx: &mut ViewContext<Self>) {
         let text: Arc<str> = text.into();
 
-        if self.read_only(cx) {
+        if self.reject_read_only_edit(cx) {
             return;
         }
 
@@ -2914,8 +3149,19 @@ impl Editor {
                                         ),
                                         &bracket_pair.start[..prefix_len],
                                     ));
+                            // Quotes and other pairs whose start and end are the same aren't
+                            // closed right after a word character, e.g. in `don't`.
+                            let preceding_text_allows_autoclose = bracket_pair.start
+                                != bracket_pair.end
+                                || snapshot
+                                    .reversed_chars_at(selection.start)
+                                    .next()
+                                    .map_or(true, |c| {
+                                        char_kind(&Some(scope.clone()), c) != CharKind::Word
+                                    });
                             if autoclose
                                 && following_text_allows_autoclose
+                                && preceding_text_allows_autoclose
                                 && preceding_text_matches_prefix
                             {
                                 let anchor = snapshot.anchor_before(selection.end);
@@ -3054,7 +3300,18 @@ impl Editor {
             edits.push((selection.start..selection.end, text.clone()));
         }
 
+        // Typing the first char of a word after whitespace starts a new undo group, so that
+        // undoing text typed in one go removes it word by word.
+        let starts_word = text.chars().next().map_or(false, |c| !c.is_whitespace())
+            && snapshot
+                .reversed_chars_at(self.selections.newest_anchor().head())
+                .next()
+                .map_or(false, char::is_whitespace);
         drop(snapshot);
+        if starts_word && !self.selection_history.transaction_in_progress() {
+            self.buffer
+                .update(cx, |buffer, cx| buffer.finalize_last_transaction(cx));
+        }
 
         self.transact(cx, |this, cx| {
             this.buffer.update(cx, |buffer, cx| {
@@ -3453,7 +3710,7 @@ impl Editor {
         autoindent_mode: Option<AutoindentMode>,
         cx: &mut ViewContext<Self>,
     ) {
-        if self.read_only(cx) {
+        if self.reject_read_only_edit(cx) {
             return;
         }
 
@@ -4550,7 +4807,7 @@ impl Editor {
     }
 
     fn refresh_document_highlights(&mut self, cx: &mut ViewContext<Self>) -> Option<()> {
-        if self.pending_rename.is_some() {
+        if self.pending_rename.is_some() || self.has_long_lines(cx) {
             return None;
         }
 
@@ -5179,7 +5436,12 @@ impl Editor {
                                 buffer.indent_size_for_line(line_buffer_range.start.row);
                             let indent_len = match indent_size.kind {
                                 IndentKind::Space => {
-                                    buffer.settings_at(line_buffer_range.start, cx).tab_size
+                                    Self::indent_unit_at(
+                                        &display_map.buffer_snapshot,
+                                        Point::new(old_head.row, 0),
+                                        cx,
+                                    )
+                                    .1
                                 }
                                 IndentKind::Tab => NonZeroU32::new(1).unwrap(),
                             };
@@ -5259,7 +5521,7 @@ impl Editor {
     }
 
     pub fn tab(&mut self, _: &Tab, cx: &mut ViewContext<Self>) {
-        if self.move_to_next_snippet_tabstop(cx) || self.read_only(cx) {
+        if self.move_to_next_snippet_tabstop(cx) || self.reject_read_only_edit(cx) {
             return;
         }
 
@@ -5280,8 +5542,7 @@ impl Editor {
 
             // If the selection is non-empty, then increase the indentation of the selected lines.
             if !selection.is_empty() {
-                row_delta =
-                    Self::indent_selection(buffer, &snapshot, selection, &mut edits, row_delta, cx);
+                row_delta = Self::indent_selection(&snapshot, selection, &mut edits, row_delta, cx);
                 continue;
             }
 
@@ -5311,11 +5572,11 @@ impl Editor {
             }
 
             // Otherwise, insert a hard or soft tab.
-            let settings = buffer.settings_at(cursor, cx);
-            let tab_size = if settings.hard_tabs {
+            let (indent_kind, indent_width) = Self::indent_unit_at(&snapshot, cursor, cx);
+            let tab_size = if indent_kind == IndentKind::Tab {
                 IndentSize::tab()
             } else {
-                let tab_size = settings.tab_size.get();
+                let tab_size = indent_width.get();
                 let char_column = snapshot
                     .text_for_range(Point::new(cursor.row, 0)..cursor)
                     .flat_map(str::chars)
@@ -5338,7 +5599,7 @@ impl Editor {
     }
 
     pub fn indent(&mut self, _: &Indent, cx: &mut ViewContext<Self>) {
-        if self.read_only(cx) {
+        if self.reject_read_only_edit(cx) {
             return;
         }
         let mut selections = self.selections.all::<Point>(cx);
@@ -5353,8 +5614,7 @@ impl Editor {
             }
             prev_edited_row = selection.end.row;
 
-            row_delta =
-                Self::indent_selection(buffer, &snapshot, selection, &mut edits, row_delta, cx);
+            row_delta = Self::indent_selection(&snapshot, selection, &mut edits, row_delta, cx);
         }
 
         self.transact(cx, |this, cx| {
@@ -5364,20 +5624,14 @@ impl Editor {
     }
 
     fn indent_selection(
-        buffer: &MultiBuffer,
         snapshot: &MultiBufferSnapshot,
         selection: &mut Selection<Point>,
         edits: &mut Vec<(Range<Point>, String)>,
         delta_for_start_row: u32,
         cx: &AppContext,
     ) -> u32 {
-        let settings = buffer.settings_at(selection.start, cx);
-        let tab_size = settings.tab_size.get();
-        let indent_kind = if settings.hard_tabs {
-            IndentKind::Tab
-        } else {
-            IndentKind::Space
-        };
+        let (indent_kind, tab_size) = Self::indent_unit_at(snapshot, selection.start, cx);
+        let tab_size = tab_size.get();
         let mut start_row = selection.start.row;
         let mut end_row = selection.end.row + 1;
 
@@ -5439,8 +5693,34 @@ impl Editor {
         }
     }
 
+    /// Returns the kind of indentation to indent with at the given position, and how many
+    /// columns a level of it takes. The buffer's chosen or detected indentation wins over
+    /// the settings.
+    fn indent_unit_at(
+        snapshot: &MultiBufferSnapshot,
+        position: Point,
+        cx: &AppContext,
+    ) -> (IndentKind, NonZeroU32) {
+        let settings = snapshot.settings_at(position, cx);
+        match snapshot.language_indent_size_at(position, cx) {
+            Some(IndentSize {
+                kind: IndentKind::Space,
+                len,
+            }) => (
+                IndentKind::Space,
+                NonZeroU32::new(len).unwrap_or(settings.tab_size),
+            ),
+            Some(IndentSize {
+                kind: IndentKind::Tab,
+                ..
+            }) => (IndentKind::Tab, settings.tab_size),
+            None if settings.hard_tabs => (IndentKind::Tab, settings.tab_size),
+            None => (IndentKind::Space, settings.tab_size),
+        }
+    }
+
     pub fn outdent(&mut self, _: &Outdent, cx: &mut ViewContext<Self>) {
-        if self.read_only(cx) {
+        if self.reject_read_only_edit(cx) {
             return;
         }
         let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
@@ -5451,8 +5731,7 @@ impl Editor {
             let buffer = self.buffer.read(cx);
             let snapshot = buffer.snapshot(cx);
             for selection in &selections {
-                let settings = buffer.settings_at(selection.start, cx);
-                let tab_size = settings.tab_size.get();
+                let tab_size = Self::indent_unit_at(&snapshot, selection.start, cx).1.get();
                 let mut rows = selection.spanned_rows(false, &display_map);
 
                 // Avoid re-outdenting a row that has already been outdented by a
@@ -5510,6 +5789,99 @@ impl Editor {
         });
     }
 
+    pub fn convert_indentation_to_spaces(
+        &mut self,
+        _: &ConvertIndentationToSpaces,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.convert_indentation(IndentKind::Space, cx);
+    }
+
+    pub fn convert_indentation_to_tabs(
+        &mut self,
+        _: &ConvertIndentationToTabs,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.convert_indentation(IndentKind::Tab, cx);
+    }
+
+    /// Rewrites the leading whitespace of every line in the buffer using the given kind of
+    /// indentation, keeping its width. Converting to tabs keeps the spaces that don't add
+    /// up to a full tab stop.
+    fn convert_indentation(&mut self, kind: IndentKind, cx: &mut ViewContext<Self>) {
+        self.rewrite_indentation(cx, |indent_width, tab_size| match kind {
+            IndentKind::Space => " ".repeat(indent_width as usize),
+            IndentKind::Tab => {
+                let tabs = "\t".repeat((indent_width / tab_size) as usize);
+                tabs + &" ".repeat((indent_width % tab_size) as usize)
+            }
+        });
+    }
+
+    /// Rewrites the leading whitespace of every line in the buffer from one unit of
+    /// indentation to another, keeping how many levels each line is indented by. What doesn't
+    /// add up to a full level is kept as spaces.
+    pub fn reindent(&mut self, from: IndentSize, to: IndentSize, cx: &mut ViewContext<Self>) {
+        self.rewrite_indentation(cx, |indent_width, tab_size| {
+            let from_width = match from.kind {
+                IndentKind::Space => from.len.max(1),
+                IndentKind::Tab => tab_size,
+            };
+            let levels = indent_width / from_width;
+            let rest = " ".repeat((indent_width % from_width) as usize);
+            match to.kind {
+                IndentKind::Space => " ".repeat((levels * to.len) as usize) + &rest,
+                IndentKind::Tab => "\t".repeat(levels as usize) + &rest,
+            }
+        });
+    }
+
+    /// Replaces the leading whitespace of every line in the buffer with the one returned for
+    /// its width in columns and the line's tab size.
+    fn rewrite_indentation(
+        &mut self,
+        cx: &mut ViewContext<Self>,
+        new_indent: impl Fn(u32, u32) -> String,
+    ) {
+        if self.reject_read_only_edit(cx) {
+            return;
+        }
+
+        let mut edits = Vec::new();
+        {
+            let buffer = self.buffer.read(cx);
+            let snapshot = buffer.snapshot(cx);
+            for row in 0..=snapshot.max_point().row {
+                let tab_size = buffer.settings_at(Point::new(row, 0), cx).tab_size.get();
+                let mut indent_len = 0;
+                let mut indent_width = 0;
+                for c in snapshot.chars_at(Point::new(row, 0)) {
+                    match c {
+                        ' ' => indent_width += 1,
+                        '\t' => indent_width += tab_size - indent_width % tab_size,
+                        _ => break,
+                    }
+                    indent_len += 1;
+                }
+
+                let new_indent = new_indent(indent_width, tab_size);
+                let indent_range = Point::new(row, 0)..Point::new(row, indent_len);
+                let old_indent = snapshot
+                    .text_for_range(indent_range.clone())
+                    .collect::<String>();
+                if old_indent != new_indent {
+                    edits.push((indent_range, new_indent));
+                }
+            }
+        }
+
+        self.transact(cx, |this, cx| {
+            this.buffer.update(cx, |b, cx| b.edit(edits, None, cx));
+            let selections = this.selections.all::<usize>(cx);
+            this.change_selections(Some(Autoscroll::fit()), cx, |s| s.select(selections));
+        });
+    }
+
     pub fn delete_line(&mut self, _: &DeleteLine, cx: &mut ViewContext<Self>) {
         let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
         let selections = self.selections.all::<Point>(cx);
@@ -5593,7 +5965,19 @@ impl Editor {
     }
 
     pub fn join_lines(&mut self, _: &JoinLines, cx: &mut ViewContext<Self>) {
-        if self.read_only(cx) {
+        self.join_lines_impl(true, cx);
+    }
+
+    pub fn join_lines_without_space(
+        &mut self,
+        _: &JoinLinesWithoutSpace,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.join_lines_impl(false, cx);
+    }
+
+    fn join_lines_impl(&mut self, insert_space: bool, cx: &mut ViewContext<Self>) {
+        if self.reject_read_only_edit(cx) {
             return;
         }
         let mut row_ranges = Vec::<Range<MultiBufferRow>>::new();
@@ -5632,7 +6016,7 @@ impl Editor {
                     let indent = snapshot.indent_size_for_line(next_line_row);
                     let start_of_next_line = Point::new(next_line_row.0, indent.len);
 
-                    let replace = if snapshot.line_len(next_line_row) > indent.len {
+                    let replace = if snapshot.line_len(next_line_row) > indent.len && insert_space {
                         " "
                     } else {
                         ""
@@ -5650,6 +6034,30 @@ impl Editor {
         });
     }
 
+    pub fn sort_lines(&mut self, action: &SortLines, cx: &mut ViewContext<Self>) {
+        let &SortLines {
+            descending,
+            case_insensitive,
+            natural,
+        } = action;
+        self.manipulate_lines(cx, |lines| {
+            lines.sort_by(|a, b| {
+                let ordering = if natural {
+                    compare_natural(a, b, case_insensitive)
+                } else if case_insensitive {
+                    a.to_lowercase().cmp(&b.to_lowercase())
+                } else {
+                    a.cmp(b)
+                };
+                if descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            })
+        })
+    }
+
     pub fn sort_lines_case_sensitive(
         &mut self,
         _: &SortLinesCaseSensitive,
@@ -5712,6 +6120,16 @@ impl Editor {
         }
     }
 
+    /// Discards the unsaved changes of the editor's buffers by reloading them from disk.
+    /// The reload is applied as a diff, so selections, folds and the scroll position
+    /// are kept wherever the text didn't change.
+    pub fn revert_file(&mut self, _: &RevertFile, cx: &mut ViewContext<Self>) {
+        let Some(project) = self.project.clone() else {
+            return;
+        };
+        workspace::item::Item::reload(self, project, cx).detach_and_log_err(cx);
+    }
+
     pub fn open_active_item_in_terminal(&mut self, _: &OpenInTerminal, cx: &mut ViewContext<Self>) {
         if let Some(working_directory) = self.active_excerpt(cx).and_then(|(_, buffer, _)| {
             let project_path = buffer.read(cx).project_path(cx)?;
@@ -5788,7 +6206,21 @@ impl Editor {
 
         let mut edits = Vec::new();
 
-        let selections = self.selections.all::<Point>(cx);
+        let mut selections = self.selections.all::<Point>(cx);
+        // With nothing selected, all lines of the buffer are manipulated, except for the empty
+        // line that follows a trailing newline.
+        if let [selection] = selections.as_mut_slice() {
+            if selection.is_empty() && buffer.as_singleton().is_some() {
+                let max_point = buffer.max_point();
+                selection.start = Point::zero();
+                selection.end = if max_point.column == 0 && max_point.row > 0 {
+                    let last_row = MultiBufferRow(max_point.row - 1);
+                    Point::new(last_row.0, buffer.line_len(last_row))
+                } else {
+                    max_point
+                };
+            }
+        }
         let mut selections = selections.iter().peekable();
         let mut contiguous_row_selections = Vec::new();
         let mut new_selections = Vec::new();
@@ -5878,21 +6310,15 @@ impl Editor {
     }
 
     pub fn convert_to_title_case(&mut self, _: &ConvertToTitleCase, cx: &mut ViewContext<Self>) {
-        self.manipulate_text(cx, |text| {
-            // Hack to get around the fact that to_case crate doesn't support '\n' as a word boundary
-            // https://github.com/rutrum/convert-case/issues/16
-            text.split('\n')
-                .map(|line| line.to_case(Case::Title))
-                .join("\n")
-        })
+        self.manipulate_text(cx, |text| convert_lines_to_case(text, Case::Title))
     }
 
     pub fn convert_to_snake_case(&mut self, _: &ConvertToSnakeCase, cx: &mut ViewContext<Self>) {
-        self.manipulate_text(cx, |text| text.to_case(Case::Snake))
+        self.manipulate_text(cx, |text| convert_lines_to_case(text, Case::Snake))
     }
 
     pub fn convert_to_kebab_case(&mut self, _: &ConvertToKebabCase, cx: &mut ViewContext<Self>) {
-        self.manipulate_text(cx, |text| text.to_case(Case::Kebab))
+        self.manipulate_text(cx, |text| convert_lines_to_case(text, Case::Kebab))
     }
 
     pub fn convert_to_upper_camel_case(
@@ -5900,13 +6326,7 @@ impl Editor {
         _: &ConvertToUpperCamelCase,
         cx: &mut ViewContext<Self>,
     ) {
-        self.manipulate_text(cx, |text| {
-            // Hack to get around the fact that to_case crate doesn't support '\n' as a word boundary
-            // https://github.com/rutrum/convert-case/issues/16
-            text.split('\n')
-                .map(|line| line.to_case(Case::UpperCamel))
-                .join("\n")
-        })
+        self.manipulate_text(cx, |text| convert_lines_to_case(text, Case::UpperCamel))
     }
 
     pub fn convert_to_lower_camel_case(
@@ -5914,7 +6334,7 @@ impl Editor {
         _: &ConvertToLowerCamelCase,
         cx: &mut ViewContext<Self>,
     ) {
-        self.manipulate_text(cx, |text| text.to_case(Case::Camel))
+        self.manipulate_text(cx, |text| convert_lines_to_case(text, Case::Camel))
     }
 
     pub fn convert_to_opposite_case(
@@ -6047,6 +6467,48 @@ impl Editor {
         self.duplicate_line(false, cx);
     }
 
+    /// Duplicates the text of each non-empty selection right after it, selecting the copy.
+    /// When no text is selected, the lines containing the cursors are duplicated instead.
+    pub fn duplicate_selection(&mut self, _: &DuplicateSelection, cx: &mut ViewContext<Self>) {
+        let selections = self.selections.all::<usize>(cx);
+        if selections.iter().all(|selection| selection.is_empty()) {
+            self.duplicate_line(false, cx);
+            return;
+        }
+
+        let buffer = self.buffer.read(cx).snapshot(cx);
+        let mut edits = Vec::new();
+        let mut new_selections = Vec::new();
+        let mut inserted_len = 0;
+        for selection in selections {
+            if selection.is_empty() {
+                new_selections.push(selection.map(|offset| offset + inserted_len));
+                continue;
+            }
+
+            let text = buffer
+                .text_for_range(selection.start..selection.end)
+                .collect::<String>();
+            let copy_start = selection.end + inserted_len;
+            inserted_len += text.len();
+            new_selections.push(Selection {
+                id: selection.id,
+                start: copy_start,
+                end: copy_start + text.len(),
+                reversed: selection.reversed,
+                goal: SelectionGoal::None,
+            });
+            edits.push((selection.end..selection.end, text));
+        }
+
+        self.transact(cx, |this, cx| {
+            this.buffer.update(cx, |buffer, cx| {
+                buffer.edit(edits, None, cx);
+            });
+            this.change_selections(Some(Autoscroll::fit()), cx, |s| s.select(new_selections));
+        });
+    }
+
     pub fn move_line_up(&mut self, _: &MoveLineUp, cx: &mut ViewContext<Self>) {
         let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
         let buffer = self.buffer.read(cx).snapshot(cx);
@@ -6350,6 +6812,9 @@ impl Editor {
         let buffer = self.buffer.read(cx).read(cx);
         let mut text = String::new();
 
+        // Empty selections copy their entire line, unless they are mixed with non-empty
+        // ones, like the cursors a block selection places on lines that are too short.
+        let copy_entire_lines = selections.iter().all(|selection| selection.is_empty());
         let mut clipboard_selections = Vec::with_capacity(selections.len());
         {
             let max_point = buffer.max_point();
@@ -6357,7 +6822,7 @@ impl Editor {
             for selection in selections.iter() {
                 let mut start = selection.start;
                 let mut end = selection.end;
-                let is_entire_line = selection.is_empty() || self.selections.line_mode;
+                let is_entire_line = self.selections.line_mode || copy_entire_lines;
                 if is_entire_line {
                     start = Point::new(start.row, 0);
                     end = cmp::min(max_point, Point::new(end.row + 1, 0));
@@ -6384,7 +6849,19 @@ impl Editor {
     }
 
     pub fn paste(&mut self, _: &Paste, cx: &mut ViewContext<Self>) {
-        if self.read_only(cx) {
+        self.do_paste(false, cx);
+    }
+
+    pub fn paste_and_match_indentation(
+        &mut self,
+        _: &PasteAndMatchIndentation,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.do_paste(true, cx);
+    }
+
+    fn do_paste(&mut self, match_indentation: bool, cx: &mut ViewContext<Self>) {
+        if self.reject_read_only_edit(cx) {
             return;
         }
 
@@ -6402,6 +6879,13 @@ impl Editor {
                     }
 
                     this.buffer.update(cx, |buffer, cx| {
+                        let auto_indent_on_paste = old_selections.first().map_or(true, |s| {
+                            buffer.settings_at(s.start, cx).auto_indent_on_paste
+                        });
+                        let match_indentation = match_indentation
+                            || old_selections.first().map_or(false, |s| {
+                                buffer.settings_at(s.start, cx).match_indentation_on_paste
+                            });
                         let snapshot = buffer.read(cx);
                         let mut start_offset = 0;
                         let mut edits = Vec::new();
@@ -6436,31 +6920,114 @@ impl Editor {
                                 selection.range()
                             };
 
+                            let to_insert = if match_indentation {
+                                match_pasted_indentation(
+                                    to_insert,
+                                    range.start,
+                                    original_indent_column,
+                                    &snapshot,
+                                    cx,
+                                )
+                            } else {
+                                Cow::Borrowed(to_insert)
+                            };
                             edits.push((range, to_insert));
                             original_indent_columns.extend(original_indent_column);
                         }
                         drop(snapshot);
 
-                        buffer.edit(
-                            edits,
-                            Some(AutoindentMode::Block {
+                        // Text whose indentation was matched is already indented as it should be.
+                        let autoindent = (auto_indent_on_paste && !match_indentation).then_some(
+                            AutoindentMode::Block {
                                 original_indent_columns,
-                            }),
-                            cx,
+                            },
                         );
+                        buffer.edit(edits, autoindent, cx);
                     });
 
                     let selections = this.selections.all::<usize>(cx);
                     this.change_selections(Some(Autoscroll::fit()), cx, |s| s.select(selections));
                 } else {
-                    this.insert(&clipboard_text, cx);
+                    // Text copied elsewhere gets split among the cursors when it has as many
+                    // lines as there are cursors.
+                    let old_selections = this.selections.all::<usize>(cx);
+                    let lines = clipboard_text.lines().collect::<Vec<_>>();
+                    if old_selections.len() > 1 && lines.len() == old_selections.len() {
+                        this.buffer.update(cx, |buffer, cx| {
+                            buffer.edit(
+                                old_selections
+                                    .iter()
+                                    .zip(lines)
+                                    .map(|(selection, line)| (selection.range(), line)),
+                                Some(AutoindentMode::Block {
+                                    original_indent_columns: Vec::new(),
+                                }),
+                                cx,
+                            );
+                        });
+
+                        let selections = this.selections.all::<usize>(cx);
+                        this.change_selections(Some(Autoscroll::fit()), cx, |s| {
+                            s.select(selections)
+                        });
+                    } else if match_indentation
+                        || old_selections.first().map_or(false, |s| {
+                            this.buffer
+                                .read(cx)
+                                .settings_at(s.start, cx)
+                                .match_indentation_on_paste
+                        })
+                    {
+                        this.buffer.update(cx, |buffer, cx| {
+                            let snapshot = buffer.read(cx);
+                            let edits = old_selections
+                                .iter()
+                                .map(|selection| {
+                                    let text = match_pasted_indentation(
+                                        &clipboard_text,
+                                        selection.start,
+                                        None,
+                                        &snapshot,
+                                        cx,
+                                    );
+                                    (selection.range(), text)
+                                })
+                                .collect::<Vec<_>>();
+                            drop(snapshot);
+                            buffer.edit(edits, None, cx);
+                        });
+
+                        let selections = this.selections.all::<usize>(cx);
+                        this.change_selections(Some(Autoscroll::fit()), cx, |s| {
+                            s.select(selections)
+                        });
+                    } else {
+                        this.insert(&clipboard_text, cx);
+                    }
                 }
+
+                this.detect_indent_after_paste(item.text(), cx);
             }
         });
     }
 
+    /// Detects the indentation of a buffer that had nothing to detect it from, once enough
+    /// indented text got pasted into it.
+    fn detect_indent_after_paste(&mut self, pasted_text: &str, cx: &mut ViewContext<Self>) {
+        const MIN_PASTED_LINES: usize = 16;
+
+        let Some(buffer) = self.buffer.read(cx).as_singleton() else {
+            return;
+        };
+        if buffer.read(cx).detected_indent().is_none()
+            && pasted_text.lines().count() >= MIN_PASTED_LINES
+        {
+            buffer.update(cx, |buffer, cx| buffer.detect_indent(cx));
+        }
+    }
+
     pub fn undo(&mut self, _: &Undo, cx: &mut ViewContext<Self>) {
-        if self.read_only(cx) {
+        if self.reject_read_only_edit(cx) {
             return;
         }
 
@@ -6481,7 +7048,7 @@ impl Editor {
     }
 
     pub fn redo(&mut self, _: &Redo, cx: &mut ViewContext<Self>) {
-        if self.read_only(cx) {
+        if self.reject_read_only_edit(cx) {
             return;
         }
 
@@ -7234,6 +7801,23 @@ impl Editor {
         self.nav_history.as_ref()
     }
 
+    /// Moves the cursor to the given offset, unfolding it and centering it on the screen. Jumps
+    /// get recorded in the navigation history, even short ones.
+    pub(crate) fn jump_to_offset(&mut self, offset: usize, cx: &mut ViewContext<Self>) {
+        let buffer = self.buffer.read(cx).snapshot(cx);
+        let offset = buffer.clip_offset(offset, Bias::Left);
+        let old_head = self.selections.newest_anchor().head();
+        let row_delta = offset.to_point(&buffer).row as i64 - old_head.to_point(&buffer).row as i64;
+        // Jumps over enough rows already get recorded when the selections change.
+        if row_delta.abs() < MIN_NAVIGATION_HISTORY_ROW_DELTA {
+            self.push_to_nav_history(old_head, None, cx);
+        }
+        self.unfold_ranges([offset..offset], false, true, cx);
+        self.change_selections(Some(Autoscroll::center()), cx, |s| {
+            s.select_ranges([offset..offset])
+        });
+    }
+
     fn push_to_nav_history(
         &mut self,
         cursor_anchor: Anchor,
@@ -7604,41 +8188,80 @@ impl Editor {
         let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
 
         self.select_next_match_internal(&display_map, false, None, cx)?;
-        let Some(select_next_state) = self.select_next_state.as_mut() else {
+        let Some(select_next_state) = self.select_next_state.as_ref() else {
             return Ok(());
         };
         if select_next_state.done {
             return Ok(());
         }
+        let wordwise = select_next_state.wordwise;
 
-        let mut new_selections = self.selections.all::<usize>(cx);
+        // Every selection holds the text that's being looked for by now.
+        let buffer = display_map.buffer_snapshot.clone();
+        let newest_selection = self.selections.newest::<usize>(cx);
+        let query = SearchQuery::text(
+            buffer
+                .text_for_range(newest_selection.range())
+                .collect::<String>(),
+            wordwise,
+            true,
+            false,
+            Vec::new(),
+            Vec::new(),
+        )?;
+        if buffer.len() <= MAX_SYNC_SELECT_ALL_MATCHES_LEN {
+            let query_matches = smol::block_on(items::search_buffer(buffer, Vec::new(), &query));
+            self.select_query_matches(query_matches, cx);
+            return Ok(());
+        }
 
-        let buffer = &display_map.buffer_snapshot;
-        let query_matches = select_next_state
-            .query
-            .stream_find_iter(buffer.bytes_in_range(0..buffer.len()));
-
-        for query_match in query_matches {
-            let query_match = query_match.unwrap(); // can only fail due to I/O
-            let offset_range = query_match.start()..query_match.end();
-            let display_range = offset_range.start.to_display_point(&display_map)
-                ..offset_range.end.to_display_point(&display_map);
-
-            if !select_next_state.wordwise
-                || (!movement::is_inside_word(&display_map, display_range.start)
-                    && !movement::is_inside_word(&display_map, display_range.end))
-            {
-                self.selections.change_with(cx, |selections| {
-                    new_selections.push(Selection {
-                        id: selections.new_selection_id(),
-                        start: offset_range.start,
-                        end: offset_range.end,
-                        reversed: false,
-                        goal: SelectionGoal::None,
-                    });
+        // The matches are dropped when the buffer or the selections changed while searching, as
+        // they would select text that the user didn't ask for anymore.
+        let edit_count = buffer.edit_count();
+        let selections = self.selections.disjoint_anchors();
+        let search = cx
+            .background_executor()
+            .spawn(async move { items::search_buffer(buffer, Vec::new(), &query).await });
+        cx.spawn(|this, mut cx| async move {
+            let query_matches = search.await;
+            this.update(&mut cx, |this, cx| {
+                let unchanged = this.buffer.read(cx).snapshot(cx).edit_count() == edit_count
+                    && Arc::ptr_eq(&this.selections.disjoint_anchors(), &selections);
+                if unchanged {
+                    this.select_query_matches(query_matches, cx)
+                }
+            })
+        })
+        .detach_and_log_err(cx);
+        Ok(())
+    }
+
+    fn select_query_matches(
+        &mut self,
+        mut query_matches: Vec<Range<Anchor>>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let Some(select_next_state) = self.select_next_state.as_mut() else {
+            return;
+        };
+        select_next_state.done = true;
+
+        let match_count = query_matches.len();
+        query_matches.truncate(MAX_SELECT_ALL_MATCHES);
+        let buffer = self.buffer.read(cx).snapshot(cx);
+        let mut new_selections = self.selections.all::<usize>(cx);
+        self.selections.change_with(cx, |selections| {
+            for query_match in &query_matches {
+                let offset_range = query_match.to_offset(&buffer);
+                new_selections.push(Selection {
+                    id: selections.new_selection_id(),
+                    start: offset_range.start,
+                    end: offset_range.end,
+                    reversed: false,
+                    goal: SelectionGoal::None,
                 });
             }
-        }
+        });
 
         new_selections.sort_by_key(|selection| selection.start);
         let mut ix = 0;
@@ -7656,7 +8279,6 @@ impl Editor {
             }
         }
 
-        select_next_state.done = true;
         self.unfold_ranges(
             new_selections.iter().map(|selection| selection.range()),
             false,
@@ -7667,7 +8289,21 @@ impl Editor {
             selections.select(new_selections)
         });
 
-        Ok(())
+        if match_count > MAX_SELECT_ALL_MATCHES {
+            if let Some(workspace) = self.workspace() {
+                let message = format!(
+                    "Selected the first {MAX_SELECT_ALL_MATCHES} of {match_count} occurrences"
+                );
+                workspace.update(cx, |workspace, cx| {
+                    struct SelectAllMatchesLimit;
+
+                    workspace.show_toast(
+                        Toast::new(NotificationId::unique::<SelectAllMatchesLimit>(), message),
+                        cx,
+                    )
+                });
+            }
+        }
     }
 
     pub fn select_next(&mut self, action: &SelectNext, cx: &mut ViewContext<Self>) -> Result<()> {
@@ -7825,6 +8461,21 @@ impl Editor {
     }
 
     pub fn toggle_comments(&mut self, action: &ToggleComments, cx: &mut ViewContext<Self>) {
+        self.toggle_comments_internal(action.advance_downwards, false, cx);
+    }
+
+    /// Toggles block comments around the selected lines, even for languages that also
+    /// have line comments.
+    pub fn toggle_block_comments(&mut self, _: &ToggleBlockComments, cx: &mut ViewContext<Self>) {
+        self.toggle_comments_internal(false, true, cx);
+    }
+
+    fn toggle_comments_internal(
+        &mut self,
+        advance_downwards: bool,
+        prefer_block_comments: bool,
+        cx: &mut ViewContext<Self>,
+    ) {
         let text_layout_details = &self.text_layout_details(cx);
         self.transact(cx, |this, cx| {
             let mut selections = this.selections.all::<MultiBufferPoint>(cx);
@@ -7936,9 +8587,12 @@ impl Editor {
                     continue;
                 }
 
-                // If the language has line comments, toggle those.
+                // If the language has line comments, toggle those, unless block comments
+                // were asked for and the language has them.
                 let full_comment_prefixes = language.line_comment_prefixes();
-                if !full_comment_prefixes.is_empty() {
+                let use_block_comments =
+                    prefer_block_comments && language.block_comment_delimiters().is_some();
+                if !full_comment_prefixes.is_empty() && !use_block_comments {
                     let first_prefix = full_comment_prefixes
                         .first()
                         .expect("prefixes is non-empty");
@@ -8071,7 +8725,7 @@ impl Editor {
             let selections_selecting = selections
                 .iter()
                 .any(|selection| selection.start != selection.end);
-            let advance_downwards = action.advance_downwards
+            let advance_downwards = advance_downwards
                 && selections_on_single_row
                 && !selections_selecting
                 && this.mode != EditorMode::SingleLine;
@@ -8158,6 +8812,53 @@ impl Editor {
         self.select_larger_syntax_node_stack = stack;
     }
 
+    /// Grows each selection to the smallest syntax node that encloses it, or in buffers without
+    /// a syntax tree, to the next of the ranges `expanded_selection_range` finds. Shrinking undoes
+    /// the expansions one at a time, even if edits moved the selections in between.
+    pub fn expand_selection(&mut self, _: &ExpandSelection, cx: &mut ViewContext<Self>) {
+        let buffer = self.buffer.read(cx).snapshot(cx);
+        let old_selections = self.selections.all::<usize>(cx);
+
+        let mut stack = mem::take(&mut self.expand_selection_stack);
+        let mut expanded = false;
+        let new_selections = old_selections
+            .iter()
+            .map(|selection| {
+                let old_range = selection.start..selection.end;
+                let new_range = buffer
+                    .enclosing_node_range(old_range.clone(), 1)
+                    .or_else(|| expanded_selection_range(&buffer, old_range.clone()))
+                    .unwrap_or_else(|| old_range.clone());
+                expanded |= new_range != old_range;
+                Selection {
+                    id: selection.id,
+                    start: new_range.start,
+                    end: new_range.end,
+                    goal: SelectionGoal::None,
+                    reversed: selection.reversed,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        if expanded {
+            stack.push(self.selections.disjoint_anchors());
+            self.change_selections(Some(Autoscroll::fit()), cx, |s| {
+                s.select(new_selections);
+            });
+        }
+        self.expand_selection_stack = stack;
+    }
+
+    pub fn shrink_selection(&mut self, _: &ShrinkSelection, cx: &mut ViewContext<Self>) {
+        let mut stack = mem::take(&mut self.expand_selection_stack);
+        if let Some(selections) = stack.pop() {
+            self.change_selections(Some(Autoscroll::fit()), cx, |s| {
+                s.select_anchors(selections.to_vec());
+            });
+        }
+        self.expand_selection_stack = stack;
+    }
+
     fn refresh_runnables(&mut self, cx: &mut ViewContext<Self>) -> Task<()> {
         let project = self.project.clone();
         cx.spawn(|this, mut cx| async move {
@@ -8353,6 +9054,50 @@ impl Editor {
         });
     }
 
+    /// Moves each cursor to just after the opening bracket of the innermost bracket pair it is
+    /// inside of. Cursors that are already there move to the start of the next enclosing pair.
+    pub fn move_to_start_of_bracket_scope(
+        &mut self,
+        _: &MoveToStartOfBracketScope,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.change_selections(Some(Autoscroll::fit()), cx, |s| {
+            s.move_offsets_with(|snapshot, selection| {
+                let head = selection.head();
+                let filter = |open: Range<usize>, close: Range<usize>| {
+                    open.end < head && head <= close.start
+                };
+                if let Some((open, _)) =
+                    snapshot.innermost_enclosing_bracket_ranges(head..head, Some(&filter))
+                {
+                    selection.collapse_to(open.end, SelectionGoal::None);
+                }
+            })
+        });
+    }
+
+    /// Moves each cursor to just before the closing bracket of the innermost bracket pair it is
+    /// inside of. Cursors that are already there move to the end of the next enclosing pair.
+    pub fn move_to_end_of_bracket_scope(
+        &mut self,
+        _: &MoveToEndOfBracketScope,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.change_selections(Some(Autoscroll::fit()), cx, |s| {
+            s.move_offsets_with(|snapshot, selection| {
+                let head = selection.head();
+                let filter = |open: Range<usize>, close: Range<usize>| {
+                    open.end <= head && head < close.start
+                };
+                if let Some((_, close)) =
+                    snapshot.innermost_enclosing_bracket_ranges(head..head, Some(&filter))
+                {
+                    selection.collapse_to(close.start, SelectionGoal::None);
+                }
+            })
+        });
+    }
+
     pub fn undo_selection(&mut self, _: &UndoSelection, cx: &mut ViewContext<Self>) {
         self.end_selection(cx);
         self.selection_history.mode = SelectionHistoryMode::Undoing;
@@ -9590,6 +10335,15 @@ impl Editor {
 
     fn start_transaction_at(&mut self, now: Instant, cx: &mut ViewContext<Self>) {
         self.end_selection(cx);
+        // Moving the cursors in between edits starts a new undo group, regardless of how
+        // quickly the edits follow each other.
+        if self
+            .selection_history
+            .selections_moved_since_last_transaction(&self.selections.disjoint_anchors())
+        {
+            self.buffer
+                .update(cx, |buffer, cx| buffer.finalize_last_transaction(cx));
+        }
         if let Some(tx_id) = self
             .buffer
             .update(cx, |buffer, cx| buffer.start_transaction_at(now, cx))
@@ -9668,6 +10422,50 @@ impl Editor {
         }
     }
 
+    pub fn fold_all(&mut self, _: &FoldAll, cx: &mut ViewContext<Self>) {
+        self.fold_at_level(&FoldAtLevel { level: 1 }, cx);
+    }
+
+    /// Folds every foldable range nested exactly `level` deep, leaving the ranges
+    /// around them open.
+    pub fn fold_at_level(&mut self, fold_at_level: &FoldAtLevel, cx: &mut ViewContext<Self>) {
+        let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
+        let max_row = display_map.buffer_snapshot.max_buffer_row().0;
+        let level = fold_at_level.level.max(1) as usize;
+
+        let mut fold_ranges = Vec::new();
+        let mut enclosing_ranges: Vec<Range<Point>> = Vec::new();
+        let mut row = 0;
+        while row <= max_row {
+            while enclosing_ranges
+                .last()
+                .map_or(false, |range| range.end.row < row)
+            {
+                enclosing_ranges.pop();
+            }
+
+            match display_map.foldable_range(MultiBufferRow(row)) {
+                Some((range, placeholder)) if enclosing_ranges.len() + 1 == level => {
+                    // Anything nested inside this range is hidden by folding it.
+                    row = range.end.row.max(row) + 1;
+                    fold_ranges.push((range, placeholder));
+                }
+                Some((range, _)) => {
+                    enclosing_ranges.push(range);
+                    row += 1;
+                }
+                None => row += 1,
+            }
+        }
+
+        self.fold_ranges(fold_ranges, true, cx);
+    }
+
+    pub fn unfold_all(&mut self, _: &UnfoldAll, cx: &mut ViewContext<Self>) {
+        let end = self.buffer.read(cx).read(cx).len();
+        self.unfold_ranges([0..end], true, true, cx);
+    }
+
     pub fn unfold_lines(&mut self, _: &UnfoldLines, cx: &mut ViewContext<Self>) {
         let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
         let buffer = &display_map.buffer_snapshot;
@@ -9977,6 +10775,12 @@ impl Editor {
         self.style.as_ref()
     }
 
+    /// Returns whether the buffer has lines so long that some features are turned off, see
+    /// [`DisplaySnapshot::has_long_lines`].
+    pub fn has_long_lines(&self, cx: &AppContext) -> bool {
+        self.display_map.read(cx).has_long_lines(cx)
+    }
+
     // Called by the element. This method is not designed to be called outside of the editor
     // element's layout code because it does not notify when rewrapping is computed synchronously.
     pub(crate) fn set_wrap_width(&self, width: Option<Pixels>, cx: &mut AppContext) -> bool {
@@ -10066,30 +10870,69 @@ impl Editor {
         cx.notify();
     }
 
+    /// The file of a singleton editor's buffer, if it's saved on this machine. The actions
+    /// about the file are only available when there's one.
+    pub(crate) fn target_file<'a>(
+        &self,
+        cx: &'a AppContext,
+    ) -> Option<&'a dyn language::LocalFile> {
+        self.buffer()
+            .read(cx)
+            .as_singleton()?
+            .read(cx)
+            .file()?
+            .as_local()
+    }
+
+    /// The path of the file relative to its worktree, prefixed with the worktree's root name
+    /// when the project has several, so that the path stays unambiguous.
+    fn target_file_relative_path(&self, cx: &AppContext) -> Option<PathBuf> {
+        let file = self.target_file(cx)?;
+        let has_several_worktrees = self.project.as_ref().map_or(false, |project| {
+            project.read(cx).visible_worktrees(cx).count() > 1
+        });
+        if has_several_worktrees {
+            Some(file.full_path(cx))
+        } else {
+            Some(file.path().to_path_buf())
+        }
+    }
+
     pub fn reveal_in_finder(&mut self, _: &RevealInFinder, cx: &mut ViewContext<Self>) {
-        if let Some(buffer) = self.buffer().read(cx).as_singleton() {
-            if let Some(file) = buffer.read(cx).file().and_then(|f| f.as_local()) {
-                cx.reveal_path(&file.abs_path(cx));
-            }
+        if let Some(file) = self.target_file(cx) {
+            cx.reveal_path(&file.abs_path(cx));
+        }
+    }
+
+    pub fn open_in_default_app(&mut self, _: &OpenInDefaultApp, cx: &mut ViewContext<Self>) {
+        if let Some(file) = self.target_file(cx) {
+            cx.open_with_system(&file.abs_path(cx));
         }
     }
 
     pub fn copy_path(&mut self, _: &CopyPath, cx: &mut ViewContext<Self>) {
-        if let Some(buffer) = self.buffer().read(cx).as_singleton() {
-            if let Some(file) = buffer.read(cx).file().and_then(|f| f.as_local()) {
-                if let Some(path) = file.abs_path(cx).to_str() {
-                    cx.write_to_clipboard(ClipboardItem::new(path.to_string()));
-                }
+        if let Some(file) = self.target_file(cx) {
+            if let Some(path) = file.abs_path(cx).to_str() {
+                cx.write_to_clipboard(ClipboardItem::new(path.to_string()));
             }
         }
     }
 
     pub fn copy_relative_path(&mut self, _: &CopyRelativePath, cx: &mut ViewContext<Self>) {
-        if let Some(buffer) = self.buffer().read(cx).as_singleton() {
-            if let Some(file) = buffer.read(cx).file().and_then(|f| f.as_local()) {
-                if let Some(path) = file.path().to_str() {
-                    cx.write_to_clipboard(ClipboardItem::new(path.to_string()));
-                }
+        if let Some(path) = self.target_file_relative_path(cx) {
+            if let Some(path) = path.to_str() {
+                cx.write_to_clipboard(ClipboardItem::new(path.to_string()));
+            }
+        }
+    }
+
+    /// Copies the relative path of the file and the line of the newest cursor, as in
+    /// `src/main.rs:12`.
+    pub fn copy_file_location(&mut self, _: &CopyFileLocation, cx: &mut ViewContext<Self>) {
+        if let Some(path) = self.target_file_relative_path(cx) {
+            if let Some(path) = path.to_str() {
+                let row = self.selections.newest::<Point>(cx).head().row;
+                cx.write_to_clipboard(ClipboardItem::new(format!("{path}:{}", row + 1)));
             }
         }
     }
@@ -10564,9 +11407,10 @@ impl Editor {
         display_snapshot: &DisplaySnapshot,
         theme: &ThemeColors,
     ) -> Vec<(Range<DisplayPoint>, Hsla)> {
-        let mut results = Vec::new();
+        let mut results: Vec<(Range<DisplayPoint>, Hsla)> = Vec::new();
         for (color_fetcher, ranges) in self.background_highlights.values() {
             let color = color_fetcher(theme);
+            let first_result_ix = results.len();
             let start_ix = match ranges.binary_search_by(|probe| {
                 let cmp = probe
                     .end
@@ -10590,6 +11434,14 @@ impl Editor {
 
                 let start = range.start.to_display_point(&display_snapshot);
                 let end = range.end.to_display_point(&display_snapshot);
+                // Overlapping ranges of the same highlight are merged, so that translucent
+                // colors don't get painted twice where they overlap.
+                if let Some((previous, _)) = results[first_result_ix..].last_mut() {
+                    if start < previous.end {
+                        previous.end = cmp::max(previous.end, end);
+                        continue;
+                    }
+                }
                 results.push((start..end, color))
             }
         }
@@ -10796,6 +11648,7 @@ impl Editor {
                 self.active_indent_guides_state.dirty = true;
                 self.refresh_active_diagnostics(cx);
                 self.refresh_code_actions(cx);
+                self.refresh_spelling(true, cx);
                 if self.has_active_inline_completion(cx) {
                     self.update_visible_inline_completion(cx);
                 }
@@ -10860,11 +11713,13 @@ impl Editor {
             }
             multi_buffer::Event::Reparsed => {
                 self.tasks_update_task = Some(self.refresh_runnables(cx));
+                self.refresh_spelling(false, cx);
 
                 cx.emit(EditorEvent::Reparsed);
             }
             multi_buffer::Event::LanguageChanged => {
                 linked_editing_ranges::refresh_linked_ranges(self, cx);
+                self.recheck_spelling(cx);
                 cx.emit(EditorEvent::Reparsed);
                 cx.notify();
             }
@@ -10909,6 +11764,13 @@ impl Editor {
         let editor_settings = EditorSettings::get_global(cx);
         self.scroll_manager.vertical_scroll_margin = editor_settings.vertical_scroll_margin;
         self.show_breadcrumbs = editor_settings.toolbar.breadcrumbs;
+        let long_line_threshold = editor_settings.long_line_threshold;
+        self.display_map.update(cx, |map, cx| {
+            map.set_long_line_threshold(long_line_threshold, cx)
+        });
+
+        self.spelling_settings_changed(cx);
+        refresh_word_highlights(self, cx);
 
         if self.mode == EditorMode::Full {
             let inline_blame_enabled = ProjectSettings::get_global(cx).git.inline_blame_enabled();
@@ -11541,6 +12403,162 @@ fn inlay_hint_settings(
         .inlay_hints
 }
 
+/// Returns the range `ExpandSelection` grows the given range to where there's no syntax tree: the
+/// smallest of the surrounding word, the lines it spans, the contents of an enclosing bracket
+/// pair, that pair including its brackets, and the whole buffer, that contains the range without
+/// being equal to it.
+fn expanded_selection_range(
+    buffer: &MultiBufferSnapshot,
+    range: Range<usize>,
+) -> Option<Range<usize>> {
+    let mut candidates = Vec::new();
+
+    let (word_range, kind) = buffer.surrounding_word(range.start);
+    if kind == Some(CharKind::Word) {
+        candidates.push(word_range);
+    }
+
+    let start_row = buffer.offset_to_point(range.start).row;
+    let end_row = buffer.offset_to_point(range.end).row;
+    candidates.push(
+        buffer.point_to_offset(Point::new(start_row, 0))
+            ..buffer.point_to_offset(Point::new(
+                end_row,
+                buffer.line_len(MultiBufferRow(end_row)),
+            )),
+    );
+
+    if let Some(bracket_ranges) = buffer.enclosing_bracket_ranges(range.clone()) {
+        for (open, close) in bracket_ranges {
+            candidates.push(open.end..close.start);
+            candidates.push(open.start..close.end);
+        }
+    }
+
+    candidates.push(0..buffer.len());
+    candidates
+        .into_iter()
+        .filter(|candidate| {
+            candidate.start <= range.start && range.end <= candidate.end && *candidate != range
+        })
+        .min_by_key(|candidate| candidate.len())
+}
+
+/// Converts the case of each line of `text`, leaving the characters that aren't letters or
+/// digits at the start and end of every line untouched.
+fn convert_lines_to_case(text: &str, case: Case) -> String {
+    // Convert line by line, since the to_case crate doesn't support '\n' as a word boundary
+    // https://github.com/rutrum/convert-case/issues/16
+    text.split('\n')
+        .map(|line| {
+            let start = line.find(char::is_alphanumeric).unwrap_or(line.len());
+            let end = line
+                .char_indices()
+                .rev()
+                .find(|(_, c)| c.is_alphanumeric())
+                .map_or(start, |(ix, c)| ix + c.len_utf8());
+            format!(
+                "{}{}{}",
+                &line[..start],
+                line[start..end].to_case(case),
+                &line[end..]
+            )
+        })
+        .join("\n")
+}
+
+/// Reindents text pasted at the given offset, so that its least indented lines get the
+/// indentation of the line it's pasted into, and its other lines keep their indentation relative
+/// to those, in the indentation style of the buffer. Text pasted after the start of the content
+/// of a line is left as it is.
+///
+/// `first_line_indent` is the indentation of the line the text was copied from, if it's known.
+/// When the text's first line has less indentation than that, the copy started after the
+/// indentation, so the first line doesn't count towards the text's indentation.
+fn match_pasted_indentation<'a>(
+    text: &'a str,
+    offset: usize,
+    first_line_indent: Option<u32>,
+    snapshot: &MultiBufferSnapshot,
+    cx: &AppContext,
+) -> Cow<'a, str> {
+    let point = offset.to_point(snapshot);
+    let line_prefix = snapshot
+        .text_for_range(Point::new(point.row, 0)..point)
+        .collect::<String>();
+    if !line_prefix.chars().all(|c| c == ' ' || c == '\t') {
+        return Cow::Borrowed(text);
+    }
+    // Entire lines pasted before a line get that line's indentation. Otherwise, the
+    // indentation before the cursor is already there for the first line.
+    let (target_indent, indent_first_line) = if point.column == 0 && text.ends_with('\n') {
+        let indent_len = snapshot.indent_size_for_line(MultiBufferRow(point.row)).len;
+        let indent = snapshot
+            .text_for_range(Point::new(point.row, 0)..Point::new(point.row, indent_len))
+            .collect::<String>();
+        (indent, true)
+    } else {
+        (line_prefix, false)
+    };
+
+    let tab_size = snapshot.settings_at(offset, cx).tab_size.get();
+    let indent_unit = snapshot
+        .language_indent_size_at(offset, cx)
+        .unwrap_or_else(|| IndentSize::spaces(tab_size));
+    let indent_columns = |line: &str| {
+        line.chars()
+            .take_while(|c| *c == ' ' || *c == '\t')
+            .fold(0, |column, c| match c {
+                '\t' => column / tab_size * tab_size + tab_size,
+                _ => column + 1,
+            })
+    };
+
+    let lines = text.split('\n').collect::<Vec<_>>();
+    let first_line_copied_after_indent = first_line_indent.map_or(false, |first_line_indent| {
+        let first_line = lines[0];
+        let indent_len = first_line.len() - first_line.trim_start_matches([' ', '\t']).len();
+        (indent_len as u32) < first_line_indent
+    });
+    let min_indent_columns = lines
+        .iter()
+        .enumerate()
+        .filter(|(ix, line)| {
+            !(*ix == 0 && first_line_copied_after_indent) && !line.trim().is_empty()
+        })
+        .map(|(_, line)| indent_columns(line))
+        .min()
+        .unwrap_or(0);
+
+    let mut reindented = String::with_capacity(text.len());
+    for (ix, line) in lines.iter().enumerate() {
+        if ix > 0 {
+            reindented.push('\n');
+        }
+        let content = line.trim_start_matches([' ', '\t']);
+        if content.is_empty() {
+            continue;
+        }
+        if ix > 0 || indent_first_line {
+            reindented.push_str(&target_indent);
+        }
+        if ix > 0 || !first_line_copied_after_indent {
+            let relative_columns = indent_columns(line).saturating_sub(min_indent_columns);
+            match indent_unit.kind {
+                IndentKind::Tab => {
+                    reindented.push_str(&"\t".repeat((relative_columns / tab_size) as usize));
+                    reindented.push_str(&" ".repeat((relative_columns % tab_size) as usize));
+                }
+                IndentKind::Space => {
+                    reindented.push_str(&" ".repeat(relative_columns as usize));
+                }
+            }
+        }
+        reindented.push_str(content);
+    }
+    Cow::Owned(reindented)
+}
+
 fn consume_contiguous_rows(
     contiguous_row_selections: &mut Vec<Selection<Point>>,
     selection: &Selection<Point>,
@@ -11634,7 +12652,7 @@ impl EditorSnapshot {
         let show_git_gutter = self.show_git_diff_gutter.unwrap_or_else(|| {
             matches!(
                 ProjectSettings::get_global(cx).git.git_gutter,
-                Some(GitGutterSetting::TrackedFiles)
+                Some(GitGutterSetting::TrackedFiles | GitGutterSetting::SavedFile)
             )
         });
         let gutter_settings = EditorSettings::get_global(cx).gutter;
@@ -16,12 +16,15 @@ pub struct EditorSettings {
     pub scrollbar: Scrollbar,
     pub gutter: Gutter,
     pub vertical_scroll_margin: f32,
+    pub scroll_beyond_last_line: ScrollBeyondLastLine,
     pub scroll_sensitivity: f32,
     pub relative_line_numbers: bool,
     pub seed_search_query_from_cursor: SeedQuerySetting,
     pub multi_cursor_modifier: MultiCursorModifier,
     pub redact_private_values: bool,
     pub expand_excerpt_lines: u32,
+    pub long_line_threshold: usize,
+    pub word_highlights: bool,
     #[serde(default)]
     pub double_click_in_multibuffer: DoubleClickInMultibuffer,
 }
@@ -39,6 +42,19 @@ pub enum CurrentLineHighlight {
     All,
 }
 
+/// How far the editor can be scrolled past its last line.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ScrollBeyondLastLine {
+    /// The last line can be scrolled up to the top of the editor.
+    OnePage,
+    /// The last line stays at the bottom of the editor.
+    Off,
+    /// The last line can be scrolled up to `vertical_scroll_margin` lines above the
+    /// bottom of the editor.
+    VerticalScrollMargin,
+}
+
 /// When to populate a new search's query based on the text under the cursor.
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
@@ -159,6 +175,10 @@ pub struct EditorSettingsContent {
     ///
     /// Default: 3.
     pub vertical_scroll_margin: Option<f32>,
+    /// How far the editor can be scrolled past its last line.
+    ///
+    /// Default: one_page
+    pub scroll_beyond_last_line: Option<ScrollBeyondLastLine>,
     /// Scroll sensitivity multiplier. This multiplier is applied
     /// to both the horizontal and vertical delta values while scrolling.
     ///
@@ -188,6 +208,17 @@ pub struct EditorSettingsContent {
     /// Default: 3
     pub expand_excerpt_lines: Option<u32>,
 
+    /// Lines with more characters than this are laid out and wrapped without being
+    /// measured in full, and disable bracket and occurrence highlighting in their buffer.
+    ///
+    /// Default: 20000
+    pub long_line_threshold: Option<usize>,
+
+    /// Whether to highlight the other occurrences of the word under the cursor.
+    ///
+    /// Default: true
+    pub word_highlights: Option<bool>,
+
     /// What to do when multibuffer is double clicked in some of its excerpts
     /// (parts of singleton buffers).
     ///
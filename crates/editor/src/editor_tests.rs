@@ -2,7 +2,7 @@ use super::*;
 use crate::{
     scroll::scroll_amount::ScrollAmount,
     test::{
-        assert_text_with_selections, build_editor, editor_hunks,
+        assert_text_with_selections, build_editor, build_editor_with_project, editor_hunks,
         editor_lsp_test_context::EditorLspTestContext, editor_test_context::EditorTestContext,
         expanded_hunks, expanded_hunks_background_highlights, select_ranges,
     },
@@ -20,8 +20,8 @@ use language::{
     },
     BracketPairConfig,
     Capability::ReadWrite,
-    FakeLspAdapter, IndentGuide, LanguageConfig, LanguageConfigOverride, LanguageMatcher, Override,
-    Point,
+    FakeLspAdapter, IndentGuide, IndentSize, LanguageConfig, LanguageConfigOverride,
+    LanguageMatcher, Override, Point,
 };
 use language_settings::IndentGuideSettings;
 use multi_buffer::MultiBufferIndentGuide;
@@ -38,7 +38,7 @@ use util::{
     test::{marked_text_ranges, marked_text_ranges_by, sample_text, TextRangeMarker},
 };
 use workspace::{
-    item::{FollowEvent, FollowableItem, Item, ItemHandle},
+    item::{FollowEvent, FollowableItem, Item, ItemHandle, PointOrAnchor},
     NavigationEntry, ViewId,
 };
 
@@ -233,6 +233,48 @@ fn test_undo_redo_with_selection_restoration(cx: &mut TestAppContext) {
     });
 }
 
+#[gpui::test]
+fn test_undo_grouping_boundaries(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let buffer = cx.new_model(|cx| {
+        let mut buffer = language::Buffer::local("", cx);
+        // Group everything typed in this test, apart from the boundaries under test.
+        buffer.set_group_interval(Duration::from_secs(60));
+        buffer
+    });
+    let buffer = cx.new_model(|cx| MultiBuffer::singleton(buffer, cx));
+    let editor = cx.add_window(|cx| build_editor(buffer.clone(), cx));
+
+    _ = editor.update(cx, |editor, cx| {
+        for c in "hello  world".chars() {
+            editor.handle_input(&c.to_string(), cx);
+        }
+        assert_eq!(editor.text(cx), "hello  world");
+
+        // Every word is undone on its own, along with the whitespace after it.
+        editor.undo(&Undo, cx);
+        assert_eq!(editor.text(cx), "hello  ");
+        editor.undo(&Undo, cx);
+        assert_eq!(editor.text(cx), "");
+        editor.redo(&Redo, cx);
+        editor.redo(&Redo, cx);
+        assert_eq!(editor.text(cx), "hello  world");
+        assert_eq!(editor.selections.ranges(cx), vec![12..12]);
+
+        // Moving the cursor in between edits starts a new group.
+        editor.handle_input("!", cx);
+        editor.change_selections(None, cx, |s| s.select_ranges([5..5]));
+        editor.handle_input(",", cx);
+        assert_eq!(editor.text(cx), "hello,  world!");
+        editor.undo(&Undo, cx);
+        assert_eq!(editor.text(cx), "hello  world!");
+        assert_eq!(editor.selections.ranges(cx), vec![5..5]);
+        editor.undo(&Undo, cx);
+        assert_eq!(editor.text(cx), "hello  ");
+    });
+}
+
 #[gpui::test]
 fn test_ime_composition(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
@@ -436,6 +478,68 @@ fn test_selection_with_mouse(cx: &mut TestAppContext) {
     );
 }
 
+#[gpui::test]
+fn test_word_and_line_selection_with_mouse(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let editor = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple("one two three\nfour five six\nseven\n", cx);
+        build_editor(buffer, cx)
+    });
+    let point = |row, column| DisplayPoint::new(DisplayRow(row), column);
+    let drag_to = |position, cx: &mut TestAppContext| {
+        editor
+            .update(cx, |view, cx| {
+                view.update_selection(position, 0, gpui::Point::<f32>::default(), cx);
+                view.selections.display_ranges(cx)
+            })
+            .unwrap()
+    };
+
+    // Double-clicking selects a word, and dragging extends the selection by whole words while
+    // keeping the original word selected.
+    let selections = editor
+        .update(cx, |view, cx| {
+            view.begin_selection(point(0, 5), false, 2, cx);
+            view.selections.display_ranges(cx)
+        })
+        .unwrap();
+    assert_eq!(selections, [point(0, 4)..point(0, 7)]);
+    assert_eq!(drag_to(point(1, 6), cx), [point(0, 4)..point(1, 9)]);
+    assert_eq!(drag_to(point(0, 1), cx), [point(0, 7)..point(0, 0)]);
+    _ = editor.update(cx, |view, cx| view.end_selection(cx));
+
+    // Triple-clicking selects a line, and dragging extends the selection by whole lines.
+    let selections = editor
+        .update(cx, |view, cx| {
+            view.begin_selection(point(1, 2), false, 3, cx);
+            view.selections.display_ranges(cx)
+        })
+        .unwrap();
+    assert_eq!(selections, [point(1, 0)..point(2, 0)]);
+    assert_eq!(drag_to(point(2, 3), cx), [point(1, 0)..point(3, 0)]);
+    assert_eq!(drag_to(point(0, 3), cx), [point(2, 0)..point(0, 0)]);
+    _ = editor.update(cx, |view, cx| view.end_selection(cx));
+
+    // Double-clicking with the multi-cursor modifier adds a word selection instead of replacing
+    // the existing selections.
+    let selections = editor
+        .update(cx, |view, cx| {
+            view.begin_selection(point(0, 0), false, 1, cx);
+            view.end_selection(cx);
+            view.begin_selection(point(2, 1), true, 1, cx);
+            view.end_selection(cx);
+            view.begin_selection(point(2, 1), true, 2, cx);
+            view.end_selection(cx);
+            view.selections.display_ranges(cx)
+        })
+        .unwrap();
+    assert_eq!(
+        selections,
+        [point(0, 0)..point(0, 0), point(2, 0)..point(2, 5)]
+    );
+}
+
 #[gpui::test]
 fn test_canceling_pending_selection(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
@@ -822,6 +926,104 @@ fn test_fold_action(cx: &mut TestAppContext) {
     });
 }
 
+#[gpui::test]
+fn test_fold_at_level(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let view = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple(
+            &"
+                impl Foo {
+                    // Hello!
+
+                    fn a() {
+                        if true {
+                            1
+                        }
+                    }
+
+                    fn b() {
+                        2
+                    }
+                }
+
+                fn c() {
+                    3
+                }
+            "
+            .unindent(),
+            cx,
+        );
+        build_editor(buffer.clone(), cx)
+    });
+
+    _ = view.update(cx, |view, cx| {
+        view.fold_at_level(&FoldAtLevel { level: 2 }, cx);
+        assert_eq!(
+            view.display_text(cx),
+            "
+                impl Foo {
+                    // Hello!
+
+                    fn a() {⋯
+                    }
+
+                    fn b() {⋯
+                    }
+                }
+
+                fn c() {
+                    3
+                }
+            "
+            .unindent(),
+        );
+
+        view.unfold_all(&UnfoldAll, cx);
+        assert_eq!(view.display_text(cx), view.buffer.read(cx).read(cx).text());
+
+        view.fold_at_level(&FoldAtLevel { level: 3 }, cx);
+        assert_eq!(
+            view.display_text(cx),
+            "
+                impl Foo {
+                    // Hello!
+
+                    fn a() {
+                        if true {⋯
+                        }
+                    }
+
+                    fn b() {
+                        2
+                    }
+                }
+
+                fn c() {
+                    3
+                }
+            "
+            .unindent(),
+        );
+
+        view.fold_all(&FoldAll, cx);
+        assert_eq!(
+            view.display_text(cx),
+            "
+                impl Foo {⋯
+                }
+
+                fn c() {⋯
+                }
+            "
+            .unindent(),
+        );
+
+        view.unfold_all(&UnfoldAll, cx);
+        assert_eq!(view.display_text(cx), view.buffer.read(cx).read(cx).text());
+    });
+}
+
 #[gpui::test]
 fn test_move_cursor(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
@@ -1016,6 +1218,60 @@ fn test_move_cursor_multibyte(cx: &mut TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_move_and_delete_grapheme_clusters(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
+
+    // Moving horizontally never lands inside of a cluster.
+    let stops = [
+        "ˇa👩\u{200D}💻🇺🇸🇫🇷e\u{301}हिंदी",
+        "aˇ👩\u{200D}💻🇺🇸🇫🇷e\u{301}हिंदी",
+        "a👩\u{200D}💻ˇ🇺🇸🇫🇷e\u{301}हिंदी",
+        "a👩\u{200D}💻🇺🇸ˇ🇫🇷e\u{301}हिंदी",
+        "a👩\u{200D}💻🇺🇸🇫🇷ˇe\u{301}हिंदी",
+        "a👩\u{200D}💻🇺🇸🇫🇷e\u{301}ˇहिंदी",
+        "a👩\u{200D}💻🇺🇸🇫🇷e\u{301}हिंˇदी",
+        "a👩\u{200D}💻🇺🇸🇫🇷e\u{301}हिंदीˇ",
+    ];
+    cx.set_state(stops[0]);
+    for stop in &stops[1..] {
+        cx.update_editor(|editor, cx| editor.move_right(&MoveRight, cx));
+        cx.assert_editor_state(stop);
+    }
+    for stop in stops.iter().rev().skip(1) {
+        cx.update_editor(|editor, cx| editor.move_left(&MoveLeft, cx));
+        cx.assert_editor_state(stop);
+    }
+
+    // Deleting removes whole clusters.
+    cx.set_state("a👩\u{200D}💻ˇb");
+    cx.update_editor(|editor, cx| editor.backspace(&Backspace, cx));
+    cx.assert_editor_state("aˇb");
+
+    cx.set_state("aˇ🇺🇸🇫🇷");
+    cx.update_editor(|editor, cx| editor.delete(&Delete, cx));
+    cx.assert_editor_state("aˇ🇫🇷");
+
+    cx.set_state("cafe\u{301}ˇ हिंˇदी");
+    cx.update_editor(|editor, cx| editor.backspace(&Backspace, cx));
+    cx.assert_editor_state("cafˇ ˇदी");
+
+    // Moving vertically over wide clusters keeps the goal column.
+    cx.set_state("abcdeˇfghij\n👩\u{200D}💻👩\u{200D}💻👩\u{200D}💻\nabcdefghij");
+    cx.update_editor(|editor, cx| {
+        editor.move_down(&MoveDown, cx);
+        let head = editor.selections.newest::<Point>(cx).head();
+        assert_eq!(head.row, 1);
+        assert!(
+            [0, 11, 22, 33].contains(&head.column),
+            "cursor at {head:?} is inside of a cluster"
+        );
+        editor.move_down(&MoveDown, cx);
+    });
+    cx.assert_editor_state("abcdefghij\n👩\u{200D}💻👩\u{200D}💻👩\u{200D}💻\nabcdeˇfghij");
+}
+
 #[gpui::test]
 fn test_move_cursor_different_line_lengths(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
@@ -1691,6 +1947,92 @@ async fn test_autoscroll(cx: &mut gpui::TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_scroll_beyond_last_line(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
+
+    let line_height = cx.update_editor(|editor, cx| {
+        editor.set_vertical_scroll_margin(2, cx);
+        editor
+            .style()
+            .unwrap()
+            .text
+            .line_height_in_pixels(cx.rem_size())
+    });
+    let window = cx.window;
+    cx.simulate_window_resize(window, size(px(1000.), 6. * line_height));
+    cx.set_state("ˇone\ntwo\nthree\nfour\nfive\nsix\nseven\neight\nnine\nten");
+
+    let scroll_to_end = |scroll_beyond_last_line, cx: &mut EditorTestContext| {
+        cx.update(|cx| {
+            cx.update_global::<SettingsStore, _>(|settings, cx| {
+                settings.update_user_settings::<EditorSettings>(cx, |settings| {
+                    settings.scroll_beyond_last_line = Some(scroll_beyond_last_line);
+                });
+            })
+        });
+        cx.update_editor(|editor, cx| editor.set_scroll_position(point(0., 100.), cx));
+        cx.update_editor(|editor, cx| editor.snapshot(cx).scroll_position().y)
+    };
+
+    // The last line can be scrolled to the top of the editor.
+    assert_eq!(scroll_to_end(ScrollBeyondLastLine::OnePage, &mut cx), 9.);
+    // The last line stays at the bottom of the editor.
+    assert_eq!(scroll_to_end(ScrollBeyondLastLine::Off, &mut cx), 4.);
+    // The last line can be scrolled up by the vertical scroll margin.
+    assert_eq!(
+        scroll_to_end(ScrollBeyondLastLine::VerticalScrollMargin, &mut cx),
+        6.
+    );
+
+    // Folded lines are counted as a single display row.
+    cx.update_editor(|editor, cx| {
+        editor.fold_ranges(
+            [(Point::new(1, 0)..Point::new(4, 0), FoldPlaceholder::test())],
+            true,
+            cx,
+        );
+    });
+    assert_eq!(scroll_to_end(ScrollBeyondLastLine::Off, &mut cx), 1.);
+    assert_eq!(scroll_to_end(ScrollBeyondLastLine::OnePage, &mut cx), 6.);
+}
+
+#[gpui::test]
+async fn test_autoscroll_center_if_offscreen(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
+
+    let line_height = cx.update_editor(|editor, cx| {
+        editor.set_vertical_scroll_margin(1, cx);
+        editor
+            .style()
+            .unwrap()
+            .text
+            .line_height_in_pixels(cx.rem_size())
+    });
+    let window = cx.window;
+    cx.simulate_window_resize(window, size(px(1000.), 7. * line_height));
+    cx.set_state(&format!("ˇ{}", "line\n".repeat(30)));
+
+    let select_row = |row, cx: &mut EditorTestContext| {
+        cx.update_editor(|editor, cx| {
+            editor.change_selections(Some(Autoscroll::center_if_offscreen()), cx, |s| {
+                s.select_ranges([Point::new(row, 0)..Point::new(row, 0)])
+            })
+        });
+        cx.update_editor(|editor, cx| editor.snapshot(cx).scroll_position().y)
+    };
+
+    // A cursor that's already onscreen doesn't scroll the editor.
+    assert_eq!(select_row(4, &mut cx), 0.);
+    // A cursor within the vertical scroll margin does, and ends up centered.
+    assert_eq!(select_row(6, &mut cx), 3.);
+    // As does a cursor that's offscreen.
+    assert_eq!(select_row(20, &mut cx), 17.);
+    assert_eq!(select_row(5, &mut cx), 2.);
+}
+
 #[gpui::test]
 async fn test_move_page_up_page_down(cx: &mut gpui::TestAppContext) {
     init_test(cx, |_| {});
@@ -2431,6 +2773,101 @@ async fn test_indent_outdent_with_hard_tabs(cx: &mut gpui::TestAppContext) {
     "});
 }
 
+#[gpui::test]
+async fn test_convert_indentation(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |settings| {
+        settings.defaults.tab_size = NonZeroU32::new(4);
+    });
+
+    let mut cx = EditorTestContext::new(cx).await;
+    cx.set_state(indoc! {"
+        fn main() {
+            if true {
+        \t\tˇone();
+          \t  two();
+            }
+        }
+    "});
+
+    cx.update_editor(|e, cx| e.convert_indentation_to_tabs(&ConvertIndentationToTabs, cx));
+    cx.assert_editor_state(indoc! {"
+        fn main() {
+        \tif true {
+        \t\tˇone();
+        \t  two();
+        \t}
+        }
+    "});
+
+    cx.update_editor(|e, cx| e.convert_indentation_to_spaces(&ConvertIndentationToSpaces, cx));
+    cx.assert_editor_state(indoc! {"
+        fn main() {
+            if true {
+                ˇone();
+              two();
+            }
+        }
+    "});
+
+    // The whole conversion is undone at once.
+    cx.update_editor(|e, cx| e.undo(&Undo, cx));
+    cx.assert_editor_state(indoc! {"
+        fn main() {
+        \tif true {
+        \t\tˇone();
+        \t  two();
+        \t}
+        }
+    "});
+}
+
+#[gpui::test]
+async fn test_reindent(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |settings| {
+        settings.defaults.tab_size = NonZeroU32::new(4);
+    });
+
+    let mut cx = EditorTestContext::new(cx).await;
+    cx.set_state(indoc! {"
+        fn main() {
+            if true {
+                ˇone(
+                  two);
+            }
+        }
+    "});
+
+    cx.update_editor(|e, cx| e.reindent(IndentSize::spaces(4), IndentSize::spaces(2), cx));
+    cx.assert_editor_state(indoc! {"
+        fn main() {
+          if true {
+            ˇone(
+              two);
+          }
+        }
+    "});
+
+    cx.update_editor(|e, cx| e.reindent(IndentSize::spaces(2), IndentSize::tab(), cx));
+    cx.assert_editor_state(indoc! {"
+        fn main() {
+        \tif true {
+        \t\tˇone(
+        \t\t\ttwo);
+        \t}
+        }
+    "});
+
+    cx.update_editor(|e, cx| e.reindent(IndentSize::tab(), IndentSize::spaces(8), cx));
+    cx.assert_editor_state(indoc! {"
+        fn main() {
+                if true {
+                        ˇone(
+                                two);
+                }
+        }
+    "});
+}
+
 #[gpui::test]
 fn test_indent_outdent_with_excerpts(cx: &mut TestAppContext) {
     init_test(cx, |settings| {
@@ -2800,10 +3237,20 @@ fn test_join_lines_with_multi_selection(cx: &mut TestAppContext) {
 }
 
 #[gpui::test]
-async fn test_join_lines_with_git_diff_base(
-    executor: BackgroundExecutor,
-    cx: &mut gpui::TestAppContext,
-) {
+async fn test_join_lines_without_space(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
+
+    cx.set_state("aˇaa\n    bbb\nccc");
+    cx.update_editor(|e, cx| e.join_lines_without_space(&JoinLinesWithoutSpace, cx));
+    cx.assert_editor_state("aaaˇbbb\nccc");
+}
+
+#[gpui::test]
+async fn test_join_lines_with_git_diff_base(
+    executor: BackgroundExecutor,
+    cx: &mut gpui::TestAppContext,
+) {
     init_test(cx, |_| {});
 
     let mut cx = EditorTestContext::new(cx).await;
@@ -2930,7 +3377,7 @@ async fn test_manipulate_lines_with_single_selection(cx: &mut TestAppContext) {
     // From here on out, test more complex cases of manipulate_lines() with a single driver method: sort_lines_case_sensitive()
     // Since all methods calling manipulate_lines() are doing the exact same general thing (reordering lines)
 
-    // Don't manipulate when cursor is on single line, but expand the selection
+    // Manipulate the whole buffer when nothing is selected, keeping the trailing newline
     cx.set_state(indoc! {"
         ddˇdd
         ccc
@@ -2939,12 +3386,17 @@ async fn test_manipulate_lines_with_single_selection(cx: &mut TestAppContext) {
     "});
     cx.update_editor(|e, cx| e.sort_lines_case_sensitive(&SortLinesCaseSensitive, cx));
     cx.assert_editor_state(indoc! {"
-        «ddddˇ»
-        ccc
+        «a
         bb
-        a
+        ccc
+        ddddˇ»
     "});
 
+    // A final line without a newline is sorted like any other
+    cx.set_state("ccc\nˇa\nbb");
+    cx.update_editor(|e, cx| e.sort_lines_case_sensitive(&SortLinesCaseSensitive, cx));
+    cx.assert_editor_state("«a\nbb\ncccˇ»");
+
     // Basic manipulate case
     // Start selection moves to column 0
     // End of selection shrinks to fit shorter line
@@ -3024,6 +3476,80 @@ async fn test_manipulate_lines_with_single_selection(cx: &mut TestAppContext) {
     "});
 }
 
+#[gpui::test]
+async fn test_sort_lines_with_options(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+
+    cx.set_state(indoc! {"
+        «b
+        item10
+        A
+        item2
+        aˇ»
+    "});
+    cx.update_editor(|e, cx| {
+        e.sort_lines(
+            &SortLines {
+                descending: true,
+                ..Default::default()
+            },
+            cx,
+        )
+    });
+    cx.assert_editor_state(indoc! {"
+        «item2
+        item10
+        b
+        a
+        Aˇ»
+    "});
+
+    cx.update_editor(|e, cx| {
+        e.sort_lines(
+            &SortLines {
+                case_insensitive: true,
+                natural: true,
+                ..Default::default()
+            },
+            cx,
+        )
+    });
+    cx.assert_editor_state(indoc! {"
+        «a
+        A
+        b
+        item2
+        item10ˇ»
+    "});
+
+    // Each selection is sorted on its own, as a single transaction
+    cx.set_state(indoc! {"
+        «3
+        1ˇ»
+
+        «y
+        xˇ»
+    "});
+    cx.update_editor(|e, cx| e.sort_lines(&SortLines::default(), cx));
+    cx.assert_editor_state(indoc! {"
+        «1
+        3ˇ»
+
+        «x
+        yˇ»
+    "});
+    cx.update_editor(|e, cx| e.undo(&Undo, cx));
+    cx.assert_editor_state(indoc! {"
+        «3
+        1ˇ»
+
+        «y
+        xˇ»
+    "});
+}
+
 #[gpui::test]
 async fn test_unique_lines_multi_selection(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
@@ -3296,6 +3822,42 @@ async fn test_manipulate_text(cx: &mut TestAppContext) {
         «aaaBbbˇ» «bbbCccˇ» «cccDddˇ»
     "});
 
+    // Identifiers are split on underscores, hyphens and case transitions, while the
+    // non-word characters around them are kept
+    cx.set_state(indoc! {"
+        «  __fooBar-baz_Qux();ˇ»
+    "});
+    cx.update_editor(|e, cx| e.convert_to_snake_case(&ConvertToSnakeCase, cx));
+    cx.assert_editor_state(indoc! {"
+        «  __foo_bar_baz_qux();ˇ»
+    "});
+    cx.update_editor(|e, cx| e.convert_to_kebab_case(&ConvertToKebabCase, cx));
+    cx.assert_editor_state(indoc! {"
+        «  __foo-bar-baz-qux();ˇ»
+    "});
+    cx.update_editor(|e, cx| e.convert_to_upper_camel_case(&ConvertToUpperCamelCase, cx));
+    cx.assert_editor_state(indoc! {"
+        «  __FooBarBazQux();ˇ»
+    "});
+
+    // Non-ASCII letters go through their proper case mappings, with every cursor converted in
+    // a single transaction
+    cx.set_state(indoc! {"
+        ˇÉtéChaud ˇstraße_groß
+    "});
+    cx.update_editor(|e, cx| e.convert_to_snake_case(&ConvertToSnakeCase, cx));
+    cx.assert_editor_state(indoc! {"
+        «été_chaudˇ» «straße_großˇ»
+    "});
+    cx.update_editor(|e, cx| e.convert_to_lower_camel_case(&ConvertToLowerCamelCase, cx));
+    cx.assert_editor_state(indoc! {"
+        «étéChaudˇ» «straßeGroßˇ»
+    "});
+    cx.update_editor(|e, cx| e.undo(&Undo, cx));
+    cx.assert_editor_state(indoc! {"
+        «été_chaudˇ» «straße_großˇ»
+    "});
+
     cx.set_state(indoc! {"
         «hElLo, WoRld!ˇ»
     "});
@@ -3408,6 +3970,25 @@ fn test_duplicate_line(cx: &mut TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_duplicate_selection(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
+
+    cx.set_state("«abˇ»c d«eˇ»f ˇghi");
+    cx.update_editor(|e, cx| e.duplicate_selection(&DuplicateSelection, cx));
+    cx.assert_editor_state("ab«abˇ»c de«eˇ»f ˇghi");
+
+    // Undo should be transactional.
+    cx.update_editor(|e, cx| e.undo(&Undo, cx));
+    cx.assert_editor_state("«abˇ»c d«eˇ»f ˇghi");
+
+    // Without any selected text, the cursors' lines are duplicated.
+    cx.set_state("abc\ndˇef");
+    cx.update_editor(|e, cx| e.duplicate_selection(&DuplicateSelection, cx));
+    cx.assert_editor_state("abc\ndef\ndˇef");
+}
+
 #[gpui::test]
 fn test_move_line_up_down(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
@@ -3714,6 +4295,79 @@ async fn test_clipboard(cx: &mut gpui::TestAppContext) {
         tˇhe lazy dog"});
 }
 
+#[gpui::test]
+async fn test_columnar_selection_over_short_lines(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+    cx.set_state(indoc! {"
+        ˇone two
+        x
+        three four"});
+    cx.update_editor(|editor, cx| {
+        editor.begin_columnar_selection(DisplayPoint::new(DisplayRow(0), 4), 4, true, cx);
+        editor.update_selection(
+            DisplayPoint::new(DisplayRow(2), 7),
+            7,
+            gpui::Point::<f32>::default(),
+            cx,
+        );
+        editor.end_selection(cx);
+    });
+    // Lines that are too short for the block get a cursor at their end.
+    cx.assert_editor_state(indoc! {"
+        one «twoˇ»
+        xˇ
+        thre«e fˇ»our"});
+
+    // Copying yields one segment per line, with nothing for the short line.
+    cx.update_editor(|e, cx| e.copy(&Copy, cx));
+    assert_eq!(
+        cx.read_from_clipboard().map(|item| item.text().to_owned()),
+        Some("two\n\ne f".to_owned())
+    );
+
+    cx.update_editor(|e, cx| e.handle_input("!", cx));
+    cx.assert_editor_state(indoc! {"
+        one !ˇ
+        x!ˇ
+        thre!ˇour"});
+}
+
+#[gpui::test]
+async fn test_paste_external_text_with_multiple_cursors(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+
+    // Each cursor pastes one line of text copied elsewhere, when there are as many lines
+    // as cursors.
+    cx.set_state(indoc! {"
+        aˇ
+        «bˇ»
+        cˇ"});
+    cx.update_editor(|e, cx| {
+        cx.write_to_clipboard(ClipboardItem::new("1\n2\n3\n".into()));
+        e.paste(&Paste, cx);
+    });
+    cx.assert_editor_state(indoc! {"
+        a1ˇ
+        2ˇ
+        c3ˇ"});
+
+    // Otherwise the entire text is pasted at each cursor.
+    cx.set_state("aˇ bˇ cˇ");
+    cx.update_editor(|e, cx| {
+        cx.write_to_clipboard(ClipboardItem::new("1\n2".into()));
+        e.paste(&Paste, cx);
+    });
+    cx.assert_editor_state("a1\n2ˇ b1\n2ˇ c1\n2ˇ");
+
+    // Pasting is undone at once.
+    cx.update_editor(|e, cx| e.undo(&Undo, cx));
+    cx.assert_editor_state("aˇ bˇ cˇ");
+}
+
 #[gpui::test]
 async fn test_paste_multiline(cx: &mut gpui::TestAppContext) {
     init_test(cx, |_| {});
@@ -3828,6 +4482,118 @@ async fn test_paste_multiline(cx: &mut gpui::TestAppContext) {
     "});
 }
 
+#[gpui::test]
+async fn test_paste_and_match_indentation(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+
+    // Copy a block that starts after the indentation of its first line.
+    cx.set_state(indoc! {"
+        fn a() {
+            if b {
+                «c(
+                    d,
+                );ˇ»
+            }
+        }
+    "});
+    cx.update_editor(|e, cx| e.copy(&Copy, cx));
+
+    // Its lines keep their indentation relative to each other.
+    cx.set_state(indoc! {"
+        fn e() {
+            ˇ
+        }
+    "});
+    cx.update_editor(|e, cx| e.paste_and_match_indentation(&PasteAndMatchIndentation, cx));
+    cx.assert_editor_state(indoc! {"
+        fn e() {
+            c(
+                d,
+            );ˇ
+        }
+    "});
+
+    // Each cursor gets the text indented to match its own line, in a single transaction.
+    cx.set_state("ˇ\n    ˇ");
+    cx.update_editor(|e, cx| {
+        cx.write_to_clipboard(ClipboardItem::new("x {\n  y\n}".into()));
+        e.paste_and_match_indentation(&PasteAndMatchIndentation, cx);
+    });
+    cx.assert_editor_state("x {\n  y\n}ˇ\n    x {\n      y\n    }ˇ");
+    cx.update_editor(|e, cx| e.undo(&Undo, cx));
+    cx.assert_editor_state("ˇ\n    ˇ");
+
+    // Text pasted after the start of a line's content is left alone.
+    cx.set_state("let x = ˇ;");
+    cx.update_editor(|e, cx| {
+        cx.write_to_clipboard(ClipboardItem::new("f(\n        a)".into()));
+        e.paste_and_match_indentation(&PasteAndMatchIndentation, cx);
+    });
+    cx.assert_editor_state("let x = f(\n        a)ˇ;");
+}
+
+#[gpui::test]
+async fn test_match_indentation_on_paste_with_hard_tabs(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |settings| {
+        settings.defaults.hard_tabs = Some(true);
+        settings.defaults.match_indentation_on_paste = Some(true);
+    });
+
+    let mut cx = EditorTestContext::new(cx).await;
+
+    // Text indented with spaces gets indented with tabs.
+    cx.set_state(indoc! {"
+        fn a() {
+        \tif b {
+        \t\tˇ
+        \t}
+        }
+    "});
+    cx.update_editor(|e, cx| {
+        cx.write_to_clipboard(ClipboardItem::new(
+            "    c(\n        d,\n      e,\n    );\n".into(),
+        ));
+        e.paste(&Paste, cx);
+    });
+    cx.assert_editor_state(indoc! {"
+        fn a() {
+        \tif b {
+        \t\tc(
+        \t\t\td,
+        \t\t  e,
+        \t\t);
+        ˇ
+        \t}
+        }
+    "});
+
+    // Entire lines pasted before a line get its indentation.
+    cx.set_state(indoc! {"
+        fn a() {
+        \tif b {
+        \t\tc();ˇ
+        \t}
+        }
+    "});
+    cx.update_editor(|e, cx| e.copy(&Copy, cx));
+    cx.set_state(indoc! {"
+        fn a() {
+        \tˇif b {
+        \t}
+        }
+    "});
+    cx.update_editor(|e, cx| e.paste(&Paste, cx));
+    cx.assert_editor_state(indoc! {"
+        fn a() {
+        \tc();
+        \tˇif b {
+        \t}
+        }
+    "});
+}
+
 #[gpui::test]
 fn test_select_all(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
@@ -3845,6 +4611,46 @@ fn test_select_all(cx: &mut TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_selection_history_after_mouse_drag(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+    cx.set_state("one two ˇthree");
+    cx.update_editor(|editor, cx| {
+        editor.begin_selection(DisplayPoint::new(DisplayRow(0), 4), false, 1, cx);
+        for column in 5..=7 {
+            editor.update_selection(
+                DisplayPoint::new(DisplayRow(0), column),
+                0,
+                gpui::Point::<f32>::default(),
+                cx,
+            );
+        }
+        editor.end_selection(cx);
+        editor.select_all(&SelectAll, cx);
+    });
+    cx.assert_editor_state("«one two threeˇ»");
+
+    // Undoing the select-all restores the dragged selection, and undoing again skips
+    // the intermediate positions of the drag.
+    cx.update_editor(|editor, cx| editor.undo_selection(&UndoSelection, cx));
+    cx.assert_editor_state("one «twoˇ» three");
+    cx.update_editor(|editor, cx| editor.undo_selection(&UndoSelection, cx));
+    cx.assert_editor_state("one two ˇthree");
+    cx.update_editor(|editor, cx| editor.redo_selection(&RedoSelection, cx));
+    cx.assert_editor_state("one «twoˇ» three");
+
+    // Historical selections are anchored, so they follow edits made since.
+    cx.update_editor(|editor, cx| {
+        editor.buffer().update(cx, |buffer, cx| {
+            buffer.edit([(0..0, "1 ")], None, cx);
+        });
+        editor.undo_selection(&UndoSelection, cx);
+    });
+    cx.assert_editor_state("1 one two ˇthree");
+}
+
 #[gpui::test]
 fn test_select_line(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
@@ -3893,54 +4699,176 @@ fn test_select_line(cx: &mut TestAppContext) {
 }
 
 #[gpui::test]
-fn test_split_selection_into_lines(cx: &mut TestAppContext) {
+async fn test_marks(cx: &mut gpui::TestAppContext) {
     init_test(cx, |_| {});
 
-    let view = cx.add_window(|cx| {
-        let buffer = MultiBuffer::build_simple(&sample_text(9, 5, 'a'), cx);
-        build_editor(buffer, cx)
-    });
-    _ = view.update(cx, |view, cx| {
-        view.fold_ranges(
-            vec![
-                (Point::new(0, 2)..Point::new(1, 2), FoldPlaceholder::test()),
-                (Point::new(2, 3)..Point::new(4, 1), FoldPlaceholder::test()),
-                (Point::new(7, 0)..Point::new(8, 4), FoldPlaceholder::test()),
-            ],
-            true,
-            cx,
-        );
-        view.change_selections(None, cx, |s| {
-            s.select_display_ranges([
-                DisplayPoint::new(DisplayRow(0), 0)..DisplayPoint::new(DisplayRow(0), 1),
-                DisplayPoint::new(DisplayRow(0), 2)..DisplayPoint::new(DisplayRow(0), 2),
-                DisplayPoint::new(DisplayRow(1), 0)..DisplayPoint::new(DisplayRow(1), 0),
-                DisplayPoint::new(DisplayRow(4), 4)..DisplayPoint::new(DisplayRow(4), 4),
-            ])
+    let mut cx = EditorTestContext::new(cx).await;
+    cx.set_state("one\ntwˇo\nthree\nfour");
+    let (a, first) = cx.update_editor(|editor, cx| {
+        let a = editor.insert_mark(Some('a'), cx).unwrap();
+        editor.change_selections(None, cx, |s| {
+            s.select_ranges([Point::new(3, 1)..Point::new(3, 1)])
         });
-        assert_eq!(view.display_text(cx), "aa⋯bbb\nccc⋯eeee\nfffff\nggggg\n⋯i");
+        (a, editor.insert_mark(None, cx).unwrap())
     });
 
-    _ = view.update(cx, |view, cx| {
-        view.split_selection_into_lines(&SplitSelectionIntoLines, cx);
-        assert_eq!(
-            view.display_text(cx),
-            "aaaaa\nbbbbb\nccc⋯eeee\nfffff\nggggg\n⋯i"
-        );
-        assert_eq!(
-            view.selections.display_ranges(cx),
-            [
-                DisplayPoint::new(DisplayRow(0), 1)..DisplayPoint::new(DisplayRow(0), 1),
-                DisplayPoint::new(DisplayRow(0), 2)..DisplayPoint::new(DisplayRow(0), 2),
-                DisplayPoint::new(DisplayRow(2), 0)..DisplayPoint::new(DisplayRow(2), 0),
-                DisplayPoint::new(DisplayRow(5), 4)..DisplayPoint::new(DisplayRow(5), 4)
-            ]
-        );
+    // Marks follow the edits made before them.
+    cx.update_editor(|editor, cx| {
+        editor.change_selections(None, cx, |s| s.select_ranges([0..0]));
+        editor.handle_input("zero\n", cx);
+        editor.jump_to_mark(&JumpToMark { name: "a".into() }, cx);
     });
+    cx.assert_editor_state("zero\none\ntwˇo\nthree\nfour");
+    cx.update_editor(|editor, cx| {
+        let names = editor
+            .marks(cx)
+            .unwrap()
+            .iter()
+            .map(|mark| mark.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, [MarkName::Letter('a'), MarkName::Number(1)]);
+        assert!(editor.go_to_mark(first, cx));
 
-    _ = view.update(cx, |view, cx| {
-        view.change_selections(None, cx, |s| {
-            s.select_display_ranges([
+        // Digits aren't letters, as their names would read like the ones of numbered marks.
+        assert!(editor.insert_mark(Some('1'), cx).is_err());
+
+        // The marks belong to the buffer, so its other editors share them.
+        let buffer = editor.buffer().read(cx).as_singleton().unwrap();
+        let other_editor = cx.new_view(|cx| Editor::for_buffer(buffer, None, cx));
+        assert_eq!(other_editor.read(cx).marks(cx).unwrap().len(), 2);
+    });
+    cx.assert_editor_state("zero\none\ntwo\nthree\nfˇour");
+
+    // Setting a mark again moves it and keeps its identity, while deleting the line of a mark
+    // drops it.
+    cx.update_editor(|editor, cx| {
+        assert_eq!(editor.insert_mark(Some('a'), cx).unwrap(), a);
+        editor.change_selections(None, cx, |s| {
+            s.select_ranges([Point::new(1, 0)..Point::new(1, 0)])
+        });
+        let second = editor.insert_mark(None, cx).unwrap();
+        assert_eq!(
+            editor.marks(cx).unwrap().get(second).unwrap().name,
+            MarkName::Number(2)
+        );
+
+        editor.delete_line(&DeleteLine, cx);
+        assert!(editor.marks(cx).unwrap().get(second).is_none());
+        assert_eq!(editor.marks(cx).unwrap().len(), 2);
+
+        editor.change_selections(None, cx, |s| {
+            s.select_ranges([Point::new(3, 0)..Point::new(3, 0)])
+        });
+        editor.delete_line(&DeleteLine, cx);
+        assert!(editor.marks(cx).unwrap().is_empty());
+
+        editor.insert_mark(None, cx).unwrap();
+        editor.clear_marks(&ClearMarks, cx);
+        assert!(editor.marks(cx).unwrap().is_empty());
+    });
+}
+
+#[gpui::test]
+async fn test_spell_check(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |settings| settings.defaults.spell_check = Some(true));
+    cx.update(|cx| {
+        spell_check::set_global(
+            Arc::new(
+                spell_check::FakeSpellChecker::new(["one", "two", "three"])
+                    .with_suggestions("tow", &["two", "tow"]),
+            ),
+            cx,
+        )
+    });
+
+    let mut cx = EditorTestContext::new(cx).await;
+    let misspelled_words = |cx: &mut EditorTestContext| {
+        cx.executor().advance_clock(spelling::SPELL_CHECK_DEBOUNCE);
+        cx.run_until_parked();
+        cx.update_editor(|editor, cx| {
+            let buffer = editor.buffer.read(cx).snapshot(cx);
+            editor
+                .misspellings()
+                .iter()
+                .map(|range| buffer.text_for_range(range.clone()).collect::<String>())
+                .collect::<Vec<_>>()
+        })
+    };
+
+    cx.set_state("one tow\nthreˇ three");
+    assert_eq!(misspelled_words(&mut cx), ["tow", "thre"]);
+
+    // Fixing a word gets its line checked again.
+    cx.simulate_input("e");
+    assert_eq!(misspelled_words(&mut cx), ["tow"]);
+
+    cx.update_editor(|editor, cx| {
+        let range = editor.misspellings()[0].clone();
+        editor.replace_misspelling(range, "two", cx);
+    });
+    cx.assert_editor_state("one two\nthreeˇ three");
+    assert!(misspelled_words(&mut cx).is_empty());
+
+    cx.update_editor(|editor, cx| editor.undo(&Undo, cx));
+    assert_eq!(misspelled_words(&mut cx), ["tow"]);
+
+    // Words added to the dictionary stop being misspelled.
+    cx.update_editor(|editor, cx| {
+        editor.change_selections(None, cx, |s| s.select_ranges([5..5]));
+        editor.add_word_to_dictionary(&AddWordToDictionary, cx);
+    });
+    assert!(misspelled_words(&mut cx).is_empty());
+}
+
+#[gpui::test]
+fn test_split_selection_into_lines(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let view = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple(&sample_text(9, 5, 'a'), cx);
+        build_editor(buffer, cx)
+    });
+    _ = view.update(cx, |view, cx| {
+        view.fold_ranges(
+            vec![
+                (Point::new(0, 2)..Point::new(1, 2), FoldPlaceholder::test()),
+                (Point::new(2, 3)..Point::new(4, 1), FoldPlaceholder::test()),
+                (Point::new(7, 0)..Point::new(8, 4), FoldPlaceholder::test()),
+            ],
+            true,
+            cx,
+        );
+        view.change_selections(None, cx, |s| {
+            s.select_display_ranges([
+                DisplayPoint::new(DisplayRow(0), 0)..DisplayPoint::new(DisplayRow(0), 1),
+                DisplayPoint::new(DisplayRow(0), 2)..DisplayPoint::new(DisplayRow(0), 2),
+                DisplayPoint::new(DisplayRow(1), 0)..DisplayPoint::new(DisplayRow(1), 0),
+                DisplayPoint::new(DisplayRow(4), 4)..DisplayPoint::new(DisplayRow(4), 4),
+            ])
+        });
+        assert_eq!(view.display_text(cx), "aa⋯bbb\nccc⋯eeee\nfffff\nggggg\n⋯i");
+    });
+
+    _ = view.update(cx, |view, cx| {
+        view.split_selection_into_lines(&SplitSelectionIntoLines, cx);
+        assert_eq!(
+            view.display_text(cx),
+            "aaaaa\nbbbbb\nccc⋯eeee\nfffff\nggggg\n⋯i"
+        );
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            [
+                DisplayPoint::new(DisplayRow(0), 1)..DisplayPoint::new(DisplayRow(0), 1),
+                DisplayPoint::new(DisplayRow(0), 2)..DisplayPoint::new(DisplayRow(0), 2),
+                DisplayPoint::new(DisplayRow(2), 0)..DisplayPoint::new(DisplayRow(2), 0),
+                DisplayPoint::new(DisplayRow(5), 4)..DisplayPoint::new(DisplayRow(5), 4)
+            ]
+        );
+    });
+
+    _ = view.update(cx, |view, cx| {
+        view.change_selections(None, cx, |s| {
+            s.select_display_ranges([
                 DisplayPoint::new(DisplayRow(5), 0)..DisplayPoint::new(DisplayRow(0), 1)
             ])
         });
@@ -4248,6 +5176,66 @@ async fn test_select_next(cx: &mut gpui::TestAppContext) {
     cx.assert_editor_state("«abcˇ»\n«abcˇ» «abcˇ»\ndefabc\n«abcˇ»");
 }
 
+#[gpui::test]
+async fn test_select_next_overlapping_and_wrapping_occurrences(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+
+    // Occurrences overlapping the selected ones are skipped.
+    cx.set_state("a«aaˇ» aa\naaa");
+    cx.update_editor(|e, cx| e.select_next(&SelectNext::default(), cx))
+        .unwrap();
+    cx.assert_editor_state("a«aaˇ» «aaˇ»\naaa");
+    cx.update_editor(|e, cx| e.select_next(&SelectNext::default(), cx))
+        .unwrap();
+    cx.assert_editor_state("a«aaˇ» «aaˇ»\n«aaˇ»a");
+    cx.update_editor(|e, cx| e.select_next(&SelectNext::default(), cx))
+        .unwrap();
+    cx.assert_editor_state("a«aaˇ» «aaˇ»\n«aaˇ»a");
+
+    // The search wraps around the end of the buffer, and stops once it gets back to the
+    // first selection.
+    cx.set_state("aa aa «aaˇ»");
+    cx.update_editor(|e, cx| e.select_next(&SelectNext::default(), cx))
+        .unwrap();
+    cx.assert_editor_state("«aaˇ» aa «aaˇ»");
+    cx.update_editor(|e, cx| e.select_next(&SelectNext::default(), cx))
+        .unwrap();
+    cx.assert_editor_state("«aaˇ» «aaˇ» «aaˇ»");
+    cx.update_editor(|e, cx| e.select_next(&SelectNext::default(), cx))
+        .unwrap();
+    cx.assert_editor_state("«aaˇ» «aaˇ» «aaˇ»");
+
+    // Skipping an occurrence replaces the newest selection with the next occurrence.
+    cx.set_state("«aaˇ» aa aa");
+    cx.update_editor(|e, cx| e.select_next(&SelectNext::default(), cx))
+        .unwrap();
+    cx.assert_editor_state("«aaˇ» «aaˇ» aa");
+    cx.update_editor(|e, cx| {
+        e.select_next(
+            &SelectNext {
+                replace_newest: true,
+            },
+            cx,
+        )
+    })
+    .unwrap();
+    cx.assert_editor_state("«aaˇ» aa «aaˇ»");
+    cx.update_editor(|e, cx| e.select_next(&SelectNext::default(), cx))
+        .unwrap();
+    cx.assert_editor_state("«aaˇ» aa «aaˇ»");
+
+    // When seeded from the word under the cursor, only whole words are selected.
+    cx.set_state("ˇaa aaa aa");
+    cx.update_editor(|e, cx| e.select_next(&SelectNext::default(), cx))
+        .unwrap();
+    cx.assert_editor_state("«aaˇ» aaa aa");
+    cx.update_editor(|e, cx| e.select_next(&SelectNext::default(), cx))
+        .unwrap();
+    cx.assert_editor_state("«aaˇ» aaa «aaˇ»");
+}
+
 #[gpui::test]
 async fn test_select_all_matches(cx: &mut gpui::TestAppContext) {
     init_test(cx, |_| {});
@@ -4258,6 +5246,60 @@ async fn test_select_all_matches(cx: &mut gpui::TestAppContext) {
     cx.update_editor(|e, cx| e.select_all_matches(&SelectAllMatches, cx))
         .unwrap();
     cx.assert_editor_state("«abcˇ»\n«abcˇ» «abcˇ»\ndefabc\n«abcˇ»");
+
+    // Selected text matches anywhere, not only at word boundaries.
+    cx.set_state("«abˇ»c\nabc ab\ndefabc");
+    cx.update_editor(|e, cx| e.select_all_matches(&SelectAllMatches, cx))
+        .unwrap();
+    cx.assert_editor_state("«abˇ»c\n«abˇ»c «abˇ»\ndef«abˇ»c");
+
+    // Edits made with all the occurrences selected are undone at once.
+    cx.update_editor(|e, cx| e.handle_input("x", cx));
+    cx.assert_editor_state("xˇc\nxˇc xˇ\ndefxˇc");
+    cx.update_editor(|e, cx| e.undo(&Undo, cx));
+    cx.assert_editor_state("«abˇ»c\n«abˇ»c «abˇ»\ndef«abˇ»c");
+}
+
+#[gpui::test]
+async fn test_select_all_matches_limit(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+    cx.set_state(&format!("ˇa{}", " a".repeat(MAX_SELECT_ALL_MATCHES + 10)));
+
+    cx.update_editor(|e, cx| e.select_all_matches(&SelectAllMatches, cx))
+        .unwrap();
+    cx.update_editor(|e, cx| {
+        let selections = e.selections.all::<usize>(cx);
+        assert_eq!(selections.len(), MAX_SELECT_ALL_MATCHES);
+        assert_eq!(selections[0].range(), 0..1);
+        assert_eq!(selections[1].range(), 2..3);
+    });
+}
+
+#[gpui::test]
+async fn test_select_all_matches_in_large_buffer(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+    let filler = "x".repeat(MAX_SYNC_SELECT_ALL_MATCHES_LEN);
+    cx.set_state(&format!("«abcˇ» {filler} abc"));
+
+    // Large buffers are searched in the background.
+    cx.update_editor(|e, cx| e.select_all_matches(&SelectAllMatches, cx))
+        .unwrap();
+    cx.run_until_parked();
+    cx.update_editor(|e, cx| assert_eq!(e.selections.count(), 2));
+
+    // Matches found after typing started are dropped.
+    let selections = cx.update_editor(|e, cx| {
+        e.change_selections(None, cx, |s| s.select_ranges([0..3]));
+        e.select_all_matches(&SelectAllMatches, cx).unwrap();
+        e.handle_input("d", cx);
+        e.selections.ranges::<usize>(cx)
+    });
+    cx.run_until_parked();
+    cx.update_editor(|e, cx| assert_eq!(e.selections.ranges::<usize>(cx), selections));
 }
 
 #[gpui::test]
@@ -4807,11 +5849,22 @@ async fn test_autoclose_pairs(cx: &mut gpui::TestAppContext) {
     cx.assert_editor_state("{«aˇ»} b");
 
     // Autclose pair where the start and end characters are the same
-    cx.set_state("aˇ");
+    cx.set_state("a ˇ");
     cx.update_editor(|view, cx| view.handle_input("\"", cx));
-    cx.assert_editor_state("a\"ˇ\"");
+    cx.assert_editor_state("a \"ˇ\"");
     cx.update_editor(|view, cx| view.handle_input("\"", cx));
-    cx.assert_editor_state("a\"\"ˇ");
+    cx.assert_editor_state("a \"\"ˇ");
+
+    // Don't autoclose a pair whose start and end characters are the same
+    // right after a word character
+    cx.set_state("aˇ");
+    cx.update_editor(|view, cx| view.handle_input("\"", cx));
+    cx.assert_editor_state("a\"ˇ");
+
+    // Brackets are still closed after a word character
+    cx.set_state("aˇ");
+    cx.update_editor(|view, cx| view.handle_input("(", cx));
+    cx.assert_editor_state("a(ˇ)");
 }
 
 #[gpui::test]
@@ -5781,6 +6834,81 @@ async fn test_snippets(cx: &mut gpui::TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_revert_file(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "file.txt": "one\ntwo\nthree\nfour\nfive\nsix\nseven\neight\nnine\nten",
+        }),
+    )
+    .await;
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/dir/file.txt", cx)
+        })
+        .await
+        .unwrap();
+    let multi_buffer = cx.new_model(|cx| MultiBuffer::singleton(buffer.clone(), cx));
+    let (editor, cx) =
+        cx.add_window_view(|cx| build_editor_with_project(project.clone(), multi_buffer, cx));
+
+    editor.update(cx, |editor, cx| {
+        editor.set_vertical_scroll_margin(0, cx);
+        editor.change_selections(None, cx, |s| {
+            s.select_ranges([Point::new(6, 3)..Point::new(6, 3)])
+        });
+        editor.fold_ranges(
+            [(Point::new(8, 2)..Point::new(9, 1), FoldPlaceholder::test())],
+            false,
+            cx,
+        );
+        editor.set_scroll_position(point(0., 3.), cx);
+    });
+    buffer.update(cx, |buffer, cx| buffer.edit([(0..0, "!")], None, cx));
+    cx.run_until_parked();
+
+    // The file shrinks above the viewport, and the line with the cursor is
+    // replaced.
+    fs.insert_file(
+        "/dir/file.txt",
+        "one\nfour\nfive\nsix\n7\neight\nnine\nten".into(),
+    )
+    .await;
+    cx.run_until_parked();
+    editor.update(cx, |editor, cx| editor.revert_file(&RevertFile, cx));
+    cx.run_until_parked();
+
+    editor.update(cx, |editor, cx| {
+        assert_eq!(editor.text(cx), "one\nfour\nfive\nsix\n7\neight\nnine\nten");
+        assert!(!editor.is_dirty(cx));
+        // The cursor is clamped to the end of the text that replaced its line.
+        assert_eq!(
+            editor.selections.ranges::<Point>(cx),
+            [Point::new(4, 1)..Point::new(4, 1)]
+        );
+        // Folds and the scroll position follow the lines they were on.
+        assert_eq!(
+            editor.display_text(cx),
+            "one\nfour\nfive\nsix\n7\neight\nni⋯en"
+        );
+        assert_eq!(editor.scroll_position(cx), point(0., 1.));
+    });
+
+    // The reload is undone as a single transaction.
+    editor.update(cx, |editor, cx| {
+        editor.undo(&Undo, cx);
+        assert_eq!(
+            editor.text(cx),
+            "!one\ntwo\nthree\nfour\nfive\nsix\nseven\neight\nnine\nten"
+        );
+    });
+}
+
 #[gpui::test]
 async fn test_document_format_during_save(cx: &mut gpui::TestAppContext) {
     init_test(cx, |_| {});
@@ -7200,6 +8328,49 @@ async fn test_toggle_block_comment(cx: &mut gpui::TestAppContext) {
     );
 }
 
+#[gpui::test]
+async fn test_toggle_block_comments_with_line_comments(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
+    let language = Arc::new(Language::new(
+        LanguageConfig {
+            line_comments: vec!["// ".into()],
+            block_comment: Some(("/* ".into(), " */".into())),
+            ..Default::default()
+        },
+        Some(tree_sitter_rust::language()),
+    ));
+    cx.update_buffer(|buffer, cx| buffer.set_language(Some(language), cx));
+
+    cx.set_state(indoc! {"
+        fn a() {
+            b();ˇ
+        }
+    "});
+
+    // Block comments are used even though the language has line comments.
+    cx.update_editor(|e, cx| e.toggle_block_comments(&ToggleBlockComments, cx));
+    cx.assert_editor_state(indoc! {"
+        fn a() {
+            /* b();ˇ */
+        }
+    "});
+
+    cx.update_editor(|e, cx| e.toggle_block_comments(&ToggleBlockComments, cx));
+    cx.assert_editor_state(indoc! {"
+        fn a() {
+            b();ˇ
+        }
+    "});
+
+    cx.update_editor(|e, cx| e.toggle_comments(&ToggleComments::default(), cx));
+    cx.assert_editor_state(indoc! {"
+        fn a() {
+            // b();ˇ
+        }
+    "});
+}
+
 #[gpui::test]
 fn test_editing_disjoint_excerpts(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
@@ -7643,6 +8814,358 @@ fn test_highlighted_ranges(cx: &mut TestAppContext) {
     });
 }
 
+#[gpui::test]
+fn test_overlapping_highlighted_ranges(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let editor = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple(&sample_text(6, 8, 'a'), cx);
+        build_editor(buffer.clone(), cx)
+    });
+
+    _ = editor.update(cx, |editor, cx| {
+        struct Type1;
+        struct Type2;
+
+        let buffer = editor.buffer.read(cx).snapshot(cx);
+        let anchor_range =
+            |range: Range<Point>| buffer.anchor_after(range.start)..buffer.anchor_after(range.end);
+
+        // Overlapping ranges of the same highlight are merged.
+        editor.highlight_background::<Type1>(
+            &[
+                anchor_range(Point::new(1, 1)..Point::new(1, 4)),
+                anchor_range(Point::new(1, 2)..Point::new(2, 3)),
+                anchor_range(Point::new(2, 3)..Point::new(2, 5)),
+            ],
+            |_| Hsla::red(),
+            cx,
+        );
+        // Ranges of different highlights are kept apart even where they overlap.
+        editor.highlight_background::<Type2>(
+            &[anchor_range(Point::new(1, 3)..Point::new(2, 1))],
+            |_| Hsla::green(),
+            cx,
+        );
+
+        let snapshot = editor.snapshot(cx);
+        let mut highlighted_ranges = editor.background_highlights_in_range(
+            anchor_range(Point::new(0, 0)..Point::new(5, 0)),
+            &snapshot,
+            cx.theme().colors(),
+        );
+        highlighted_ranges.sort_unstable_by_key(|(range, color)| (*color, range.start));
+        assert_eq!(
+            highlighted_ranges,
+            &[
+                (
+                    DisplayPoint::new(DisplayRow(1), 1)..DisplayPoint::new(DisplayRow(2), 3),
+                    Hsla::red(),
+                ),
+                (
+                    DisplayPoint::new(DisplayRow(2), 3)..DisplayPoint::new(DisplayRow(2), 5),
+                    Hsla::red(),
+                ),
+                (
+                    DisplayPoint::new(DisplayRow(1), 3)..DisplayPoint::new(DisplayRow(2), 1),
+                    Hsla::green(),
+                ),
+            ]
+        );
+
+        editor.clear_background_highlights::<Type1>(cx);
+        assert_eq!(
+            editor.background_highlights_in_range(
+                anchor_range(Point::new(0, 0)..Point::new(5, 0)),
+                &snapshot,
+                cx.theme().colors(),
+            ),
+            &[(
+                DisplayPoint::new(DisplayRow(1), 3)..DisplayPoint::new(DisplayRow(2), 1),
+                Hsla::green(),
+            )]
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_read_only_file(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/a", json!({ "protected.txt": "one two" }))
+        .await;
+    fs.set_read_only("/a/protected.txt", true);
+
+    let project = Project::test(fs.clone(), ["/a".as_ref()], cx).await;
+    let workspace = cx.add_window(|cx| Workspace::test_new(project.clone(), cx));
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+    let worktree_id = project.update(cx, |project, cx| {
+        project.worktrees().next().unwrap().read(cx).id()
+    });
+    let editor = workspace
+        .update(cx, |workspace, cx| {
+            workspace.open_path((worktree_id, "protected.txt"), None, true, cx)
+        })
+        .unwrap()
+        .await
+        .unwrap()
+        .downcast::<Editor>()
+        .unwrap();
+
+    // Edits are rejected with a notification, while selections still work.
+    editor.update(cx, |editor, cx| {
+        assert!(editor.read_only(cx));
+        editor.select_all(&SelectAll, cx);
+        editor.handle_input("x", cx);
+        editor.backspace(&Backspace, cx);
+        assert_eq!(editor.text(cx), "one two");
+        assert_eq!(editor.selections.ranges::<usize>(cx), [0..7]);
+    });
+    _ = workspace.update(cx, |workspace, _| {
+        assert!(!workspace.notification_ids().is_empty())
+    });
+
+    // The file can't be made writable until its permissions allow it.
+    editor.update(cx, |editor, cx| {
+        editor.toggle_read_only(&ToggleReadOnly, cx)
+    });
+    cx.run_until_parked();
+    editor.update(cx, |editor, cx| assert!(editor.read_only(cx)));
+
+    fs.set_read_only("/a/protected.txt", false);
+    editor.update(cx, |editor, cx| {
+        editor.toggle_read_only(&ToggleReadOnly, cx)
+    });
+    cx.run_until_parked();
+    editor.update(cx, |editor, cx| {
+        assert!(!editor.read_only(cx));
+        editor.handle_input("x", cx);
+        assert_eq!(editor.text(cx), "x");
+
+        editor.toggle_read_only(&ToggleReadOnly, cx);
+        assert!(editor.read_only(cx));
+        editor.handle_input("y", cx);
+        assert_eq!(editor.text(cx), "x");
+    });
+}
+
+#[gpui::test]
+async fn test_open_path_at(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/a", json!({ "one.txt": "one\ntwo\nthree" }))
+        .await;
+    fs.insert_tree("/b", json!({ "outside.txt": "outside" }))
+        .await;
+
+    let project = Project::test(fs.clone(), ["/a".as_ref()], cx).await;
+    let workspace = cx.add_window(|cx| Workspace::test_new(project.clone(), cx));
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+
+    // A file that isn't open yet gets opened with its cursor at the position.
+    let item = workspace
+        .update(cx, |workspace, cx| {
+            workspace.open_path_at(
+                "/a/one.txt".into(),
+                PointOrAnchor::Point(Point::new(1, 1)),
+                true,
+                cx,
+            )
+        })
+        .unwrap()
+        .await
+        .unwrap();
+    let editor = item.downcast::<Editor>().unwrap();
+    editor.update(cx, |editor, cx| {
+        assert_eq!(
+            editor.selections.ranges::<Point>(cx),
+            [Point::new(1, 1)..Point::new(1, 1)]
+        );
+    });
+
+    // A file that's open already is activated, with its position clamped to the buffer.
+    let item = workspace
+        .update(cx, |workspace, cx| {
+            workspace.open_path_at(
+                "/a/one.txt".into(),
+                PointOrAnchor::Point(Point::new(10, 10)),
+                true,
+                cx,
+            )
+        })
+        .unwrap()
+        .await
+        .unwrap();
+    assert_eq!(item.item_id(), editor.item_id());
+    _ = workspace.update(cx, |workspace, cx| {
+        assert_eq!(workspace.active_pane().read(cx).items_len(), 1);
+    });
+    editor.update(cx, |editor, cx| {
+        assert_eq!(
+            editor.selections.ranges::<Point>(cx),
+            [Point::new(2, 5)..Point::new(2, 5)]
+        );
+    });
+
+    // Anchors are resolved in the buffer they were created in.
+    let anchor = editor.update(cx, |editor, cx| {
+        let buffer = editor.buffer().read(cx).as_singleton().unwrap();
+        buffer.read(cx).anchor_before(Point::new(0, 2))
+    });
+    workspace
+        .update(cx, |workspace, cx| {
+            workspace.open_path_at("/a/one.txt".into(), PointOrAnchor::Anchor(anchor), true, cx)
+        })
+        .unwrap()
+        .await
+        .unwrap();
+    editor.update(cx, |editor, cx| {
+        assert_eq!(
+            editor.selections.ranges::<Point>(cx),
+            [Point::new(0, 2)..Point::new(0, 2)]
+        );
+    });
+
+    // Each jump is recorded, so navigating back returns to where the cursor was.
+    _ = workspace.update(cx, |workspace, cx| {
+        workspace
+            .go_back(workspace.active_pane().downgrade(), cx)
+            .detach_and_log_err(cx);
+    });
+    cx.run_until_parked();
+    editor.update(cx, |editor, cx| {
+        assert_eq!(
+            editor.selections.ranges::<Point>(cx),
+            [Point::new(2, 5)..Point::new(2, 5)]
+        );
+    });
+
+    // Files outside of the worktrees get opened too.
+    let item = workspace
+        .update(cx, |workspace, cx| {
+            workspace.open_path_at(
+                "/b/outside.txt".into(),
+                PointOrAnchor::Point(Point::new(0, 3)),
+                true,
+                cx,
+            )
+        })
+        .unwrap()
+        .await
+        .unwrap();
+    let outside_editor = item.downcast::<Editor>().unwrap();
+    outside_editor.update(cx, |editor, cx| {
+        assert_eq!(editor.text(cx), "outside");
+        assert_eq!(
+            editor.selections.ranges::<Point>(cx),
+            [Point::new(0, 3)..Point::new(0, 3)]
+        );
+    });
+
+    // Missing files and directories aren't opened.
+    let missing = workspace
+        .update(cx, |workspace, cx| {
+            workspace.open_path_at(
+                "/a/missing.txt".into(),
+                PointOrAnchor::Point(Point::new(0, 0)),
+                true,
+                cx,
+            )
+        })
+        .unwrap()
+        .await;
+    assert!(missing.unwrap_err().to_string().contains("does not exist"));
+    let directory = workspace
+        .update(cx, |workspace, cx| {
+            workspace.open_path_at(
+                "/b".into(),
+                PointOrAnchor::Point(Point::new(0, 0)),
+                true,
+                cx,
+            )
+        })
+        .unwrap()
+        .await;
+    assert!(directory
+        .unwrap_err()
+        .to_string()
+        .contains("is a directory"));
+    _ = workspace.update(cx, |workspace, cx| {
+        assert_eq!(workspace.active_pane().read(cx).items_len(), 2);
+    });
+}
+
+#[gpui::test]
+async fn test_copy_file_paths(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/a", json!({ "src": { "main.rs": "one\ntwo\nthree" } }))
+        .await;
+    fs.insert_tree("/b", json!({ "lib.rs": "" })).await;
+
+    let project = Project::test(fs, ["/a".as_ref()], cx).await;
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/a/src/main.rs", cx)
+        })
+        .await
+        .unwrap();
+    let editor = cx.add_window(|cx| {
+        let mut editor = Editor::for_buffer(buffer, Some(project.clone()), cx);
+        editor.change_selections(None, cx, |s| {
+            s.select_ranges([Point::new(1, 2)..Point::new(1, 2)])
+        });
+        editor
+    });
+
+    _ = editor.update(cx, |editor, cx| {
+        editor.copy_relative_path(&CopyRelativePath, cx);
+        assert_eq!(
+            cx.read_from_clipboard().map(|item| item.text().to_owned()),
+            Some("src/main.rs".into())
+        );
+        editor.copy_file_location(&CopyFileLocation, cx);
+        assert_eq!(
+            cx.read_from_clipboard().map(|item| item.text().to_owned()),
+            Some("src/main.rs:2".into())
+        );
+        editor.copy_path(&CopyPath, cx);
+        assert_eq!(
+            cx.read_from_clipboard().map(|item| item.text().to_owned()),
+            Some("/a/src/main.rs".into())
+        );
+    });
+
+    // With several worktrees, relative paths start with the root's name.
+    project
+        .update(cx, |project, cx| {
+            project.find_or_create_local_worktree("/b", true, cx)
+        })
+        .await
+        .unwrap();
+    _ = editor.update(cx, |editor, cx| {
+        editor.copy_file_location(&CopyFileLocation, cx);
+        assert_eq!(
+            cx.read_from_clipboard().map(|item| item.text().to_owned()),
+            Some("a/src/main.rs:2".into())
+        );
+    });
+
+    // Editors without a file don't copy anything.
+    let untitled = cx.add_window(|cx| Editor::single_line(cx));
+    _ = untitled.update(cx, |editor, cx| {
+        assert!(editor.target_file(cx).is_none());
+        editor.copy_relative_path(&CopyRelativePath, cx);
+        assert_eq!(
+            cx.read_from_clipboard().map(|item| item.text().to_owned()),
+            Some("a/src/main.rs:2".into())
+        );
+    });
+}
+
 #[gpui::test]
 async fn test_following(cx: &mut gpui::TestAppContext) {
     init_test(cx, |_| {});
@@ -8278,6 +9801,133 @@ async fn test_move_to_enclosing_bracket(cx: &mut gpui::TestAppContext) {
     );
 }
 
+#[gpui::test]
+async fn test_move_to_bracket_scope(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorLspTestContext::new_typescript(Default::default(), cx).await;
+
+    cx.set_state("function a() { if (b) { cˇ(); } }");
+    cx.update_editor(|editor, cx| {
+        editor.move_to_start_of_bracket_scope(&MoveToStartOfBracketScope, cx)
+    });
+    cx.assert_editor_state("function a() { if (b) {ˇ c(); } }");
+    // Repeating the action moves out to the next enclosing scope.
+    cx.update_editor(|editor, cx| {
+        editor.move_to_start_of_bracket_scope(&MoveToStartOfBracketScope, cx)
+    });
+    cx.assert_editor_state("function a() {ˇ if (b) { c(); } }");
+    cx.update_editor(|editor, cx| {
+        editor.move_to_start_of_bracket_scope(&MoveToStartOfBracketScope, cx)
+    });
+    cx.assert_editor_state("function a() {ˇ if (b) { c(); } }");
+
+    cx.set_state("function a() { if (b) { cˇ(); } }");
+    cx.update_editor(|editor, cx| {
+        editor.move_to_end_of_bracket_scope(&MoveToEndOfBracketScope, cx)
+    });
+    cx.assert_editor_state("function a() { if (b) { c(); ˇ} }");
+    cx.update_editor(|editor, cx| {
+        editor.move_to_end_of_bracket_scope(&MoveToEndOfBracketScope, cx)
+    });
+    cx.assert_editor_state("function a() { if (b) { c(); } ˇ}");
+    cx.update_editor(|editor, cx| {
+        editor.move_to_end_of_bracket_scope(&MoveToEndOfBracketScope, cx)
+    });
+    cx.assert_editor_state("function a() { if (b) { c(); } ˇ}");
+
+    // Each cursor moves within its own scope.
+    cx.set_state("foo(aˇ, [bˇ]);");
+    cx.update_editor(|editor, cx| {
+        editor.move_to_start_of_bracket_scope(&MoveToStartOfBracketScope, cx)
+    });
+    cx.assert_editor_state("foo(ˇa, [ˇb]);");
+}
+
+#[gpui::test]
+async fn test_expand_and_shrink_selection(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+    cx.update_buffer(|buffer, cx| buffer.set_language(Some(rust_lang()), cx));
+    let states = [
+        "fn a() { let b = c(ˇd); }",
+        "fn a() { let b = c(«dˇ»); }",
+        "fn a() { let b = c«(d)ˇ»; }",
+        "fn a() { let b = «c(d)ˇ»; }",
+        "fn a() { «let b = c(d);ˇ» }",
+        "fn a() «{ let b = c(d); }ˇ»",
+        "«fn a() { let b = c(d); }ˇ»",
+    ];
+
+    cx.set_state(states[0]);
+    for state in &states[1..] {
+        cx.update_editor(|editor, cx| editor.expand_selection(&ExpandSelection, cx));
+        cx.assert_editor_state(state);
+    }
+    cx.update_editor(|editor, cx| editor.expand_selection(&ExpandSelection, cx));
+    cx.assert_editor_state(states[states.len() - 1]);
+
+    for state in states[..states.len() - 1].iter().rev() {
+        cx.update_editor(|editor, cx| editor.shrink_selection(&ShrinkSelection, cx));
+        cx.assert_editor_state(state);
+    }
+
+    // Each selection expands within its own subtree.
+    cx.set_state("fn a() { ˇb(1); c(ˇ2); }");
+    cx.update_editor(|editor, cx| editor.expand_selection(&ExpandSelection, cx));
+    cx.assert_editor_state("fn a() { «bˇ»(1); c(«2ˇ»); }");
+    cx.update_editor(|editor, cx| editor.expand_selection(&ExpandSelection, cx));
+    cx.assert_editor_state("fn a() { «b(1)ˇ»; c«(2)ˇ»; }");
+    cx.update_editor(|editor, cx| editor.expand_selection(&ExpandSelection, cx));
+    cx.assert_editor_state("fn a() { «b(1);ˇ» «c(2)ˇ»; }");
+
+    // Shrinking retraces the expansions even after edits moved the selections.
+    cx.update_buffer(|buffer, cx| buffer.edit([(0..0, "// x\n")], None, cx));
+    cx.update_editor(|editor, cx| editor.shrink_selection(&ShrinkSelection, cx));
+    cx.assert_editor_state("// x\nfn a() { «b(1)ˇ»; c«(2)ˇ»; }");
+    cx.update_editor(|editor, cx| editor.shrink_selection(&ShrinkSelection, cx));
+    cx.assert_editor_state("// x\nfn a() { «bˇ»(1); c(«2ˇ»); }");
+}
+
+#[gpui::test]
+async fn test_expand_and_shrink_selection_without_grammar(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+    let states = [
+        "let a = foo(barˇ, baz);\nlet b = 1;",
+        "let a = foo(«barˇ», baz);\nlet b = 1;",
+        "let a = foo(«bar, bazˇ»);\nlet b = 1;",
+        "let a = foo«(bar, baz)ˇ»;\nlet b = 1;",
+        "«let a = foo(bar, baz);ˇ»\nlet b = 1;",
+        "«let a = foo(bar, baz);\nlet b = 1;ˇ»",
+    ];
+
+    cx.set_state(states[0]);
+    for state in &states[1..] {
+        cx.update_editor(|editor, cx| editor.expand_selection(&ExpandSelection, cx));
+        cx.assert_editor_state(state);
+    }
+    cx.update_editor(|editor, cx| editor.expand_selection(&ExpandSelection, cx));
+    cx.assert_editor_state(states[states.len() - 1]);
+
+    for state in states[..states.len() - 1].iter().rev() {
+        cx.update_editor(|editor, cx| editor.shrink_selection(&ShrinkSelection, cx));
+        cx.assert_editor_state(state);
+    }
+    cx.update_editor(|editor, cx| editor.shrink_selection(&ShrinkSelection, cx));
+    cx.assert_editor_state(states[0]);
+
+    // Changing the selections in any other way forgets how they were expanded.
+    cx.update_editor(|editor, cx| {
+        editor.expand_selection(&ExpandSelection, cx);
+        editor.move_right(&MoveRight, cx);
+        editor.shrink_selection(&ShrinkSelection, cx);
+    });
+    cx.assert_editor_state("let a = foo(barˇ, baz);\nlet b = 1;");
+}
+
 #[gpui::test]
 async fn test_on_type_formatting_not_triggered(cx: &mut gpui::TestAppContext) {
     init_test(cx, |_| {});
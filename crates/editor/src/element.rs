@@ -1,7 +1,8 @@
 use crate::{
     blame_entry_tooltip::{blame_entry_relative_timestamp, BlameEntryTooltip},
     display_map::{
-        BlockContext, BlockStyle, DisplaySnapshot, HighlightedChunk, ToDisplayPoint, TransformBlock,
+        BlockContext, BlockStyle, DisplaySnapshot, HighlightedChunk, InvisibleKind, ToDisplayPoint,
+        TransformBlock,
     },
     editor_settings::{
         CurrentLineHighlight, DoubleClickInMultibuffer, MultiCursorModifier, ShowScrollbar,
@@ -177,8 +178,12 @@ impl EditorElement {
         register_action(view, cx, Editor::tab_prev);
         register_action(view, cx, Editor::indent);
         register_action(view, cx, Editor::outdent);
+        register_action(view, cx, Editor::convert_indentation_to_spaces);
+        register_action(view, cx, Editor::convert_indentation_to_tabs);
         register_action(view, cx, Editor::delete_line);
         register_action(view, cx, Editor::join_lines);
+        register_action(view, cx, Editor::join_lines_without_space);
+        register_action(view, cx, Editor::sort_lines);
         register_action(view, cx, Editor::sort_lines_case_sensitive);
         register_action(view, cx, Editor::sort_lines_case_insensitive);
         register_action(view, cx, Editor::reverse_lines);
@@ -200,12 +205,14 @@ impl EditorElement {
         register_action(view, cx, Editor::cut_to_end_of_line);
         register_action(view, cx, Editor::duplicate_line_up);
         register_action(view, cx, Editor::duplicate_line_down);
+        register_action(view, cx, Editor::duplicate_selection);
         register_action(view, cx, Editor::move_line_up);
         register_action(view, cx, Editor::move_line_down);
         register_action(view, cx, Editor::transpose);
         register_action(view, cx, Editor::cut);
         register_action(view, cx, Editor::copy);
         register_action(view, cx, Editor::paste);
+        register_action(view, cx, Editor::paste_and_match_indentation);
         register_action(view, cx, Editor::undo);
         register_action(view, cx, Editor::redo);
         register_action(view, cx, Editor::move_page_up);
@@ -262,6 +269,11 @@ impl EditorElement {
         });
         register_action(view, cx, Editor::select_line);
         register_action(view, cx, Editor::split_selection_into_lines);
+        register_action(view, cx, Editor::set_mark);
+        register_action(view, cx, Editor::jump_to_mark);
+        register_action(view, cx, Editor::clear_marks);
+        register_action(view, cx, Editor::correct_spelling);
+        register_action(view, cx, Editor::add_word_to_dictionary);
         register_action(view, cx, Editor::add_selection_above);
         register_action(view, cx, Editor::add_selection_below);
         register_action(view, cx, |editor, action, cx| {
@@ -271,9 +283,14 @@ impl EditorElement {
             editor.select_previous(action, cx).log_err();
         });
         register_action(view, cx, Editor::toggle_comments);
+        register_action(view, cx, Editor::toggle_block_comments);
         register_action(view, cx, Editor::select_larger_syntax_node);
         register_action(view, cx, Editor::select_smaller_syntax_node);
         register_action(view, cx, Editor::move_to_enclosing_bracket);
+        register_action(view, cx, Editor::move_to_start_of_bracket_scope);
+        register_action(view, cx, Editor::move_to_end_of_bracket_scope);
+        register_action(view, cx, Editor::expand_selection);
+        register_action(view, cx, Editor::shrink_selection);
         register_action(view, cx, Editor::undo_selection);
         register_action(view, cx, Editor::redo_selection);
         if !view.read(cx).is_singleton(cx) {
@@ -309,8 +326,11 @@ impl EditorElement {
         });
         register_action(view, cx, Editor::open_url);
         register_action(view, cx, Editor::fold);
+        register_action(view, cx, Editor::fold_all);
         register_action(view, cx, Editor::fold_at);
+        register_action(view, cx, Editor::fold_at_level);
         register_action(view, cx, Editor::unfold_lines);
+        register_action(view, cx, Editor::unfold_all);
         register_action(view, cx, Editor::unfold_at);
         register_action(view, cx, Editor::fold_selected_ranges);
         register_action(view, cx, Editor::show_completions);
@@ -320,12 +340,17 @@ impl EditorElement {
         register_action(view, cx, Editor::toggle_soft_wrap);
         register_action(view, cx, Editor::toggle_tab_bar);
         register_action(view, cx, Editor::toggle_line_numbers);
+        register_action(view, cx, Editor::toggle_read_only);
         register_action(view, cx, Editor::toggle_indent_guides);
         register_action(view, cx, Editor::toggle_inlay_hints);
         register_action(view, cx, hover_popover::hover);
-        register_action(view, cx, Editor::reveal_in_finder);
-        register_action(view, cx, Editor::copy_path);
-        register_action(view, cx, Editor::copy_relative_path);
+        if view.read(cx).target_file(cx).is_some() {
+            register_action(view, cx, Editor::reveal_in_finder);
+            register_action(view, cx, Editor::open_in_default_app);
+            register_action(view, cx, Editor::copy_path);
+            register_action(view, cx, Editor::copy_relative_path);
+            register_action(view, cx, Editor::copy_file_location);
+        }
         register_action(view, cx, Editor::copy_highlight_json);
         register_action(view, cx, Editor::copy_permalink_to_line);
         register_action(view, cx, Editor::open_permalink_to_line);
@@ -390,6 +415,7 @@ impl EditorElement {
         register_action(view, cx, Editor::accept_partial_inline_completion);
         register_action(view, cx, Editor::accept_inline_completion);
         register_action(view, cx, Editor::revert_selected_hunks);
+        register_action(view, cx, Editor::revert_file);
         register_action(view, cx, Editor::open_active_item_in_terminal)
     }
 
@@ -1230,7 +1256,7 @@ impl EditorElement {
             .map(|hunk| diff_hunk_to_display(&hunk, snapshot))
             .dedup()
             .map(|hunk| match git_gutter_setting {
-                GitGutterSetting::TrackedFiles => {
+                GitGutterSetting::TrackedFiles | GitGutterSetting::SavedFile => {
                     let hitbox = if let DisplayDiffHunk::Unfolded {
                         display_row_range, ..
                     } = &hunk
@@ -1620,6 +1646,13 @@ impl EditorElement {
         }
     }
 
+    /// Computes the numbers shown in the gutter for the given rows when line numbers are
+    /// relative to the `relative_to` row. Each number is the distance in buffer lines from
+    /// the cursor's line, so soft-wrapped continuations of a line (which show no number)
+    /// don't count and every line hidden in a fold does. The cursor's line is left out,
+    /// as it shows its absolute number.
+    ///
+    /// Only the given rows are visited, no matter how far the cursor is scrolled away.
     fn calculate_relative_line_numbers(
         &self,
         snapshot: &EditorSnapshot,
@@ -1631,39 +1664,22 @@ impl EditorElement {
             return relative_rows;
         };
 
-        let start = rows.start.min(relative_to);
-        let end = rows.end.max(relative_to);
-
-        let buffer_rows = snapshot
-            .buffer_rows(start)
-            .take(1 + end.minus(start) as usize)
-            .collect::<Vec<_>>();
-
-        let head_idx = relative_to.minus(start);
-        let mut delta = 1;
-        let mut i = head_idx + 1;
-        while i < buffer_rows.len() as u32 {
-            if buffer_rows[i as usize].is_some() {
-                if rows.contains(&DisplayRow(i + start.0)) {
-                    relative_rows.insert(DisplayRow(i + start.0), delta);
-                }
-                delta += 1;
-            }
-            i += 1;
-        }
-        delta = 1;
-        i = head_idx.min(buffer_rows.len() as u32 - 1);
-        while i > 0 && buffer_rows[i as usize].is_none() {
-            i -= 1;
-        }
-
-        while i > 0 {
-            i -= 1;
-            if buffer_rows[i as usize].is_some() {
-                if rows.contains(&DisplayRow(i + start.0)) {
-                    relative_rows.insert(DisplayRow(i + start.0), delta);
+        let head_row = snapshot
+            .clip_point(DisplayPoint::new(relative_to, 0), Bias::Left)
+            .to_point(&snapshot.display_snapshot)
+            .row;
+        for (ix, buffer_row) in snapshot
+            .buffer_rows(rows.start)
+            .take(rows.len())
+            .enumerate()
+        {
+            if let Some(buffer_row) = buffer_row {
+                if buffer_row.0 != head_row {
+                    relative_rows.insert(
+                        DisplayRow(rows.start.0 + ix as u32),
+                        buffer_row.0.abs_diff(head_row),
+                    );
                 }
-                delta += 1;
             }
         }
 
@@ -1840,9 +1856,27 @@ impl EditorElement {
                     len: line.len,
                     fragments: smallvec![LineFragment::Text(line)],
                     invisibles: Vec::new(),
+                    trailing_whitespace_start: 0,
                     font_size,
                 })
                 .collect()
+        } else if snapshot.has_long_lines() {
+            // Only read as much of each row as gets rendered.
+            let chunks = snapshot.truncated_highlighted_chunks(
+                rows.clone(),
+                MAX_LINE_LEN,
+                true,
+                &self.style,
+            );
+            LineWithInvisibles::from_chunks(
+                chunks,
+                &self.style.text,
+                MAX_LINE_LEN,
+                rows.len(),
+                line_number_layouts,
+                snapshot.mode,
+                cx,
+            )
         } else {
             let chunks = snapshot.highlighted_chunks(rows.clone(), true, &self.style);
             LineWithInvisibles::from_chunks(
@@ -2830,7 +2864,7 @@ impl EditorElement {
             .unwrap_or_else(|| {
                 matches!(
                     ProjectSettings::get_global(cx).git.git_gutter,
-                    Some(GitGutterSetting::TrackedFiles)
+                    Some(GitGutterSetting::TrackedFiles | GitGutterSetting::SavedFile)
                 )
             });
         if show_git_gutter {
@@ -3937,6 +3971,8 @@ fn deploy_blame_entry_context_menu(
 pub(crate) struct LineWithInvisibles {
     fragments: SmallVec<[LineFragment; 1]>,
     invisibles: Vec<Invisible>,
+    /// The offset at which the whitespace at the end of the line starts.
+    trailing_whitespace_start: usize,
     len: usize,
     width: Pixels,
     font_size: Pixels,
@@ -3979,6 +4015,7 @@ impl LineWithInvisibles {
         let mut fragments: SmallVec<[LineFragment; 1]> = SmallVec::new();
         let mut line = String::new();
         let mut invisibles = Vec::new();
+        let mut trailing_whitespace_start = 0;
         let mut width = Pixels::ZERO;
         let mut len = 0;
         let mut styles = Vec::new();
@@ -4056,6 +4093,7 @@ impl LineWithInvisibles {
                             len: mem::take(&mut len),
                             fragments: mem::take(&mut fragments),
                             invisibles: std::mem::take(&mut invisibles),
+                            trailing_whitespace_start: mem::take(&mut trailing_whitespace_start),
                             font_size,
                         });
 
@@ -4109,21 +4147,27 @@ impl LineWithInvisibles {
                                     });
                                 }
                             } else {
-                                invisibles.extend(
-                                    line_chunk
-                                        .bytes()
-                                        .enumerate()
-                                        .filter(|(_, line_byte)| {
-                                            let is_whitespace =
-                                                (*line_byte as char).is_whitespace();
-                                            non_whitespace_added |= !is_whitespace;
-                                            is_whitespace
-                                                && (non_whitespace_added || !inside_wrapped_string)
-                                        })
-                                        .map(|(whitespace_index, _)| Invisible::Whitespace {
-                                            line_offset: line.len() + whitespace_index,
-                                        }),
-                                )
+                                for (ix, c) in line_chunk.char_indices() {
+                                    let line_offset = line.len() + ix;
+                                    let Some(kind) = InvisibleKind::for_char(c) else {
+                                        non_whitespace_added = true;
+                                        trailing_whitespace_start = line_offset + c.len_utf8();
+                                        continue;
+                                    };
+                                    if !non_whitespace_added && inside_wrapped_string {
+                                        continue;
+                                    }
+                                    invisibles.push(match kind {
+                                        InvisibleKind::Space => {
+                                            Invisible::Whitespace { line_offset }
+                                        }
+                                        kind => Invisible::Unusual {
+                                            line_offset,
+                                            len: c.len_utf8(),
+                                            kind,
+                                        },
+                                    });
+                                }
                             }
                         }
 
@@ -4230,6 +4274,13 @@ impl LineWithInvisibles {
                 Invisible::Whitespace { line_offset } => {
                     (*line_offset, line_offset + 1, &layout.space_invisible)
                 }
+                Invisible::Unusual {
+                    line_offset, len, ..
+                } => (
+                    *line_offset,
+                    line_offset + len,
+                    &layout.unusual_space_invisible,
+                ),
             };
 
             let x_offset = self.x_for_index(token_offset);
@@ -4249,10 +4300,29 @@ impl LineWithInvisibles {
             )
         };
 
-        let invisible_iter = self.invisibles.iter().map(extract_whitespace_info);
+        if whitespace_setting == ShowWhitespaceSetting::None {
+            return;
+        }
+
+        // Unusual characters are easy to miss and cause subtle bugs, so they're always shown.
+        let (unusual, whitespace): (Vec<_>, Vec<_>) = self
+            .invisibles
+            .iter()
+            .partition(|invisible| matches!(invisible, Invisible::Unusual { .. }));
+        for invisible in unusual {
+            let (_, paint) = extract_whitespace_info(invisible);
+            paint(cx);
+        }
+
+        let invisible_iter = whitespace.iter().copied().map(extract_whitespace_info);
         match whitespace_setting {
-            ShowWhitespaceSetting::None => return,
+            ShowWhitespaceSetting::None => {}
             ShowWhitespaceSetting::All => invisible_iter.for_each(|(_, paint)| paint(cx)),
+            ShowWhitespaceSetting::Trailing => invisible_iter.for_each(|([start, _], paint)| {
+                if start >= self.trailing_whitespace_start {
+                    paint(cx);
+                }
+            }),
             ShowWhitespaceSetting::Selection => invisible_iter.for_each(|([start, _], paint)| {
                 let invisible_point = DisplayPoint::new(row, start as u32);
                 if !selection_ranges
@@ -4274,9 +4344,7 @@ impl LineWithInvisibles {
                 // the above cases.
                 // Note: We zip in the original `invisibles` to check for tab equality
                 let mut last_seen: Option<(bool, usize, Box<dyn Fn(&mut WindowContext)>)> = None;
-                for (([start, end], paint), invisible) in
-                    invisible_iter.zip_eq(self.invisibles.iter())
-                {
+                for (([start, end], paint), invisible) in invisible_iter.zip_eq(whitespace.iter()) {
                     let should_render = match (&last_seen, invisible) {
                         (_, Invisible::Tab { .. }) => true,
                         (Some((_, last_end, _)), _) => *last_end == start,
@@ -4409,6 +4477,12 @@ enum Invisible {
     Whitespace {
         line_offset: usize,
     },
+    /// A space-like or zero-width character that is drawn as a warning.
+    Unusual {
+        line_offset: usize,
+        len: usize,
+        kind: InvisibleKind,
+    },
 }
 
 impl EditorElement {
@@ -4810,9 +4884,14 @@ impl Element for EditorElement {
                         cx,
                     );
 
+                    let max_scroll_top = self.editor.read(cx).max_scroll_top(
+                        &snapshot.display_snapshot,
+                        height_in_lines,
+                        cx,
+                    );
                     let scroll_max = point(
                         ((scroll_width - text_hitbox.size.width) / em_width).max(0.0),
-                        max_row.as_f32(),
+                        max_scroll_top,
                     );
 
                     self.editor.update(cx, |editor, cx| {
@@ -5005,6 +5084,21 @@ impl Element for EditorElement {
                             }],
                         )
                         .unwrap();
+                    let unusual_space_invisible = cx
+                        .text_system()
+                        .shape_line(
+                            "•".into(),
+                            invisible_symbol_font_size,
+                            &[TextRun {
+                                len: "•".len(),
+                                font: self.style.text.font(),
+                                color: cx.theme().status().warning,
+                                background_color: None,
+                                underline: None,
+                                strikethrough: None,
+                            }],
+                        )
+                        .unwrap();
 
                     EditorLayout {
                         mode: snapshot.mode,
@@ -5048,6 +5142,7 @@ impl Element for EditorElement {
                         flap_trailers,
                         tab_invisible,
                         space_invisible,
+                        unusual_space_invisible,
                     }
                 })
             })
@@ -5173,6 +5268,7 @@ pub struct EditorLayout {
     mouse_context_menu: Option<AnyElement>,
     tab_invisible: ShapedLine,
     space_invisible: ShapedLine,
+    unusual_space_invisible: ShapedLine,
 }
 
 impl EditorLayout {
@@ -5380,7 +5476,8 @@ fn layout_line(
     style: &EditorStyle,
     cx: &mut WindowContext,
 ) -> LineWithInvisibles {
-    let chunks = snapshot.highlighted_chunks(row..row + DisplayRow(1), true, style);
+    let chunks =
+        snapshot.truncated_highlighted_chunks(row..row + DisplayRow(1), MAX_LINE_LEN, true, style);
     LineWithInvisibles::from_chunks(chunks, &style.text, MAX_LINE_LEN, 1, &[], snapshot.mode, cx)
         .pop()
         .unwrap()
@@ -5671,7 +5768,7 @@ mod tests {
     use crate::{
         display_map::{BlockDisposition, BlockProperties},
         editor_tests::{init_test, update_test_language_settings},
-        Editor, MultiBuffer,
+        Editor, FoldPlaceholder, MultiBuffer,
     };
     use gpui::{TestAppContext, VisualTestContext};
     use language::language_settings;
@@ -5757,6 +5854,68 @@ mod tests {
         assert_eq!(relative_rows[&DisplayRow(2)], 3);
     }
 
+    #[gpui::test]
+    fn test_relative_line_numbers_with_soft_wraps_and_folds(cx: &mut TestAppContext) {
+        init_test(cx, |_| {});
+        let window = cx.add_window(|cx| {
+            let buffer =
+                MultiBuffer::build_simple("thequickbrownfox\njumpedoverthelazydogs\nc\nd\ne", cx);
+            Editor::new(EditorMode::Full, buffer, None, true, cx)
+        });
+
+        let editor = window.root(cx).unwrap();
+        let style = cx.update(|cx| editor.read(cx).style().unwrap().clone());
+        let element = EditorElement::new(&editor, style);
+        let relative_rows = |snapshot: &EditorSnapshot, relative_to: u32| {
+            let mut relative_rows = element
+                .calculate_relative_line_numbers(
+                    snapshot,
+                    &(DisplayRow(0)..snapshot.max_point().row().next_row()),
+                    Some(DisplayRow(relative_to)),
+                )
+                .into_iter()
+                .map(|(row, delta)| (row.0, delta))
+                .collect::<Vec<_>>();
+            relative_rows.sort();
+            relative_rows
+        };
+
+        window
+            .update(cx, |editor, cx| {
+                editor.set_wrap_width(Some(140.0.into()), cx);
+                assert_eq!(
+                    editor.display_text(cx),
+                    "thequickbrownfox\njumpedoverthelaz\nydogs\nc\nd\ne"
+                );
+                let snapshot = editor.snapshot(cx);
+
+                // Soft-wrapped continuations have no number and aren't counted.
+                assert_eq!(
+                    relative_rows(&snapshot, 3),
+                    [(0, 2), (1, 1), (4, 1), (5, 2)]
+                );
+                // A cursor on a continuation is on the line that was wrapped.
+                assert_eq!(
+                    relative_rows(&snapshot, 2),
+                    [(0, 1), (3, 1), (4, 2), (5, 3)]
+                );
+
+                // Lines hidden in a fold still count towards the distance.
+                editor.fold_ranges(
+                    [(Point::new(2, 0)..Point::new(3, 0), FoldPlaceholder::test())],
+                    false,
+                    cx,
+                );
+                assert_eq!(
+                    editor.display_text(cx),
+                    "thequickbrownfox\njumpedoverthelaz\nydogs\n⋯d\ne"
+                );
+                let snapshot = editor.snapshot(cx);
+                assert_eq!(relative_rows(&snapshot, 0), [(1, 1), (3, 2), (4, 4)]);
+            })
+            .unwrap();
+    }
+
     #[gpui::test]
     async fn test_vim_visual_selections(cx: &mut TestAppContext) {
         init_test(cx, |_| {});
@@ -6110,6 +6269,117 @@ mod tests {
         }
     }
 
+    #[gpui::test]
+    fn test_unusual_invisibles_drawing(cx: &mut TestAppContext) {
+        let input_text = "a\u{00A0}b c\u{2007}\u{FEFF}d ";
+        let expected_invisibles = vec![
+            Invisible::Unusual {
+                line_offset: 1,
+                len: 2,
+                kind: InvisibleKind::UnusualSpace,
+            },
+            Invisible::Whitespace { line_offset: 4 },
+            Invisible::Unusual {
+                line_offset: 6,
+                len: 3,
+                kind: InvisibleKind::UnusualSpace,
+            },
+            Invisible::Unusual {
+                line_offset: 9,
+                len: 3,
+                kind: InvisibleKind::ZeroWidth,
+            },
+            Invisible::Whitespace { line_offset: 13 },
+        ];
+
+        init_test(cx, |s| {
+            s.defaults.show_whitespaces = Some(ShowWhitespaceSetting::Trailing);
+        });
+
+        let window = cx.add_window(|cx| {
+            let buffer = MultiBuffer::build_simple(input_text, cx);
+            Editor::new(EditorMode::Full, buffer, None, true, cx)
+        });
+        let cx = &mut VisualTestContext::from_window(*window, cx);
+        let editor = window.root(cx).unwrap();
+        let style = cx.update(|cx| editor.read(cx).style().unwrap().clone());
+        let (_, state) = cx.draw(point(px(500.), px(500.)), size(px(500.), px(500.)), |_| {
+            EditorElement::new(&editor, style)
+        });
+        let line = &state.position_map.line_layouts[0];
+        assert_eq!(line.invisibles, expected_invisibles);
+        assert_eq!(line.trailing_whitespace_start, 13);
+    }
+
+    #[gpui::test]
+    fn test_long_line_layout(cx: &mut TestAppContext) {
+        init_test(cx, |s| {
+            s.defaults.soft_wrap = Some(language_settings::SoftWrap::None);
+        });
+
+        // A single line of about 200KB, like a minified script.
+        let mut input_text = String::new();
+        for ix in 0.. {
+            if input_text.len() >= 200_000 {
+                break;
+            }
+            write!(
+                input_text,
+                "var a{ix}=function(b){{return b*{ix}+\"x{ix}\"}};"
+            )
+            .unwrap();
+        }
+
+        let window = cx.add_window(|cx| {
+            let buffer = MultiBuffer::build_simple(&input_text, cx);
+            Editor::new(EditorMode::Full, buffer, None, true, cx)
+        });
+        let cx = &mut VisualTestContext::from_window(*window, cx);
+        let editor = window.root(cx).unwrap();
+        let style = cx.update(|cx| editor.read(cx).style().unwrap().clone());
+        let (_, state) = cx.draw(point(px(500.), px(500.)), size(px(500.), px(500.)), |_| {
+            EditorElement::new(&editor, style.clone())
+        });
+        assert!(editor.update(cx, |editor, cx| editor.has_long_lines(cx)));
+        // Only as much of the line as gets rendered is laid out.
+        assert_eq!(state.position_map.line_layouts.len(), 1);
+        let line_len = state.position_map.line_layouts[0].len;
+        assert!(line_len > 0 && line_len <= MAX_LINE_LEN, "{line_len}");
+
+        // Soft wrap cuts the line into rows of the same number of characters, rather than
+        // measuring it to wrap at word boundaries.
+        editor.update(cx, |editor, cx| {
+            editor.set_soft_wrap_mode(language_settings::SoftWrap::EditorWidth, cx)
+        });
+        cx.draw(point(px(500.), px(500.)), size(px(500.), px(500.)), |_| {
+            EditorElement::new(&editor, style.clone())
+        });
+        cx.run_until_parked();
+        let snapshot = editor.update(cx, |editor, cx| editor.snapshot(cx));
+        let last_row = snapshot.max_point().row();
+        assert!(last_row > DisplayRow(0));
+        let row_len = snapshot.line_len(DisplayRow(0));
+        for row in 1..last_row.0 {
+            assert_eq!(snapshot.line_len(DisplayRow(row)), row_len, "row {row}");
+        }
+        assert!(snapshot.line_len(last_row) <= row_len);
+
+        // Editing is unaffected.
+        let middle = input_text.len() / 2;
+        editor.update(cx, |editor, cx| {
+            editor.change_selections(None, cx, |s| s.select_ranges([middle..middle]));
+            for text in ["x", "y", "z"] {
+                editor.handle_input(text, cx);
+            }
+        });
+        cx.run_until_parked();
+        assert_eq!(
+            editor.update(cx, |editor, cx| editor.text(cx)[middle..middle + 3]
+                .to_string()),
+            "xyz"
+        );
+    }
+
     fn collect_invisibles_from_new_editor(
         cx: &mut TestAppContext,
         editor_mode: EditorMode,
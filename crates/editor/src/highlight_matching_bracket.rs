@@ -15,6 +15,10 @@ pub fn refresh_matching_bracket_highlights(editor: &mut Editor, cx: &mut ViewCon
 
     let head = newest_selection.head();
     let snapshot = editor.snapshot(cx);
+    // Finding the enclosing brackets may take scanning all of a long line.
+    if snapshot.has_long_lines() {
+        return;
+    }
     if let Some((opening_range, closing_range)) = snapshot
         .buffer_snapshot
         .innermost_enclosing_bracket_ranges(head..head, None)
@@ -1,7 +1,14 @@
 use crate::{
-    editor_settings::SeedQuerySetting, persistence::DB, scroll::ScrollAnchor, Anchor, Autoscroll,
-    Editor, EditorEvent, EditorSettings, ExcerptId, ExcerptRange, MultiBuffer, MultiBufferSnapshot,
-    NavigationData, SearchWithinRange, ToPoint as _,
+    editor_settings::SeedQuerySetting,
+    marks::track_marks,
+    persistence::DB,
+    recovery::{
+        offer_recovery, read_recovery, recovery_file_path, remove_recovery, restore_recovery,
+    },
+    save_progress::save_buffers_with_progress,
+    scroll::ScrollAnchor,
+    Anchor, Autoscroll, Editor, EditorEvent, EditorSettings, ExcerptId, ExcerptRange, MultiBuffer,
+    MultiBufferSnapshot, NavigationData, SearchWithinRange, ToOffset as _, ToPoint as _,
 };
 use anyhow::{anyhow, Context as _, Result};
 use collections::HashSet;
@@ -9,17 +16,18 @@ use futures::future::try_join_all;
 use git::repository::GitFileStatus;
 use gpui::{
     point, AnyElement, AppContext, AsyncWindowContext, Context, Entity, EntityId, EventEmitter,
-    IntoElement, Model, ParentElement, Pixels, SharedString, Styled, Task, View, ViewContext,
-    VisualContext, WeakView, WindowContext,
+    IntoElement, Model, ParentElement, Pixels, PromptLevel, SharedString, Styled, Task, View,
+    ViewContext, VisualContext, WeakView, WindowContext,
 };
 use language::{
-    proto::serialize_anchor as serialize_text_anchor, Bias, Buffer, CharKind, Point, SelectionGoal,
+    proto::serialize_anchor as serialize_text_anchor, Bias, Buffer, CharKind, Encoding, Point,
+    SelectionGoal,
 };
 use multi_buffer::AnchorRangeExt;
 use project::{search::SearchQuery, FormatTrigger, Item as _, Project, ProjectPath};
 use rpc::proto::{self, update_view, PeerId};
 use settings::Settings;
-use workspace::item::{ItemSettings, TabContentParams};
+use workspace::item::{ItemSettings, PointOrAnchor, TabContentParams};
 
 use std::{
     any::TypeId,
@@ -572,6 +580,27 @@ impl Item for Editor {
         }
     }
 
+    fn move_cursor_to(&mut self, position: PointOrAnchor, cx: &mut ViewContext<Self>) -> bool {
+        let buffer = self.buffer.read(cx).snapshot(cx);
+        let offset = match position {
+            PointOrAnchor::Point(point) => buffer.clip_point(point, Bias::Left).to_offset(&buffer),
+            PointOrAnchor::Anchor(anchor) => {
+                let Some((excerpt_id, buffer_id, _)) = buffer.as_singleton() else {
+                    return false;
+                };
+                if anchor.buffer_id != Some(buffer_id) {
+                    return false;
+                }
+                let Some(anchor) = buffer.anchor_in_excerpt(*excerpt_id, anchor) else {
+                    return false;
+                };
+                anchor.to_offset(&buffer)
+            }
+        };
+        self.jump_to_offset(offset, cx);
+        true
+    }
+
     fn tab_tooltip_text(&self, cx: &AppContext) -> Option<SharedString> {
         let file_path = self
             .buffer()
@@ -596,6 +625,10 @@ impl Item for Editor {
         Some(path.to_string_lossy().to_string().into())
     }
 
+    fn tab_content_text(&self, cx: &AppContext) -> Option<SharedString> {
+        Some(self.title(cx).to_string().into())
+    }
+
     fn tab_content(&self, params: TabContentParams, cx: &WindowContext) -> AnyElement {
         let label_color = if ItemSettings::get_global(cx).git_status {
             self.buffer()
@@ -678,6 +711,10 @@ impl Item for Editor {
         self.hide_hovered_link(cx);
     }
 
+    fn discarded(&mut self, cx: &mut ViewContext<Self>) {
+        self.discard_recovery(cx);
+    }
+
     fn is_dirty(&self, cx: &AppContext) -> bool {
         self.buffer().read(cx).read(cx).is_dirty()
     }
@@ -711,11 +748,13 @@ impl Item for Editor {
                 .await?;
             }
 
+            if !confirm_saving_unencodable_buffers(&this, &buffers, &mut cx).await? {
+                return Ok(());
+            }
+
             if buffers.len() == 1 {
                 // Apply full save routine for singleton buffers, to allow to `touch` the file via the editor.
-                project
-                    .update(&mut cx, |project, cx| project.save_buffers(buffers, cx))?
-                    .await?;
+                save_buffers_with_progress(&this, &project, buffers, &mut cx).await?;
             } else {
                 // For multi-buffers, only format and save the buffers with changes.
                 // For clean buffers, we simulate saving by calling `Buffer::did_save`,
@@ -728,11 +767,7 @@ impl Item for Editor {
                         .unwrap_or(false)
                 });
 
-                project
-                    .update(&mut cx, |project, cx| {
-                        project.save_buffers(dirty_buffers, cx)
-                    })?
-                    .await?;
+                save_buffers_with_progress(&this, &project, dirty_buffers, &mut cx).await?;
                 for buffer in clean_buffers {
                     buffer
                         .update(&mut cx, |buffer, cx| {
@@ -846,6 +881,9 @@ impl Item for Editor {
 
     fn added_to_workspace(&mut self, workspace: &mut Workspace, cx: &mut ViewContext<Self>) {
         self.workspace = Some((workspace.weak_handle(), workspace.database_id()));
+        if let Some(buffer) = self.buffer().read(cx).as_singleton() {
+            track_marks(&buffer, cx);
+        }
         let Some(workspace_id) = workspace.database_id() else {
             return;
         };
@@ -873,6 +911,7 @@ impl Item for Editor {
 
         if let Some(buffer) = self.buffer().read(cx).as_singleton() {
             serialize(buffer.clone(), workspace_id, item_id, cx);
+            self.serialize_selections(cx);
 
             cx.subscribe(&buffer, |this, buffer, event, cx| {
                 if let Some((_, Some(workspace_id))) = this.workspace.as_ref() {
@@ -887,6 +926,8 @@ impl Item for Editor {
                 }
             })
             .detach();
+
+            self.track_recovery(workspace_id, cx);
         }
     }
 
@@ -935,41 +976,81 @@ impl Item for Editor {
         item_id: ItemId,
         cx: &mut ViewContext<Pane>,
     ) -> Task<Result<View<Self>>> {
+        let fs = project.read(cx).fs().clone();
         let project_item: Result<_> = project.update(cx, |project, cx| {
             // Look up the path with this key associated, create a self with that path
-            let path = DB
-                .get_path(item_id, workspace_id)?
-                .context("No path stored for this editor")?;
+            let Some(path) = DB.get_path(item_id, workspace_id)? else {
+                return Ok(None);
+            };
 
-            let (worktree, path) = project
+            let (worktree, relative_path) = project
                 .find_local_worktree(&path, cx)
                 .with_context(|| format!("No worktree for path: {path:?}"))?;
             let project_path = ProjectPath {
                 worktree_id: worktree.read(cx).id(),
-                path: path.into(),
+                path: relative_path.into(),
             };
 
-            Ok(project.open_path(project_path, cx))
+            Ok(Some((path, project.open_path(project_path, cx))))
         });
 
-        project_item
-            .map(|project_item| {
-                cx.spawn(|pane, mut cx| async move {
-                    let (_, project_item) = project_item.await?;
-                    let buffer = project_item
-                        .downcast::<Buffer>()
-                        .map_err(|_| anyhow!("Project item at stored path was not a buffer"))?;
-                    pane.update(&mut cx, |_, cx| {
-                        cx.new_view(|cx| {
-                            let mut editor = Editor::for_buffer(buffer, Some(project), cx);
-
-                            editor.read_scroll_position_from_db(item_id, workspace_id, cx);
-                            editor
-                        })
+        match project_item {
+            Ok(Some((path, project_item))) => cx.spawn(|pane, mut cx| async move {
+                let (_, project_item) = project_item.await?;
+                let buffer = project_item
+                    .downcast::<Buffer>()
+                    .map_err(|_| anyhow!("Project item at stored path was not a buffer"))?;
+
+                // Editors of the same file share its buffer, and are offered its changes once.
+                let recovery_path = recovery_file_path(workspace_id, Some(&path), item_id);
+                pane.update(&mut cx, |_, cx| {
+                    offer_recovery(fs, buffer.clone(), path, recovery_path, cx)
+                })?
+                .await;
+
+                pane.update(&mut cx, |_, cx| {
+                    cx.new_view(|cx| {
+                        let mut editor = Editor::for_buffer(buffer, Some(project), cx);
+
+                        // Files may have gotten shorter since the selections were stored.
+                        if let Some(selections) = DB
+                            .get_editor_selections(item_id, workspace_id)
+                            .log_err()
+                            .filter(|selections| !selections.is_empty())
+                        {
+                            let len = editor.buffer().read(cx).len(cx);
+                            editor.change_selections(None, cx, |s| {
+                                s.select_ranges(
+                                    selections
+                                        .into_iter()
+                                        .map(|(start, end)| start.min(len)..end.min(len)),
+                                )
+                            });
+                        }
+                        editor.read_scroll_position_from_db(item_id, workspace_id, cx);
+                        editor
                     })
                 })
-            })
-            .unwrap_or_else(|error| Task::ready(Err(error)))
+            }),
+            Ok(None) => cx.spawn(|pane, mut cx| async move {
+                // Untitled editors have no stored path, but may have left unsaved text behind.
+                let recovery_path = recovery_file_path(workspace_id, None, item_id);
+                let recovery = read_recovery(fs.as_ref(), &recovery_path)
+                    .await
+                    .context("No path stored for this editor")?;
+                let buffer = project.update(&mut cx, |project, cx| {
+                    project.create_local_buffer("", None, cx)
+                })?;
+                restore_recovery(&buffer, recovery, &mut cx).await?;
+                // The restored editor gets a new item id, and with it a new recovery file.
+                remove_recovery(fs.as_ref(), &recovery_path).await.log_err();
+
+                pane.update(&mut cx, |_, cx| {
+                    cx.new_view(|cx| Editor::for_buffer(buffer, Some(project), cx))
+                })
+            }),
+            Err(error) => Task::ready(Err(error)),
+        }
     }
 }
 
@@ -1065,7 +1146,7 @@ impl SearchableItem for Editor {
     ) {
         self.unfold_ranges([matches[index].clone()], false, true, cx);
         let range = self.range_for_match(&matches[index]);
-        self.change_selections(Some(Autoscroll::fit()), cx, |s| {
+        self.change_selections(Some(Autoscroll::center_if_offscreen()), cx, |s| {
             s.select_ranges([range]);
         })
     }
@@ -1083,20 +1164,36 @@ impl SearchableItem for Editor {
         identifier: &Self::Match,
         query: &SearchQuery,
         cx: &mut ViewContext<Self>,
+    ) {
+        self.replace_all(&mut std::iter::once(identifier), query, cx);
+    }
+
+    fn replace_all(
+        &mut self,
+        matches: &mut dyn Iterator<Item = &Self::Match>,
+        query: &SearchQuery,
+        cx: &mut ViewContext<Self>,
     ) {
         let text = self.buffer.read(cx);
         let text = text.snapshot(cx);
-        let text = text.text_for_range(identifier.clone()).collect::<Vec<_>>();
-        let text: Cow<_> = if text.len() == 1 {
-            text.first().cloned().unwrap().into()
-        } else {
-            let joined_chunks = text.join("");
-            joined_chunks.into()
-        };
+        let mut edits = Vec::new();
+        for range in matches {
+            let chunks = text.text_for_range(range.clone()).collect::<Vec<_>>();
+            let matched_text: Cow<_> = if chunks.len() == 1 {
+                chunks.first().cloned().unwrap().into()
+            } else {
+                chunks.join("").into()
+            };
+
+            if let Some(replacement) = query.replacement_for(&matched_text) {
+                edits.push((range.clone(), Arc::from(&*replacement)));
+            }
+        }
 
-        if let Some(replacement) = query.replacement_for(&text) {
+        // All replacements are applied at once, so they're undone together.
+        if !edits.is_empty() {
             self.transact(cx, |this, cx| {
-                this.edit([(identifier.clone(), Arc::from(&*replacement))], cx);
+                this.edit(edits, cx);
             });
         }
     }
@@ -1162,62 +1259,8 @@ impl SearchableItem for Editor {
                 ranges.iter().map(|range| range.clone()).collect::<Vec<_>>()
             });
 
-        cx.background_executor().spawn(async move {
-            let mut ranges = Vec::new();
-
-            if let Some((_, _, excerpt_buffer)) = buffer.as_singleton() {
-                let search_within_ranges = if search_within_ranges.is_empty() {
-                    vec![None]
-                } else {
-                    search_within_ranges
-                        .into_iter()
-                        .map(|range| Some(range.to_offset(&buffer)))
-                        .collect::<Vec<_>>()
-                };
-
-                for range in search_within_ranges {
-                    let buffer = &buffer;
-                    ranges.extend(
-                        query
-                            .search(excerpt_buffer, range.clone())
-                            .await
-                            .into_iter()
-                            .map(|matched_range| {
-                                let offset = range.clone().map(|r| r.start).unwrap_or(0);
-                                buffer.anchor_after(matched_range.start + offset)
-                                    ..buffer.anchor_before(matched_range.end + offset)
-                            }),
-                    );
-                }
-            } else {
-                let search_within_ranges = if search_within_ranges.is_empty() {
-                    vec![buffer.anchor_before(0)..buffer.anchor_after(buffer.len())]
-                } else {
-                    search_within_ranges
-                };
-
-                for (excerpt_id, search_buffer, search_range) in
-                    buffer.excerpts_in_ranges(search_within_ranges)
-                {
-                    ranges.extend(
-                        query
-                            .search(&search_buffer, Some(search_range.clone()))
-                            .await
-                            .into_iter()
-                            .map(|match_range| {
-                                let start = search_buffer
-                                    .anchor_after(search_range.start + match_range.start);
-                                let end = search_buffer
-                                    .anchor_before(search_range.start + match_range.end);
-                                buffer.anchor_in_excerpt(excerpt_id, start).unwrap()
-                                    ..buffer.anchor_in_excerpt(excerpt_id, end).unwrap()
-                            }),
-                    );
-                }
-            };
-
-            ranges
-        })
+        cx.background_executor()
+            .spawn(async move { search_buffer(buffer, search_within_ranges, &query).await })
     }
 
     fn active_match_index(
@@ -1237,6 +1280,68 @@ impl SearchableItem for Editor {
     }
 }
 
+/// Finds the matches of the query in the buffer, only within the given ranges unless they're
+/// empty.
+pub(crate) async fn search_buffer(
+    buffer: MultiBufferSnapshot,
+    search_within_ranges: Vec<Range<Anchor>>,
+    query: &SearchQuery,
+) -> Vec<Range<Anchor>> {
+    let mut ranges = Vec::new();
+
+    if let Some((_, _, excerpt_buffer)) = buffer.as_singleton() {
+        let search_within_ranges = if search_within_ranges.is_empty() {
+            vec![None]
+        } else {
+            search_within_ranges
+                .into_iter()
+                .map(|range| Some(range.to_offset(&buffer)))
+                .collect::<Vec<_>>()
+        };
+
+        for range in search_within_ranges {
+            let buffer = &buffer;
+            ranges.extend(
+                query
+                    .search(excerpt_buffer, range.clone())
+                    .await
+                    .into_iter()
+                    .map(|matched_range| {
+                        let offset = range.clone().map(|r| r.start).unwrap_or(0);
+                        buffer.anchor_after(matched_range.start + offset)
+                            ..buffer.anchor_before(matched_range.end + offset)
+                    }),
+            );
+        }
+    } else {
+        let search_within_ranges = if search_within_ranges.is_empty() {
+            vec![buffer.anchor_before(0)..buffer.anchor_after(buffer.len())]
+        } else {
+            search_within_ranges
+        };
+
+        for (excerpt_id, search_buffer, search_range) in
+            buffer.excerpts_in_ranges(search_within_ranges)
+        {
+            ranges.extend(
+                query
+                    .search(&search_buffer, Some(search_range.clone()))
+                    .await
+                    .into_iter()
+                    .map(|match_range| {
+                        let start =
+                            search_buffer.anchor_after(search_range.start + match_range.start);
+                        let end = search_buffer.anchor_before(search_range.start + match_range.end);
+                        buffer.anchor_in_excerpt(excerpt_id, start).unwrap()
+                            ..buffer.anchor_in_excerpt(excerpt_id, end).unwrap()
+                    }),
+            );
+        }
+    };
+
+    ranges
+}
+
 pub fn active_match_index(
     ranges: &[Range<Anchor>],
     cursor: &Anchor,
@@ -1284,6 +1389,50 @@ pub fn entry_git_aware_label_color(
     }
 }
 
+/// Asks whether the buffers that contain characters their file's encoding can't represent
+/// should be saved as UTF-8 instead, switching them over if so. Returns `false` if saving
+/// was cancelled.
+async fn confirm_saving_unencodable_buffers(
+    this: &WeakView<Editor>,
+    buffers: &HashSet<Model<Buffer>>,
+    cx: &mut AsyncWindowContext,
+) -> Result<bool> {
+    let mut unencodable_buffers = Vec::new();
+    for buffer in buffers {
+        let unencodable = buffer.read_with(cx, |buffer, _| {
+            Some((buffer.unencodable_char()?, buffer.encoding()))
+        })?;
+        if let Some(unencodable) = unencodable {
+            unencodable_buffers.push((buffer.clone(), unencodable));
+        }
+    }
+    let Some((_, (unencodable_char, encoding))) = unencodable_buffers.first() else {
+        return Ok(true);
+    };
+
+    let message = format!("This file can't be saved as {}", encoding.name());
+    let detail = format!(
+        "It contains characters such as {unencodable_char:?} that {} can't represent.",
+        encoding.name()
+    );
+    let answer = this.update(cx, |_, cx| {
+        cx.prompt(
+            PromptLevel::Warning,
+            &message,
+            Some(&detail),
+            &["Save as UTF-8", "Cancel"],
+        )
+    })?;
+    if answer.await != Ok(0) {
+        return Ok(false);
+    }
+
+    for (buffer, _) in unencodable_buffers {
+        buffer.update(cx, |buffer, cx| buffer.set_encoding(Encoding::Utf8, cx))?;
+    }
+    Ok(true)
+}
+
 fn path_for_buffer<'a>(
     buffer: &Model<MultiBuffer>,
     height: usize,
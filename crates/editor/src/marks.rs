@@ -0,0 +1,149 @@
+use std::path::PathBuf;
+
+use anyhow::{Context as _, Result};
+use collections::HashSet;
+use gpui::{AppContext, EntityId, Global, Model, ViewContext};
+use language::{Buffer, MarkId, Marks};
+use text::{Point, ToOffset};
+use util::ResultExt;
+
+use crate::{
+    actions::{ClearMarks, JumpToMark, SetMark},
+    persistence::DB,
+    Editor,
+};
+
+/// The buffers whose marks get stored in the database whenever they change.
+#[derive(Default)]
+struct TrackedMarks(HashSet<EntityId>);
+
+impl Global for TrackedMarks {}
+
+/// Restores the marks stored for the file of a buffer the first time it's tracked, and stores
+/// them whenever they change from then on, so that they're back when the file is opened again.
+pub(crate) fn track_marks(buffer: &Model<Buffer>, cx: &mut AppContext) {
+    let buffer_id = buffer.entity_id();
+    let Some(path) = marks_path(buffer, cx) else {
+        return;
+    };
+    if !cx.default_global::<TrackedMarks>().0.insert(buffer_id) {
+        return;
+    }
+
+    if let Some(marks) = DB.get_buffer_marks(path).log_err() {
+        buffer.update(cx, |buffer, _| {
+            buffer.restore_marks(marks.into_iter().filter_map(
+                |(name, position, region_start, region_end)| {
+                    Some((name.parse().log_err()?, position, region_start..region_end))
+                },
+            ))
+        });
+    }
+    cx.subscribe(buffer, |buffer, event: &language::Event, cx| {
+        if let language::Event::MarksChanged = event {
+            serialize_marks(&buffer, cx);
+        }
+    })
+    .detach();
+    cx.observe_release(buffer, move |_, cx| {
+        cx.default_global::<TrackedMarks>().0.remove(&buffer_id);
+    })
+    .detach();
+}
+
+fn marks_path(buffer: &Model<Buffer>, cx: &AppContext) -> Option<PathBuf> {
+    Some(buffer.read(cx).file()?.as_local()?.abs_path(cx))
+}
+
+fn serialize_marks(buffer: &Model<Buffer>, cx: &mut AppContext) {
+    let Some(path) = marks_path(buffer, cx) else {
+        return;
+    };
+    let buffer = buffer.read(cx);
+    let marks = buffer
+        .marks()
+        .iter()
+        .map(|mark| {
+            (
+                mark.name.to_string(),
+                mark.position.to_offset(buffer),
+                mark.region.start.to_offset(buffer),
+                mark.region.end.to_offset(buffer),
+            )
+        })
+        .collect::<Vec<_>>();
+    cx.background_executor()
+        .spawn(async move { DB.save_buffer_marks(path, marks).await.log_err() })
+        .detach();
+}
+
+impl Editor {
+    /// Returns the marks of the editor's buffer, which all of the buffer's editors share.
+    /// Editors of multiple buffers have none.
+    pub fn marks<'a>(&self, cx: &'a AppContext) -> Option<&'a Marks> {
+        Some(self.buffer.read(cx).as_singleton()?.read(cx).marks())
+    }
+
+    pub fn set_mark(&mut self, action: &SetMark, cx: &mut ViewContext<Self>) {
+        self.insert_mark(action.letter, cx).log_err();
+    }
+
+    /// Sets a mark at the newest cursor, with the given letter or else a number.
+    pub fn insert_mark(
+        &mut self,
+        letter: Option<char>,
+        cx: &mut ViewContext<Self>,
+    ) -> Result<MarkId> {
+        let buffer = self
+            .buffer
+            .read(cx)
+            .as_singleton()
+            .context("marks can only be set in editors of a single buffer")?;
+        // Restore the stored marks first, so that they don't replace this one.
+        track_marks(&buffer, cx);
+        let head = self.selections.newest::<Point>(cx).head();
+        buffer.update(cx, |buffer, cx| buffer.set_mark(letter, head, cx))
+    }
+
+    pub fn jump_to_mark(&mut self, action: &JumpToMark, cx: &mut ViewContext<Self>) {
+        let Some(name) = action.name.parse().log_err() else {
+            return;
+        };
+        if let Some(id) = self
+            .marks(cx)
+            .and_then(|marks| marks.named(name))
+            .map(|mark| mark.id)
+        {
+            self.go_to_mark(id, cx);
+        }
+    }
+
+    /// Moves the cursor to the mark, so that navigating back returns to where it was.
+    pub fn go_to_mark(&mut self, id: MarkId, cx: &mut ViewContext<Self>) -> bool {
+        let Some(buffer) = self.buffer.read(cx).as_singleton() else {
+            return false;
+        };
+        let buffer = buffer.read(cx);
+        let Some(offset) = buffer
+            .marks()
+            .get(id)
+            .map(|mark| mark.position.to_offset(buffer))
+        else {
+            return false;
+        };
+        self.jump_to_offset(offset, cx);
+        true
+    }
+
+    pub fn remove_mark(&mut self, id: MarkId, cx: &mut ViewContext<Self>) {
+        if let Some(buffer) = self.buffer.read(cx).as_singleton() {
+            buffer.update(cx, |buffer, cx| buffer.remove_mark(id, cx));
+        }
+    }
+
+    pub fn clear_marks(&mut self, _: &ClearMarks, cx: &mut ViewContext<Self>) {
+        if let Some(buffer) = self.buffer.read(cx).as_singleton() {
+            buffer.update(cx, |buffer, cx| buffer.clear_marks(cx));
+        }
+    }
+}
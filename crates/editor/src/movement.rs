@@ -3,7 +3,8 @@
 
 use super::{Bias, DisplayPoint, DisplaySnapshot, SelectionGoal, ToDisplayPoint};
 use crate::{
-    char_kind, scroll::ScrollAnchor, CharKind, DisplayRow, EditorStyle, RowExt, ToOffset, ToPoint,
+    char_kind, is_word_boundary, scroll::ScrollAnchor, CharKind, DisplayRow, EditorStyle, RowExt,
+    ToOffset, ToPoint,
 };
 use gpui::{px, Pixels, WindowTextSystem};
 use language::Point;
@@ -267,8 +268,7 @@ pub fn previous_word_start(map: &DisplaySnapshot, point: DisplayPoint) -> Displa
     let scope = map.buffer_snapshot.language_scope_at(raw_point);
 
     find_preceding_boundary_display_point(map, point, FindRange::MultiLine, |left, right| {
-        (char_kind(&scope, left) != char_kind(&scope, right) && !right.is_whitespace())
-            || left == '\n'
+        (is_word_boundary(&scope, left, right) && !right.is_whitespace()) || left == '\n'
     })
 }
 
@@ -280,8 +280,7 @@ pub fn previous_subword_start(map: &DisplaySnapshot, point: DisplayPoint) -> Dis
     let scope = map.buffer_snapshot.language_scope_at(raw_point);
 
     find_preceding_boundary_display_point(map, point, FindRange::MultiLine, |left, right| {
-        let is_word_start =
-            char_kind(&scope, left) != char_kind(&scope, right) && !right.is_whitespace();
+        let is_word_start = is_word_boundary(&scope, left, right) && !right.is_whitespace();
         let is_subword_start =
             left == '_' && right != '_' || left.is_lowercase() && right.is_uppercase();
         is_word_start || is_subword_start || left == '\n'
@@ -295,8 +294,7 @@ pub fn next_word_end(map: &DisplaySnapshot, point: DisplayPoint) -> DisplayPoint
     let scope = map.buffer_snapshot.language_scope_at(raw_point);
 
     find_boundary(map, point, FindRange::MultiLine, |left, right| {
-        (char_kind(&scope, left) != char_kind(&scope, right) && !left.is_whitespace())
-            || right == '\n'
+        (is_word_boundary(&scope, left, right) && !left.is_whitespace()) || right == '\n'
     })
 }
 
@@ -308,8 +306,7 @@ pub fn next_subword_end(map: &DisplaySnapshot, point: DisplayPoint) -> DisplayPo
     let scope = map.buffer_snapshot.language_scope_at(raw_point);
 
     find_boundary(map, point, FindRange::MultiLine, |left, right| {
-        let is_word_end =
-            (char_kind(&scope, left) != char_kind(&scope, right)) && !left.is_whitespace();
+        let is_word_end = is_word_boundary(&scope, left, right) && !left.is_whitespace();
         let is_subword_end =
             left != '_' && right == '_' || left.is_lowercase() && right.is_uppercase();
         is_word_end || is_subword_end || right == '\n'
@@ -582,6 +579,7 @@ mod tests {
     use gpui::{font, Context as _};
     use language::Capability;
     use project::Project;
+    use rand::prelude::*;
     use settings::SettingsStore;
     use util::post_inc;
 
@@ -611,6 +609,11 @@ mod tests {
         assert(" ˇdefγˇ", cx);
         assert(" ˇbcΔˇ", cx);
         assert(" abˇ——ˇcd", cx);
+        assert(" ˇfoo42_barˇ", cx);
+        assert(" ˇcafe\u{301}ˇ", cx);
+        assert("漢字ˇかなˇ", cx);
+        assert("(ˇ😀ˇ", cx);
+        assert("a ˇ👨\u{200D}👩\u{200D}👧ˇ", cx);
     }
 
     #[gpui::test]
@@ -782,6 +785,59 @@ mod tests {
         assert("loremˇ_ipsumˇ", cx);
         assert(" ˇbcΔˇ", cx);
         assert(" abˇ——ˇcd", cx);
+        assert("ˇ漢字ˇかな", cx);
+        assert("ˇcafe\u{301}ˇ!", cx);
+        assert("ˇ👍\u{1F3FD}ˇ!", cx);
+    }
+
+    #[gpui::test(iterations = 100)]
+    fn test_random_word_boundaries(cx: &mut gpui::AppContext, mut rng: StdRng) {
+        init_test(cx);
+
+        const PIECES: &[&str] = &[
+            "a",
+            "Z",
+            "7",
+            "_",
+            "-",
+            "(",
+            ".",
+            "e\u{301}",
+            "漢",
+            "字",
+            "か",
+            "カ",
+            "한",
+            "😀",
+            "👍\u{1F3FD}",
+            "👨\u{200D}👩",
+            "❤\u{FE0F}",
+        ];
+        let len = rng.gen_range(0..20);
+        let text = (0..len)
+            .map(|_| *PIECES.choose(&mut rng).unwrap())
+            .collect::<String>();
+        let (snapshot, _) = marked_display_snapshot(&text, cx);
+        let end = snapshot.max_point();
+
+        // Without whitespace, the word ends that moving right stops at are
+        // the same as the word starts that moving left stops at.
+        let mut rightward_stops = vec![DisplayPoint::zero()];
+        let mut point = DisplayPoint::zero();
+        while point < end {
+            point = next_word_end(&snapshot, point);
+            rightward_stops.push(point);
+        }
+
+        let mut leftward_stops = vec![end];
+        let mut point = end;
+        while point > DisplayPoint::zero() {
+            point = previous_word_start(&snapshot, point);
+            leftward_stops.push(point);
+        }
+        leftward_stops.reverse();
+
+        assert_eq!(rightward_stops, leftward_stops, "text: {:?}", text);
     }
 
     #[gpui::test]
@@ -878,6 +934,10 @@ mod tests {
         assert("lorem\nˇˇipsumˇ", cx);
         assert("loremˇ,ˇˇ ipsum", cx);
         assert("ˇloremˇˇ, ipsum", cx);
+        assert("ˇfoo_42ˇbarˇ ipsum", cx);
+        assert("ˇcafe\u{301}ˇˇ!", cx);
+        assert("漢字ˇˇかなˇ", cx);
+        assert("ˇ漢ˇ字ˇかな", cx);
     }
 
     #[gpui::test]
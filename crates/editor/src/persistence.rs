@@ -1,5 +1,6 @@
 use std::path::PathBuf;
 
+use anyhow::Result;
 use db::sqlez_macros::sql;
 use db::{define_connection, query};
 
@@ -15,6 +16,21 @@ define_connection!(
     //   scroll_vertical_offset: f32,
     //   scroll_horizontal_offset: f32,
     // )
+    //
+    // editor_selections(
+    //   editor_id: usize,
+    //   workspace_id: usize,
+    //   start: usize,
+    //   end: usize,
+    // )
+    //
+    // buffer_marks(
+    //   path: PathBuf,
+    //   name: String,
+    //   position: usize,
+    //   region_start: usize,
+    //   region_end: usize,
+    // )
     pub static ref DB: EditorDb<WorkspaceDb> =
         &[sql! (
             CREATE TABLE editors(
@@ -31,6 +47,27 @@ define_connection!(
             ALTER TABLE editors ADD COLUMN scroll_top_row INTEGER NOT NULL DEFAULT 0;
             ALTER TABLE editors ADD COLUMN scroll_horizontal_offset REAL NOT NULL DEFAULT 0;
             ALTER TABLE editors ADD COLUMN scroll_vertical_offset REAL NOT NULL DEFAULT 0;
+        ),
+        sql! (
+            CREATE TABLE editor_selections(
+                editor_id INTEGER NOT NULL,
+                workspace_id INTEGER NOT NULL,
+                start INTEGER NOT NULL,
+                end INTEGER NOT NULL,
+                FOREIGN KEY(editor_id, workspace_id) REFERENCES editors(item_id, workspace_id)
+                ON DELETE CASCADE
+                ON UPDATE CASCADE
+            ) STRICT;
+        ),
+        sql! (
+            CREATE TABLE buffer_marks(
+                path BLOB NOT NULL,
+                name TEXT NOT NULL,
+                position INTEGER NOT NULL,
+                region_start INTEGER NOT NULL,
+                region_end INTEGER NOT NULL,
+                PRIMARY KEY(path, name)
+            ) STRICT;
         )];
 );
 
@@ -80,4 +117,141 @@ impl EditorDb {
             WHERE item_id = ?1 AND workspace_id = ?2
         }
     }
+
+    // Returns the selections, as offset ranges
+    query! {
+        pub fn get_editor_selections(editor_id: ItemId, workspace_id: WorkspaceId) -> Result<Vec<(usize, usize)>> {
+            SELECT start, end
+            FROM editor_selections
+            WHERE editor_id = ? AND workspace_id = ?
+        }
+    }
+
+    pub async fn save_editor_selections(
+        &self,
+        editor_id: ItemId,
+        workspace_id: WorkspaceId,
+        selections: Vec<(usize, usize)>,
+    ) -> Result<()> {
+        self.write(move |conn| {
+            conn.with_savepoint("update_editor_selections", || {
+                conn.exec_bound(sql!(
+                    DELETE FROM editor_selections WHERE editor_id = ? AND workspace_id = ?
+                ))?((editor_id, workspace_id))?;
+                for (start, end) in selections {
+                    conn.exec_bound(sql!(
+                        INSERT INTO editor_selections(editor_id, workspace_id, start, end)
+                        VALUES (?, ?, ?, ?)
+                    ))?((editor_id, workspace_id, start, end))?;
+                }
+                Ok(())
+            })
+        })
+        .await
+    }
+
+    // Returns the marks of the file at the given path, as names along with the offsets of their
+    // positions and regions
+    query! {
+        pub fn get_buffer_marks(path: PathBuf) -> Result<Vec<(String, usize, usize, usize)>> {
+            SELECT name, position, region_start, region_end
+            FROM buffer_marks
+            WHERE path = ?
+        }
+    }
+
+    pub async fn save_buffer_marks(
+        &self,
+        path: PathBuf,
+        marks: Vec<(String, usize, usize, usize)>,
+    ) -> Result<()> {
+        self.write(move |conn| {
+            conn.with_savepoint("update_buffer_marks", || {
+                conn.exec_bound(sql!(
+                    DELETE FROM buffer_marks WHERE path = ?
+                ))?(path.clone())?;
+                for (name, position, region_start, region_end) in marks {
+                    conn.exec_bound(sql!(
+                        INSERT INTO buffer_marks(path, name, position, region_start, region_end)
+                        VALUES (?, ?, ?, ?, ?)
+                    ))?((
+                        path.clone(),
+                        name,
+                        position,
+                        region_start,
+                        region_end,
+                    ))?;
+                }
+                Ok(())
+            })
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use db::open_test_db;
+
+    #[gpui::test]
+    async fn test_editor_selections() {
+        let db = EditorDb(open_test_db("test_editor_selections").await);
+        let workspace_id = db
+            .write(|conn| {
+                conn.select_row::<WorkspaceId>(sql!(
+                    INSERT INTO workspaces DEFAULT VALUES RETURNING workspace_id
+                ))
+                .unwrap()()
+                .unwrap()
+                .unwrap()
+            })
+            .await;
+        let item_id = 1;
+        db.save_path(item_id, workspace_id, PathBuf::from("/a.txt"))
+            .await
+            .unwrap();
+        assert_eq!(
+            db.get_editor_selections(item_id, workspace_id).unwrap(),
+            Vec::new()
+        );
+
+        db.save_editor_selections(item_id, workspace_id, vec![(1, 3), (5, 5)])
+            .await
+            .unwrap();
+        assert_eq!(
+            db.get_editor_selections(item_id, workspace_id).unwrap(),
+            vec![(1, 3), (5, 5)]
+        );
+
+        // Saving the selections again replaces them.
+        db.save_editor_selections(item_id, workspace_id, vec![(2, 2)])
+            .await
+            .unwrap();
+        assert_eq!(
+            db.get_editor_selections(item_id, workspace_id).unwrap(),
+            vec![(2, 2)]
+        );
+    }
+
+    #[gpui::test]
+    async fn test_buffer_marks() {
+        let db = EditorDb(open_test_db("test_buffer_marks").await);
+        let path = PathBuf::from("/a.txt");
+
+        let marks = vec![("a".to_string(), 4, 0, 10), ("1".to_string(), 12, 10, 20)];
+        db.save_buffer_marks(path.clone(), marks.clone())
+            .await
+            .unwrap();
+        assert_eq!(db.get_buffer_marks(path.clone()).unwrap(), marks);
+        assert_eq!(
+            db.get_buffer_marks(PathBuf::from("/b.txt")).unwrap(),
+            Vec::new()
+        );
+
+        db.save_buffer_marks(path.clone(), Vec::new())
+            .await
+            .unwrap();
+        assert_eq!(db.get_buffer_marks(path).unwrap(), Vec::new());
+    }
 }
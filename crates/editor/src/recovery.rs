@@ -0,0 +1,664 @@
+//! Recovery files keep the unsaved contents of editors on disk, so that they survive a crash or
+//! a restart. They are written a short while after each edit, removed once the buffer is saved,
+//! reverted or its changes are discarded, and offered back to the user when the workspace is
+//! restored, along with the buffer's undo history.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use anyhow::Result;
+use collections::HashMap;
+use futures::{
+    future::{BoxFuture, Shared},
+    FutureExt as _,
+};
+use gpui::{
+    AppContext, AsyncWindowContext, Context as _, EntityId, Global, Model, ModelContext,
+    PromptLevel, Subscription, Task, ViewContext, WeakModel,
+};
+use language::Buffer;
+use project::{Fs, RemoveOptions};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use util::{paths::PathExt, paths::RECOVERY_DIR, ResultExt};
+use workspace::{ItemId, Pane, WorkspaceId};
+
+use crate::Editor;
+
+/// How long to wait after an edit before writing the recovery file.
+pub(crate) const RECOVERY_DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// Buffers larger than this aren't written to recovery files.
+const MAX_RECOVERY_LEN: usize = 16 * 1024 * 1024;
+
+/// Buffers larger than this are recovered without their undo history, as recovering it means
+/// going over the whole text for every transaction.
+const MAX_RECOVERED_HISTORY_LEN: usize = 1024 * 1024;
+
+/// How many of the most recent transactions are kept in a recovery file.
+const MAX_RECOVERED_TRANSACTIONS: usize = 100;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Recovery {
+    /// The path of the file the buffer was editing, if any.
+    pub path: Option<PathBuf>,
+    pub text: String,
+    pub written_at: SystemTime,
+    /// The edits of the transactions on the buffer's undo stack, oldest first. Undoing them all,
+    /// from the last one, turns `text` back into the text they were made to.
+    #[serde(default)]
+    pub undo_stack: Vec<RecoveredEdit>,
+}
+
+/// A transaction on the undo stack, as the single edit it made to the buffer's text.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct RecoveredEdit {
+    pub offset: usize,
+    pub old_text: String,
+    pub new_text: String,
+}
+
+/// Returns where the recovery file for a buffer is kept. Buffers of a file are keyed by the
+/// file's path, untitled buffers by the item id of the editor they were first opened in.
+pub(crate) fn recovery_file_path(
+    workspace_id: WorkspaceId,
+    path: Option<&Path>,
+    item_id: ItemId,
+) -> PathBuf {
+    let dir = RECOVERY_DIR.join(i64::from(workspace_id).to_string());
+    match path {
+        Some(path) => {
+            let digest = Sha256::digest(path.to_string_lossy().as_bytes());
+            let name = digest
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect::<String>();
+            dir.join(format!("{name}.json"))
+        }
+        None => dir.join(format!("untitled-{item_id}.json")),
+    }
+}
+
+pub(crate) async fn write_recovery(
+    fs: &dyn Fs,
+    recovery_path: &Path,
+    recovery: &Recovery,
+) -> Result<()> {
+    if let Some(dir) = recovery_path.parent() {
+        fs.create_dir(dir).await?;
+    }
+    fs.atomic_write(
+        recovery_path.to_path_buf(),
+        serde_json::to_string(recovery)?,
+    )
+    .await
+}
+
+/// Reads a recovery file. A file that can't be parsed is removed, since it would never be
+/// restored anyway.
+pub(crate) async fn read_recovery(fs: &dyn Fs, recovery_path: &Path) -> Option<Recovery> {
+    let contents = fs.load(recovery_path).await.ok()?;
+    match serde_json::from_str(&contents) {
+        Ok(recovery) => Some(recovery),
+        Err(error) => {
+            log::warn!("discarding corrupted recovery file {recovery_path:?}: {error}");
+            remove_recovery(fs, recovery_path).await.log_err();
+            None
+        }
+    }
+}
+
+pub(crate) async fn remove_recovery(fs: &dyn Fs, recovery_path: &Path) -> Result<()> {
+    fs.remove_file(
+        recovery_path,
+        RemoveOptions {
+            recursive: false,
+            ignore_if_not_exists: true,
+        },
+    )
+    .await
+}
+
+/// Brings the recovery file of the given buffer up to date: it gets written while the buffer
+/// has unsaved changes, and removed once it doesn't.
+pub(crate) fn update_recovery_file(
+    fs: Arc<dyn Fs>,
+    buffer: &Model<Buffer>,
+    recovery_path: PathBuf,
+    cx: &AppContext,
+) -> BoxFuture<'static, ()> {
+    let buffer = buffer.read(cx);
+    let recovery = (buffer.is_dirty() && buffer.len() <= MAX_RECOVERY_LEN).then(|| {
+        let snapshot = buffer.text_snapshot();
+        let transaction_starts = if buffer.len() <= MAX_RECOVERED_HISTORY_LEN {
+            let mut starts = buffer
+                .undo_transactions()
+                .rev()
+                .take(MAX_RECOVERED_TRANSACTIONS)
+                .map(|transaction| transaction.start.clone())
+                .collect::<Vec<_>>();
+            starts.reverse();
+            starts
+        } else {
+            Vec::new()
+        };
+        let path = buffer
+            .file()
+            .and_then(|file| file.as_local())
+            .map(|file| file.abs_path(cx));
+        move || {
+            let text = snapshot.text();
+            let mut texts = transaction_starts
+                .iter()
+                .map(|start| snapshot.rope_for_version(start).to_string())
+                .collect::<Vec<_>>();
+            texts.push(text.clone());
+            Recovery {
+                path,
+                text,
+                written_at: SystemTime::now(),
+                undo_stack: texts
+                    .windows(2)
+                    .filter_map(|texts| edit_between(&texts[0], &texts[1]))
+                    .collect(),
+            }
+        }
+    });
+    async move {
+        match recovery {
+            Some(recovery) => write_recovery(fs.as_ref(), &recovery_path, &recovery()).await,
+            None => remove_recovery(fs.as_ref(), &recovery_path).await,
+        }
+        .log_err();
+    }
+    .boxed()
+}
+
+/// Returns the single edit that turns `old` into `new`, unless they're the same.
+fn edit_between(old: &str, new: &str) -> Option<RecoveredEdit> {
+    if old == new {
+        return None;
+    }
+    let prefix_len = old
+        .chars()
+        .zip(new.chars())
+        .take_while(|(old, new)| old == new)
+        .map(|(old, _)| old.len_utf8())
+        .sum::<usize>();
+    let suffix_len = old[prefix_len..]
+        .chars()
+        .rev()
+        .zip(new[prefix_len..].chars().rev())
+        .take_while(|(old, new)| old == new)
+        .map(|(old, _)| old.len_utf8())
+        .sum::<usize>();
+    Some(RecoveredEdit {
+        offset: prefix_len,
+        old_text: old[prefix_len..old.len() - suffix_len].to_string(),
+        new_text: new[prefix_len..new.len() - suffix_len].to_string(),
+    })
+}
+
+/// Returns the text from before the given edits were made, or `None` if they weren't made to
+/// produce this text.
+fn text_before_edits(text: &str, edits: &[RecoveredEdit]) -> Option<String> {
+    let mut text = text.to_string();
+    for edit in edits.iter().rev() {
+        let range = edit.offset..edit.offset + edit.new_text.len();
+        if text.get(range.clone())? != edit.new_text {
+            return None;
+        }
+        text.replace_range(range, &edit.old_text);
+    }
+    Some(text)
+}
+
+/// Replaces the buffer's text with the recovered text. The text the recovered undo stack was
+/// made to is restored as a single transaction that can be undone to get back to the contents
+/// on disk, and each of the recovered transactions is replayed on top of it.
+pub(crate) async fn restore_recovery(
+    buffer: &Model<Buffer>,
+    recovery: Recovery,
+    cx: &mut AsyncWindowContext,
+) -> Result<()> {
+    let (base_text, undo_stack) = match text_before_edits(&recovery.text, &recovery.undo_stack) {
+        Some(base_text) => (base_text, recovery.undo_stack),
+        None => {
+            log::warn!("recovered undo history doesn't match the recovered text, dropping it");
+            (recovery.text, Vec::new())
+        }
+    };
+    let version = buffer.update(cx, |buffer, _| buffer.version())?;
+    let diff = buffer
+        .update(cx, |buffer, cx| buffer.diff(base_text, cx))?
+        .await;
+    buffer.update(cx, |buffer, cx| {
+        // The recovered edits only line up with the text when nothing else changed it.
+        let replay = buffer.version() == version;
+        buffer.finalize_last_transaction();
+        buffer.apply_diff(diff, cx);
+        buffer.finalize_last_transaction();
+        if replay {
+            for edit in undo_stack {
+                let range = edit.offset..edit.offset + edit.old_text.len();
+                buffer.edit([(range, edit.new_text)], None, cx);
+                buffer.finalize_last_transaction();
+            }
+        }
+    })
+}
+
+/// The recoveries of the buffers that are open, and the restore offers that are waiting for an
+/// answer, so that each buffer has one of them no matter how many editors show it.
+#[derive(Default)]
+struct Recoveries {
+    tracked: HashMap<EntityId, WeakModel<BufferRecovery>>,
+    offers: HashMap<EntityId, Shared<Task<()>>>,
+}
+
+impl Global for Recoveries {}
+
+/// Offers to restore the unsaved changes that a previous session left behind for a file, unless
+/// they're gone, stale or the same as what's on disk. When the file's buffer is already being
+/// offered, the same offer is returned instead of asking again.
+pub(crate) fn offer_recovery(
+    fs: Arc<dyn Fs>,
+    buffer: Model<Buffer>,
+    path: PathBuf,
+    recovery_path: PathBuf,
+    cx: &mut ViewContext<Pane>,
+) -> Shared<Task<()>> {
+    let buffer_id = buffer.entity_id();
+    if let Some(offer) = cx.default_global::<Recoveries>().offers.get(&buffer_id) {
+        return offer.clone();
+    }
+
+    let offer = cx
+        .spawn(|pane, mut cx| async move {
+            async {
+                let Some(recovery) = read_recovery(fs.as_ref(), &recovery_path).await else {
+                    return Ok(());
+                };
+                let mtime = fs.metadata(&path).await.ok().flatten().map(|m| m.mtime);
+                let differs = buffer.update(&mut cx, |buffer, _| buffer.text() != recovery.text)?;
+                // Unsaved changes that are older than the file on disk were made to
+                // contents that don't exist anymore.
+                let restore = differs
+                    && mtime.map_or(true, |mtime| mtime <= recovery.written_at)
+                    && pane
+                        .update(&mut cx, |_, cx| {
+                            cx.prompt(
+                                PromptLevel::Warning,
+                                &format!(
+                                    "{} has unsaved changes from a previous session.",
+                                    path.compact().display()
+                                ),
+                                Some("Do you want to restore them?"),
+                                &["Restore", "Discard"],
+                            )
+                        })?
+                        .await
+                        == Ok(0);
+                if restore {
+                    restore_recovery(&buffer, recovery, &mut cx).await
+                } else {
+                    remove_recovery(fs.as_ref(), &recovery_path).await
+                }
+            }
+            .await
+            .log_err();
+            cx.update(|cx| {
+                cx.default_global::<Recoveries>().offers.remove(&buffer_id);
+            })
+            .ok();
+        })
+        .shared();
+    cx.default_global::<Recoveries>()
+        .offers
+        .insert(buffer_id, offer.clone());
+    offer
+}
+
+/// Keeps the recovery file of a buffer up to date. It's shared by all the editors of the buffer.
+pub(crate) struct BufferRecovery {
+    fs: Arc<dyn Fs>,
+    workspace_id: WorkspaceId,
+    /// The editor that started tracking the buffer, whose item id keys untitled buffers.
+    item_id: ItemId,
+    write_task: Option<Task<()>>,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl BufferRecovery {
+    /// Returns the recovery of the given buffer, starting to track it if nothing does yet.
+    fn for_buffer(
+        buffer: &Model<Buffer>,
+        fs: Arc<dyn Fs>,
+        workspace_id: WorkspaceId,
+        item_id: ItemId,
+        cx: &mut AppContext,
+    ) -> Model<Self> {
+        let buffer_id = buffer.entity_id();
+        if let Some(recovery) = cx
+            .default_global::<Recoveries>()
+            .tracked
+            .get(&buffer_id)
+            .and_then(|recovery| recovery.upgrade())
+        {
+            return recovery;
+        }
+
+        let recovery = cx.new_model(|cx| {
+            let recovery_id = cx.entity_id();
+            Self {
+                fs,
+                workspace_id,
+                item_id,
+                write_task: None,
+                _subscriptions: vec![
+                    cx.subscribe(buffer, Self::handle_buffer_event),
+                    cx.on_app_quit({
+                        let buffer = buffer.downgrade();
+                        move |this, cx| {
+                            let update = buffer.upgrade().map(|buffer| {
+                                cx.background_executor()
+                                    .spawn(this.update_file(&buffer, cx))
+                            });
+                            async move {
+                                if let Some(update) = update {
+                                    update.await;
+                                }
+                            }
+                        }
+                    }),
+                    cx.on_release(move |_, cx| {
+                        let tracked = &mut cx.default_global::<Recoveries>().tracked;
+                        if tracked
+                            .get(&buffer_id)
+                            .map_or(false, |recovery| recovery.entity_id() == recovery_id)
+                        {
+                            tracked.remove(&buffer_id);
+                        }
+                    }),
+                ],
+            }
+        });
+        cx.default_global::<Recoveries>()
+            .tracked
+            .insert(buffer_id, recovery.downgrade());
+        recovery
+    }
+
+    fn recovery_path(&self, buffer: &Model<Buffer>, cx: &AppContext) -> PathBuf {
+        let path = buffer
+            .read(cx)
+            .file()
+            .and_then(|file| file.as_local())
+            .map(|file| file.abs_path(cx));
+        recovery_file_path(self.workspace_id, path.as_deref(), self.item_id)
+    }
+
+    fn update_file(&self, buffer: &Model<Buffer>, cx: &AppContext) -> BoxFuture<'static, ()> {
+        update_recovery_file(self.fs.clone(), buffer, self.recovery_path(buffer, cx), cx)
+    }
+
+    fn handle_buffer_event(
+        &mut self,
+        buffer: Model<Buffer>,
+        event: &language::Event,
+        cx: &mut ModelContext<Self>,
+    ) {
+        match event {
+            language::Event::Edited => {
+                self.write_task = Some(cx.spawn(|this, mut cx| async move {
+                    cx.background_executor().timer(RECOVERY_DEBOUNCE).await;
+                    if let Ok(update) = this.update(&mut cx, |this, cx| {
+                        cx.background_executor()
+                            .spawn(this.update_file(&buffer, cx))
+                    }) {
+                        update.await;
+                    }
+                }));
+            }
+            language::Event::DirtyChanged | language::Event::Saved | language::Event::Reloaded => {
+                let update = self.update_file(&buffer, cx);
+                self.write_task = Some(cx.background_executor().spawn(update));
+            }
+            language::Event::FileHandleChanged => {
+                // An untitled buffer that got saved is now recovered under its path.
+                let fs = self.fs.clone();
+                let untitled_path = recovery_file_path(self.workspace_id, None, self.item_id);
+                cx.background_executor()
+                    .spawn(async move {
+                        remove_recovery(fs.as_ref(), &untitled_path).await.log_err();
+                    })
+                    .detach();
+            }
+            _ => {}
+        }
+    }
+
+    /// Removes the recovery file, as the buffer's unsaved changes were thrown away. A write
+    /// that is underway finishes first, so that it doesn't bring the file back.
+    fn discard(&mut self, buffer: &Model<Buffer>, cx: &mut ModelContext<Self>) {
+        let fs = self.fs.clone();
+        let recovery_path = self.recovery_path(buffer, cx);
+        let write_task = self.write_task.take();
+        cx.background_executor()
+            .spawn(async move {
+                if let Some(write_task) = write_task {
+                    write_task.await;
+                }
+                remove_recovery(fs.as_ref(), &recovery_path).await.log_err();
+            })
+            .detach();
+    }
+}
+
+impl Editor {
+    /// Starts keeping the recovery file of this editor's buffer up to date. Only singleton
+    /// buffers in local projects are tracked, remote buffers are recovered by their host.
+    pub(crate) fn track_recovery(&mut self, workspace_id: WorkspaceId, cx: &mut ViewContext<Self>) {
+        let Some(buffer) = self.buffer().read(cx).as_singleton() else {
+            return;
+        };
+        let Some(project) = self.project.as_ref() else {
+            return;
+        };
+        if !project.read(cx).is_local() {
+            return;
+        }
+
+        let fs = project.read(cx).fs().clone();
+        let item_id = cx.view().item_id().as_u64() as ItemId;
+        self.recovery = Some(BufferRecovery::for_buffer(
+            &buffer,
+            fs,
+            workspace_id,
+            item_id,
+            cx,
+        ));
+    }
+
+    /// Forgets the unsaved changes of this editor's buffer, which the user decided to throw away.
+    pub(crate) fn discard_recovery(&mut self, cx: &mut ViewContext<Self>) {
+        let Some(buffer) = self.buffer().read(cx).as_singleton() else {
+            return;
+        };
+        if let Some(recovery) = &self.recovery {
+            recovery.update(cx, |recovery, cx| recovery.discard(&buffer, cx));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::{Context as _, TestAppContext};
+    use project::FakeFs;
+    use std::time::UNIX_EPOCH;
+
+    #[gpui::test]
+    async fn test_recovery_file_round_trip(cx: &mut TestAppContext) {
+        let fs = FakeFs::new(cx.executor());
+        let recovery_path = Path::new("/recovery/1/untitled-3.json");
+        let recovery = Recovery {
+            path: Some(PathBuf::from("/root/a.txt")),
+            text: "unsaved\ntext".into(),
+            written_at: UNIX_EPOCH + Duration::from_secs(100),
+            undo_stack: vec![RecoveredEdit {
+                offset: 0,
+                old_text: String::new(),
+                new_text: "unsaved".into(),
+            }],
+        };
+
+        assert_eq!(read_recovery(fs.as_ref(), recovery_path).await, None);
+        write_recovery(fs.as_ref(), recovery_path, &recovery)
+            .await
+            .unwrap();
+        assert_eq!(
+            read_recovery(fs.as_ref(), recovery_path).await,
+            Some(recovery)
+        );
+
+        remove_recovery(fs.as_ref(), recovery_path).await.unwrap();
+        assert_eq!(read_recovery(fs.as_ref(), recovery_path).await, None);
+        remove_recovery(fs.as_ref(), recovery_path).await.unwrap();
+    }
+
+    #[gpui::test]
+    async fn test_corrupted_recovery_file_is_removed(cx: &mut TestAppContext) {
+        let fs = FakeFs::new(cx.executor());
+        let recovery_path = Path::new("/recovery/1/untitled-3.json");
+        fs.insert_file(recovery_path, b"{\"text\": \"trunc".to_vec())
+            .await;
+
+        assert_eq!(read_recovery(fs.as_ref(), recovery_path).await, None);
+        assert!(!fs.is_file(recovery_path).await);
+    }
+
+    #[gpui::test]
+    async fn test_update_recovery_file(cx: &mut TestAppContext) {
+        let fs = FakeFs::new(cx.executor());
+        let recovery_path = PathBuf::from("/recovery/1/untitled-3.json");
+        let buffer = cx.new_model(|cx| Buffer::local("", cx));
+
+        buffer.update(cx, |buffer, cx| buffer.set_text("unsaved", cx));
+        cx.update(|cx| update_recovery_file(fs.clone(), &buffer, recovery_path.clone(), cx))
+            .await;
+        let recovery = read_recovery(fs.as_ref(), &recovery_path).await.unwrap();
+        assert_eq!(recovery.path, None);
+        assert_eq!(recovery.text, "unsaved");
+
+        // Buffers that are too large to keep around don't get a recovery file.
+        buffer.update(cx, |buffer, cx| {
+            buffer.set_text("a".repeat(MAX_RECOVERY_LEN + 1), cx)
+        });
+        cx.update(|cx| update_recovery_file(fs.clone(), &buffer, recovery_path.clone(), cx))
+            .await;
+        assert!(!fs.is_file(&recovery_path).await);
+
+        // Neither do buffers without unsaved changes.
+        buffer.update(cx, |buffer, cx| buffer.set_text("unsaved", cx));
+        cx.update(|cx| update_recovery_file(fs.clone(), &buffer, recovery_path.clone(), cx))
+            .await;
+        assert!(fs.is_file(&recovery_path).await);
+        buffer.update(cx, |buffer, cx| {
+            let version = buffer.version();
+            buffer.did_save(version, None, cx);
+        });
+        cx.update(|cx| update_recovery_file(fs.clone(), &buffer, recovery_path.clone(), cx))
+            .await;
+        assert!(!fs.is_file(&recovery_path).await);
+    }
+
+    #[gpui::test]
+    async fn test_recovering_undo_stack(cx: &mut TestAppContext) {
+        let fs = FakeFs::new(cx.executor());
+        let recovery_path = PathBuf::from("/recovery/1/untitled-3.json");
+        let buffer = cx.new_model(|cx| Buffer::local("one two", cx));
+        buffer.update(cx, |buffer, cx| {
+            buffer.edit([(3..3, " and a half")], None, cx);
+            buffer.finalize_last_transaction();
+            buffer.edit([(0..3, "ONE")], None, cx);
+            buffer.finalize_last_transaction();
+        });
+        cx.update(|cx| update_recovery_file(fs.clone(), &buffer, recovery_path.clone(), cx))
+            .await;
+        let recovery = read_recovery(fs.as_ref(), &recovery_path).await.unwrap();
+        assert_eq!(recovery.text, "ONE and a half two");
+        assert_eq!(
+            recovery.undo_stack,
+            vec![
+                RecoveredEdit {
+                    offset: 4,
+                    old_text: String::new(),
+                    new_text: "and a half ".into(),
+                },
+                RecoveredEdit {
+                    offset: 0,
+                    old_text: "one".into(),
+                    new_text: "ONE".into(),
+                },
+            ]
+        );
+        assert_eq!(
+            text_before_edits(&recovery.text, &recovery.undo_stack).as_deref(),
+            Some("one two")
+        );
+        assert_eq!(text_before_edits("ONE", &recovery.undo_stack), None);
+
+        // The file changed on disk since, so restoring it first goes back to the text the
+        // recovered transactions were made to.
+        let restored = cx.new_model(|cx| Buffer::local("stale", cx));
+        let cx = cx.add_empty_window();
+        cx.update(|cx| {
+            let restored = restored.clone();
+            cx.spawn(|mut cx| async move { restore_recovery(&restored, recovery, &mut cx).await })
+        })
+        .await
+        .unwrap();
+        restored.update(cx, |buffer, cx| {
+            assert_eq!(buffer.text(), "ONE and a half two");
+            buffer.undo(cx);
+            assert_eq!(buffer.text(), "one and a half two");
+            buffer.undo(cx);
+            assert_eq!(buffer.text(), "one two");
+            buffer.undo(cx);
+            assert_eq!(buffer.text(), "stale");
+        });
+    }
+
+    #[gpui::test]
+    async fn test_buffer_recovery_is_shared_and_discarded(cx: &mut TestAppContext) {
+        let fs = FakeFs::new(cx.executor());
+        let buffer = cx.new_model(|cx| Buffer::local("", cx));
+        let workspace_id = WorkspaceId::default();
+        let (first, second) = cx.update(|cx| {
+            (
+                BufferRecovery::for_buffer(&buffer, fs.clone(), workspace_id, 1, cx),
+                BufferRecovery::for_buffer(&buffer, fs.clone(), workspace_id, 2, cx),
+            )
+        });
+        assert_eq!(first.entity_id(), second.entity_id());
+
+        // Untitled buffers are recovered under the editor that started tracking them.
+        let recovery_path = recovery_file_path(workspace_id, None, 1);
+        buffer.update(cx, |buffer, cx| buffer.set_text("unsaved", cx));
+        cx.executor().advance_clock(RECOVERY_DEBOUNCE);
+        cx.run_until_parked();
+        assert!(fs.is_file(&recovery_path).await);
+        assert!(!fs.is_file(&recovery_file_path(workspace_id, None, 2)).await);
+
+        // A pending write doesn't bring the file back after the changes were discarded.
+        buffer.update(cx, |buffer, cx| buffer.set_text("more", cx));
+        second.update(cx, |recovery, cx| recovery.discard(&buffer, cx));
+        cx.executor().advance_clock(RECOVERY_DEBOUNCE);
+        cx.run_until_parked();
+        assert!(!fs.is_file(&recovery_path).await);
+    }
+}
@@ -0,0 +1,186 @@
+//! Saves of editors go through [`Project::save_buffer_with_progress`], so that saves which take a
+//! while, like those of large files or slow drives, show how far along they are and can be
+//! cancelled.
+
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use collections::HashSet;
+use futures::{future::join_all, FutureExt as _};
+use gpui::{
+    AsyncWindowContext, DismissEvent, Entity as _, EventEmitter, Model, Render, Task,
+    VisualContext as _, WeakView,
+};
+use language::Buffer;
+use project::{Project, SaveOutcome, SaveProgress};
+use ui::prelude::*;
+use workspace::notifications::NotificationId;
+
+use crate::Editor;
+
+/// Saves that are done sooner than this don't show their progress.
+const SHOW_PROGRESS_DELAY: Duration = Duration::from_millis(500);
+
+/// How often the shown progress gets updated.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Saves the buffers of the editor, overwriting files that conflict with them, as the user has
+/// already been asked about those. Cancelled saves leave the files alone and aren't errors.
+pub(crate) async fn save_buffers_with_progress(
+    editor: &WeakView<Editor>,
+    project: &Model<Project>,
+    buffers: HashSet<Model<Buffer>>,
+    cx: &mut AsyncWindowContext,
+) -> Result<()> {
+    if buffers.is_empty() {
+        return Ok(());
+    }
+
+    let mut progresses = Vec::with_capacity(buffers.len());
+    let mut names = Vec::with_capacity(buffers.len());
+    let saves = project.update(cx, |project, cx| {
+        buffers
+            .into_iter()
+            .map(|buffer| {
+                let progress = SaveProgress::default();
+                progresses.push(progress.clone());
+                names.push(
+                    buffer
+                        .read(cx)
+                        .file()
+                        .map(|file| file.path().to_string_lossy().to_string())
+                        .unwrap_or_else(|| "untitled".to_string()),
+                );
+                project.save_buffer_with_progress(buffer, progress, true, cx)
+            })
+            .collect::<Vec<_>>()
+    })?;
+
+    let mut saves = join_all(saves).fuse();
+    let mut delay = cx.background_executor().timer(SHOW_PROGRESS_DELAY).fuse();
+    let outcomes = futures::select_biased! {
+        outcomes = saves => outcomes,
+        () = delay => {
+            let notification_id = show_progress(editor, progresses, &names, cx);
+            let outcomes = saves.await;
+            if let Some(notification_id) = notification_id {
+                editor
+                    .update(cx, |editor, cx| {
+                        if let Some(workspace) = editor.workspace() {
+                            workspace.update(cx, |workspace, cx| {
+                                workspace.dismiss_notification(&notification_id, cx)
+                            });
+                        }
+                    })
+                    .ok();
+            }
+            outcomes
+        }
+    };
+
+    let mut errors = outcomes
+        .into_iter()
+        .zip(names)
+        .filter_map(|(outcome, name)| match outcome {
+            SaveOutcome::Saved { .. } | SaveOutcome::Cancelled => None,
+            SaveOutcome::Conflict => Some(anyhow!("{name} changed on disk")),
+            SaveOutcome::IoError(error) => Some(error.context(format!("saving {name}"))),
+        });
+    match errors.next() {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
+}
+
+fn show_progress(
+    editor: &WeakView<Editor>,
+    progresses: Vec<SaveProgress>,
+    names: &[String],
+    cx: &mut AsyncWindowContext,
+) -> Option<NotificationId> {
+    let title = match names {
+        [name] => name.clone(),
+        names => format!("{} files", names.len()),
+    };
+    editor
+        .update(cx, |editor, cx| {
+            let workspace = editor.workspace()?;
+            let notification_id =
+                NotificationId::identified::<SaveProgressNotification>(cx.view().entity_id());
+            workspace.update(cx, |workspace, cx| {
+                workspace.show_notification(notification_id.clone(), cx, |cx| {
+                    cx.new_view(|cx| SaveProgressNotification::new(title, progresses, cx))
+                })
+            });
+            Some(notification_id)
+        })
+        .ok()
+        .flatten()
+}
+
+/// Shows how much of the files being saved has been written, with a button that cancels the
+/// saves.
+struct SaveProgressNotification {
+    title: String,
+    progresses: Vec<SaveProgress>,
+    _refresh: Task<()>,
+}
+
+impl SaveProgressNotification {
+    fn new(title: String, progresses: Vec<SaveProgress>, cx: &mut ViewContext<Self>) -> Self {
+        let refresh = cx.spawn(|this, mut cx| async move {
+            loop {
+                cx.background_executor().timer(PROGRESS_INTERVAL).await;
+                if this.update(&mut cx, |_, cx| cx.notify()).is_err() {
+                    break;
+                }
+            }
+        });
+        Self {
+            title,
+            progresses,
+            _refresh: refresh,
+        }
+    }
+
+    fn percent_written(&self) -> usize {
+        let (written, total) = self
+            .progresses
+            .iter()
+            .map(SaveProgress::written)
+            .fold((0, 0), |(written, total), (w, t)| (written + w, total + t));
+        if total == 0 {
+            0
+        } else {
+            written * 100 / total
+        }
+    }
+
+    fn cancel(&mut self, cx: &mut ViewContext<Self>) {
+        for progress in &self.progresses {
+            progress.cancel();
+        }
+        cx.emit(DismissEvent);
+    }
+}
+
+impl EventEmitter<DismissEvent> for SaveProgressNotification {}
+
+impl Render for SaveProgressNotification {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        h_flex()
+            .elevation_3(cx)
+            .p_4()
+            .gap_3()
+            .justify_between()
+            .child(div().max_w_80().child(Label::new(format!(
+                "Saving {}… {}%",
+                self.title,
+                self.percent_written()
+            ))))
+            .child(
+                Button::new("cancel-save", "Cancel")
+                    .on_click(cx.listener(|this, _, cx| this.cancel(cx))),
+            )
+    }
+}
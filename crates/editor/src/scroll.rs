@@ -7,7 +7,7 @@ use crate::{
     hover_popover::hide_hover,
     persistence::DB,
     Anchor, DisplayPoint, DisplayRow, Editor, EditorEvent, EditorMode, EditorSettings,
-    InlayHintRefreshReason, MultiBufferSnapshot, RowExt, ToPoint,
+    InlayHintRefreshReason, MultiBufferSnapshot, RowExt, ScrollBeyondLastLine, ToPoint,
 };
 pub use autoscroll::{Autoscroll, AutoscrollStrategy};
 use gpui::{point, px, AppContext, Entity, Global, Pixels, Task, ViewContext, WindowContext};
@@ -330,6 +330,30 @@ impl Editor {
         self.scroll_manager.visible_line_count
     }
 
+    /// The greatest scroll position, in display rows, that the editor can be scrolled to
+    /// when `visible_lines` rows fit in it. Display rows already account for soft wraps
+    /// and folds, so those don't need to be considered separately.
+    pub(crate) fn max_scroll_top(
+        &self,
+        map: &DisplaySnapshot,
+        visible_lines: f32,
+        cx: &AppContext,
+    ) -> f32 {
+        let max_row = map.max_point().row().as_f32();
+        let scroll_beyond_last_line = if matches!(self.mode, EditorMode::AutoHeight { .. }) {
+            ScrollBeyondLastLine::Off
+        } else {
+            EditorSettings::get_global(cx).scroll_beyond_last_line
+        };
+        match scroll_beyond_last_line {
+            ScrollBeyondLastLine::OnePage => max_row,
+            ScrollBeyondLastLine::Off => (max_row - visible_lines + 1.).max(0.),
+            ScrollBeyondLastLine::VerticalScrollMargin => {
+                (max_row - visible_lines + 1. + self.scroll_manager.vertical_scroll_margin).max(0.)
+            }
+        }
+    }
+
     pub(crate) fn set_visible_line_count(&mut self, lines: f32, cx: &mut ViewContext<Self>) {
         let opened_first_time = self.scroll_manager.visible_line_count.is_none();
         self.scroll_manager.visible_line_count = Some(lines);
@@ -27,6 +27,12 @@ impl Autoscroll {
         Self::Strategy(AutoscrollStrategy::Center)
     }
 
+    /// scrolls so the newest cursor is vertically centered, unless it's
+    /// already onscreen (outside of the vertical_scroll_margin)
+    pub fn center_if_offscreen() -> Self {
+        Self::Strategy(AutoscrollStrategy::CenterIfOffscreen)
+    }
+
     /// scrolls so the neweset cursor is near the top
     /// (offset by vertical_scroll_margin)
     pub fn focused() -> Self {
@@ -44,6 +50,7 @@ pub enum AutoscrollStrategy {
     Newest,
     #[default]
     Center,
+    CenterIfOffscreen,
     Focused,
     Top,
     Bottom,
@@ -84,11 +91,7 @@ impl Editor {
                 }
             }
         }
-        let max_scroll_top = if matches!(self.mode, EditorMode::AutoHeight { .. }) {
-            (display_map.max_point().row().as_f32() - visible_lines + 1.).max(0.)
-        } else {
-            display_map.max_point().row().as_f32()
-        };
+        let max_scroll_top = self.max_scroll_top(&display_map, visible_lines, cx);
         if scroll_position.y > max_scroll_top {
             scroll_position.y = max_scroll_top;
         }
@@ -128,6 +131,7 @@ impl Editor {
 
             // If the selections can't all fit on screen, scroll to the newest.
             if autoscroll == Autoscroll::newest()
+                || autoscroll == Autoscroll::center_if_offscreen()
                 || autoscroll == Autoscroll::fit() && target_bottom - target_top > visible_lines
             {
                 let newest_selection_top = selections
@@ -192,6 +196,17 @@ impl Editor {
                 scroll_position.y = (target_top - margin).max(0.0);
                 self.set_scroll_position_internal(scroll_position, local, true, cx);
             }
+            AutoscrollStrategy::CenterIfOffscreen => {
+                let scroll_margin = margin.min(self.scroll_manager.vertical_scroll_margin);
+                let start_row = scroll_position.y;
+                let end_row = start_row + visible_lines;
+                let onscreen = (target_top - scroll_margin).max(0.0) >= start_row
+                    && target_bottom + scroll_margin < end_row;
+                if !onscreen {
+                    scroll_position.y = (target_top - margin).max(0.0);
+                    self.set_scroll_position_internal(scroll_position, local, true, cx);
+                }
+            }
             AutoscrollStrategy::Focused => {
                 scroll_position.y =
                     (target_top - self.scroll_manager.vertical_scroll_margin).max(0.0);
@@ -0,0 +1,336 @@
+use std::{ops::Range, sync::Arc, time::Duration};
+
+use gpui::{px, AppContext, HighlightStyle, Model, Task, UnderlineStyle, ViewContext};
+use language::{language_settings::language_settings, Buffer, BufferSnapshot, Language, Point};
+use multi_buffer::{Anchor, ToOffset};
+use spell_check::{SpellChecker, UserDictionary};
+use text::ToOffset as _;
+use theme::ActiveTheme;
+use util::ResultExt;
+
+use crate::{
+    actions::{AddWordToDictionary, CorrectSpelling},
+    mouse_context_menu::MouseContextMenu,
+    Editor, EditorMode,
+};
+
+/// How long to wait after an edit before checking the edited lines.
+pub(crate) const SPELL_CHECK_DEBOUNCE: Duration = Duration::from_millis(250);
+
+const MAX_SUGGESTIONS: usize = 8;
+
+/// The key of the highlights that underline misspelled words.
+pub(crate) enum Misspelling {}
+
+/// The misspelled words of an editor's buffer. After the whole buffer has been checked once,
+/// only the lines edited since the last check get checked again.
+#[derive(Default)]
+pub(crate) struct Spelling {
+    /// The ranges of the misspelled words, in order.
+    misspellings: Vec<Range<Anchor>>,
+    /// The version of the buffer as of the last check, once the whole buffer was checked.
+    checked_version: Option<clock::Global>,
+    /// Whether the settings of the buffer enable spell checking, as of the last check.
+    enabled: bool,
+    check_task: Option<Task<()>>,
+}
+
+/// Returns whether all text of a buffer with the given language gets checked, except for its code
+/// blocks, rather than only its comments and strings.
+fn is_prose(language: Option<&Arc<Language>>) -> bool {
+    language.map_or(true, |language| {
+        language.grammar().is_none() || language.name().as_ref() == "Markdown"
+    })
+}
+
+/// Returns whether a word is spelled like a word of prose, rather than like an identifier or a
+/// number.
+fn is_checked_word(word: &str) -> bool {
+    word.chars()
+        .all(|c| c.is_alphabetic() || c == '\'' || c == '’')
+        && !word.chars().skip(1).any(char::is_uppercase)
+}
+
+/// Returns the ranges of the lines edited since the given version, without overlaps.
+fn edited_lines(snapshot: &BufferSnapshot, since: &clock::Global) -> Vec<Range<usize>> {
+    let mut lines = Vec::<Range<usize>>::new();
+    for edit in snapshot.edits_since::<Point>(since) {
+        let start = snapshot.point_to_offset(Point::new(edit.new.start.row, 0));
+        let end = snapshot.point_to_offset(Point::new(
+            edit.new.end.row,
+            snapshot.line_len(edit.new.end.row),
+        ));
+        match lines.last_mut() {
+            Some(last) if start <= last.end => last.end = last.end.max(end),
+            _ => lines.push(start..end),
+        }
+    }
+    lines
+}
+
+/// Returns the misspelled words of the given ranges of a buffer.
+fn find_misspellings(
+    snapshot: &BufferSnapshot,
+    ranges: &[Range<usize>],
+    checker: &dyn SpellChecker,
+    dictionary: &collections::HashSet<String>,
+) -> Vec<Range<usize>> {
+    let is_prose = is_prose(snapshot.language());
+    let mut misspellings = Vec::new();
+    for range in ranges {
+        let ranges_to_check = if is_prose {
+            let mut ranges_to_check = Vec::new();
+            let mut start = range.start;
+            for code_block in snapshot.code_block_ranges(range.clone()) {
+                if start < code_block.start {
+                    ranges_to_check.push(start..code_block.start);
+                }
+                start = code_block.end;
+            }
+            if start < range.end {
+                ranges_to_check.push(start..range.end);
+            }
+            ranges_to_check
+        } else {
+            snapshot.comment_and_string_ranges(range.clone())
+        };
+        for range in ranges_to_check {
+            let text = snapshot.text_for_range(range.clone()).collect::<String>();
+            for word_range in checker.misspelled_words(&text) {
+                let word = &text[word_range.clone()];
+                if is_checked_word(word) && !UserDictionary::contains(dictionary, word) {
+                    misspellings.push(range.start + word_range.start..range.start + word_range.end);
+                }
+            }
+        }
+    }
+    misspellings
+}
+
+impl Editor {
+    fn spell_checked_buffer(
+        &self,
+        cx: &AppContext,
+    ) -> Option<(Arc<dyn SpellChecker>, Model<Buffer>)> {
+        if self.mode != EditorMode::Full {
+            return None;
+        }
+        let checker = spell_check::global(cx)?;
+        let buffer = self.buffer.read(cx).as_singleton()?;
+        let enabled = {
+            let buffer = buffer.read(cx);
+            language_settings(buffer.language(), buffer.file(), cx).spell_check
+        };
+        enabled.then_some((checker, buffer))
+    }
+
+    /// Checks the whole buffer again, such as after the words of the dictionary changed.
+    pub(crate) fn recheck_spelling(&mut self, cx: &mut ViewContext<Self>) {
+        self.spelling.checked_version = None;
+        self.refresh_spelling(false, cx);
+    }
+
+    /// Checks the whole buffer again if the settings changed whether it gets checked.
+    pub(crate) fn spelling_settings_changed(&mut self, cx: &mut ViewContext<Self>) {
+        if self.spell_checked_buffer(cx).is_some() != self.spelling.enabled {
+            self.recheck_spelling(cx);
+        }
+    }
+
+    /// Checks the lines that were edited since the last check, or the whole buffer if it wasn't
+    /// checked yet.
+    pub(crate) fn refresh_spelling(&mut self, debounce: bool, cx: &mut ViewContext<Self>) {
+        let Some((checker, buffer)) = self.spell_checked_buffer(cx) else {
+            if self.spelling.enabled {
+                self.spelling = Spelling::default();
+                self.clear_highlights::<Misspelling>(cx);
+            }
+            return;
+        };
+        self.spelling.enabled = true;
+        let snapshot = buffer.read(cx).snapshot();
+        // Until a buffer with a grammar gets parsed, it's unknown where its comments and code
+        // blocks are.
+        let has_grammar = snapshot
+            .language()
+            .map_or(false, |language| language.grammar().is_some());
+        if has_grammar && snapshot.syntax_layers().next().is_none() {
+            return;
+        }
+
+        let dictionary = UserDictionary::words(cx);
+        let checked_version = self.spelling.checked_version.clone();
+        self.spelling.check_task = Some(cx.spawn(|this, mut cx| async move {
+            if debounce {
+                cx.background_executor().timer(SPELL_CHECK_DEBOUNCE).await;
+            }
+            let (version, checked_ranges, misspellings) = cx
+                .background_executor()
+                .spawn(async move {
+                    let ranges = match &checked_version {
+                        Some(checked_version) => edited_lines(&snapshot, checked_version),
+                        None => vec![0..snapshot.len()],
+                    };
+                    let misspellings =
+                        find_misspellings(&snapshot, &ranges, checker.as_ref(), &dictionary);
+                    let anchor_range = |range: &Range<usize>| {
+                        snapshot.anchor_after(range.start)..snapshot.anchor_before(range.end)
+                    };
+                    (
+                        snapshot.version().clone(),
+                        ranges.iter().map(anchor_range).collect::<Vec<_>>(),
+                        misspellings.iter().map(anchor_range).collect::<Vec<_>>(),
+                    )
+                })
+                .await;
+            this.update(&mut cx, |this, cx| {
+                this.spelling_checked(version, checked_ranges, misspellings, cx)
+            })
+            .log_err();
+        }));
+    }
+
+    fn spelling_checked(
+        &mut self,
+        version: clock::Global,
+        checked_ranges: Vec<Range<text::Anchor>>,
+        found_misspellings: Vec<Range<text::Anchor>>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.spelling.check_task = None;
+        let multi_buffer = self.buffer.read(cx).snapshot(cx);
+        let Some((&excerpt_id, _, buffer)) = multi_buffer.as_singleton() else {
+            return;
+        };
+
+        // Misspellings in the lines that were checked again are replaced by the ones found there,
+        // while the words that got deleted entirely are dropped.
+        let checked_ranges = checked_ranges
+            .iter()
+            .map(|range| range.start.to_offset(buffer)..range.end.to_offset(buffer))
+            .collect::<Vec<_>>();
+        let mut misspellings = self
+            .spelling
+            .misspellings
+            .iter()
+            .filter(|misspelling| {
+                let start = misspelling.start.to_offset(&multi_buffer);
+                let end = misspelling.end.to_offset(&multi_buffer);
+                start < end
+                    && !checked_ranges
+                        .iter()
+                        .any(|range| start <= range.end && end >= range.start)
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        misspellings.extend(found_misspellings.into_iter().filter_map(|range| {
+            Some(
+                multi_buffer.anchor_in_excerpt(excerpt_id, range.start)?
+                    ..multi_buffer.anchor_in_excerpt(excerpt_id, range.end)?,
+            )
+        }));
+        misspellings.sort_by(|a, b| a.start.cmp(&b.start, &multi_buffer));
+
+        self.spelling.misspellings = misspellings;
+        self.spelling.checked_version = Some(version);
+        let style = HighlightStyle {
+            underline: Some(UnderlineStyle {
+                color: Some(cx.theme().status().error),
+                thickness: px(1.),
+                wavy: true,
+            }),
+            ..Default::default()
+        };
+        self.highlight_text::<Misspelling>(self.spelling.misspellings.clone(), style, cx);
+    }
+
+    /// Returns the misspelled words of the buffer, in order.
+    pub fn misspellings(&self) -> &[Range<Anchor>] {
+        &self.spelling.misspellings
+    }
+
+    /// Returns the misspelled word that the newest cursor is in or next to.
+    pub fn misspelling_at_cursor(&self, cx: &AppContext) -> Option<Range<Anchor>> {
+        let buffer = self.buffer.read(cx).snapshot(cx);
+        let head = self.selections.newest_anchor().head().to_offset(&buffer);
+        self.spelling
+            .misspellings
+            .iter()
+            .find(|misspelling| {
+                misspelling.start.to_offset(&buffer) <= head
+                    && head <= misspelling.end.to_offset(&buffer)
+            })
+            .cloned()
+    }
+
+    /// Shows the words that the misspelled word at the newest cursor might have been meant to be,
+    /// to replace it with one of them.
+    pub fn correct_spelling(&mut self, _: &CorrectSpelling, cx: &mut ViewContext<Self>) {
+        let Some(checker) = spell_check::global(cx) else {
+            return;
+        };
+        let Some(range) = self.misspelling_at_cursor(cx) else {
+            return;
+        };
+        let word = self
+            .buffer
+            .read(cx)
+            .snapshot(cx)
+            .text_for_range(range.clone())
+            .collect::<String>();
+        let suggestions = checker.suggestions(&word);
+
+        let editor = cx.view().downgrade();
+        let context_menu = ui::ContextMenu::build(cx, move |mut menu, _| {
+            if suggestions.is_empty() {
+                menu = menu.header("No Suggestions");
+            }
+            for suggestion in suggestions.into_iter().take(MAX_SUGGESTIONS) {
+                let editor = editor.clone();
+                let range = range.clone();
+                menu = menu.entry(suggestion.clone(), None, move |cx| {
+                    editor
+                        .update(cx, |editor, cx| {
+                            editor.replace_misspelling(range.clone(), &suggestion, cx)
+                        })
+                        .ok();
+                });
+            }
+            menu.separator()
+                .entry("Add to Dictionary", None, move |cx| {
+                    editor
+                        .update(cx, |editor, cx| {
+                            editor.add_word_to_dictionary(&AddWordToDictionary, cx)
+                        })
+                        .ok();
+                })
+        });
+        let position = self.pixel_position_of_newest_cursor.unwrap_or_default();
+        self.mouse_context_menu = Some(MouseContextMenu::new(position, context_menu, cx));
+        cx.notify();
+    }
+
+    /// Replaces a misspelled word with the given one, as an edit that can be undone.
+    pub fn replace_misspelling(
+        &mut self,
+        range: Range<Anchor>,
+        replacement: &str,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.transact(cx, |editor, cx| editor.edit([(range, replacement)], cx));
+    }
+
+    /// Adds the misspelled word at the newest cursor to the user's dictionary.
+    pub fn add_word_to_dictionary(&mut self, _: &AddWordToDictionary, cx: &mut ViewContext<Self>) {
+        let Some(range) = self.misspelling_at_cursor(cx) else {
+            return;
+        };
+        let word = self
+            .buffer
+            .read(cx)
+            .snapshot(cx)
+            .text_for_range(range)
+            .collect::<String>();
+        UserDictionary::add_word(&word, cx).detach_and_log_err(cx);
+    }
+}
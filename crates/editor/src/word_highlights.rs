@@ -0,0 +1,184 @@
+use std::{ops::Range, time::Duration};
+
+use gpui::ViewContext;
+use language::{is_word_boundary, CharKind};
+use multi_buffer::{Anchor, MultiBufferSnapshot};
+use settings::Settings as _;
+use text::Bias;
+
+use crate::{DisplayPoint, DisplayRow, Editor, EditorSettings};
+
+/// How long the cursor has to rest in a word before its other occurrences get highlighted.
+pub const WORD_HIGHLIGHTS_DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// Words shorter than this are too common for their occurrences to be worth highlighting.
+const MIN_WORD_CHARS: usize = 3;
+
+/// How many rows above and below the visible ones are searched for occurrences.
+const SEARCH_MARGIN_ROWS: u32 = 100;
+
+pub enum WordHighlight {}
+
+/// Highlights the other occurrences of the word the newest cursor is in, in and around the
+/// visible rows, once the cursor rested there for [`WORD_HIGHLIGHTS_DEBOUNCE`].
+pub fn refresh_word_highlights(editor: &mut Editor, cx: &mut ViewContext<Editor>) {
+    editor.clear_background_highlights::<WordHighlight>(cx);
+    editor.word_highlights_task = None;
+    if !EditorSettings::get_global(cx).word_highlights {
+        return;
+    }
+
+    let newest_selection = editor.selections.newest::<usize>(cx);
+    // Don't highlight words while text is selected
+    if !newest_selection.is_empty() {
+        return;
+    }
+
+    let snapshot = editor.snapshot(cx);
+    // The visible rows of a long line hold too much text to search for occurrences.
+    if snapshot.has_long_lines() {
+        return;
+    }
+    let buffer = snapshot.buffer_snapshot.clone();
+    let head = newest_selection.head();
+    let (word_range, kind) = buffer.surrounding_word(head);
+    if kind != Some(CharKind::Word)
+        || buffer.text_stats_for_range(word_range.clone()).chars < MIN_WORD_CHARS
+    {
+        return;
+    }
+
+    let display_snapshot = &snapshot.display_snapshot;
+    let scroll_top = snapshot.scroll_position().y as u32;
+    let visible_rows = editor.visible_line_count().unwrap_or(0.) as u32;
+    let search_start =
+        DisplayPoint::new(DisplayRow(scroll_top.saturating_sub(SEARCH_MARGIN_ROWS)), 0);
+    let search_end = display_snapshot.clip_point(
+        DisplayPoint::new(
+            DisplayRow(scroll_top + visible_rows + SEARCH_MARGIN_ROWS),
+            u32::MAX,
+        ),
+        Bias::Left,
+    );
+    let search_range = search_start.to_offset(display_snapshot, Bias::Left)
+        ..search_end.to_offset(display_snapshot, Bias::Right);
+
+    editor.word_highlights_task = Some(cx.spawn(|editor, mut cx| async move {
+        cx.background_executor()
+            .timer(WORD_HIGHLIGHTS_DEBOUNCE)
+            .await;
+
+        let occurrences = cx
+            .background_executor()
+            .spawn(async move { word_occurrences(&buffer, word_range, search_range) })
+            .await;
+
+        editor
+            .update(&mut cx, |editor, cx| {
+                editor.highlight_background::<WordHighlight>(
+                    &occurrences,
+                    |theme| theme.editor_word_highlight_background,
+                    cx,
+                );
+            })
+            .ok();
+    }));
+}
+
+/// Finds the whole-word, case-sensitive occurrences of the word at `word_range` within
+/// `search_range`, other than the word itself.
+fn word_occurrences(
+    buffer: &MultiBufferSnapshot,
+    word_range: Range<usize>,
+    search_range: Range<usize>,
+) -> Vec<Range<Anchor>> {
+    let word = buffer
+        .text_for_range(word_range.clone())
+        .collect::<String>();
+    let text = buffer
+        .text_for_range(search_range.clone())
+        .collect::<String>();
+    let scope = buffer.language_scope_at(word_range.start);
+    let (Some(first_char), Some(last_char)) = (word.chars().next(), word.chars().last()) else {
+        return Vec::new();
+    };
+
+    text.match_indices(&word)
+        .filter_map(|(ix, _)| {
+            let start = search_range.start + ix;
+            let end = start + word.len();
+            if start == word_range.start {
+                return None;
+            }
+
+            let starts_word = buffer
+                .reversed_chars_at(start)
+                .next()
+                .map_or(true, |prev| is_word_boundary(&scope, prev, first_char));
+            let ends_word = buffer
+                .chars_at(end)
+                .next()
+                .map_or(true, |next| is_word_boundary(&scope, last_char, next));
+            (starts_word && ends_word)
+                .then(|| buffer.anchor_after(start)..buffer.anchor_before(end))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{editor_tests::init_test, test::editor_test_context::EditorTestContext};
+    use settings::SettingsStore;
+
+    #[gpui::test]
+    async fn test_word_highlights(cx: &mut gpui::TestAppContext) {
+        init_test(cx, |_| {});
+        let mut cx = EditorTestContext::new(cx).await;
+
+        // Only whole words with the same case are highlighted, once the cursor rested.
+        cx.set_state("let foo = fooˇ + foo_bar + foo;\nfoo(Foo, xfoo);");
+        cx.assert_editor_background_highlights::<WordHighlight>(
+            "let foo = foo + foo_bar + foo;\nfoo(Foo, xfoo);",
+        );
+        cx.executor().advance_clock(WORD_HIGHLIGHTS_DEBOUNCE);
+        cx.run_until_parked();
+        cx.assert_editor_background_highlights::<WordHighlight>(
+            "let «foo» = foo + foo_bar + «foo»;\n«foo»(Foo, xfoo);",
+        );
+
+        // Moving the cursor clears them.
+        cx.set_state("let foo = foo + foo_ˇbar + foo;\nfoo(Foo, xfoo);");
+        cx.assert_editor_background_highlights::<WordHighlight>(
+            "let foo = foo + foo_bar + foo;\nfoo(Foo, xfoo);",
+        );
+        cx.executor().advance_clock(WORD_HIGHLIGHTS_DEBOUNCE);
+        cx.run_until_parked();
+        cx.assert_editor_background_highlights::<WordHighlight>(
+            "let foo = foo + foo_bar + foo;\nfoo(Foo, xfoo);",
+        );
+
+        // Nothing is highlighted while text is selected, or for short words.
+        for state in ["let foo = «fooˇ» + foo;", "let ab = abˇ + ab;"] {
+            cx.set_state(state);
+            cx.executor().advance_clock(WORD_HIGHLIGHTS_DEBOUNCE);
+            cx.run_until_parked();
+            cx.assert_editor_background_highlights::<WordHighlight>(
+                &state.replace(['«', '»', 'ˇ'], ""),
+            );
+        }
+
+        // Nothing is highlighted when they are turned off.
+        cx.update(|cx| {
+            cx.update_global::<SettingsStore, _>(|settings, cx| {
+                settings.update_user_settings::<EditorSettings>(cx, |settings| {
+                    settings.word_highlights = Some(false);
+                });
+            })
+        });
+        cx.set_state("let foo = fooˇ + foo;");
+        cx.executor().advance_clock(WORD_HIGHLIGHTS_DEBOUNCE);
+        cx.run_until_parked();
+        cx.assert_editor_background_highlights::<WordHighlight>("let foo = foo + foo;");
+    }
+}
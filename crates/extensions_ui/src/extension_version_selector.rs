@@ -115,11 +115,7 @@ impl PickerDelegate for ExtensionVersionSelectorDelegate {
             .map(|(id, extension)| {
                 let text = format!("v{}", extension.manifest.version);
 
-                StringMatchCandidate {
-                    id,
-                    char_bag: text.as_str().into(),
-                    string: text,
-                }
+                StringMatchCandidate::new(id, text)
             })
             .collect::<Vec<_>>();
 
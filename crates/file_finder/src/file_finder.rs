@@ -1,10 +1,13 @@
 #[cfg(test)]
 mod file_finder_tests;
 
+mod file_finder_settings;
 mod new_path_prompt;
 
+use anyhow::{anyhow, bail, Context as _};
 use collections::{BTreeSet, HashMap};
-use editor::{scroll::Autoscroll, Bias, Editor};
+use file_finder_settings::FileFinderSettings;
+use futures::{channel::mpsc, StreamExt};
 use fuzzy::{CharBag, PathMatch, PathMatchCandidate};
 use gpui::{
     actions, impl_actions, rems, Action, AnyElement, AppContext, DismissEvent, EventEmitter,
@@ -18,7 +21,8 @@ use project::{PathMatchCandidateSet, Project, ProjectPath, WorktreeId};
 use settings::Settings;
 use std::{
     cmp,
-    path::{Path, PathBuf},
+    ffi::OsStr,
+    path::{Component, Path, PathBuf},
     sync::{
         atomic::{self, AtomicBool},
         Arc,
@@ -27,9 +31,16 @@ use std::{
 use text::Point;
 use ui::{prelude::*, HighlightedLabel, ListItem, ListItemSpacing};
 use util::{paths::PathLikeWithPosition, post_inc, ResultExt};
-use workspace::{item::PreviewTabsSettings, ModalView, Workspace};
+use workspace::{
+    item::{PointOrAnchor, PreviewTabsSettings},
+    ModalView, Workspace,
+};
+
+/// Chars that can't be used in the names of files created from the query, as at least
+/// one of the supported platforms doesn't allow them.
+const INVALID_FILE_NAME_CHARS: &[char] = &['<', '>', ':', '"', '|', '?', '*', '\0'];
 
-actions!(file_finder, [SelectPrev]);
+actions!(file_finder, [SelectPrev, ToggleIncludeIgnored]);
 impl_actions!(file_finder, [Toggle]);
 
 #[derive(Default, PartialEq, Eq, Clone, serde::Deserialize)]
@@ -46,6 +57,7 @@ pub struct FileFinder {
 }
 
 pub fn init(cx: &mut AppContext) {
+    FileFinderSettings::register(cx);
     cx.observe_new_views(FileFinder::register).detach();
     cx.observe_new_views(NewPathPrompt::register).detach();
 }
@@ -80,7 +92,7 @@ impl FileFinder {
                 FoundPath::new(project_path, abs_path)
             });
 
-        let history_items = workspace
+        let mut history_items = workspace
             .recent_navigation_history(Some(MAX_RECENT_SELECTIONS), cx)
             .into_iter()
             .filter(|(_, history_abs_path)| match history_abs_path {
@@ -89,6 +101,26 @@ impl FileFinder {
             })
             .map(|(history_path, abs_path)| FoundPath::new(history_path, abs_path))
             .collect::<Vec<_>>();
+        // Files opened during the previous session go after the ones opened in this one.
+        for abs_path in workspace.restored_recent_paths() {
+            if history_items.len() >= MAX_RECENT_SELECTIONS {
+                break;
+            }
+            if !history_file_exists(abs_path)
+                || history_items
+                    .iter()
+                    .any(|item| item.absolute.as_ref() == Some(abs_path))
+            {
+                continue;
+            }
+            if let Some((worktree, relative_path)) = project.find_local_worktree(abs_path, cx) {
+                let project_path = ProjectPath {
+                    worktree_id: worktree.read(cx).id(),
+                    path: Arc::from(relative_path),
+                };
+                history_items.push(FoundPath::new(project_path, Some(abs_path.clone())));
+            }
+        }
 
         let project = workspace.project().clone();
         let weak_workspace = cx.view().downgrade();
@@ -134,6 +166,34 @@ impl FileFinder {
         self.init_modifiers = Some(cx.modifiers());
         cx.dispatch_action(Box::new(menu::SelectPrev));
     }
+
+    fn handle_toggle_include_ignored(
+        &mut self,
+        _: &ToggleIncludeIgnored,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.picker.update(cx, |picker, cx| {
+            let include_ignored = !picker.delegate.include_ignored;
+            picker.delegate.include_ignored = include_ignored;
+            picker.refresh(cx);
+
+            // Ignored directories are only scanned on demand, so search again once they are.
+            if include_ignored {
+                let load = picker.delegate.project.read(cx).load_ignored_dirs(cx);
+                cx.spawn(|picker, mut cx| async move {
+                    load.await;
+                    picker
+                        .update(&mut cx, |picker, cx| {
+                            if picker.delegate.include_ignored {
+                                picker.refresh(cx);
+                            }
+                        })
+                        .ok();
+                })
+                .detach();
+            }
+        });
+    }
 }
 
 impl EventEmitter<DismissEvent> for FileFinder {}
@@ -151,6 +211,7 @@ impl Render for FileFinder {
             .w(rems(34.))
             .on_modifiers_changed(cx.listener(Self::handle_modifiers_changed))
             .on_action(cx.listener(Self::handle_select_prev))
+            .on_action(cx.listener(Self::handle_toggle_include_ignored))
             .child(self.picker.clone())
     }
 }
@@ -170,6 +231,10 @@ pub struct FileFinderDelegate {
     cancel_flag: Arc<AtomicBool>,
     history_items: Vec<FoundPath>,
     separate_history: bool,
+    /// Whether the search goes through the files ignored by the version control system
+    /// too, until the finder is closed. Hidden files are searched either way, like any
+    /// other file, and the files excluded from scanning in the settings never are.
+    include_ignored: bool,
 }
 
 /// Use a custom ordering for file finder: the regular one
@@ -184,18 +249,9 @@ struct ProjectPanelOrdMatch(PathMatch);
 
 impl Ord for ProjectPanelOrdMatch {
     fn cmp(&self, other: &Self) -> cmp::Ordering {
-        self.0
-            .score
-            .partial_cmp(&other.0.score)
-            .unwrap_or(cmp::Ordering::Equal)
-            .then_with(|| self.0.worktree_id.cmp(&other.0.worktree_id))
-            .then_with(|| {
-                other
-                    .0
-                    .distance_to_relative_ancestor
-                    .cmp(&self.0.distance_to_relative_ancestor)
-            })
-            .then_with(|| self.0.path.cmp(&other.0.path).reverse())
+        // Use the same order the matcher selected the top matches with, so that they
+        // don't get reshuffled when displayed.
+        self.0.cmp(&other.0)
     }
 }
 
@@ -226,6 +282,10 @@ impl Matches {
         self.matches.get(index)
     }
 
+    fn position(&self, m: &Match) -> Option<usize> {
+        self.matches.iter().position(|other| other == m)
+    }
+
     fn push_new_matches<'a>(
         &'a mut self,
         history_items: impl IntoIterator<Item = &'a FoundPath> + Clone,
@@ -260,6 +320,10 @@ impl Matches {
                 } else {
                     query_match.flatten()
                 };
+                let query_match = query_match.map(|mut query_match| {
+                    query_match.0.score += history_recency_bonus(i);
+                    query_match
+                });
                 Some((i + 1, Match::History(history_item.clone(), query_match)))
             });
 
@@ -335,6 +399,8 @@ fn matching_history_item_paths<'a>(
                         .to_lowercase()
                         .chars(),
                 ),
+                is_ignored: false,
+                prepared: None,
             };
             Some((found_path.project.worktree_id, candidate))
         })
@@ -357,6 +423,7 @@ fn matching_history_item_paths<'a>(
                 worktree.to_usize(),
                 query.path_like.path_query(),
                 false,
+                true,
                 max_results,
             )
             .into_iter()
@@ -384,6 +451,13 @@ impl FoundPath {
 }
 
 const MAX_RECENT_SELECTIONS: usize = 20;
+const MAX_HISTORY_RECENCY_BONUS: f64 = 0.05;
+
+/// A bonus added to the fuzzy score of a history item, so that among similarly good matches
+/// the ones opened more recently come first. Halves with every step back in the history.
+fn history_recency_bonus(history_position: usize) -> f64 {
+    MAX_HISTORY_RECENCY_BONUS * 0.5f64.powi(history_position as i32)
+}
 
 #[cfg(not(test))]
 fn history_file_exists(abs_path: &PathBuf) -> bool {
@@ -441,6 +515,7 @@ impl FileFinderDelegate {
             cancel_flag: Arc::new(AtomicBool::new(false)),
             history_items,
             separate_history,
+            include_ignored: false,
         }
     }
 
@@ -466,6 +541,7 @@ impl FileFinderDelegate {
         let relative_to = self
             .currently_opened_path
             .as_ref()
+            .filter(|_| FileFinderSettings::get_global(cx).prefer_nearby_files)
             .map(|found_path| Arc::clone(&found_path.project.path));
         let worktrees = self
             .project
@@ -473,15 +549,17 @@ impl FileFinderDelegate {
             .visible_worktrees(cx)
             .collect::<Vec<_>>();
         let include_root_name = worktrees.len() > 1;
+        let include_ignored = self.include_ignored;
         let candidate_sets = worktrees
             .into_iter()
             .map(|worktree| {
                 let worktree = worktree.read(cx);
                 PathMatchCandidateSet {
                     snapshot: worktree.snapshot(),
-                    include_ignored: worktree
-                        .root_entry()
-                        .map_or(false, |entry| entry.is_ignored),
+                    include_ignored: include_ignored
+                        || worktree
+                            .root_entry()
+                            .map_or(false, |entry| entry.is_ignored),
                     include_root_name,
                     directories_only: false,
                 }
@@ -493,26 +571,38 @@ impl FileFinderDelegate {
         self.cancel_flag = Arc::new(AtomicBool::new(false));
         let cancel_flag = self.cancel_flag.clone();
         cx.spawn(|picker, mut cx| async move {
-            let matches = fuzzy::match_path_sets(
+            let (snapshots_tx, mut snapshots_rx) = mpsc::unbounded();
+            let search = fuzzy::match_path_sets_streaming(
                 candidate_sets.as_slice(),
                 query.path_like.path_query(),
                 relative_to,
                 false,
+                true,
                 100,
                 &cancel_flag,
                 cx.background_executor().clone(),
-            )
-            .await
-            .into_iter()
-            .map(ProjectPanelOrdMatch);
-            let did_cancel = cancel_flag.load(atomic::Ordering::Relaxed);
-            picker
-                .update(&mut cx, |picker, cx| {
+                snapshots_tx,
+            );
+            let apply_snapshots = async {
+                while let Some(snapshot) = snapshots_rx.next().await {
+                    // Until the last snapshot, the matches are as incomplete as those of a
+                    // cancelled search.
+                    let did_cancel = !snapshot.is_complete;
+                    let matches = snapshot.matches.into_iter().map(ProjectPanelOrdMatch);
                     picker
-                        .delegate
-                        .set_search_matches(search_id, did_cancel, query, matches, cx)
-                })
-                .log_err();
+                        .update(&mut cx, |picker, cx| {
+                            picker.delegate.set_search_matches(
+                                search_id,
+                                did_cancel,
+                                query.clone(),
+                                matches,
+                                cx,
+                            )
+                        })
+                        .log_err();
+                }
+            };
+            futures::join!(search, apply_snapshots);
         })
     }
 
@@ -525,6 +615,15 @@ impl FileFinderDelegate {
         cx: &mut ViewContext<Picker<Self>>,
     ) {
         if search_id >= self.latest_search_id {
+            // When more matches of the search come in, keep the match that was picked among
+            // the earlier ones selected.
+            let picked_match = if search_id == self.latest_search_id
+                && self.selected_index != self.calculate_selected_index()
+            {
+                self.matches.get(self.selected_index).cloned()
+            } else {
+                None
+            };
             self.latest_search_id = search_id;
             let extend_old_matches = self.latest_search_did_cancel
                 && Some(query.path_like.path_query())
@@ -541,7 +640,9 @@ impl FileFinderDelegate {
             );
             self.latest_search_query = Some(query);
             self.latest_search_did_cancel = did_cancel;
-            self.selected_index = self.calculate_selected_index();
+            self.selected_index = picked_match
+                .and_then(|picked_match| self.matches.position(&picked_match))
+                .unwrap_or_else(|| self.calculate_selected_index());
             cx.notify();
         }
     }
@@ -593,6 +694,8 @@ impl FileFinderDelegate {
                     path,
                     path_prefix: "".into(),
                     distance_to_relative_ancestor: usize::MAX,
+                    proximity_bonus: 0.0,
+                    is_ignored: false,
                 };
                 if let Some(found_path_match) = found_path_match {
                     path_match
@@ -636,26 +739,13 @@ impl FileFinderDelegate {
         let path = &path_match.path;
         let path_string = path.to_string_lossy();
         let full_path = [path_match.path_prefix.as_ref(), path_string.as_ref()].join("");
-        let mut path_positions = path_match.positions.clone();
+        let (path_positions, file_name_positions) = path_match.split_positions();
 
         let file_name = path.file_name().map_or_else(
             || path_match.path_prefix.to_string(),
             |file_name| file_name.to_string_lossy().to_string(),
         );
-        let file_name_start = path_match.path_prefix.len() + path_string.len() - file_name.len();
-        let file_name_positions = path_positions
-            .iter()
-            .filter_map(|pos| {
-                if pos >= &file_name_start {
-                    Some(pos - file_name_start)
-                } else {
-                    None
-                }
-            })
-            .collect();
-
         let full_path = full_path.trim_end_matches(&file_name).to_string();
-        path_positions.retain(|idx| *idx < full_path.len());
 
         (file_name, file_name_positions, full_path, path_positions)
     }
@@ -685,13 +775,19 @@ impl FileFinderDelegate {
                             if let Some((worktree, relative_path)) =
                                 project.find_local_worktree(query_path, cx)
                             {
+                                let worktree = worktree.read(cx);
+                                let is_ignored = worktree
+                                    .entry_for_path(&relative_path)
+                                    .map_or(false, |entry| entry.is_ignored);
                                 path_matches.push(ProjectPanelOrdMatch(PathMatch {
                                     score: 1.0,
                                     positions: Vec::new(),
-                                    worktree_id: worktree.read(cx).id().to_usize(),
+                                    worktree_id: worktree.id().to_usize(),
                                     path: Arc::from(relative_path),
                                     path_prefix: "".into(),
                                     distance_to_relative_ancestor: usize::MAX,
+                                    proximity_bonus: 0.0,
+                                    is_ignored,
                                 }));
                             }
                         })
@@ -716,6 +812,75 @@ impl FileFinderDelegate {
         })
     }
 
+    /// Resolves the path of a file to create from the query. When the project has several
+    /// worktrees, relative paths have to start with the root name of the worktree to
+    /// create the file in.
+    fn project_path_for_new_file(
+        &self,
+        query_path: &str,
+        cx: &AppContext,
+    ) -> anyhow::Result<ProjectPath> {
+        let project = self.project.read(cx);
+        let path = Path::new(query_path);
+        let (worktree, relative_path) = if path.is_absolute() {
+            project
+                .find_local_worktree(path, cx)
+                .with_context(|| format!("cannot create {query_path:?} outside of the project"))?
+        } else {
+            let worktrees = project.visible_worktrees(cx).collect::<Vec<_>>();
+            match worktrees.as_slice() {
+                [] => bail!("the project has no folder to create {query_path:?} in"),
+                [worktree] => (worktree.clone(), path.to_path_buf()),
+                _ => {
+                    let mut components = path.components();
+                    let root_name = components.next().map(|component| component.as_os_str());
+                    let worktree = worktrees
+                        .iter()
+                        .find(|worktree| {
+                            root_name == Some(OsStr::new(worktree.read(cx).root_name()))
+                        })
+                        .ok_or_else(|| {
+                            let root_names = worktrees
+                                .iter()
+                                .map(|worktree| worktree.read(cx).root_name())
+                                .join(", ");
+                            anyhow!(
+                                "start {query_path:?} with the folder to create it in: {root_names}"
+                            )
+                        })?;
+                    (worktree.clone(), components.as_path().to_path_buf())
+                }
+            }
+        };
+
+        let mut new_path = PathBuf::new();
+        for component in relative_path.components() {
+            match component {
+                Component::Normal(file_name) => {
+                    if file_name
+                        .to_string_lossy()
+                        .contains(INVALID_FILE_NAME_CHARS)
+                    {
+                        bail!("{file_name:?} contains characters that file names can't contain");
+                    }
+                    new_path.push(file_name);
+                }
+                Component::CurDir => {}
+                Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                    bail!("cannot create {query_path:?} outside of its folder")
+                }
+            }
+        }
+        if new_path.as_os_str().is_empty() || query_path.ends_with(std::path::is_separator) {
+            bail!("{query_path:?} is not a file path");
+        }
+
+        Ok(ProjectPath {
+            worktree_id: worktree.read(cx).id(),
+            path: new_path.into(),
+        })
+    }
+
     /// Skips first history match (that is displayed topmost) if it's currently opened.
     fn calculate_selected_index(&self) -> usize {
         if let Some(Match::History(path, _)) = self.matches.get(0) {
@@ -737,6 +902,18 @@ impl PickerDelegate for FileFinderDelegate {
         "Search project files...".into()
     }
 
+    fn no_matches_text(&self, cx: &mut WindowContext) -> SharedString {
+        match &self.latest_search_query {
+            Some(query) if !query.path_like.path_query().is_empty() => format!(
+                "No matches. Press {} to create \"{}\"",
+                cx.keystroke_text_for(&picker::ConfirmInput { secondary: false }),
+                query.path_like.path_query()
+            )
+            .into(),
+            _ => "No matches".into(),
+        }
+    }
+
     fn match_count(&self) -> usize {
         self.matches.len()
     }
@@ -774,7 +951,6 @@ impl PickerDelegate for FileFinderDelegate {
         raw_query: String,
         cx: &mut ViewContext<Picker<Self>>,
     ) -> Task<()> {
-        let raw_query = raw_query.replace(' ', "");
         let raw_query = raw_query.trim();
         if raw_query.is_empty() {
             let project = self.project.read(cx);
@@ -914,20 +1090,11 @@ impl PickerDelegate for FileFinderDelegate {
                 cx.spawn(|_, mut cx| async move {
                     let item = open_task.await.log_err()?;
                     if let Some(row) = row {
-                        if let Some(active_editor) = item.downcast::<Editor>() {
-                            active_editor
-                                .downgrade()
-                                .update(&mut cx, |editor, cx| {
-                                    let snapshot = editor.snapshot(cx).display_snapshot;
-                                    let point = snapshot
-                                        .buffer_snapshot
-                                        .clip_point(Point::new(row, col), Bias::Left);
-                                    editor.change_selections(Some(Autoscroll::center()), cx, |s| {
-                                        s.select_ranges([point..point])
-                                    });
-                                })
-                                .log_err();
-                        }
+                        finder
+                            .update(&mut cx, |_, cx| {
+                                item.move_cursor_to(PointOrAnchor::Point(Point::new(row, col)), cx)
+                            })
+                            .log_err();
                     }
                     finder.update(&mut cx, |_, cx| cx.emit(DismissEvent)).ok()?;
 
@@ -938,6 +1105,54 @@ impl PickerDelegate for FileFinderDelegate {
         }
     }
 
+    /// Creates the file the query points to, along with any missing parent directories,
+    /// and opens it. Files that exist already are only opened.
+    fn confirm_input(&mut self, _secondary: bool, cx: &mut ViewContext<Picker<Self>>) {
+        let Some(query) = self.latest_search_query.as_ref() else {
+            return;
+        };
+        let project_path = match self.project_path_for_new_file(query.path_like.path_query(), cx) {
+            Ok(project_path) => project_path,
+            Err(error) => {
+                self.workspace
+                    .update(cx, |workspace, cx| workspace.show_error(&error, cx))
+                    .log_err();
+                return;
+            }
+        };
+
+        let create_entry = self.project.update(cx, |project, cx| {
+            if project.entry_for_path(&project_path, cx).is_some() {
+                None
+            } else {
+                Some(project.create_entry(project_path.clone(), false, cx))
+            }
+        });
+        let workspace = self.workspace.clone();
+        let finder = self.file_finder.clone();
+        cx.spawn(|_, mut cx| async move {
+            let open_result = async {
+                if let Some(create_entry) = create_entry {
+                    create_entry.await?;
+                }
+                let open_task = workspace.update(&mut cx, |workspace, cx| {
+                    workspace.open_path(project_path, None, true, cx)
+                })?;
+                anyhow::Ok(open_task.await?)
+            }
+            .await;
+            match open_result {
+                Ok(_) => finder
+                    .update(&mut cx, |_, cx| cx.emit(DismissEvent))
+                    .log_err(),
+                Err(error) => workspace
+                    .update(&mut cx, |workspace, cx| workspace.show_error(&error, cx))
+                    .log_err(),
+            };
+        })
+        .detach();
+    }
+
     fn dismissed(&mut self, cx: &mut ViewContext<Picker<FileFinderDelegate>>) {
         self.file_finder
             .update(cx, |_, cx| cx.emit(DismissEvent))
@@ -965,6 +1180,12 @@ impl PickerDelegate for FileFinderDelegate {
                 .size(IconSize::Small.rems())
                 .into_any_element(),
         };
+        let is_ignored = match &path_match {
+            Match::History(_, found_path_match) => found_path_match
+                .as_ref()
+                .map_or(false, |path_match| path_match.0.is_ignored),
+            Match::Search(path_match) => path_match.0.is_ignored,
+        };
         let (file_name, file_name_positions, full_path, full_path_positions) =
             self.labels_for_match(path_match, cx, ix);
 
@@ -978,7 +1199,13 @@ impl PickerDelegate for FileFinderDelegate {
                     h_flex()
                         .gap_2()
                         .py_px()
-                        .child(HighlightedLabel::new(file_name, file_name_positions))
+                        .child(HighlightedLabel::new(file_name, file_name_positions).color(
+                            if is_ignored {
+                                Color::Ignored
+                            } else {
+                                Color::Default
+                            },
+                        ))
                         .child(
                             HighlightedLabel::new(full_path, full_path_positions)
                                 .size(LabelSize::Small)
@@ -1003,6 +1230,8 @@ mod tests {
                 path: Arc::from(Path::new("b0.5")),
                 path_prefix: Arc::from(""),
                 distance_to_relative_ancestor: 0,
+                proximity_bonus: 0.0,
+                is_ignored: false,
             }),
             ProjectPanelOrdMatch(PathMatch {
                 score: 1.0,
@@ -1011,6 +1240,8 @@ mod tests {
                 path: Arc::from(Path::new("c1.0")),
                 path_prefix: Arc::from(""),
                 distance_to_relative_ancestor: 0,
+                proximity_bonus: 0.0,
+                is_ignored: false,
             }),
             ProjectPanelOrdMatch(PathMatch {
                 score: 1.0,
@@ -1019,6 +1250,8 @@ mod tests {
                 path: Arc::from(Path::new("a1.0")),
                 path_prefix: Arc::from(""),
                 distance_to_relative_ancestor: 0,
+                proximity_bonus: 0.0,
+                is_ignored: false,
             }),
             ProjectPanelOrdMatch(PathMatch {
                 score: 0.5,
@@ -1027,6 +1260,8 @@ mod tests {
                 path: Arc::from(Path::new("a0.5")),
                 path_prefix: Arc::from(""),
                 distance_to_relative_ancestor: 0,
+                proximity_bonus: 0.0,
+                is_ignored: false,
             }),
             ProjectPanelOrdMatch(PathMatch {
                 score: 1.0,
@@ -1035,6 +1270,8 @@ mod tests {
                 path: Arc::from(Path::new("b1.0")),
                 path_prefix: Arc::from(""),
                 distance_to_relative_ancestor: 0,
+                proximity_bonus: 0.0,
+                is_ignored: false,
             }),
         ];
         file_finder_sorted_output.sort_by(|a, b| b.cmp(a));
@@ -1049,6 +1286,8 @@ mod tests {
                     path: Arc::from(Path::new("a1.0")),
                     path_prefix: Arc::from(""),
                     distance_to_relative_ancestor: 0,
+                    proximity_bonus: 0.0,
+                    is_ignored: false,
                 }),
                 ProjectPanelOrdMatch(PathMatch {
                     score: 1.0,
@@ -1057,6 +1296,8 @@ mod tests {
                     path: Arc::from(Path::new("b1.0")),
                     path_prefix: Arc::from(""),
                     distance_to_relative_ancestor: 0,
+                    proximity_bonus: 0.0,
+                    is_ignored: false,
                 }),
                 ProjectPanelOrdMatch(PathMatch {
                     score: 1.0,
@@ -1065,6 +1306,8 @@ mod tests {
                     path: Arc::from(Path::new("c1.0")),
                     path_prefix: Arc::from(""),
                     distance_to_relative_ancestor: 0,
+                    proximity_bonus: 0.0,
+                    is_ignored: false,
                 }),
                 ProjectPanelOrdMatch(PathMatch {
                     score: 0.5,
@@ -1073,6 +1316,8 @@ mod tests {
                     path: Arc::from(Path::new("a0.5")),
                     path_prefix: Arc::from(""),
                     distance_to_relative_ancestor: 0,
+                    proximity_bonus: 0.0,
+                    is_ignored: false,
                 }),
                 ProjectPanelOrdMatch(PathMatch {
                     score: 0.5,
@@ -1081,6 +1326,8 @@ mod tests {
                     path: Arc::from(Path::new("b0.5")),
                     path_prefix: Arc::from(""),
                     distance_to_relative_ancestor: 0,
+                    proximity_bonus: 0.0,
+                    is_ignored: false,
                 }),
             ]
         );
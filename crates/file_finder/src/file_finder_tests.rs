@@ -6,6 +6,7 @@ use gpui::{Entity, TestAppContext, VisualTestContext};
 use menu::{Confirm, SelectNext, SelectPrev};
 use project::FS_WATCH_LATENCY;
 use serde_json::json;
+use settings::SettingsStore;
 use workspace::{AppState, Workspace};
 
 #[ctor::ctor]
@@ -409,6 +410,78 @@ async fn test_matching_cancellation(cx: &mut TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_selection_preserved_across_partial_matches(cx: &mut TestAppContext) {
+    let app_state = init_test(cx);
+    app_state
+        .fs
+        .as_fake()
+        .insert_tree(
+            "/dir",
+            json!({
+                "hello": "",
+                "halogen-light": "",
+                "happiness": "",
+                "height": "",
+            }),
+        )
+        .await;
+
+    let project = Project::test(app_state.fs.clone(), ["/dir".as_ref()], cx).await;
+
+    let (picker, _, cx) = build_find_picker(project, cx);
+
+    let query = test_path_like("h");
+    picker
+        .update(cx, |picker, cx| {
+            picker.delegate.spawn_search(query.clone(), cx)
+        })
+        .await;
+
+    picker.update(cx, |picker, cx| {
+        let matches = collect_search_matches(picker).search_matches_only();
+        assert_eq!(matches.len(), 4);
+        let delegate = &mut picker.delegate;
+
+        // Simulate the first snapshot of a search that's still going on.
+        drop(delegate.spawn_search(query.clone(), cx));
+        let search_id = delegate.search_count - 1;
+        delegate.set_search_matches(
+            search_id,
+            true, // did-cancel
+            query.clone(),
+            vec![
+                ProjectPanelOrdMatch(matches[1].clone()),
+                ProjectPanelOrdMatch(matches[3].clone()),
+            ],
+            cx,
+        );
+        assert_eq!(delegate.selected_index(), 0);
+        delegate.set_selected_index(1, cx);
+
+        // Better matches found later on don't take the selection away from the picked one.
+        delegate.set_search_matches(
+            search_id,
+            false,
+            query.clone(),
+            matches.iter().cloned().map(ProjectPanelOrdMatch),
+            cx,
+        );
+        assert_eq!(delegate.selected_index(), 3);
+
+        // A new search selects its best match again.
+        drop(delegate.spawn_search(query.clone(), cx));
+        delegate.set_search_matches(
+            delegate.search_count - 1,
+            false,
+            query.clone(),
+            matches.iter().cloned().map(ProjectPanelOrdMatch),
+            cx,
+        );
+        assert_eq!(delegate.selected_index(), 0);
+    });
+}
+
 #[gpui::test]
 async fn test_ignored_root(cx: &mut TestAppContext) {
     let app_state = init_test(cx);
@@ -456,6 +529,90 @@ async fn test_ignored_root(cx: &mut TestAppContext) {
     picker.update(cx, |picker, _| assert_eq!(picker.delegate.matches.len(), 7));
 }
 
+#[gpui::test]
+async fn test_toggle_include_ignored(cx: &mut TestAppContext) {
+    let app_state = init_test(cx);
+    app_state
+        .fs
+        .as_fake()
+        .insert_tree(
+            "/root",
+            json!({
+                ".gitignore": "obj",
+                ".hidden": { "main.rs": "" },
+                "obj": {
+                    "main.rs": "",
+                    "debug": { "main.rs": "" },
+                },
+                "pkg": { "main.rs": "" },
+            }),
+        )
+        .await;
+
+    let project = Project::test(app_state.fs.clone(), ["/root".as_ref()], cx).await;
+    let worktree = project.read_with(cx, |project, _| project.worktrees().next().unwrap());
+    worktree.update(cx, |worktree, _| {
+        assert_eq!(
+            worktree.entry_for_path("obj").unwrap().kind,
+            project::EntryKind::UnloadedDir,
+            "ignored directories should only be scanned on demand"
+        );
+    });
+    let (picker, workspace, cx) = build_find_picker(project, cx);
+
+    cx.simulate_input("main.rs");
+    picker.update(cx, |picker, _| {
+        let mut paths = collect_search_matches(picker).search_paths_only();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from(".hidden/main.rs"),
+                PathBuf::from("pkg/main.rs")
+            ],
+            "only the files that aren't ignored, hidden or not, should be searched by default"
+        );
+    });
+
+    cx.dispatch_action(ToggleIncludeIgnored);
+    cx.run_until_parked();
+    picker.update(cx, |picker, _| {
+        let mut matches = collect_search_matches(picker)
+            .search_matches_only()
+            .iter()
+            .map(|path_match| (path_match.path.to_path_buf(), path_match.is_ignored))
+            .collect::<Vec<_>>();
+        matches.sort();
+        assert_eq!(
+            matches,
+            vec![
+                (PathBuf::from(".hidden/main.rs"), false),
+                (PathBuf::from("obj/debug/main.rs"), true),
+                (PathBuf::from("obj/main.rs"), true),
+                (PathBuf::from("pkg/main.rs"), false),
+            ],
+            "the files in ignored directories should be loaded and searched after toggling"
+        );
+    });
+
+    cx.dispatch_action(menu::Cancel);
+    let picker = open_file_picker(&workspace, cx);
+    cx.simulate_input("main.rs");
+    picker.update(cx, |picker, _| {
+        assert!(!picker.delegate.include_ignored);
+        let mut paths = collect_search_matches(picker).search_paths_only();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from(".hidden/main.rs"),
+                PathBuf::from("pkg/main.rs")
+            ],
+            "ignored files should not be searched after reopening the file finder"
+        );
+    });
+}
+
 #[gpui::test]
 async fn test_single_file_worktrees(cx: &mut TestAppContext) {
     let app_state = init_test(cx);
@@ -559,6 +716,212 @@ async fn test_path_distance_ordering(cx: &mut TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_nearby_files_ordering(cx: &mut TestAppContext) {
+    let app_state = init_test(cx);
+    app_state
+        .fs
+        .as_fake()
+        .insert_tree(
+            "/root",
+            json!({
+                "handler.rs": "",
+                "src": { "handler.rs": "" },
+                "crates": {
+                    "server": {
+                        "src": {
+                            "api": {
+                                "handler.rs": "",
+                                "routes.rs": "",
+                            }
+                        }
+                    }
+                }
+            }),
+        )
+        .await;
+
+    let project = Project::test(app_state.fs.clone(), ["/root".as_ref()], cx).await;
+    let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project, cx));
+    let worktree_id = cx.read(|cx| {
+        let worktrees = workspace.read(cx).worktrees(cx).collect::<Vec<_>>();
+        assert_eq!(worktrees.len(), 1);
+        WorktreeId::from_usize(worktrees[0].entity_id().as_u64() as usize)
+    });
+    workspace
+        .update(cx, |workspace, cx| {
+            workspace.open_path(
+                ProjectPath {
+                    worktree_id,
+                    path: Arc::from(Path::new("crates/server/src/api/routes.rs")),
+                },
+                None,
+                true,
+                cx,
+            )
+        })
+        .await
+        .unwrap();
+
+    let finder = open_file_picker(&workspace, cx);
+    finder
+        .update(cx, |finder, cx| {
+            finder.delegate.spawn_search(test_path_like("handler"), cx)
+        })
+        .await;
+    finder.update(cx, |picker, _| {
+        let matches = collect_search_matches(picker).search_matches_only();
+        assert_eq!(
+            matches[0].path.as_ref(),
+            Path::new("crates/server/src/api/handler.rs"),
+            "the file next to the opened one should outrank the shorter paths"
+        );
+        assert!(matches[0].proximity_bonus > 0.0);
+        assert!(matches[1..]
+            .iter()
+            .all(|path_match| path_match.proximity_bonus < matches[0].proximity_bonus));
+    });
+    cx.dispatch_action(menu::Cancel);
+
+    cx.update(|cx| {
+        SettingsStore::update_global(cx, |store, cx| {
+            store.update_user_settings::<FileFinderSettings>(cx, |settings| {
+                settings.prefer_nearby_files = Some(false);
+            });
+        })
+    });
+    let finder = open_file_picker(&workspace, cx);
+    finder
+        .update(cx, |finder, cx| {
+            finder.delegate.spawn_search(test_path_like("handler"), cx)
+        })
+        .await;
+    finder.update(cx, |picker, _| {
+        let matches = collect_search_matches(picker).search_matches_only();
+        assert_eq!(matches[0].path.as_ref(), Path::new("handler.rs"));
+        assert!(matches
+            .iter()
+            .all(|path_match| path_match.proximity_bonus == 0.0));
+    });
+}
+
+#[gpui::test]
+async fn test_create_file_from_query(cx: &mut TestAppContext) {
+    let app_state = init_test(cx);
+    app_state
+        .fs
+        .as_fake()
+        .insert_tree("/root", json!({ "a.txt": "" }))
+        .await;
+
+    let project = Project::test(app_state.fs.clone(), ["/root".as_ref()], cx).await;
+    let (picker, workspace, cx) = build_find_picker(project, cx);
+
+    cx.simulate_input("new/dir/file.rs");
+    picker.update(cx, |picker, _| {
+        assert_eq!(picker.delegate.matches.len(), 0);
+    });
+    cx.dispatch_action(picker::ConfirmInput { secondary: false });
+    cx.run_until_parked();
+
+    assert!(
+        app_state
+            .fs
+            .is_file(Path::new("/root/new/dir/file.rs"))
+            .await
+    );
+    cx.read(|cx| {
+        let active_editor = workspace.read(cx).active_item_as::<Editor>(cx).unwrap();
+        assert_eq!(active_editor.read(cx).title(cx), "file.rs");
+        assert!(workspace.read(cx).active_modal::<FileFinder>(cx).is_none());
+    });
+
+    for invalid_query in ["../outside.rs", "/outside.rs", "new/", "inva|id.rs"] {
+        let picker = open_file_picker(&workspace, cx);
+        picker
+            .update(cx, |picker, cx| {
+                picker
+                    .delegate
+                    .update_matches(invalid_query.to_string(), cx)
+            })
+            .await;
+        cx.dispatch_action(picker::ConfirmInput { secondary: false });
+        cx.run_until_parked();
+
+        cx.read(|cx| {
+            let workspace = workspace.read(cx);
+            assert!(
+                workspace.active_modal::<FileFinder>(cx).is_some(),
+                "file finder should stay open for {invalid_query:?}"
+            );
+            assert_eq!(
+                workspace.notification_ids().len(),
+                1,
+                "an error should be shown for {invalid_query:?}"
+            );
+        });
+        workspace.update(cx, |workspace, cx| {
+            for id in workspace.notification_ids() {
+                workspace.dismiss_notification(&id, cx);
+            }
+        });
+        cx.dispatch_action(menu::Cancel);
+    }
+    assert_eq!(
+        app_state.fs.as_fake().files(),
+        vec![
+            PathBuf::from("/root/a.txt"),
+            PathBuf::from("/root/new/dir/file.rs")
+        ]
+    );
+}
+
+#[gpui::test]
+async fn test_create_file_from_query_with_multiple_worktrees(cx: &mut TestAppContext) {
+    let app_state = init_test(cx);
+    app_state
+        .fs
+        .as_fake()
+        .insert_tree("/dir1", json!({ "a.txt": "" }))
+        .await;
+    app_state
+        .fs
+        .as_fake()
+        .insert_tree("/dir2", json!({ "b.txt": "" }))
+        .await;
+
+    let project = Project::test(
+        app_state.fs.clone(),
+        ["/dir1".as_ref(), "/dir2".as_ref()],
+        cx,
+    )
+    .await;
+    let (_picker, workspace, cx) = build_find_picker(project, cx);
+
+    // Without the root name, it's unclear which worktree to create the file in.
+    cx.simulate_input("c.txt");
+    cx.dispatch_action(picker::ConfirmInput { secondary: false });
+    cx.run_until_parked();
+    cx.read(|cx| {
+        let workspace = workspace.read(cx);
+        assert!(workspace.active_modal::<FileFinder>(cx).is_some());
+        assert_eq!(workspace.notification_ids().len(), 1);
+    });
+    assert!(!app_state.fs.is_file(Path::new("/dir1/c.txt")).await);
+    assert!(!app_state.fs.is_file(Path::new("/dir2/c.txt")).await);
+
+    let picker = active_file_picker(&workspace, cx);
+    picker.update(cx, |picker, cx| picker.set_query("dir2/c.txt", cx));
+    cx.run_until_parked();
+    cx.dispatch_action(picker::ConfirmInput { secondary: false });
+    cx.run_until_parked();
+    assert!(app_state.fs.is_file(Path::new("/dir2/c.txt")).await);
+    cx.read(|cx| {
+        let active_editor = workspace.read(cx).active_item_as::<Editor>(cx).unwrap();
+        assert_eq!(active_editor.read(cx).title(cx), "c.txt");
+    });
+}
+
 #[gpui::test]
 async fn test_search_worktree_without_files(cx: &mut TestAppContext) {
     let app_state = init_test(cx);
@@ -1410,6 +1773,145 @@ async fn test_nonexistent_history_items_not_shown(cx: &mut gpui::TestAppContext)
         });
 }
 
+#[gpui::test]
+async fn test_recently_opened_history_items_first_among_equal_matches(
+    cx: &mut gpui::TestAppContext,
+) {
+    let app_state = init_test(cx);
+
+    app_state
+        .fs
+        .as_fake()
+        .insert_tree(
+            "/src",
+            json!({
+                "test": {
+                    "a": { "main.rs": "// A" },
+                    "b": { "main.rs": "// B" },
+                }
+            }),
+        )
+        .await;
+
+    let project = Project::test(app_state.fs.clone(), ["/src".as_ref()], cx).await;
+    let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project, cx));
+    open_close_queried_buffer("a/main", 1, "main.rs", &workspace, cx).await;
+    open_close_queried_buffer("b/main", 1, "main.rs", &workspace, cx).await;
+
+    let picker = open_file_picker(&workspace, cx);
+    cx.simulate_input("main");
+    picker.update(cx, |picker, _| {
+        assert_eq!(
+            collect_search_matches(picker).history,
+            vec![
+                PathBuf::from("test/b/main.rs"),
+                PathBuf::from("test/a/main.rs"),
+            ],
+            "Of the equally good history matches, the most recently opened one should be on top"
+        );
+    });
+    cx.dispatch_action(menu::Cancel);
+
+    open_close_queried_buffer("a/main", 1, "main.rs", &workspace, cx).await;
+
+    let picker = open_file_picker(&workspace, cx);
+    cx.simulate_input("main");
+    picker.update(cx, |picker, _| {
+        assert_eq!(
+            collect_search_matches(picker).history,
+            vec![
+                PathBuf::from("test/a/main.rs"),
+                PathBuf::from("test/b/main.rs"),
+            ],
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_restored_history_items_shown_after_current_ones(cx: &mut TestAppContext) {
+    let app_state = init_test(cx);
+
+    app_state
+        .fs
+        .as_fake()
+        .insert_tree(
+            "/test",
+            json!({
+                "test": {
+                    "1.txt": "// One",
+                    "2.txt": "// Two",
+                    "3.txt": "// Three",
+                    "4.txt": "// Four",
+                }
+            }),
+        )
+        .await;
+
+    let project = Project::test(app_state.fs.clone(), ["/test".as_ref()], cx).await;
+    let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project, cx));
+    workspace.update(cx, |workspace, _| {
+        workspace.set_restored_recent_paths(vec![
+            PathBuf::from("/test/test/3.txt"),
+            PathBuf::from("/test/test/1.txt"),
+            PathBuf::from("/test/test/4.txt"),
+        ])
+    });
+
+    open_close_queried_buffer("1", 1, "1.txt", &workspace, cx).await;
+    open_close_queried_buffer("2", 1, "2.txt", &workspace, cx).await;
+
+    let picker = open_file_picker(&workspace, cx);
+    picker.update(cx, |finder, _| {
+        assert_eq!(finder.delegate.matches.len(), 4);
+        assert_match_at_position(finder, 0, "2.txt");
+        assert_match_at_position(finder, 1, "1.txt");
+        assert_match_at_position(finder, 2, "3.txt");
+        assert_match_at_position(finder, 3, "4.txt");
+    });
+}
+
+#[gpui::test]
+async fn test_nonexistent_restored_history_items_not_shown(cx: &mut gpui::TestAppContext) {
+    let app_state = init_test(cx);
+
+    app_state
+        .fs
+        .as_fake()
+        .insert_tree(
+            "/src",
+            json!({
+                "test": {
+                    "first.rs": "// First Rust file",
+                    "nonexistent.rs": "// Second Rust file",
+                    "third.rs": "// Third Rust file",
+                }
+            }),
+        )
+        .await;
+
+    let project = Project::test(app_state.fs.clone(), ["/src".as_ref()], cx).await;
+    let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project, cx));
+    workspace.update(cx, |workspace, _| {
+        workspace.set_restored_recent_paths(vec![
+            PathBuf::from("/src/test/nonexistent.rs"),
+            PathBuf::from("/src/test/third.rs"),
+            PathBuf::from("/src/test/first.rs"),
+        ])
+    });
+
+    let picker = open_file_picker(&workspace, cx);
+    picker.update(cx, |picker, _| {
+        assert_eq!(
+            collect_search_matches(picker).history,
+            vec![
+                PathBuf::from("/src/test/third.rs"),
+                PathBuf::from("/src/test/first.rs"),
+            ],
+            "Files restored from the previous session should not be shown once they are gone from disk"
+        );
+    });
+}
+
 #[gpui::test]
 async fn test_search_results_refreshed_on_worktree_updates(cx: &mut gpui::TestAppContext) {
     let app_state = init_test(cx);
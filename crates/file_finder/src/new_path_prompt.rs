@@ -295,6 +295,7 @@ impl PickerDelegate for NewPathDelegate {
                 &dir,
                 None,
                 false,
+                true,
                 100,
                 &cancel_flag,
                 cx.background_executor().clone(),
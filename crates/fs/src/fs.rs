@@ -13,20 +13,22 @@ use async_tar::Archive;
 use futures::{future::BoxFuture, AsyncRead, Stream, StreamExt};
 use git::repository::{GitRepository, RealGitRepository};
 use rope::Rope;
-use smol::io::AsyncWriteExt;
 use std::{
     io::{self, Write},
     path::{Component, Path, PathBuf},
     pin::Pin,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering::SeqCst},
+        Arc,
+    },
     time::{Duration, SystemTime},
 };
 use tempfile::{NamedTempFile, TempDir};
-use text::LineEnding;
+use text::{Encoding, LineEnding};
 use util::{paths, ResultExt};
 
 #[cfg(any(test, feature = "test-support"))]
-use collections::{btree_map, BTreeMap};
+use collections::{btree_map, BTreeMap, HashSet};
 #[cfg(any(test, feature = "test-support"))]
 use git::repository::{FakeGitRepositoryState, GitFileStatus};
 #[cfg(any(test, feature = "test-support"))]
@@ -68,8 +70,30 @@ pub trait Fs: Send + Sync {
     }
     async fn open_sync(&self, path: &Path) -> Result<Box<dyn io::Read>>;
     async fn load(&self, path: &Path) -> Result<String>;
+    async fn load_bytes(&self, path: &Path) -> Result<Vec<u8>>;
     async fn atomic_write(&self, path: PathBuf, text: String) -> Result<()>;
     async fn save(&self, path: &Path, text: &Rope, line_ending: LineEnding) -> Result<()>;
+    async fn save_with_encoding(
+        &self,
+        path: &Path,
+        text: &Rope,
+        line_ending: LineEnding,
+        encoding: Encoding,
+    ) -> Result<()> {
+        self.save_with_progress(path, text, line_ending, encoding, &SaveProgress::default())
+            .await
+    }
+    /// Saves like `save_with_encoding`, reporting how much of the file has been written to
+    /// `progress`, and failing with [`SaveCancelled`] when the save gets cancelled through it
+    /// before the file is replaced.
+    async fn save_with_progress(
+        &self,
+        path: &Path,
+        text: &Rope,
+        line_ending: LineEnding,
+        encoding: Encoding,
+        progress: &SaveProgress,
+    ) -> Result<()>;
     async fn canonicalize(&self, path: &Path) -> Result<PathBuf>;
     async fn is_file(&self, path: &Path) -> bool;
     async fn is_dir(&self, path: &Path) -> bool;
@@ -126,8 +150,71 @@ pub struct Metadata {
     pub mtime: SystemTime,
     pub is_symlink: bool,
     pub is_dir: bool,
+    /// Whether the current user isn't allowed to write to the file.
+    pub is_read_only: bool,
+}
+
+/// Follows a save while its file is written, and lets it be cancelled until the file gets
+/// replaced. Clones follow the same save.
+#[derive(Clone, Debug, Default)]
+pub struct SaveProgress(Arc<SaveProgressState>);
+
+#[derive(Debug, Default)]
+struct SaveProgressState {
+    written_len: AtomicUsize,
+    total_len: AtomicUsize,
+    cancelled: AtomicBool,
+}
+
+impl SaveProgress {
+    /// Returns how many bytes of the file have been written, out of how many there are. Both
+    /// are zero until the contents have been encoded.
+    pub fn written(&self) -> (usize, usize) {
+        (
+            self.0.written_len.load(SeqCst),
+            self.0.total_len.load(SeqCst),
+        )
+    }
+
+    /// Cancels the save, which leaves the file as it was unless it has already been replaced.
+    pub fn cancel(&self) {
+        self.0.cancelled.store(true, SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(SeqCst)
+    }
+
+    fn start(&self, total_len: usize) {
+        self.0.written_len.store(0, SeqCst);
+        self.0.total_len.store(total_len, SeqCst);
+    }
+
+    fn advance(&self, len: usize) {
+        self.0.written_len.fetch_add(len, SeqCst);
+    }
+
+    fn check_cancelled(&self) -> Result<()> {
+        if self.is_cancelled() {
+            Err(SaveCancelled.into())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// The error of a save that was cancelled through its [`SaveProgress`].
+#[derive(Debug)]
+pub struct SaveCancelled;
+
+impl std::fmt::Display for SaveCancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "the save was cancelled")
+    }
 }
 
+impl std::error::Error for SaveCancelled {}
+
 #[derive(Default)]
 pub struct RealFs {
     git_hosting_provider_registry: Arc<GitHostingProviderRegistry>,
@@ -321,6 +408,10 @@ impl Fs for RealFs {
         Ok(text)
     }
 
+    async fn load_bytes(&self, path: &Path) -> Result<Vec<u8>> {
+        Ok(smol::fs::read(path).await?)
+    }
+
     async fn atomic_write(&self, path: PathBuf, data: String) -> Result<()> {
         smol::unblock(move || {
             let mut tmp_file = if cfg!(target_os = "linux") {
@@ -341,17 +432,39 @@ impl Fs for RealFs {
     }
 
     async fn save(&self, path: &Path, text: &Rope, line_ending: LineEnding) -> Result<()> {
-        let buffer_size = text.summary().len.min(10 * 1024);
+        self.save_with_encoding(path, text, line_ending, Encoding::Utf8)
+            .await
+    }
+
+    async fn save_with_progress(
+        &self,
+        path: &Path,
+        text: &Rope,
+        line_ending: LineEnding,
+        encoding: Encoding,
+        progress: &SaveProgress,
+    ) -> Result<()> {
         if let Some(path) = path.parent() {
             self.create_dir(path).await?;
         }
-        let file = smol::fs::File::create(path).await?;
-        let mut writer = smol::io::BufWriter::with_capacity(buffer_size, file);
-        for chunk in chunks(text, line_ending) {
-            writer.write_all(chunk.as_bytes()).await?;
-        }
-        writer.flush().await?;
-        Ok(())
+        let path = path.to_path_buf();
+        let text = text.clone();
+        let progress = progress.clone();
+        // Encoding large files takes a while too, so it happens on the blocking pool along
+        // with the write.
+        smol::unblock(move || {
+            let content = encode(&text, line_ending, encoding)?;
+            write_atomically(&path, &content, &progress).map_err(|error| {
+                if error.is::<SaveCancelled>() {
+                    error
+                } else if is_disk_full(&error) {
+                    anyhow!("failed to save {path:?}: the disk is full")
+                } else {
+                    anyhow!("failed to save {path:?}: {error:#}")
+                }
+            })
+        })
+        .await
     }
 
     async fn canonicalize(&self, path: &Path) -> Result<PathBuf> {
@@ -400,6 +513,7 @@ impl Fs for RealFs {
             mtime: metadata.modified().unwrap(),
             is_symlink,
             is_dir: metadata.file_type().is_dir(),
+            is_read_only: is_read_only(path, &metadata),
         }))
     }
 
@@ -638,6 +752,7 @@ struct FakeFsState {
     buffered_events: Vec<PathBuf>,
     metadata_call_count: usize,
     read_dir_call_count: usize,
+    read_only_paths: HashSet<PathBuf>,
 }
 
 #[cfg(any(test, feature = "test-support"))]
@@ -789,6 +904,7 @@ impl FakeFs {
                 events_paused: false,
                 read_dir_call_count: 0,
                 metadata_call_count: 0,
+                read_only_paths: Default::default(),
             }),
         })
     }
@@ -955,6 +1071,17 @@ impl FakeFs {
         }
     }
 
+    /// Makes the given path read-only, as if its permissions didn't allow writing to it.
+    pub fn set_read_only(&self, path: impl AsRef<Path>, read_only: bool) {
+        let path = normalize_path(path.as_ref());
+        let mut state = self.state.lock();
+        if read_only {
+            state.read_only_paths.insert(path);
+        } else {
+            state.read_only_paths.remove(&path);
+        }
+    }
+
     pub fn set_branch_name(&self, dot_git: &Path, branch: Option<impl Into<String>>) {
         self.with_git_state(dot_git, true, |state| {
             state.branch_name = branch.map(Into::into)
@@ -1423,6 +1550,10 @@ impl Fs for FakeFs {
         Ok(String::from_utf8(content.clone())?)
     }
 
+    async fn load_bytes(&self, path: &Path) -> Result<Vec<u8>> {
+        self.load_internal(path).await
+    }
+
     async fn atomic_write(&self, path: PathBuf, data: String) -> Result<()> {
         self.simulate_random_delay().await;
         let path = normalize_path(path.as_path());
@@ -1441,6 +1572,27 @@ impl Fs for FakeFs {
         Ok(())
     }
 
+    async fn save_with_progress(
+        &self,
+        path: &Path,
+        text: &Rope,
+        line_ending: LineEnding,
+        encoding: Encoding,
+        progress: &SaveProgress,
+    ) -> Result<()> {
+        self.simulate_random_delay().await;
+        let path = normalize_path(path);
+        let content = encode(text, line_ending, encoding)?;
+        progress.start(content.len());
+        if let Some(path) = path.parent() {
+            self.create_dir(path).await?;
+        }
+        progress.check_cancelled()?;
+        progress.advance(content.len());
+        self.write_file_internal(path, content)?;
+        Ok(())
+    }
+
     async fn canonicalize(&self, path: &Path) -> Result<PathBuf> {
         let path = normalize_path(path);
         self.simulate_random_delay().await;
@@ -1484,6 +1636,7 @@ impl Fs for FakeFs {
                 }
             }
 
+            let is_read_only = state.read_only_paths.contains(&path);
             let entry = entry.lock();
             Ok(Some(match &*entry {
                 FakeFsEntry::File { inode, mtime, .. } => Metadata {
@@ -1491,12 +1644,14 @@ impl Fs for FakeFs {
                     mtime: *mtime,
                     is_dir: false,
                     is_symlink,
+                    is_read_only,
                 },
                 FakeFsEntry::Dir { inode, mtime, .. } => Metadata {
                     inode: *inode,
                     mtime: *mtime,
                     is_dir: true,
                     is_symlink,
+                    is_read_only,
                 },
                 FakeFsEntry::Symlink { .. } => unreachable!(),
             }))
@@ -1608,6 +1763,153 @@ fn chunks(rope: &Rope, line_ending: LineEnding) -> impl Iterator<Item = &str> {
     })
 }
 
+/// Returns whether the current user isn't allowed to write to the file at `path`. On Unix, that
+/// is asked of the system, as the permission bits alone don't tell who they apply to, and don't
+/// account for ACLs or read-only mounts.
+fn is_read_only(path: &Path, metadata: &std::fs::Metadata) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+
+        match std::ffi::CString::new(path.as_os_str().as_bytes()) {
+            Ok(path) => unsafe { libc::access(path.as_ptr(), libc::W_OK) != 0 },
+            Err(_) => metadata.permissions().readonly(),
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        _ = path;
+        metadata.permissions().readonly()
+    }
+}
+
+/// Replaces the contents of the file at `path` by writing them to a temporary file in the
+/// same directory and renaming that over it, so that a failed save never leaves the file
+/// half written. Symlinks are written through rather than replaced, and the permissions,
+/// group and extended attributes of the original file are kept. New files get the usual
+/// permissions for the umask rather than those of a temporary file. Files that can't be
+/// replaced like that, because they belong to another user or their directory isn't
+/// writable, are overwritten in place instead.
+///
+/// A cancelled save gives up before the file gets replaced, or before it gets truncated when
+/// it's overwritten in place.
+fn write_atomically(path: &Path, content: &[u8], progress: &SaveProgress) -> Result<()> {
+    let path = match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_symlink() => std::fs::canonicalize(path)?,
+        _ => path.to_path_buf(),
+    };
+    let metadata = std::fs::metadata(&path).ok();
+    if metadata
+        .as_ref()
+        .map_or(false, |metadata| is_read_only(&path, metadata))
+    {
+        return Err(anyhow!("the file is read-only"));
+    }
+
+    #[cfg(unix)]
+    let owned_by_other_user = metadata.as_ref().map_or(false, |metadata| {
+        metadata.uid() != unsafe { libc::geteuid() }
+    });
+    #[cfg(not(unix))]
+    let owned_by_other_user = false;
+
+    let temp_file = if owned_by_other_user {
+        None
+    } else {
+        path.parent().and_then(|dir| {
+            let mut builder = tempfile::Builder::new();
+            #[cfg(unix)]
+            if metadata.is_none() {
+                use std::os::unix::fs::PermissionsExt;
+                builder.permissions(std::fs::Permissions::from_mode(0o666));
+            }
+            builder.tempfile_in(dir).ok()
+        })
+    };
+    let Some(mut temp_file) = temp_file else {
+        progress.check_cancelled()?;
+        let mut file = std::fs::File::create(&path)?;
+        write_chunks(&mut file, content, progress, false)?;
+        file.sync_all()?;
+        return Ok(());
+    };
+
+    write_chunks(&mut temp_file, content, progress, true)?;
+    if let Some(metadata) = &metadata {
+        #[cfg(unix)]
+        copy_ownership_and_xattrs(&path, metadata, temp_file.as_file());
+        temp_file
+            .as_file()
+            .set_permissions(metadata.permissions())?;
+    }
+    temp_file.as_file().sync_all()?;
+    progress.check_cancelled()?;
+    temp_file.persist(&path)?;
+    Ok(())
+}
+
+/// Gives the file replacing the one at `path` the same group and extended attributes, as far
+/// as the user is allowed to. Its owner is already the same, since files belonging to other
+/// users are overwritten in place.
+#[cfg(unix)]
+fn copy_ownership_and_xattrs(path: &Path, metadata: &std::fs::Metadata, file: &std::fs::File) {
+    use xattr::FileExt as _;
+
+    std::os::unix::fs::fchown(file, None, Some(metadata.gid())).log_err();
+    if let Ok(names) = xattr::list(path) {
+        for name in names {
+            if let Ok(Some(value)) = xattr::get(path, &name) {
+                // Some attributes, like security labels, can only be set by the system.
+                file.set_xattr(&name, &value).ok();
+            }
+        }
+    }
+}
+
+/// Writes the content in chunks, reporting each of them to the progress, and checking between
+/// them whether the save was cancelled when it can still be.
+fn write_chunks(
+    file: &mut impl Write,
+    content: &[u8],
+    progress: &SaveProgress,
+    cancellable: bool,
+) -> Result<()> {
+    const CHUNK_LEN: usize = 1024 * 1024;
+
+    progress.start(content.len());
+    for chunk in content.chunks(CHUNK_LEN) {
+        if cancellable {
+            progress.check_cancelled()?;
+        }
+        file.write_all(chunk)?;
+        progress.advance(chunk.len());
+    }
+    Ok(())
+}
+
+fn is_disk_full(error: &anyhow::Error) -> bool {
+    let Some(error) = error.downcast_ref::<io::Error>() else {
+        return false;
+    };
+    #[cfg(unix)]
+    let disk_full_errors = [libc::ENOSPC];
+    // ERROR_HANDLE_DISK_FULL and ERROR_DISK_FULL
+    #[cfg(windows)]
+    let disk_full_errors = [39, 112];
+    error
+        .raw_os_error()
+        .map_or(false, |code| disk_full_errors.contains(&code))
+}
+
+fn encode(rope: &Rope, line_ending: LineEnding, encoding: Encoding) -> Result<Vec<u8>> {
+    let mut content = Vec::with_capacity(rope.summary().len);
+    encoding.write_bom(&mut content);
+    for chunk in chunks(rope, line_ending) {
+        encoding.encode(chunk, &mut content)?;
+    }
+    Ok(content)
+}
+
 pub fn normalize_path(path: &Path) -> PathBuf {
     let mut components = path.components().peekable();
     let mut ret = if let Some(c @ Component::Prefix(..)) = components.peek().cloned() {
@@ -1723,6 +2025,109 @@ mod tests {
     use gpui::BackgroundExecutor;
     use serde_json::json;
 
+    #[cfg(unix)]
+    #[gpui::test]
+    async fn test_real_fs_save(executor: BackgroundExecutor) {
+        use std::os::unix::fs::{symlink, PermissionsExt};
+
+        executor.allow_parking();
+        let dir = TempDir::new().unwrap();
+        let file_path = dir.path().join("script.sh");
+        let link_path = dir.path().join("link.sh");
+        std::fs::write(&file_path, "old").unwrap();
+        std::fs::set_permissions(&file_path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        symlink(&file_path, &link_path).unwrap();
+
+        // Saving through a symlink replaces its target, keeping its permissions.
+        let fs = RealFs::default();
+        fs.save(&link_path, &"new".into(), LineEnding::Unix)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(&file_path).unwrap(), "new");
+        assert!(std::fs::symlink_metadata(&link_path)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        let mode = std::fs::metadata(&file_path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o755);
+
+        // Files that the user can't write to aren't overwritten, even when others could write
+        // to them. Root can write to any of them.
+        if unsafe { libc::geteuid() } != 0 {
+            for mode in [0o444, 0o466] {
+                std::fs::set_permissions(&file_path, std::fs::Permissions::from_mode(mode))
+                    .unwrap();
+                let metadata = fs.metadata(&file_path).await.unwrap().unwrap();
+                assert!(metadata.is_read_only);
+                let error = fs
+                    .save(&file_path, &"newer".into(), LineEnding::Unix)
+                    .await
+                    .unwrap_err();
+                assert!(error.to_string().contains("read-only"), "{error}");
+                assert_eq!(error.to_string().matches("failed to save").count(), 1);
+                assert_eq!(std::fs::read_to_string(&file_path).unwrap(), "new");
+            }
+        }
+
+        // Group and other permissions are kept too, rather than those of the temporary file.
+        std::fs::set_permissions(&file_path, std::fs::Permissions::from_mode(0o664)).unwrap();
+        fs.save(&file_path, &"new".into(), LineEnding::Unix)
+            .await
+            .unwrap();
+        let mode = std::fs::metadata(&file_path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o664);
+
+        // New files get the same permissions as any other new file.
+        let new_file_path = dir.path().join("new.txt");
+        let reference_path = dir.path().join("reference.txt");
+        fs.save(&new_file_path, &"new".into(), LineEnding::Unix)
+            .await
+            .unwrap();
+        std::fs::write(&reference_path, "new").unwrap();
+        let mode = std::fs::metadata(&new_file_path)
+            .unwrap()
+            .permissions()
+            .mode();
+        let reference_mode = std::fs::metadata(&reference_path)
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode, reference_mode);
+        std::fs::remove_file(&new_file_path).unwrap();
+        std::fs::remove_file(&reference_path).unwrap();
+
+        // Cancelled saves leave the file alone, along with its directory.
+        std::fs::set_permissions(&file_path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        let progress = SaveProgress::default();
+        progress.cancel();
+        let error = fs
+            .save_with_progress(
+                &file_path,
+                &"newer".into(),
+                LineEnding::Unix,
+                Encoding::Utf8,
+                &progress,
+            )
+            .await
+            .unwrap_err();
+        assert!(error.is::<SaveCancelled>(), "{error}");
+        assert_eq!(std::fs::read_to_string(&file_path).unwrap(), "new");
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 2);
+
+        let progress = SaveProgress::default();
+        fs.save_with_progress(
+            &file_path,
+            &"newer".into(),
+            LineEnding::Unix,
+            Encoding::Utf8,
+            &progress,
+        )
+        .await
+        .unwrap();
+        assert_eq!(std::fs::read_to_string(&file_path).unwrap(), "newer");
+        assert_eq!(progress.written(), (5, 5));
+    }
+
     #[gpui::test]
     async fn test_fake_fs(executor: BackgroundExecutor) {
         let fs = FakeFs::new(executor.clone());
@@ -0,0 +1,199 @@
+use std::{
+    collections::HashSet,
+    path::PathBuf,
+    thread,
+    time::{Duration, Instant},
+};
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use fuzzy::{match_fixed_path_set, CharBag, PathMatch, PathMatchCandidate, PreparedPath};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+const PATH_COUNT: usize = 500_000;
+const MAX_RESULTS: usize = 100;
+const QUERIES: &[&str] = &["main", "fbv", "src/edit", "wrkspc tests"];
+const WORDS: &[&str] = &[
+    "src",
+    "crates",
+    "editor",
+    "workspace",
+    "buffer",
+    "view",
+    "fuzzy",
+    "main",
+    "lib",
+    "tests",
+    "util",
+    "project",
+    "panel",
+    "file",
+    "finder",
+    "display",
+    "map",
+    "server",
+    "api",
+    "handler",
+];
+const EXTENSIONS: &[&str] = &["rs", "toml", "md", "json", "ts"];
+
+fn generate_paths(mut rng: StdRng, count: usize) -> Vec<PathBuf> {
+    let mut paths = HashSet::new();
+    while paths.len() < count {
+        paths.insert(generate_path(&mut rng));
+    }
+    paths.into_iter().collect()
+}
+
+fn generate_path(rng: &mut StdRng) -> PathBuf {
+    let mut path = PathBuf::new();
+    for _ in 0..rng.gen_range(1..6) {
+        path.push(WORDS.choose(rng).unwrap());
+    }
+    let file_name = format!(
+        "{}_{}.{}",
+        WORDS.choose(rng).unwrap(),
+        WORDS.choose(rng).unwrap(),
+        EXTENSIONS.choose(rng).unwrap()
+    );
+    path.push(file_name);
+    path
+}
+
+/// Matches the paths by splitting them into one shard per thread and merging the top
+/// results of every shard, the same way `match_path_sets` does on the background executor.
+fn match_sharded(
+    paths: &[PathBuf],
+    char_bags: &[CharBag],
+    query: &str,
+    thread_count: usize,
+) -> Vec<PathMatch> {
+    let shard_size = (paths.len() + thread_count - 1) / thread_count;
+    let shard_results = thread::scope(|scope| {
+        paths
+            .chunks(shard_size)
+            .zip(char_bags.chunks(shard_size))
+            .map(|(paths, char_bags)| {
+                scope.spawn(move || {
+                    let candidates = paths
+                        .iter()
+                        .zip(char_bags)
+                        .map(|(path, char_bag)| PathMatchCandidate {
+                            path,
+                            char_bag: *char_bag,
+                            is_ignored: false,
+                            prepared: None,
+                        })
+                        .collect();
+                    match_fixed_path_set(candidates, 0, query, false, true, MAX_RESULTS)
+                })
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|shard| shard.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+
+    let mut results = shard_results.into_iter().flatten().collect::<Vec<_>>();
+    results.sort_unstable_by(|a, b| b.cmp(a));
+    results.truncate(MAX_RESULTS);
+    results
+}
+
+fn fuzzy_benchmarks(c: &mut Criterion) {
+    let paths = generate_paths(StdRng::seed_from_u64(1), PATH_COUNT);
+    let char_bags = paths
+        .iter()
+        .map(|path| CharBag::from(path.to_string_lossy().to_lowercase().as_str()))
+        .collect::<Vec<_>>();
+
+    for query in QUERIES {
+        let serial_results = match_sharded(&paths, &char_bags, query, 1);
+        for thread_count in [2, 4, 8] {
+            assert_eq!(
+                match_sharded(&paths, &char_bags, query, thread_count),
+                serial_results,
+                "sharded results for {query:?} differ from the serial ones"
+            );
+        }
+    }
+
+    let mut group = c.benchmark_group("match_path_set");
+    group.sample_size(10);
+    for thread_count in [1, 2, 4, 8] {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(thread_count),
+            &thread_count,
+            |b, &thread_count| {
+                b.iter(|| {
+                    for query in QUERIES {
+                        black_box(match_sharded(&paths, &char_bags, query, thread_count));
+                    }
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+/// Matches paths that were prepared ahead of time, after replacing a share of them with new
+/// ones before every round of queries, the way the paths of a worktree change between queries.
+/// Only the new paths get prepared, outside of the measured time, so the latency of the queries
+/// shouldn't depend on how many paths changed.
+fn prepared_path_benchmarks(c: &mut Criterion) {
+    let paths = generate_paths(StdRng::seed_from_u64(1), PATH_COUNT);
+
+    let mut group = c.benchmark_group("match_prepared_path_set");
+    group.sample_size(10);
+    for churn_percent in [0, 1, 10] {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(churn_percent),
+            &churn_percent,
+            |b, &churn_percent| {
+                let mut rng = StdRng::seed_from_u64(2);
+                let mut paths = paths.clone();
+                let mut prepared_paths = paths
+                    .iter()
+                    .map(|path| PreparedPath::new(path))
+                    .collect::<Vec<_>>();
+                b.iter_custom(|iters| {
+                    let mut elapsed = Duration::ZERO;
+                    for _ in 0..iters {
+                        for _ in 0..paths.len() * churn_percent / 100 {
+                            let ix = rng.gen_range(0..paths.len());
+                            paths[ix] = generate_path(&mut rng);
+                            prepared_paths[ix] = PreparedPath::new(&paths[ix]);
+                        }
+
+                        let start = Instant::now();
+                        for query in QUERIES {
+                            let candidates = paths
+                                .iter()
+                                .zip(&prepared_paths)
+                                .map(|(path, prepared)| PathMatchCandidate {
+                                    path,
+                                    char_bag: prepared.char_bag(),
+                                    is_ignored: false,
+                                    prepared: Some(prepared),
+                                })
+                                .collect();
+                            black_box(match_fixed_path_set(
+                                candidates,
+                                0,
+                                query,
+                                false,
+                                true,
+                                MAX_RESULTS,
+                            ));
+                        }
+                        elapsed += start.elapsed();
+                    }
+                    elapsed
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, fuzzy_benchmarks, prepared_path_benchmarks);
+criterion_main!(benches);
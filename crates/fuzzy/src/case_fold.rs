@@ -0,0 +1,70 @@
+use std::{char::ToLowercase, slice};
+
+/// Iterator over the chars that a single char folds to, see [`fold_case`].
+pub(crate) enum CaseFold {
+    Lowercase(ToLowercase),
+    Chars(slice::Iter<'static, char>),
+}
+
+impl Iterator for CaseFold {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        match self {
+            CaseFold::Lowercase(chars) => chars.next(),
+            CaseFold::Chars(chars) => chars.next().copied(),
+        }
+    }
+}
+
+/// Folds the case of a char, so that chars which only differ by case compare equal.
+///
+/// This is the char's lowercase mapping, except for the chars whose lowercase form isn't
+/// what people type when searching for them: `ß` folds to `ss`, the final sigma `ς` to `σ`,
+/// and both the dotted capital `İ` and the dotless `ı` to a plain `i`, so that Turkish
+/// names match regardless of the keyboard layout used to type the query.
+pub(crate) fn fold_case(c: char) -> CaseFold {
+    let folded: &'static [char] = match c {
+        'ß' | 'ẞ' => &['s', 's'],
+        'ſ' => &['s'],
+        'İ' | 'ı' => &['i'],
+        'ς' => &['σ'],
+        'ϐ' => &['β'],
+        'ϑ' => &['θ'],
+        'ϕ' => &['φ'],
+        'ϖ' => &['π'],
+        'ϰ' => &['κ'],
+        'ϱ' => &['ρ'],
+        'ϵ' => &['ε'],
+        _ => return CaseFold::Lowercase(c.to_lowercase()),
+    };
+    CaseFold::Chars(folded.iter())
+}
+
+/// Folds the case of a char that has to stay a single char, leaving the chars that
+/// fold to several chars unchanged.
+pub(crate) fn fold_char(c: char) -> char {
+    if c.is_ascii() {
+        return c.to_ascii_lowercase();
+    }
+    let mut folded = fold_case(c);
+    match (folded.next(), folded.next()) {
+        (Some(folded), None) => folded,
+        _ => c,
+    }
+}
+
+/// Returns the chars of the query along with their case folded counterparts. Both are
+/// aligned, repeating the original char for every char it folds to, so that the same
+/// index refers to the same query char in both.
+pub(crate) fn fold_query(query: &str) -> (Vec<char>, Vec<char>) {
+    let mut query_chars = Vec::with_capacity(query.len());
+    let mut folded_chars = Vec::with_capacity(query.len());
+    for c in query.chars() {
+        for folded in fold_case(c) {
+            query_chars.push(c);
+            folded_chars.push(folded);
+        }
+    }
+    (query_chars, folded_chars)
+}
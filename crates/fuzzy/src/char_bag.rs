@@ -1,5 +1,7 @@
 use std::iter::FromIterator;
 
+use crate::case_fold::fold_case;
+
 #[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub struct CharBag(u64);
 
@@ -8,7 +10,24 @@ impl CharBag {
         self.0 & other.0 == other.0
     }
 
+    pub fn union(self, other: CharBag) -> CharBag {
+        CharBag(self.0 | other.0)
+    }
+
     fn insert(&mut self, c: char) {
+        if c.is_ascii() {
+            self.insert_ascii(c);
+        } else {
+            // Non-ASCII chars may fold to ASCII ones, such as `ß` to `ss`.
+            for c in fold_case(c) {
+                if c.is_ascii() {
+                    self.insert_ascii(c);
+                }
+            }
+        }
+    }
+
+    fn insert_ascii(&mut self, c: char) {
         let c = c.to_ascii_lowercase();
         if c.is_ascii_lowercase() {
             let mut count = self.0;
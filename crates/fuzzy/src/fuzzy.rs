@@ -1,10 +1,15 @@
+mod case_fold;
 mod char_bag;
 mod matcher;
 mod paths;
+mod prepared_path;
 mod strings;
 
 pub use char_bag::CharBag;
+pub use matcher::ScoringWeights;
 pub use paths::{
-    match_fixed_path_set, match_path_sets, PathMatch, PathMatchCandidate, PathMatchCandidateSet,
+    match_fixed_path_set, match_path_sets, match_path_sets_streaming, PathMatch,
+    PathMatchCandidate, PathMatchCandidateSet, PathMatchSnapshot,
 };
+pub use prepared_path::PreparedPath;
 pub use strings::{match_strings, StringMatch, StringMatchCandidate};
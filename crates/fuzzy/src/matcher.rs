@@ -3,25 +3,126 @@ use std::{
     sync::atomic::{self, AtomicBool},
 };
 
-use crate::CharBag;
+use crate::{case_fold::fold_case, CharBag, PreparedPath};
 
 const BASE_DISTANCE_PENALTY: f64 = 0.6;
 const ADDITIONAL_DISTANCE_PENALTY: f64 = 0.05;
 const MIN_DISTANCE_PENALTY: f64 = 0.2;
+const DIRECTORY_MATCH_PENALTY: f64 = 0.7;
+/// How much a match that directly follows the previous one multiplies the score by, so that
+/// every char that extends a streak of consecutive matches adds to its score. Fuzzy scores
+/// are scaled down by the bonus of a streak of the whole query, so that contiguous matches
+/// score the same as an exact match of the same chars.
+const CONSECUTIVE_MATCH_BONUS: f64 = 1.25;
+
+/// How the shape of a match weighs into its score, which differs between kinds of candidates.
+/// Matches of a term's chars as a contiguous substring of the candidate score the most.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScoringWeights {
+    /// The fraction of its score, from 0 to 1, that a match loses when the term's chars
+    /// aren't contiguous in the candidate, so that contiguous matches decisively beat the
+    /// same chars scattered over another candidate.
+    pub contiguity: f64,
+    /// The fraction of the score of a contiguous match, from 0 to 1, that a match of the
+    /// term's chars at the starts of successive words or path components scores, like `wv`
+    /// in `workspace_view.rs`.
+    pub acronym: f64,
+}
+
+impl ScoringWeights {
+    /// The weights for long candidates like paths, where short queries match scattered
+    /// chars of many candidates.
+    pub const PATHS: Self = Self {
+        contiguity: 0.6,
+        acronym: 0.25,
+    };
+
+    /// The weights for short candidates like the names of commands, which are often looked
+    /// up by the initials of their words.
+    pub const STRINGS: Self = Self {
+        contiguity: 0.3,
+        acronym: 0.9,
+    };
+}
 
 pub struct Matcher<'a> {
+    /// The term of the query that is currently being scored.
     query: &'a [char],
     lowercase_query: &'a [char],
+    /// All terms of the query, each of which has to match for a candidate to match.
+    terms: Vec<QueryTerm<'a>>,
     query_char_bag: CharBag,
     smart_case: bool,
+    prefer_file_name: bool,
+    weights: ScoringWeights,
     max_results: usize,
     min_score: f64,
+    /// The most that the matches built from the candidates add to their scores.
+    max_score_bonus: f64,
+    file_name_start: usize,
+    /// The indices of the chars of the prefix and candidate that start a word.
+    word_starts: Vec<usize>,
+    best_term_match_positions: Vec<usize>,
     match_positions: Vec<usize>,
+    term_match_positions: Vec<usize>,
+    /// For every char of the lowercased candidate, the byte offset within the original
+    /// candidate of the char it was lowercased from.
+    candidate_char_offsets: Vec<usize>,
     last_positions: Vec<usize>,
-    score_matrix: Vec<Option<f64>>,
+    /// Memoized scores, along with the score accumulated before reaching them.
+    score_matrix: Vec<Option<(f64, f64)>>,
     best_position_matrix: Vec<usize>,
 }
 
+/// A term of a query, matched either fuzzily or as a contiguous substring.
+#[derive(Clone, Copy, Debug)]
+struct QueryTerm<'a> {
+    query: &'a [char],
+    lowercase_query: &'a [char],
+    exact: bool,
+    /// The term has to match at the start of the candidate.
+    anchored_start: bool,
+    /// The term has to match at the end of the candidate.
+    anchored_end: bool,
+}
+
+impl<'a> QueryTerm<'a> {
+    fn fuzzy(query: &'a [char], lowercase_query: &'a [char]) -> Self {
+        Self {
+            query,
+            lowercase_query,
+            exact: false,
+            anchored_start: false,
+            anchored_end: false,
+        }
+    }
+
+    /// Parses the query syntax of a single term: `'term` matches a contiguous substring,
+    /// `^term` matches at the start of the candidate and `term$` at its end. A term that
+    /// is empty once the syntax is stripped is matched literally instead.
+    fn parse(query: &'a [char], lowercase_query: &'a [char]) -> Self {
+        let mut term = Self::fuzzy(query, lowercase_query);
+        if let Some(&first @ ('\'' | '^')) = term.query.first() {
+            term.anchored_start = first == '^';
+            term.exact = true;
+            term.query = &term.query[1..];
+            term.lowercase_query = &term.lowercase_query[1..];
+        }
+        if term.query.len() > 1 && term.query.last() == Some(&'$') {
+            term.anchored_end = true;
+            term.exact = true;
+            term.query = &term.query[..term.query.len() - 1];
+            term.lowercase_query = &term.lowercase_query[..term.lowercase_query.len() - 1];
+        }
+
+        if term.query.is_empty() {
+            Self::fuzzy(query, lowercase_query)
+        } else {
+            term
+        }
+    }
+}
+
 pub trait Match: Ord {
     fn score(&self) -> f64;
     fn set_positions(&mut self, positions: Vec<usize>);
@@ -30,6 +131,10 @@ pub trait Match: Ord {
 pub trait MatchCandidate {
     fn has_chars(&self, bag: CharBag) -> bool;
     fn to_string(&self) -> Cow<'_, str>;
+    /// The candidate's chars, folded ahead of time, if it has them.
+    fn prepared(&self) -> Option<&PreparedPath> {
+        None
+    }
 }
 
 impl<'a> Matcher<'a> {
@@ -38,22 +143,69 @@ impl<'a> Matcher<'a> {
         lowercase_query: &'a [char],
         query_char_bag: CharBag,
         smart_case: bool,
+        prefer_file_name: bool,
         max_results: usize,
     ) -> Self {
         Self {
             query,
             lowercase_query,
+            terms: vec![QueryTerm::fuzzy(query, lowercase_query)],
             query_char_bag,
             min_score: 0.0,
+            max_score_bonus: 0.0,
+            file_name_start: 0,
+            word_starts: Vec::new(),
+            best_term_match_positions: Vec::new(),
             last_positions: vec![0; lowercase_query.len()],
-            match_positions: vec![0; query.len()],
+            match_positions: Vec::new(),
+            term_match_positions: vec![0; query.len()],
+            candidate_char_offsets: Vec::new(),
             score_matrix: Vec::new(),
             best_position_matrix: Vec::new(),
             smart_case,
+            prefer_file_name,
+            weights: ScoringWeights::PATHS,
             max_results,
         }
     }
 
+    /// Sets how the shape of a match weighs into its score, which defaults to
+    /// [`ScoringWeights::PATHS`].
+    pub fn weights(mut self, weights: ScoringWeights) -> Self {
+        self.weights = weights;
+        self
+    }
+
+    /// Lets the matches built from the candidates score up to `max_score_bonus` higher than
+    /// the candidates themselves, which keeps the candidates that only make it into the
+    /// results thanks to that bonus from being skipped.
+    pub fn max_score_bonus(mut self, max_score_bonus: f64) -> Self {
+        self.max_score_bonus = max_score_bonus;
+        self
+    }
+
+    /// Treats every whitespace-separated term of the query as a separate query, all of
+    /// which have to match a candidate, in any order. Leading and trailing whitespace is
+    /// ignored. Terms prefixed with `'` or `^`, or suffixed with `$`, have to match
+    /// exactly, see [`QueryTerm::parse`].
+    pub fn parse_terms(mut self) -> Self {
+        let terms = self
+            .query
+            .split(|c| c.is_whitespace())
+            .zip(self.lowercase_query.split(|c| c.is_whitespace()))
+            .filter(|(term, _)| !term.is_empty())
+            .map(|(term, lowercase_term)| QueryTerm::parse(term, lowercase_term))
+            .collect::<Vec<_>>();
+        if !terms.is_empty() {
+            // Every term only needs its own characters to be present in a candidate.
+            self.query_char_bag = terms.iter().fold(CharBag::default(), |bag, term| {
+                bag.union(CharBag::from(term.lowercase_query))
+            });
+            self.terms = terms;
+        }
+        self
+    }
+
     pub fn match_candidates<C: MatchCandidate, R, F>(
         &mut self,
         prefix: &[char],
@@ -70,39 +222,50 @@ impl<'a> Matcher<'a> {
         let mut lowercase_candidate_chars = Vec::new();
 
         for candidate in candidates {
-            if !candidate.has_chars(self.query_char_bag) {
-                continue;
-            }
-
+            // Check for cancellation before anything else, so that a newer query can abort
+            // this pass promptly even when most of the candidates don't contain the query.
             if cancel_flag.load(atomic::Ordering::Relaxed) {
                 break;
             }
 
-            candidate_chars.clear();
-            lowercase_candidate_chars.clear();
-            for c in candidate.to_string().chars() {
-                candidate_chars.push(c);
-                lowercase_candidate_chars.append(&mut c.to_lowercase().collect::<Vec<_>>());
-            }
-
-            if !self.find_last_positions(lowercase_prefix, &lowercase_candidate_chars) {
+            if !candidate.has_chars(self.query_char_bag) {
                 continue;
             }
 
-            let matrix_len = self.query.len() * (prefix.len() + candidate_chars.len());
-            self.score_matrix.clear();
-            self.score_matrix.resize(matrix_len, None);
-            self.best_position_matrix.clear();
-            self.best_position_matrix.resize(matrix_len, 0);
+            // Some chars fold to multiple chars, in which case the original char is repeated
+            // to keep both sequences aligned.
+            candidate_chars.clear();
+            lowercase_candidate_chars.clear();
+            self.candidate_char_offsets.clear();
+            let prepared = candidate.prepared();
+            if let Some(prepared) = prepared {
+                prepared.fill_chars(
+                    &candidate.to_string(),
+                    &mut candidate_chars,
+                    &mut lowercase_candidate_chars,
+                    &mut self.candidate_char_offsets,
+                );
+            } else {
+                for (byte_ix, c) in candidate.to_string().char_indices() {
+                    for lowercase_c in fold_case(c) {
+                        candidate_chars.push(c);
+                        lowercase_candidate_chars.push(lowercase_c);
+                        self.candidate_char_offsets.push(byte_ix);
+                    }
+                }
+            }
 
-            let score = self.score_match(
+            let score = self.score_terms(
                 &candidate_chars,
                 &lowercase_candidate_chars,
                 prefix,
                 lowercase_prefix,
+                prepared,
             );
 
-            if score > 0.0 {
+            // Avoid building matches that can't make it into the results.
+            let is_full = results.len() == self.max_results;
+            if score > 0.0 && !(is_full && score + self.max_score_bonus < self.min_score) {
                 let mut mat = build_match(&candidate, score);
                 if let Err(i) = results.binary_search_by(|m| mat.cmp(m)) {
                     if results.len() < self.max_results {
@@ -121,6 +284,144 @@ impl<'a> Matcher<'a> {
         }
     }
 
+    /// Scores every term of the query against the candidate, returning the mean of their
+    /// scores, or zero if any of them doesn't match. The positions matched by each term
+    /// are merged into `match_positions`.
+    fn score_terms(
+        &mut self,
+        path: &[char],
+        path_cased: &[char],
+        prefix: &[char],
+        lowercase_prefix: &[char],
+        prepared: Option<&PreparedPath>,
+    ) -> f64 {
+        let is_path_sep = |c: &char| *c == '/' || *c == '\\';
+        self.file_name_start = path
+            .iter()
+            .rposition(is_path_sep)
+            .map(|ix| prefix.len() + ix + 1)
+            .or_else(|| prefix.iter().rposition(is_path_sep).map(|ix| ix + 1))
+            .unwrap_or(0);
+
+        let path_len = prefix.len() + path.len();
+        let char_at = |ix: usize| {
+            if ix < prefix.len() {
+                prefix[ix]
+            } else {
+                path[ix - prefix.len()]
+            }
+        };
+        let is_word_start =
+            |ix: usize| ix == 0 || word_boundary_score(char_at(ix - 1), char_at(ix)).is_some();
+        self.word_starts.clear();
+        if let Some(prepared) = prepared {
+            // Only the word starts up to the first char of the path depend on the prefix.
+            let unprepared_len = prefix.len() + path.len().min(1);
+            self.word_starts
+                .extend((0..unprepared_len).filter(|&ix| is_word_start(ix)));
+            self.word_starts
+                .extend(prepared.word_starts().map(|ix| prefix.len() + ix));
+        } else {
+            self.word_starts
+                .extend((0..path_len).filter(|&ix| is_word_start(ix)));
+        }
+
+        let mut score = 0.0;
+        self.match_positions.clear();
+        for term_ix in 0..self.terms.len() {
+            let term = self.terms[term_ix];
+            self.query = term.query;
+            self.lowercase_query = term.lowercase_query;
+
+            let term_score = if term.exact {
+                self.score_exact_match(term, path, path_cased, prefix, lowercase_prefix)
+            } else {
+                self.last_positions.resize(self.lowercase_query.len(), 0);
+                self.term_match_positions.resize(self.query.len(), 0);
+                if !self.find_last_positions(lowercase_prefix, path_cased) {
+                    return 0.0;
+                }
+
+                let matrix_len = self.query.len() * (prefix.len() + path.len());
+                self.score_matrix.clear();
+                self.score_matrix.resize(matrix_len, None);
+                self.best_position_matrix.clear();
+                self.best_position_matrix.resize(matrix_len, 0);
+
+                self.score_fuzzy_term(term, path, path_cased, prefix, lowercase_prefix)
+            };
+            if term_score <= 0.0 {
+                return 0.0;
+            }
+            score += term_score;
+            self.match_positions
+                .extend_from_slice(&self.term_match_positions);
+        }
+
+        // Distinct query chars may match chars lowercased from the same original char.
+        if self.terms.len() > 1 {
+            self.match_positions.sort_unstable();
+        }
+        self.match_positions.dedup();
+        score / self.terms.len() as f64
+    }
+
+    /// Scores a fuzzy term, taking the contiguous and the acronym matches of its characters
+    /// into account along with the best fuzzy match. The positions of the match with the best
+    /// score end up in `term_match_positions`.
+    fn score_fuzzy_term(
+        &mut self,
+        term: QueryTerm,
+        path: &[char],
+        path_cased: &[char],
+        prefix: &[char],
+        lowercase_prefix: &[char],
+    ) -> f64 {
+        let mut best_score = (1.0 - self.weights.contiguity)
+            * self.score_match(path, path_cased, prefix, lowercase_prefix);
+        if best_score <= 0.0 {
+            return 0.0;
+        }
+        self.best_term_match_positions.clear();
+        self.best_term_match_positions
+            .extend_from_slice(&self.term_match_positions);
+
+        let contiguous_term = QueryTerm {
+            exact: true,
+            ..term
+        };
+        let contiguous_score =
+            self.score_exact_match(contiguous_term, path, path_cased, prefix, lowercase_prefix);
+        if contiguous_score > best_score {
+            best_score = contiguous_score;
+            self.best_term_match_positions.clear();
+            self.best_term_match_positions
+                .extend_from_slice(&self.term_match_positions);
+        }
+
+        // Acronyms have no streaks of consecutive matches, so they're scaled down like the
+        // fuzzy matches without any.
+        let acronym_score = self.weights.acronym
+            * self.score_acronym_match(path, path_cased, prefix, lowercase_prefix)
+            / self.max_streak_bonus();
+        if acronym_score > best_score {
+            best_score = acronym_score;
+            self.best_term_match_positions.clear();
+            self.best_term_match_positions
+                .extend_from_slice(&self.term_match_positions);
+        }
+
+        self.term_match_positions.clear();
+        self.term_match_positions
+            .extend_from_slice(&self.best_term_match_positions);
+        best_score
+    }
+
+    /// The bonus of a streak of consecutive matches of the whole term.
+    fn max_streak_bonus(&self) -> f64 {
+        CONSECUTIVE_MATCH_BONUS.powi(self.query.len() as i32 - 1)
+    }
+
     fn find_last_positions(
         &mut self,
         lowercase_prefix: &[char],
@@ -147,15 +448,10 @@ impl<'a> Matcher<'a> {
         prefix: &[char],
         lowercase_prefix: &[char],
     ) -> f64 {
-        let score = self.recursive_score_match(
-            path,
-            path_cased,
-            prefix,
-            lowercase_prefix,
-            0,
-            0,
-            self.query.len() as f64,
-        ) * self.query.len() as f64;
+        let scale = self.query.len() as f64 / self.max_streak_bonus();
+        let score =
+            self.recursive_score_match(path, path_cased, prefix, lowercase_prefix, 0, 0, scale)
+                * scale;
 
         if score <= 0.0 {
             return 0.0;
@@ -163,25 +459,182 @@ impl<'a> Matcher<'a> {
 
         let path_len = prefix.len() + path.len();
         let mut cur_start = 0;
-        let mut byte_ix = 0;
-        let mut char_ix = 0;
         for i in 0..self.query.len() {
             let match_char_ix = self.best_position_matrix[i * path_len + cur_start];
-            while char_ix < match_char_ix {
-                let ch = prefix
-                    .get(char_ix)
-                    .or_else(|| path.get(char_ix - prefix.len()))
-                    .unwrap();
-                byte_ix += ch.len_utf8();
-                char_ix += 1;
-            }
             cur_start = match_char_ix + 1;
-            self.match_positions[i] = byte_ix;
+            self.term_match_positions[i] = self.byte_offset(prefix, match_char_ix);
         }
 
         score
     }
 
+    /// Converts an index into the lowercased prefix and candidate chars to a byte offset
+    /// within the original prefix and candidate strings.
+    fn byte_offset(&self, prefix: &[char], char_ix: usize) -> usize {
+        let byte_len = |chars: &[char]| chars.iter().map(|c| c.len_utf8()).sum::<usize>();
+        if char_ix < prefix.len() {
+            byte_len(&prefix[..char_ix])
+        } else {
+            byte_len(prefix) + self.candidate_char_offsets[char_ix - prefix.len()]
+        }
+    }
+
+    /// Scores the best contiguous occurrence of an exact term within the candidate, the
+    /// same way as a fuzzy match of those characters would be scored.
+    fn score_exact_match(
+        &mut self,
+        term: QueryTerm,
+        path: &[char],
+        path_cased: &[char],
+        prefix: &[char],
+        lowercase_prefix: &[char],
+    ) -> f64 {
+        let path_len = prefix.len() + path.len();
+        let term_len = term.lowercase_query.len();
+        if term_len > path_len {
+            return 0.0;
+        }
+        let char_at = |ix: usize| prefix.get(ix).or_else(|| path.get(ix - prefix.len()));
+        let lowercase_char_at = |ix: usize| {
+            lowercase_prefix
+                .get(ix)
+                .or_else(|| path_cased.get(ix - prefix.len()))
+        };
+
+        let mut best_score = 0.0;
+        let mut best_start = 0;
+        let mut last_slash = 0;
+        for start in 0..=path_len - term_len {
+            if start > 0 && char_at(start - 1).map_or(false, |c| *c == '/' || *c == '\\') {
+                last_slash = start - 1;
+            }
+            if term.anchored_start && start != 0 && start != prefix.len() {
+                continue;
+            }
+            if term.anchored_end && start + term_len != path_len {
+                continue;
+            }
+            if !(0..term_len)
+                .all(|i| lowercase_char_at(start + i) == Some(&term.lowercase_query[i]))
+            {
+                continue;
+            }
+
+            let mut score = match (start.checked_sub(1).and_then(char_at), char_at(start)) {
+                (Some(&last), Some(&curr)) => {
+                    word_boundary_score(last, curr).unwrap_or(BASE_DISTANCE_PENALTY)
+                }
+                _ => 1.0,
+            };
+            score /= (path_len - last_slash) as f64;
+            for i in 0..term_len {
+                let curr = char_at(start + i).copied();
+                if self.smart_case && term.query.get(i).copied() != curr {
+                    score *= 0.001;
+                }
+                if self.prefer_file_name && start + i < self.file_name_start {
+                    score *= DIRECTORY_MATCH_PENALTY;
+                }
+            }
+
+            if score > best_score {
+                best_score = score;
+                best_start = start;
+            }
+        }
+
+        if best_score > 0.0 {
+            self.term_match_positions.clear();
+            for i in 0..term_len {
+                let byte_ix = self.byte_offset(prefix, best_start + i);
+                self.term_match_positions.push(byte_ix);
+            }
+        }
+        best_score * term_len as f64
+    }
+
+    /// Scores the best run of successive word starts in the candidate that match the chars of
+    /// the term one by one, by how many of the words of the path components it spans it
+    /// covers.
+    fn score_acronym_match(
+        &mut self,
+        path: &[char],
+        path_cased: &[char],
+        prefix: &[char],
+        lowercase_prefix: &[char],
+    ) -> f64 {
+        let path_len = prefix.len() + path.len();
+        let term_len = self.lowercase_query.len();
+        if term_len < 2 || term_len > self.word_starts.len() {
+            return 0.0;
+        }
+        let char_at = |ix: usize| prefix.get(ix).or_else(|| path.get(ix - prefix.len()));
+        let lowercase_char_at = |ix: usize| {
+            lowercase_prefix
+                .get(ix)
+                .or_else(|| path_cased.get(ix - prefix.len()))
+        };
+        let is_path_sep = |ix: usize| char_at(ix).map_or(false, |c| *c == '/' || *c == '\\');
+
+        let mut best_score = 0.0;
+        let mut best_run = 0;
+        for run in 0..=self.word_starts.len() - term_len {
+            let positions = &self.word_starts[run..run + term_len];
+            if !positions
+                .iter()
+                .zip(self.lowercase_query)
+                .all(|(ix, query_char)| lowercase_char_at(*ix) == Some(query_char))
+            {
+                continue;
+            }
+
+            let start = positions[0];
+            let mut score = match start.checked_sub(1).and_then(char_at) {
+                Some(&last) => word_boundary_score(last, *char_at(start).unwrap())
+                    .unwrap_or(BASE_DISTANCE_PENALTY),
+                None => 1.0,
+            };
+            // An acronym of every word of the path components it spans scores like a match of
+            // those whole components.
+            let end = positions[term_len - 1];
+            let components_start = (0..start)
+                .rev()
+                .find(|ix| is_path_sep(*ix))
+                .map_or(0, |ix| ix + 1);
+            let components_end = (end..path_len)
+                .find(|ix| is_path_sep(*ix))
+                .unwrap_or(path_len);
+            let words = self
+                .word_starts
+                .iter()
+                .filter(|ix| (components_start..components_end).contains(*ix))
+                .count();
+            score /= words as f64;
+            for (i, ix) in positions.iter().enumerate() {
+                if self.smart_case && self.query.get(i) != char_at(*ix) {
+                    score *= 0.001;
+                }
+                if self.prefer_file_name && *ix < self.file_name_start {
+                    score *= DIRECTORY_MATCH_PENALTY;
+                }
+            }
+
+            if score > best_score {
+                best_score = score;
+                best_run = run;
+            }
+        }
+
+        if best_score > 0.0 {
+            self.term_match_positions.clear();
+            for i in 0..term_len {
+                let byte_ix = self.byte_offset(prefix, self.word_starts[best_run + i]);
+                self.term_match_positions.push(byte_ix);
+            }
+        }
+        best_score * term_len as f64
+    }
+
     #[allow(clippy::too_many_arguments)]
     fn recursive_score_match(
         &mut self,
@@ -199,10 +652,25 @@ impl<'a> Matcher<'a> {
 
         let path_len = prefix.len() + path.len();
 
-        if let Some(memoized) = self.score_matrix[query_idx * path_len + path_idx] {
-            return memoized;
+        // Branches get pruned based on the score accumulated so far, so a memoized score is
+        // only exact for accumulated scores up to the one it was computed with.
+        if let Some((memoized, memoized_cur_score)) =
+            self.score_matrix[query_idx * path_len + path_idx]
+        {
+            if self.min_score == 0.0 || cur_score <= memoized_cur_score {
+                return memoized;
+            }
         }
 
+        // Each of the remaining chars can at best extend a streak of consecutive matches.
+        let max_remaining_score =
+            CONSECUTIVE_MATCH_BONUS.powi((self.query.len() - query_idx - 1) as i32);
+        let max_score = if query_idx > 0 {
+            max_remaining_score * CONSECUTIVE_MATCH_BONUS
+        } else {
+            max_remaining_score
+        };
+
         let mut score = 0.0;
         let mut best_position = 0;
 
@@ -237,14 +705,8 @@ impl<'a> Matcher<'a> {
                         path[j - 1 - prefix.len()]
                     };
 
-                    if last == '/' {
-                        char_score = 0.9;
-                    } else if (last == '-' || last == '_' || last == ' ' || last.is_numeric())
-                        || (last.is_lowercase() && curr.is_uppercase())
-                    {
-                        char_score = 0.8;
-                    } else if last == '.' {
-                        char_score = 0.7;
+                    if let Some(boundary_score) = word_boundary_score(last, curr) {
+                        char_score = boundary_score;
                     } else if query_idx == 0 {
                         char_score = BASE_DISTANCE_PENALTY;
                     } else {
@@ -253,6 +715,8 @@ impl<'a> Matcher<'a> {
                                 - (j - path_idx - 1) as f64 * ADDITIONAL_DISTANCE_PENALTY,
                         );
                     }
+                } else if query_idx > 0 {
+                    char_score = CONSECUTIVE_MATCH_BONUS;
                 }
 
                 // Apply a severe penalty if the case doesn't match.
@@ -262,6 +726,13 @@ impl<'a> Matcher<'a> {
                     char_score *= 0.001;
                 }
 
+                // Matches within the parent directories count for less than the ones
+                // within the file name, so that `main` ranks `src/main.rs` above
+                // `src/main_helpers/util.rs`.
+                if self.prefer_file_name && j < self.file_name_start {
+                    char_score *= DIRECTORY_MATCH_PENALTY;
+                }
+
                 let mut multiplier = char_score;
 
                 // Scale the score based on how deep within the path we found the match.
@@ -270,10 +741,13 @@ impl<'a> Matcher<'a> {
                 }
 
                 let mut next_score = 1.0;
-                if self.min_score > 0.0 {
+                // The minimum score only bounds the score of a single term query, as the
+                // terms of a multi-term query are averaged.
+                if self.min_score > 0.0 && self.terms.len() == 1 {
                     next_score = cur_score * multiplier;
-                    // Scores only decrease. If we can't pass the previous best, bail
-                    if next_score < self.min_score {
+                    // If even a streak of consecutive matches of the remaining chars can't
+                    // pass the previous best, bail
+                    if next_score * max_remaining_score < self.min_score {
                         // Ensure that score is non-zero so we use it in the memo table.
                         if score == 0.0 {
                             score = 1e-18;
@@ -295,8 +769,8 @@ impl<'a> Matcher<'a> {
                 if new_score > score {
                     score = new_score;
                     best_position = j;
-                    // Optimization: can't score better than 1.
-                    if new_score == 1.0 {
+                    // Optimization: can't score better than that.
+                    if new_score == max_score {
                         break;
                     }
                 }
@@ -307,14 +781,34 @@ impl<'a> Matcher<'a> {
             self.best_position_matrix[query_idx * path_len + path_idx] = best_position;
         }
 
-        self.score_matrix[query_idx * path_len + path_idx] = Some(score);
+        self.score_matrix[query_idx * path_len + path_idx] = Some((score, cur_score));
         score
     }
 }
 
+/// Scores a match of `curr` based on the character preceding it, rewarding matches
+/// that land at the start of a path component or a word. Returns `None` when `curr`
+/// sits in the middle of a word, in which case the distance from the previous match
+/// determines the score instead. Matches that directly follow the previous one get the
+/// [`CONSECUTIVE_MATCH_BONUS`] instead, which lets unbroken runs of matched characters
+/// outrank scattered ones.
+pub(crate) fn word_boundary_score(last: char, curr: char) -> Option<f64> {
+    if last == '/' || last == '\\' {
+        Some(0.9)
+    } else if (last == '-' || last == '_' || last == ' ' || last.is_numeric())
+        || (last.is_lowercase() && curr.is_uppercase())
+    {
+        Some(0.8)
+    } else if last == '.' {
+        Some(0.7)
+    } else {
+        None
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::{PathMatch, PathMatchCandidate};
+    use crate::{case_fold::fold_query, PathMatch, PathMatchCandidate};
 
     use super::*;
     use std::{
@@ -325,18 +819,18 @@ mod tests {
     #[test]
     fn test_get_last_positions() {
         let mut query: &[char] = &['d', 'c'];
-        let mut matcher = Matcher::new(query, query, query.into(), false, 10);
+        let mut matcher = Matcher::new(query, query, query.into(), false, false, 10);
         let result = matcher.find_last_positions(&['a', 'b', 'c'], &['b', 'd', 'e', 'f']);
         assert!(!result);
 
         query = &['c', 'd'];
-        let mut matcher = Matcher::new(query, query, query.into(), false, 10);
+        let mut matcher = Matcher::new(query, query, query.into(), false, false, 10);
         let result = matcher.find_last_positions(&['a', 'b', 'c'], &['b', 'd', 'e', 'f']);
         assert!(result);
         assert_eq!(matcher.last_positions, vec![2, 4]);
 
         query = &['z', '/', 'z', 'f'];
-        let mut matcher = Matcher::new(query, query, query.into(), false, 10);
+        let mut matcher = Matcher::new(query, query, query.into(), false, false, 10);
         let result = matcher.find_last_positions(&['z', 'e', 'd', '/'], &['z', 'e', 'd', '/', 'f']);
         assert!(result);
         assert_eq!(matcher.last_positions, vec![0, 3, 4, 8]);
@@ -359,7 +853,7 @@ mod tests {
         ];
 
         assert_eq!(
-            match_single_path_query("abc", false, &paths),
+            match_single_path_query("abc", false, false, &paths),
             vec![
                 ("abC", vec![0, 1, 2]),
                 ("abcd", vec![0, 1, 2]),
@@ -368,12 +862,12 @@ mod tests {
             ]
         );
         assert_eq!(
-            match_single_path_query("t/i/a/t/d", false, &paths),
+            match_single_path_query("t/i/a/t/d", false, false, &paths),
             vec![("/this/is/a/test/dir", vec![1, 5, 6, 8, 9, 10, 11, 15, 16]),]
         );
 
         assert_eq!(
-            match_single_path_query("tiatd", false, &paths),
+            match_single_path_query("tiatd", false, false, &paths),
             vec![
                 ("/test/tiatd", vec![6, 7, 8, 9, 10]),
                 ("/this/is/a/test/dir", vec![1, 6, 9, 11, 16]),
@@ -389,7 +883,7 @@ mod tests {
         let paths = vec!["\u{0130}"];
         let query = "\u{0130}";
         assert_eq!(
-            match_single_path_query(query, false, &paths),
+            match_single_path_query(query, false, false, &paths),
             vec![("\u{0130}", vec![0])]
         );
 
@@ -397,9 +891,71 @@ mod tests {
         let paths = vec!["i\u{307}"];
         let query = "\u{0130}";
         assert_eq!(
-            match_single_path_query(query, false, &paths),
+            match_single_path_query(query, false, false, &paths),
             vec![("i\u{307}", vec![0])]
         );
+
+        // Positions refer to the original path, even after chars that lowercase to
+        // multiple chars.
+        let paths = vec!["\u{0130}/ab", "\u{0130}\u{0130}x"];
+        assert_eq!(
+            match_single_path_query("ab", false, false, &paths),
+            vec![("\u{0130}/ab", vec![3, 4])]
+        );
+        assert_eq!(
+            match_single_path_query("x", false, false, &paths),
+            vec![("\u{0130}\u{0130}x", vec![4])]
+        );
+    }
+
+    #[test]
+    fn test_case_folding() {
+        // German
+        let paths = vec!["Müller_report.txt", "Straße.md", "STRASSE.md"];
+        assert_eq!(
+            match_single_path_query("müller", false, false, &paths),
+            vec![("Müller_report.txt", vec![0, 1, 3, 4, 5, 6])]
+        );
+        assert_eq!(
+            match_single_path_query("strasse", false, false, &paths),
+            vec![
+                ("STRASSE.md", vec![0, 1, 2, 3, 4, 5, 6]),
+                ("Straße.md", vec![0, 1, 2, 3, 4, 6]),
+            ]
+        );
+        assert_eq!(
+            match_single_path_query("straße", false, false, &paths),
+            vec![
+                ("STRASSE.md", vec![0, 1, 2, 3, 4, 5, 6]),
+                ("Straße.md", vec![0, 1, 2, 3, 4, 6]),
+            ]
+        );
+
+        // Turkish
+        let paths = vec!["İstanbul.txt", "ıspanak.txt", "Izmir.txt"];
+        assert_eq!(
+            match_single_path_query("istanbul", false, false, &paths),
+            vec![("İstanbul.txt", vec![0, 2, 3, 4, 5, 6, 7, 8])]
+        );
+        assert_eq!(
+            match_single_path_query("ISPANAK", false, false, &paths),
+            vec![("ıspanak.txt", vec![0, 2, 3, 4, 5, 6, 7])]
+        );
+        assert_eq!(
+            match_single_path_query("ızmir", false, false, &paths),
+            vec![("Izmir.txt", vec![0, 1, 2, 3, 4])]
+        );
+
+        // Greek, where sigma has a different lowercase form at the end of a word.
+        let paths = vec!["ΣΟΦΟΣ.txt", "σοφος.md"];
+        for query in ["σοφος", "σοφοσ", "ΣΟΦΟΣ"] {
+            let mut matches = match_single_path_query(query, false, false, &paths)
+                .into_iter()
+                .map(|(path, _)| path)
+                .collect::<Vec<_>>();
+            matches.sort_unstable();
+            assert_eq!(matches, vec!["ΣΟΦΟΣ.txt", "σοφος.md"], "query: {query:?}");
+        }
     }
 
     #[test]
@@ -407,14 +963,14 @@ mod tests {
         let paths = vec!["aαbβ/cγdδ", "αβγδ/bcde", "c1️⃣2️⃣3️⃣/d4️⃣5️⃣6️⃣/e7️⃣8️⃣9️⃣/f", "/d/🆒/h"];
         assert_eq!("1️⃣".len(), 7);
         assert_eq!(
-            match_single_path_query("bcd", false, &paths),
+            match_single_path_query("bcd", false, false, &paths),
             vec![
                 ("αβγδ/bcde", vec![9, 10, 11]),
                 ("aαbβ/cγdδ", vec![3, 7, 10]),
             ]
         );
         assert_eq!(
-            match_single_path_query("cde", false, &paths),
+            match_single_path_query("cde", false, false, &paths),
             vec![
                 ("αβγδ/bcde", vec![10, 11, 12]),
                 ("c1️⃣2️⃣3️⃣/d4️⃣5️⃣6️⃣/e7️⃣8️⃣9️⃣/f", vec![0, 23, 46]),
@@ -422,13 +978,464 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_match_rankings() {
+        // Each case lists candidates in the order they are expected to be ranked.
+        let cases: &[(&str, &[&str])] = &[
+            (
+                "fbv",
+                &[
+                    "src/FuzzyBufferView.rs",
+                    "src/feature_breakdown_overview.rs",
+                ],
+            ),
+            ("fbv", &["src/fuzzy_buffer_view.rs", "src/afxbxvx.rs"]),
+            ("bv", &["src/buffer_view.rs", "src/abxvz.rs"]),
+            ("ed", &["src/ed.rs", "editor/mod.rs", "src/feed.rs"]),
+            ("pp", &["project_panel.rs", "src/apxpy.rs"]),
+            (
+                "wt",
+                &["crates/wt/lib.rs", "src/worktree.rs", "src/axwxxxxtx.rs"],
+            ),
+            ("ms", &["crates\\main\\search.rs", "crates\\mxxxxxxs.rs"]),
+            // Streaks of consecutive matches beat the same chars at the starts of words.
+            ("sermod", &["src/server_model.rs", "src/s_e_r_m_o_d.rs"]),
+            // Contiguous matches beat the same chars scattered over a path.
+            ("view", &["src/preview.rs", "src/vim_item_editor_window.rs"]),
+            ("edit", &["src/editor.rs", "src/credit.rs", "src/ex_dit.rs"]),
+            (
+                "fs",
+                &[
+                    "crates/fs/src/fs.rs",
+                    "crates/file_finder/src/file_search.rs",
+                ],
+            ),
+            // Acronyms of the words of a file name beat scattered matches.
+            ("wv", &["src/workspace_view.rs", "src/wave.rs"]),
+            ("pf", &["src/project_finder.rs", "src/perf.rs"]),
+            ("fm", &["src/file_manager.rs", "src/format.rs"]),
+            (
+                "wkvw",
+                &["src/workspace_view.rs", "src/awkward_overview.rs"],
+            ),
+        ];
+        // Cases for when matches within file names are preferred over the ones within parent
+        // directories, as the file finder does.
+        let file_name_cases: &[(&str, &[&str])] = &[
+            ("main", &["src/main.rs", "src/main_helpers/util.rs"]),
+            ("util", &["src/main_helpers/util.rs", "src/util/helpers.rs"]),
+            ("ed", &["crates/foo/src/ed.rs", "ed/main.rs"]),
+            ("ed", &["src/ed.rs", "src/feed.rs", "editor/mod.rs"]),
+        ];
+
+        for (prefer_file_name, cases) in [(false, cases), (true, file_name_cases)] {
+            for (query, expected) in cases {
+                let mut candidates = expected.to_vec();
+                candidates.reverse();
+                let actual = match_single_path_query(query, false, prefer_file_name, &candidates)
+                    .into_iter()
+                    .map(|(path, _)| path)
+                    .collect::<Vec<_>>();
+                assert_eq!(
+                    &actual, expected,
+                    "unexpected ranking for query {query:?}, prefer_file_name: {prefer_file_name}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_scoring_weights() {
+        // Short candidates are often looked up by the initials of their words.
+        let candidates = ["pane: sail", "workspace: save all"];
+        let actual = match_single_query_with_weights(
+            "sa",
+            false,
+            false,
+            ScoringWeights::STRINGS,
+            &candidates,
+        );
+        assert_eq!(actual[0].0, "workspace: save all");
+        assert_eq!(actual[0].1, vec![11, 16]);
+        let actual =
+            match_single_query_with_weights("sa", false, false, ScoringWeights::PATHS, &candidates);
+        assert_eq!(actual[0].0, "pane: sail");
+
+        let actual = match_single_query_with_weights(
+            "cp",
+            false,
+            false,
+            ScoringWeights::STRINGS,
+            &["editor: copy", "command palette: toggle"],
+        );
+        assert_eq!(actual[0], ("command palette: toggle", vec![0, 8]));
+    }
+
+    #[test]
+    fn test_match_multiple_terms() {
+        let paths = vec![
+            "tests/workspace_view.rs",
+            "src/view.rs",
+            "tests/pane.rs",
+            "src/workspace view.rs",
+        ];
+
+        assert_eq!(
+            match_single_path_query("view tests", false, false, &paths),
+            vec![(
+                "tests/workspace_view.rs",
+                vec![0, 1, 2, 3, 4, 16, 17, 18, 19]
+            )]
+        );
+        assert_eq!(
+            match_single_path_query("  tests   view ", false, false, &paths),
+            match_single_path_query("view tests", false, false, &paths),
+        );
+        assert_eq!(
+            match_single_path_query("view nothing", false, false, &paths),
+            vec![]
+        );
+        // Overlapping terms highlight each position once.
+        assert_eq!(
+            match_single_path_query("view vie", false, false, &["src/view.rs"]),
+            vec![("src/view.rs", vec![4, 5, 6, 7])]
+        );
+    }
+
+    #[test]
+    fn test_match_exact_terms() {
+        let paths = vec![
+            "src/mod.rs",
+            "src/m_o_d.rs",
+            "lib/src/mod.rs",
+            "src/tests/mod.rs.snap",
+        ];
+
+        assert_eq!(
+            match_single_path_query("'mod.rs", false, true, &paths),
+            vec![
+                ("src/mod.rs", vec![4, 5, 6, 7, 8, 9]),
+                ("lib/src/mod.rs", vec![8, 9, 10, 11, 12, 13]),
+                ("src/tests/mod.rs.snap", vec![10, 11, 12, 13, 14, 15]),
+            ]
+        );
+        assert_eq!(
+            match_single_path_query("^src/ 'mod", false, true, &paths),
+            vec![
+                ("src/mod.rs", vec![0, 1, 2, 3, 4, 5, 6]),
+                ("src/tests/mod.rs.snap", vec![0, 1, 2, 3, 10, 11, 12]),
+            ]
+        );
+        assert_eq!(
+            match_single_path_query(".rs$ ^lib", false, true, &paths),
+            vec![("lib/src/mod.rs", vec![0, 1, 2, 11, 12, 13])]
+        );
+        assert_eq!(
+            match_single_path_query("^src/mod.rs$", false, true, &paths),
+            vec![("src/mod.rs", vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9])]
+        );
+
+        // Malformed patterns are matched literally.
+        assert_eq!(
+            match_single_path_query("^", false, true, &["a^b", "ab"]),
+            vec![("a^b", vec![1])]
+        );
+        assert_eq!(
+            match_single_path_query("' $", false, true, &["it's $5", "its 5"]),
+            vec![("it's $5", vec![2, 5])]
+        );
+    }
+
+    #[test]
+    fn test_cancellation_stops_visiting_candidates() {
+        let query = ['a'];
+        let mut matcher = Matcher::new(&query, &query, query[..].into(), false, false, 100);
+        let paths = (0..100)
+            .map(|i| PathBuf::from(if i % 2 == 0 { "a.rs" } else { "b.rs" }))
+            .collect::<Vec<_>>();
+
+        let cancel_flag = AtomicBool::new(false);
+        let visited = std::cell::Cell::new(0);
+        let candidates = paths.iter().map(|path| {
+            visited.set(visited.get() + 1);
+            if visited.get() == 10 {
+                cancel_flag.store(true, atomic::Ordering::Relaxed);
+            }
+            PathMatchCandidate {
+                path,
+                char_bag: CharBag::from(path.to_string_lossy().as_ref()),
+                is_ignored: false,
+                prepared: None,
+            }
+        });
+
+        let mut results = Vec::new();
+        matcher.match_candidates(
+            &[],
+            &[],
+            candidates,
+            &mut results,
+            &cancel_flag,
+            |candidate, score| PathMatch {
+                score,
+                worktree_id: 0,
+                positions: Vec::new(),
+                path: Arc::from(candidate.path),
+                path_prefix: "".into(),
+                distance_to_relative_ancestor: usize::MAX,
+                proximity_bonus: 0.0,
+                is_ignored: candidate.is_ignored,
+            },
+        );
+        assert_eq!(visited.get(), 10);
+        assert!(results.len() < 10);
+    }
+
+    #[test]
+    fn test_match_results_independent_of_sharding() {
+        let words = [
+            "src",
+            "crates",
+            "editor",
+            "workspace",
+            "buffer",
+            "view",
+            "fuzzy",
+            "main",
+            "lib",
+            "tests",
+            "util",
+            "project",
+            "panel",
+            "file",
+            "finder",
+            "display",
+            "map",
+            "server",
+        ];
+        let mut seed = 1_u64;
+        let mut random_word = || {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+            words[(seed >> 33) as usize % words.len()]
+        };
+        let mut paths = (0..1000)
+            .map(|i| {
+                let mut path = PathBuf::new();
+                for _ in 0..i % 5 {
+                    path.push(random_word());
+                }
+                path.push(format!("{}_{}.rs", random_word(), random_word()));
+                path
+            })
+            .collect::<Vec<_>>();
+        paths.sort();
+        paths.dedup();
+
+        let match_paths = |query: &str, paths: &[PathBuf]| {
+            let (query, lowercase_query) = fold_query(query);
+            let mut matcher = Matcher::new(
+                &query,
+                &lowercase_query,
+                CharBag::from(&lowercase_query[..]),
+                false,
+                true,
+                10,
+            );
+            let mut results = Vec::new();
+            matcher.match_candidates(
+                &[],
+                &[],
+                paths.iter().map(|path| PathMatchCandidate {
+                    path,
+                    char_bag: CharBag::from(path.to_string_lossy().as_ref()),
+                    is_ignored: false,
+                    prepared: None,
+                }),
+                &mut results,
+                &AtomicBool::new(false),
+                |candidate, score| PathMatch {
+                    score,
+                    worktree_id: 0,
+                    positions: Vec::new(),
+                    path: Arc::from(candidate.path),
+                    path_prefix: "".into(),
+                    distance_to_relative_ancestor: usize::MAX,
+                    proximity_bonus: 0.0,
+                    is_ignored: candidate.is_ignored,
+                },
+            );
+            results
+        };
+
+        for query in ["fbv", "main", "src/vw", "bfu"] {
+            let serial_results = match_paths(query, &paths);
+            let mut sharded_results = paths
+                .chunks(paths.len() / 7)
+                .flat_map(|shard| match_paths(query, shard))
+                .collect::<Vec<_>>();
+            sharded_results.sort_unstable_by(|a, b| b.cmp(a));
+            sharded_results.truncate(10);
+            assert_eq!(sharded_results, serial_results, "query: {query:?}");
+        }
+    }
+
+    #[test]
+    fn test_match_order_independent_of_candidate_order() {
+        let paths = ["abc", "ab", "b", "a", "ba", "c"]
+            .iter()
+            .flat_map(|dir| {
+                ["x.rs", "xy.rs", "yx.rs"]
+                    .iter()
+                    .map(move |file_name| format!("{dir}/{file_name}"))
+            })
+            .collect::<Vec<_>>();
+        let mut paths = paths.iter().map(String::as_str).collect::<Vec<_>>();
+
+        let expected = match_single_path_query("x", false, true, &paths);
+        assert_eq!(
+            expected.iter().map(|(path, _)| *path).collect::<Vec<_>>(),
+            vec![
+                "a/x.rs",
+                "b/x.rs",
+                "c/x.rs",
+                "ab/x.rs",
+                "ba/x.rs",
+                "abc/x.rs",
+                "a/xy.rs",
+                "b/xy.rs",
+                "c/xy.rs",
+                "ab/xy.rs",
+                "ba/xy.rs",
+                "abc/xy.rs",
+                "a/yx.rs",
+                "b/yx.rs",
+                "c/yx.rs",
+                "ab/yx.rs",
+                "ba/yx.rs",
+                "abc/yx.rs",
+            ]
+        );
+
+        let mut seed = 1_u64;
+        for _ in 0..20 {
+            for i in (1..paths.len()).rev() {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+                paths.swap(i, (seed >> 33) as usize % (i + 1));
+            }
+            assert_eq!(
+                match_single_path_query("x", false, true, &paths),
+                expected,
+                "paths: {paths:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_prepared_paths_match_like_unprepared_ones() {
+        let paths = [
+            "src/main.rs",
+            "crates/fuzzy/src/matcher.rs",
+            "docs/StraßeNamen.md",
+            "İstanbul/ıspanak_2.txt",
+            "a-b_c.d/e f/GitHubWorkflow.yml",
+            "",
+            "x",
+        ]
+        .into_iter()
+        .map(PathBuf::from)
+        .collect::<Vec<_>>();
+        // Words far into a long path are kept in later words of the bitmap.
+        let long_path = PathBuf::from(
+            (0..40)
+                .map(|i| format!("dir{i}"))
+                .collect::<Vec<_>>()
+                .join("/"),
+        );
+        let paths = paths.into_iter().chain([long_path]).collect::<Vec<_>>();
+        let prepared_paths = paths
+            .iter()
+            .map(|path| PreparedPath::new(path))
+            .collect::<Vec<_>>();
+
+        for prefix in ["", "root", "root/", "fooBar-"] {
+            let (prefix, lowercase_prefix) = fold_query(prefix);
+            for query in [
+                "m", "src/mat", "strasse", "istanbul", "gh wf", "dir39", "'b_c", "x$",
+            ] {
+                let (query, lowercase_query) = fold_query(query);
+                let match_paths = |prepared: bool| {
+                    let mut matcher = Matcher::new(
+                        &query,
+                        &lowercase_query,
+                        CharBag::from(&lowercase_query[..]),
+                        false,
+                        true,
+                        100,
+                    )
+                    .parse_terms();
+                    let mut results = Vec::new();
+                    matcher.match_candidates(
+                        &prefix,
+                        &lowercase_prefix,
+                        paths
+                            .iter()
+                            .zip(&prepared_paths)
+                            .map(|(path, prepared_path)| PathMatchCandidate {
+                                path,
+                                char_bag: CharBag::from(path.to_string_lossy().as_ref()),
+                                is_ignored: false,
+                                prepared: prepared.then_some(prepared_path),
+                            }),
+                        &mut results,
+                        &AtomicBool::new(false),
+                        |candidate, score| PathMatch {
+                            score,
+                            worktree_id: 0,
+                            positions: Vec::new(),
+                            path: Arc::from(candidate.path),
+                            path_prefix: "".into(),
+                            distance_to_relative_ancestor: usize::MAX,
+                            proximity_bonus: 0.0,
+                            is_ignored: candidate.is_ignored,
+                        },
+                    );
+                    results
+                        .into_iter()
+                        .map(|result| (result.path, result.score, result.positions))
+                        .collect::<Vec<_>>()
+                };
+                assert_eq!(
+                    match_paths(true),
+                    match_paths(false),
+                    "query: {query:?}, prefix: {prefix:?}"
+                );
+            }
+        }
+    }
+
     fn match_single_path_query<'a>(
         query: &str,
         smart_case: bool,
+        prefer_file_name: bool,
         paths: &[&'a str],
     ) -> Vec<(&'a str, Vec<usize>)> {
-        let lowercase_query = query.to_lowercase().chars().collect::<Vec<_>>();
-        let query = query.chars().collect::<Vec<_>>();
+        match_single_query_with_weights(
+            query,
+            smart_case,
+            prefer_file_name,
+            ScoringWeights::PATHS,
+            paths,
+        )
+    }
+
+    fn match_single_query_with_weights<'a>(
+        query: &str,
+        smart_case: bool,
+        prefer_file_name: bool,
+        weights: ScoringWeights,
+        paths: &[&'a str],
+    ) -> Vec<(&'a str, Vec<usize>)> {
+        let (query, lowercase_query) = fold_query(query);
         let query_chars = CharBag::from(&lowercase_query[..]);
 
         let path_arcs: Vec<Arc<Path>> = paths
@@ -442,10 +1449,21 @@ mod tests {
             path_entries.push(PathMatchCandidate {
                 char_bag,
                 path: &path_arcs[i],
+                is_ignored: false,
+                prepared: None,
             });
         }
 
-        let mut matcher = Matcher::new(&query, &lowercase_query, query_chars, smart_case, 100);
+        let mut matcher = Matcher::new(
+            &query,
+            &lowercase_query,
+            query_chars,
+            smart_case,
+            prefer_file_name,
+            100,
+        )
+        .weights(weights)
+        .parse_terms();
 
         let cancel_flag = AtomicBool::new(false);
         let mut results = Vec::new();
@@ -463,6 +1481,8 @@ mod tests {
                 path: Arc::from(candidate.path),
                 path_prefix: "".into(),
                 distance_to_relative_ancestor: usize::MAX,
+                proximity_bonus: 0.0,
+                is_ignored: candidate.is_ignored,
             },
         );
 
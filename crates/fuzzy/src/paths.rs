@@ -1,20 +1,29 @@
-use gpui::BackgroundExecutor;
+use futures::channel::mpsc;
+use gpui::{BackgroundExecutor, TaskPriority};
 use std::{
     borrow::Cow,
     cmp::{self, Ordering},
+    ops::Range,
     path::Path,
-    sync::{atomic::AtomicBool, Arc},
+    sync::{
+        atomic::{self, AtomicBool},
+        Arc,
+    },
 };
 
 use crate::{
+    case_fold::{fold_char, fold_query},
     matcher::{Match, MatchCandidate, Matcher},
-    CharBag,
+    CharBag, PreparedPath,
 };
 
 #[derive(Clone, Debug)]
 pub struct PathMatchCandidate<'a> {
     pub path: &'a Path,
     pub char_bag: CharBag,
+    pub is_ignored: bool,
+    /// The path, prepared ahead of time, which spares folding it again.
+    pub prepared: Option<&'a PreparedPath>,
 }
 
 #[derive(Clone, Debug)]
@@ -27,6 +36,12 @@ pub struct PathMatch {
     /// Number of steps removed from a shared parent with the relative path
     /// Used to order closer paths first in the search list
     pub distance_to_relative_ancestor: usize,
+    /// The part of the score given for the leading directories shared with the relative
+    /// path, see [`proximity_bonus`]
+    pub proximity_bonus: f64,
+    /// Whether the path is ignored by the version control system, in which case it ranks
+    /// below the non-ignored paths with the same score
+    pub is_ignored: bool,
 }
 
 pub trait PathMatchCandidateSet<'a>: Send + Sync {
@@ -40,6 +55,38 @@ pub trait PathMatchCandidateSet<'a>: Send + Sync {
     fn candidates(&'a self, start: usize) -> Self::Candidates;
 }
 
+impl PathMatch {
+    /// Byte offset of the file name within the concatenation of `path_prefix` and `path`,
+    /// which is the string that `positions` index into.
+    pub fn file_name_start(&self) -> usize {
+        let path_len = self.path.to_string_lossy().len();
+        let file_name_len = self.path.file_name().map_or_else(
+            || self.path_prefix.len(),
+            |file_name| file_name.to_string_lossy().len(),
+        );
+        self.path_prefix.len() + path_len - file_name_len
+    }
+
+    fn full_path_len(&self) -> usize {
+        self.path_prefix.len() + self.path.as_os_str().len()
+    }
+
+    /// Splits the match positions into the ones within the parent directories and the
+    /// ones within the file name, the latter being relative to the start of the file name.
+    pub fn split_positions(&self) -> (Vec<usize>, Vec<usize>) {
+        let file_name_start = self.file_name_start();
+        let (directory_positions, file_name_positions): (Vec<_>, Vec<_>) = self
+            .positions
+            .iter()
+            .partition(|&&position| position < file_name_start);
+        let file_name_positions = file_name_positions
+            .into_iter()
+            .map(|position| position - file_name_start)
+            .collect();
+        (directory_positions, file_name_positions)
+    }
+}
+
 impl Match for PathMatch {
     fn score(&self) -> f64 {
         self.score
@@ -58,6 +105,10 @@ impl<'a> MatchCandidate for PathMatchCandidate<'a> {
     fn to_string(&self) -> Cow<'a, str> {
         self.path.to_string_lossy()
     }
+
+    fn prepared(&self) -> Option<&PreparedPath> {
+        self.prepared
+    }
 }
 
 impl PartialEq for PathMatch {
@@ -74,18 +125,25 @@ impl PartialOrd for PathMatch {
     }
 }
 
+/// Better matches compare greater. Matches with the same score are ordered with the
+/// non-ignored paths first, then by their distance to the relative path, then shorter
+/// paths come first, then paths are ordered lexicographically, so that the order of the
+/// results never depends on the order in which the candidates got matched.
 impl Ord for PathMatch {
     fn cmp(&self, other: &Self) -> Ordering {
         self.score
             .partial_cmp(&other.score)
             .unwrap_or(Ordering::Equal)
-            .then_with(|| self.worktree_id.cmp(&other.worktree_id))
+            .then_with(|| other.is_ignored.cmp(&self.is_ignored))
             .then_with(|| {
                 other
                     .distance_to_relative_ancestor
                     .cmp(&self.distance_to_relative_ancestor)
             })
-            .then_with(|| self.path.cmp(&other.path))
+            .then_with(|| other.full_path_len().cmp(&self.full_path_len()))
+            .then_with(|| other.path_prefix.cmp(&self.path_prefix))
+            .then_with(|| other.path.cmp(&self.path))
+            .then_with(|| other.worktree_id.cmp(&self.worktree_id))
     }
 }
 
@@ -94,10 +152,10 @@ pub fn match_fixed_path_set(
     worktree_id: usize,
     query: &str,
     smart_case: bool,
+    prefer_file_name: bool,
     max_results: usize,
 ) -> Vec<PathMatch> {
-    let lowercase_query = query.to_lowercase().chars().collect::<Vec<_>>();
-    let query = query.chars().collect::<Vec<_>>();
+    let (query, lowercase_query) = fold_query(query);
     let query_char_bag = CharBag::from(&lowercase_query[..]);
 
     let mut matcher = Matcher::new(
@@ -105,8 +163,10 @@ pub fn match_fixed_path_set(
         &lowercase_query,
         query_char_bag,
         smart_case,
+        prefer_file_name,
         max_results,
-    );
+    )
+    .parse_terms();
 
     let mut results = Vec::new();
     matcher.match_candidates(
@@ -122,16 +182,20 @@ pub fn match_fixed_path_set(
             path: Arc::from(candidate.path),
             path_prefix: Arc::from(""),
             distance_to_relative_ancestor: usize::MAX,
+            proximity_bonus: 0.0,
+            is_ignored: candidate.is_ignored,
         },
     );
     results
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn match_path_sets<'a, Set: PathMatchCandidateSet<'a>>(
     candidate_sets: &'a [Set],
     query: &str,
     relative_to: Option<Arc<Path>>,
     smart_case: bool,
+    prefer_file_name: bool,
     max_results: usize,
     cancel_flag: &AtomicBool,
     executor: BackgroundExecutor,
@@ -141,33 +205,131 @@ pub async fn match_path_sets<'a, Set: PathMatchCandidateSet<'a>>(
         return Vec::new();
     }
 
-    let lowercase_query = query.to_lowercase().chars().collect::<Vec<_>>();
-    let query = query.chars().collect::<Vec<_>>();
+    let (query, lowercase_query) = fold_query(query);
+    match_path_set_range(
+        candidate_sets,
+        0..path_count,
+        &query,
+        &lowercase_query,
+        &relative_to,
+        smart_case,
+        prefer_file_name,
+        max_results,
+        cancel_flag,
+        &executor,
+    )
+    .await
+}
 
-    let lowercase_query = &lowercase_query;
-    let query = &query;
-    let query_char_bag = CharBag::from(&lowercase_query[..]);
+/// How many candidates are matched between two snapshots sent by [`match_path_sets_streaming`].
+const STREAMING_CHUNK_SIZE: usize = 20_000;
+
+/// The best matches found so far by [`match_path_sets_streaming`], sorted from best to worst.
+#[derive(Clone, Debug)]
+pub struct PathMatchSnapshot {
+    pub matches: Vec<PathMatch>,
+    /// Whether all candidates have been matched, which makes this the last snapshot.
+    pub is_complete: bool,
+}
+
+/// Like [`match_path_sets`], but matches the candidates in chunks, sending a snapshot of
+/// the best `max_results` matches found so far after every chunk. When the matching gets
+/// cancelled, the snapshot of the matches found before that is the last one sent.
+#[allow(clippy::too_many_arguments)]
+pub async fn match_path_sets_streaming<'a, Set: PathMatchCandidateSet<'a>>(
+    candidate_sets: &'a [Set],
+    query: &str,
+    relative_to: Option<Arc<Path>>,
+    smart_case: bool,
+    prefer_file_name: bool,
+    max_results: usize,
+    cancel_flag: &AtomicBool,
+    executor: BackgroundExecutor,
+    snapshots: mpsc::UnboundedSender<PathMatchSnapshot>,
+) {
+    let path_count: usize = candidate_sets.iter().map(|s| s.len()).sum();
+    let (query, lowercase_query) = fold_query(query);
+
+    let mut matches = Vec::new();
+    let mut chunk_start = 0;
+    loop {
+        let chunk_end = cmp::min(chunk_start + STREAMING_CHUNK_SIZE, path_count);
+        if chunk_start < chunk_end {
+            let chunk_matches = match_path_set_range(
+                candidate_sets,
+                chunk_start..chunk_end,
+                &query,
+                &lowercase_query,
+                &relative_to,
+                smart_case,
+                prefer_file_name,
+                max_results,
+                cancel_flag,
+                &executor,
+            )
+            .await;
+            util::extend_sorted(&mut matches, chunk_matches, max_results, |a, b| b.cmp(a));
+        }
+        chunk_start = chunk_end;
 
-    let num_cpus = executor.num_cpus().min(path_count);
-    let segment_size = (path_count + num_cpus - 1) / num_cpus;
+        let did_cancel = cancel_flag.load(atomic::Ordering::Relaxed);
+        let is_complete = !did_cancel && chunk_start == path_count;
+        let snapshot = PathMatchSnapshot {
+            matches: matches.clone(),
+            is_complete,
+        };
+        if snapshots.unbounded_send(snapshot).is_err() || did_cancel || is_complete {
+            break;
+        }
+    }
+}
+
+/// Matches the candidates within the given range of all the candidate sets' candidates,
+/// splitting them into one segment per CPU.
+#[allow(clippy::too_many_arguments)]
+async fn match_path_set_range<'a, Set: PathMatchCandidateSet<'a>>(
+    candidate_sets: &'a [Set],
+    range: Range<usize>,
+    query: &[char],
+    lowercase_query: &[char],
+    relative_to: &Option<Arc<Path>>,
+    smart_case: bool,
+    prefer_file_name: bool,
+    max_results: usize,
+    cancel_flag: &AtomicBool,
+    executor: &BackgroundExecutor,
+) -> Vec<PathMatch> {
+    let query_char_bag = CharBag::from(lowercase_query);
+
+    let num_cpus = executor.num_cpus().min(range.len());
+    let segment_size = (range.len() + num_cpus - 1) / num_cpus;
     let mut segment_results = (0..num_cpus)
         .map(|_| Vec::with_capacity(max_results))
         .collect::<Vec<_>>();
 
     executor
-        .scoped(|scope| {
+        .scoped_with_priority(TaskPriority::High, |scope| {
             for (segment_idx, results) in segment_results.iter_mut().enumerate() {
                 let relative_to = relative_to.clone();
+                let range = range.clone();
                 scope.spawn(async move {
-                    let segment_start = segment_idx * segment_size;
-                    let segment_end = segment_start + segment_size;
+                    let segment_start =
+                        cmp::min(range.start + segment_idx * segment_size, range.end);
+                    let segment_end = cmp::min(segment_start + segment_size, range.end);
                     let mut matcher = Matcher::new(
                         query,
                         lowercase_query,
                         query_char_bag,
                         smart_case,
+                        prefer_file_name,
                         max_results,
-                    );
+                    )
+                    .parse_terms()
+                    .max_score_bonus(if relative_to.is_some() {
+                        MAX_PROXIMITY_BONUS
+                    } else {
+                        0.0
+                    });
 
                     let mut tree_start = 0;
                     for candidate_set in candidate_sets {
@@ -180,31 +342,37 @@ pub async fn match_path_sets<'a, Set: PathMatchCandidateSet<'a>>(
 
                             let worktree_id = candidate_set.id();
                             let prefix = candidate_set.prefix().chars().collect::<Vec<_>>();
-                            let lowercase_prefix = prefix
-                                .iter()
-                                .map(|c| c.to_ascii_lowercase())
-                                .collect::<Vec<_>>();
+                            let lowercase_prefix =
+                                prefix.iter().map(|c| fold_char(*c)).collect::<Vec<_>>();
                             matcher.match_candidates(
                                 &prefix,
                                 &lowercase_prefix,
                                 candidates,
                                 results,
                                 cancel_flag,
-                                |candidate, score| PathMatch {
-                                    score,
-                                    worktree_id,
-                                    positions: Vec::new(),
-                                    path: Arc::from(candidate.path),
-                                    path_prefix: candidate_set.prefix(),
-                                    distance_to_relative_ancestor: relative_to.as_ref().map_or(
-                                        usize::MAX,
-                                        |relative_to| {
-                                            distance_between_paths(
-                                                candidate.path,
-                                                relative_to.as_ref(),
-                                            )
-                                        },
-                                    ),
+                                |candidate, score| {
+                                    let proximity_bonus =
+                                        relative_to.as_ref().map_or(0.0, |relative_to| {
+                                            proximity_bonus(candidate.path, relative_to)
+                                        });
+                                    PathMatch {
+                                        score: score + proximity_bonus,
+                                        worktree_id,
+                                        positions: Vec::new(),
+                                        path: Arc::from(candidate.path),
+                                        path_prefix: candidate_set.prefix(),
+                                        distance_to_relative_ancestor: relative_to.as_ref().map_or(
+                                            usize::MAX,
+                                            |relative_to| {
+                                                distance_between_paths(
+                                                    candidate.path,
+                                                    relative_to.as_ref(),
+                                                )
+                                            },
+                                        ),
+                                        proximity_bonus,
+                                        is_ignored: candidate.is_ignored,
+                                    }
                                 },
                             );
                         }
@@ -229,6 +397,30 @@ pub async fn match_path_sets<'a, Set: PathMatchCandidateSet<'a>>(
     results
 }
 
+/// The bonus given to the files in the same directory as the relative path.
+const MAX_PROXIMITY_BONUS: f64 = 0.15;
+
+/// A bonus added to the score of a path, growing with the number of leading directories it
+/// shares with the directory of the relative path, so that the files near the relative
+/// path outrank the equally good matches elsewhere. Files in the same directory get
+/// [`MAX_PROXIMITY_BONUS`], and there is no bonus when the relative path is at the root.
+fn proximity_bonus(path: &Path, relative_to: &Path) -> f64 {
+    let Some(relative_dir) = relative_to.parent() else {
+        return 0.0;
+    };
+    let relative_dir_depth = relative_dir.components().count();
+    if relative_dir_depth == 0 {
+        return 0.0;
+    }
+    let shared_depth = path.parent().map_or(0, |dir| {
+        dir.components()
+            .zip(relative_dir.components())
+            .take_while(|(component, relative_component)| component == relative_component)
+            .count()
+    });
+    MAX_PROXIMITY_BONUS * shared_depth as f64 / relative_dir_depth as f64
+}
+
 /// Compute the distance from a given path to some other path
 /// If there is no shared path, returns usize::MAX
 fn distance_between_paths(path: &Path, relative_to: &Path) -> usize {
@@ -246,12 +438,252 @@ fn distance_between_paths(path: &Path, relative_to: &Path) -> usize {
 
 #[cfg(test)]
 mod tests {
-    use std::path::Path;
+    use std::{
+        path::{Path, PathBuf},
+        sync::{atomic::AtomicBool, Arc},
+    };
+
+    use futures::{channel::mpsc, StreamExt};
+
+    use super::{
+        distance_between_paths, match_path_sets, match_path_sets_streaming, proximity_bonus,
+        PathMatch, PathMatchCandidate, PathMatchCandidateSet, MAX_PROXIMITY_BONUS,
+        STREAMING_CHUNK_SIZE,
+    };
+    use crate::CharBag;
+
+    struct TestCandidateSet {
+        paths: Vec<PathBuf>,
+        char_bags: Vec<CharBag>,
+    }
 
-    use super::distance_between_paths;
+    impl<'a> PathMatchCandidateSet<'a> for TestCandidateSet {
+        type Candidates = std::vec::IntoIter<PathMatchCandidate<'a>>;
+
+        fn id(&self) -> usize {
+            0
+        }
+
+        fn len(&self) -> usize {
+            self.paths.len()
+        }
+
+        fn prefix(&self) -> Arc<str> {
+            Arc::from("")
+        }
+
+        fn candidates(&'a self, start: usize) -> Self::Candidates {
+            self.paths[start..]
+                .iter()
+                .zip(&self.char_bags[start..])
+                .map(|(path, char_bag)| PathMatchCandidate {
+                    path,
+                    char_bag: *char_bag,
+                    is_ignored: false,
+                    prepared: None,
+                })
+                .collect::<Vec<_>>()
+                .into_iter()
+        }
+    }
 
     #[test]
     fn test_distance_between_paths_empty() {
         distance_between_paths(Path::new(""), Path::new(""));
     }
+
+    #[test]
+    fn test_proximity_bonus() {
+        let relative_to = Path::new("crates/server/src/api/routes.rs");
+        assert_eq!(
+            proximity_bonus(Path::new("crates/server/src/api/handler.rs"), relative_to),
+            MAX_PROXIMITY_BONUS
+        );
+        assert_eq!(
+            proximity_bonus(
+                Path::new("crates/server/src/api/v2/handler.rs"),
+                relative_to
+            ),
+            MAX_PROXIMITY_BONUS
+        );
+        assert_eq!(
+            proximity_bonus(Path::new("crates/server/src/handler.rs"), relative_to),
+            MAX_PROXIMITY_BONUS * 3.0 / 4.0
+        );
+        assert_eq!(
+            proximity_bonus(Path::new("crates/client/handler.rs"), relative_to),
+            MAX_PROXIMITY_BONUS / 4.0
+        );
+        assert_eq!(
+            proximity_bonus(Path::new("src/api/handler.rs"), relative_to),
+            0.0
+        );
+        assert_eq!(proximity_bonus(Path::new("handler.rs"), relative_to), 0.0);
+        assert_eq!(
+            proximity_bonus(Path::new("src/handler.rs"), Path::new("main.rs")),
+            0.0
+        );
+    }
+
+    #[test]
+    fn test_split_positions() {
+        let path_match = PathMatch {
+            score: 1.0,
+            positions: vec![0, 5, 9, 10],
+            worktree_id: 0,
+            path: Arc::from(Path::new("src/main.rs")),
+            path_prefix: Arc::from("root/"),
+            distance_to_relative_ancestor: usize::MAX,
+            proximity_bonus: 0.0,
+            is_ignored: false,
+        };
+        assert_eq!(path_match.file_name_start(), 9);
+        assert_eq!(path_match.split_positions(), (vec![0, 5], vec![0, 1]));
+
+        let path_match = PathMatch {
+            path: Arc::from(Path::new("")),
+            path_prefix: Arc::from("single-file.rs"),
+            positions: vec![0, 7],
+            ..path_match
+        };
+        assert_eq!(path_match.file_name_start(), 0);
+        assert_eq!(path_match.split_positions(), (vec![], vec![0, 7]));
+    }
+
+    #[gpui::test]
+    async fn test_match_path_sets_streaming(cx: &mut gpui::TestAppContext) {
+        let paths = (0..STREAMING_CHUNK_SIZE * 5 / 2)
+            .map(|ix| PathBuf::from(format!("dir{}/file{ix}.rs", ix % 7)))
+            .collect::<Vec<_>>();
+        let char_bags = paths
+            .iter()
+            .map(|path| CharBag::from(path.to_string_lossy().as_ref()))
+            .collect();
+        let candidate_sets = [TestCandidateSet { paths, char_bags }];
+        let cancel_flag = AtomicBool::new(false);
+
+        let (snapshots_tx, snapshots_rx) = mpsc::unbounded();
+        match_path_sets_streaming(
+            &candidate_sets,
+            "fil3",
+            None,
+            false,
+            true,
+            10,
+            &cancel_flag,
+            cx.executor(),
+            snapshots_tx,
+        )
+        .await;
+        let snapshots = snapshots_rx.collect::<Vec<_>>().await;
+
+        assert_eq!(snapshots.len(), 3);
+        assert_eq!(
+            snapshots
+                .iter()
+                .map(|snapshot| snapshot.is_complete)
+                .collect::<Vec<_>>(),
+            [false, false, true]
+        );
+        for snapshot in &snapshots {
+            assert_eq!(snapshot.matches.len(), 10);
+            assert!(snapshot
+                .matches
+                .windows(2)
+                .all(|pair| pair[0].score >= pair[1].score));
+        }
+        for pair in snapshots.windows(2) {
+            for (previous, next) in pair[0].matches.iter().zip(&pair[1].matches) {
+                assert!(next.score >= previous.score);
+            }
+        }
+
+        let matches = match_path_sets(
+            &candidate_sets,
+            "fil3",
+            None,
+            false,
+            true,
+            10,
+            &cancel_flag,
+            cx.executor(),
+        )
+        .await;
+        assert_eq!(
+            snapshots.last().unwrap().matches,
+            matches,
+            "the last snapshot should contain the same matches as a single pass"
+        );
+    }
+
+    #[gpui::test]
+    async fn test_match_path_sets_prefers_nearby_files(cx: &mut gpui::TestAppContext) {
+        let paths = [
+            "handler.rs",
+            "src/handler.rs",
+            "crates/client/handler.rs",
+            "crates/server/src/api/handler.rs",
+            "crates/server/src/api/routes.rs",
+        ]
+        .into_iter()
+        .map(PathBuf::from)
+        .collect::<Vec<_>>();
+        let char_bags = paths
+            .iter()
+            .map(|path| CharBag::from(path.to_string_lossy().as_ref()))
+            .collect();
+        let candidate_sets = [TestCandidateSet { paths, char_bags }];
+        let cancel_flag = AtomicBool::new(false);
+
+        let matches = match_path_sets(
+            &candidate_sets,
+            "handler",
+            None,
+            false,
+            true,
+            10,
+            &cancel_flag,
+            cx.executor(),
+        )
+        .await;
+        assert_eq!(
+            matches[0].path.as_ref(),
+            Path::new("handler.rs"),
+            "shorter paths should win without a relative path"
+        );
+        assert!(matches
+            .iter()
+            .all(|path_match| path_match.proximity_bonus == 0.0));
+
+        let matches = match_path_sets(
+            &candidate_sets,
+            "handler",
+            Some(Arc::from(Path::new("crates/server/src/api/routes.rs"))),
+            false,
+            true,
+            10,
+            &cancel_flag,
+            cx.executor(),
+        )
+        .await;
+        assert_eq!(
+            matches
+                .iter()
+                .map(|path_match| (path_match.path.as_ref(), path_match.proximity_bonus))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    Path::new("crates/server/src/api/handler.rs"),
+                    MAX_PROXIMITY_BONUS
+                ),
+                (Path::new("handler.rs"), 0.0),
+                (
+                    Path::new("crates/client/handler.rs"),
+                    MAX_PROXIMITY_BONUS / 4.0
+                ),
+                (Path::new("src/handler.rs"), 0.0),
+            ],
+            "the file next to the relative path should outrank the ones elsewhere"
+        );
+    }
 }
@@ -0,0 +1,137 @@
+use std::path::Path;
+
+use crate::{case_fold::fold_case, matcher::word_boundary_score, CharBag};
+
+/// The parts of a path that matching it needs, computed ahead of time for the paths that get
+/// matched against many queries, such as the ones of a worktree, so that they don't have to
+/// be folded again for every query. Strings that are matched again and again get prepared
+/// the same way.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PreparedPath {
+    char_bag: CharBag,
+    chars: PreparedChars,
+    /// A bit for every char, set when the char starts a word within the path. The bit of the
+    /// first char is never set, since whether it starts a word depends on what precedes it.
+    word_starts: Box<[u64]>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum PreparedChars {
+    /// The case folded path, when the path is all ASCII. Every char of such a path is a
+    /// single byte that folds to a single byte, so its chars and their offsets follow from
+    /// the path itself.
+    Ascii(Box<str>),
+    /// The case folded chars of the path, along with the chars that they were folded from
+    /// and the byte offsets of those within the path.
+    Unicode {
+        folded_chars: Box<[char]>,
+        chars: Box<[char]>,
+        char_offsets: Box<[usize]>,
+    },
+}
+
+impl PreparedPath {
+    pub fn new(path: &Path) -> Self {
+        Self::for_str(&path.to_string_lossy())
+    }
+
+    pub(crate) fn for_str(path: &str) -> Self {
+        if path.is_ascii() {
+            return Self {
+                char_bag: CharBag::from(path),
+                chars: PreparedChars::Ascii(path.to_ascii_lowercase().into()),
+                word_starts: word_start_bits(path.bytes().map(char::from), path.len()),
+            };
+        }
+
+        let mut chars = Vec::with_capacity(path.len());
+        let mut folded_chars = Vec::with_capacity(path.len());
+        let mut char_offsets = Vec::with_capacity(path.len());
+        // Some chars fold to multiple chars, in which case the original char is repeated to
+        // keep both sequences aligned, like the matcher does.
+        for (byte_ix, c) in path.char_indices() {
+            for folded in fold_case(c) {
+                chars.push(c);
+                folded_chars.push(folded);
+                char_offsets.push(byte_ix);
+            }
+        }
+
+        let word_starts = word_start_bits(chars.iter().copied(), chars.len());
+        Self {
+            char_bag: CharBag::from(path),
+            chars: PreparedChars::Unicode {
+                folded_chars: folded_chars.into(),
+                chars: chars.into(),
+                char_offsets: char_offsets.into(),
+            },
+            word_starts,
+        }
+    }
+
+    pub fn char_bag(&self) -> CharBag {
+        self.char_bag
+    }
+
+    /// Fills in the chars of the path, which has to be the one this was prepared from, along
+    /// with their case folded counterparts and their byte offsets within the path.
+    pub(crate) fn fill_chars(
+        &self,
+        path: &str,
+        chars: &mut Vec<char>,
+        folded_chars: &mut Vec<char>,
+        char_offsets: &mut Vec<usize>,
+    ) {
+        match &self.chars {
+            PreparedChars::Ascii(folded_path) => {
+                folded_chars.extend(folded_path.bytes().map(char::from));
+                chars.extend(path.bytes().map(char::from));
+                char_offsets.extend(0..path.len());
+            }
+            PreparedChars::Unicode {
+                folded_chars: prepared_folded_chars,
+                chars: prepared_chars,
+                char_offsets: prepared_char_offsets,
+            } => {
+                folded_chars.extend_from_slice(prepared_folded_chars);
+                chars.extend_from_slice(prepared_chars);
+                char_offsets.extend_from_slice(prepared_char_offsets);
+            }
+        }
+    }
+
+    /// Returns the indices of the chars that start a word within the path, besides the first
+    /// char, in ascending order.
+    pub(crate) fn word_starts(&self) -> impl Iterator<Item = usize> + '_ {
+        self.word_starts
+            .iter()
+            .enumerate()
+            .flat_map(|(word_ix, &word)| {
+                let mut word = word;
+                std::iter::from_fn(move || {
+                    if word == 0 {
+                        None
+                    } else {
+                        let bit = word.trailing_zeros() as usize;
+                        word &= word - 1;
+                        Some(word_ix * 64 + bit)
+                    }
+                })
+            })
+    }
+}
+
+/// Returns a bit for each of the given chars, set when the char starts a word.
+fn word_start_bits(chars: impl Iterator<Item = char>, len: usize) -> Box<[u64]> {
+    let mut word_starts = vec![0u64; len.div_ceil(64)];
+    let mut prev_char = None;
+    for (ix, c) in chars.enumerate() {
+        if prev_char.map_or(false, |prev_char| {
+            word_boundary_score(prev_char, c).is_some()
+        }) {
+            word_starts[ix / 64] |= 1 << (ix % 64);
+        }
+        prev_char = Some(c);
+    }
+    word_starts.into()
+}
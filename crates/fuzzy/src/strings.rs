@@ -1,14 +1,15 @@
 use crate::{
-    matcher::{Match, MatchCandidate, Matcher},
-    CharBag,
+    case_fold::fold_query,
+    matcher::{Match, MatchCandidate, Matcher, ScoringWeights},
+    CharBag, PreparedPath,
 };
-use gpui::BackgroundExecutor;
+use gpui::{BackgroundExecutor, TaskPriority};
 use std::{
     borrow::Cow,
     cmp::{self, Ordering},
     iter,
     ops::Range,
-    sync::atomic::AtomicBool,
+    sync::{atomic::AtomicBool, OnceLock},
 };
 
 #[derive(Clone, Debug)]
@@ -16,6 +17,9 @@ pub struct StringMatchCandidate {
     pub id: usize,
     pub string: String,
     pub char_bag: CharBag,
+    /// The string's folded chars, computed the first time it's matched, so that candidates
+    /// kept across queries are only folded once rather than on every keystroke.
+    prepared: OnceLock<PreparedPath>,
 }
 
 impl Match for StringMatch {
@@ -34,6 +38,7 @@ impl StringMatchCandidate {
             id,
             char_bag: CharBag::from(string.as_str()),
             string,
+            prepared: OnceLock::new(),
         }
     }
 }
@@ -46,6 +51,13 @@ impl<'a> MatchCandidate for &'a StringMatchCandidate {
     fn to_string(&self) -> Cow<'a, str> {
         self.string.as_str().into()
     }
+
+    fn prepared(&self) -> Option<&PreparedPath> {
+        Some(
+            self.prepared
+                .get_or_init(|| PreparedPath::for_str(&self.string)),
+        )
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -129,8 +141,7 @@ pub async fn match_strings(
             .collect();
     }
 
-    let lowercase_query = query.to_lowercase().chars().collect::<Vec<_>>();
-    let query = query.chars().collect::<Vec<_>>();
+    let (query, lowercase_query) = fold_query(query);
 
     let lowercase_query = &lowercase_query;
     let query = &query;
@@ -143,7 +154,7 @@ pub async fn match_strings(
         .collect::<Vec<_>>();
 
     executor
-        .scoped(|scope| {
+        .scoped_with_priority(TaskPriority::High, |scope| {
             for (segment_idx, results) in segment_results.iter_mut().enumerate() {
                 let cancel_flag = &cancel_flag;
                 scope.spawn(async move {
@@ -154,8 +165,10 @@ pub async fn match_strings(
                         lowercase_query,
                         query_char_bag,
                         smart_case,
+                        false,
                         max_results,
-                    );
+                    )
+                    .weights(ScoringWeights::STRINGS);
 
                     matcher.match_candidates(
                         &[],
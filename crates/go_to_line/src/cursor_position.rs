@@ -6,8 +6,8 @@ use settings::{Settings, SettingsSources};
 use std::fmt::Write;
 use text::{Point, Selection};
 use ui::{
-    div, Button, ButtonCommon, Clickable, FluentBuilder, IntoElement, LabelSize, ParentElement,
-    Render, Tooltip, ViewContext,
+    h_flex, Button, ButtonCommon, Clickable, Color, FluentBuilder, IntoElement, LabelSize,
+    ParentElement, Render, Styled, Tooltip, ViewContext,
 };
 use util::paths::FILE_ROW_COLUMN_DELIMITER;
 use workspace::{item::ItemHandle, StatusItemView, Workspace};
@@ -15,6 +15,7 @@ use workspace::{item::ItemHandle, StatusItemView, Workspace};
 #[derive(Copy, Clone, Default, PartialOrd, PartialEq)]
 struct SelectionStats {
     lines: usize,
+    words: usize,
     characters: usize,
     selections: usize,
 }
@@ -22,6 +23,8 @@ struct SelectionStats {
 pub struct CursorPosition {
     position: Option<Point>,
     selected_count: SelectionStats,
+    editor: Option<WeakView<Editor>>,
+    has_long_lines: bool,
     workspace: WeakView<Workspace>,
     _observe_active_editor: Option<Subscription>,
 }
@@ -31,6 +34,8 @@ impl CursorPosition {
         Self {
             position: None,
             selected_count: Default::default(),
+            editor: None,
+            has_long_lines: false,
             workspace: workspace.weak_handle(),
             _observe_active_editor: None,
         }
@@ -44,7 +49,9 @@ impl CursorPosition {
         self.selected_count.selections = editor.selections.count();
         let mut last_selection: Option<Selection<usize>> = None;
         for selection in editor.selections.all::<usize>(cx) {
-            self.selected_count.characters += selection.end - selection.start;
+            let stats = buffer.text_stats_for_range(selection.start..selection.end);
+            self.selected_count.characters += stats.chars;
+            self.selected_count.words += stats.words;
             if last_selection
                 .as_ref()
                 .map_or(true, |last_selection| selection.id > last_selection.id)
@@ -62,9 +69,23 @@ impl CursorPosition {
         }
         self.position = last_selection.map(|s| s.head().to_point(&buffer));
 
+        self.has_long_lines = editor.has_long_lines(cx);
+
         cx.notify();
     }
 
+    /// Counts the lines, words and characters of the whole buffer. It scans all of its text, so
+    /// it's only done when the counts are shown.
+    fn buffer_stats(editor: &View<Editor>, cx: &AppContext) -> String {
+        let buffer = editor.read(cx).buffer().read(cx).snapshot(cx);
+        let lines = buffer.max_point().row + 1;
+        let stats = buffer.text_stats_for_range(0..buffer.len());
+        format!(
+            "{lines} lines, {} words, {} characters",
+            stats.words, stats.chars
+        )
+    }
+
     fn write_position(&self, text: &mut String, cx: &AppContext) {
         if self.selected_count
             <= (SelectionStats {
@@ -77,6 +98,7 @@ impl CursorPosition {
         }
         let SelectionStats {
             lines,
+            words,
             characters,
             selections,
         } = self.selected_count;
@@ -84,14 +106,15 @@ impl CursorPosition {
         let is_short_format = format == &LineIndicatorFormat::Short;
         let lines = (lines > 1).then_some((lines, "line"));
         let selections = (selections > 1).then_some((selections, "selection"));
+        let words = (words > 0).then_some((words, "word"));
         let characters = (characters > 0).then_some((characters, "character"));
-        if (None, None, None) == (characters, selections, lines) {
+        if (None, None, None, None) == (characters, words, selections, lines) {
             // Nothing to display.
             return;
         }
         write!(text, " (").unwrap();
         let mut wrote_once = false;
-        for (count, name) in [selections, lines, characters].into_iter().flatten() {
+        for (count, name) in [selections, lines, words, characters].into_iter().flatten() {
             if wrote_once {
                 write!(text, ", ").unwrap();
             }
@@ -110,33 +133,62 @@ impl CursorPosition {
 
 impl Render for CursorPosition {
     fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
-        div().when_some(self.position, |el, position| {
-            let mut text = format!(
-                "{}{FILE_ROW_COLUMN_DELIMITER}{}",
-                position.row + 1,
-                position.column + 1
-            );
-            self.write_position(&mut text, cx);
-
-            el.child(
-                Button::new("go-to-line-column", text)
-                    .label_size(LabelSize::Small)
-                    .on_click(cx.listener(|this, _, cx| {
-                        if let Some(workspace) = this.workspace.upgrade() {
-                            workspace.update(cx, |workspace, cx| {
-                                if let Some(editor) = workspace
-                                    .active_item(cx)
-                                    .and_then(|item| item.act_as::<Editor>(cx))
-                                {
-                                    workspace
-                                        .toggle_modal(cx, |cx| crate::GoToLine::new(editor, cx))
+        h_flex()
+            .gap_1()
+            .when(self.has_long_lines, |el| {
+                el.child(
+                    Button::new("long-lines", "Long Lines")
+                        .label_size(LabelSize::Small)
+                        .color(Color::Warning)
+                        .tooltip(|cx| {
+                            Tooltip::text(
+                                "Lines longer than `long_line_threshold` are wrapped in fixed \
+                                 chunks, and bracket and occurrence highlighting are off",
+                                cx,
+                            )
+                        }),
+                )
+            })
+            .when_some(self.position, |el, position| {
+                let mut text = format!(
+                    "{}{FILE_ROW_COLUMN_DELIMITER}{}",
+                    position.row + 1,
+                    position.column + 1
+                );
+                self.write_position(&mut text, cx);
+                let editor = self.editor.clone();
+
+                el.child(
+                    Button::new("go-to-line-column", text)
+                        .label_size(LabelSize::Small)
+                        .on_click(cx.listener(|this, _, cx| {
+                            if let Some(workspace) = this.workspace.upgrade() {
+                                workspace.update(cx, |workspace, cx| {
+                                    if let Some(editor) = workspace
+                                        .active_item(cx)
+                                        .and_then(|item| item.act_as::<Editor>(cx))
+                                    {
+                                        workspace
+                                            .toggle_modal(cx, |cx| crate::GoToLine::new(editor, cx))
+                                    }
+                                });
+                            }
+                        }))
+                        .tooltip(move |cx| {
+                            match editor.as_ref().and_then(|editor| editor.upgrade()) {
+                                Some(editor) => Tooltip::with_meta(
+                                    "Go to Line/Column",
+                                    Some(&crate::Toggle),
+                                    Self::buffer_stats(&editor, cx),
+                                    cx,
+                                ),
+                                None => {
+                                    Tooltip::for_action("Go to Line/Column", &crate::Toggle, cx)
                                 }
-                            });
-                        }
-                    }))
-                    .tooltip(|cx| Tooltip::for_action("Go to Line/Column", &crate::Toggle, cx)),
-            )
-        })
+                            }
+                        }),
+                )
+            })
     }
 }
 
@@ -148,9 +200,12 @@ impl StatusItemView for CursorPosition {
     ) {
         if let Some(editor) = active_pane_item.and_then(|item| item.act_as::<Editor>(cx)) {
             self._observe_active_editor = Some(cx.observe(&editor, Self::update_position));
+            self.editor = Some(editor.downgrade());
             self.update_position(editor, cx);
         } else {
             self.position = None;
+            self.has_long_lines = false;
+            self.editor = None;
             self._observe_active_editor = None;
         }
 
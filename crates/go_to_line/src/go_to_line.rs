@@ -1,16 +1,19 @@
 pub mod cursor_position;
 
+use anyhow::Context as _;
 use cursor_position::LineIndicatorFormat;
 use editor::{scroll::Autoscroll, Editor};
 use gpui::{
     actions, div, prelude::*, AnyWindowHandle, AppContext, DismissEvent, EventEmitter, FocusHandle,
     FocusableView, Render, SharedString, Styled, Subscription, View, ViewContext, VisualContext,
 };
+use project::ProjectPath;
 use settings::Settings;
+use std::path::{Path, PathBuf};
 use text::{Bias, Point};
 use theme::ActiveTheme;
 use ui::{h_flex, prelude::*, v_flex, Label};
-use util::paths::FILE_ROW_COLUMN_DELIMITER;
+use util::paths::{PathLikeWithPosition, FILE_ROW_COLUMN_DELIMITER};
 use workspace::ModalView;
 
 actions!(go_to_line, [Toggle]);
@@ -39,6 +42,47 @@ impl EventEmitter<DismissEvent> for GoToLine {}
 
 enum GoToLineRowHighlights {}
 
+/// A location typed into the modal: `42`, `42:7`, `:42`, or a location pasted from
+/// compiler output such as `src/main.rs:42:7`. Rows and columns are 1-based.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct LineQuery {
+    path: Option<PathBuf>,
+    row: Option<u32>,
+    column: Option<u32>,
+}
+
+impl LineQuery {
+    fn parse(input: &str) -> Self {
+        let input = input.trim();
+        let position = input
+            .strip_prefix(FILE_ROW_COLUMN_DELIMITER)
+            .unwrap_or(input);
+        let mut components = position
+            .splitn(2, FILE_ROW_COLUMN_DELIMITER)
+            .map(str::trim)
+            .fuse();
+        let row = components.next().and_then(|row| row.parse::<u32>().ok());
+        let column = components.next().and_then(|col| col.parse::<u32>().ok());
+        if row.is_some() || input.is_empty() {
+            return Self {
+                path: None,
+                row,
+                column,
+            };
+        }
+
+        PathLikeWithPosition::parse_str(input, |path| anyhow::Ok(PathBuf::from(path)))
+            .ok()
+            .filter(|location| location.row.is_some())
+            .map(|location| Self {
+                path: Some(location.path_like),
+                row: location.row,
+                column: location.column,
+            })
+            .unwrap_or_default()
+    }
+}
+
 impl GoToLine {
     fn register(editor: &mut Editor, cx: &mut ViewContext<Editor>) {
         let handle = cx.view().downgrade();
@@ -133,23 +177,46 @@ impl GoToLine {
         }
     }
 
+    /// Returns the position to move to in the active editor, or `None` if the query is
+    /// incomplete or refers to another file.
     fn point_from_query(&self, cx: &ViewContext<Self>) -> Option<Point> {
-        let (row, column) = self.line_column_from_query(cx);
-        Some(Point::new(
-            row?.saturating_sub(1),
-            column.unwrap_or(0).saturating_sub(1),
-        ))
+        let query = self.query(cx);
+        if self.other_file(&query, cx).is_some() {
+            return None;
+        }
+        point_for(&query)
     }
 
-    fn line_column_from_query(&self, cx: &ViewContext<Self>) -> (Option<u32>, Option<u32>) {
-        let input = self.line_editor.read(cx).text(cx);
-        let mut components = input
-            .splitn(2, FILE_ROW_COLUMN_DELIMITER)
-            .map(str::trim)
-            .fuse();
-        let row = components.next().and_then(|row| row.parse::<u32>().ok());
-        let column = components.next().and_then(|col| col.parse::<u32>().ok());
-        (row, column)
+    fn query(&self, cx: &AppContext) -> LineQuery {
+        LineQuery::parse(&self.line_editor.read(cx).text(cx))
+    }
+
+    /// Returns the path in the query if it doesn't refer to the file being edited.
+    fn other_file<'a>(&self, query: &'a LineQuery, cx: &AppContext) -> Option<&'a Path> {
+        let path = query.path.as_deref()?;
+        let buffer = self.active_editor.read(cx).buffer().read(cx).as_singleton();
+        let is_active_file = buffer
+            .and_then(|buffer| buffer.read(cx).file().cloned())
+            .map_or(false, |file| {
+                file.path().ends_with(path) || path.ends_with(file.path())
+            });
+        (!is_active_file).then_some(path)
+    }
+
+    fn project_path_for(&self, path: &Path, cx: &AppContext) -> Option<ProjectPath> {
+        let workspace = self.active_editor.read(cx).workspace()?;
+        let project = workspace.read(cx).project().read(cx);
+        if path.is_absolute() {
+            return project.project_path_for_absolute_path(path, cx);
+        }
+        project.worktrees().find_map(|worktree| {
+            let worktree = worktree.read(cx);
+            worktree.entry_for_path(path)?;
+            Some(ProjectPath {
+                worktree_id: worktree.id(),
+                path: path.into(),
+            })
+        })
     }
 
     fn cancel(&mut self, _: &menu::Cancel, cx: &mut ViewContext<Self>) {
@@ -157,7 +224,34 @@ impl GoToLine {
     }
 
     fn confirm(&mut self, _: &menu::Confirm, cx: &mut ViewContext<Self>) {
-        if let Some(point) = self.point_from_query(cx) {
+        let query = self.query(cx);
+        if let Some(path) = self.other_file(&query, cx) {
+            let project_path = self.project_path_for(path, cx);
+            let workspace = self.active_editor.read(cx).workspace();
+            if let Some((project_path, workspace)) = project_path.zip(workspace) {
+                let open_task = workspace.update(cx, |workspace, cx| {
+                    workspace.open_path(project_path, None, true, cx)
+                });
+                let point = point_for(&query);
+                cx.spawn(|_, mut cx| async move {
+                    let editor = open_task
+                        .await?
+                        .downcast::<Editor>()
+                        .context("opened item is not an editor")?;
+                    if let Some(point) = point {
+                        editor.update(&mut cx, |editor, cx| {
+                            let snapshot = editor.buffer().read(cx).snapshot(cx);
+                            let point = snapshot.clip_point(point, Bias::Left);
+                            editor.change_selections(Some(Autoscroll::center()), cx, |s| {
+                                s.select_ranges([point..point])
+                            });
+                        })?;
+                    }
+                    anyhow::Ok(())
+                })
+                .detach_and_log_err(cx);
+            }
+        } else if let Some(point) = self.point_from_query(cx) {
             self.active_editor.update(cx, |editor, cx| {
                 let snapshot = editor.snapshot(cx).display_snapshot;
                 let point = snapshot.buffer_snapshot.clip_point(point, Bias::Left);
@@ -174,16 +268,30 @@ impl GoToLine {
     }
 }
 
+fn point_for(query: &LineQuery) -> Option<Point> {
+    Some(Point::new(
+        query.row?.saturating_sub(1),
+        query.column.unwrap_or(0).saturating_sub(1),
+    ))
+}
+
 impl Render for GoToLine {
     fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
         let mut help_text = self.current_text.clone();
-        let query = self.line_column_from_query(cx);
-        if let Some(line) = query.0 {
-            if let Some(column) = query.1 {
-                help_text = format!("Go to line {line}, column {column}").into();
+        let query = self.query(cx);
+        if let Some(line) = query.row {
+            let position = if let Some(column) = query.column {
+                format!("line {line}, column {column}")
             } else {
-                help_text = format!("Go to line {line}").into();
-            }
+                format!("line {line}")
+            };
+            help_text = match self.other_file(&query, cx) {
+                Some(path) if self.project_path_for(path, cx).is_some() => {
+                    format!("Open {} at {position}", path.display()).into()
+                }
+                Some(path) => format!("No file {} in this project", path.display()).into(),
+                None => format!("Go to {position}").into(),
+            };
         }
 
         div()
@@ -337,6 +445,92 @@ mod tests {
         assert_single_caret_at_row(&editor, expected_highlighted_row, cx);
     }
 
+    #[test]
+    fn test_parse_line_query() {
+        let query = |path: Option<&str>, row, column| LineQuery {
+            path: path.map(PathBuf::from),
+            row,
+            column,
+        };
+        assert_eq!(LineQuery::parse(""), query(None, None, None));
+        assert_eq!(LineQuery::parse("42"), query(None, Some(42), None));
+        assert_eq!(LineQuery::parse(" 42:7 "), query(None, Some(42), Some(7)));
+        assert_eq!(LineQuery::parse(":42"), query(None, Some(42), None));
+        assert_eq!(LineQuery::parse(":42:7"), query(None, Some(42), Some(7)));
+        assert_eq!(
+            LineQuery::parse("src/main.rs:42:7"),
+            query(Some("src/main.rs"), Some(42), Some(7))
+        );
+        assert_eq!(
+            LineQuery::parse("src/main.rs:42"),
+            query(Some("src/main.rs"), Some(42), None)
+        );
+        assert_eq!(LineQuery::parse("src/main.rs"), query(None, None, None));
+        assert_eq!(LineQuery::parse("abc"), query(None, None, None));
+    }
+
+    #[gpui::test]
+    async fn test_go_to_line_with_path(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            "/dir",
+            json!({
+                "src": {
+                    "a.rs": "one\ntwo\nthree\nfour\n",
+                    "b.rs": "five\nsix\nseven\n",
+                },
+            }),
+        )
+        .await;
+
+        let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project.clone(), cx));
+        let worktree_id = workspace.update(cx, |workspace, cx| {
+            workspace.project().update(cx, |project, cx| {
+                project.worktrees().next().unwrap().read(cx).id()
+            })
+        });
+        let editor = workspace
+            .update(cx, |workspace, cx| {
+                workspace.open_path((worktree_id, "src/a.rs"), None, true, cx)
+            })
+            .await
+            .unwrap()
+            .downcast::<Editor>()
+            .unwrap();
+
+        // A location in the file being edited moves the cursor in place.
+        let go_to_line_view = open_go_to_line_view(&workspace, cx);
+        cx.simulate_input("dir/src/a.rs:3:2");
+        assert_eq!(highlighted_display_rows(&editor, cx), vec![2]);
+        cx.dispatch_action(menu::Confirm);
+        drop(go_to_line_view);
+        assert_single_caret_at_row(&editor, 2, cx);
+
+        // A location in another file opens that file at the location.
+        let go_to_line_view = open_go_to_line_view(&workspace, cx);
+        cx.simulate_input("src/b.rs:2");
+        assert_eq!(
+            highlighted_display_rows(&editor, cx),
+            Vec::<u32>::new(),
+            "Locations in other files should not be highlighted in the active editor"
+        );
+        cx.dispatch_action(menu::Confirm);
+        drop(go_to_line_view);
+        cx.run_until_parked();
+        let other_editor = workspace.update(cx, |workspace, cx| {
+            workspace.active_item_as::<Editor>(cx).unwrap()
+        });
+        assert_ne!(other_editor.entity_id(), editor.entity_id());
+        assert_eq!(
+            other_editor.update(cx, |editor, cx| editor.text(cx)),
+            "five\nsix\nseven\n"
+        );
+        assert_single_caret_at_row(&other_editor, 1, cx);
+        assert_single_caret_at_row(&editor, 2, cx);
+    }
+
     fn open_go_to_line_view(
         workspace: &View<Workspace>,
         cx: &mut VisualTestContext,
@@ -14,6 +14,11 @@ use std::any::{Any, TypeId};
 /// ```rust
 /// actions!(editor, [MoveUp, MoveDown, MoveLeft, MoveRight, Newline]);
 /// ```
+/// Listed actions can be given a human-readable name, which the UI shows instead of one derived
+/// from the action's type name.
+/// ```rust
+/// actions!(editor, [#[display_name = "editor: select all occurrences"] SelectAllMatches]);
+/// ```
 /// More complex data types can also be actions, providing they implement Clone, PartialEq,
 /// and serde_derive::Deserialize.
 /// Use `impl_actions!` to automatically implement the action in the given namespace.
@@ -52,6 +57,12 @@ pub trait Action: 'static + Send {
     /// Get the name of this action, for displaying in UI
     fn name(&self) -> &str;
 
+    /// Get the human-readable name the action was declared with, if any. The UI derives one
+    /// from [`Action::name`] when there is none.
+    fn display_name(&self) -> Option<&'static str> {
+        None
+    }
+
     /// Get the name of this action for debugging
     fn debug_name() -> &'static str
     where
@@ -180,7 +191,7 @@ impl ActionRegistry {
 /// To use more complex data types as actions, use `impl_actions!`
 #[macro_export]
 macro_rules! actions {
-    ($namespace:path, [ $($name:ident),* $(,)? ]) => {
+    ($namespace:path, [ $($(#[display_name = $display_name:literal])? $name:ident),* $(,)? ]) => {
         $(
             #[doc = "The `"]
             #[doc = stringify!($name)]
@@ -189,7 +200,7 @@ macro_rules! actions {
             #[serde(crate = "gpui::private::serde")]
             pub struct $name;
 
-            gpui::__impl_action!($namespace, $name,
+            gpui::__impl_action!($namespace, $name, [$($display_name)?],
                 fn build(_: gpui::private::serde_json::Value) -> gpui::Result<::std::boxed::Box<dyn gpui::Action>> {
                     Ok(Box::new(Self))
                 }
@@ -203,9 +214,9 @@ macro_rules! actions {
 /// Implements the Action trait for any struct that implements Clone, Default, PartialEq, and serde_deserialize::Deserialize
 #[macro_export]
 macro_rules! impl_actions {
-    ($namespace:path, [ $($name:ident),* $(,)? ]) => {
+    ($namespace:path, [ $($(#[display_name = $display_name:literal])? $name:ident),* $(,)? ]) => {
         $(
-            gpui::__impl_action!($namespace, $name,
+            gpui::__impl_action!($namespace, $name, [$($display_name)?],
                 fn build(value: gpui::private::serde_json::Value) -> gpui::Result<::std::boxed::Box<dyn gpui::Action>> {
                     Ok(std::boxed::Box::new(gpui::private::serde_json::from_value::<Self>(value)?))
                 }
@@ -219,7 +230,7 @@ macro_rules! impl_actions {
 #[doc(hidden)]
 #[macro_export]
 macro_rules! __impl_action {
-    ($namespace:path, $name:ident, $build:item) => {
+    ($namespace:path, $name:ident, [$($display_name:literal)?], $build:item) => {
         impl gpui::Action for $name {
             fn name(&self) -> &'static str
             {
@@ -230,6 +241,12 @@ macro_rules! __impl_action {
                 )
             }
 
+            $(
+                fn display_name(&self) -> ::std::option::Option<&'static str> {
+                    ::std::option::Option::Some($display_name)
+                }
+            )?
+
             fn debug_name() -> &'static str
             where
                 Self: ::std::marker::Sized
@@ -9,7 +9,10 @@ use crate::{
 };
 use anyhow::{anyhow, bail};
 use futures::{channel::oneshot, Stream, StreamExt};
-use std::{cell::RefCell, future::Future, ops::Deref, rc::Rc, sync::Arc, time::Duration};
+use std::{
+    cell::RefCell, future::Future, ops::Deref, pin::pin, rc::Rc, sync::Arc, task::Poll,
+    time::Duration,
+};
 
 /// A TestAppContext is provided to tests created with `#[gpui::test]`, it provides
 /// an implementation of `Context` with additional methods that are useful in tests.
@@ -267,6 +270,11 @@ impl TestAppContext {
         self.test_platform.simulate_new_path_selection(select_path);
     }
 
+    /// Returns the file name that the pending "Save" dialog suggests.
+    pub fn suggested_new_path_name(&self) -> Option<String> {
+        self.test_platform.suggested_new_path_name()
+    }
+
     /// Simulates clicking a button in an platform-level alert dialog.
     pub fn simulate_prompt_answer(&self, button_ix: usize) {
         self.test_platform.simulate_prompt_answer(button_ix);
@@ -353,6 +361,29 @@ impl TestAppContext {
         self.background_executor.run_until_parked()
     }
 
+    /// Runs a single task that is ready to run, and returns whether there was one. Which task
+    /// runs is decided by the seed of the test, so stepping through the tasks one at a time
+    /// reproduces the same interleaving for the same seed.
+    pub fn step(&mut self) -> bool {
+        self.dispatcher.tick(false)
+    }
+
+    /// Polls the given future, running one task at a time until it completes. Returns `None`
+    /// if the future is still pending once there are no more tasks to run.
+    pub fn run_until<R>(&mut self, future: impl Future<Output = R>) -> Option<R> {
+        let mut future = pin!(future);
+        let waker = futures::task::noop_waker();
+        let mut cx = std::task::Context::from_waker(&waker);
+        loop {
+            if let Poll::Ready(result) = future.as_mut().poll(&mut cx) {
+                return Some(result);
+            }
+            if !self.step() {
+                return None;
+            }
+        }
+    }
+
     /// Simulate dispatching an action to the currently focused node in the window.
     pub fn dispatch_action<A>(&mut self, window: AnyWindowHandle, action: A)
     where
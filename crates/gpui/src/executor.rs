@@ -94,6 +94,19 @@ impl<T> Future for Task<T> {
     }
 }
 
+/// How urgently a background task should run. Threads pick up tasks of higher priority
+/// first, but tasks of lower priority still get to run while higher ones keep coming.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TaskPriority {
+    /// Work that can wait, such as indexing or catching up on history.
+    Low,
+    /// Most work.
+    #[default]
+    Medium,
+    /// Work that the user is waiting on while typing, such as fuzzy matching.
+    High,
+}
+
 /// A task label is an opaque identifier that you can use to
 /// refer to a task in tests.
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
@@ -132,7 +145,34 @@ impl BackgroundExecutor {
     where
         R: Send + 'static,
     {
-        self.spawn_internal::<R>(Box::pin(future), None)
+        self.spawn_internal::<R>(Box::pin(future), None, TaskPriority::default())
+    }
+
+    /// Enqueues the given future to be run to completion on a background thread, ahead of
+    /// or after other tasks depending on the given priority.
+    pub fn spawn_with_priority<R>(
+        &self,
+        priority: TaskPriority,
+        future: impl Future<Output = R> + Send + 'static,
+    ) -> Task<R>
+    where
+        R: Send + 'static,
+    {
+        self.spawn_internal::<R>(Box::pin(future), None, priority)
+    }
+
+    /// Runs the given closure on a thread that is set aside for blocking work, such as
+    /// filesystem access, so that it doesn't hold up the threads that run other tasks.
+    pub fn spawn_blocking<R>(&self, f: impl FnOnce() -> R + Send + 'static) -> Task<R>
+    where
+        R: Send + 'static,
+    {
+        let dispatcher = self.dispatcher.clone();
+        let (runnable, task) = async_task::spawn(async move { f() }, move |runnable| {
+            dispatcher.dispatch_blocking(runnable)
+        });
+        runnable.schedule();
+        Task::Spawned(task)
     }
 
     /// Enqueues the given future to be run to completion on a background thread.
@@ -145,17 +185,19 @@ impl BackgroundExecutor {
     where
         R: Send + 'static,
     {
-        self.spawn_internal::<R>(Box::pin(future), Some(label))
+        self.spawn_internal::<R>(Box::pin(future), Some(label), TaskPriority::default())
     }
 
     fn spawn_internal<R: Send + 'static>(
         &self,
         future: AnyFuture<R>,
         label: Option<TaskLabel>,
+        priority: TaskPriority,
     ) -> Task<R> {
         let dispatcher = self.dispatcher.clone();
-        let (runnable, task) =
-            async_task::spawn(future, move |runnable| dispatcher.dispatch(runnable, label));
+        let (runnable, task) = async_task::spawn(future, move |runnable| {
+            dispatcher.dispatch(runnable, label, priority)
+        });
         runnable.schedule();
         Task::Spawned(task)
     }
@@ -300,6 +342,15 @@ impl BackgroundExecutor {
     /// Scoped lets you start a number of tasks and waits
     /// for all of them to complete before returning.
     pub async fn scoped<'scope, F>(&self, scheduler: F)
+    where
+        F: FnOnce(&mut Scope<'scope>),
+    {
+        self.scoped_with_priority(TaskPriority::default(), scheduler)
+            .await
+    }
+
+    /// Like [`Self::scoped`], but the futures are run with the given priority.
+    pub async fn scoped_with_priority<'scope, F>(&self, priority: TaskPriority, scheduler: F)
     where
         F: FnOnce(&mut Scope<'scope>),
     {
@@ -307,7 +358,7 @@ impl BackgroundExecutor {
         (scheduler)(&mut scope);
         let spawned = mem::take(&mut scope.futures)
             .into_iter()
-            .map(|f| self.spawn(f))
+            .map(|f| self.spawn_with_priority(priority, f))
             .collect::<Vec<_>>();
         for task in spawned {
             task.await;
@@ -499,3 +550,92 @@ impl<'a> Drop for Scope<'a> {
         self.executor.block(self.rx.next());
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TestAppContext, TestDispatcher};
+    use futures::{channel::oneshot, FutureExt as _};
+    use parking_lot::Mutex;
+    use rand::prelude::*;
+
+    // For compatibility with the test macro
+    use crate as gpui;
+
+    #[crate::test]
+    fn test_task_priorities(cx: &mut TestAppContext) {
+        let executor = cx.executor();
+        let mut high_priority_ran_first = 0;
+        for _ in 0..100 {
+            let completed = Arc::new(Mutex::new(Vec::new()));
+            let spawn = |priority| {
+                let completed = completed.clone();
+                executor
+                    .spawn_with_priority(priority, async move { completed.lock().push(priority) })
+                    .detach();
+            };
+            for _ in 0..5 {
+                spawn(TaskPriority::Low);
+            }
+            spawn(TaskPriority::High);
+            executor.run_until_parked();
+
+            let completed = completed.lock();
+            assert_eq!(completed.len(), 6);
+            if completed[0] == TaskPriority::High {
+                high_priority_ran_first += 1;
+            }
+        }
+
+        // The high priority task mostly runs before the low priority ones that were queued
+        // first, but the order is still up to chance.
+        assert!(high_priority_ran_first > 50, "{high_priority_ran_first}");
+        assert!(high_priority_ran_first < 100, "{high_priority_ran_first}");
+    }
+
+    #[crate::test]
+    fn test_spawn_blocking(cx: &mut TestAppContext) {
+        let executor = cx.executor();
+        let task = executor.spawn_blocking(|| 1 + 1);
+        executor.run_until_parked();
+        assert_eq!(task.now_or_never(), Some(2));
+    }
+
+    #[crate::test(iterations = 10, seed = 100)]
+    fn test_stepping_is_deterministic(mut rng: StdRng) {
+        let run = |seed| {
+            let mut cx =
+                TestAppContext::new(TestDispatcher::new(StdRng::seed_from_u64(seed)), None);
+            let order = Arc::new(Mutex::new(Vec::new()));
+            for ix in 0..10 {
+                let order = order.clone();
+                cx.executor()
+                    .spawn(async move { order.lock().push(ix) })
+                    .detach();
+            }
+            let mut steps = 0;
+            while cx.step() {
+                steps += 1;
+            }
+            assert_eq!(steps, 10);
+            cx.quit();
+            let order = order.lock().clone();
+            order
+        };
+
+        // The same seed runs the tasks in the same order.
+        let seed = rng.gen();
+        assert_eq!(run(seed), run(seed));
+    }
+
+    #[crate::test]
+    fn test_run_until(cx: &mut TestAppContext) {
+        let (tx, rx) = oneshot::channel();
+        cx.executor().spawn(async move { tx.send(1).ok() }).detach();
+        assert_eq!(cx.run_until(rx), Some(Ok(1)));
+
+        // A future that nothing completes is still pending once the tasks have run.
+        let (_tx, rx) = oneshot::channel::<()>();
+        assert_eq!(cx.run_until(rx), None);
+    }
+}
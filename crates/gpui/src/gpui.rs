@@ -97,6 +97,7 @@ pub mod test;
 mod text_system;
 mod util;
 mod view;
+mod watch;
 mod window;
 
 /// Do not touch, here be dragons for use by gpui_macros and such.
@@ -149,6 +150,7 @@ pub use test::*;
 pub use text_system::*;
 pub use util::arc_cow::ArcCow;
 pub use view::*;
+pub use watch::*;
 pub use window::*;
 
 use std::{any::Any, borrow::BorrowMut};
@@ -50,8 +50,9 @@
 ///  KeyBinding::new("cmd-k left", pane::SplitLeft, Some("Pane"))
 ///
 use crate::{
-    Action, ActionRegistry, DispatchPhase, EntityId, FocusId, KeyBinding, KeyContext, Keymap,
-    KeymatchResult, Keystroke, KeystrokeMatcher, ModifiersChangedEvent, WindowContext,
+    match_context_stack, Action, ActionRegistry, DispatchPhase, EntityId, FocusId, KeyBinding,
+    KeyContext, Keymap, KeymatchResult, Keystroke, KeystrokeMatcher, ModifiersChangedEvent,
+    ResolvedKeyBinding, WindowContext,
 };
 use collections::FxHashMap;
 use smallvec::SmallVec;
@@ -416,19 +417,20 @@ impl DispatchTree {
             .collect()
     }
 
+    /// Resolves every binding of the keymap within the given context stack. Dispatching a
+    /// keystroke matches it against the bindings in the same order, see [`Keymap::resolve`].
+    pub fn resolved_bindings(&self, context_stack: &[KeyContext]) -> Vec<ResolvedKeyBinding> {
+        self.keymap.borrow().resolve(context_stack)
+    }
+
     // dispatch_key pushes the next keystroke into any key binding matchers.
-    // any matching bindings are returned in the order that they should be dispatched:
-    // * First by length of binding (so if you have a binding for "b" and "ab", the "ab" binding fires first)
-    // * Secondly by depth in the tree (so if Editor has a binding for "b" and workspace a
-    // binding for "b", the Editor action fires first).
+    // any matching bindings are returned in the order that they should be dispatched,
+    // see [`match_context_stack`].
     pub fn dispatch_key(
         &mut self,
         keystroke: &Keystroke,
         dispatch_path: &SmallVec<[DispatchNodeId; 32]>,
     ) -> KeymatchResult {
-        let mut bindings = SmallVec::<[KeyBinding; 1]>::new();
-        let mut pending = false;
-
         let mut context_stack: SmallVec<[KeyContext; 4]> = SmallVec::new();
         for node_id in dispatch_path {
             let node = self.node(*node_id);
@@ -438,34 +440,12 @@ impl DispatchTree {
             }
         }
 
-        while !context_stack.is_empty() {
-            let keystroke_matcher = self
-                .keystroke_matchers
-                .entry(context_stack.clone())
-                .or_insert_with(|| KeystrokeMatcher::new(self.keymap.clone()));
-
-            let result = keystroke_matcher.match_keystroke(keystroke, &context_stack);
-            if result.pending && !pending && !bindings.is_empty() {
-                context_stack.pop();
-                continue;
-            }
-
-            pending = result.pending || pending;
-            for new_binding in result.bindings {
-                match bindings
-                    .iter()
-                    .position(|el| el.keystrokes.len() < new_binding.keystrokes.len())
-                {
-                    Some(idx) => {
-                        bindings.insert(idx, new_binding);
-                    }
-                    None => bindings.push(new_binding),
-                }
-            }
-            context_stack.pop();
-        }
-
-        KeymatchResult { bindings, pending }
+        match_context_stack(&context_stack, |context_stack| {
+            self.keystroke_matchers
+                .entry(context_stack.into())
+                .or_insert_with(|| KeystrokeMatcher::new(self.keymap.clone()))
+                .match_keystroke(keystroke, context_stack)
+        })
     }
 
     pub fn has_pending_keystrokes(&self) -> bool {
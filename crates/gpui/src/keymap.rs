@@ -9,13 +9,52 @@ pub(crate) use matcher::*;
 use crate::{Action, Keystroke, NoAction};
 use collections::{HashMap, HashSet};
 use smallvec::SmallVec;
-use std::any::{Any, TypeId};
+use std::{
+    any::{Any, TypeId},
+    borrow::Borrow,
+};
 
 /// An opaque identifier of which version of the keymap is currently active.
 /// The keymap's version is changed whenever bindings are added or removed.
 #[derive(Copy, Clone, Eq, PartialEq, Default)]
 pub struct KeymapVersion(usize);
 
+/// How a binding of the keymap resolves within a given context stack.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum KeyBindingStatus {
+    /// The binding's keystrokes dispatch its action.
+    Active,
+    /// The binding's keystrokes also start a longer binding that's enabled, so its action is
+    /// only dispatched when no other keystroke follows them within a second.
+    Pending,
+    /// The binding is enabled, but the binding at the given index of the resolution has
+    /// the same keystrokes and takes precedence over it.
+    Shadowed(usize),
+    /// The binding isn't enabled in the context stack, either because its context
+    /// predicate doesn't match or because its keystrokes were disabled.
+    Inactive,
+}
+
+/// A binding of the keymap, along with how it resolves within a given context stack.
+#[derive(Clone, Debug)]
+pub struct ResolvedKeyBinding {
+    /// The binding itself.
+    pub binding: KeyBinding,
+    /// How the binding resolves.
+    pub status: KeyBindingStatus,
+}
+
+/// Two bindings of the keymap that bind the same keystrokes to different actions in
+/// contexts that overlap.
+#[derive(Clone, Debug)]
+pub struct KeyBindingConflict {
+    /// The binding that was added later, which takes precedence wherever both bindings
+    /// are enabled for the same context.
+    pub binding: KeyBinding,
+    /// The binding that was added earlier.
+    pub shadowed: KeyBinding,
+}
+
 /// A collection of key bindings for the user's application.
 #[derive(Default)]
 pub struct Keymap {
@@ -89,6 +128,171 @@ impl Keymap {
             .filter(move |binding| binding.action().partial_eq(action))
     }
 
+    /// Iterate over the bindings that are enabled in the given context, starting from the one
+    /// that takes precedence. This is the order that keystrokes are matched against them in.
+    pub fn bindings_in_context<'a>(
+        &'a self,
+        context: &'a [KeyContext],
+    ) -> impl 'a + Iterator<Item = &'a KeyBinding> {
+        self.indexed_bindings_in_context(context)
+            .map(|(_, binding)| binding)
+    }
+
+    fn indexed_bindings_in_context<'a>(
+        &'a self,
+        context: &'a [KeyContext],
+    ) -> impl 'a + Iterator<Item = (usize, &'a KeyBinding)> {
+        self.bindings
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(move |(_, binding)| self.binding_enabled(binding, context))
+    }
+
+    /// Resolve every binding within the given context stack, arranged from lowest to highest.
+    ///
+    /// Like when dispatching a keystroke, the bindings of the whole stack are considered
+    /// before the ones of its shorter prefixes, so the enabled bindings come first, in the
+    /// order that they take precedence in. The bindings that aren't enabled follow them, the
+    /// ones added last first.
+    pub fn resolve(&self, context_stack: &[KeyContext]) -> Vec<ResolvedKeyBinding> {
+        let mut resolved = Vec::<ResolvedKeyBinding>::with_capacity(self.bindings.len());
+        let mut resolved_indices = HashSet::default();
+        let mut matches = HashMap::<&[Keystroke], KeymatchResult<&KeyBinding>>::default();
+        let mut active_indices = HashMap::<&[Keystroke], usize>::default();
+        for len in (1..=context_stack.len()).rev() {
+            for (ix, binding) in self.indexed_bindings_in_context(&context_stack[..len]) {
+                if !resolved_indices.insert(ix) {
+                    continue;
+                }
+                let keystrokes = binding.keystrokes();
+                let result = matches.entry(keystrokes).or_insert_with(|| {
+                    match_context_stack(context_stack, |context| {
+                        self.match_in_context(keystrokes, context)
+                    })
+                });
+                let dispatched_first = result
+                    .bindings
+                    .first()
+                    .map_or(false, |first| std::ptr::eq(*first, binding));
+                let status = if dispatched_first {
+                    active_indices.insert(keystrokes, resolved.len());
+                    if result.pending {
+                        KeyBindingStatus::Pending
+                    } else {
+                        KeyBindingStatus::Active
+                    }
+                } else {
+                    active_indices
+                        .get(keystrokes)
+                        .map_or(KeyBindingStatus::Inactive, |&active_ix| {
+                            KeyBindingStatus::Shadowed(active_ix)
+                        })
+                };
+                resolved.push(ResolvedKeyBinding {
+                    binding: binding.clone(),
+                    status,
+                });
+            }
+        }
+
+        for (ix, binding) in self.bindings.iter().enumerate().rev() {
+            if !resolved_indices.contains(&ix) {
+                resolved.push(ResolvedKeyBinding {
+                    binding: binding.clone(),
+                    status: KeyBindingStatus::Inactive,
+                });
+            }
+        }
+        resolved
+    }
+
+    /// Match the given keystrokes against the bindings that are enabled in the given context,
+    /// without regard to the keystrokes that were pending before them.
+    fn match_in_context<'a>(
+        &'a self,
+        keystrokes: &[Keystroke],
+        context: &[KeyContext],
+    ) -> KeymatchResult<&'a KeyBinding> {
+        let mut result = KeymatchResult {
+            bindings: SmallVec::new(),
+            pending: false,
+        };
+        for binding in self.bindings.iter().rev() {
+            if !self.binding_enabled(binding, context) {
+                continue;
+            }
+            match binding.match_keystrokes(keystrokes) {
+                KeyMatch::Matched => result.bindings.push(binding),
+                KeyMatch::Pending => result.pending = true,
+                KeyMatch::None => {}
+            }
+        }
+        result
+    }
+
+    /// Find the bindings that bind the same keystrokes to different actions in contexts that
+    /// overlap, either because one of them has no context predicate, or because one of their
+    /// predicates requires everything that the other one requires. Bindings that a `null`
+    /// binding disables wherever both of them are enabled don't conflict.
+    pub fn conflicts(&self) -> Vec<KeyBindingConflict> {
+        let mut indices_by_keystrokes = HashMap::<&[Keystroke], SmallVec<[usize; 2]>>::default();
+        for (ix, binding) in self.bindings.iter().enumerate() {
+            indices_by_keystrokes
+                .entry(binding.keystrokes())
+                .or_default()
+                .push(ix);
+        }
+
+        let mut conflicts = Vec::new();
+        for (ix, binding) in self.bindings.iter().enumerate() {
+            for &earlier_ix in &indices_by_keystrokes[binding.keystrokes()] {
+                if earlier_ix >= ix {
+                    break;
+                }
+                let earlier = &self.bindings[earlier_ix];
+                if !binding.action().partial_eq(earlier.action())
+                    && contexts_overlap(
+                        binding.context_predicate.as_ref(),
+                        earlier.context_predicate.as_ref(),
+                    )
+                    && !self.disabled_where_both_enabled(binding, earlier)
+                {
+                    conflicts.push(KeyBindingConflict {
+                        binding: binding.clone(),
+                        shadowed: earlier.clone(),
+                    });
+                }
+            }
+        }
+        conflicts
+    }
+
+    /// Whether a `null` binding disables the given bindings, which have the same keystrokes, in
+    /// every context that enables both of them.
+    fn disabled_where_both_enabled(&self, a: &KeyBinding, b: &KeyBinding) -> bool {
+        let Some(disabled_predicates) = self.disabled_keystrokes.get(&a.keystrokes) else {
+            return false;
+        };
+        let mut required = SmallVec::<[&KeyBindingContextPredicate; 4]>::new();
+        for predicate in [&a.context_predicate, &b.context_predicate]
+            .into_iter()
+            .flatten()
+        {
+            conjuncts(predicate, &mut required);
+        }
+        disabled_predicates
+            .iter()
+            .any(|disabled_predicate| match disabled_predicate {
+                None => true,
+                Some(disabled_predicate) => {
+                    let mut disabled = SmallVec::<[&KeyBindingContextPredicate; 4]>::new();
+                    conjuncts(disabled_predicate, &mut disabled);
+                    disabled.iter().all(|conjunct| required.contains(conjunct))
+                }
+            })
+    }
+
     /// Check if the given binding is enabled, given a certain key context.
     pub fn binding_enabled(&self, binding: &KeyBinding, context: &[KeyContext]) -> bool {
         // If binding has a context predicate, it must match the current context,
@@ -118,11 +322,74 @@ impl Keymap {
     }
 }
 
+/// Matches a keystroke against the bindings of every prefix of the context stack, from the
+/// whole stack to its first context, and combines the results in the order that their bindings
+/// get dispatched in:
+/// * First by length of binding (so if you have a binding for "b" and "ab", the "ab" binding fires first)
+/// * Secondly by depth in the tree (so if Editor has a binding for "b" and workspace a
+/// binding for "b", the Editor action fires first).
+///
+/// A shorter prefix that only waits for more keystrokes is ignored once a longer one matched
+/// bindings, so that those bindings get dispatched right away.
+pub(crate) fn match_context_stack<B: Borrow<KeyBinding>>(
+    context_stack: &[KeyContext],
+    mut match_in_context: impl FnMut(&[KeyContext]) -> KeymatchResult<B>,
+) -> KeymatchResult<B> {
+    let mut bindings = SmallVec::<[B; 1]>::new();
+    let mut pending = false;
+    for len in (1..=context_stack.len()).rev() {
+        let result = match_in_context(&context_stack[..len]);
+        if result.pending && !pending && !bindings.is_empty() {
+            continue;
+        }
+
+        pending = result.pending || pending;
+        for new_binding in result.bindings {
+            let new_len = new_binding.borrow().keystrokes.len();
+            match bindings
+                .iter()
+                .position(|el| el.borrow().keystrokes.len() < new_len)
+            {
+                Some(idx) => bindings.insert(idx, new_binding),
+                None => bindings.push(new_binding),
+            }
+        }
+    }
+    KeymatchResult { bindings, pending }
+}
+
+fn conjuncts<'a>(
+    predicate: &'a KeyBindingContextPredicate,
+    into: &mut SmallVec<[&'a KeyBindingContextPredicate; 4]>,
+) {
+    if let KeyBindingContextPredicate::And(left, right) = predicate {
+        conjuncts(left, into);
+        conjuncts(right, into);
+    } else {
+        into.push(predicate);
+    }
+}
+
+fn contexts_overlap(
+    a: Option<&KeyBindingContextPredicate>,
+    b: Option<&KeyBindingContextPredicate>,
+) -> bool {
+    let (Some(a), Some(b)) = (a, b) else {
+        return true;
+    };
+    let (mut a_conjuncts, mut b_conjuncts) = (SmallVec::new(), SmallVec::new());
+    conjuncts(a, &mut a_conjuncts);
+    conjuncts(b, &mut b_conjuncts);
+    a_conjuncts.iter().all(|a| b_conjuncts.contains(a))
+        || b_conjuncts.iter().all(|b| a_conjuncts.contains(b))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate as gpui;
-    use gpui::actions;
+    use gpui::{actions, SharedString};
+    use std::{cell::RefCell, rc::Rc};
 
     actions!(
         keymap_test,
@@ -180,4 +447,157 @@ mod tests {
         // binding is globally disabled
         assert!(!keymap.binding_enabled(&bindings[1], &[KeyContext::parse("barf").unwrap()]));
     }
+
+    #[test]
+    fn test_keymap_resolve() {
+        let bindings = [
+            KeyBinding::new("ctrl-a", ActionAlpha {}, None),
+            KeyBinding::new("ctrl-a", ActionBeta {}, Some("editor")),
+            KeyBinding::new("ctrl-b", ActionGamma {}, Some("pane")),
+            KeyBinding::new("ctrl-a", ActionDelta {}, Some("pane")),
+            KeyBinding::new("ctrl-c", ActionGamma {}, Some("terminal")),
+            KeyBinding::new("ctrl-b", NoAction {}, Some("editor")),
+        ];
+
+        let mut keymap = Keymap::default();
+        keymap.add_bindings(bindings.clone());
+
+        let context_stack = [
+            KeyContext::parse("pane").unwrap(),
+            KeyContext::parse("editor").unwrap(),
+        ];
+        let resolved = keymap
+            .resolve(&context_stack)
+            .into_iter()
+            .map(|resolved| (resolved.binding.action().name(), resolved.status))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            resolved,
+            [
+                // The bindings enabled for the whole stack come first, the ones added last first.
+                ("keymap_test::ActionBeta", KeyBindingStatus::Active),
+                ("keymap_test::ActionAlpha", KeyBindingStatus::Shadowed(0)),
+                // Then the ones enabled for its shorter prefixes.
+                ("keymap_test::ActionDelta", KeyBindingStatus::Shadowed(0)),
+                ("keymap_test::ActionGamma", KeyBindingStatus::Active),
+                ("keymap_test::ActionGamma", KeyBindingStatus::Inactive),
+            ]
+        );
+
+        // The resolution is consistent with the order that keystrokes are matched in.
+        let mut matcher = KeystrokeMatcher::new(Rc::new(RefCell::new(keymap)));
+        let result = matcher.match_keystroke(&Keystroke::parse("ctrl-a").unwrap(), &context_stack);
+        assert_eq!(
+            result.bindings[0].action().name(),
+            "keymap_test::ActionBeta"
+        );
+    }
+
+    #[test]
+    fn test_keymap_resolve_pending() {
+        let bindings = [
+            KeyBinding::new("ctrl-k", ActionAlpha {}, None),
+            KeyBinding::new("ctrl-k ctrl-s", ActionBeta {}, Some("editor")),
+            KeyBinding::new("ctrl-x", ActionGamma {}, Some("editor")),
+            KeyBinding::new("ctrl-x ctrl-s", ActionDelta {}, Some("pane")),
+        ];
+
+        let mut keymap = Keymap::default();
+        keymap.add_bindings(bindings);
+
+        let context_stack = [
+            KeyContext::parse("pane").unwrap(),
+            KeyContext::parse("editor").unwrap(),
+        ];
+        let resolved = keymap
+            .resolve(&context_stack)
+            .into_iter()
+            .map(|resolved| (resolved.binding.action().name(), resolved.status))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            resolved,
+            [
+                // A longer binding of a shorter prefix of the stack doesn't hold it back.
+                ("keymap_test::ActionGamma", KeyBindingStatus::Active),
+                ("keymap_test::ActionBeta", KeyBindingStatus::Active),
+                // A longer binding enabled in the same context does.
+                ("keymap_test::ActionAlpha", KeyBindingStatus::Pending),
+                ("keymap_test::ActionDelta", KeyBindingStatus::Active),
+            ]
+        );
+
+        let mut matcher = KeystrokeMatcher::new(Rc::new(RefCell::new(keymap)));
+        let result = matcher.match_keystroke(&Keystroke::parse("ctrl-k").unwrap(), &context_stack);
+        assert!(result.pending);
+        assert_eq!(
+            result.bindings[0].action().name(),
+            "keymap_test::ActionAlpha"
+        );
+    }
+
+    #[test]
+    fn test_keymap_conflicts() {
+        let bindings = [
+            KeyBinding::new("ctrl-a", ActionAlpha {}, Some("editor")).with_source("default"),
+            KeyBinding::new("ctrl-a", ActionBeta {}, Some("terminal")).with_source("default"),
+            KeyBinding::new("ctrl-a", ActionAlpha {}, Some("editor && mode == full")),
+            KeyBinding::new("ctrl-a", ActionGamma {}, Some("mode == full && editor"))
+                .with_source("keymap.json:3"),
+            KeyBinding::new("ctrl-b", ActionDelta {}, None),
+            KeyBinding::new("ctrl-b", ActionAlpha {}, Some("pane")),
+            // Disabling the keystrokes where both bindings are enabled resolves the conflict.
+            KeyBinding::new("ctrl-c", ActionAlpha {}, Some("editor")),
+            KeyBinding::new("ctrl-c", ActionBeta {}, Some("editor && mode == full")),
+            KeyBinding::new("ctrl-c", NoAction {}, Some("editor")),
+            KeyBinding::new("ctrl-d", ActionAlpha {}, None),
+            KeyBinding::new("ctrl-d", ActionBeta {}, Some("terminal")),
+            KeyBinding::new("ctrl-d", NoAction {}, Some("pane")),
+        ];
+
+        let mut keymap = Keymap::default();
+        keymap.add_bindings(bindings);
+
+        let conflicts = keymap
+            .conflicts()
+            .into_iter()
+            .map(|conflict| {
+                (
+                    conflict.binding.action().name(),
+                    conflict.binding.source().cloned(),
+                    conflict.shadowed.action().name(),
+                    conflict.shadowed.source().cloned(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            conflicts,
+            [
+                (
+                    "keymap_test::ActionGamma",
+                    Some(SharedString::from("keymap.json:3")),
+                    "keymap_test::ActionAlpha",
+                    Some(SharedString::from("default")),
+                ),
+                (
+                    "keymap_test::ActionGamma",
+                    Some(SharedString::from("keymap.json:3")),
+                    "keymap_test::ActionAlpha",
+                    None,
+                ),
+                (
+                    "keymap_test::ActionAlpha",
+                    None,
+                    "keymap_test::ActionDelta",
+                    None
+                ),
+                // Disabling them somewhere else doesn't.
+                (
+                    "keymap_test::ActionBeta",
+                    None,
+                    "keymap_test::ActionAlpha",
+                    None
+                ),
+            ]
+        );
+    }
 }
@@ -1,4 +1,4 @@
-use crate::{Action, KeyBindingContextPredicate, KeyMatch, Keystroke};
+use crate::{Action, KeyBindingContextPredicate, KeyMatch, Keystroke, SharedString};
 use anyhow::Result;
 use smallvec::SmallVec;
 
@@ -7,6 +7,7 @@ pub struct KeyBinding {
     pub(crate) action: Box<dyn Action>,
     pub(crate) keystrokes: SmallVec<[Keystroke; 2]>,
     pub(crate) context_predicate: Option<KeyBindingContextPredicate>,
+    pub(crate) source: Option<SharedString>,
 }
 
 impl Clone for KeyBinding {
@@ -15,6 +16,7 @@ impl Clone for KeyBinding {
             action: self.action.boxed_clone(),
             keystrokes: self.keystrokes.clone(),
             context_predicate: self.context_predicate.clone(),
+            source: self.source.clone(),
         }
     }
 }
@@ -42,9 +44,16 @@ impl KeyBinding {
             keystrokes,
             action,
             context_predicate: context,
+            source: None,
         })
     }
 
+    /// Describe where this binding was defined, such as the keymap file and line it came from.
+    pub fn with_source(mut self, source: impl Into<SharedString>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
     /// Check if the given keystrokes match this binding.
     pub fn match_keystrokes(&self, pending_keystrokes: &[Keystroke]) -> KeyMatch {
         if self.keystrokes.as_ref().starts_with(pending_keystrokes) {
@@ -68,6 +77,16 @@ impl KeyBinding {
     pub fn action(&self) -> &dyn Action {
         self.action.as_ref()
     }
+
+    /// Get the context predicate that must match for this binding to be enabled, if any
+    pub fn context_predicate(&self) -> Option<&KeyBindingContextPredicate> {
+        self.context_predicate.as_ref()
+    }
+
+    /// Get the description of where this binding was defined, if it was given one
+    pub fn source(&self) -> Option<&SharedString> {
+        self.source.as_ref()
+    }
 }
 
 impl std::fmt::Debug for KeyBinding {
@@ -76,6 +95,7 @@ impl std::fmt::Debug for KeyBinding {
             .field("keystrokes", &self.keystrokes)
             .field("context_predicate", &self.context_predicate)
             .field("action", &self.action.name())
+            .field("source", &self.source)
             .finish()
     }
 }
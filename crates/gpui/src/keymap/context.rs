@@ -316,6 +316,16 @@ impl KeyBindingContextPredicate {
         }
     }
 
+    fn precedence(&self) -> u32 {
+        match self {
+            Self::Identifier(_) | Self::Not(_) => u32::MAX,
+            Self::Equal(_, _) | Self::NotEqual(_, _) => PRECEDENCE_EQ,
+            Self::Child(_, _) => PRECEDENCE_CHILD,
+            Self::And(_, _) => PRECEDENCE_AND,
+            Self::Or(_, _) => PRECEDENCE_OR,
+        }
+    }
+
     fn new_or(self, other: Self) -> Result<Self> {
         Ok(Self::Or(Box::new(self), Box::new(other)))
     }
@@ -345,6 +355,42 @@ impl KeyBindingContextPredicate {
     }
 }
 
+/// Formats the predicate in the same format that it's parsed from, adding the parentheses
+/// that are needed to parse it back into the same predicate.
+impl fmt::Display for KeyBindingContextPredicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn operand(
+            f: &mut fmt::Formatter<'_>,
+            operand: &KeyBindingContextPredicate,
+            min_precedence: u32,
+        ) -> fmt::Result {
+            if operand.precedence() < min_precedence {
+                write!(f, "({operand})")
+            } else {
+                write!(f, "{operand}")
+            }
+        }
+
+        let (left, operator, right) = match self {
+            Self::Identifier(name) => return write!(f, "{name}"),
+            Self::Equal(left, right) => return write!(f, "{left} == {right}"),
+            Self::NotEqual(left, right) => return write!(f, "{left} != {right}"),
+            Self::Not(predicate) => {
+                write!(f, "!")?;
+                return operand(f, predicate, PRECEDENCE_NOT);
+            }
+            Self::Child(left, right) => (left, ">", right),
+            Self::And(left, right) => (left, "&&", right),
+            Self::Or(left, right) => (left, "||", right),
+        };
+        // The operators associate to the left, so an operand on the right with the same
+        // precedence has to be parenthesized.
+        operand(f, left, self.precedence())?;
+        write!(f, " {operator} ")?;
+        operand(f, right, self.precedence() + 1)
+    }
+}
+
 const PRECEDENCE_CHILD: u32 = 1;
 const PRECEDENCE_OR: u32 = 2;
 const PRECEDENCE_AND: u32 = 3;
@@ -511,6 +557,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_display_round_trips() {
+        for source in [
+            "Editor",
+            "Editor && mode == full",
+            "!Editor",
+            "!(a == b)",
+            "a && (b == c || d != e)",
+            "a || b && c",
+            "(a || b) && c",
+            "Workspace > Pane > Editor",
+            "Workspace > (Pane > Editor)",
+            "!(a && b) || c",
+        ] {
+            let predicate = KeyBindingContextPredicate::parse(source).unwrap();
+            assert_eq!(predicate.to_string(), source);
+            assert_eq!(
+                KeyBindingContextPredicate::parse(&predicate.to_string()).unwrap(),
+                predicate
+            );
+        }
+    }
+
     #[test]
     fn test_parse_parenthesized_expressions() {
         assert_eq!(
@@ -8,8 +8,8 @@ pub(crate) struct KeystrokeMatcher {
     keymap_version: KeymapVersion,
 }
 
-pub struct KeymatchResult {
-    pub bindings: SmallVec<[KeyBinding; 1]>,
+pub struct KeymatchResult<B = KeyBinding> {
+    pub bindings: SmallVec<[B; 1]>,
     pub pending: bool,
 }
 
@@ -52,11 +52,7 @@ impl KeystrokeMatcher {
         let mut pending_key = None;
         let mut bindings = SmallVec::new();
 
-        for binding in keymap.bindings().rev() {
-            if !keymap.binding_enabled(binding, context_stack) {
-                continue;
-            }
-
+        for binding in keymap.bindings_in_context(context_stack) {
             for candidate in keystroke.match_candidates() {
                 self.pending_keystrokes.push(candidate.clone());
                 match binding.match_keystrokes(&self.pending_keystrokes) {
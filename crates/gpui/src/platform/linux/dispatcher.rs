@@ -1,4 +1,4 @@
-use crate::{PlatformDispatcher, TaskLabel};
+use crate::{PlatformDispatcher, TaskLabel, TaskPriority};
 use async_task::Runnable;
 use calloop::{
     channel::{self, Sender},
@@ -6,10 +6,18 @@ use calloop::{
     EventLoop,
 };
 use parking::{Parker, Unparker};
-use parking_lot::Mutex;
-use std::{thread, time::Duration};
+use parking_lot::{Condvar, Mutex};
+use std::{collections::VecDeque, sync::Arc, thread, time::Duration};
 use util::ResultExt;
 
+/// The most threads that run blocking work at once.
+const MAX_BLOCKING_THREADS: usize = 64;
+/// How long a thread for blocking work waits for more before it exits.
+const BLOCKING_THREAD_KEEP_ALIVE: Duration = Duration::from_secs(10);
+/// How many times in a row queued runnables can be passed over for ones of higher priority
+/// before they get to run.
+const MAX_PASSED_OVER: usize = 8;
+
 struct TimerAfter {
     duration: Duration,
     runnable: Runnable,
@@ -19,23 +27,155 @@ pub(crate) struct LinuxDispatcher {
     parker: Mutex<Parker>,
     main_sender: Sender<Runnable>,
     timer_sender: Sender<TimerAfter>,
-    background_sender: flume::Sender<Runnable>,
+    background_queue: Arc<PriorityQueue>,
+    blocking_pool: Arc<BlockingPool>,
     _background_threads: Vec<thread::JoinHandle<()>>,
     main_thread_id: thread::ThreadId,
 }
 
+/// The runnables waiting for a background thread. Runnables of higher priority run first,
+/// unless lower priority ones have been passed over too often.
+#[derive(Default)]
+struct PriorityQueue {
+    state: Mutex<PriorityQueueState>,
+    runnable_available: Condvar,
+}
+
+#[derive(Default)]
+struct PriorityQueueState {
+    queues: [VecDeque<Runnable>; 3],
+    passed_over: [usize; 3],
+    closed: bool,
+}
+
+impl PriorityQueue {
+    fn push(&self, runnable: Runnable, priority: TaskPriority) {
+        self.state.lock().queues[priority as usize].push_back(runnable);
+        self.runnable_available.notify_one();
+    }
+
+    /// Waits for the next runnable, or returns `None` once the queue is closed.
+    fn pop(&self) -> Option<Runnable> {
+        let mut state = self.state.lock();
+        loop {
+            if state.closed {
+                return None;
+            }
+            if let Some(runnable) = state.pop() {
+                return Some(runnable);
+            }
+            self.runnable_available.wait(&mut state);
+        }
+    }
+
+    fn close(&self) {
+        self.state.lock().closed = true;
+        self.runnable_available.notify_all();
+    }
+}
+
+impl PriorityQueueState {
+    fn pop(&mut self) -> Option<Runnable> {
+        let waiting = |ix: &usize| !self.queues[*ix].is_empty();
+        let starved_ix = (0..self.queues.len())
+            .filter(waiting)
+            .find(|ix| self.passed_over[*ix] >= MAX_PASSED_OVER);
+        let ix = starved_ix.or_else(|| (0..self.queues.len()).rev().find(waiting))?;
+
+        for lower_ix in 0..ix {
+            if !self.queues[lower_ix].is_empty() {
+                self.passed_over[lower_ix] += 1;
+            }
+        }
+        self.passed_over[ix] = 0;
+        self.queues[ix].pop_front()
+    }
+}
+
+/// The threads that run blocking work. They're only started when there is work that no idle
+/// thread can pick up, and they exit once they've been idle for a while.
+#[derive(Default)]
+struct BlockingPool {
+    state: Mutex<BlockingPoolState>,
+    runnable_available: Condvar,
+}
+
+#[derive(Default)]
+struct BlockingPoolState {
+    queue: VecDeque<Runnable>,
+    thread_count: usize,
+    idle_thread_count: usize,
+    closed: bool,
+}
+
+impl BlockingPool {
+    fn push(self: &Arc<Self>, runnable: Runnable) {
+        let mut state = self.state.lock();
+        state.queue.push_back(runnable);
+        if state.queue.len() <= state.idle_thread_count
+            || state.thread_count >= MAX_BLOCKING_THREADS
+        {
+            self.runnable_available.notify_one();
+            return;
+        }
+
+        let pool = self.clone();
+        match thread::Builder::new()
+            .name("BlockingWorker".into())
+            .spawn(move || pool.run())
+        {
+            Ok(_) => state.thread_count += 1,
+            Err(error) => {
+                log::error!("failed to start a thread for blocking work: {error}");
+                self.runnable_available.notify_one();
+            }
+        }
+    }
+
+    fn run(&self) {
+        let mut state = self.state.lock();
+        loop {
+            if let Some(runnable) = state.queue.pop_front() {
+                drop(state);
+                runnable.run();
+                state = self.state.lock();
+                continue;
+            }
+            if state.closed {
+                break;
+            }
+
+            state.idle_thread_count += 1;
+            let timed_out = self
+                .runnable_available
+                .wait_for(&mut state, BLOCKING_THREAD_KEEP_ALIVE)
+                .timed_out();
+            state.idle_thread_count -= 1;
+            if timed_out && state.queue.is_empty() {
+                break;
+            }
+        }
+        state.thread_count -= 1;
+    }
+
+    fn close(&self) {
+        self.state.lock().closed = true;
+        self.runnable_available.notify_all();
+    }
+}
+
 impl LinuxDispatcher {
     pub fn new(main_sender: Sender<Runnable>) -> Self {
-        let (background_sender, background_receiver) = flume::unbounded::<Runnable>();
+        let background_queue = Arc::new(PriorityQueue::default());
         let thread_count = std::thread::available_parallelism()
             .map(|i| i.get())
             .unwrap_or(1);
 
         let mut background_threads = (0..thread_count)
             .map(|_| {
-                let receiver = background_receiver.clone();
+                let queue = background_queue.clone();
                 std::thread::spawn(move || {
-                    for runnable in receiver {
+                    while let Some(runnable) = queue.pop() {
                         runnable.run();
                     }
                 })
@@ -78,20 +218,32 @@ impl LinuxDispatcher {
             parker: Mutex::new(Parker::new()),
             main_sender,
             timer_sender,
-            background_sender,
+            background_queue,
+            blocking_pool: Arc::default(),
             _background_threads: background_threads,
             main_thread_id: thread::current().id(),
         }
     }
 }
 
+impl Drop for LinuxDispatcher {
+    fn drop(&mut self) {
+        self.background_queue.close();
+        self.blocking_pool.close();
+    }
+}
+
 impl PlatformDispatcher for LinuxDispatcher {
     fn is_main_thread(&self) -> bool {
         thread::current().id() == self.main_thread_id
     }
 
-    fn dispatch(&self, runnable: Runnable, _: Option<TaskLabel>) {
-        self.background_sender.send(runnable).unwrap();
+    fn dispatch(&self, runnable: Runnable, _: Option<TaskLabel>, priority: TaskPriority) {
+        self.background_queue.push(runnable, priority);
+    }
+
+    fn dispatch_blocking(&self, runnable: Runnable) {
+        self.blocking_pool.push(runnable);
     }
 
     fn dispatch_on_main_thread(&self, runnable: Runnable) {
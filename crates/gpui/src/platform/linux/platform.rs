@@ -94,6 +94,10 @@ pub(crate) struct LinuxCommon {
     pub(crate) callbacks: PlatformHandlers,
     pub(crate) signal: LoopSignal,
     pub(crate) menus: Vec<OwnedMenu>,
+    /// The hash of the text last written to the clipboard, along with its metadata. The
+    /// system clipboard only stores text, so metadata is kept here and handed back out
+    /// for as long as the clipboard still holds that text.
+    clipboard_metadata: Option<(u64, String)>,
 }
 
 impl LinuxCommon {
@@ -120,6 +124,7 @@ impl LinuxCommon {
             callbacks,
             signal,
             menus: Vec::new(),
+            clipboard_metadata: None,
         };
 
         (common, main_receiver)
@@ -301,15 +306,21 @@ impl<P: LinuxClient + 'static> Platform for P {
         done_rx
     }
 
-    fn prompt_for_new_path(&self, directory: &Path) -> oneshot::Receiver<Option<PathBuf>> {
+    fn prompt_for_new_path(
+        &self,
+        directory: &Path,
+        suggested_name: Option<&str>,
+    ) -> oneshot::Receiver<Option<PathBuf>> {
         let (done_tx, done_rx) = oneshot::channel();
         let directory = directory.to_owned();
+        let suggested_name = suggested_name.map(ToOwned::to_owned);
         self.foreground_executor()
             .spawn(async move {
                 let result = SaveFileRequest::default()
                     .modal(true)
                     .title("Select new path")
                     .accept_label("Accept")
+                    .current_name(suggested_name.as_deref())
                     .send()
                     .await
                     .ok()
@@ -338,6 +349,10 @@ impl<P: LinuxClient + 'static> Platform for P {
         open::that_detached(dir);
     }
 
+    fn open_with_system(&self, path: &Path) {
+        open::that_detached(path).log_err();
+    }
+
     fn on_quit(&self, callback: Box<dyn FnMut()>) {
         self.with_common(|common| {
             common.callbacks.quit = Some(callback);
@@ -482,6 +497,12 @@ impl<P: LinuxClient + 'static> Platform for P {
     }
 
     fn write_to_clipboard(&self, item: ClipboardItem) {
+        self.with_common(|common| {
+            common.clipboard_metadata = item
+                .metadata
+                .clone()
+                .map(|metadata| (ClipboardItem::text_hash(&item.text), metadata));
+        });
         self.write_to_clipboard(item)
     }
 
@@ -490,7 +511,16 @@ impl<P: LinuxClient + 'static> Platform for P {
     }
 
     fn read_from_clipboard(&self) -> Option<ClipboardItem> {
-        self.read_from_clipboard()
+        let mut item = self.read_from_clipboard()?;
+        if item.metadata.is_none() {
+            // Metadata is stale once another application has replaced the clipboard's text.
+            let text_hash = ClipboardItem::text_hash(&item.text);
+            item.metadata = self.with_common(|common| match &common.clipboard_metadata {
+                Some((hash, metadata)) if *hash == text_hash => Some(metadata.clone()),
+                _ => None,
+            });
+        }
+        Some(item)
     }
 
     fn add_recent_document(&self, _path: &Path) {}
@@ -2,7 +2,7 @@
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 
-use crate::{PlatformDispatcher, TaskLabel};
+use crate::{PlatformDispatcher, TaskLabel, TaskPriority};
 use async_task::Runnable;
 use objc::{
     class, msg_send,
@@ -53,10 +53,25 @@ impl PlatformDispatcher for MacDispatcher {
         is_main_thread == YES
     }
 
-    fn dispatch(&self, runnable: Runnable, _: Option<TaskLabel>) {
+    fn dispatch(&self, runnable: Runnable, _: Option<TaskLabel>, priority: TaskPriority) {
+        let queue_priority = match priority {
+            TaskPriority::High | TaskPriority::Medium => DISPATCH_QUEUE_PRIORITY_HIGH,
+            TaskPriority::Low => DISPATCH_QUEUE_PRIORITY_LOW,
+        };
         unsafe {
             dispatch_async_f(
-                dispatch_get_global_queue(DISPATCH_QUEUE_PRIORITY_HIGH.try_into().unwrap(), 0),
+                dispatch_get_global_queue(queue_priority.try_into().unwrap(), 0),
+                runnable.into_raw().as_ptr() as *mut c_void,
+                Some(trampoline),
+            );
+        }
+    }
+
+    fn dispatch_blocking(&self, runnable: Runnable) {
+        // GCD starts more threads when the ones of a queue are blocked.
+        unsafe {
+            dispatch_async_f(
+                dispatch_get_global_queue(DISPATCH_QUEUE_PRIORITY_DEFAULT.try_into().unwrap(), 0),
                 runnable.into_raw().as_ptr() as *mut c_void,
                 Some(trampoline),
             );
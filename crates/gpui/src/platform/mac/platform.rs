@@ -643,8 +643,13 @@ impl Platform for MacPlatform {
         done_rx
     }
 
-    fn prompt_for_new_path(&self, directory: &Path) -> oneshot::Receiver<Option<PathBuf>> {
+    fn prompt_for_new_path(
+        &self,
+        directory: &Path,
+        suggested_name: Option<&str>,
+    ) -> oneshot::Receiver<Option<PathBuf>> {
         let directory = directory.to_owned();
+        let suggested_name = suggested_name.map(ToOwned::to_owned);
         let (done_tx, done_rx) = oneshot::channel();
         self.foreground_executor()
             .spawn(async move {
@@ -653,6 +658,10 @@ impl Platform for MacPlatform {
                     let path = ns_string(directory.to_string_lossy().as_ref());
                     let url = NSURL::fileURLWithPath_isDirectory_(nil, path, true.to_objc());
                     panel.setDirectoryURL(url);
+                    if let Some(suggested_name) = suggested_name {
+                        let name = ns_string(&suggested_name);
+                        let _: () = msg_send![panel, setNameFieldStringValue: name];
+                    }
 
                     let done_tx = Cell::new(Some(done_tx));
                     let block = ConcreteBlock::new(move |response: NSModalResponse| {
@@ -697,6 +706,21 @@ impl Platform for MacPlatform {
         }
     }
 
+    fn open_with_system(&self, path: &Path) {
+        unsafe {
+            let path = path.to_path_buf();
+            self.0
+                .lock()
+                .background_executor
+                .spawn(async move {
+                    let full_path = ns_string(path.to_str().unwrap_or(""));
+                    let workspace: id = msg_send![class!(NSWorkspace), sharedWorkspace];
+                    let _: BOOL = msg_send![workspace, openFile: full_path];
+                })
+                .detach();
+        }
+    }
+
     fn on_quit(&self, callback: Box<dyn FnMut()>) {
         self.0.lock().quit = Some(callback);
     }
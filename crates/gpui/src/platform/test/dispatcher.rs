@@ -1,4 +1,4 @@
-use crate::{PlatformDispatcher, TaskLabel};
+use crate::{PlatformDispatcher, TaskLabel, TaskPriority};
 use async_task::Runnable;
 use backtrace::Backtrace;
 use collections::{HashMap, HashSet, VecDeque};
@@ -29,7 +29,7 @@ pub struct TestDispatcher {
 struct TestDispatcherState {
     random: StdRng,
     foreground: HashMap<TestDispatcherId, VecDeque<Runnable>>,
-    background: Vec<Runnable>,
+    background: Vec<(Runnable, TaskPriority)>,
     deprioritized_background: Vec<Runnable>,
     delayed: Vec<(Duration, Runnable)>,
     time: Duration,
@@ -119,7 +119,7 @@ impl TestDispatcher {
                 break;
             }
             let (_, runnable) = state.delayed.remove(0);
-            state.background.push(runnable);
+            state.background.push((runnable, TaskPriority::default()));
         }
 
         let foreground_len: usize = if background_only {
@@ -159,8 +159,27 @@ impl TestDispatcher {
                     .pop_front()
                     .unwrap();
             } else {
-                let ix = state.random.gen_range(0..background_len);
-                runnable = state.background.swap_remove(ix);
+                // Tasks of higher priority are more likely to run next, but any of them can.
+                let total_weight = state
+                    .background
+                    .iter()
+                    .map(|(_, priority)| priority_weight(*priority))
+                    .sum::<usize>();
+                let mut choice = state.random.gen_range(0..total_weight);
+                let ix = state
+                    .background
+                    .iter()
+                    .position(|(_, priority)| {
+                        let weight = priority_weight(*priority);
+                        if choice < weight {
+                            true
+                        } else {
+                            choice -= weight;
+                            false
+                        }
+                    })
+                    .unwrap();
+                runnable = state.background.swap_remove(ix).0;
             };
         };
 
@@ -234,6 +253,15 @@ impl TestDispatcher {
     }
 }
 
+/// How likely a background task of the given priority is to be picked next, relative to others.
+fn priority_weight(priority: TaskPriority) -> usize {
+    match priority {
+        TaskPriority::Low => 1,
+        TaskPriority::Medium => 4,
+        TaskPriority::High => 16,
+    }
+}
+
 impl Clone for TestDispatcher {
     fn clone(&self) -> Self {
         let id = post_inc(&mut self.state.lock().next_id.0);
@@ -251,20 +279,25 @@ impl PlatformDispatcher for TestDispatcher {
         self.state.lock().is_main_thread
     }
 
-    fn dispatch(&self, runnable: Runnable, label: Option<TaskLabel>) {
+    fn dispatch(&self, runnable: Runnable, label: Option<TaskLabel>, priority: TaskPriority) {
         {
             let mut state = self.state.lock();
-            if label.map_or(false, |label| {
+            let deprioritized = label.map_or(false, |label| {
                 state.deprioritized_task_labels.contains(&label)
-            }) {
+            });
+            if deprioritized {
                 state.deprioritized_background.push(runnable);
             } else {
-                state.background.push(runnable);
+                state.background.push((runnable, priority));
             }
         }
         self.unparker.unpark();
     }
 
+    fn dispatch_blocking(&self, runnable: Runnable) {
+        self.dispatch(runnable, None, TaskPriority::default());
+    }
+
     fn dispatch_on_main_thread(&self, runnable: Runnable) {
         self.state
             .lock()
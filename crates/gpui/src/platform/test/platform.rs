@@ -33,7 +33,7 @@ pub(crate) struct TestPlatform {
 #[derive(Default)]
 pub(crate) struct TestPrompts {
     multiple_choice: VecDeque<oneshot::Sender<usize>>,
-    new_path: VecDeque<(PathBuf, oneshot::Sender<Option<PathBuf>>)>,
+    new_path: VecDeque<(PathBuf, Option<String>, oneshot::Sender<Option<PathBuf>>)>,
 }
 
 impl TestPlatform {
@@ -63,7 +63,7 @@ impl TestPlatform {
         &self,
         select_path: impl FnOnce(&std::path::Path) -> Option<std::path::PathBuf>,
     ) {
-        let (path, tx) = self
+        let (path, _, tx) = self
             .prompts
             .borrow_mut()
             .new_path
@@ -72,6 +72,15 @@ impl TestPlatform {
         tx.send(select_path(&path)).ok();
     }
 
+    pub(crate) fn suggested_new_path_name(&self) -> Option<String> {
+        let prompts = self.prompts.borrow();
+        let (_, suggested_name, _) = prompts
+            .new_path
+            .front()
+            .expect("no pending new path prompt");
+        suggested_name.clone()
+    }
+
     pub(crate) fn simulate_prompt_answer(&self, response_ix: usize) {
         let tx = self
             .prompts
@@ -219,12 +228,14 @@ impl Platform for TestPlatform {
     fn prompt_for_new_path(
         &self,
         directory: &std::path::Path,
+        suggested_name: Option<&str>,
     ) -> oneshot::Receiver<Option<std::path::PathBuf>> {
         let (tx, rx) = oneshot::channel();
-        self.prompts
-            .borrow_mut()
-            .new_path
-            .push_back((directory.to_path_buf(), tx));
+        self.prompts.borrow_mut().new_path.push_back((
+            directory.to_path_buf(),
+            suggested_name.map(ToOwned::to_owned),
+            tx,
+        ));
         rx
     }
 
@@ -232,6 +243,10 @@ impl Platform for TestPlatform {
         unimplemented!()
     }
 
+    fn open_with_system(&self, _path: &std::path::Path) {
+        unimplemented!()
+    }
+
     fn on_quit(&self, _callback: Box<dyn FnMut()>) {}
 
     fn on_reopen(&self, _callback: Box<dyn FnMut()>) {
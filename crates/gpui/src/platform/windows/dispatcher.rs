@@ -22,7 +22,7 @@ use windows::{
     },
 };
 
-use crate::{PlatformDispatcher, TaskLabel};
+use crate::{PlatformDispatcher, TaskLabel, TaskPriority};
 
 pub(crate) struct WindowsDispatcher {
     controller: DispatcherQueueController,
@@ -56,7 +56,12 @@ impl WindowsDispatcher {
         }
     }
 
-    fn dispatch_on_threadpool(&self, runnable: Runnable) {
+    fn dispatch_on_threadpool(
+        &self,
+        runnable: Runnable,
+        priority: WorkItemPriority,
+        options: WorkItemOptions,
+    ) {
         let handler = {
             let mut task_wrapper = Some(runnable);
             WorkItemHandler::new(move |_| {
@@ -64,12 +69,7 @@ impl WindowsDispatcher {
                 Ok(())
             })
         };
-        ThreadPool::RunWithPriorityAndOptionsAsync(
-            &handler,
-            WorkItemPriority::High,
-            WorkItemOptions::TimeSliced,
-        )
-        .log_err();
+        ThreadPool::RunWithPriorityAndOptionsAsync(&handler, priority, options).log_err();
     }
 
     fn dispatch_on_threadpool_after(&self, runnable: Runnable, duration: Duration) {
@@ -100,13 +100,21 @@ impl PlatformDispatcher for WindowsDispatcher {
         current().id() == self.main_thread_id
     }
 
-    fn dispatch(&self, runnable: Runnable, label: Option<TaskLabel>) {
-        self.dispatch_on_threadpool(runnable);
+    fn dispatch(&self, runnable: Runnable, label: Option<TaskLabel>, priority: TaskPriority) {
+        let priority = match priority {
+            TaskPriority::High | TaskPriority::Medium => WorkItemPriority::High,
+            TaskPriority::Low => WorkItemPriority::Low,
+        };
+        self.dispatch_on_threadpool(runnable, priority, WorkItemOptions::TimeSliced);
         if let Some(label) = label {
             log::debug!("TaskLabel: {label:?}");
         }
     }
 
+    fn dispatch_blocking(&self, runnable: Runnable) {
+        self.dispatch_on_threadpool(runnable, WorkItemPriority::Normal, WorkItemOptions::None);
+    }
+
     fn dispatch_on_main_thread(&self, runnable: Runnable) {
         let handler = {
             let mut task_wrapper = Some(runnable);
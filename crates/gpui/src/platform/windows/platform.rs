@@ -391,13 +391,18 @@ impl Platform for WindowsPlatform {
         rx
     }
 
-    fn prompt_for_new_path(&self, directory: &Path) -> Receiver<Option<PathBuf>> {
+    fn prompt_for_new_path(
+        &self,
+        directory: &Path,
+        suggested_name: Option<&str>,
+    ) -> Receiver<Option<PathBuf>> {
         let directory = directory.to_owned();
+        let suggested_name = suggested_name.map(ToOwned::to_owned);
         let (tx, rx) = oneshot::channel();
         self.foreground_executor()
             .spawn(async move {
                 unsafe {
-                    let Ok(dialog) = show_savefile_dialog(directory) else {
+                    let Ok(dialog) = show_savefile_dialog(directory, suggested_name) else {
                         let _ = tx.send(None);
                         return;
                     };
@@ -437,6 +442,24 @@ impl Platform for WindowsPlatform {
             .detach();
     }
 
+    fn open_with_system(&self, path: &Path) {
+        let Ok(file_full_path) = path.canonicalize() else {
+            log::error!("unable to parse file path");
+            return;
+        };
+        self.background_executor()
+            .spawn(async move {
+                let Some(path) = file_full_path.to_str() else {
+                    return;
+                };
+                if path.is_empty() {
+                    return;
+                }
+                open_target(path);
+            })
+            .detach();
+    }
+
     fn on_quit(&self, callback: Box<dyn FnMut()>) {
         self.state.borrow_mut().callbacks.quit = Some(callback);
     }
@@ -635,8 +658,16 @@ fn open_target_in_explorer(target: &str) {
     }
 }
 
-unsafe fn show_savefile_dialog(directory: PathBuf) -> Result<IFileSaveDialog> {
+unsafe fn show_savefile_dialog(
+    directory: PathBuf,
+    suggested_name: Option<String>,
+) -> Result<IFileSaveDialog> {
     let dialog: IFileSaveDialog = CoCreateInstance(&FileSaveDialog, None, CLSCTX_ALL)?;
+    if let Some(suggested_name) = suggested_name {
+        let _ = dialog
+            .SetFileName(&HSTRING::from(suggested_name))
+            .inspect_err(|e| log::error!("unable to set file name for save file dialog: {}", e));
+    }
     let bind_context = CreateBindCtx(0)?;
     let Ok(full_path) = directory.canonicalize() else {
         return Ok(dialog);
@@ -37,15 +37,20 @@ use std::{
 /// Run the given test function with the configured parameters.
 /// This is intended for use with the `gpui::test` macro
 /// and generally should not be used directly.
+///
+/// Every iteration runs with its own seed, starting at `starting_seed` (or 0). The `SEED` and
+/// `ITERATIONS` environment variables override the starting seed and the number of iterations,
+/// so that a failure can be reproduced with the seed it reports.
 pub fn run_test(
     mut num_iterations: u64,
+    starting_seed: Option<u64>,
     max_retries: usize,
     test_fn: &mut (dyn RefUnwindSafe + Fn(TestDispatcher, u64)),
     on_fail_fn: Option<fn()>,
 ) {
     let starting_seed = env::var("SEED")
         .map(|seed| seed.parse().expect("invalid SEED variable"))
-        .unwrap_or(0);
+        .unwrap_or(starting_seed.unwrap_or(0));
     if let Ok(iterations) = env::var("ITERATIONS") {
         num_iterations = iterations.parse().expect("invalid ITERATIONS variable");
     }
@@ -69,9 +74,10 @@ pub fn run_test(
                         println!("retrying: attempt {}", retry);
                         retry += 1;
                     } else {
-                        if is_randomized {
-                            eprintln!("failing seed: {}", seed);
-                        }
+                        // Even a test that runs once is scheduled with a seed, which
+                        // decides the order its tasks ran in.
+                        eprintln!("failing seed: {}", seed);
+                        eprintln!("rerun with SEED={seed} ITERATIONS=1 to reproduce");
                         if let Some(f) = on_fail_fn {
                             f()
                         }
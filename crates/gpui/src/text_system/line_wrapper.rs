@@ -1,6 +1,7 @@
 use crate::{px, FontId, FontRun, Pixels, PlatformTextSystem};
 use collections::HashMap;
 use std::{iter, sync::Arc};
+use unicode_segmentation::UnicodeSegmentation as _;
 
 /// The GPUI line wrapper, used to wrap lines of text to a given width.
 pub struct LineWrapper {
@@ -9,12 +10,18 @@ pub struct LineWrapper {
     pub(crate) font_size: Pixels,
     cached_ascii_char_widths: [Option<Pixels>; 128],
     cached_other_char_widths: HashMap<char, Pixels>,
+    cached_cluster_widths: HashMap<String, Pixels>,
 }
 
 impl LineWrapper {
     /// The maximum indent that can be applied to a line.
     pub const MAX_INDENT: u32 = 256;
 
+    /// How many grapheme cluster widths are cached at most. Text with many distinct clusters,
+    /// like emoji sequences or scripts with combining marks, would grow the cache without bound
+    /// otherwise, so it's cleared once it gets this large.
+    const MAX_CACHED_CLUSTERS: usize = 4096;
+
     pub(crate) fn new(
         font_id: FontId,
         font_size: Pixels,
@@ -26,10 +33,13 @@ impl LineWrapper {
             font_size,
             cached_ascii_char_widths: [None; 128],
             cached_other_char_widths: HashMap::default(),
+            cached_cluster_widths: HashMap::default(),
         }
     }
 
-    /// Wrap a line of text to the given width with this wrapper's font and font size.
+    /// Wrap a line of text to the given width with this wrapper's font and font size. Lines
+    /// are only wrapped between grapheme clusters, so that e.g. an emoji sequence or a letter
+    /// with combining accents is never split.
     pub fn wrap_line<'a>(
         &'a mut self,
         line: &'a str,
@@ -42,9 +52,10 @@ impl LineWrapper {
         let mut last_candidate_width = px(0.);
         let mut last_wrap_ix = 0;
         let mut prev_c = '\0';
-        let mut char_indices = line.char_indices();
+        let mut clusters = line.grapheme_indices(true);
         iter::from_fn(move || {
-            for (ix, c) in char_indices.by_ref() {
+            for (ix, cluster) in clusters.by_ref() {
+                let c = cluster.chars().next().unwrap();
                 if c == '\n' {
                     continue;
                 }
@@ -58,8 +69,12 @@ impl LineWrapper {
                     first_non_whitespace_ix = Some(ix);
                 }
 
-                let char_width = self.width_for_char(c);
-                width += char_width;
+                let cluster_width = if cluster.len() == c.len_utf8() {
+                    self.width_for_char(c)
+                } else {
+                    self.width_for_cluster(cluster)
+                };
+                width += cluster_width;
                 if width > wrap_width && ix > last_wrap_ix {
                     if let (None, Some(first_non_whitespace_ix)) = (indent, first_non_whitespace_ix)
                     {
@@ -74,7 +89,7 @@ impl LineWrapper {
                         last_candidate_ix = 0;
                     } else {
                         last_wrap_ix = ix;
-                        width = char_width;
+                        width = cluster_width;
                     }
 
                     if let Some(indent) = indent {
@@ -90,6 +105,22 @@ impl LineWrapper {
         })
     }
 
+    /// Wrap a line of text every so many grapheme clusters, as many as fit into the given width
+    /// if they were all as wide as an "m". As the clusters aren't measured, this is much
+    /// cheaper than [`Self::wrap_line`] for pathologically long lines.
+    pub fn chunk_line<'a>(
+        &'a mut self,
+        line: &'a str,
+        wrap_width: Pixels,
+    ) -> impl Iterator<Item = Boundary> + 'a {
+        let columns = ((wrap_width / self.width_for_char('m')).floor() as usize).max(1);
+        line.grapheme_indices(true)
+            .filter(|(_, grapheme)| !grapheme.starts_with('\n'))
+            .step_by(columns)
+            .skip(1)
+            .map(|(ix, _)| Boundary::new(ix, 0))
+    }
+
     #[inline(always)]
     fn width_for_char(&mut self, c: char) -> Pixels {
         if (c as u32) < 128 {
@@ -109,15 +140,32 @@ impl LineWrapper {
         }
     }
 
+    fn width_for_cluster(&mut self, cluster: &str) -> Pixels {
+        if let Some(cached_width) = self.cached_cluster_widths.get(cluster) {
+            *cached_width
+        } else {
+            let width = self.compute_width(cluster);
+            if self.cached_cluster_widths.len() >= Self::MAX_CACHED_CLUSTERS {
+                self.cached_cluster_widths.clear();
+            }
+            self.cached_cluster_widths
+                .insert(cluster.to_string(), width);
+            width
+        }
+    }
+
     fn compute_width_for_char(&self, c: char) -> Pixels {
         let mut buffer = [0; 4];
-        let buffer = c.encode_utf8(&mut buffer);
+        self.compute_width(c.encode_utf8(&mut buffer))
+    }
+
+    fn compute_width(&self, text: &str) -> Pixels {
         self.platform_text_system
             .layout_line(
-                buffer,
+                text,
                 self.font_size,
                 &[FontRun {
-                    len: buffer.len(),
+                    len: text.len(),
                     font_id: self.font_id,
                 }],
             )
@@ -209,12 +257,61 @@ mod tests {
                     Boundary::new(22, 3),
                 ]
             );
+
+            assert_eq!(
+                wrapper
+                    .chunk_line("aaaaaaaaaaaaaaaaaaaaaaaaa", px(72.))
+                    .collect::<Vec<_>>(),
+                &[
+                    Boundary::new(7, 0),
+                    Boundary::new(14, 0),
+                    Boundary::new(21, 0)
+                ],
+            );
+
+            // Lines are never wrapped inside of a grapheme cluster.
+            let accented = "e\u{301}";
+            let text = accented.repeat(12);
+            let boundaries = wrapper.wrap_line(&text, px(72.)).collect::<Vec<_>>();
+            assert!(!boundaries.is_empty());
+            for boundary in boundaries {
+                assert_eq!(boundary.ix % accented.len(), 0, "{boundary:?}");
+            }
+            let text = accented.repeat(25);
+            assert_eq!(
+                wrapper.chunk_line(&text, px(72.)).collect::<Vec<_>>(),
+                &[
+                    Boundary::new(7 * accented.len(), 0),
+                    Boundary::new(14 * accented.len(), 0),
+                    Boundary::new(21 * accented.len(), 0)
+                ],
+            );
         });
     }
 
     // For compatibility with the test macro
     use crate as gpui;
 
+    #[test]
+    fn test_cluster_width_cache_is_bounded() {
+        let dispatcher = TestDispatcher::new(StdRng::seed_from_u64(0));
+        let cx = TestAppContext::new(dispatcher, None);
+
+        cx.update(|cx| {
+            let text_system = cx.text_system().clone();
+            let mut wrapper = LineWrapper::new(
+                text_system.font_id(&font("Courier")).unwrap(),
+                px(16.),
+                text_system.platform_text_system.clone(),
+            );
+            for ix in 0..LineWrapper::MAX_CACHED_CLUSTERS as u32 * 2 {
+                let base = char::from_u32(0x4e00 + ix).unwrap();
+                wrapper.width_for_cluster(&format!("{base}\u{301}"));
+                assert!(wrapper.cached_cluster_widths.len() <= LineWrapper::MAX_CACHED_CLUSTERS);
+            }
+        });
+    }
+
     #[crate::test]
     fn test_wrap_shaped_line(cx: &mut TestAppContext) {
         cx.update(|cx| {
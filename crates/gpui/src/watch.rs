@@ -0,0 +1,272 @@
+//! Combinators for watch-style receivers, such as the ones of [`postage::watch`] channels.
+
+use crate::{BackgroundExecutor, Task};
+use futures::{FutureExt as _, Stream, StreamExt as _};
+use std::{
+    pin::Pin,
+    task::{ready, Context, Poll},
+    time::Duration,
+};
+
+/// A stream that yields the latest value of something whenever it changes, and ends once
+/// the sender is dropped.
+pub trait WatchReceiver: Stream + Unpin + Sized {
+    /// Returns the latest value, if there is one.
+    fn latest(&self) -> Option<Self::Item>;
+
+    /// Returns a receiver of `f` applied to this receiver's values, which only yields when
+    /// the mapped value changes.
+    fn map<U, F>(self, f: F) -> Map<Self, U, F>
+    where
+        U: Clone + PartialEq,
+        F: Fn(&Self::Item) -> U,
+    {
+        Map {
+            receiver: self,
+            f,
+            last: None,
+        }
+    }
+
+    /// Returns a receiver that only yields the values for which `predicate` returns true.
+    fn filter<P>(self, predicate: P) -> Filter<Self, P>
+    where
+        Self::Item: Clone,
+        P: Fn(&Self::Item) -> bool,
+    {
+        Filter {
+            receiver: self,
+            predicate,
+            last: None,
+        }
+    }
+
+    /// Returns a receiver that yields a value once no newer value has arrived for
+    /// `duration`, so that a burst of updates only yields the last one. When the sender is
+    /// dropped, the value that is still waiting is yielded right away.
+    fn debounce(self, duration: Duration, executor: &BackgroundExecutor) -> Debounce<Self> {
+        Debounce {
+            receiver: self,
+            receiver_done: false,
+            pending: None,
+            timer: None,
+            duration,
+            executor: executor.clone(),
+        }
+    }
+}
+
+impl<T: Clone> WatchReceiver for postage::watch::Receiver<T> {
+    fn latest(&self) -> Option<T> {
+        Some(self.borrow().clone())
+    }
+}
+
+/// The receiver returned by [`WatchReceiver::map`].
+pub struct Map<R, U, F> {
+    receiver: R,
+    f: F,
+    last: Option<U>,
+}
+
+// The fields are never pinned.
+impl<R, U, F> Unpin for Map<R, U, F> {}
+
+impl<R, U, F> Stream for Map<R, U, F>
+where
+    R: WatchReceiver,
+    U: Clone + PartialEq,
+    F: Fn(&R::Item) -> U,
+{
+    type Item = U;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<U>> {
+        let this = &mut *self;
+        while let Some(value) = ready!(this.receiver.poll_next_unpin(cx)) {
+            let value = (this.f)(&value);
+            if this.last.as_ref() != Some(&value) {
+                this.last = Some(value.clone());
+                return Poll::Ready(Some(value));
+            }
+        }
+        Poll::Ready(None)
+    }
+}
+
+impl<R, U, F> WatchReceiver for Map<R, U, F>
+where
+    R: WatchReceiver,
+    U: Clone + PartialEq,
+    F: Fn(&R::Item) -> U,
+{
+    fn latest(&self) -> Option<U> {
+        self.receiver.latest().map(|value| (self.f)(&value))
+    }
+}
+
+/// The receiver returned by [`WatchReceiver::filter`].
+pub struct Filter<R: Stream, P> {
+    receiver: R,
+    predicate: P,
+    last: Option<R::Item>,
+}
+
+// The fields are never pinned.
+impl<R: Stream, P> Unpin for Filter<R, P> {}
+
+impl<R, P> Stream for Filter<R, P>
+where
+    R: WatchReceiver,
+    R::Item: Clone,
+    P: Fn(&R::Item) -> bool,
+{
+    type Item = R::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<R::Item>> {
+        let this = &mut *self;
+        while let Some(value) = ready!(this.receiver.poll_next_unpin(cx)) {
+            if (this.predicate)(&value) {
+                this.last = Some(value.clone());
+                return Poll::Ready(Some(value));
+            }
+        }
+        Poll::Ready(None)
+    }
+}
+
+impl<R, P> WatchReceiver for Filter<R, P>
+where
+    R: WatchReceiver,
+    R::Item: Clone,
+    P: Fn(&R::Item) -> bool,
+{
+    /// Returns the latest value that passed the predicate.
+    fn latest(&self) -> Option<R::Item> {
+        self.receiver
+            .latest()
+            .filter(|value| (self.predicate)(value))
+            .or_else(|| self.last.clone())
+    }
+}
+
+/// The receiver returned by [`WatchReceiver::debounce`].
+pub struct Debounce<R: Stream> {
+    receiver: R,
+    receiver_done: bool,
+    pending: Option<R::Item>,
+    timer: Option<Task<()>>,
+    duration: Duration,
+    executor: BackgroundExecutor,
+}
+
+// The fields are never pinned.
+impl<R: Stream> Unpin for Debounce<R> {}
+
+impl<R: WatchReceiver> Stream for Debounce<R> {
+    type Item = R::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<R::Item>> {
+        let this = &mut *self;
+        while !this.receiver_done {
+            match this.receiver.poll_next_unpin(cx) {
+                Poll::Ready(Some(value)) => {
+                    this.pending = Some(value);
+                    this.timer = Some(this.executor.timer(this.duration));
+                }
+                Poll::Ready(None) => this.receiver_done = true,
+                Poll::Pending => break,
+            }
+        }
+
+        if !this.receiver_done {
+            let Some(timer) = this.timer.as_mut() else {
+                return Poll::Pending;
+            };
+            ready!(timer.poll_unpin(cx));
+            this.timer = None;
+        }
+        match this.pending.take() {
+            Some(value) => Poll::Ready(Some(value)),
+            None if this.receiver_done => Poll::Ready(None),
+            None => Poll::Pending,
+        }
+    }
+}
+
+impl<R: WatchReceiver> WatchReceiver for Debounce<R> {
+    fn latest(&self) -> Option<R::Item> {
+        self.receiver.latest()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TestAppContext;
+    use postage::watch;
+
+    // For compatibility with the test macro
+    use crate as gpui;
+
+    #[test]
+    fn test_map_and_filter() {
+        let (mut tx, rx) = watch::channel_with(0_usize);
+        let mut tens = rx.clone().map(|value| value / 10);
+        let mut odd = rx.filter(|value| value % 2 == 1);
+        assert_eq!(tens.next().now_or_never(), Some(Some(0)));
+        assert_eq!(odd.next().now_or_never(), None);
+        assert_eq!(odd.latest(), None);
+
+        // Updates that don't change the mapped value don't notify.
+        *tx.borrow_mut() = 5;
+        assert_eq!(tens.next().now_or_never(), None);
+        assert_eq!(tens.latest(), Some(0));
+        assert_eq!(odd.next().now_or_never(), Some(Some(5)));
+
+        *tx.borrow_mut() = 12;
+        assert_eq!(tens.next().now_or_never(), Some(Some(1)));
+        assert_eq!(odd.next().now_or_never(), None);
+        assert_eq!(odd.latest(), Some(5));
+
+        // Updates that arrive between polls are coalesced.
+        *tx.borrow_mut() = 21;
+        *tx.borrow_mut() = 33;
+        assert_eq!(tens.next().now_or_never(), Some(Some(3)));
+        assert_eq!(tens.next().now_or_never(), None);
+        assert_eq!(odd.next().now_or_never(), Some(Some(33)));
+
+        // Dropping the sender ends the derived receivers.
+        drop(tx);
+        assert_eq!(tens.next().now_or_never(), Some(None));
+        assert_eq!(odd.next().now_or_never(), Some(None));
+    }
+
+    #[crate::test]
+    fn test_debounce(cx: &mut TestAppContext) {
+        let executor = cx.executor();
+        let delay = Duration::from_millis(100);
+        let (mut tx, rx) = watch::channel_with(0_usize);
+        let mut debounced = rx.map(|value| value / 2).debounce(delay, &executor);
+        assert_eq!(debounced.next().now_or_never(), None);
+        executor.advance_clock(delay);
+        assert_eq!(debounced.next().now_or_never(), Some(Some(0)));
+
+        // A burst of updates only yields the last one, once the updates have settled.
+        for value in 1..=6 {
+            *tx.borrow_mut() = value;
+            assert_eq!(debounced.next().now_or_never(), None);
+            executor.advance_clock(delay / 2);
+        }
+        assert_eq!(debounced.next().now_or_never(), None);
+        executor.advance_clock(delay / 2);
+        assert_eq!(debounced.next().now_or_never(), Some(Some(3)));
+        assert_eq!(debounced.latest(), Some(3));
+
+        // When the sender is dropped, the waiting value is yielded and the receiver ends.
+        *tx.borrow_mut() = 8;
+        assert_eq!(debounced.next().now_or_never(), None);
+        drop(tx);
+        assert_eq!(debounced.next().now_or_never(), Some(Some(4)));
+        assert_eq!(debounced.next().now_or_never(), Some(None));
+    }
+}
@@ -1,4 +1,4 @@
-use gpui::{actions, impl_actions};
+use gpui::{actions, impl_actions, Action};
 use gpui_macros::register_action;
 use serde_derive::Deserialize;
 
@@ -48,3 +48,34 @@ fn test_action_macros() {
         }
     }
 }
+
+#[test]
+fn test_action_display_names() {
+    actions!(
+        test,
+        [
+            UnnamedAction,
+            #[display_name = "test: a named action"]
+            NamedAction,
+        ]
+    );
+
+    #[derive(PartialEq, Clone, Deserialize)]
+    struct NamedActionWithData {}
+
+    impl_actions!(
+        test,
+        [
+            #[display_name = "test: another named action"]
+            NamedActionWithData
+        ]
+    );
+
+    assert_eq!(UnnamedAction.display_name(), None);
+    assert_eq!(NamedAction.name(), "test::NamedAction");
+    assert_eq!(NamedAction.display_name(), Some("test: a named action"));
+    assert_eq!(
+        NamedActionWithData {}.display_name(),
+        Some("test: another named action")
+    );
+}
@@ -52,8 +52,10 @@ pub fn style_helpers(input: TokenStream) -> TokenStream {
 /// Using the same `StdRng` for behaviour in your test will allow you to exercise a wide
 /// variety of scenarios and interleavings just by changing the seed.
 ///
-/// #[gpui::test] also takes three different arguments:
+/// #[gpui::test] also takes four different arguments:
 /// - `#[gpui::test(iterations=10)]` will run the test ten times with a different initial SEED.
+/// - `#[gpui::test(seed=42)]` will start from the given SEED instead of 0. The `SEED` environment
+///    variable still takes precedence, so that the failing seed printed by a test can be rerun.
 /// - `#[gpui::test(retries=3)]` will run the test up to four times if it fails to try and make it pass.
 /// - `#[gpui::test(on_failure="crate::test::report_failure")]` will call the specified function after the
 ///    tests fail so that you can write out more detail about the failure.
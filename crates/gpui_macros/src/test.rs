@@ -11,6 +11,7 @@ pub fn test(args: TokenStream, function: TokenStream) -> TokenStream {
     let args = syn::parse_macro_input!(args as AttributeArgs);
     let mut max_retries = 0;
     let mut num_iterations = 1;
+    let mut starting_seed = quote!(None);
     let mut on_failure_fn_name = quote!(None);
 
     for arg in args {
@@ -21,6 +22,10 @@ pub fn test(args: TokenStream, function: TokenStream) -> TokenStream {
                     match key_name.as_deref() {
                         Some("retries") => max_retries = parse_int(&meta.lit)?,
                         Some("iterations") => num_iterations = parse_int(&meta.lit)?,
+                        Some("seed") => {
+                            let seed = parse_int(&meta.lit)?;
+                            starting_seed = quote!(Some(#seed as u64));
+                        }
                         Some("on_failure") => {
                             if let Lit::Str(name) = meta.lit {
                                 let mut path = syn::Path {
@@ -134,6 +139,7 @@ pub fn test(args: TokenStream, function: TokenStream) -> TokenStream {
 
                 gpui::run_test(
                     #num_iterations as u64,
+                    #starting_seed,
                     #max_retries,
                     &mut |dispatcher, _seed| {
                         let executor = gpui::BackgroundExecutor::new(std::sync::Arc::new(dispatcher.clone()));
@@ -217,6 +223,7 @@ pub fn test(args: TokenStream, function: TokenStream) -> TokenStream {
 
                 gpui::run_test(
                     #num_iterations as u64,
+                    #starting_seed,
                     #max_retries,
                     &mut |dispatcher, _seed| {
                         #cx_vars
@@ -0,0 +1,92 @@
+use editor::Editor;
+use gpui::{div, IntoElement, ParentElement, Render, Subscription, View, ViewContext, WeakView};
+use language::{language_settings::IndentSettingsSource, IndentResolution, IndentSource};
+use ui::{Button, ButtonCommon, Clickable, FluentBuilder, LabelSize, Tooltip};
+use workspace::{item::ItemHandle, StatusItemView, Workspace};
+
+use crate::{indentation_label, IndentationSelector};
+
+pub struct ActiveBufferIndentation {
+    active_indentation: Option<IndentResolution>,
+    workspace: WeakView<Workspace>,
+    _observe_active_editor: Option<Subscription>,
+}
+
+impl ActiveBufferIndentation {
+    pub fn new(workspace: &Workspace) -> Self {
+        Self {
+            active_indentation: None,
+            workspace: workspace.weak_handle(),
+            _observe_active_editor: None,
+        }
+    }
+
+    fn update_indentation(&mut self, editor: View<Editor>, cx: &mut ViewContext<Self>) {
+        self.active_indentation = None;
+
+        let editor = editor.read(cx);
+        if let Some((_, buffer, _)) = editor.active_excerpt(cx) {
+            self.active_indentation = Some(buffer.read(cx).indent_resolution(cx));
+        }
+
+        cx.notify();
+    }
+}
+
+impl Render for ActiveBufferIndentation {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        div().when_some(self.active_indentation, |el, resolution| {
+            el.child(
+                Button::new("change-indentation", indentation_label(resolution.indent))
+                    .label_size(LabelSize::Small)
+                    .on_click(cx.listener(|this, _, cx| {
+                        if let Some(workspace) = this.workspace.upgrade() {
+                            workspace.update(cx, |workspace, cx| {
+                                IndentationSelector::toggle(workspace, cx)
+                            });
+                        }
+                    }))
+                    .tooltip(move |cx| {
+                        let meta = match resolution.source {
+                            IndentSource::Override => "Picked for this file",
+                            IndentSource::Detected => "Detected from the file's contents",
+                            IndentSource::Settings(IndentSettingsSource::Editorconfig) => {
+                                "From .editorconfig"
+                            }
+                            IndentSource::Settings(IndentSettingsSource::Project) => {
+                                "From the project settings"
+                            }
+                            IndentSource::Settings(IndentSettingsSource::UserLanguage) => {
+                                "From your settings for this language"
+                            }
+                            IndentSource::Settings(IndentSettingsSource::User) => {
+                                "From your settings"
+                            }
+                            IndentSource::Settings(IndentSettingsSource::Default) => {
+                                "From the default settings"
+                            }
+                        };
+                        Tooltip::with_meta("Change Indentation…", None, meta, cx)
+                    }),
+            )
+        })
+    }
+}
+
+impl StatusItemView for ActiveBufferIndentation {
+    fn set_active_pane_item(
+        &mut self,
+        active_pane_item: Option<&dyn ItemHandle>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        if let Some(editor) = active_pane_item.and_then(|item| item.act_as::<Editor>(cx)) {
+            self._observe_active_editor = Some(cx.observe(&editor, Self::update_indentation));
+            self.update_indentation(editor, cx);
+        } else {
+            self.active_indentation = None;
+            self._observe_active_editor = None;
+        }
+
+        cx.notify();
+    }
+}
@@ -0,0 +1,287 @@
+mod active_buffer_indentation;
+
+pub use active_buffer_indentation::ActiveBufferIndentation;
+use editor::Editor;
+use fuzzy::{match_strings, StringMatch, StringMatchCandidate};
+use gpui::{
+    actions, AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView, Model,
+    ParentElement, Render, Styled, View, ViewContext, VisualContext, WeakView,
+};
+use language::{Buffer, IndentKind, IndentSize};
+use picker::{Picker, PickerDelegate};
+use std::sync::Arc;
+use ui::{prelude::*, HighlightedLabel, ListItem, ListItemSpacing};
+use util::ResultExt;
+use workspace::{ModalView, Workspace};
+
+actions!(indentation_selector, [ChangeIndentation]);
+
+/// The indentations that can be picked.
+const INDENTATIONS: [IndentSize; 4] = [
+    IndentSize {
+        len: 1,
+        kind: IndentKind::Tab,
+    },
+    IndentSize {
+        len: 2,
+        kind: IndentKind::Space,
+    },
+    IndentSize {
+        len: 4,
+        kind: IndentKind::Space,
+    },
+    IndentSize {
+        len: 8,
+        kind: IndentKind::Space,
+    },
+];
+
+pub fn init(cx: &mut AppContext) {
+    cx.observe_new_views(IndentationSelector::register).detach();
+}
+
+/// The short name of an indentation, as shown in the status bar.
+pub fn indentation_label(indent: IndentSize) -> String {
+    match indent.kind {
+        IndentKind::Tab => "Tabs".to_string(),
+        IndentKind::Space => format!("Spaces: {}", indent.len),
+    }
+}
+
+/// What picking an entry of the selector does to the buffer. None of them are saved.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum IndentationChange {
+    /// Edits the buffer with the indentation from now on.
+    Set(IndentSize),
+    /// Also rewrites the existing indentation of the buffer with it.
+    Convert(IndentSize),
+    /// Goes back to the detected indentation, or to the settings when nothing was detected.
+    Detect,
+}
+
+impl IndentationChange {
+    fn all() -> impl Iterator<Item = Self> {
+        INDENTATIONS
+            .into_iter()
+            .map(Self::Set)
+            .chain(INDENTATIONS.into_iter().map(Self::Convert))
+            .chain([Self::Detect])
+    }
+
+    fn label(&self) -> String {
+        match self {
+            Self::Set(indent) => indentation_label(*indent),
+            Self::Convert(indent) => format!("Convert to {}", indentation_label(*indent)),
+            Self::Detect => "Detect from File".to_string(),
+        }
+    }
+}
+
+pub struct IndentationSelector {
+    picker: View<Picker<IndentationSelectorDelegate>>,
+}
+
+impl IndentationSelector {
+    fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+        workspace.register_action(move |workspace, _: &ChangeIndentation, cx| {
+            Self::toggle(workspace, cx);
+        });
+    }
+
+    fn toggle(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) -> Option<()> {
+        let editor = workspace.active_item(cx)?.act_as::<Editor>(cx)?;
+        let (_, buffer, _) = editor.read(cx).active_excerpt(cx)?;
+
+        let editor = editor.downgrade();
+        workspace.toggle_modal(cx, move |cx| IndentationSelector::new(editor, buffer, cx));
+        Some(())
+    }
+
+    fn new(editor: WeakView<Editor>, buffer: Model<Buffer>, cx: &mut ViewContext<Self>) -> Self {
+        let delegate = IndentationSelectorDelegate::new(cx.view().downgrade(), editor, buffer);
+        let picker = cx.new_view(|cx| Picker::uniform_list(delegate, cx));
+        Self { picker }
+    }
+}
+
+impl Render for IndentationSelector {
+    fn render(&mut self, _cx: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex().w(rems(34.)).child(self.picker.clone())
+    }
+}
+
+impl FocusableView for IndentationSelector {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl EventEmitter<DismissEvent> for IndentationSelector {}
+impl ModalView for IndentationSelector {}
+
+pub struct IndentationSelectorDelegate {
+    indentation_selector: WeakView<IndentationSelector>,
+    editor: WeakView<Editor>,
+    buffer: Model<Buffer>,
+    changes: Vec<IndentationChange>,
+    candidates: Vec<StringMatchCandidate>,
+    matches: Vec<StringMatch>,
+    selected_index: usize,
+}
+
+impl IndentationSelectorDelegate {
+    fn new(
+        indentation_selector: WeakView<IndentationSelector>,
+        editor: WeakView<Editor>,
+        buffer: Model<Buffer>,
+    ) -> Self {
+        let changes = IndentationChange::all().collect::<Vec<_>>();
+        let candidates = changes
+            .iter()
+            .enumerate()
+            .map(|(candidate_id, change)| StringMatchCandidate::new(candidate_id, change.label()))
+            .collect::<Vec<_>>();
+
+        Self {
+            indentation_selector,
+            editor,
+            buffer,
+            changes,
+            candidates,
+            matches: vec![],
+            selected_index: 0,
+        }
+    }
+}
+
+impl PickerDelegate for IndentationSelectorDelegate {
+    type ListItem = ListItem;
+
+    fn placeholder_text(&self, _cx: &mut WindowContext) -> Arc<str> {
+        "Change the indentation of this file...".into()
+    }
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn confirm(&mut self, _: bool, cx: &mut ViewContext<Picker<Self>>) {
+        if let Some(mat) = self.matches.get(self.selected_index) {
+            match self.changes[mat.candidate_id] {
+                IndentationChange::Set(indent) => {
+                    self.buffer.update(cx, |buffer, cx| {
+                        buffer.set_indent_override(Some(indent), cx)
+                    });
+                }
+                IndentationChange::Convert(indent) => {
+                    let current_indent = self.buffer.read(cx).indent_resolution(cx).indent;
+                    self.editor
+                        .update(cx, |editor, cx| editor.reindent(current_indent, indent, cx))
+                        .log_err();
+                    self.buffer.update(cx, |buffer, cx| {
+                        buffer.set_indent_override(Some(indent), cx)
+                    });
+                }
+                IndentationChange::Detect => {
+                    self.buffer.update(cx, |buffer, cx| {
+                        buffer.detect_indent(cx);
+                        buffer.set_indent_override(None, cx);
+                    });
+                }
+            }
+        }
+        self.dismissed(cx);
+    }
+
+    fn dismissed(&mut self, cx: &mut ViewContext<Picker<Self>>) {
+        self.indentation_selector
+            .update(cx, |_, cx| cx.emit(DismissEvent))
+            .log_err();
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(&mut self, ix: usize, _: &mut ViewContext<Picker<Self>>) {
+        self.selected_index = ix;
+    }
+
+    fn update_matches(
+        &mut self,
+        query: String,
+        cx: &mut ViewContext<Picker<Self>>,
+    ) -> gpui::Task<()> {
+        let background = cx.background_executor().clone();
+        let candidates = self.candidates.clone();
+        cx.spawn(|this, mut cx| async move {
+            let matches = if query.is_empty() {
+                candidates
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, candidate)| StringMatch {
+                        candidate_id: index,
+                        string: candidate.string,
+                        positions: Vec::new(),
+                        score: 0.0,
+                    })
+                    .collect()
+            } else {
+                match_strings(
+                    &candidates,
+                    &query,
+                    false,
+                    100,
+                    &Default::default(),
+                    background,
+                )
+                .await
+            };
+
+            this.update(&mut cx, |this, cx| {
+                let delegate = &mut this.delegate;
+                delegate.matches = matches;
+                delegate.selected_index = delegate
+                    .selected_index
+                    .min(delegate.matches.len().saturating_sub(1));
+                cx.notify();
+            })
+            .log_err();
+        })
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        cx: &mut ViewContext<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let mat = &self.matches[ix];
+        let buffer = self.buffer.read(cx);
+        let mut label = mat.string.clone();
+        match self.changes[mat.candidate_id] {
+            IndentationChange::Set(indent) => {
+                if buffer.indent_override() == Some(indent) {
+                    label.push_str(" (current)");
+                }
+            }
+            IndentationChange::Convert(_) => {}
+            IndentationChange::Detect => {
+                if let Some(detected_indent) = buffer.detected_indent() {
+                    label.push_str(&format!(" ({})", indentation_label(detected_indent)));
+                }
+                if buffer.indent_override().is_none() {
+                    label.push_str(" (current)");
+                }
+            }
+        }
+
+        Some(
+            ListItem::new(ix)
+                .inset(true)
+                .spacing(ListItemSpacing::Sparse)
+                .selected(selected)
+                .child(HighlightedLabel::new(label, mat.positions.clone())),
+        )
+    }
+}
@@ -0,0 +1,309 @@
+use std::{ops::Range, sync::Arc};
+
+use fuzzy::{match_strings, StringMatch, StringMatchCandidate};
+use gpui::{
+    AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView, KeyBindingStatus,
+    Keystroke, ParentElement, Render, ResolvedKeyBinding, Styled, View, ViewContext, VisualContext,
+    WeakView,
+};
+use picker::{Picker, PickerDelegate};
+use ui::{prelude::*, HighlightedLabel, ListItem, ListItemSpacing};
+use util::ResultExt;
+use workspace::{ModalView, Workspace};
+use zed_actions::ShowKeyBindings;
+
+pub fn init(cx: &mut AppContext) {
+    cx.observe_new_views(KeyBindingsView::register).detach();
+}
+
+/// Lists every binding of the keymap, resolved within the context of the element that was
+/// focused when the view got opened, which is the same resolution that dispatching a
+/// keystroke to that element goes through.
+pub struct KeyBindingsView {
+    picker: View<Picker<KeyBindingsDelegate>>,
+}
+
+impl KeyBindingsView {
+    fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+        workspace.register_action(|workspace, _: &ShowKeyBindings, cx| {
+            let bindings = cx.resolved_bindings();
+            workspace.toggle_modal(cx, move |cx| KeyBindingsView::new(bindings, cx));
+        });
+    }
+
+    fn new(bindings: Vec<ResolvedKeyBinding>, cx: &mut ViewContext<Self>) -> Self {
+        let delegate = KeyBindingsDelegate::new(cx.view().downgrade(), bindings);
+        let picker = cx.new_view(|cx| Picker::uniform_list(delegate, cx));
+        Self { picker }
+    }
+}
+
+impl Render for KeyBindingsView {
+    fn render(&mut self, _cx: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex().w(rems(48.)).child(self.picker.clone())
+    }
+}
+
+impl FocusableView for KeyBindingsView {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl EventEmitter<DismissEvent> for KeyBindingsView {}
+impl ModalView for KeyBindingsView {}
+
+/// A binding, along with the parts of its candidate string that get shown separately.
+struct Entry {
+    binding: ResolvedKeyBinding,
+    keystrokes: Range<usize>,
+    action: Range<usize>,
+    context: Range<usize>,
+}
+
+pub struct KeyBindingsDelegate {
+    view: WeakView<KeyBindingsView>,
+    entries: Vec<Entry>,
+    candidates: Vec<StringMatchCandidate>,
+    matches: Vec<StringMatch>,
+    selected_index: usize,
+}
+
+impl KeyBindingsDelegate {
+    fn new(view: WeakView<KeyBindingsView>, bindings: Vec<ResolvedKeyBinding>) -> Self {
+        let mut entries = Vec::with_capacity(bindings.len());
+        let mut candidates = Vec::with_capacity(bindings.len());
+        for (id, binding) in bindings.into_iter().enumerate() {
+            // The keystrokes, action and context are matched against as a whole, so that a
+            // query can mention any of them.
+            let mut string = keystrokes_text(binding.binding.keystrokes());
+            let keystrokes = 0..string.len();
+            string.push(' ');
+            let action_start = string.len();
+            string.push_str(binding.binding.action().name());
+            let action = action_start..string.len();
+            string.push(' ');
+            let context_start = string.len();
+            if let Some(predicate) = binding.binding.context_predicate() {
+                string.push_str(&predicate.to_string());
+            }
+            let context = context_start..string.len();
+
+            candidates.push(StringMatchCandidate::new(id, string));
+            entries.push(Entry {
+                binding,
+                keystrokes,
+                action,
+                context,
+            });
+        }
+
+        Self {
+            view,
+            entries,
+            candidates,
+            matches: Vec::new(),
+            selected_index: 0,
+        }
+    }
+
+    fn source_label(&self, binding: &ResolvedKeyBinding) -> String {
+        let source = |binding: &ResolvedKeyBinding| {
+            binding
+                .binding
+                .source()
+                .map_or_else(|| "unknown source".to_string(), ToString::to_string)
+        };
+        match binding.status {
+            KeyBindingStatus::Active => source(binding),
+            KeyBindingStatus::Pending => {
+                format!("{}, waits for a longer binding", source(binding))
+            }
+            KeyBindingStatus::Shadowed(ix) => format!(
+                "{}, shadowed by {}",
+                source(binding),
+                source(&self.entries[ix].binding)
+            ),
+            KeyBindingStatus::Inactive => format!("{}, not enabled here", source(binding)),
+        }
+    }
+}
+
+impl PickerDelegate for KeyBindingsDelegate {
+    type ListItem = ListItem;
+
+    fn placeholder_text(&self, _cx: &mut WindowContext) -> Arc<str> {
+        "Search key bindings...".into()
+    }
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn confirm(&mut self, _: bool, cx: &mut ViewContext<Picker<Self>>) {
+        self.dismissed(cx);
+    }
+
+    fn dismissed(&mut self, cx: &mut ViewContext<Picker<Self>>) {
+        self.view
+            .update(cx, |_, cx| cx.emit(DismissEvent))
+            .log_err();
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(&mut self, ix: usize, _: &mut ViewContext<Picker<Self>>) {
+        self.selected_index = ix;
+    }
+
+    fn update_matches(
+        &mut self,
+        query: String,
+        cx: &mut ViewContext<Picker<Self>>,
+    ) -> gpui::Task<()> {
+        let background = cx.background_executor().clone();
+        let candidates = self.candidates.clone();
+        cx.spawn(|this, mut cx| async move {
+            let matches = if query.is_empty() {
+                candidates
+                    .into_iter()
+                    .map(|candidate| StringMatch {
+                        candidate_id: candidate.id,
+                        string: candidate.string,
+                        positions: Vec::new(),
+                        score: 0.0,
+                    })
+                    .collect()
+            } else {
+                match_strings(
+                    &candidates,
+                    &query,
+                    false,
+                    candidates.len(),
+                    &Default::default(),
+                    background,
+                )
+                .await
+            };
+
+            this.update(&mut cx, |this, cx| {
+                let delegate = &mut this.delegate;
+                delegate.matches = matches;
+                delegate.selected_index = delegate
+                    .selected_index
+                    .min(delegate.matches.len().saturating_sub(1));
+                cx.notify();
+            })
+            .log_err();
+        })
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _cx: &mut ViewContext<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let mat = self.matches.get(ix)?;
+        let entry = self.entries.get(mat.candidate_id)?;
+        let segment = |range: &Range<usize>| {
+            HighlightedLabel::new(
+                mat.string[range.clone()].to_string(),
+                mat.positions
+                    .iter()
+                    .filter(|&&position| range.contains(&position))
+                    .map(|&position| position - range.start)
+                    .collect(),
+            )
+        };
+        let color = if entry.binding.status == KeyBindingStatus::Active {
+            Color::Default
+        } else {
+            Color::Muted
+        };
+
+        Some(
+            ListItem::new(ix)
+                .inset(true)
+                .spacing(ListItemSpacing::Sparse)
+                .selected(selected)
+                .child(
+                    h_flex()
+                        .w_full()
+                        .gap_2()
+                        .justify_between()
+                        .child(
+                            h_flex()
+                                .gap_2()
+                                .child(segment(&entry.keystrokes).color(color))
+                                .child(segment(&entry.action).color(color))
+                                .child(segment(&entry.context).color(Color::Muted)),
+                        )
+                        .child(
+                            Label::new(self.source_label(&entry.binding))
+                                .size(LabelSize::Small)
+                                .color(Color::Muted),
+                        ),
+                ),
+        )
+    }
+}
+
+/// Formats keystrokes the way that keymap files spell them out, so that they can be searched
+/// for with the same text.
+fn keystrokes_text(keystrokes: &[Keystroke]) -> String {
+    let mut text = String::new();
+    for keystroke in keystrokes {
+        if !text.is_empty() {
+            text.push(' ');
+        }
+        let modifiers = &keystroke.modifiers;
+        for (enabled, name) in [
+            (modifiers.control, "ctrl"),
+            (modifiers.alt, "alt"),
+            (modifiers.shift, "shift"),
+            (modifiers.function, "fn"),
+            (
+                modifiers.platform,
+                if cfg!(target_os = "macos") {
+                    "cmd"
+                } else {
+                    "super"
+                },
+            ),
+        ] {
+            if enabled {
+                text.push_str(name);
+                text.push('-');
+            }
+        }
+        text.push_str(&keystroke.key);
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keystrokes_text() {
+        let keystrokes = ["ctrl-k", "shift-alt-left", "-", "ctrl--"]
+            .into_iter()
+            .map(|keystroke| Keystroke::parse(keystroke).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            keystrokes_text(&keystrokes),
+            "ctrl-k alt-shift-left - ctrl--"
+        );
+        for keystrokes in ["ctrl-k ctrl-s", "alt-shift-left", "ctrl--"] {
+            let parsed = keystrokes
+                .split_whitespace()
+                .map(|keystroke| Keystroke::parse(keystroke).unwrap())
+                .collect::<Vec<_>>();
+            assert_eq!(keystrokes_text(&parsed), keystrokes);
+        }
+    }
+}
@@ -6,23 +6,30 @@ pub use crate::{
 };
 use crate::{
     diagnostic_set::{DiagnosticEntry, DiagnosticGroup},
-    language_settings::{language_settings, IndentGuideSettings, LanguageSettings},
+    indent_detection::detect_indent,
+    language_settings::{
+        indent_settings_source, language_settings, IndentGuideSettings, IndentSettingsSource,
+        LanguageSettings, TrailingWhitespaceLines,
+    },
     markdown::parse_markdown,
+    marks::{MarkId, MarkName, Marks},
     outline::OutlineItem,
     syntax_map::{
         SyntaxLayer, SyntaxMap, SyntaxMapCapture, SyntaxMapCaptures, SyntaxMapMatches,
         SyntaxSnapshot, ToTreeSitterPoint,
     },
     task_context::RunnableRange,
-    LanguageScope, Outline, RunnableCapture, RunnableTag,
+    BracketPairConfig, LanguageScope, Outline, RunnableCapture, RunnableTag,
 };
 use anyhow::{anyhow, Context, Result};
 pub use clock::ReplicaId;
+use collections::HashMap;
 use futures::channel::oneshot;
 use gpui::{
     AnyElement, AppContext, EventEmitter, HighlightStyle, ModelContext, Task, TaskLabel,
     WindowContext,
 };
+use itertools::Either;
 use lazy_static::lazy_static;
 use lsp::LanguageServerId;
 use parking_lot::Mutex;
@@ -57,7 +64,7 @@ pub use text::{
 use theme::SyntaxTheme;
 #[cfg(any(test, feature = "test-support"))]
 use util::RandomCharIter;
-use util::RangeExt;
+use util::{RangeExt, ResultExt};
 
 #[cfg(any(test, feature = "test-support"))]
 pub use {tree_sitter_rust, tree_sitter_typescript};
@@ -94,6 +101,16 @@ pub struct Buffer {
     /// The version vector when this buffer was last loaded from
     /// or saved to disk.
     saved_version: clock::Global,
+    /// The line ending the buffer had when it was last loaded or saved to disk.
+    saved_line_ending: LineEnding,
+    /// The encoding of the buffer's file on disk.
+    encoding: Encoding,
+    /// The encoding the buffer had when it was last loaded or saved to disk.
+    saved_encoding: Encoding,
+    /// The indentation the buffer's file was found to use when it was loaded.
+    detected_indent: Option<IndentSize>,
+    /// The indentation the user chose for this buffer, regardless of settings.
+    indent_override: Option<IndentSize>,
     transaction_depth: usize,
     was_dirty_before_starting_transaction: Option<bool>,
     reload_task: Option<Task<Result<()>>>,
@@ -113,10 +130,15 @@ pub struct Buffer {
     git_diff_update_count: usize,
     completion_triggers: Vec<String>,
     completion_triggers_timestamp: clock::Lamport,
+    line_ending_timestamp: clock::Lamport,
+    /// The line ending conversions on the undo and redo stacks, keyed by the empty
+    /// transaction that was pushed for each of them.
+    line_ending_changes: HashMap<TransactionId, LineEndingChange>,
     deferred_ops: OperationQueue<Operation>,
     capability: Capability,
     has_conflict: bool,
     diff_base_version: usize,
+    marks: Marks,
 }
 
 /// An immutable, cheaply cloneable representation of a fixed
@@ -134,6 +156,8 @@ pub struct BufferSnapshot {
     selections_update_count: usize,
     language: Option<Arc<Language>>,
     parse_count: usize,
+    detected_indent: Option<IndentSize>,
+    indent_override: Option<IndentSize>,
 }
 
 /// The kind and amount of indentation in a particular line. For now,
@@ -156,6 +180,75 @@ pub enum IndentKind {
     Tab,
 }
 
+/// The indentation that a buffer is edited with, and where it comes from.
+///
+/// The first of these sources that sets the indentation wins:
+/// 1. the indentation picked for the buffer with "Change Indentation…", which isn't saved,
+/// 2. the indentation detected from the buffer's contents, if `detect_indentation` is on,
+/// 3. the closest `.editorconfig` file,
+/// 4. the closest `.zed/settings.json` file,
+/// 5. the user's settings for the buffer's language,
+/// 6. the user's settings for all languages,
+/// 7. the default settings.
+///
+/// An `.editorconfig` file that sets the indentation also turns `detect_indentation` off.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IndentResolution {
+    /// The unit of indentation.
+    pub indent: IndentSize,
+    /// The source that set it.
+    pub source: IndentSource,
+}
+
+/// Where the indentation of a buffer comes from, see [`IndentResolution`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IndentSource {
+    /// It was picked for this buffer with [`Buffer::set_indent_override`].
+    Override,
+    /// It was detected from the buffer's contents.
+    Detected,
+    /// It comes from the settings.
+    Settings(IndentSettingsSource),
+}
+
+fn resolve_indent(
+    indent_override: Option<IndentSize>,
+    detected_indent: Option<IndentSize>,
+    language: Option<&Arc<Language>>,
+    file: Option<&Arc<dyn File>>,
+    cx: &AppContext,
+) -> IndentResolution {
+    let (indent, source) =
+        resolve_indent_size(indent_override, detected_indent, language, file, cx);
+    IndentResolution {
+        indent,
+        source: source
+            .unwrap_or_else(|| IndentSource::Settings(indent_settings_source(language, file, cx))),
+    }
+}
+
+/// Picks the indentation like [`resolve_indent`], but without finding out which settings
+/// file it comes from, as that takes parsing them. The source is `None` when it comes from
+/// the settings.
+fn resolve_indent_size(
+    indent_override: Option<IndentSize>,
+    detected_indent: Option<IndentSize>,
+    language: Option<&Arc<Language>>,
+    file: Option<&Arc<dyn File>>,
+    cx: &AppContext,
+) -> (IndentSize, Option<IndentSource>) {
+    let settings = language_settings(language, file, cx);
+    if let Some(indent) = indent_override {
+        (indent, Some(IndentSource::Override))
+    } else if let Some(indent) = detected_indent.filter(|_| settings.detect_indentation) {
+        (indent, Some(IndentSource::Detected))
+    } else if settings.hard_tabs {
+        (IndentSize::tab(), None)
+    } else {
+        (IndentSize::spaces(settings.tab_size.get()), None)
+    }
+}
+
 /// The shape of a selection cursor.
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
 pub enum CursorShape {
@@ -292,6 +385,16 @@ pub enum Operation {
         /// The buffer's lamport timestamp.
         lamport_timestamp: clock::Lamport,
     },
+
+    /// An update to the line ending the buffer is saved with.
+    UpdateLineEnding {
+        /// The new line ending.
+        line_ending: LineEnding,
+        /// Whether the buffer's text on disk still uses more than one line ending.
+        has_mixed_line_endings: bool,
+        /// The buffer's lamport timestamp.
+        lamport_timestamp: clock::Lamport,
+    },
 }
 
 /// An event that occurs in a buffer.
@@ -324,6 +427,8 @@ pub enum Event {
     CapabilityChanged,
     /// The buffer was explicitly requested to close.
     Closed,
+    /// The buffer's marks were set, removed, or dropped along with their lines.
+    MarksChanged,
 }
 
 /// The file associated with a buffer.
@@ -382,6 +487,9 @@ pub trait LocalFile: File {
     /// Loads the file's contents from disk.
     fn load(&self, cx: &AppContext) -> Task<Result<String>>;
 
+    /// Loads the file's raw contents from disk, without decoding them.
+    fn load_bytes(&self, cx: &AppContext) -> Task<Result<Vec<u8>>>;
+
     /// Returns true if the file should not be shared with collaborators.
     fn is_private(&self, _: &AppContext) -> bool {
         false
@@ -496,9 +604,18 @@ impl fmt::Debug for ChunkRenderer {
 pub struct Diff {
     pub(crate) base_version: clock::Global,
     line_ending: LineEnding,
+    has_mixed_line_endings: bool,
     edits: Vec<(Range<usize>, Arc<str>)>,
 }
 
+/// A change to a buffer's line ending, along with what it replaced so it can be undone.
+#[derive(Clone, Copy, Debug)]
+struct LineEndingChange {
+    old_line_ending: LineEnding,
+    old_has_mixed_line_endings: bool,
+    new_line_ending: LineEnding,
+}
+
 #[derive(Clone, Copy)]
 pub(crate) struct DiagnosticEndpoint {
     offset: usize,
@@ -653,6 +770,12 @@ impl Buffer {
             },
         ));
 
+        operations.push(proto::serialize_operation(&Operation::UpdateLineEnding {
+            line_ending: self.line_ending(),
+            has_mixed_line_endings: self.has_mixed_line_endings(),
+            lamport_timestamp: self.line_ending_timestamp,
+        }));
+
         let text_operations = self.text.operations().clone();
         cx.background_executor().spawn(async move {
             let since = since.unwrap_or_default();
@@ -673,6 +796,13 @@ impl Buffer {
         self
     }
 
+    /// Assign the encoding the buffer's file was decoded from, returning the buffer.
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self.saved_encoding = encoding;
+        self
+    }
+
     /// Returns the [Capability] of this buffer.
     pub fn capability(&self) -> Capability {
         self.capability
@@ -691,10 +821,18 @@ impl Buffer {
         capability: Capability,
     ) -> Self {
         let saved_mtime = file.as_ref().and_then(|file| file.mtime());
+        // Only the contents of files are worth detecting the indentation of, other
+        // buffers usually start out empty.
+        let detected_indent = file.as_ref().and_then(|_| detect_indent(&buffer));
 
         Self {
             saved_mtime,
             saved_version: buffer.version(),
+            saved_line_ending: buffer.line_ending(),
+            encoding: Encoding::default(),
+            saved_encoding: Encoding::default(),
+            detected_indent,
+            indent_override: None,
             reload_task: None,
             transaction_depth: 0,
             was_dirty_before_starting_transaction: None,
@@ -706,6 +844,7 @@ impl Buffer {
                 })
                 .map(Rope::from),
             diff_base_version: 0,
+            marks: Marks::default(),
             git_diff: git::diff::BufferDiff::new(),
             file,
             capability,
@@ -725,6 +864,8 @@ impl Buffer {
             git_diff_update_count: 0,
             completion_triggers: Default::default(),
             completion_triggers_timestamp: Default::default(),
+            line_ending_timestamp: Default::default(),
+            line_ending_changes: Default::default(),
             deferred_ops: OperationQueue::new(),
             has_conflict: false,
         }
@@ -751,6 +892,8 @@ impl Buffer {
             language: self.language.clone(),
             parse_count: self.parse_count,
             selections_update_count: self.selections_update_count,
+            detected_indent: self.detected_indent,
+            indent_override: self.indent_override,
         }
     }
 
@@ -815,6 +958,8 @@ impl Buffer {
         cx: &mut ModelContext<Self>,
     ) {
         self.saved_version = version;
+        self.saved_line_ending = self.line_ending();
+        self.saved_encoding = self.encoding;
         self.has_conflict = false;
         self.saved_mtime = mtime;
         cx.emit(Event::Saved);
@@ -829,15 +974,19 @@ impl Buffer {
         let (tx, rx) = futures::channel::oneshot::channel();
         let prev_version = self.text.version();
         self.reload_task = Some(cx.spawn(|this, mut cx| async move {
-            let Some((new_mtime, new_text)) = this.update(&mut cx, |this, cx| {
+            let Some((new_mtime, new_bytes, encoding)) = this.update(&mut cx, |this, cx| {
                 let file = this.file.as_ref()?.as_local()?;
-                Some((file.mtime(), file.load(cx)))
+                Some((file.mtime(), file.load_bytes(cx), this.encoding))
             })?
             else {
                 return Ok(());
             };
 
-            let new_text = new_text.await?;
+            let new_bytes = new_bytes.await?;
+            let new_text = cx
+                .background_executor()
+                .spawn(async move { encoding.decode(&new_bytes) })
+                .await;
             let diff = this
                 .update(&mut cx, |this, cx| this.diff(new_text.clone(), cx))?
                 .await;
@@ -877,7 +1026,9 @@ impl Buffer {
     ) {
         self.saved_version = version;
         self.text.set_line_ending(line_ending);
+        self.saved_line_ending = line_ending;
         self.saved_mtime = mtime;
+        self.detected_indent = detect_indent(&self.text);
         cx.emit(Event::Reloaded);
         cx.notify();
     }
@@ -901,7 +1052,7 @@ impl Buffer {
                 }
             } else {
                 let new_mtime = new_file.mtime();
-                if new_mtime != old_file.mtime() {
+                if !mtimes_match(new_mtime, old_file.mtime()) {
                     file_changed = true;
 
                     if !self.is_dirty() {
@@ -1174,7 +1325,11 @@ impl Buffer {
     fn compute_autoindents(&self) -> Option<impl Future<Output = BTreeMap<u32, IndentSize>>> {
         let max_rows_between_yields = 100;
         let snapshot = self.snapshot();
-        if snapshot.syntax.is_empty() || self.autoindent_requests.is_empty() {
+        let has_grammar = snapshot
+            .language
+            .as_ref()
+            .map_or(false, |language| language.grammar().is_some());
+        if (has_grammar && snapshot.syntax.is_empty()) || self.autoindent_requests.is_empty() {
             return None;
         }
 
@@ -1406,7 +1561,7 @@ impl Buffer {
         cx.background_executor()
             .spawn_labeled(*BUFFER_DIFF_TASK, async move {
                 let old_text = old_text.to_string();
-                let line_ending = LineEnding::detect(&new_text);
+                let (line_ending, has_mixed_line_endings) = LineEnding::detect_mixed(&new_text);
                 LineEnding::normalize(&mut new_text);
 
                 let diff = TextDiff::from_chars(old_text.as_str(), new_text.as_str());
@@ -1466,23 +1621,56 @@ impl Buffer {
                 Diff {
                     base_version,
                     line_ending,
+                    has_mixed_line_endings,
                     edits,
                 }
             })
     }
 
-    /// Spawns a background task that searches the buffer for any whitespace
-    /// at the ends of a lines, and returns a `Diff` that removes that whitespace.
-    pub fn remove_trailing_whitespace(&self, cx: &AppContext) -> Task<Diff> {
+    /// Spawns a background task that searches the given lines of the buffer for any
+    /// whitespace at the ends of a lines, and returns a `Diff` that removes that whitespace.
+    ///
+    /// When only removing it from modified lines, nothing is removed if that would touch
+    /// an enormous number of lines, as happens when saving a file with many unrelated
+    /// lines that end in whitespace after e.g. reindenting it.
+    pub fn remove_trailing_whitespace(
+        &self,
+        lines: TrailingWhitespaceLines,
+        cx: &AppContext,
+    ) -> Task<Diff> {
+        const MAX_MODIFIED_LINES_TO_TRIM: usize = 10_000;
+
         let old_text = self.as_rope().clone();
         let line_ending = self.line_ending();
+        let has_mixed_line_endings = self.has_mixed_line_endings();
         let base_version = self.version();
+        let modified_rows = match lines {
+            TrailingWhitespaceLines::AllLines => None,
+            TrailingWhitespaceLines::ModifiedLines => Some(
+                self.edits_since::<Point>(&self.saved_version)
+                    .map(|edit| edit.new.start.row..edit.new.end.row + 1)
+                    .collect::<Vec<_>>(),
+            ),
+        };
         cx.background_executor().spawn(async move {
-            let ranges = trailing_whitespace_ranges(&old_text);
+            let mut ranges = trailing_whitespace_ranges(&old_text);
+            if let Some(modified_rows) = modified_rows {
+                ranges.retain(|range| {
+                    let row = old_text.offset_to_point(range.start).row;
+                    let ix = modified_rows.partition_point(|rows| rows.end <= row);
+                    modified_rows
+                        .get(ix)
+                        .map_or(false, |rows| rows.start <= row)
+                });
+                if ranges.len() > MAX_MODIFIED_LINES_TO_TRIM {
+                    ranges.clear();
+                }
+            }
             let empty = Arc::<str>::from("");
             Diff {
                 base_version,
                 line_ending,
+                has_mixed_line_endings,
                 edits: ranges
                     .into_iter()
                     .map(|range| (range, empty.clone()))
@@ -1547,14 +1735,170 @@ impl Buffer {
 
         self.start_transaction();
         self.text.set_line_ending(diff.line_ending);
+        self.text
+            .set_has_mixed_line_endings(diff.has_mixed_line_endings);
         self.edit(adjusted_edits, None, cx);
         self.end_transaction(cx)
     }
 
+    /// Changes the line ending that the buffer's lines are joined with when it is saved,
+    /// marking the buffer as dirty if it differs from the one on disk.
+    ///
+    /// The conversion is pushed onto the undo stack as a transaction of its own.
+    pub fn set_line_ending(&mut self, line_ending: LineEnding, cx: &mut ModelContext<Self>) {
+        if line_ending == self.line_ending() && !self.has_mixed_line_endings() {
+            return;
+        }
+
+        let was_dirty = self.is_dirty();
+        let old_version = self.version.clone();
+        let change = LineEndingChange {
+            old_line_ending: self.line_ending(),
+            old_has_mixed_line_endings: self.has_mixed_line_endings(),
+            new_line_ending: line_ending,
+        };
+        let transaction_id = self.text.push_empty_transaction(Instant::now());
+        self.line_ending_changes.insert(transaction_id, change);
+        self.prune_line_ending_changes();
+        self.replay_line_ending_change(transaction_id, false, cx);
+        self.did_edit(&old_version, was_dirty, cx);
+    }
+
+    /// Reapplies the line ending conversion made in the given transaction, or reverts it
+    /// when undoing, and sends the result to the buffer's other replicas.
+    fn replay_line_ending_change(
+        &mut self,
+        transaction_id: TransactionId,
+        undo: bool,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let Some(change) = self.line_ending_changes.get(&transaction_id).copied() else {
+            return;
+        };
+        let (line_ending, has_mixed_line_endings) = if undo {
+            (change.old_line_ending, change.old_has_mixed_line_endings)
+        } else {
+            (change.new_line_ending, false)
+        };
+        self.text.set_line_ending(line_ending);
+        self.text.set_has_mixed_line_endings(has_mixed_line_endings);
+        self.line_ending_timestamp = self.text.lamport_clock.tick();
+        self.send_operation(
+            Operation::UpdateLineEnding {
+                line_ending,
+                has_mixed_line_endings,
+                lamport_timestamp: self.line_ending_timestamp,
+            },
+            cx,
+        );
+        cx.notify();
+    }
+
+    /// Forgets the line ending conversions whose transactions have left the undo history,
+    /// e.g. because a new edit cleared the redo stack.
+    fn prune_line_ending_changes(&mut self) {
+        if !self.line_ending_changes.is_empty() {
+            let text = &self.text;
+            self.line_ending_changes
+                .retain(|transaction_id, _| text.has_transaction(*transaction_id));
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn line_ending_change_count(&self) -> usize {
+        self.line_ending_changes.len()
+    }
+
+    /// The indentation detected from the buffer's contents, if they were indented
+    /// consistently enough to tell.
+    pub fn detected_indent(&self) -> Option<IndentSize> {
+        self.detected_indent
+    }
+
+    /// Detects the buffer's indentation from its current contents, e.g. after a lot of
+    /// text was pasted into a buffer that had nothing to detect it from.
+    pub fn detect_indent(&mut self, cx: &mut ModelContext<Self>) {
+        let detected_indent = detect_indent(&self.text);
+        if detected_indent != self.detected_indent {
+            self.detected_indent = detected_indent;
+            cx.notify();
+        }
+    }
+
+    /// The indentation the user chose for this buffer, which wins over both the detected
+    /// indentation and the settings.
+    pub fn indent_override(&self) -> Option<IndentSize> {
+        self.indent_override
+    }
+
+    /// The indentation the buffer is edited with in its language, and where it comes from.
+    pub fn indent_resolution(&self, cx: &AppContext) -> IndentResolution {
+        resolve_indent(
+            self.indent_override,
+            self.detected_indent,
+            self.language(),
+            self.file.as_ref(),
+            cx,
+        )
+    }
+
+    /// Picks the indentation of this buffer, which takes precedence over every other source
+    /// and isn't saved. `None` goes back to the detected indentation and the settings.
+    pub fn set_indent_override(
+        &mut self,
+        indent_override: Option<IndentSize>,
+        cx: &mut ModelContext<Self>,
+    ) {
+        if indent_override != self.indent_override {
+            self.indent_override = indent_override;
+            cx.notify();
+        }
+    }
+
+    /// The encoding of the buffer's file on disk, which it is saved in.
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// Changes the encoding that the buffer is saved in, marking the buffer as dirty
+    /// if it differs from the one on disk.
+    pub fn set_encoding(&mut self, encoding: Encoding, cx: &mut ModelContext<Self>) {
+        if encoding == self.encoding {
+            return;
+        }
+
+        let was_dirty = self.is_dirty();
+        self.encoding = encoding;
+        if was_dirty != self.is_dirty() {
+            cx.emit(Event::DirtyChanged);
+        }
+        cx.notify();
+    }
+
+    /// Reloads the buffer's file from disk, decoding it with the given encoding.
+    pub fn reopen_with_encoding(
+        &mut self,
+        encoding: Encoding,
+        cx: &mut ModelContext<Self>,
+    ) -> oneshot::Receiver<Option<Transaction>> {
+        self.encoding = encoding;
+        self.saved_encoding = encoding;
+        self.reload(cx)
+    }
+
+    /// Returns the first character of the buffer that can't be saved in its encoding.
+    pub fn unencodable_char(&self) -> Option<char> {
+        self.as_rope()
+            .chars()
+            .find(|c| !self.encoding.can_encode(*c))
+    }
+
     /// Checks if the buffer has unsaved changes.
     pub fn is_dirty(&self) -> bool {
         self.has_conflict
             || self.has_edits_since(&self.saved_version)
+            || self.line_ending() != self.saved_line_ending
+            || self.encoding != self.saved_encoding
             || self
                 .file
                 .as_ref()
@@ -1566,7 +1910,9 @@ impl Buffer {
     pub fn has_conflict(&self) -> bool {
         self.has_conflict
             || self.file.as_ref().map_or(false, |file| {
-                file.mtime() > self.saved_mtime && self.has_edits_since(&self.saved_version)
+                file.mtime() > self.saved_mtime
+                    && !mtimes_match(file.mtime(), self.saved_mtime)
+                    && self.has_edits_since(&self.saved_version)
             })
     }
 
@@ -1614,6 +1960,7 @@ impl Buffer {
             false
         };
         if let Some((transaction_id, start_version)) = self.text.end_transaction_at(now) {
+            self.prune_line_ending_changes();
             self.did_edit(&start_version, was_dirty, cx);
             Some(transaction_id)
         } else {
@@ -1624,6 +1971,7 @@ impl Buffer {
     /// Manually add a transaction to the buffer's undo history.
     pub fn push_transaction(&mut self, transaction: Transaction, now: Instant) {
         self.text.push_transaction(transaction, now);
+        self.prune_line_ending_changes();
     }
 
     /// Prevent the last transaction from being grouped with any subsequent transactions,
@@ -1640,11 +1988,13 @@ impl Buffer {
     /// Manually remove a transaction from the buffer's undo history
     pub fn forget_transaction(&mut self, transaction_id: TransactionId) {
         self.text.forget_transaction(transaction_id);
+        self.prune_line_ending_changes();
     }
 
     /// Manually merge two adjacent transactions in the buffer's undo history.
     pub fn merge_transactions(&mut self, transaction: TransactionId, destination: TransactionId) {
         self.text.merge_transactions(transaction, destination);
+        self.prune_line_ending_changes();
     }
 
     /// Waits for the buffer to receive operations with the given timestamps.
@@ -1715,6 +2065,61 @@ impl Buffer {
         }
     }
 
+    /// Returns the marks of this buffer, which are shared by all of its editors.
+    pub fn marks(&self) -> &Marks {
+        &self.marks
+    }
+
+    /// Sets the mark with the given letter at the given position, or else a new numbered mark.
+    pub fn set_mark<T: ToPoint>(
+        &mut self,
+        letter: Option<char>,
+        position: T,
+        cx: &mut ModelContext<Self>,
+    ) -> Result<MarkId> {
+        let point = position.to_point(&self.text);
+        let id = self.marks.set(letter, point, &self.text)?;
+        cx.emit(Event::MarksChanged);
+        cx.notify();
+        Ok(id)
+    }
+
+    pub fn remove_mark(&mut self, id: MarkId, cx: &mut ModelContext<Self>) -> bool {
+        let removed = self.marks.remove(id).is_some();
+        if removed {
+            cx.emit(Event::MarksChanged);
+            cx.notify();
+        }
+        removed
+    }
+
+    pub fn clear_marks(&mut self, cx: &mut ModelContext<Self>) {
+        if !self.marks.is_empty() {
+            self.marks.clear();
+            cx.emit(Event::MarksChanged);
+            cx.notify();
+        }
+    }
+
+    /// Sets the marks stored for this buffer, as names along with offsets of their positions and
+    /// regions. The file may have gotten shorter since the marks were stored.
+    pub fn restore_marks(
+        &mut self,
+        marks: impl IntoIterator<Item = (MarkName, usize, Range<usize>)>,
+    ) {
+        let len = self.len();
+        for (name, position, region) in marks {
+            let position = self.clip_offset(position.min(len), Bias::Left);
+            let region = region.start.min(len)..region.end.min(len);
+            if region.is_empty() {
+                continue;
+            }
+            let position = self.anchor_before(position);
+            let region = self.anchor_after(region.start)..self.anchor_before(region.end);
+            self.marks.set_named(name, position, region).log_err();
+        }
+    }
+
     /// Replaces the buffer's entire text.
     pub fn set_text<T>(&mut self, text: T, cx: &mut ModelContext<Self>) -> Option<clock::Lamport>
     where
@@ -1724,9 +2129,35 @@ impl Buffer {
         self.edit([(0..self.len(), text)], None, cx)
     }
 
+    /// Replaces all of the given ranges with the same text, as a single edit. See
+    /// [`Buffer::edit`].
+    pub fn edit_ranges<I, S, T>(
+        &mut self,
+        ranges: I,
+        new_text: T,
+        autoindent_mode: Option<AutoindentMode>,
+        cx: &mut ModelContext<Self>,
+    ) -> Option<clock::Lamport>
+    where
+        I: IntoIterator<Item = Range<S>>,
+        S: ToOffset,
+        T: Into<Arc<str>>,
+    {
+        let new_text = new_text.into();
+        self.edit(
+            ranges.into_iter().map(|range| (range, new_text.clone())),
+            autoindent_mode,
+            cx,
+        )
+    }
+
     /// Applies the given edits to the buffer. Each edit is specified as a range of text to
     /// delete, and a string of text to insert at that location.
     ///
+    /// The edits are all applied as a single operation, with offsets referring to the text
+    /// before any of them is applied, so they can be given in any order. Overlapping or
+    /// adjacent edits are coalesced, and insertions at the same offset keep their order.
+    ///
     /// If an [`AutoindentMode`] is provided, then the buffer will enqueue an auto-indent
     /// request for the edited ranges, which will be processed when the buffer finishes
     /// parsing.
@@ -1744,26 +2175,29 @@ impl Buffer {
         S: ToOffset,
         T: Into<Arc<str>>,
     {
-        // Skip invalid edits and coalesce contiguous ones.
-        let mut edits: Vec<(Range<usize>, Arc<str>)> = Vec::new();
+        // Skip invalid edits, sort the rest and coalesce contiguous ones.
+        let mut valid_edits = Vec::new();
         for (range, new_text) in edits_iter {
             let mut range = range.start.to_offset(self)..range.end.to_offset(self);
             if range.start > range.end {
                 mem::swap(&mut range.start, &mut range.end);
             }
-            let new_text = new_text.into();
+            let new_text: Arc<str> = new_text.into();
             if !new_text.is_empty() || !range.is_empty() {
-                if let Some((prev_range, prev_text)) = edits.last_mut() {
-                    if prev_range.end >= range.start {
-                        prev_range.end = cmp::max(prev_range.end, range.end);
-                        *prev_text = format!("{prev_text}{new_text}").into();
-                    } else {
-                        edits.push((range, new_text));
-                    }
-                } else {
-                    edits.push((range, new_text));
+                valid_edits.push((range, new_text));
+            }
+        }
+        valid_edits.sort_by_key(|(range, _)| range.start);
+        let mut edits: Vec<(Range<usize>, Arc<str>)> = Vec::new();
+        for (range, new_text) in valid_edits {
+            if let Some((prev_range, prev_text)) = edits.last_mut() {
+                if prev_range.end >= range.start {
+                    prev_range.end = cmp::max(prev_range.end, range.end);
+                    *prev_text = format!("{prev_text}{new_text}").into();
+                    continue;
                 }
             }
+            edits.push((range, new_text));
         }
         if edits.is_empty() {
             return None;
@@ -1772,7 +2206,11 @@ impl Buffer {
         self.start_transaction();
         self.pending_autoindent.take();
         let autoindent_request = autoindent_mode
-            .and_then(|mode| self.language.as_ref().map(|_| (self.snapshot(), mode)));
+            .filter(|_| {
+                self.language.is_some()
+                    && language_settings(self.language(), self.file.as_ref(), cx).auto_indent
+            })
+            .map(|mode| (self.snapshot(), mode));
 
         let edit_operation = self.text.edit(edits.iter().cloned());
         let edit_id = edit_operation.timestamp();
@@ -1854,6 +2292,11 @@ impl Buffer {
         cx: &mut ModelContext<Self>,
     ) {
         if self.edits_since::<usize>(old_version).next().is_none() {
+            // The line ending may still have changed, which can make the buffer dirty.
+            if was_dirty != self.is_dirty() {
+                cx.emit(Event::DirtyChanged);
+                cx.notify();
+            }
             return;
         }
 
@@ -1863,6 +2306,9 @@ impl Buffer {
         if was_dirty != self.is_dirty() {
             cx.emit(Event::DirtyChanged);
         }
+        if self.marks.remove_deleted(&self.text) {
+            cx.emit(Event::MarksChanged);
+        }
         cx.notify();
     }
 
@@ -1901,15 +2347,17 @@ impl Buffer {
     }
 
     fn flush_deferred_ops(&mut self, cx: &mut ModelContext<Self>) {
-        let mut deferred_ops = Vec::new();
-        for op in self.deferred_ops.drain().iter().cloned() {
-            if self.can_apply_op(&op) {
+        let mut deferred_ops = mem::take(&mut self.deferred_ops);
+        loop {
+            let ops = deferred_ops.drain_ready(usize::MAX, |op| self.can_apply_op(op));
+            if ops.is_empty() {
+                break;
+            }
+            for op in ops {
                 self.apply_op(op, cx);
-            } else {
-                deferred_ops.push(op);
             }
         }
-        self.deferred_ops.insert(deferred_ops);
+        self.deferred_ops = deferred_ops;
     }
 
     fn can_apply_op(&self, operation: &Operation) -> bool {
@@ -1927,7 +2375,7 @@ impl Buffer {
             Operation::UpdateSelections { selections, .. } => selections
                 .iter()
                 .all(|s| self.can_resolve(&s.start) && self.can_resolve(&s.end)),
-            Operation::UpdateCompletionTriggers { .. } => true,
+            Operation::UpdateCompletionTriggers { .. } | Operation::UpdateLineEnding { .. } => true,
         }
     }
 
@@ -1980,6 +2428,18 @@ impl Buffer {
                 self.completion_triggers = triggers;
                 self.text.lamport_clock.observe(lamport_timestamp);
             }
+            Operation::UpdateLineEnding {
+                line_ending,
+                has_mixed_line_endings,
+                lamport_timestamp,
+            } => {
+                if lamport_timestamp > self.line_ending_timestamp {
+                    self.text.set_line_ending(line_ending);
+                    self.text.set_has_mixed_line_endings(has_mixed_line_endings);
+                    self.line_ending_timestamp = lamport_timestamp;
+                }
+                self.text.lamport_clock.observe(lamport_timestamp);
+            }
         }
     }
 
@@ -2027,6 +2487,7 @@ impl Buffer {
 
         if let Some((transaction_id, operation)) = self.text.undo() {
             self.send_operation(Operation::Buffer(operation), cx);
+            self.replay_line_ending_change(transaction_id, true, cx);
             self.did_edit(&old_version, was_dirty, cx);
             Some(transaction_id)
         } else {
@@ -2044,6 +2505,7 @@ impl Buffer {
         let old_version = self.version.clone();
         if let Some(operation) = self.text.undo_transaction(transaction_id) {
             self.send_operation(Operation::Buffer(operation), cx);
+            self.replay_line_ending_change(transaction_id, true, cx);
             self.did_edit(&old_version, was_dirty, cx);
             true
         } else {
@@ -2062,8 +2524,9 @@ impl Buffer {
 
         let operations = self.text.undo_to_transaction(transaction_id);
         let undone = !operations.is_empty();
-        for operation in operations {
+        for (transaction_id, operation) in operations {
             self.send_operation(Operation::Buffer(operation), cx);
+            self.replay_line_ending_change(transaction_id, true, cx);
         }
         if undone {
             self.did_edit(&old_version, was_dirty, cx)
@@ -2078,6 +2541,7 @@ impl Buffer {
 
         if let Some((transaction_id, operation)) = self.text.redo() {
             self.send_operation(Operation::Buffer(operation), cx);
+            self.replay_line_ending_change(transaction_id, false, cx);
             self.did_edit(&old_version, was_dirty, cx);
             Some(transaction_id)
         } else {
@@ -2096,8 +2560,9 @@ impl Buffer {
 
         let operations = self.text.redo_to_transaction(transaction_id);
         let redone = !operations.is_empty();
-        for operation in operations {
+        for (transaction_id, operation) in operations {
             self.send_operation(Operation::Buffer(operation), cx);
+            self.replay_line_ending_change(transaction_id, false, cx);
         }
         if redone {
             self.did_edit(&old_version, was_dirty, cx)
@@ -2206,12 +2671,14 @@ impl BufferSnapshot {
     /// Returns [`IndentSize`] for a given position that respects user settings
     /// and language preferences.
     pub fn language_indent_size_at<T: ToOffset>(&self, position: T, cx: &AppContext) -> IndentSize {
-        let settings = language_settings(self.language_at(position), self.file(), cx);
-        if settings.hard_tabs {
-            IndentSize::tab()
-        } else {
-            IndentSize::spaces(settings.tab_size.get())
-        }
+        resolve_indent_size(
+            self.indent_override,
+            self.detected_indent,
+            self.language_at(position),
+            self.file(),
+            cx,
+        )
+        .0
     }
 
     /// Retrieve the suggested indent size for all of the given rows. The unit of indentation
@@ -2251,7 +2718,20 @@ impl BufferSnapshot {
         &self,
         row_range: Range<u32>,
     ) -> Option<impl Iterator<Item = Option<IndentSuggestion>> + '_> {
-        let config = &self.language.as_ref()?.config;
+        let language = self.language.as_ref()?;
+        if language.grammar().is_none() {
+            // Plain text and other languages that don't define brackets keep the indentation
+            // that was typed.
+            let brackets = indent_brackets(&language.config.brackets);
+            if brackets.is_empty() {
+                return None;
+            }
+            return Some(Either::Left(
+                self.suggest_autoindents_from_brackets(brackets, row_range),
+            ));
+        }
+
+        let config = &language.config;
         let prev_non_blank_row = self.prev_non_blank_row(row_range.start);
 
         // Find the suggested indentation ranges based on the syntax tree.
@@ -2371,7 +2851,7 @@ impl BufferSnapshot {
             row_range.start.saturating_sub(1)
         };
         let mut prev_row_start = Point::new(prev_row, self.indent_size_for_line(prev_row).len);
-        Some(row_range.map(move |row| {
+        Some(Either::Right(row_range.map(move |row| {
             let row_start = Point::new(row, self.indent_size_for_line(row).len);
 
             let mut indent_from_prev_row = false;
@@ -2449,7 +2929,74 @@ impl BufferSnapshot {
             prev_row = row;
             prev_row_start = row_start;
             suggestion
-        }))
+        })))
+    }
+
+    /// Suggests indentation for languages without a grammar, from the given pairs of their
+    /// opening and closing brackets: lines following one that ends with an opening bracket or a
+    /// colon are indented one level deeper, lines starting with a closing bracket are aligned
+    /// with the line of the bracket they close, and all other lines keep the indentation of the
+    /// line before them.
+    fn suggest_autoindents_from_brackets(
+        &self,
+        brackets: Vec<(char, char)>,
+        row_range: Range<u32>,
+    ) -> impl Iterator<Item = Option<IndentSuggestion>> + '_ {
+        row_range.map(move |row| {
+            let line_start = Point::new(row, self.indent_size_for_line(row).len);
+            if let Some(first_char) = self.chars_at(line_start).next() {
+                if brackets.iter().any(|(_, close)| *close == first_char) {
+                    return Some(IndentSuggestion {
+                        basis_row: self.row_of_unclosed_bracket_before(row, &brackets)?,
+                        delta: Ordering::Equal,
+                        within_error: false,
+                    });
+                }
+            }
+
+            let prev_row = self.prev_non_blank_row(row)?;
+            let prev_line_end = self
+                .reversed_chars_at(Point::new(prev_row, self.line_len(prev_row)))
+                .find(|c| !c.is_whitespace());
+            let opens = prev_line_end.map_or(false, |last_char| {
+                last_char == ':' || brackets.iter().any(|(open, _)| *open == last_char)
+            });
+            let delta = if opens {
+                Ordering::Greater
+            } else {
+                Ordering::Equal
+            };
+            Some(IndentSuggestion {
+                basis_row: prev_row,
+                delta,
+                within_error: false,
+            })
+        })
+    }
+
+    /// Returns the row of the innermost bracket that is still open at the start of the given
+    /// row, looking back a bounded number of characters.
+    fn row_of_unclosed_bracket_before(&self, row: u32, brackets: &[(char, char)]) -> Option<u32> {
+        const MAX_CHARS_TO_SCAN: usize = 10_000;
+
+        let mut depth = 0;
+        let mut current_row = row;
+        for c in self
+            .reversed_chars_at(Point::new(row, 0))
+            .take(MAX_CHARS_TO_SCAN)
+        {
+            if c == '\n' {
+                current_row -= 1;
+            } else if brackets.iter().any(|(_, close)| *close == c) {
+                depth += 1;
+            } else if brackets.iter().any(|(open, _)| *open == c) {
+                if depth == 0 {
+                    return Some(current_row);
+                }
+                depth -= 1;
+            }
+        }
+        None
     }
 
     fn prev_non_blank_row(&self, mut row: u32) -> Option<u32> {
@@ -2536,6 +3083,66 @@ impl BufferSnapshot {
             .last()
     }
 
+    /// Returns the parts of the given range that are within comments or strings, according to
+    /// the syntax trees of the buffer, in order and without overlaps.
+    pub fn comment_and_string_ranges(&self, range: Range<usize>) -> Vec<Range<usize>> {
+        self.ranges_of_syntax_nodes(range, |kind| {
+            kind.contains("comment") || kind.contains("string")
+        })
+    }
+
+    /// Returns the parts of the given range that are within code blocks or code spans, like
+    /// those of Markdown, in order and without overlaps.
+    pub fn code_block_ranges(&self, range: Range<usize>) -> Vec<Range<usize>> {
+        self.ranges_of_syntax_nodes(range, |kind| {
+            kind.contains("code_block") || kind == "code_span"
+        })
+    }
+
+    /// Returns the parts of the given range within the outermost syntax nodes of the kinds
+    /// matching the predicate, in order and without overlaps.
+    fn ranges_of_syntax_nodes(
+        &self,
+        range: Range<usize>,
+        is_matching_kind: impl Fn(&str) -> bool,
+    ) -> Vec<Range<usize>> {
+        let mut ranges = Vec::new();
+        for layer in self.syntax.layers_for_range(range.clone(), &self.text) {
+            let mut cursor = layer.node().walk();
+            'walk: loop {
+                let node = cursor.node();
+                let node_range = node.byte_range();
+                if node_range.start < range.end && node_range.end > range.start {
+                    if is_matching_kind(node.kind()) {
+                        let start = node_range.start.max(range.start);
+                        ranges.push(start..node_range.end.min(range.end));
+                    } else if cursor.goto_first_child() {
+                        continue;
+                    }
+                }
+                while !cursor.goto_next_sibling() {
+                    if !cursor.goto_parent() {
+                        break 'walk;
+                    }
+                }
+            }
+        }
+
+        // Injected layers can be within comments or strings of the layers they're injected into.
+        ranges.sort_unstable_by_key(|range| range.start);
+        let mut merged_ranges = Vec::<Range<usize>>::with_capacity(ranges.len());
+        for range in ranges {
+            if let Some(last_range) = merged_ranges.last_mut() {
+                if range.start <= last_range.end {
+                    last_range.end = last_range.end.max(range.end);
+                    continue;
+                }
+            }
+            merged_ranges.push(range);
+        }
+        merged_ranges
+    }
+
     /// Returns the main [Language]
     pub fn language(&self) -> Option<&Arc<Language>> {
         self.language.as_ref()
@@ -2616,26 +3223,47 @@ impl BufferSnapshot {
             prev_chars.peek().copied().map(kind),
             next_chars.peek().copied().map(kind),
         );
+        let in_word = |ch| Some(kind(ch)) == word_kind && ch != '\n';
 
+        // When the position is on a word boundary, the word following it is preferred.
+        let prev_char = prev_chars.peek().copied();
+        let mut right = next_chars.peek().copied().filter(|ch| in_word(*ch));
         for ch in prev_chars {
-            if Some(kind(ch)) == word_kind && ch != '\n' {
-                start -= ch.len_utf8();
-            } else {
+            if !in_word(ch) || right.map_or(false, |right| is_word_boundary(&scope, ch, right)) {
                 break;
             }
+            start -= ch.len_utf8();
+            right = Some(ch);
         }
 
+        let mut left = if start < end { prev_char } else { None };
         for ch in next_chars {
-            if Some(kind(ch)) == word_kind && ch != '\n' {
-                end += ch.len_utf8();
-            } else {
+            if !in_word(ch) || left.map_or(false, |left| is_word_boundary(&scope, left, ch)) {
                 break;
             }
+            end += ch.len_utf8();
+            left = Some(ch);
         }
 
         (start..end, word_kind)
     }
 
+    /// Returns the range of the syntax node that encloses the given range `steps` levels up: the
+    /// smallest node larger than the range for one step, the smallest node larger than that one
+    /// for two, and so on. Returns `None` if the buffer has no syntax tree, or if the tree ends
+    /// before that many levels.
+    pub fn enclosing_node_range<T: ToOffset>(
+        &self,
+        range: Range<T>,
+        steps: usize,
+    ) -> Option<Range<usize>> {
+        let mut range = range.start.to_offset(self)..range.end.to_offset(self);
+        for _ in 0..steps {
+            range = self.range_for_syntax_ancestor(range)?;
+        }
+        Some(range)
+    }
+
     /// Returns the range for the closes syntax node enclosing the given range.
     pub fn range_for_syntax_ancestor<T: ToOffset>(&self, range: Range<T>) -> Option<Range<usize>> {
         let range = range.start.to_offset(self)..range.end.to_offset(self);
@@ -2892,6 +3520,15 @@ impl BufferSnapshot {
         let range = range.start.to_offset(self).saturating_sub(1)
             ..self.len().min(range.end.to_offset(self) + 1);
 
+        let has_brackets_query = self
+            .language
+            .as_ref()
+            .and_then(|language| language.grammar())
+            .map_or(false, |grammar| grammar.brackets_config.is_some());
+        if !has_brackets_query || self.syntax.is_empty() {
+            return Either::Left(self.bracket_ranges_from_text(range).into_iter());
+        }
+
         let mut matches = self.syntax.matches(range.clone(), &self.text, |grammar| {
             grammar.brackets_config.as_ref().map(|c| &c.query)
         });
@@ -2901,7 +3538,7 @@ impl BufferSnapshot {
             .map(|grammar| grammar.brackets_config.as_ref().unwrap())
             .collect::<Vec<_>>();
 
-        iter::from_fn(move || {
+        Either::Right(iter::from_fn(move || {
             while let Some(mat) = matches.peek() {
                 let mut open = None;
                 let mut close = None;
@@ -2928,7 +3565,56 @@ impl BufferSnapshot {
                 return Some((open, close));
             }
             None
-        })
+        }))
+    }
+
+    /// Finds the pairs of brackets and double quotes overlapping the given range by counting
+    /// how they nest in the surrounding text. Used when the language has no brackets query,
+    /// only looking a bounded distance away from the range.
+    fn bracket_ranges_from_text(&self, range: Range<usize>) -> Vec<(Range<usize>, Range<usize>)> {
+        const MAX_BYTES_TO_SCAN: usize = 10_000;
+
+        let scan_start =
+            self.clip_offset(range.start.saturating_sub(MAX_BYTES_TO_SCAN), Bias::Left);
+        let scan_end = self.clip_offset(self.len().min(range.end + MAX_BYTES_TO_SCAN), Bias::Right);
+
+        let mut pairs = Vec::new();
+        let mut open_brackets = Vec::<(u8, usize)>::new();
+        let mut open_quote = None;
+        let mut escaped = false;
+        let mut offset = scan_start;
+        for chunk in self.text_for_range(scan_start..scan_end) {
+            for byte in chunk.bytes() {
+                match byte {
+                    b'\n' => open_quote = None,
+                    b'"' if !escaped => match open_quote.take() {
+                        Some(open) => pairs.push((open..open + 1, offset..offset + 1)),
+                        None => open_quote = Some(offset),
+                    },
+                    b'(' | b'[' | b'{' if open_quote.is_none() => {
+                        open_brackets.push((byte, offset));
+                    }
+                    b')' | b']' | b'}' if open_quote.is_none() => {
+                        let opening_byte = match byte {
+                            b')' => b'(',
+                            b']' => b'[',
+                            _ => b'{',
+                        };
+                        // A closing bracket that doesn't match the innermost open one is ignored.
+                        if open_brackets.last().map(|(byte, _)| *byte) == Some(opening_byte) {
+                            let (_, open) = open_brackets.pop().unwrap();
+                            pairs.push((open..open + 1, offset..offset + 1));
+                        }
+                    }
+                    _ => {}
+                }
+                escaped = byte == b'\\' && !escaped;
+                offset += 1;
+            }
+        }
+
+        pairs.retain(|(open, close)| (open.start..=close.end).overlaps(&range));
+        pairs
     }
 
     /// Returns enclosing bracket ranges containing the given range
@@ -3473,6 +4159,24 @@ impl BufferSnapshot {
         })
     }
 
+    /// Returns the closest diagnostic that starts after the given position, or before it
+    /// if `reversed` is true. Diagnostics that start exactly at the position are skipped,
+    /// so that repeatedly moving to the returned diagnostic visits each one in turn.
+    pub fn next_diagnostic<T: ToOffset>(
+        &self,
+        position: T,
+        reversed: bool,
+    ) -> Option<DiagnosticEntry<usize>> {
+        let position = position.to_offset(self);
+        if reversed {
+            self.diagnostics_in_range::<_, usize>(0..position, true)
+                .find(|entry| entry.range.start < position)
+        } else {
+            self.diagnostics_in_range::<_, usize>(position..self.len(), false)
+                .find(|entry| entry.range.start > position)
+        }
+    }
+
     /// Returns all the diagnostic groups associated with the given
     /// language server id. If no language server id is provided,
     /// all diagnostics groups are returned.
@@ -3583,6 +4287,23 @@ fn indent_size_for_text(text: impl Iterator<Item = char>) -> IndentSize {
     result
 }
 
+/// Returns the pairs of single-character brackets that indentation follows, leaving out quotes
+/// and other pairs that open and close with the same character.
+fn indent_brackets(config: &BracketPairConfig) -> Vec<(char, char)> {
+    config
+        .pairs
+        .iter()
+        .filter_map(|pair| {
+            let mut start = pair.start.chars();
+            let mut end = pair.end.chars();
+            match (start.next(), start.next(), end.next(), end.next()) {
+                (Some(open), None, Some(close), None) if open != close => Some((open, close)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
 impl Clone for BufferSnapshot {
     fn clone(&self) -> Self {
         Self {
@@ -3808,6 +4529,9 @@ impl operation_queue::Operation for Operation {
             }
             | Operation::UpdateCompletionTriggers {
                 lamport_timestamp, ..
+            }
+            | Operation::UpdateLineEnding {
+                lamport_timestamp, ..
             } => *lamport_timestamp,
         }
     }
@@ -3961,7 +4685,7 @@ pub(crate) fn contiguous_ranges(
 pub fn char_kind(scope: &Option<LanguageScope>, c: char) -> CharKind {
     if c.is_whitespace() {
         return CharKind::Whitespace;
-    } else if c.is_alphanumeric() || c == '_' {
+    } else if is_word_char(c) {
         return CharKind::Word;
     }
 
@@ -3976,6 +4700,22 @@ pub fn char_kind(scope: &Option<LanguageScope>, c: char) -> CharKind {
     CharKind::Punctuation
 }
 
+/// Returns whether there is a word boundary between two adjacent characters.
+///
+/// This loosely follows the word boundary rules of UAX #29: characters of different
+/// [CharKind]s are separated, a word doesn't continue across a change of script between
+/// e.g. latin, Han and kana characters, and neither combining marks nor the joiners and
+/// modifiers of emoji sequences are ever split from the character they attach to.
+pub fn is_word_boundary(scope: &Option<LanguageScope>, left: char, right: char) -> bool {
+    if is_grapheme_extender(right) || left == ZERO_WIDTH_JOINER {
+        return false;
+    }
+
+    let kind = char_kind(scope, left);
+    kind != char_kind(scope, right)
+        || (kind != CharKind::Whitespace && is_script_boundary(left, right))
+}
+
 /// Find all of the ranges of whitespace that occur at the ends of lines
 /// in the given rope.
 ///
@@ -4014,3 +4754,24 @@ pub fn trailing_whitespace_ranges(rope: &Rope) -> Vec<Range<usize>> {
 
     ranges
 }
+
+/// Returns whether two file modification times refer to the same write.
+///
+/// Some filesystems (HFS+, ext3, many network mounts) only store mtimes with a
+/// granularity of one second, so the mtime reported right after a save can
+/// differ from the one read back later. When either time has no sub-second
+/// part, the two are compared at that granularity instead.
+pub(crate) fn mtimes_match(a: Option<SystemTime>, b: Option<SystemTime>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) if a != b => {
+            let (Ok(a), Ok(b)) = (
+                a.duration_since(SystemTime::UNIX_EPOCH),
+                b.duration_since(SystemTime::UNIX_EPOCH),
+            ) else {
+                return false;
+            };
+            (a.subsec_nanos() == 0 || b.subsec_nanos() == 0) && a.as_secs() == b.as_secs()
+        }
+        (a, b) => a == b,
+    }
+}
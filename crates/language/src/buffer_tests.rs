@@ -1,6 +1,7 @@
 use super::*;
 use crate::language_settings::{
     AllLanguageSettings, AllLanguageSettingsContent, LanguageSettingsContent,
+    TrailingWhitespaceLines,
 };
 use crate::Buffer;
 use clock::ReplicaId;
@@ -15,8 +16,9 @@ use regex::RegexBuilder;
 use settings::SettingsStore;
 use std::{
     env,
+    num::NonZeroU32,
     ops::Range,
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime},
 };
 use text::network::Network;
 use text::{BufferId, LineEnding, LineIndent};
@@ -64,6 +66,99 @@ fn test_line_endings(cx: &mut gpui::AppContext) {
     });
 }
 
+#[gpui::test]
+fn test_set_line_ending(cx: &mut gpui::AppContext) {
+    init_settings(cx, |_| {});
+
+    let buffer = cx.new_model(|cx| Buffer::local("one\r\ntwo\nthree\r\n", cx));
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let ops = Arc::new(Mutex::new(Vec::new()));
+    buffer.update(cx, |buffer, cx| {
+        let events = events.clone();
+        let ops = ops.clone();
+        cx.subscribe(&cx.handle(), move |_, _, event, _| match event.clone() {
+            Event::Operation(op) => ops.lock().push(op),
+            event => events.lock().push(event),
+        })
+        .detach();
+
+        assert_eq!(buffer.line_ending(), LineEnding::Windows);
+        assert!(buffer.has_mixed_line_endings());
+        assert!(!buffer.is_dirty());
+
+        buffer.set_line_ending(LineEnding::Unix, cx);
+        assert_eq!(buffer.text(), "one\ntwo\nthree\n");
+        assert!(!buffer.has_mixed_line_endings());
+        assert!(buffer.is_dirty());
+
+        // The conversion can be undone and redone like an edit.
+        buffer.undo(cx);
+        assert_eq!(buffer.line_ending(), LineEnding::Windows);
+        assert!(buffer.has_mixed_line_endings());
+        assert!(!buffer.is_dirty());
+        buffer.redo(cx);
+        assert_eq!(buffer.line_ending(), LineEnding::Unix);
+        assert!(!buffer.has_mixed_line_endings());
+        assert!(buffer.is_dirty());
+
+        buffer.did_save(buffer.version(), None, cx);
+        assert!(!buffer.is_dirty());
+
+        buffer.set_line_ending(LineEnding::Windows, cx);
+        assert!(buffer.is_dirty());
+        buffer.set_line_ending(LineEnding::Unix, cx);
+        assert!(!buffer.is_dirty());
+    });
+    assert_eq!(
+        mem::take(&mut *events.lock()),
+        vec![
+            Event::DirtyChanged,
+            Event::DirtyChanged,
+            Event::DirtyChanged,
+            Event::Saved,
+            Event::DirtyChanged,
+            Event::DirtyChanged
+        ]
+    );
+
+    // The conversions are replicated, including the ones made by undoing.
+    buffer.update(cx, |buffer, cx| {
+        buffer.undo(cx);
+        buffer.undo(cx);
+        buffer.undo(cx);
+        assert_eq!(buffer.line_ending(), LineEnding::Windows);
+        assert!(buffer.has_mixed_line_endings());
+        assert_eq!(buffer.line_ending_change_count(), 3);
+    });
+    let replica = cx.new_model(|cx| {
+        let mut replica = Buffer::remote(
+            BufferId::from(cx.entity_id().as_non_zero_u64()),
+            1,
+            Capability::ReadWrite,
+            "one\ntwo\nthree\n",
+        );
+        replica.apply_ops(mem::take(&mut *ops.lock()), cx).unwrap();
+        replica
+    });
+    assert_eq!(replica.read(cx).line_ending(), LineEnding::Windows);
+    assert!(replica.read(cx).has_mixed_line_endings());
+
+    // Reloading the file doesn't forget that it mixes line endings.
+    let diff = cx
+        .background_executor()
+        .block(buffer.read(cx).diff("one\r\ntwo\nfour\r\n".into(), cx));
+    buffer.update(cx, |buffer, cx| {
+        buffer.apply_diff(diff, cx);
+        buffer.did_reload(buffer.version(), LineEnding::Windows, None, cx);
+        assert_eq!(buffer.text(), "one\ntwo\nfour\n");
+        assert!(buffer.has_mixed_line_endings());
+
+        // The edit cleared the redo stack, and with it the conversions that were on it.
+        assert!(buffer.peek_redo_stack().is_none());
+        assert_eq!(buffer.line_ending_change_count(), 0);
+    });
+}
+
 #[gpui::test]
 fn test_select_language(cx: &mut AppContext) {
     init_settings(cx, |_| {});
@@ -353,6 +448,232 @@ fn test_edit_events(cx: &mut gpui::AppContext) {
     );
 }
 
+#[gpui::test]
+fn test_edit_ranges(cx: &mut gpui::AppContext) {
+    let ops = Arc::new(Mutex::new(Vec::new()));
+    let buffer = cx.new_model(|cx| {
+        let mut buffer = Buffer::local("one two one three one", cx);
+        buffer.set_group_interval(Duration::from_secs(0));
+        buffer
+    });
+    cx.subscribe(&buffer, {
+        let ops = ops.clone();
+        move |_, event, _| {
+            if let Event::Operation(op) = event {
+                ops.lock().push(op.clone());
+            }
+        }
+    })
+    .detach();
+
+    buffer.update(cx, |buffer, cx| {
+        buffer.edit_ranges([0..3, 8..11, 18..21], "1", None, cx);
+        assert_eq!(buffer.text(), "1 two 1 three 1");
+
+        // All of the ranges are replaced at once, so one undo restores all of them.
+        buffer.undo(cx);
+        assert_eq!(buffer.text(), "one two one three one");
+        buffer.redo(cx);
+        assert_eq!(buffer.text(), "1 two 1 three 1");
+    });
+
+    // The edit is sent to other replicas as a single operation, which survives
+    // being serialized.
+    let replica = cx.new_model(|cx| {
+        Buffer::remote(
+            BufferId::from(cx.entity_id().as_non_zero_u64()),
+            1,
+            Capability::ReadWrite,
+            "one two one three one",
+        )
+    });
+    let ops = mem::take(&mut *ops.lock());
+    let Operation::Buffer(text::Operation::Edit(edit)) = &ops[0] else {
+        panic!("expected an edit operation, got {:?}", ops[0]);
+    };
+    assert_eq!(edit.ranges.len(), 3);
+    replica.update(cx, |replica, cx| {
+        replica
+            .apply_ops(
+                ops[..1]
+                    .iter()
+                    .map(|op| deserialize_operation(proto::serialize_operation(op)).unwrap()),
+                cx,
+            )
+            .unwrap();
+        assert_eq!(replica.text(), "1 two 1 three 1");
+    });
+
+    // Edits can be given in any order.
+    buffer.update(cx, |buffer, cx| {
+        buffer.edit([(14..15, "3"), (0..1, "one"), (6..7, "2")], None, cx);
+        assert_eq!(buffer.text(), "one two 2 three 3");
+        buffer.edit([(3..3, "!"), (0..0, "<"), (3..3, "?")], None, cx);
+        assert_eq!(buffer.text(), "<one!? two 2 three 3");
+    });
+}
+
+#[gpui::test]
+fn test_remote_selections(cx: &mut gpui::AppContext) {
+    let ops = Arc::new(Mutex::new(Vec::new()));
+    let buffer = cx.new_model(|cx| Buffer::local("abc def", cx));
+    cx.subscribe(&buffer, {
+        let ops = ops.clone();
+        move |_, event, _| {
+            if let Event::Operation(op) = event {
+                ops.lock().push(op.clone());
+            }
+        }
+    })
+    .detach();
+    let replica = cx.new_model(|cx| {
+        Buffer::remote(
+            BufferId::from(cx.entity_id().as_non_zero_u64()),
+            1,
+            Capability::ReadWrite,
+            "abc def",
+        )
+    });
+
+    buffer.update(cx, |buffer, cx| {
+        for range in [0..3, 4..7] {
+            let selection = Selection {
+                id: 0,
+                start: buffer.anchor_before(range.start),
+                end: buffer.anchor_before(range.end),
+                reversed: false,
+                goal: SelectionGoal::None,
+            };
+            buffer.set_active_selections(Arc::from([selection]), false, Default::default(), cx);
+        }
+    });
+    let remote_selections = |replica: &Model<Buffer>, cx: &AppContext| {
+        let snapshot = replica.read(cx).snapshot();
+        snapshot
+            .remote_selections_in_range(Anchor::MIN..Anchor::MAX)
+            .map(|(replica_id, _, _, selections)| {
+                let ranges = selections
+                    .map(|selection| selection.range().to_offset(&snapshot))
+                    .collect::<Vec<_>>();
+                (replica_id, ranges)
+            })
+            .collect::<Vec<_>>()
+    };
+
+    // An update that arrives after a newer one from the same peer is ignored.
+    let ops = mem::take(&mut *ops.lock());
+    assert_eq!(ops.len(), 2);
+    replica.update(cx, |replica, cx| {
+        replica
+            .apply_ops(
+                ops.iter()
+                    .rev()
+                    .map(|op| deserialize_operation(proto::serialize_operation(op)).unwrap()),
+                cx,
+            )
+            .unwrap();
+    });
+    assert_eq!(remote_selections(&replica, cx), [(0, vec![4..7])]);
+
+    // The selections are anchored, so edits keep them on the same text.
+    replica.update(cx, |replica, cx| {
+        replica.edit([(0..0, "xx")], None, cx);
+    });
+    assert_eq!(remote_selections(&replica, cx), [(0, vec![6..9])]);
+
+    // The selections go away when the peer leaves.
+    replica.update(cx, |replica, cx| replica.remove_peer(0, cx));
+    assert!(remote_selections(&replica, cx).is_empty());
+}
+
+#[gpui::test]
+fn test_overlapping_diagnostics(cx: &mut AppContext) {
+    let buffer = cx.new_model(|cx| Buffer::local("let a = b + c;\n", cx));
+    let diagnostic = |severity, message: &str, group_id| Diagnostic {
+        severity,
+        message: message.to_string(),
+        group_id,
+        is_primary: true,
+        ..Default::default()
+    };
+
+    buffer.update(cx, |buffer, cx| {
+        let diagnostics = DiagnosticSet::new(
+            [
+                DiagnosticEntry {
+                    range: PointUtf16::new(0, 8)..PointUtf16::new(0, 9),
+                    diagnostic: diagnostic(DiagnosticSeverity::ERROR, "unknown b", 0),
+                },
+                DiagnosticEntry {
+                    range: PointUtf16::new(0, 4)..PointUtf16::new(0, 13),
+                    diagnostic: diagnostic(DiagnosticSeverity::WARNING, "unused a", 1),
+                },
+            ],
+            buffer,
+        );
+        buffer.update_diagnostics(LanguageServerId(0), diagnostics, cx);
+        let diagnostics = DiagnosticSet::new(
+            [DiagnosticEntry {
+                range: PointUtf16::new(0, 8)..PointUtf16::new(0, 13),
+                diagnostic: diagnostic(DiagnosticSeverity::HINT, "simplify", 0),
+            }],
+            buffer,
+        );
+        buffer.update_diagnostics(LanguageServerId(1), diagnostics, cx);
+    });
+
+    let diagnostics_in_range = |buffer: &Buffer, range: Range<usize>| {
+        buffer
+            .snapshot()
+            .diagnostics_in_range::<_, usize>(range, false)
+            .map(|entry| (entry.range, entry.diagnostic.message))
+            .collect::<Vec<_>>()
+    };
+
+    buffer.update(cx, |buffer, cx| {
+        // Diagnostics starting at the same position are ordered by severity.
+        assert_eq!(
+            diagnostics_in_range(buffer, 8..9),
+            [
+                (4..13, "unused a".to_string()),
+                (8..9, "unknown b".to_string()),
+                (8..13, "simplify".to_string()),
+            ]
+        );
+        assert!(diagnostics_in_range(buffer, 0..4).is_empty());
+
+        let snapshot = buffer.snapshot();
+        assert_eq!(snapshot.next_diagnostic(0, false).unwrap().range, 4..13);
+        assert_eq!(snapshot.next_diagnostic(4, false).unwrap().range, 8..9);
+        assert!(snapshot.next_diagnostic(8, false).is_none());
+        assert_eq!(snapshot.next_diagnostic(13, true).unwrap().range.start, 8);
+        assert_eq!(snapshot.next_diagnostic(8, true).unwrap().range, 4..13);
+        assert!(snapshot.next_diagnostic(4, true).is_none());
+
+        // Replacing one server's diagnostics leaves the other server's alone.
+        buffer.update_diagnostics(LanguageServerId(1), DiagnosticSet::default(), cx);
+        assert_eq!(
+            diagnostics_in_range(buffer, 0..buffer.len()),
+            [
+                (4..13, "unused a".to_string()),
+                (8..9, "unknown b".to_string()),
+            ]
+        );
+
+        // Deleting diagnosed text shrinks the diagnostics that contain it, and collapses
+        // the ones that only covered deleted text.
+        buffer.edit([(8..12, "")], None, cx);
+        assert_eq!(buffer.text(), "let a = c;\n");
+        assert_eq!(
+            diagnostics_in_range(buffer, 0..buffer.len()),
+            [
+                (4..9, "unused a".to_string()),
+                (8..8, "unknown b".to_string()),
+            ]
+        );
+    });
+}
+
 #[gpui::test]
 async fn test_apply_diff(cx: &mut TestAppContext) {
     let text = "a\nbb\nccc\ndddd\neeeee\nffffff\n";
@@ -376,6 +697,42 @@ async fn test_apply_diff(cx: &mut TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_remove_trailing_whitespace_from_modified_lines(cx: &mut gpui::TestAppContext) {
+    let buffer = cx.new_model(|cx| Buffer::local("a  \nb  \nc  ", cx));
+    buffer.update(cx, |buffer, cx| {
+        buffer.edit([(Point::new(1, 0)..Point::new(1, 0), "x")], None, cx);
+    });
+
+    let diff = buffer
+        .update(cx, |buffer, cx| {
+            buffer.remove_trailing_whitespace(TrailingWhitespaceLines::ModifiedLines, cx)
+        })
+        .await;
+    buffer.update(cx, |buffer, cx| {
+        buffer.apply_diff(diff, cx);
+        assert_eq!(buffer.text(), "a  \nxb\nc  ");
+    });
+}
+
+#[gpui::test]
+async fn test_normalize_whitespace_with_crlf_line_endings(cx: &mut gpui::TestAppContext) {
+    // The final line consists only of whitespace.
+    let buffer = cx.new_model(|cx| Buffer::local("a  \r\nb\t\r\n   ", cx));
+
+    let diff = buffer
+        .update(cx, |buffer, cx| {
+            buffer.remove_trailing_whitespace(TrailingWhitespaceLines::AllLines, cx)
+        })
+        .await;
+    buffer.update(cx, |buffer, cx| {
+        buffer.apply_diff(diff, cx);
+        buffer.ensure_final_newline(cx);
+        assert_eq!(buffer.text(), "a\nb\n");
+        assert_eq!(buffer.line_ending(), LineEnding::Windows);
+    });
+}
+
 #[gpui::test(iterations = 10)]
 async fn test_normalize_whitespace(cx: &mut gpui::TestAppContext) {
     let text = [
@@ -392,7 +749,9 @@ async fn test_normalize_whitespace(cx: &mut gpui::TestAppContext) {
 
     // Spawn a task to format the buffer's whitespace.
     // Pause so that the foratting task starts running.
-    let format = buffer.update(cx, |buffer, cx| buffer.remove_trailing_whitespace(cx));
+    let format = buffer.update(cx, |buffer, cx| {
+        buffer.remove_trailing_whitespace(TrailingWhitespaceLines::AllLines, cx)
+    });
     smol::future::yield_now().await;
 
     // Edit the buffer while the normalization task is running.
@@ -1048,6 +1407,79 @@ fn test_range_for_syntax_ancestor(cx: &mut AppContext) {
     }
 }
 
+#[gpui::test]
+fn test_enclosing_node_range(cx: &mut AppContext) {
+    let mut assert = |language, text: &str, position: &str, expected_nodes: &[&str]| {
+        cx.new_model(|cx| {
+            let buffer = Buffer::local(text, cx).with_language(Arc::new(language), cx);
+            let snapshot = buffer.snapshot();
+            let offset = text.find(position).unwrap();
+            for (ix, expected_node) in expected_nodes.iter().enumerate() {
+                assert_eq!(
+                    snapshot
+                        .enclosing_node_range(offset..offset, ix + 1)
+                        .map(|range| &text[range]),
+                    Some(*expected_node),
+                    "{} steps up",
+                    ix + 1
+                );
+            }
+            assert_eq!(
+                snapshot.enclosing_node_range(offset..offset, expected_nodes.len() + 1),
+                None
+            );
+            buffer
+        });
+    };
+
+    assert(
+        rust_lang(),
+        "fn a() { let b = c(d); }",
+        "d)",
+        &[
+            "d",
+            "(d)",
+            "c(d)",
+            "let b = c(d);",
+            "{ let b = c(d); }",
+            "fn a() { let b = c(d); }",
+        ],
+    );
+    assert(
+        json_lang(),
+        r#"{"a": [1, {"b": true}]}"#,
+        "true",
+        &[
+            "true",
+            r#""b": true"#,
+            r#"{"b": true}"#,
+            r#"[1, {"b": true}]"#,
+            r#""a": [1, {"b": true}]"#,
+            r#"{"a": [1, {"b": true}]}"#,
+        ],
+    );
+}
+
+#[gpui::test]
+fn test_bracket_ranges_without_grammar(cx: &mut AppContext) {
+    let mut assert = |selection_text, bracket_pair_texts| {
+        let language = Language::new(LanguageConfig::default(), None);
+        assert_bracket_pairs(selection_text, bracket_pair_texts, language, cx)
+    };
+
+    assert(
+        "f(a, [b]ˇ) {}",
+        vec!["f«(»a, [b]«)» {}", "f(a, «[»b«]») {}"],
+    );
+
+    // Brackets within quotes are skipped, and mismatched closing brackets are ignored.
+    assert("f(\"(\", ˇ]) {}", vec!["f«(»\"(\", ]«)» {}"]);
+    assert("say(\"hiˇ\")", vec!["say«(»\"hi\"«)»", "say(«\"»hi«\"»)"]);
+
+    // Unmatched brackets don't pair with anything.
+    assert("ˇ(a", vec![]);
+}
+
 #[gpui::test]
 fn test_autoindent_with_soft_tabs(cx: &mut AppContext) {
     init_settings(cx, |_| {});
@@ -1088,6 +1520,89 @@ fn test_autoindent_with_soft_tabs(cx: &mut AppContext) {
     });
 }
 
+#[gpui::test]
+fn test_autoindent_without_grammar(cx: &mut AppContext) {
+    init_settings(cx, |_| {});
+
+    cx.new_model(|cx| {
+        let bracket_pair = |start: &str, end: &str| BracketPair {
+            start: start.into(),
+            end: end.into(),
+            close: true,
+            newline: true,
+        };
+        let language = Arc::new(Language::new(
+            LanguageConfig {
+                brackets: BracketPairConfig {
+                    pairs: vec![
+                        bracket_pair("{", "}"),
+                        bracket_pair("[", "]"),
+                        bracket_pair("(", ")"),
+                        bracket_pair("\"", "\""),
+                    ],
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            None,
+        ));
+        let mut buffer = Buffer::local("f() {", cx).with_language(language, cx);
+
+        // A newline after an opening bracket is indented.
+        buffer.edit([(5..5, "\n")], Some(AutoindentMode::EachLine), cx);
+        assert_eq!(buffer.text(), "f() {\n    ");
+
+        // Typing the closing bracket outdents the line to match the opening one.
+        buffer.edit(
+            [(Point::new(1, 4)..Point::new(1, 4), "}")],
+            Some(AutoindentMode::EachLine),
+            cx,
+        );
+        assert_eq!(buffer.text(), "f() {\n}");
+
+        buffer.edit(
+            [(
+                Point::new(1, 1)..Point::new(1, 1),
+                "\na {\nb [\nc\n]\n}\nif x:\ny",
+            )],
+            Some(AutoindentMode::EachLine),
+            cx,
+        );
+        assert_eq!(
+            buffer.text(),
+            "f() {\n}\na {\n    b [\n        c\n    ]\n}\nif x:\n    y"
+        );
+
+        buffer
+    });
+
+    // Languages that don't define brackets, like plain text, aren't indented.
+    cx.new_model(|cx| {
+        let language = Arc::new(Language::new(LanguageConfig::default(), None));
+        let mut buffer = Buffer::local("f() {", cx).with_language(language, cx);
+        buffer.edit([(5..5, "\n}")], Some(AutoindentMode::EachLine), cx);
+        assert_eq!(buffer.text(), "f() {\n}");
+        buffer
+    });
+}
+
+#[gpui::test]
+fn test_autoindent_disabled(cx: &mut AppContext) {
+    init_settings(cx, |settings| {
+        settings.defaults.auto_indent = Some(false);
+    });
+
+    cx.new_model(|cx| {
+        let text = "fn a() {}";
+        let mut buffer = Buffer::local(text, cx).with_language(Arc::new(rust_lang()), cx);
+
+        buffer.edit([(8..8, "\n\n")], Some(AutoindentMode::EachLine), cx);
+        assert_eq!(buffer.text(), "fn a() {\n\n}");
+
+        buffer
+    });
+}
+
 #[gpui::test]
 fn test_autoindent_with_hard_tabs(cx: &mut AppContext) {
     init_settings(cx, |settings| {
@@ -1130,6 +1645,99 @@ fn test_autoindent_with_hard_tabs(cx: &mut AppContext) {
     });
 }
 
+#[gpui::test]
+fn test_detect_indent(cx: &mut AppContext) {
+    init_settings(cx, |_| {});
+
+    let detect = |text: &str, cx: &mut AppContext| {
+        let buffer = cx.new_model(|cx| {
+            let mut buffer = Buffer::local(text, cx);
+            buffer.detect_indent(cx);
+            buffer
+        });
+        buffer.read(cx).detected_indent()
+    };
+
+    assert_eq!(
+        detect("fn a() {\n\tif b {\n\t\tc();\n\t}\n}\n", cx),
+        Some(IndentSize::tab())
+    );
+    assert_eq!(
+        detect("fn a() {\n  if b {\n    c();\n  }\n}\n", cx),
+        Some(IndentSize::spaces(2))
+    );
+    assert_eq!(detect("a\nb\n\nc\n", cx), None);
+
+    // Continuation lines and the middle of block comments don't count.
+    let text = indoc! {"
+        /**
+         * A function.
+         */
+        fn a(b: u32,
+             c: u32) {
+            if b {
+                d(e,
+                  f);
+            }
+            if c {
+                g();
+            }
+        }
+    "};
+    assert_eq!(detect(text, cx), Some(IndentSize::spaces(4)));
+
+    // Huge files are sampled.
+    let text = "a {\n  b\n}\n".repeat(10_000);
+    assert_eq!(detect(&text, cx), Some(IndentSize::spaces(2)));
+}
+
+#[gpui::test]
+fn test_autoindent_with_detected_indent(cx: &mut AppContext) {
+    init_settings(cx, |settings| {
+        settings.defaults.tab_size = NonZeroU32::new(4);
+    });
+
+    cx.new_model(|cx| {
+        let text = "fn a() {\n  b();\n}";
+        let mut buffer = Buffer::local(text, cx).with_language(Arc::new(rust_lang()), cx);
+        buffer.detect_indent(cx);
+        assert_eq!(buffer.detected_indent(), Some(IndentSize::spaces(2)));
+
+        buffer.edit([(15..15, "\nc();")], Some(AutoindentMode::EachLine), cx);
+        assert_eq!(buffer.text(), "fn a() {\n  b();\n  c();\n}");
+
+        // A picked indentation wins over the detected one.
+        buffer.set_indent_override(Some(IndentSize::tab()), cx);
+        assert_eq!(
+            buffer.snapshot().language_indent_size_at(0, cx),
+            IndentSize::tab()
+        );
+        buffer.set_indent_override(None, cx);
+        assert_eq!(
+            buffer.snapshot().language_indent_size_at(0, cx),
+            IndentSize::spaces(2)
+        );
+
+        buffer
+    });
+
+    // The detected indentation is ignored when detection is disabled.
+    cx.update_global::<SettingsStore, _>(|settings, cx| {
+        settings.update_user_settings::<AllLanguageSettings>(cx, |settings| {
+            settings.defaults.detect_indentation = Some(false);
+        });
+    });
+    cx.new_model(|cx| {
+        let mut buffer = Buffer::local("fn a() {\n  b();\n}", cx);
+        buffer.detect_indent(cx);
+        assert_eq!(
+            buffer.snapshot().language_indent_size_at(0, cx),
+            IndentSize::spaces(4)
+        );
+        buffer
+    });
+}
+
 #[gpui::test]
 fn test_autoindent_does_not_adjust_lines_with_unchanged_suggestion(cx: &mut AppContext) {
     init_settings(cx, |_| {});
@@ -1963,6 +2571,89 @@ fn test_language_scope_at_with_rust(cx: &mut AppContext) {
     });
 }
 
+#[gpui::test]
+fn test_comment_and_string_ranges(cx: &mut AppContext) {
+    init_settings(cx, |_| {});
+
+    cx.new_model(|cx| {
+        let text = r#"
+            // A comment
+            fn main() {
+                let s = "a string";
+                /* another one */
+            }
+        "#
+        .unindent();
+        let buffer = Buffer::local(text.clone(), cx).with_language(Arc::new(rust_lang()), cx);
+        let snapshot = buffer.snapshot();
+
+        let range_of = |substring: &str| {
+            let start = text.find(substring).unwrap();
+            start..start + substring.len()
+        };
+        assert_eq!(
+            snapshot.comment_and_string_ranges(0..text.len()),
+            &[
+                range_of("// A comment"),
+                range_of("\"a string\""),
+                range_of("/* another one */"),
+            ]
+        );
+
+        // Only the parts within the range are returned.
+        let string_start = text.find("string").unwrap();
+        assert_eq!(
+            snapshot.comment_and_string_ranges(string_start..text.len()),
+            &[
+                string_start..range_of("\"a string\"").end,
+                range_of("/* another one */"),
+            ]
+        );
+        assert_eq!(
+            snapshot.comment_and_string_ranges(range_of("fn main")),
+            &[] as &[Range<usize>]
+        );
+
+        buffer
+    });
+}
+
+#[gpui::test]
+fn test_code_block_ranges(cx: &mut AppContext) {
+    init_settings(cx, |_| {});
+
+    cx.new_model(|cx| {
+        let text = r#"
+            Some text.
+
+            ```rust
+            fn main() {}
+            ```
+
+            More text.
+        "#
+        .unindent();
+        let language = Language::new(
+            LanguageConfig {
+                name: "Markdown".into(),
+                ..Default::default()
+            },
+            Some(tree_sitter_markdown::language()),
+        );
+        let buffer = Buffer::local(text.clone(), cx).with_language(Arc::new(language), cx);
+        let snapshot = buffer.snapshot();
+
+        let ranges = snapshot.code_block_ranges(0..text.len());
+        assert_eq!(ranges.len(), 1);
+        let code_block = &text[ranges[0].clone()];
+        assert!(code_block.starts_with("```rust"), "{code_block:?}");
+        assert!(code_block.contains("fn main() {}"), "{code_block:?}");
+        assert!(!code_block.contains("text"), "{code_block:?}");
+
+        buffer
+    });
+}
+
 #[gpui::test]
 fn test_language_scope_at_with_combined_injections(cx: &mut AppContext) {
     init_settings(cx, |_| {});
@@ -2052,6 +2743,46 @@ fn test_serialization(cx: &mut gpui::AppContext) {
     assert_eq!(buffer2.read(cx).text(), "abcDF");
 }
 
+#[gpui::test]
+fn test_serializing_operations_together(cx: &mut gpui::AppContext) {
+    let buffer = cx.new_model(|cx| {
+        let mut buffer = Buffer::local("", cx);
+        for ix in 0..40 {
+            buffer.edit([(ix..ix, "a")], None, cx);
+        }
+        buffer.undo(cx);
+        buffer
+    });
+    let operations = cx
+        .background_executor()
+        .block(buffer.read(cx).serialize_ops(None, cx))
+        .into_iter()
+        .map(|op| deserialize_operation(op).unwrap())
+        .collect::<Vec<_>>();
+
+    // Versions are delta encoded against the earlier operations of the same message.
+    let messages = proto::serialize_operations(&operations);
+    let delta_encoded = messages
+        .iter()
+        .filter(|message| match &message.variant {
+            Some(rpc::proto::operation::Variant::Edit(edit)) => edit.version.is_empty(),
+            Some(rpc::proto::operation::Variant::Undo(undo)) => undo.version.is_empty(),
+            _ => false,
+        })
+        .count();
+    assert!(delta_encoded > 0);
+    assert_eq!(
+        proto::deserialize_operations(messages.clone()).unwrap(),
+        operations
+    );
+
+    // Such versions can't be decoded without the operations that came before them.
+    assert!(messages
+        .into_iter()
+        .skip(2)
+        .any(|message| deserialize_operation(message).is_err()));
+}
+
 #[gpui::test]
 async fn test_find_matching_indent(cx: &mut TestAppContext) {
     cx.update(|cx| init_settings(cx, |_| {}));
@@ -2157,6 +2888,17 @@ fn test_random_collaboration(cx: &mut AppContext, mut rng: StdRng) {
     let mut replica_ids = Vec::new();
     let mut buffers = Vec::new();
     let network = Arc::new(Mutex::new(Network::new(rng.clone())));
+    // Operations are sent in batches, like the project sends them, so that their versions are
+    // encoded against each other on the way.
+    let outboxes = Arc::new(Mutex::new(BTreeMap::<ReplicaId, Vec<Operation>>::default()));
+    let flush_outbox = |replica_id: ReplicaId| {
+        let operations = outboxes.lock().remove(&replica_id).unwrap_or_default();
+        if !operations.is_empty() {
+            network
+                .lock()
+                .broadcast(replica_id, vec![proto::serialize_operations(&operations)]);
+        }
+    };
     let base_buffer = cx.new_model(|cx| Buffer::local(base_text.as_str(), cx));
 
     for i in 0..rng.gen_range(min_peers..=max_peers) {
@@ -2175,12 +2917,14 @@ fn test_random_collaboration(cx: &mut AppContext, mut rng: StdRng) {
                 )
                 .unwrap();
             buffer.set_group_interval(Duration::from_millis(rng.gen_range(0..=200)));
-            let network = network.clone();
+            let outboxes = outboxes.clone();
             cx.subscribe(&cx.handle(), move |buffer, _, event, _| {
                 if let Event::Operation(op) = event {
-                    network
+                    outboxes
                         .lock()
-                        .broadcast(buffer.replica_id(), vec![proto::serialize_operation(op)]);
+                        .entry(buffer.replica_id())
+                        .or_default()
+                        .push(op.clone());
                 }
             })
             .detach();
@@ -2268,6 +3012,10 @@ fn test_random_collaboration(cx: &mut AppContext, mut rng: StdRng) {
                 mutation_count -= 1;
             }
             50..=59 if replica_ids.len() < max_peers => {
+                // The replica that gets replaced has to send its operations first.
+                for replica_id in &replica_ids {
+                    flush_outbox(*replica_id);
+                }
                 let old_buffer_state = buffer.read(cx).to_proto();
                 let old_buffer_ops = cx
                     .background_executor()
@@ -2303,13 +3051,14 @@ fn test_random_collaboration(cx: &mut AppContext, mut rng: StdRng) {
                         new_buffer.text()
                     );
                     new_buffer.set_group_interval(Duration::from_millis(rng.gen_range(0..=200)));
-                    let network = network.clone();
+                    let outboxes = outboxes.clone();
                     cx.subscribe(&cx.handle(), move |buffer, _, event, _| {
                         if let Event::Operation(op) = event {
-                            network.lock().broadcast(
-                                buffer.replica_id(),
-                                vec![proto::serialize_operation(op)],
-                            );
+                            outboxes
+                                .lock()
+                                .entry(buffer.replica_id())
+                                .or_default()
+                                .push(op.clone());
                         }
                     })
                     .detach();
@@ -2326,7 +3075,8 @@ fn test_random_collaboration(cx: &mut AppContext, mut rng: StdRng) {
                             .lock()
                             .receive(new_replica_id)
                             .into_iter()
-                            .map(|op| proto::deserialize_operation(op).unwrap());
+                            .flat_map(|message| proto::deserialize_operations(message).unwrap())
+                            .collect::<Vec<_>>();
                         if ops.len() > 0 {
                             log::info!(
                                 "peer {} (version: {:?}) applying {} ops from the network. {:?}",
@@ -2355,7 +3105,8 @@ fn test_random_collaboration(cx: &mut AppContext, mut rng: StdRng) {
                     .lock()
                     .receive(replica_id)
                     .into_iter()
-                    .map(|op| proto::deserialize_operation(op).unwrap());
+                    .flat_map(|message| proto::deserialize_operations(message).unwrap())
+                    .collect::<Vec<_>>();
                 if ops.len() > 0 {
                     log::info!(
                         "peer {} (version: {:?}) applying {} ops from the network. {:?}",
@@ -2377,7 +3128,12 @@ fn test_random_collaboration(cx: &mut AppContext, mut rng: StdRng) {
             buffer.read(cx).check_invariants();
         }
 
-        if mutation_count == 0 && network.lock().is_idle() {
+        for replica_id in &replica_ids {
+            if mutation_count == 0 || rng.gen_bool(0.3) {
+                flush_outbox(*replica_id);
+            }
+        }
+        if mutation_count == 0 && network.lock().is_idle() && outboxes.lock().is_empty() {
             break;
         }
     }
@@ -2397,6 +3153,12 @@ fn test_random_collaboration(cx: &mut AppContext, mut rng: StdRng) {
             "Replica {} text != Replica 0 text",
             buffer.replica_id()
         );
+        assert_eq!(
+            buffer.deleted_text(),
+            first_buffer.deleted_text(),
+            "Replica {} deleted text != Replica 0 deleted text",
+            buffer.replica_id()
+        );
         assert_eq!(
             buffer
                 .diagnostics_in_range::<_, usize>(0..buffer.len(), false)
@@ -2706,3 +3468,21 @@ fn init_settings(cx: &mut AppContext, f: fn(&mut AllLanguageSettingsContent)) {
         settings.update_user_settings::<AllLanguageSettings>(cx, f);
     });
 }
+
+#[test]
+fn test_mtimes_match_on_coarse_filesystems() {
+    let precise = SystemTime::UNIX_EPOCH + Duration::new(1_700_000_000, 123_456_789);
+    let truncated = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let later = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_001);
+
+    assert!(crate::buffer::mtimes_match(Some(precise), Some(precise)));
+    assert!(crate::buffer::mtimes_match(Some(precise), Some(truncated)));
+    assert!(crate::buffer::mtimes_match(Some(truncated), Some(precise)));
+    assert!(!crate::buffer::mtimes_match(Some(truncated), Some(later)));
+    assert!(!crate::buffer::mtimes_match(
+        Some(precise),
+        Some(precise + Duration::from_millis(1))
+    ));
+    assert!(!crate::buffer::mtimes_match(Some(precise), None));
+    assert!(crate::buffer::mtimes_match(None, None));
+}
@@ -0,0 +1,254 @@
+//! Reads [EditorConfig](https://editorconfig.org) files into language settings.
+//!
+//! An `.editorconfig` file applies to the directory that contains it, on top of the
+//! project settings of that directory. Its sections that match every file become the
+//! default language settings, and the ones that match file names of some languages
+//! become the settings of those languages. Sections that match paths can't be expressed
+//! by the settings and are ignored.
+
+use crate::language_settings::{AllLanguageSettingsContent, LanguageSettingsContent};
+use globset::Glob;
+use gpui::Global;
+use std::{collections::BTreeMap, num::NonZeroU32, path::Path, sync::Arc};
+
+/// The name of EditorConfig files.
+pub const EDITORCONFIG_FILE_NAME: &str = ".editorconfig";
+
+/// The parsed contents of an `.editorconfig` file.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct EditorConfig {
+    sections: Vec<Section>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Section {
+    pattern: String,
+    properties: Vec<(String, String)>,
+}
+
+impl EditorConfig {
+    /// Parses the contents of an `.editorconfig` file. Lines that aren't understood are skipped,
+    /// like other EditorConfig implementations do.
+    pub fn parse(content: &str) -> Self {
+        let mut sections = Vec::new();
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+
+            if let Some(pattern) = line
+                .strip_prefix('[')
+                .and_then(|line| line.strip_suffix(']'))
+            {
+                sections.push(Section {
+                    pattern: pattern.to_string(),
+                    properties: Vec::new(),
+                });
+            } else if let Some((key, value)) = line.split_once('=') {
+                // Properties before the first section, such as `root`, don't apply to files.
+                if let Some(section) = sections.last_mut() {
+                    section
+                        .properties
+                        .push((key.trim().to_lowercase(), value.trim().to_lowercase()));
+                }
+            }
+        }
+        Self { sections }
+    }
+
+    /// Returns the settings this file defines, given the path suffixes of every language.
+    pub fn settings(
+        &self,
+        language_path_suffixes: &[(Arc<str>, Vec<String>)],
+    ) -> AllLanguageSettingsContent {
+        let mut settings = AllLanguageSettingsContent::default();
+        for section in &self.sections {
+            if section.pattern == "*" || section.pattern == "**" {
+                section.apply_to(&mut settings.defaults);
+                continue;
+            }
+            if section.pattern.contains('/') {
+                log::debug!(
+                    "ignoring .editorconfig section for paths {}",
+                    section.pattern
+                );
+                continue;
+            }
+
+            let Some(glob) = Glob::new(&section.pattern)
+                .map(|glob| glob.compile_matcher())
+                .ok()
+            else {
+                log::warn!("invalid .editorconfig section {}", section.pattern);
+                continue;
+            };
+            for (language_name, path_suffixes) in language_path_suffixes {
+                let matches_language = path_suffixes.iter().any(|suffix| {
+                    glob.is_match(suffix.as_str()) || glob.is_match(format!("file.{suffix}"))
+                });
+                if matches_language {
+                    section.apply_to(settings.languages.entry(language_name.clone()).or_default());
+                }
+            }
+        }
+        settings
+    }
+}
+
+/// The settings read from the `.editorconfig` files of each worktree directory.
+///
+/// They are part of the local settings of those directories too, and are kept here to tell
+/// which of the local settings came from an `.editorconfig` file.
+#[derive(Default)]
+pub struct EditorConfigSettings {
+    settings: BTreeMap<(usize, Arc<Path>), AllLanguageSettingsContent>,
+}
+
+impl Global for EditorConfigSettings {}
+
+impl EditorConfigSettings {
+    /// Sets the settings of the `.editorconfig` file in a worktree directory, or removes them
+    /// when the file is gone.
+    pub fn set(
+        &mut self,
+        root_id: usize,
+        directory: Arc<Path>,
+        settings: Option<AllLanguageSettingsContent>,
+    ) {
+        match settings {
+            Some(settings) => {
+                self.settings.insert((root_id, directory), settings);
+            }
+            None => {
+                self.settings.remove(&(root_id, directory));
+            }
+        }
+    }
+
+    /// Removes the settings of every `.editorconfig` file in a worktree.
+    pub fn clear(&mut self, root_id: usize) {
+        self.settings.retain(|(id, _), _| *id != root_id);
+    }
+
+    /// Returns the settings of the `.editorconfig` file in a worktree directory.
+    pub fn get(
+        &self,
+        root_id: usize,
+        directory: &Arc<Path>,
+    ) -> Option<&AllLanguageSettingsContent> {
+        self.settings.get(&(root_id, directory.clone()))
+    }
+}
+
+impl Section {
+    fn apply_to(&self, settings: &mut LanguageSettingsContent) {
+        let property = |name: &str| {
+            self.properties
+                .iter()
+                .rev()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.as_str())
+        };
+        let number = |name: &str| property(name).and_then(|value| value.parse::<u32>().ok());
+
+        match property("indent_style") {
+            Some("tab") => settings.hard_tabs = Some(true),
+            Some("space") => settings.hard_tabs = Some(false),
+            _ => {}
+        }
+        let tab_size = match property("indent_size") {
+            Some("tab") | None => number("tab_width"),
+            Some(_) => number("indent_size"),
+        };
+        if let Some(tab_size) = tab_size.and_then(NonZeroU32::new) {
+            settings.tab_size = Some(tab_size);
+        }
+        if settings.hard_tabs.is_some() || settings.tab_size.is_some() {
+            // The file says how to indent, so it's not guessed from the existing lines.
+            settings.detect_indentation = Some(false);
+        }
+        if let Some(max_line_length) = number("max_line_length") {
+            settings.preferred_line_length = Some(max_line_length);
+        }
+        if let Some(trim) = boolean(property("trim_trailing_whitespace")) {
+            settings.remove_trailing_whitespace_on_save = Some(trim);
+        }
+        if let Some(insert) = boolean(property("insert_final_newline")) {
+            settings.ensure_final_newline_on_save = Some(insert);
+        }
+    }
+}
+
+fn boolean(value: Option<&str>) -> Option<bool> {
+    match value? {
+        "true" => Some(true),
+        "false" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_editorconfig_settings() {
+        let editorconfig = EditorConfig::parse(
+            r#"
+            # top-most EditorConfig file
+            root = true
+
+            [*]
+            indent_style = space
+            indent_size = 4
+            trim_trailing_whitespace = true
+            insert_final_newline = true
+
+            [Makefile]
+            indent_style = tab
+            tab_width = 8
+
+            [*.{yml,yaml}]
+            indent_size = 2
+            max_line_length = 120
+
+            [docs/*.md]
+            trim_trailing_whitespace = false
+            "#,
+        );
+        let languages = [
+            (
+                Arc::from("Make"),
+                vec!["Makefile".to_string(), "mk".to_string()],
+            ),
+            (
+                Arc::from("YAML"),
+                vec!["yml".to_string(), "yaml".to_string()],
+            ),
+            (Arc::from("Markdown"), vec!["md".to_string()]),
+        ];
+        let settings = editorconfig.settings(&languages);
+
+        assert_eq!(settings.defaults.hard_tabs, Some(false));
+        assert_eq!(settings.defaults.tab_size, NonZeroU32::new(4));
+        assert_eq!(settings.defaults.detect_indentation, Some(false));
+        assert_eq!(
+            settings.defaults.remove_trailing_whitespace_on_save,
+            Some(true)
+        );
+        assert_eq!(settings.defaults.ensure_final_newline_on_save, Some(true));
+
+        let make = &settings.languages["Make"];
+        assert_eq!(make.hard_tabs, Some(true));
+        assert_eq!(make.tab_size, NonZeroU32::new(8));
+
+        let yaml = &settings.languages["YAML"];
+        assert_eq!(yaml.hard_tabs, None);
+        assert_eq!(yaml.tab_size, NonZeroU32::new(2));
+        assert_eq!(yaml.preferred_line_length, Some(120));
+
+        // Sections for paths don't turn into language settings.
+        assert!(!settings.languages.contains_key("Markdown"));
+    }
+}
@@ -0,0 +1,103 @@
+//! Detects how a file is indented from a sample of its lines, so that editing it keeps
+//! indenting it the same way, whatever the indentation settings say.
+
+use std::ops::Range;
+
+use text::{BufferSnapshot, Point};
+
+use crate::IndentSize;
+
+/// How many lines are looked at. Longer files are sampled in evenly spaced blocks of lines,
+/// so that detection stays cheap on huge files.
+const MAX_SAMPLED_LINES: u32 = 1000;
+const SAMPLED_BLOCKS: u32 = 10;
+
+/// The indent widths that can be detected, in the order in which they win ties.
+const INDENT_WIDTHS: [u32; 3] = [4, 2, 8];
+
+enum LineIndent {
+    Blank,
+    /// A line that doesn't tell anything about the indentation, like the middle of a
+    /// block comment.
+    Ignored,
+    Tabs,
+    Spaces(u32),
+}
+
+/// Returns the indentation the given text uses, or `None` if it doesn't indent consistently
+/// enough to tell.
+///
+/// Lines indented with tabs are weighed against lines indented with spaces. For spaces, the
+/// width is the amount by which a line's indentation most often grows compared to the previous
+/// line's, so that continuation lines aligned with something on the line above are outvoted.
+pub(crate) fn detect_indent(text: &BufferSnapshot) -> Option<IndentSize> {
+    let mut tab_lines = 0;
+    let mut space_lines = 0;
+    let mut width_votes = [0; INDENT_WIDTHS.len()];
+
+    for rows in sampled_rows(text.max_point().row + 1) {
+        let mut previous_spaces = None;
+        for row in rows {
+            match line_indent(text, row) {
+                LineIndent::Blank | LineIndent::Ignored => continue,
+                LineIndent::Tabs => {
+                    tab_lines += 1;
+                    previous_spaces = None;
+                }
+                LineIndent::Spaces(spaces) => {
+                    if spaces > 1 {
+                        space_lines += 1;
+                    }
+                    if let Some(previous_spaces) = previous_spaces {
+                        let growth = spaces.saturating_sub(previous_spaces);
+                        if let Some(ix) = INDENT_WIDTHS.iter().position(|width| *width == growth) {
+                            width_votes[ix] += 1;
+                        }
+                    }
+                    previous_spaces = Some(spaces);
+                }
+            }
+        }
+    }
+
+    if tab_lines > space_lines {
+        return Some(IndentSize::tab());
+    }
+    let (ix, votes) = width_votes
+        .iter()
+        .enumerate()
+        .rev()
+        .max_by_key(|(_, votes)| **votes)?;
+    (*votes > 0).then(|| IndentSize::spaces(INDENT_WIDTHS[ix]))
+}
+
+fn sampled_rows(row_count: u32) -> Vec<Range<u32>> {
+    if row_count <= MAX_SAMPLED_LINES {
+        return vec![0..row_count];
+    }
+
+    let block_len = MAX_SAMPLED_LINES / SAMPLED_BLOCKS;
+    let stride = (row_count - block_len) / (SAMPLED_BLOCKS - 1);
+    (0..SAMPLED_BLOCKS)
+        .map(|block| {
+            let start = block * stride;
+            start..start + block_len
+        })
+        .collect()
+}
+
+fn line_indent(text: &BufferSnapshot, row: u32) -> LineIndent {
+    let mut spaces = 0;
+    for ch in text.chars_at(Point::new(row, 0)) {
+        match ch {
+            ' ' => spaces += 1,
+            '\t' if spaces == 0 => return LineIndent::Tabs,
+            '\t' => return LineIndent::Ignored,
+            '\n' | '\r' => return LineIndent::Blank,
+            // The continuation lines of block comments are aligned with the comment's start.
+            '*' => return LineIndent::Ignored,
+            _ => return LineIndent::Spaces(spaces),
+        }
+    }
+    LineIndent::Blank
+}
@@ -8,9 +8,12 @@
 //! Notably we do *not* assign a single language to a single file; in real world a single file can consist of multiple programming languages - HTML is a good example of that - and `language` crate tends to reflect that status quo in its API.
 mod buffer;
 mod diagnostic_set;
+pub mod editorconfig;
 mod highlight_map;
+mod indent_detection;
 mod language_registry;
 pub mod language_settings;
+mod marks;
 mod outline;
 pub mod proto;
 mod syntax_map;
@@ -70,9 +73,10 @@ pub use language_registry::{
     PendingLanguageServer, QUERY_FILENAME_PREFIXES,
 };
 pub use lsp::LanguageServerId;
+pub use marks::{Mark, MarkId, MarkName, Marks};
 pub use outline::{render_item, Outline, OutlineItem};
 pub use syntax_map::{OwnedSyntaxLayer, SyntaxLayer};
-pub use text::{AnchorRangeExt, LineEnding};
+pub use text::{AnchorRangeExt, Encoding, LineEnding};
 pub use tree_sitter::{Node, Parser, Tree, TreeCursor};
 
 /// Initializes the `language` crate.
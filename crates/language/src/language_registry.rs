@@ -398,6 +398,28 @@ impl LanguageRegistry {
         result
     }
 
+    /// Returns the file extensions and names that each language is detected by.
+    pub fn language_path_suffixes(&self) -> Vec<(Arc<str>, Vec<String>)> {
+        let state = self.state.read();
+        state
+            .available_languages
+            .iter()
+            .filter(|language| !language.loaded)
+            .map(|language| {
+                (
+                    language.name.clone(),
+                    language.matcher.path_suffixes.clone(),
+                )
+            })
+            .chain(state.languages.iter().map(|language| {
+                (
+                    language.name(),
+                    language.config.matcher.path_suffixes.clone(),
+                )
+            }))
+            .collect()
+    }
+
     pub fn grammar_names(&self) -> Vec<Arc<str>> {
         let state = self.state.read();
         let mut result = state.grammars.keys().cloned().collect::<Vec<_>>();
@@ -436,7 +458,16 @@ impl LanguageRegistry {
 
     pub fn set_theme(&self, theme: Arc<Theme>) {
         let mut state = self.state.write();
+        // This is called whenever the settings change, and the highlight maps only need to be
+        // rebuilt when the syntax styles did.
+        let syntax_changed = state
+            .theme
+            .as_ref()
+            .map_or(true, |current| current.syntax() != theme.syntax());
         state.theme = Some(theme.clone());
+        if !syntax_changed {
+            return;
+        }
         for language in &state.languages {
             language.set_theme(theme.syntax());
         }
@@ -1,6 +1,6 @@
 //! Provides `language`-related settings.
 
-use crate::{File, Language, LanguageServerName};
+use crate::{editorconfig::EditorConfigSettings, File, Language, LanguageServerName};
 use anyhow::Result;
 use collections::{HashMap, HashSet};
 use globset::{Glob, GlobMatcher, GlobSet, GlobSetBuilder};
@@ -11,7 +11,7 @@ use schemars::{
     JsonSchema,
 };
 use serde::{Deserialize, Serialize};
-use settings::{Settings, SettingsLocation, SettingsSources};
+use settings::{Settings, SettingsLocation, SettingsSources, SettingsStore};
 use std::{num::NonZeroU32, path::Path, sync::Arc};
 use util::serde::default_true;
 
@@ -48,6 +48,80 @@ pub fn all_language_settings<'a>(
     AllLanguageSettings::get(location, cx)
 }
 
+/// The settings that the indentation of a file comes from, from the highest precedence to
+/// the lowest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IndentSettingsSource {
+    /// An `.editorconfig` file of the project.
+    Editorconfig,
+    /// A `.zed/settings.json` file of the project.
+    Project,
+    /// The user's settings for the file's language.
+    UserLanguage,
+    /// The user's settings for all languages.
+    User,
+    /// The default settings.
+    Default,
+}
+
+/// Returns which settings the `hard_tabs` and `tab_size` of the given language come from in
+/// the given file, which is the highest layer of the settings that sets either of them.
+///
+/// The local settings of closer directories take precedence, and within each layer the
+/// settings of the language take precedence over the ones for all languages.
+pub fn indent_settings_source(
+    language: Option<&Arc<Language>>,
+    file: Option<&Arc<dyn File>>,
+    cx: &AppContext,
+) -> IndentSettingsSource {
+    let language_name = language.map(|language| language.name());
+    let sets_indent = |settings: &LanguageSettingsContent| {
+        settings.hard_tabs.is_some() || settings.tab_size.is_some()
+    };
+    let language_sets_indent = |settings: &AllLanguageSettingsContent| {
+        language_name
+            .as_ref()
+            .and_then(|name| settings.languages.get(name))
+            .map_or(false, sets_indent)
+    };
+    let store = cx.global::<SettingsStore>();
+
+    if let Some(file) = file {
+        let location: SettingsLocation = file.as_ref().into();
+        let editorconfigs = cx.try_global::<EditorConfigSettings>();
+        let directories = store
+            .local_settings(location.worktree_id)
+            .filter(|(directory, _)| location.path.starts_with(directory))
+            .collect::<Vec<_>>();
+        for (directory, content) in directories.into_iter().rev() {
+            let editorconfig = editorconfigs
+                .and_then(|editorconfigs| editorconfigs.get(location.worktree_id, &directory));
+            // The local settings of a directory include its `.editorconfig` file, which wins
+            // over its `.zed/settings.json` file.
+            let Some(local) = serde_json::from_str::<AllLanguageSettingsContent>(&content).ok()
+            else {
+                continue;
+            };
+            if editorconfig.map_or(false, language_sets_indent) {
+                return IndentSettingsSource::Editorconfig;
+            } else if language_sets_indent(&local) {
+                return IndentSettingsSource::Project;
+            } else if editorconfig.map_or(false, |editorconfig| sets_indent(&editorconfig.defaults))
+            {
+                return IndentSettingsSource::Editorconfig;
+            } else if sets_indent(&local.defaults) {
+                return IndentSettingsSource::Project;
+            }
+        }
+    }
+
+    match serde_json::from_value::<AllLanguageSettingsContent>(store.raw_user_settings().clone()) {
+        Ok(user) if language_sets_indent(&user) => IndentSettingsSource::UserLanguage,
+        Ok(user) if sets_indent(&user.defaults) => IndentSettingsSource::User,
+        _ => IndentSettingsSource::Default,
+    }
+}
+
 /// The settings for all languages.
 #[derive(Debug, Clone)]
 pub struct AllLanguageSettings {
@@ -66,6 +140,9 @@ pub struct LanguageSettings {
     /// Whether to indent lines using tab characters, as opposed to multiple
     /// spaces.
     pub hard_tabs: bool,
+    /// Whether to indent files the way their existing lines are indented,
+    /// instead of using `tab_size` and `hard_tabs`.
+    pub detect_indentation: bool,
     /// How to soft-wrap long lines of text.
     pub soft_wrap: SoftWrap,
     /// The column at which to soft-wrap lines, for buffers where soft-wrap
@@ -85,6 +162,8 @@ pub struct LanguageSettings {
     /// Whether or not to remove any trailing whitespace from lines of a buffer
     /// before saving it.
     pub remove_trailing_whitespace_on_save: bool,
+    /// Which lines trailing whitespace is removed from when saving.
+    pub remove_trailing_whitespace_from: TrailingWhitespaceLines,
     /// Whether or not to ensure there's a single newline at the end of a buffer
     /// when saving it.
     pub ensure_final_newline_on_save: bool,
@@ -108,6 +187,15 @@ pub struct LanguageSettings {
     pub show_whitespaces: ShowWhitespaceSetting,
     /// Whether to start a new line with a comment when a previous line is a comment as well.
     pub extend_comment_on_newline: bool,
+    /// Whether to adjust the indentation of lines as they are typed.
+    pub auto_indent: bool,
+    /// Whether to reindent pasted text relative to where it is pasted.
+    pub auto_indent_on_paste: bool,
+    /// Whether to reindent pasted text to the indentation of the line it's pasted into, keeping
+    /// the relative indentation of its lines, regardless of the language's indentation rules.
+    pub match_indentation_on_paste: bool,
+    /// Whether to underline misspelled words in comments, strings and text.
+    pub spell_check: bool,
     /// Inlay hint related settings.
     pub inlay_hints: InlayHintSettings,
     /// Whether to automatically close brackets.
@@ -222,6 +310,12 @@ pub struct LanguageSettingsContent {
     /// Default: false
     #[serde(default)]
     pub hard_tabs: Option<bool>,
+    /// Whether to indent files the way their existing lines are indented,
+    /// instead of using `tab_size` and `hard_tabs`.
+    ///
+    /// Default: true
+    #[serde(default)]
+    pub detect_indentation: Option<bool>,
     /// How to soft-wrap long lines of text.
     ///
     /// Default: none
@@ -260,6 +354,12 @@ pub struct LanguageSettingsContent {
     /// Default: true
     #[serde(default)]
     pub remove_trailing_whitespace_on_save: Option<bool>,
+    /// Which lines trailing whitespace is removed from when saving: all of them,
+    /// or only the ones that were modified since the buffer was last saved.
+    ///
+    /// Default: all_lines
+    #[serde(default)]
+    pub remove_trailing_whitespace_from: Option<TrailingWhitespaceLines>,
     /// Whether or not to ensure there's a single newline at the end of a buffer
     /// when saving it.
     ///
@@ -307,6 +407,30 @@ pub struct LanguageSettingsContent {
     /// Default: true
     #[serde(default)]
     pub extend_comment_on_newline: Option<bool>,
+    /// Whether to adjust the indentation of lines as they are typed, e.g. indenting
+    /// after an opening bracket, and outdenting a closing bracket to match its opener.
+    ///
+    /// Default: true
+    #[serde(default)]
+    pub auto_indent: Option<bool>,
+    /// Whether to reindent pasted text relative to where it is pasted.
+    ///
+    /// Default: true
+    #[serde(default)]
+    pub auto_indent_on_paste: Option<bool>,
+    /// Whether to reindent pasted text to the indentation of the line it's pasted into, keeping
+    /// the relative indentation of its lines, regardless of the language's indentation rules.
+    /// Takes precedence over `auto_indent_on_paste`.
+    ///
+    /// Default: false
+    #[serde(default)]
+    pub match_indentation_on_paste: Option<bool>,
+    /// Whether to underline misspelled words. In code, only the words in
+    /// comments and strings get checked.
+    ///
+    /// Default: false
+    #[serde(default)]
+    pub spell_check: Option<bool>,
     /// Inlay hint related settings.
     #[serde(default)]
     pub inlay_hints: Option<InlayHintSettings>,
@@ -367,6 +491,17 @@ pub enum SoftWrap {
     PreferredLineLength,
 }
 
+/// Controls which lines have their trailing whitespace removed when saving.
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TrailingWhitespaceLines {
+    /// Remove trailing whitespace from every line of the buffer.
+    #[default]
+    AllLines,
+    /// Only remove trailing whitespace from lines modified since the buffer was last saved.
+    ModifiedLines,
+}
+
 /// Controls the behavior of formatting files when they are saved.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
@@ -405,6 +540,8 @@ pub enum ShowWhitespaceSetting {
     /// - It is adjacent to an edge (start or end)
     /// - It is adjacent to a whitespace (left or right)
     Boundary,
+    /// Draw whitespaces at the end of lines only.
+    Trailing,
 }
 
 /// Controls which formatter should be used when formatting code.
@@ -779,6 +916,7 @@ fn merge_settings(settings: &mut LanguageSettings, src: &LanguageSettingsContent
 
     merge(&mut settings.tab_size, src.tab_size);
     merge(&mut settings.hard_tabs, src.hard_tabs);
+    merge(&mut settings.detect_indentation, src.detect_indentation);
     merge(&mut settings.soft_wrap, src.soft_wrap);
     merge(&mut settings.use_autoclose, src.use_autoclose);
     merge(
@@ -805,6 +943,10 @@ fn merge_settings(settings: &mut LanguageSettings, src: &LanguageSettingsContent
         &mut settings.remove_trailing_whitespace_on_save,
         src.remove_trailing_whitespace_on_save,
     );
+    merge(
+        &mut settings.remove_trailing_whitespace_from,
+        src.remove_trailing_whitespace_from,
+    );
     merge(
         &mut settings.ensure_final_newline_on_save,
         src.ensure_final_newline_on_save,
@@ -823,6 +965,13 @@ fn merge_settings(settings: &mut LanguageSettings, src: &LanguageSettingsContent
         &mut settings.extend_comment_on_newline,
         src.extend_comment_on_newline,
     );
+    merge(&mut settings.auto_indent, src.auto_indent);
+    merge(&mut settings.auto_indent_on_paste, src.auto_indent_on_paste);
+    merge(
+        &mut settings.match_indentation_on_paste,
+        src.match_indentation_on_paste,
+    );
+    merge(&mut settings.spell_check, src.spell_check);
     merge(&mut settings.inlay_hints, src.inlay_hints);
 }
 
@@ -0,0 +1,173 @@
+use std::{fmt, ops::Range, str::FromStr};
+
+use anyhow::{anyhow, Result};
+use text::{Anchor, BufferSnapshot, Point, ToOffset};
+
+/// Identifies a mark for as long as it exists, including when it gets set again somewhere else.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MarkId(usize);
+
+/// What a mark is looked up by: either the letter it was set with, or the number it was given
+/// when it was set without one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MarkName {
+    Letter(char),
+    Number(u32),
+}
+
+impl MarkName {
+    fn validate(self) -> Result<Self> {
+        match self {
+            // Digits would make for names that read the same as numbered marks.
+            MarkName::Letter(letter) if !letter.is_alphabetic() => {
+                Err(anyhow!("invalid mark letter {letter:?}"))
+            }
+            name => Ok(name),
+        }
+    }
+}
+
+impl fmt::Display for MarkName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MarkName::Letter(letter) => write!(f, "{letter}"),
+            MarkName::Number(number) => write!(f, "{number}"),
+        }
+    }
+}
+
+impl FromStr for MarkName {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> Result<Self> {
+        if let Ok(number) = name.parse() {
+            return Ok(MarkName::Number(number));
+        }
+        let mut chars = name.chars();
+        match (chars.next(), chars.next()) {
+            (Some(letter), None) => MarkName::Letter(letter).validate(),
+            _ => Err(anyhow!("invalid mark name {name:?}")),
+        }
+    }
+}
+
+/// A named position in a buffer, which follows the edits made around it.
+#[derive(Clone, Debug)]
+pub struct Mark {
+    pub id: MarkId,
+    pub name: MarkName,
+    /// Where the cursor was when the mark was set.
+    pub position: Anchor,
+    /// The text of the line the mark was set on. The mark gets dropped once all of it has been
+    /// deleted.
+    pub region: Range<Anchor>,
+}
+
+/// The marks of a buffer, in the order they were set in.
+#[derive(Default)]
+pub struct Marks {
+    marks: Vec<Mark>,
+    next_id: usize,
+}
+
+impl Marks {
+    /// Sets the mark with the given letter, or else a new numbered mark, at the given point.
+    pub fn set(
+        &mut self,
+        letter: Option<char>,
+        point: Point,
+        buffer: &BufferSnapshot,
+    ) -> Result<MarkId> {
+        let name = match letter {
+            Some(letter) => MarkName::Letter(letter),
+            None => MarkName::Number(
+                (1..)
+                    .find(|number| self.named(MarkName::Number(*number)).is_none())
+                    .unwrap(),
+            ),
+        };
+        self.set_named(
+            name,
+            buffer.anchor_before(point),
+            mark_region(buffer, point),
+        )
+    }
+
+    /// Sets the mark with the given name, moving it if it exists.
+    pub fn set_named(
+        &mut self,
+        name: MarkName,
+        position: Anchor,
+        region: Range<Anchor>,
+    ) -> Result<MarkId> {
+        let name = name.validate()?;
+        if let Some(mark) = self.marks.iter_mut().find(|mark| mark.name == name) {
+            mark.position = position;
+            mark.region = region;
+            return Ok(mark.id);
+        }
+
+        let id = MarkId(self.next_id);
+        self.next_id += 1;
+        self.marks.push(Mark {
+            id,
+            name,
+            position,
+            region,
+        });
+        Ok(id)
+    }
+
+    pub fn get(&self, id: MarkId) -> Option<&Mark> {
+        self.marks.iter().find(|mark| mark.id == id)
+    }
+
+    pub fn named(&self, name: MarkName) -> Option<&Mark> {
+        self.marks.iter().find(|mark| mark.name == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Mark> {
+        self.marks.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.marks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.marks.is_empty()
+    }
+
+    pub fn remove(&mut self, id: MarkId) -> Option<Mark> {
+        let ix = self.marks.iter().position(|mark| mark.id == id)?;
+        Some(self.marks.remove(ix))
+    }
+
+    pub fn clear(&mut self) {
+        self.marks.clear();
+    }
+
+    /// Drops the marks whose lines were deleted, returning whether there were any.
+    pub(crate) fn remove_deleted(&mut self, buffer: &BufferSnapshot) -> bool {
+        let len = self.marks.len();
+        self.marks
+            .retain(|mark| mark.region.start.to_offset(buffer) < mark.region.end.to_offset(buffer));
+        self.marks.len() != len
+    }
+}
+
+/// Returns the range of the line that contains the given point, along with its line break, for
+/// a mark set on that point. It's only empty when the buffer is.
+fn mark_region(buffer: &BufferSnapshot, point: Point) -> Range<Anchor> {
+    let mut start = Point::new(point.row, 0);
+    let mut end = if point.row < buffer.max_point().row {
+        Point::new(point.row + 1, 0)
+    } else {
+        buffer.max_point()
+    };
+    if start == end && point.row > 0 {
+        start = Point::new(point.row - 1, buffer.line_len(point.row - 1));
+        end = point;
+    }
+    buffer.anchor_after(start)..buffer.anchor_before(end)
+}
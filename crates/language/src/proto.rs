@@ -28,17 +28,40 @@ pub fn serialize_line_ending(message: text::LineEnding) -> proto::LineEnding {
 
 /// Serializes a [`crate::Operation`] to be sent over RPC.
 pub fn serialize_operation(operation: &crate::Operation) -> proto::Operation {
+    serialize_operation_internal(operation, None)
+}
+
+/// Serializes operations to be sent over RPC in one message, in the given order. Their versions
+/// are encoded as the entries that changed since the version of the operation before, so they
+/// have to be deserialized together with [`deserialize_operations`].
+pub fn serialize_operations<'a>(
+    operations: impl IntoIterator<Item = &'a crate::Operation>,
+) -> Vec<proto::Operation> {
+    let mut versions = clock::VersionEncoder::new();
+    operations
+        .into_iter()
+        .map(|operation| serialize_operation_internal(operation, Some(&mut versions)))
+        .collect()
+}
+
+fn serialize_operation_internal(
+    operation: &crate::Operation,
+    versions: Option<&mut clock::VersionEncoder>,
+) -> proto::Operation {
     proto::Operation {
         variant: Some(match operation {
             crate::Operation::Buffer(text::Operation::Edit(edit)) => {
-                proto::operation::Variant::Edit(serialize_edit_operation(edit))
+                proto::operation::Variant::Edit(serialize_edit(edit, versions))
             }
 
             crate::Operation::Buffer(text::Operation::Undo(undo)) => {
+                let (version, encoded_version) =
+                    serialize_operation_version(&undo.version, versions);
                 proto::operation::Variant::Undo(proto::operation::Undo {
                     replica_id: undo.timestamp.replica_id as u32,
                     lamport_timestamp: undo.timestamp.value,
-                    version: serialize_version(&undo.version),
+                    version,
+                    encoded_version,
                     counts: undo
                         .counts
                         .iter()
@@ -85,16 +108,36 @@ pub fn serialize_operation(operation: &crate::Operation) -> proto::Operation {
                     triggers: triggers.clone(),
                 },
             ),
+
+            crate::Operation::UpdateLineEnding {
+                line_ending,
+                has_mixed_line_endings,
+                lamport_timestamp,
+            } => proto::operation::Variant::UpdateLineEnding(proto::operation::UpdateLineEnding {
+                replica_id: lamport_timestamp.replica_id as u32,
+                lamport_timestamp: lamport_timestamp.value,
+                line_ending: serialize_line_ending(*line_ending) as i32,
+                has_mixed_line_endings: *has_mixed_line_endings,
+            }),
         }),
     }
 }
 
 /// Serializes an [`EditOperation`] to be sent over RPC.
 pub fn serialize_edit_operation(operation: &EditOperation) -> proto::operation::Edit {
+    serialize_edit(operation, None)
+}
+
+fn serialize_edit(
+    operation: &EditOperation,
+    versions: Option<&mut clock::VersionEncoder>,
+) -> proto::operation::Edit {
+    let (version, encoded_version) = serialize_operation_version(&operation.version, versions);
     proto::operation::Edit {
         replica_id: operation.timestamp.replica_id as u32,
         lamport_timestamp: operation.timestamp.value,
-        version: serialize_version(&operation.version),
+        version,
+        encoded_version,
         ranges: operation.ranges.iter().map(serialize_range).collect(),
         new_text: operation
             .new_text
@@ -104,6 +147,38 @@ pub fn serialize_edit_operation(operation: &EditOperation) -> proto::operation::
     }
 }
 
+/// Serializes the version of an operation, either in full or, when it's sent along with other
+/// operations, encoded against the version of the one before it.
+fn serialize_operation_version(
+    version: &clock::Global,
+    versions: Option<&mut clock::VersionEncoder>,
+) -> (Vec<proto::VectorClockEntry>, Option<proto::EncodedVersion>) {
+    match versions {
+        Some(versions) => {
+            let encoded = versions.encode(version);
+            let encoded = proto::EncodedVersion {
+                seq: encoded.seq,
+                full: encoded.full,
+                entries: serialize_version_entries(encoded.entries),
+                retired: serialize_version_entries(encoded.retired),
+                retired_count: encoded.retired_count,
+            };
+            (Vec::new(), Some(encoded))
+        }
+        None => (serialize_version(version), None),
+    }
+}
+
+fn serialize_version_entries(entries: Vec<clock::Lamport>) -> Vec<proto::VectorClockEntry> {
+    entries
+        .into_iter()
+        .map(|entry| proto::VectorClockEntry {
+            replica_id: entry.replica_id as u32,
+            timestamp: entry.value,
+        })
+        .collect()
+}
+
 /// Serializes an entry in the undo map to be sent over RPC.
 pub fn serialize_undo_map_entry(
     (edit_id, counts): (&clock::Lamport, &[(clock::Lamport, u32)]),
@@ -234,13 +309,31 @@ pub fn serialize_anchor(anchor: &Anchor) -> proto::Anchor {
 // This behavior is currently copied in the collab database, for snapshotting channel notes
 /// Deserializes an [`crate::Operation`] from the RPC representation.
 pub fn deserialize_operation(message: proto::Operation) -> Result<crate::Operation> {
+    deserialize_operation_internal(message, None)
+}
+
+/// Deserializes operations that were serialized together with [`serialize_operations`].
+pub fn deserialize_operations(
+    messages: impl IntoIterator<Item = proto::Operation>,
+) -> Result<Vec<crate::Operation>> {
+    let mut versions = clock::VersionDecoder::new();
+    messages
+        .into_iter()
+        .map(|message| deserialize_operation_internal(message, Some(&mut versions)))
+        .collect()
+}
+
+fn deserialize_operation_internal(
+    message: proto::Operation,
+    versions: Option<&mut clock::VersionDecoder>,
+) -> Result<crate::Operation> {
     Ok(
         match message
             .variant
             .ok_or_else(|| anyhow!("missing operation variant"))?
         {
             proto::operation::Variant::Edit(edit) => {
-                crate::Operation::Buffer(text::Operation::Edit(deserialize_edit_operation(edit)))
+                crate::Operation::Buffer(text::Operation::Edit(deserialize_edit(edit, versions)?))
             }
             proto::operation::Variant::Undo(undo) => {
                 crate::Operation::Buffer(text::Operation::Undo(UndoOperation {
@@ -248,7 +341,11 @@ pub fn deserialize_operation(message: proto::Operation) -> Result<crate::Operati
                         replica_id: undo.replica_id as ReplicaId,
                         value: undo.lamport_timestamp,
                     },
-                    version: deserialize_version(&undo.version),
+                    version: deserialize_operation_version(
+                        &undo.version,
+                        undo.encoded_version,
+                        versions,
+                    )?,
                     counts: undo
                         .counts
                         .into_iter()
@@ -311,23 +408,82 @@ pub fn deserialize_operation(message: proto::Operation) -> Result<crate::Operati
                     },
                 }
             }
+            proto::operation::Variant::UpdateLineEnding(message) => {
+                crate::Operation::UpdateLineEnding {
+                    line_ending: deserialize_line_ending(
+                        proto::LineEnding::from_i32(message.line_ending)
+                            .ok_or_else(|| anyhow!("missing line_ending"))?,
+                    ),
+                    has_mixed_line_endings: message.has_mixed_line_endings,
+                    lamport_timestamp: clock::Lamport {
+                        replica_id: message.replica_id as ReplicaId,
+                        value: message.lamport_timestamp,
+                    },
+                }
+            }
         },
     )
 }
 
 /// Deserializes an [`EditOperation`] from the RPC representation.
-pub fn deserialize_edit_operation(edit: proto::operation::Edit) -> EditOperation {
-    EditOperation {
+pub fn deserialize_edit_operation(edit: proto::operation::Edit) -> Result<EditOperation> {
+    deserialize_edit(edit, None)
+}
+
+fn deserialize_edit(
+    edit: proto::operation::Edit,
+    versions: Option<&mut clock::VersionDecoder>,
+) -> Result<EditOperation> {
+    Ok(EditOperation {
         timestamp: clock::Lamport {
             replica_id: edit.replica_id as ReplicaId,
             value: edit.lamport_timestamp,
         },
-        version: deserialize_version(&edit.version),
+        version: deserialize_operation_version(&edit.version, edit.encoded_version, versions)?,
         ranges: edit.ranges.into_iter().map(deserialize_range).collect(),
         new_text: edit.new_text.into_iter().map(Arc::from).collect(),
+    })
+}
+
+/// Deserializes the version of an operation, which is encoded against the version of the
+/// operation before it when they were serialized together.
+fn deserialize_operation_version(
+    version: &[proto::VectorClockEntry],
+    encoded_version: Option<proto::EncodedVersion>,
+    versions: Option<&mut clock::VersionDecoder>,
+) -> Result<clock::Global> {
+    let Some(encoded_version) = encoded_version else {
+        return Ok(deserialize_version(version));
+    };
+    let versions = versions.ok_or_else(|| {
+        anyhow!("operation version is encoded against operations that weren't sent along")
+    })?;
+    let encoded_version = clock::EncodedVersion {
+        seq: encoded_version.seq,
+        full: encoded_version.full,
+        entries: deserialize_version_entries(&encoded_version.entries),
+        retired: deserialize_version_entries(&encoded_version.retired),
+        retired_count: encoded_version.retired_count,
+    };
+    match versions.decode(&encoded_version) {
+        clock::DecodedVersion::Version(version) => Ok(version),
+        clock::DecodedVersion::Pending => Err(anyhow!(
+            "operation version is encoded against an operation that's missing"
+        )),
+        clock::DecodedVersion::Duplicate => Err(anyhow!("operation version was sent twice")),
     }
 }
 
+fn deserialize_version_entries(entries: &[proto::VectorClockEntry]) -> Vec<clock::Lamport> {
+    entries
+        .iter()
+        .map(|entry| clock::Lamport {
+            replica_id: entry.replica_id as ReplicaId,
+            value: entry.timestamp,
+        })
+        .collect()
+}
+
 /// Deserializes an entry in the undo map from the RPC representation.
 pub fn deserialize_undo_map_entry(
     entry: proto::UndoMapEntry,
@@ -450,6 +606,10 @@ pub fn lamport_timestamp_for_operation(operation: &proto::Operation) -> Option<c
             replica_id = op.replica_id;
             value = op.lamport_timestamp;
         }
+        proto::operation::Variant::UpdateLineEnding(op) => {
+            replica_id = op.replica_id;
+            value = op.lamport_timestamp;
+        }
     }
 
     Some(clock::Lamport {
@@ -521,12 +681,10 @@ pub fn deserialize_range(range: proto::Range) -> Range<FullOffset> {
 /// Deserializes a clock version from the RPC representation.
 pub fn deserialize_version(message: &[proto::VectorClockEntry]) -> clock::Global {
     let mut version = clock::Global::new();
-    for entry in message {
-        version.observe(clock::Lamport {
-            replica_id: entry.replica_id as ReplicaId,
-            value: entry.timestamp,
-        });
-    }
+    version.observe_all(message.iter().map(|entry| clock::Lamport {
+        replica_id: entry.replica_id as ReplicaId,
+        value: entry.timestamp,
+    }));
     version
 }
 
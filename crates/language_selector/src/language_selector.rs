@@ -10,7 +10,7 @@ use gpui::{
 };
 use language::{Buffer, LanguageRegistry};
 use picker::{Picker, PickerDelegate};
-use project::Project;
+use project::{Item as _, Project};
 use std::sync::Arc;
 use ui::{prelude::*, HighlightedLabel, ListItem, ListItemSpacing};
 use util::ResultExt;
@@ -18,6 +18,9 @@ use workspace::{ModalView, Workspace};
 
 actions!(language_selector, [Toggle]);
 
+/// The candidate that goes back to the language detected for the buffer's file.
+const AUTO_DETECT: &str = "Auto Detect";
+
 pub fn init(cx: &mut AppContext) {
     cx.observe_new_views(LanguageSelector::register).detach();
 }
@@ -59,6 +62,7 @@ impl LanguageSelector {
             buffer,
             project,
             language_registry,
+            cx,
         );
 
         let picker = cx.new_view(|cx| Picker::uniform_list(delegate, cx));
@@ -86,6 +90,8 @@ pub struct LanguageSelectorDelegate {
     buffer: Model<Buffer>,
     project: Model<Project>,
     language_registry: Arc<LanguageRegistry>,
+    /// Whether the first candidate clears the language chosen for the buffer's file.
+    has_auto_detect: bool,
     candidates: Vec<StringMatchCandidate>,
     matches: Vec<StringMatch>,
     selected_index: usize,
@@ -97,10 +103,15 @@ impl LanguageSelectorDelegate {
         buffer: Model<Buffer>,
         project: Model<Project>,
         language_registry: Arc<LanguageRegistry>,
+        cx: &AppContext,
     ) -> Self {
-        let candidates = language_registry
-            .language_names()
+        let has_auto_detect = buffer.read(cx).project_path(cx).map_or(false, |path| {
+            project.read(cx).language_overrides().contains_key(&path)
+        });
+        let candidates = has_auto_detect
+            .then(|| AUTO_DETECT.to_string())
             .into_iter()
+            .chain(language_registry.language_names())
             .enumerate()
             .map(|(candidate_id, name)| StringMatchCandidate::new(candidate_id, name))
             .collect::<Vec<_>>();
@@ -110,6 +121,7 @@ impl LanguageSelectorDelegate {
             buffer,
             project,
             language_registry,
+            has_auto_detect,
             candidates,
             matches: vec![],
             selected_index: 0,
@@ -130,6 +142,14 @@ impl PickerDelegate for LanguageSelectorDelegate {
 
     fn confirm(&mut self, _: bool, cx: &mut ViewContext<Picker<Self>>) {
         if let Some(mat) = self.matches.get(self.selected_index) {
+            if self.has_auto_detect && mat.candidate_id == 0 {
+                self.project.update(cx, |project, cx| {
+                    project.set_language_override(&self.buffer, None, cx);
+                });
+                self.dismissed(cx);
+                return;
+            }
+
             let language_name = &self.candidates[mat.candidate_id].string;
             let language = self.language_registry.language_for_name(language_name);
             let project = self.project.downgrade();
@@ -143,7 +163,7 @@ impl PickerDelegate for LanguageSelectorDelegate {
                     .upgrade()
                     .ok_or_else(|| anyhow!("buffer was dropped"))?;
                 project.update(&mut cx, |project, cx| {
-                    project.set_language_for_buffer(&buffer, language, cx);
+                    project.set_language_override(&buffer, Some(language), cx);
                 })
             })
             .detach_and_log_err(cx);
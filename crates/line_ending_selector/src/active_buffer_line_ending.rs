@@ -0,0 +1,89 @@
+use editor::Editor;
+use gpui::{div, IntoElement, ParentElement, Render, Subscription, View, ViewContext, WeakView};
+use language::LineEnding;
+use ui::{Button, ButtonCommon, Clickable, FluentBuilder, LabelSize, Tooltip};
+use workspace::{item::ItemHandle, StatusItemView, Workspace};
+
+use crate::{line_ending_label, LineEndingSelector};
+
+pub struct ActiveBufferLineEnding {
+    /// The active buffer's line ending, and whether it was loaded with mixed line endings.
+    active_line_ending: Option<(LineEnding, bool)>,
+    workspace: WeakView<Workspace>,
+    _observe_active_editor: Option<Subscription>,
+}
+
+impl ActiveBufferLineEnding {
+    pub fn new(workspace: &Workspace) -> Self {
+        Self {
+            active_line_ending: None,
+            workspace: workspace.weak_handle(),
+            _observe_active_editor: None,
+        }
+    }
+
+    fn update_line_ending(&mut self, editor: View<Editor>, cx: &mut ViewContext<Self>) {
+        self.active_line_ending = None;
+
+        let editor = editor.read(cx);
+        if let Some((_, buffer, _)) = editor.active_excerpt(cx) {
+            let buffer = buffer.read(cx);
+            self.active_line_ending = Some((buffer.line_ending(), buffer.has_mixed_line_endings()));
+        }
+
+        cx.notify();
+    }
+}
+
+impl Render for ActiveBufferLineEnding {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        div().when_some(self.active_line_ending, |el, (line_ending, mixed)| {
+            let mut label = line_ending_label(line_ending).to_string();
+            if mixed {
+                label.push_str(" (mixed)");
+            }
+
+            el.child(
+                Button::new("change-line-ending", label)
+                    .label_size(LabelSize::Small)
+                    .on_click(cx.listener(|this, _, cx| {
+                        if let Some(workspace) = this.workspace.upgrade() {
+                            workspace.update(cx, |workspace, cx| {
+                                LineEndingSelector::toggle(workspace, cx)
+                            });
+                        }
+                    }))
+                    .tooltip(move |cx| {
+                        if mixed {
+                            Tooltip::with_meta(
+                                "Select Line Ending",
+                                None,
+                                "This file uses more than one line ending",
+                                cx,
+                            )
+                        } else {
+                            Tooltip::text("Select Line Ending", cx)
+                        }
+                    }),
+            )
+        })
+    }
+}
+
+impl StatusItemView for ActiveBufferLineEnding {
+    fn set_active_pane_item(
+        &mut self,
+        active_pane_item: Option<&dyn ItemHandle>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        if let Some(editor) = active_pane_item.and_then(|item| item.act_as::<Editor>(cx)) {
+            self._observe_active_editor = Some(cx.observe(&editor, Self::update_line_ending));
+            self.update_line_ending(editor, cx);
+        } else {
+            self.active_line_ending = None;
+            self._observe_active_editor = None;
+        }
+
+        cx.notify();
+    }
+}
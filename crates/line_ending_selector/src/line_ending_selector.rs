@@ -0,0 +1,208 @@
+mod active_buffer_line_ending;
+
+pub use active_buffer_line_ending::ActiveBufferLineEnding;
+use editor::Editor;
+use fuzzy::{match_strings, StringMatch, StringMatchCandidate};
+use gpui::{
+    actions, AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView, Model,
+    ParentElement, Render, Styled, View, ViewContext, VisualContext, WeakView,
+};
+use language::{Buffer, LineEnding};
+use picker::{Picker, PickerDelegate};
+use std::sync::Arc;
+use ui::{prelude::*, HighlightedLabel, ListItem, ListItemSpacing};
+use util::ResultExt;
+use workspace::{ModalView, Workspace};
+
+actions!(line_ending_selector, [Toggle]);
+
+const LINE_ENDINGS: [LineEnding; 2] = [LineEnding::Unix, LineEnding::Windows];
+
+pub fn init(cx: &mut AppContext) {
+    cx.observe_new_views(LineEndingSelector::register).detach();
+}
+
+/// The short name of a line ending, as shown in the status bar.
+pub fn line_ending_label(line_ending: LineEnding) -> &'static str {
+    match line_ending {
+        LineEnding::Unix => "LF",
+        LineEnding::Windows => "CRLF",
+    }
+}
+
+pub struct LineEndingSelector {
+    picker: View<Picker<LineEndingSelectorDelegate>>,
+}
+
+impl LineEndingSelector {
+    fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+        workspace.register_action(move |workspace, _: &Toggle, cx| {
+            Self::toggle(workspace, cx);
+        });
+    }
+
+    fn toggle(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) -> Option<()> {
+        let (_, buffer, _) = workspace
+            .active_item(cx)?
+            .act_as::<Editor>(cx)?
+            .read(cx)
+            .active_excerpt(cx)?;
+
+        workspace.toggle_modal(cx, move |cx| LineEndingSelector::new(buffer, cx));
+        Some(())
+    }
+
+    fn new(buffer: Model<Buffer>, cx: &mut ViewContext<Self>) -> Self {
+        let delegate = LineEndingSelectorDelegate::new(cx.view().downgrade(), buffer);
+        let picker = cx.new_view(|cx| Picker::uniform_list(delegate, cx));
+        Self { picker }
+    }
+}
+
+impl Render for LineEndingSelector {
+    fn render(&mut self, _cx: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex().w(rems(34.)).child(self.picker.clone())
+    }
+}
+
+impl FocusableView for LineEndingSelector {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl EventEmitter<DismissEvent> for LineEndingSelector {}
+impl ModalView for LineEndingSelector {}
+
+pub struct LineEndingSelectorDelegate {
+    line_ending_selector: WeakView<LineEndingSelector>,
+    buffer: Model<Buffer>,
+    candidates: Vec<StringMatchCandidate>,
+    matches: Vec<StringMatch>,
+    selected_index: usize,
+}
+
+impl LineEndingSelectorDelegate {
+    fn new(line_ending_selector: WeakView<LineEndingSelector>, buffer: Model<Buffer>) -> Self {
+        let candidates = LINE_ENDINGS
+            .iter()
+            .enumerate()
+            .map(|(candidate_id, line_ending)| {
+                StringMatchCandidate::new(candidate_id, line_ending_label(*line_ending).into())
+            })
+            .collect::<Vec<_>>();
+
+        Self {
+            line_ending_selector,
+            buffer,
+            candidates,
+            matches: vec![],
+            selected_index: 0,
+        }
+    }
+}
+
+impl PickerDelegate for LineEndingSelectorDelegate {
+    type ListItem = ListItem;
+
+    fn placeholder_text(&self, _cx: &mut WindowContext) -> Arc<str> {
+        "Select a line ending...".into()
+    }
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn confirm(&mut self, _: bool, cx: &mut ViewContext<Picker<Self>>) {
+        if let Some(mat) = self.matches.get(self.selected_index) {
+            let line_ending = LINE_ENDINGS[mat.candidate_id];
+            self.buffer.update(cx, |buffer, cx| {
+                buffer.set_line_ending(line_ending, cx);
+            });
+        }
+        self.dismissed(cx);
+    }
+
+    fn dismissed(&mut self, cx: &mut ViewContext<Picker<Self>>) {
+        self.line_ending_selector
+            .update(cx, |_, cx| cx.emit(DismissEvent))
+            .log_err();
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(&mut self, ix: usize, _: &mut ViewContext<Picker<Self>>) {
+        self.selected_index = ix;
+    }
+
+    fn update_matches(
+        &mut self,
+        query: String,
+        cx: &mut ViewContext<Picker<Self>>,
+    ) -> gpui::Task<()> {
+        let background = cx.background_executor().clone();
+        let candidates = self.candidates.clone();
+        cx.spawn(|this, mut cx| async move {
+            let matches = if query.is_empty() {
+                candidates
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, candidate)| StringMatch {
+                        candidate_id: index,
+                        string: candidate.string,
+                        positions: Vec::new(),
+                        score: 0.0,
+                    })
+                    .collect()
+            } else {
+                match_strings(
+                    &candidates,
+                    &query,
+                    false,
+                    100,
+                    &Default::default(),
+                    background,
+                )
+                .await
+            };
+
+            this.update(&mut cx, |this, cx| {
+                let delegate = &mut this.delegate;
+                delegate.matches = matches;
+                delegate.selected_index = delegate
+                    .selected_index
+                    .min(delegate.matches.len().saturating_sub(1));
+                cx.notify();
+            })
+            .log_err();
+        })
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        cx: &mut ViewContext<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let mat = &self.matches[ix];
+        let buffer = self.buffer.read(cx);
+        let mut label = mat.string.clone();
+        if buffer.line_ending() == LINE_ENDINGS[mat.candidate_id] {
+            if buffer.has_mixed_line_endings() {
+                label.push_str(" (current, mixed)");
+            } else {
+                label.push_str(" (current)");
+            }
+        }
+
+        Some(
+            ListItem::new(ix)
+                .inset(true)
+                .spacing(ListItemSpacing::Sparse)
+                .selected(selected)
+                .child(HighlightedLabel::new(label, mat.positions.clone())),
+        )
+    }
+}
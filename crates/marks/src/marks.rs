@@ -0,0 +1,210 @@
+use editor::{Editor, MarkId};
+use fuzzy::{match_strings, StringMatch, StringMatchCandidate};
+use gpui::{
+    actions, AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView, ParentElement,
+    Render, Styled, View, ViewContext, VisualContext, WeakView,
+};
+use language::ToPoint;
+use picker::{Picker, PickerDelegate};
+use std::sync::Arc;
+use ui::{prelude::*, HighlightedLabel, ListItem, ListItemSpacing};
+use util::ResultExt;
+use workspace::{ModalView, Workspace};
+
+actions!(marks, [Toggle]);
+
+pub fn init(cx: &mut AppContext) {
+    cx.observe_new_views(MarksView::register).detach();
+}
+
+/// Lists the marks of the active editor along with their lines, to jump to one of them.
+pub struct MarksView {
+    picker: View<Picker<MarksViewDelegate>>,
+}
+
+impl MarksView {
+    fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+        workspace.register_action(move |workspace, _: &Toggle, cx| {
+            Self::toggle(workspace, cx);
+        });
+    }
+
+    fn toggle(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) -> Option<()> {
+        let editor = workspace.active_item(cx)?.act_as::<Editor>(cx)?;
+        editor.read(cx).marks(cx)?;
+        workspace.toggle_modal(cx, move |cx| MarksView::new(editor, cx));
+        Some(())
+    }
+
+    fn new(editor: View<Editor>, cx: &mut ViewContext<Self>) -> Self {
+        let delegate = MarksViewDelegate::new(cx.view().downgrade(), editor, cx);
+        let picker = cx.new_view(|cx| Picker::uniform_list(delegate, cx));
+        Self { picker }
+    }
+}
+
+impl Render for MarksView {
+    fn render(&mut self, _cx: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex().w(rems(34.)).child(self.picker.clone())
+    }
+}
+
+impl FocusableView for MarksView {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl EventEmitter<DismissEvent> for MarksView {}
+impl ModalView for MarksView {}
+
+struct MarksViewDelegate {
+    marks_view: WeakView<MarksView>,
+    editor: View<Editor>,
+    /// The marks of the editor's buffer in the order of their positions, with the candidates that show them.
+    mark_ids: Vec<MarkId>,
+    candidates: Vec<StringMatchCandidate>,
+    matches: Vec<StringMatch>,
+    selected_index: usize,
+}
+
+impl MarksViewDelegate {
+    fn new(marks_view: WeakView<MarksView>, editor: View<Editor>, cx: &AppContext) -> Self {
+        let mut mark_ids = Vec::new();
+        let mut candidates = Vec::new();
+        if let Some(buffer) = editor.read(cx).buffer().read(cx).as_singleton() {
+            let buffer = buffer.read(cx);
+            let mut marks = buffer
+                .marks()
+                .iter()
+                .map(|mark| (mark.position.to_point(buffer), mark))
+                .collect::<Vec<_>>();
+            marks.sort_by_key(|(point, _)| *point);
+
+            for (ix, (point, mark)) in marks.into_iter().enumerate() {
+                let line_start = buffer
+                    .reversed_chars_at(mark.position)
+                    .take_while(|c| *c != '\n')
+                    .collect::<Vec<_>>();
+                let line = line_start
+                    .into_iter()
+                    .rev()
+                    .chain(buffer.chars_at(mark.position).take_while(|c| *c != '\n'))
+                    .collect::<String>();
+                mark_ids.push(mark.id);
+                candidates.push(StringMatchCandidate::new(
+                    ix,
+                    format!("{}  {}: {}", mark.name, point.row + 1, line.trim()),
+                ));
+            }
+        }
+
+        Self {
+            marks_view,
+            editor,
+            mark_ids,
+            candidates,
+            matches: Vec::new(),
+            selected_index: 0,
+        }
+    }
+}
+
+impl PickerDelegate for MarksViewDelegate {
+    type ListItem = ListItem;
+
+    fn placeholder_text(&self, _cx: &mut WindowContext) -> Arc<str> {
+        "Search marks...".into()
+    }
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(&mut self, ix: usize, _: &mut ViewContext<Picker<Self>>) {
+        self.selected_index = ix;
+    }
+
+    fn update_matches(
+        &mut self,
+        query: String,
+        cx: &mut ViewContext<Picker<Self>>,
+    ) -> gpui::Task<()> {
+        let background = cx.background_executor().clone();
+        let candidates = self.candidates.clone();
+        cx.spawn(|this, mut cx| async move {
+            let matches = if query.is_empty() {
+                candidates
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, candidate)| StringMatch {
+                        candidate_id: index,
+                        string: candidate.string,
+                        positions: Vec::new(),
+                        score: 0.0,
+                    })
+                    .collect()
+            } else {
+                match_strings(
+                    &candidates,
+                    &query,
+                    false,
+                    100,
+                    &Default::default(),
+                    background,
+                )
+                .await
+            };
+
+            this.update(&mut cx, |this, cx| {
+                let delegate = &mut this.delegate;
+                delegate.matches = matches;
+                delegate.selected_index = delegate
+                    .selected_index
+                    .min(delegate.matches.len().saturating_sub(1));
+                cx.notify();
+            })
+            .log_err();
+        })
+    }
+
+    fn confirm(&mut self, _: bool, cx: &mut ViewContext<Picker<Self>>) {
+        if let Some(mat) = self.matches.get(self.selected_index) {
+            let id = self.mark_ids[mat.candidate_id];
+            self.editor.update(cx, |editor, cx| {
+                editor.go_to_mark(id, cx);
+                editor.focus(cx);
+            });
+        }
+        self.dismissed(cx);
+    }
+
+    fn dismissed(&mut self, cx: &mut ViewContext<Picker<Self>>) {
+        self.marks_view
+            .update(cx, |_, cx| cx.emit(DismissEvent))
+            .log_err();
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _: &mut ViewContext<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let mat = self.matches.get(ix)?;
+        Some(
+            ListItem::new(ix)
+                .inset(true)
+                .spacing(ListItemSpacing::Sparse)
+                .selected(selected)
+                .child(HighlightedLabel::new(
+                    mat.string.clone(),
+                    mat.positions.clone(),
+                )),
+        )
+    }
+}
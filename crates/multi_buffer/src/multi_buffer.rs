@@ -9,7 +9,7 @@ use git::diff::DiffHunk;
 use gpui::{AppContext, EventEmitter, Model, ModelContext};
 use itertools::Itertools;
 use language::{
-    char_kind,
+    char_kind, is_word_boundary,
     language_settings::{language_settings, LanguageSettings},
     AutoindentMode, Buffer, BufferChunks, BufferRow, BufferSnapshot, Capability, CharKind, Chunk,
     CursorShape, DiagnosticEntry, File, IndentGuide, IndentSize, Language, LanguageScope,
@@ -35,7 +35,7 @@ use sum_tree::{Bias, Cursor, SumTree};
 use text::{
     locator::Locator,
     subscription::{Subscription, Topic},
-    BufferId, Edit, TextSummary,
+    BufferId, Edit, TextStats, TextSummary,
 };
 use theme::SyntaxTheme;
 
@@ -1649,7 +1649,7 @@ impl MultiBuffer {
             }
 
             //
-            language::Event::Operation(_) => return,
+            language::Event::Operation(_) | language::Event::MarksChanged => return,
         });
     }
 
@@ -2270,21 +2270,26 @@ impl MultiBufferSnapshot {
             prev_chars.peek().copied().map(kind),
             next_chars.peek().copied().map(kind),
         );
+        let in_word = |ch| Some(kind(ch)) == word_kind && ch != '\n';
 
+        // When the position is on a word boundary, the word following it is preferred.
+        let prev_char = prev_chars.peek().copied();
+        let mut right = next_chars.peek().copied().filter(|ch| in_word(*ch));
         for ch in prev_chars {
-            if Some(kind(ch)) == word_kind && ch != '\n' {
-                start -= ch.len_utf8();
-            } else {
+            if !in_word(ch) || right.map_or(false, |right| is_word_boundary(&scope, ch, right)) {
                 break;
             }
+            start -= ch.len_utf8();
+            right = Some(ch);
         }
 
+        let mut left = if start < end { prev_char } else { None };
         for ch in next_chars {
-            if Some(kind(ch)) == word_kind && ch != '\n' {
-                end += ch.len_utf8();
-            } else {
+            if !in_word(ch) || left.map_or(false, |left| is_word_boundary(&scope, left, ch)) {
                 break;
             }
+            end += ch.len_utf8();
+            left = Some(ch);
         }
 
         (start..end, word_kind)
@@ -2759,6 +2764,20 @@ impl MultiBufferSnapshot {
         self.excerpts.summary().text.clone()
     }
 
+    /// Returns the number of characters and words in the given range, see [`TextStats`]. The
+    /// newlines separating excerpts are counted like any other character.
+    pub fn text_stats_for_range<O: ToOffset>(&self, range: Range<O>) -> TextStats {
+        let range = self.anchor_before(range.start)..self.anchor_after(range.end);
+        let mut stats = TextStats::default();
+        for (ix, (_, buffer, range)) in self.excerpts_in_ranges([range]).enumerate() {
+            if ix > 0 {
+                stats += &TextStats::from("\n");
+            }
+            stats += &buffer.text_stats_for_range(range);
+        }
+        stats
+    }
+
     pub fn text_summary_for_range<D, O>(&self, range: Range<O>) -> D
     where
         D: TextDimension,
@@ -3665,6 +3684,21 @@ impl MultiBufferSnapshot {
         Some(excerpt.map_range_from_buffer(ancestor_buffer_range))
     }
 
+    pub fn enclosing_node_range<T: ToOffset>(
+        &self,
+        range: Range<T>,
+        steps: usize,
+    ) -> Option<Range<usize>> {
+        let range = range.start.to_offset(self)..range.end.to_offset(self);
+        let excerpt = self.excerpt_containing(range.clone())?;
+
+        let node_buffer_range = excerpt
+            .buffer()
+            .enclosing_node_range(excerpt.map_range_to_buffer(range), steps)?;
+
+        Some(excerpt.map_range_from_buffer(node_buffer_range))
+    }
+
     pub fn outline(&self, theme: Option<&SyntaxTheme>) -> Option<Outline<Anchor>> {
         let (excerpt_id, _, buffer) = self.as_singleton()?;
         let outline = buffer.outline(theme)?;
@@ -5487,6 +5521,42 @@ mod tests {
         assert_eq!(old_snapshot.anchor_after(10).to_offset(&new_snapshot), 14);
     }
 
+    #[gpui::test]
+    fn test_text_stats_for_range(cx: &mut AppContext) {
+        let buffer_1 = cx.new_model(|cx| Buffer::local("one two", cx));
+        let buffer_2 = cx.new_model(|cx| Buffer::local("three four five", cx));
+        let multibuffer = cx.new_model(|cx| {
+            let mut multibuffer = MultiBuffer::new(0, Capability::ReadWrite);
+            multibuffer.push_excerpts(
+                buffer_1.clone(),
+                [ExcerptRange {
+                    context: 0..7,
+                    primary: None,
+                }],
+                cx,
+            );
+            multibuffer.push_excerpts(
+                buffer_2.clone(),
+                [ExcerptRange {
+                    context: 0..15,
+                    primary: None,
+                }],
+                cx,
+            );
+            multibuffer
+        });
+        let snapshot = multibuffer.read(cx).snapshot(cx);
+        assert_eq!(snapshot.text(), "one two\nthree four five");
+
+        let stats = snapshot.text_stats_for_range(0..snapshot.len());
+        assert_eq!((stats.chars, stats.words), (23, 5));
+        // Words don't continue across the boundary between excerpts.
+        let stats = snapshot.text_stats_for_range(5..11);
+        assert_eq!((stats.chars, stats.words), (6, 2));
+        let stats = snapshot.text_stats_for_range(9..9);
+        assert_eq!((stats.chars, stats.words), (0, 0));
+    }
+
     #[gpui::test]
     fn test_resolving_anchors_after_replacing_their_excerpts(cx: &mut AppContext) {
         let buffer_1 = cx.new_model(|cx| Buffer::local("abcd", cx));
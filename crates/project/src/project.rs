@@ -29,21 +29,24 @@ use futures::{
     future::{join_all, try_join_all, Shared},
     prelude::future::BoxFuture,
     select,
-    stream::FuturesUnordered,
-    AsyncWriteExt, Future, FutureExt, StreamExt, TryFutureExt,
+    stream::{self, FuturesUnordered},
+    AsyncWriteExt, Future, FutureExt, Stream, StreamExt, TryFutureExt,
 };
 use fuzzy::CharBag;
 use git::{blame::Blame, repository::GitRepository};
 use globset::{Glob, GlobSet, GlobSetBuilder};
 use gpui::{
     AnyModel, AppContext, AsyncAppContext, BackgroundExecutor, BorrowAppContext, Context, Entity,
-    EventEmitter, Model, ModelContext, PromptLevel, SharedString, Task, WeakModel, WindowContext,
+    EventEmitter, Model, ModelContext, PromptLevel, SharedString, Task, TaskPriority, WeakModel,
+    WindowContext,
 };
 use http::{HttpClient, Url};
 use itertools::Itertools;
 use language::{
+    editorconfig::{EditorConfig, EditorConfigSettings, EDITORCONFIG_FILE_NAME},
     language_settings::{
-        language_settings, AllLanguageSettings, FormatOnSave, Formatter, InlayHintKind,
+        language_settings, AllLanguageSettings, AllLanguageSettingsContent, FormatOnSave,
+        Formatter, InlayHintKind,
     },
     markdown, point_to_lsp, prepare_completion_documentation,
     proto::{
@@ -68,7 +71,7 @@ use node_runtime::NodeRuntime;
 use parking_lot::{Mutex, RwLock};
 use postage::watch;
 use prettier_support::{DefaultPrettier, PrettierInstance};
-use project_settings::{LspSettings, ProjectSettings};
+use project_settings::{GitGutterSetting, LspSettings, ProjectSettings};
 use rand::prelude::*;
 use rpc::{ErrorCode, ErrorExt as _};
 use search::SearchQuery;
@@ -91,28 +94,30 @@ use std::{
     num::NonZeroU32,
     ops::Range,
     path::{self, Component, Path, PathBuf},
+    pin::pin,
     process::Stdio,
     str::{self, FromStr},
     sync::{
         atomic::{AtomicUsize, Ordering::SeqCst},
         Arc,
     },
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime},
 };
 use task::{
     static_source::{StaticSource, TrackedFile},
     RevealStrategy, TaskContext, TaskTemplate, TaskVariables, VariableName,
 };
 use terminals::Terminals;
-use text::{Anchor, BufferId, LineEnding};
+use text::{Anchor, BufferId, LineEnding, Point};
 use util::{
-    debug_panic, defer, maybe, merge_json_value_into, parse_env_output,
+    debug_panic, defer, maybe, merge_json_value_into, merge_non_null_json_value_into,
+    parse_env_output,
     paths::{
         LOCAL_SETTINGS_RELATIVE_PATH, LOCAL_TASKS_RELATIVE_PATH, LOCAL_VSCODE_TASKS_RELATIVE_PATH,
     },
     post_inc, ResultExt, TryFutureExt as _,
 };
-use worktree::{CreatedEntry, RemoteWorktreeClient, Snapshot, Traversal};
+use worktree::{CreatedEntry, MatchCandidateCursor, RemoteWorktreeClient, Snapshot, Traversal};
 
 pub use fs::*;
 pub use language::Location;
@@ -167,6 +172,8 @@ pub struct Project {
     flush_language_server_update: Option<Task<()>>,
 
     languages: Arc<LanguageRegistry>,
+    /// The names of the languages chosen for files, instead of the ones detected for them.
+    language_overrides: HashMap<ProjectPath, Arc<str>>,
     supplementary_language_servers:
         HashMap<LanguageServerId, (LanguageServerName, Arc<LanguageServer>)>,
     language_servers: HashMap<LanguageServerId, LanguageServerState>,
@@ -201,7 +208,12 @@ pub struct Project {
     _subscriptions: Vec<gpui::Subscription>,
     loading_buffers: HashMap<BufferId, Vec<oneshot::Sender<Result<Model<Buffer>, anyhow::Error>>>>,
     incomplete_remote_buffers: HashMap<BufferId, Model<Buffer>>,
+    /// The buffers opened read-only because their files can't be written to, which stay
+    /// read-only whether or not their worktrees are.
+    read_only_file_buffers: HashSet<BufferId>,
     shared_buffers: HashMap<proto::PeerId, HashSet<BufferId>>,
+    /// Held by the streams of results of the searches that guests run on this project.
+    remote_searches: Arc<()>,
     #[allow(clippy::type_complexity)]
     loading_buffers_by_path: HashMap<
         ProjectPath,
@@ -231,6 +243,52 @@ pub struct Project {
     dev_server_project_id: Option<client::DevServerProjectId>,
     search_history: SearchHistory,
     yarn_worktree_ids_reported: Vec<WorktreeId>,
+    local_settings_files: HashMap<(usize, Arc<Path>), LocalSettingsFiles>,
+}
+
+#[derive(Clone, Copy)]
+enum LocalSettingsKind {
+    Settings,
+    Editorconfig,
+}
+
+/// The files of a worktree directory that make up its local settings.
+#[derive(Default)]
+struct LocalSettingsFiles {
+    settings: Option<String>,
+    editorconfig: Option<String>,
+}
+
+impl LocalSettingsFiles {
+    /// Returns the settings of the `.editorconfig` file of the directory.
+    fn editorconfig_settings(
+        &self,
+        languages: &LanguageRegistry,
+    ) -> Option<AllLanguageSettingsContent> {
+        let editorconfig = self.editorconfig.as_deref()?;
+        Some(EditorConfig::parse(editorconfig).settings(&languages.language_path_suffixes()))
+    }
+
+    /// Returns the settings of the directory, where the `.editorconfig` file takes precedence
+    /// over the `.zed/settings.json` one.
+    fn content(
+        &self,
+        editorconfig_settings: Option<&AllLanguageSettingsContent>,
+    ) -> Option<String> {
+        let Some(editorconfig_settings) = editorconfig_settings else {
+            return self.settings.clone();
+        };
+        let mut settings = self
+            .settings
+            .as_deref()
+            .and_then(|settings| {
+                settings::parse_json_with_comments::<serde_json::Value>(settings).log_err()
+            })
+            .unwrap_or_else(|| serde_json::json!({}));
+        let editorconfig_settings = serde_json::to_value(editorconfig_settings).log_err()?;
+        merge_non_null_json_value_into(editorconfig_settings, &mut settings);
+        Some(settings.to_string())
+    }
 }
 
 pub enum LanguageServerToQuery {
@@ -248,7 +306,7 @@ struct LspBufferSnapshot {
 enum BufferOrderedMessage {
     Operation {
         buffer_id: BufferId,
-        operation: proto::Operation,
+        operation: language::Operation,
     },
     LanguageServerUpdate {
         language_server_id: LanguageServerId,
@@ -328,6 +386,7 @@ pub enum Event {
     Notification(String),
     LanguageServerPrompt(LanguageServerPromptRequest),
     LanguageNotFound(Model<Buffer>),
+    LanguageOverridesChanged,
     ActiveEntryChanged(Option<ProjectEntryId>),
     ActivateProjectPanel,
     WorktreeAdded,
@@ -585,6 +644,21 @@ impl Hover {
 #[derive(Default)]
 pub struct ProjectTransaction(pub HashMap<Model<Buffer>, language::Transaction>);
 
+/// How a save started with [`Project::save_buffer_with_progress`] turned out.
+#[derive(Debug)]
+pub enum SaveOutcome {
+    /// The buffer was saved as of `version`. It's only clean if it hasn't been edited since.
+    Saved {
+        version: clock::Global,
+        mtime: Option<SystemTime>,
+    },
+    /// The buffer has edits while its file changed on disk, so the file was left alone.
+    Conflict,
+    /// The save was cancelled before the file got replaced, so the file was left alone.
+    Cancelled,
+    IoError(anyhow::Error),
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FormatTrigger {
     Save,
@@ -701,6 +775,7 @@ impl Project {
         client.add_model_request_handler(Self::handle_lsp_command::<PrepareRename>);
         client.add_model_request_handler(Self::handle_lsp_command::<PerformRename>);
         client.add_model_request_handler(Self::handle_search_project);
+        client.add_model_streaming_request_handler(Self::handle_stream_project_search);
         client.add_model_request_handler(Self::handle_get_project_symbols);
         client.add_model_request_handler(Self::handle_open_buffer_for_symbol);
         client.add_model_request_handler(Self::handle_open_buffer_by_id);
@@ -740,6 +815,8 @@ impl Project {
                 collaborators: Default::default(),
                 opened_buffers: Default::default(),
                 shared_buffers: Default::default(),
+                remote_searches: Default::default(),
+                language_overrides: Default::default(),
                 loading_buffers_by_path: Default::default(),
                 loading_local_worktrees: Default::default(),
                 local_buffer_ids_by_path: Default::default(),
@@ -749,6 +826,7 @@ impl Project {
                 client_state: ProjectClientState::Local,
                 loading_buffers: HashMap::default(),
                 incomplete_remote_buffers: HashMap::default(),
+                read_only_file_buffers: HashSet::default(),
                 client_subscriptions: Vec::new(),
                 _subscriptions: vec![
                     cx.observe_global::<SettingsStore>(Self::on_settings_changed),
@@ -791,6 +869,7 @@ impl Project {
                 dev_server_project_id: None,
                 search_history: Self::new_search_history(),
                 yarn_worktree_ids_reported: Vec::new(),
+                local_settings_files: HashMap::default(),
             }
         })
     }
@@ -882,7 +961,10 @@ impl Project {
                 loading_buffers_by_path: Default::default(),
                 loading_buffers: Default::default(),
                 shared_buffers: Default::default(),
+                remote_searches: Default::default(),
+                language_overrides: Default::default(),
                 incomplete_remote_buffers: Default::default(),
+                read_only_file_buffers: Default::default(),
                 loading_local_worktrees: Default::default(),
                 local_buffer_ids_by_path: Default::default(),
                 local_buffer_ids_by_entry_id: Default::default(),
@@ -956,6 +1038,7 @@ impl Project {
                     .map(|dev_server_project_id| DevServerProjectId(dev_server_project_id)),
                 search_history: Self::new_search_history(),
                 yarn_worktree_ids_reported: Vec::new(),
+                local_settings_files: HashMap::default(),
             };
             this.set_role(role, cx);
             for worktree in worktrees {
@@ -1268,6 +1351,11 @@ impl Project {
             .collect()
     }
 
+    #[cfg(any(test, feature = "test-support"))]
+    pub fn remote_search_count(&self) -> usize {
+        Arc::strong_count(&self.remote_searches) - 1
+    }
+
     #[cfg(any(test, feature = "test-support"))]
     pub fn has_open_buffer(&self, path: impl Into<ProjectPath>, cx: &AppContext) -> bool {
         let path = path.into();
@@ -1517,6 +1605,18 @@ impl Project {
         worktree.update(cx, |worktree, cx| worktree.expand_entry(entry_id, cx))
     }
 
+    /// Loads the ignored directories of the local worktrees, which aren't scanned until
+    /// something needs them, so that their files can be searched too.
+    pub fn load_ignored_dirs(&self, cx: &AppContext) -> Task<()> {
+        let loads = self
+            .visible_worktrees(cx)
+            .filter_map(|worktree| Some(worktree.read(cx).as_local()?.load_ignored_dirs()))
+            .collect::<Vec<_>>();
+        cx.background_executor().spawn(async move {
+            join_all(loads).await;
+        })
+    }
+
     pub fn shared(&mut self, project_id: u64, cx: &mut ModelContext<Self>) -> Result<()> {
         if !matches!(self.client_state, ProjectClientState::Local) {
             if let ProjectClientState::Remote { in_room, .. } = &mut self.client_state {
@@ -1840,10 +1940,61 @@ impl Project {
             }
 
             *capability = new_capability;
+            for worktree in self.worktrees().collect::<Vec<_>>() {
+                worktree.update(cx, |worktree, cx| {
+                    worktree.set_read_only_by_role(new_capability == Capability::ReadOnly, cx)
+                });
+            }
             for buffer in self.opened_buffers() {
-                buffer.update(cx, |buffer, cx| buffer.set_capability(new_capability, cx));
+                let capability = self.buffer_capability(buffer.read(cx), cx);
+                buffer.update(cx, |buffer, cx| buffer.set_capability(capability, cx));
+            }
+        }
+    }
+
+    /// Marks the given worktree as read-only (or writable again), updating the
+    /// capability of every buffer already opened from it. Guests whose role is read-only
+    /// can't make their worktrees writable this way.
+    pub fn set_worktree_read_only(
+        &mut self,
+        worktree_id: WorktreeId,
+        read_only: bool,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let Some(worktree) = self.worktree_for_id(worktree_id, cx) else {
+            return;
+        };
+        worktree.update(cx, |worktree, cx| worktree.set_read_only(read_only, cx));
+        for buffer in self.opened_buffers() {
+            let in_worktree = File::from_dyn(buffer.read(cx).file())
+                .map_or(false, |file| file.worktree_id(cx) == worktree_id);
+            if in_worktree {
+                let capability = self.buffer_capability(buffer.read(cx), cx);
+                buffer.update(cx, |buffer, cx| buffer.set_capability(capability, cx));
             }
         }
+        cx.notify();
+    }
+
+    /// Returns what the given buffer should allow, given everything that can make it read-only:
+    /// the project being read-only, its file or its worktree.
+    fn buffer_capability(&self, buffer: &Buffer, cx: &AppContext) -> Capability {
+        if self.is_read_only() || self.read_only_file_buffers.contains(&buffer.remote_id()) {
+            return Capability::ReadOnly;
+        }
+        let Some(file) = File::from_dyn(buffer.file()) else {
+            return Capability::ReadWrite;
+        };
+        // The buffers of worktrees that the host stopped sharing can't be saved anymore.
+        match self.worktree_for_id(file.worktree_id(cx), cx) {
+            Some(worktree) if !worktree.read(cx).is_read_only() => Capability::ReadWrite,
+            _ => Capability::ReadOnly,
+        }
+    }
+
+    pub fn is_worktree_read_only(&self, worktree_id: WorktreeId, cx: &AppContext) -> bool {
+        self.worktree_for_id(worktree_id, cx)
+            .map_or(false, |worktree| worktree.read(cx).is_read_only())
     }
 
     fn disconnected_from_host_internal(&mut self, cx: &mut AppContext) {
@@ -1855,6 +2006,7 @@ impl Project {
             *sharing_has_stopped = true;
 
             self.collaborators.clear();
+            self.client_subscriptions.clear();
 
             for worktree in &self.worktrees {
                 if let Some(worktree) = worktree.upgrade() {
@@ -2076,30 +2228,55 @@ impl Project {
         worktree: Model<Worktree>,
         cx: &mut ModelContext<Self>,
     ) -> Task<Result<Model<Buffer>>> {
+        let capability = if worktree.read(cx).is_read_only() {
+            Capability::ReadOnly
+        } else {
+            Capability::ReadWrite
+        };
+        let diff_against_saved_file = Self::diffs_against_saved_file(cx);
         let load_buffer = worktree.update(cx, |worktree, cx| {
             let load_file = worktree.load_file(path.as_ref(), cx);
             let reservation = cx.reserve_model();
             let buffer_id = BufferId::from(reservation.entity_id().as_non_zero_u64());
             cx.spawn(move |_, mut cx| async move {
                 let loaded = load_file.await?;
+                let encoding = loaded.encoding;
+                // Files we aren't allowed to write to are opened read-only, rather than
+                // letting edits pile up that can't be saved.
+                let is_read_only_file = loaded.is_read_only;
+                let capability = if is_read_only_file {
+                    Capability::ReadOnly
+                } else {
+                    capability
+                };
+                let diff_base = if diff_against_saved_file {
+                    Some(loaded.text.clone())
+                } else {
+                    loaded.diff_base
+                };
                 let text_buffer = cx
                     .background_executor()
                     .spawn(async move { text::Buffer::new(0, buffer_id, loaded.text) })
                     .await;
-                cx.insert_model(reservation, |_| {
-                    Buffer::build(
-                        text_buffer,
-                        loaded.diff_base,
-                        Some(loaded.file),
-                        Capability::ReadWrite,
-                    )
-                })
+                let buffer = cx.insert_model(reservation, |_| {
+                    Buffer::build(text_buffer, diff_base, Some(loaded.file), capability)
+                        .with_encoding(encoding)
+                })?;
+                anyhow::Ok((buffer, is_read_only_file))
             })
         });
 
         cx.spawn(move |this, mut cx| async move {
             let buffer = match load_buffer.await {
-                Ok(buffer) => Ok(buffer),
+                Ok((buffer, is_read_only_file)) => {
+                    if is_read_only_file {
+                        let buffer_id = buffer.update(&mut cx, |buffer, _| buffer.remote_id())?;
+                        this.update(&mut cx, |this, _| {
+                            this.read_only_file_buffers.insert(buffer_id)
+                        })?;
+                    }
+                    Ok(buffer)
+                }
                 Err(error) if is_not_found_error(&error) => cx.new_model(|cx| {
                     let buffer_id = BufferId::from(cx.entity_id().as_non_zero_u64());
                     let text_buffer = text::Buffer::new(0, buffer_id, "".into());
@@ -2115,7 +2292,7 @@ impl Project {
                             is_deleted: false,
                             is_private: false,
                         })),
-                        Capability::ReadWrite,
+                        capability,
                     )
                 }),
                 Err(e) => Err(e),
@@ -2242,13 +2419,84 @@ impl Project {
         buffer: Model<Buffer>,
         cx: &mut ModelContext<Self>,
     ) -> Task<Result<()>> {
+        let save = self.save_buffer_internal(buffer, SaveProgress::default(), cx);
+        cx.background_executor().spawn(async move {
+            save.await?;
+            Ok(())
+        })
+    }
+
+    /// Saves the buffer like `save_buffer`, except that a buffer which conflicts with its file
+    /// is left unsaved unless `overwrite_conflict` is set, and that how much of the file has
+    /// been written gets reported to
+    /// `progress`, through which the save can be cancelled until the file is replaced. Saves of
+    /// remote buffers are written by the host, so they report no progress and can only be
+    /// cancelled before they're sent.
+    pub fn save_buffer_with_progress(
+        &self,
+        buffer: Model<Buffer>,
+        progress: SaveProgress,
+        overwrite_conflict: bool,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<SaveOutcome> {
+        if !overwrite_conflict && buffer.read(cx).has_conflict() {
+            return Task::ready(SaveOutcome::Conflict);
+        }
+        if progress.is_cancelled() {
+            return Task::ready(SaveOutcome::Cancelled);
+        }
+
+        let save = self.save_buffer_internal(buffer, progress, cx);
+        cx.background_executor().spawn(async move {
+            match save.await {
+                Ok((version, mtime)) => SaveOutcome::Saved { version, mtime },
+                Err(error) if error.is::<SaveCancelled>() => SaveOutcome::Cancelled,
+                Err(error) => SaveOutcome::IoError(error),
+            }
+        })
+    }
+
+    /// Saves the buffer, returning the version of it that was saved along with the new mtime
+    /// of its file.
+    fn save_buffer_internal(
+        &self,
+        buffer: Model<Buffer>,
+        progress: SaveProgress,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<(clock::Global, Option<SystemTime>)>> {
         let Some(file) = File::from_dyn(buffer.read(cx).file()) else {
             return Task::ready(Err(anyhow!("buffer doesn't have a file")));
         };
         let worktree = file.worktree.clone();
+        if worktree.read(cx).is_read_only() {
+            return Task::ready(Err(anyhow!(
+                "cannot save {:?}: worktree {:?} is read-only",
+                file.path,
+                worktree.read(cx).root_name()
+            )));
+        }
         let path = file.path.clone();
         if self.is_local() {
-            self.save_local_buffer(worktree, buffer, path, false, cx)
+            // A buffer whose edits have all been undone already matches its file on disk,
+            // so instead of writing it again, only its saved version is moved forward.
+            if !buffer.read(cx).is_dirty() {
+                let version = buffer.read(cx).version();
+                let mtime = file.mtime;
+                if let Some(project_id) = self.remote_id() {
+                    self.client
+                        .send(proto::BufferSaved {
+                            project_id,
+                            buffer_id: buffer.read(cx).remote_id().into(),
+                            version: serialize_version(&version),
+                            mtime: mtime.map(|time| time.into()),
+                        })
+                        .log_err();
+                }
+                buffer.update(cx, |buffer, cx| buffer.did_save(version.clone(), mtime, cx));
+                return Task::ready(Ok((version, mtime)));
+            }
+
+            self.save_local_buffer(worktree, buffer, path, false, progress, cx)
         } else {
             self.save_remote_buffer(buffer, None, cx)
         }
@@ -2271,7 +2519,14 @@ impl Project {
                     if let Some(old_file) = &old_file {
                         this.unregister_buffer_from_language_servers(&buffer, old_file, cx);
                     }
-                    this.save_local_buffer(worktree, buffer.clone(), path.path, true, cx)
+                    this.save_local_buffer(
+                        worktree,
+                        buffer.clone(),
+                        path.path,
+                        true,
+                        SaveProgress::default(),
+                        cx,
+                    )
                 } else {
                     this.save_remote_buffer(buffer.clone(), Some(path.to_proto()), cx)
                 }
@@ -2286,25 +2541,37 @@ impl Project {
         })
     }
 
+    /// Writes the buffer to its file, returning the version of it that was written along with
+    /// the new mtime of the file. The buffer only counts as saved up to that version, so edits
+    /// made while the file is written keep it dirty.
     pub fn save_local_buffer(
         &self,
         worktree: Model<Worktree>,
         buffer_handle: Model<Buffer>,
         path: Arc<Path>,
         mut has_changed_file: bool,
+        progress: SaveProgress,
         cx: &mut ModelContext<Self>,
-    ) -> Task<Result<()>> {
+    ) -> Task<Result<(clock::Global, Option<SystemTime>)>> {
         let buffer = buffer_handle.read(cx);
         let buffer_id = buffer.remote_id();
         let text = buffer.as_rope().clone();
         let line_ending = buffer.line_ending();
+        let encoding = buffer.encoding();
         let version = buffer.version();
         if buffer.file().is_some_and(|file| !file.is_created()) {
             has_changed_file = true;
         }
 
         let save = worktree.update(cx, |worktree, cx| {
-            worktree.write_file(path.as_ref(), text, line_ending, cx)
+            worktree.write_file_with_progress(
+                path.as_ref(),
+                text,
+                line_ending,
+                encoding,
+                progress,
+                cx,
+            )
         });
 
         let client = self.client.clone();
@@ -2343,16 +2610,18 @@ impl Project {
                 buffer.did_save(version.clone(), mtime, cx);
             })?;
 
-            Ok(())
+            Ok((version, mtime))
         })
     }
 
+    /// Asks the host to save the buffer, returning the version of it that the host saved along
+    /// with the new mtime of its file.
     pub fn save_remote_buffer(
         &self,
         buffer_handle: Model<Buffer>,
         new_path: Option<proto::ProjectPath>,
         cx: &mut ModelContext<Self>,
-    ) -> Task<Result<()>> {
+    ) -> Task<Result<(clock::Global, Option<SystemTime>)>> {
         let buffer = buffer_handle.read(cx);
         let buffer_id = buffer.remote_id().into();
         let version = buffer.version();
@@ -2374,7 +2643,7 @@ impl Project {
                 buffer.did_save(version.clone(), mtime, cx);
             })?;
 
-            Ok(())
+            Ok((version, mtime))
         })
     }
 
@@ -2456,6 +2725,7 @@ impl Project {
         self.detect_language_for_buffer(buffer, cx);
         self.register_buffer_with_language_servers(buffer, cx);
         cx.observe_release(buffer, |this, buffer, cx| {
+            this.read_only_file_buffers.remove(&buffer.remote_id());
             if let Some(file) = File::from_dyn(buffer.file()) {
                 if file.is_local() {
                     let uri = lsp::Url::from_file_path(file.abs_path(cx)).unwrap();
@@ -2614,7 +2884,7 @@ impl Project {
         let mut operations_by_buffer_id = HashMap::default();
         async fn flush_operations(
             this: &WeakModel<Project>,
-            operations_by_buffer_id: &mut HashMap<BufferId, Vec<proto::Operation>>,
+            operations_by_buffer_id: &mut HashMap<BufferId, Vec<language::Operation>>,
             needs_resync_with_host: &mut bool,
             is_local: bool,
             cx: &mut AsyncAppContext,
@@ -2625,7 +2895,7 @@ impl Project {
                     Some(this.client.request(proto::UpdateBuffer {
                         buffer_id: buffer_id.into(),
                         project_id,
-                        operations,
+                        operations: language::proto::serialize_operations(&operations),
                     }))
                 })?;
                 if let Some(request) = request {
@@ -2662,12 +2932,21 @@ impl Project {
 
                     BufferOrderedMessage::Resync => {
                         operations_by_buffer_id.clear();
-                        if this
+                        match this
                             .update(&mut cx, |this, cx| this.synchronize_remote_buffers(cx))?
                             .await
-                            .is_ok()
                         {
-                            needs_resync_with_host = false;
+                            Ok(()) => needs_resync_with_host = false,
+                            Err(error) => {
+                                log::error!("failed to resynchronize buffers: {error:?}");
+                                this.update(&mut cx, |_, cx| {
+                                    cx.emit(Event::Notification(
+                                        "Your edits could not be synced with the host. \
+                                        They will be synced once the connection is restored."
+                                            .to_string(),
+                                    ))
+                                })?;
+                            }
                         }
                     }
 
@@ -2724,12 +3003,18 @@ impl Project {
         ) {
             self.request_buffer_diff_recalculation(&buffer, cx);
         }
+        if matches!(event, BufferEvent::Saved | BufferEvent::Reloaded)
+            && self.is_local()
+            && Self::diffs_against_saved_file(cx)
+        {
+            self.set_diff_base_to_saved_text(&buffer, cx);
+        }
 
         match event {
             BufferEvent::Operation(operation) => {
                 self.enqueue_buffer_ordered_message(BufferOrderedMessage::Operation {
                     buffer_id: buffer.read(cx).remote_id(),
-                    operation: language::proto::serialize_operation(operation),
+                    operation: operation.clone(),
                 })
                 .ok();
             }
@@ -2952,6 +3237,32 @@ impl Project {
         }
     }
 
+    fn diffs_against_saved_file(cx: &AppContext) -> bool {
+        matches!(
+            ProjectSettings::get_global(cx).git.git_gutter,
+            Some(GitGutterSetting::SavedFile)
+        )
+    }
+
+    /// Makes the text of a buffer that was just saved or reloaded its diff base, so that its
+    /// diff hunks show the changes made since.
+    fn set_diff_base_to_saved_text(&mut self, buffer: &Model<Buffer>, cx: &mut ModelContext<Self>) {
+        let diff_base = buffer.read(cx).text();
+        let buffer_id = buffer.update(cx, |buffer, cx| {
+            buffer.set_diff_base(Some(diff_base.clone()), cx);
+            buffer.remote_id().into()
+        });
+        if let Some(project_id) = self.remote_id() {
+            self.client
+                .send(proto::UpdateDiffBase {
+                    project_id,
+                    buffer_id,
+                    diff_base: Some(diff_base),
+                })
+                .log_err();
+        }
+    }
+
     fn request_buffer_diff_recalculation(
         &mut self,
         buffer: &Model<Buffer>,
@@ -3159,6 +3470,40 @@ impl Project {
         &mut self,
         buffer_handle: &Model<Buffer>,
         cx: &mut ModelContext<Self>,
+    ) {
+        let Some(language_name) = self.language_override_for_buffer(buffer_handle, cx) else {
+            self.detect_file_language_for_buffer(buffer_handle, cx);
+            return;
+        };
+
+        let language = self.languages.language_for_name(&language_name);
+        let buffer = buffer_handle.downgrade();
+        cx.spawn(|this, mut cx| async move {
+            let language = language.await;
+            this.update(&mut cx, |this, cx| {
+                let buffer = buffer.upgrade()?;
+                // The override may have changed while the language was loading.
+                if this.language_override_for_buffer(&buffer, cx).as_ref() != Some(&language_name) {
+                    return None;
+                }
+                match language {
+                    Ok(language) => this.set_language_for_buffer(&buffer, language, cx),
+                    Err(error) => {
+                        log::error!("failed to load overridden language {language_name}: {error}");
+                        this.detect_file_language_for_buffer(&buffer, cx);
+                    }
+                }
+                Some(())
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    fn detect_file_language_for_buffer(
+        &mut self,
+        buffer_handle: &Model<Buffer>,
+        cx: &mut ModelContext<Self>,
     ) {
         // If the buffer has a language, set it and start the language server if we haven't already.
         let buffer = buffer_handle.read(cx);
@@ -3186,6 +3531,66 @@ impl Project {
         };
     }
 
+    fn language_override_for_buffer(
+        &self,
+        buffer: &Model<Buffer>,
+        cx: &AppContext,
+    ) -> Option<Arc<str>> {
+        let project_path = buffer.read(cx).project_path(cx)?;
+        self.language_overrides.get(&project_path).cloned()
+    }
+
+    pub fn language_overrides(&self) -> &HashMap<ProjectPath, Arc<str>> {
+        &self.language_overrides
+    }
+
+    /// Sets the language of a buffer, overriding the one detected for it. The override applies to
+    /// every buffer of the same file, until it gets cleared by passing `None`.
+    pub fn set_language_override(
+        &mut self,
+        buffer: &Model<Buffer>,
+        language: Option<Arc<Language>>,
+        cx: &mut ModelContext<Self>,
+    ) {
+        if let Some(project_path) = buffer.read(cx).project_path(cx) {
+            let changed = match &language {
+                Some(language) => {
+                    self.language_overrides
+                        .insert(project_path, language.name())
+                        .as_ref()
+                        != Some(&language.name())
+                }
+                None => self.language_overrides.remove(&project_path).is_some(),
+            };
+            if changed {
+                cx.emit(Event::LanguageOverridesChanged);
+            }
+        }
+
+        match language {
+            Some(language) => self.set_language_for_buffer(buffer, language, cx),
+            None => self.detect_language_for_buffer(buffer, cx),
+        }
+    }
+
+    /// Restores the overrides of the languages of files, e.g. when reopening a workspace.
+    pub fn restore_language_overrides(
+        &mut self,
+        overrides: impl IntoIterator<Item = (ProjectPath, Arc<str>)>,
+        cx: &mut ModelContext<Self>,
+    ) {
+        self.language_overrides.extend(overrides);
+        let overridden_buffers = self
+            .opened_buffers
+            .values()
+            .filter_map(|buffer| buffer.upgrade())
+            .filter(|buffer| self.language_override_for_buffer(buffer, cx).is_some())
+            .collect::<Vec<_>>();
+        for buffer in overridden_buffers {
+            self.detect_language_for_buffer(&buffer, cx);
+        }
+    }
+
     pub fn set_language_for_buffer(
         &mut self,
         buffer: &Model<Buffer>,
@@ -5072,7 +5477,12 @@ impl Project {
             let trailing_whitespace_diff = if remove_trailing_whitespace {
                 Some(
                     buffer
-                        .update(&mut cx, |b, cx| b.remove_trailing_whitespace(cx))?
+                        .update(&mut cx, |b, cx| {
+                            b.remove_trailing_whitespace(
+                                settings.remove_trailing_whitespace_from,
+                                cx,
+                            )
+                        })?
                         .await,
                 )
             } else {
@@ -7011,46 +7421,83 @@ impl Project {
         if self.is_local() {
             self.search_local(query, cx)
         } else if let Some(project_id) = self.remote_id() {
-            let (tx, rx) = smol::channel::unbounded();
-            let request = self.client.request(query.to_proto(project_id));
-            cx.spawn(move |this, mut cx| async move {
-                let response = request.await?;
-                let mut result = HashMap::default();
-                for location in response.locations {
-                    let buffer_id = BufferId::new(location.buffer_id)?;
-                    let target_buffer = this
-                        .update(&mut cx, |this, cx| {
-                            this.wait_for_remote_buffer(buffer_id, cx)
-                        })?
-                        .await?;
-                    let start = location
-                        .start
-                        .and_then(deserialize_anchor)
-                        .ok_or_else(|| anyhow!("missing target start"))?;
-                    let end = location
-                        .end
-                        .and_then(deserialize_anchor)
-                        .ok_or_else(|| anyhow!("missing target end"))?;
-                    result
-                        .entry(target_buffer)
-                        .or_insert(Vec::new())
-                        .push(start..end)
-                }
-                for (buffer, ranges) in result {
-                    let _ = tx.send(SearchResult::Buffer { buffer, ranges }).await;
+            self.search_remote(project_id, query, cx)
+        } else {
+            unimplemented!();
+        }
+    }
+
+    /// Streams the results of searching the project on the host. The matches in the buffers that
+    /// have changed since the host searched them are found again in the guest's copy of them.
+    fn search_remote(
+        &mut self,
+        project_id: u64,
+        query: SearchQuery,
+        cx: &mut ModelContext<Self>,
+    ) -> Receiver<SearchResult> {
+        let (tx, rx) = smol::channel::unbounded();
+        let request = self.client.request_stream(proto::StreamProjectSearch {
+            project_id,
+            worktree_id: None,
+            query: Some(query.to_proto(project_id)),
+        });
+        cx.spawn(move |this, mut cx| async move {
+            // Returning before the host is done drops its results, which cancels the search.
+            let mut results = pin!(request.await?);
+            while let Some(result) = results.next().await {
+                let result = result?;
+                if result.limit_reached {
+                    if tx.send(SearchResult::LimitReached).await.is_err() {
+                        return Ok(());
+                    }
+                    continue;
                 }
 
-                if response.limit_reached {
-                    let _ = tx.send(SearchResult::LimitReached).await;
+                let project_path = ProjectPath {
+                    worktree_id: WorktreeId::from_proto(result.worktree_id),
+                    path: Path::new(&result.path).into(),
+                };
+                let Some(buffer) = this
+                    .update(&mut cx, |this, cx| this.open_buffer(project_path, cx))?
+                    .await
+                    .log_err()
+                else {
+                    continue;
+                };
+                let snapshot = buffer.read_with(&cx, |buffer, _| buffer.snapshot())?;
+                let is_dirty = buffer.read_with(&cx, |buffer, _| buffer.is_dirty())?;
+                let ranges = if is_dirty || !remote_search_lines_match(&snapshot, &result) {
+                    let query = query.clone();
+                    cx.background_executor()
+                        .spawn(async move {
+                            query
+                                .search(&snapshot, None)
+                                .await
+                                .iter()
+                                .map(|range| {
+                                    snapshot.anchor_before(range.start)
+                                        ..snapshot.anchor_after(range.end)
+                                })
+                                .collect::<Vec<_>>()
+                        })
+                        .await
+                } else {
+                    remote_search_ranges(&snapshot, &result)
+                };
+                if !ranges.is_empty()
+                    && tx
+                        .send(SearchResult::Buffer { buffer, ranges })
+                        .await
+                        .is_err()
+                {
+                    return Ok(());
                 }
+            }
 
-                Result::<(), anyhow::Error>::Ok(())
-            })
-            .detach_and_log_err(cx);
-            rx
-        } else {
-            unimplemented!();
-        }
+            anyhow::Ok(())
+        })
+        .detach_and_log_err(cx);
+        rx
     }
 
     pub fn search_local(
@@ -7146,18 +7593,21 @@ impl Project {
             })
             .collect();
         cx.background_executor()
-            .spawn(Self::background_search(
-                unnamed_files,
-                opened_buffers,
-                cx.background_executor().clone(),
-                self.fs.clone(),
-                workers,
-                query.clone(),
-                include_root,
-                path_count,
-                snapshots,
-                matching_paths_tx,
-            ))
+            .spawn_with_priority(
+                TaskPriority::Low,
+                Self::background_search(
+                    unnamed_files,
+                    opened_buffers,
+                    cx.background_executor().clone(),
+                    self.fs.clone(),
+                    workers,
+                    query.clone(),
+                    include_root,
+                    path_count,
+                    snapshots,
+                    matching_paths_tx,
+                ),
+            )
             .detach();
 
         let (result_tx, result_rx) = smol::channel::bounded(1024);
@@ -7205,7 +7655,7 @@ impl Project {
                         let snapshot = buffer.read_with(&cx, |buffer, _| buffer.snapshot())?;
                         let ranges = cx
                             .background_executor()
-                            .spawn(async move {
+                            .spawn_with_priority(TaskPriority::Low, async move {
                                 query
                                     .search(&snapshot, None)
                                     .await
@@ -7287,7 +7737,7 @@ impl Project {
         let paths_per_worker = (path_count + workers - 1) / workers;
 
         executor
-            .scoped(|scope| {
+            .scoped_with_priority(TaskPriority::Low, |scope| {
                 let max_concurrent_workers = Arc::new(Semaphore::new(workers));
 
                 for worker_ix in 0..workers {
@@ -7577,14 +8027,31 @@ impl Project {
         visible: bool,
         cx: &mut ModelContext<Self>,
     ) -> Task<Result<(Model<Worktree>, PathBuf)>> {
-        let abs_path = abs_path.as_ref();
-        if let Some((tree, relative_path)) = self.find_local_worktree(abs_path, cx) {
-            Task::ready(Ok((tree, relative_path)))
-        } else {
-            let worktree = self.create_local_worktree(abs_path, visible, cx);
-            cx.background_executor()
-                .spawn(async move { Ok((worktree.await?, PathBuf::new())) })
+        // The worktree keeps the path as it was given, e.g. `~/code/proj`, to display it.
+        let display_path = abs_path.as_ref().to_path_buf();
+        let abs_path = worktree::absolutize_root_path(&display_path);
+        if let Some((tree, relative_path)) = self.find_local_worktree(&abs_path, cx) {
+            return Task::ready(Ok((tree, relative_path)));
         }
+
+        // The path may still point into an existing worktree through a symlink,
+        // so resolve it before creating a new one.
+        let fs = self.fs.clone();
+        cx.spawn(move |this, mut cx| async move {
+            if let Ok(canonical_path) = fs.canonicalize(&abs_path).await {
+                if let Some(found) = this.update(&mut cx, |this, cx| {
+                    this.find_local_worktree(&canonical_path, cx)
+                })? {
+                    return Ok(found);
+                }
+            }
+            let worktree = this
+                .update(&mut cx, |this, cx| {
+                    this.create_local_worktree(display_path, visible, cx)
+                })?
+                .await?;
+            Ok((worktree, PathBuf::new()))
+        })
     }
 
     pub fn find_local_worktree(
@@ -7594,11 +8061,12 @@ impl Project {
     ) -> Option<(Model<Worktree>, PathBuf)> {
         for tree in &self.worktrees {
             if let Some(tree) = tree.upgrade() {
-                if let Some(relative_path) = tree
-                    .read(cx)
-                    .as_local()
-                    .and_then(|t| abs_path.strip_prefix(t.abs_path()).ok())
-                {
+                if let Some(relative_path) = tree.read(cx).as_local().and_then(|t| {
+                    abs_path
+                        .strip_prefix(t.abs_path())
+                        .or_else(|_| abs_path.strip_prefix(t.canonical_abs_path()))
+                        .ok()
+                }) {
                     return Some((tree.clone(), relative_path.into()));
                 }
             }
@@ -7606,6 +8074,18 @@ impl Project {
         None
     }
 
+    fn worktree_for_canonical_path(
+        &self,
+        canonical_abs_path: &Path,
+        cx: &AppContext,
+    ) -> Option<Model<Worktree>> {
+        self.worktrees().find(|worktree| {
+            worktree.read(cx).as_local().map_or(false, |worktree| {
+                worktree.canonical_abs_path().as_ref() == canonical_abs_path
+            })
+        })
+    }
+
     pub fn is_shared(&self) -> bool {
         match &self.client_state {
             ProjectClientState::Shared { .. } => true,
@@ -7622,7 +8102,8 @@ impl Project {
     ) -> Task<Result<Model<Worktree>>> {
         let fs = self.fs.clone();
         let next_entry_id = self.next_entry_id.clone();
-        let path: Arc<Path> = abs_path.as_ref().into();
+        let display_path: Arc<Path> = abs_path.as_ref().into();
+        let path: Arc<Path> = worktree::absolutize_root_path(&display_path).into();
         let task = self
             .loading_local_worktrees
             .entry(path.clone())
@@ -7630,7 +8111,7 @@ impl Project {
                 cx.spawn(move |project, mut cx| {
                     async move {
                         let worktree =
-                            Worktree::local(path.clone(), visible, fs, next_entry_id, &mut cx)
+                            Worktree::local(display_path, visible, fs, next_entry_id, &mut cx)
                                 .await;
 
                         project.update(&mut cx, |project, _| {
@@ -7638,6 +8119,22 @@ impl Project {
                         })?;
 
                         let worktree = worktree?;
+                        let canonical_abs_path = worktree.update(&mut cx, |worktree, _| {
+                            worktree.as_local().unwrap().canonical_abs_path().clone()
+                        })?;
+                        // Opening the same directory through a different path (e.g. via a
+                        // symlink) should reuse the worktree that is already open.
+                        if let Some(existing_worktree) =
+                            project.update(&mut cx, |project, cx| {
+                                project
+                                    .worktree_for_canonical_path(&canonical_abs_path, cx)
+                                    .filter(|existing_worktree| {
+                                        existing_worktree.entity_id() != worktree.entity_id()
+                                    })
+                            })?
+                        {
+                            return Ok(existing_worktree);
+                        }
                         project
                             .update(&mut cx, |project, cx| project.add_worktree(&worktree, cx))?;
 
@@ -7740,6 +8237,9 @@ impl Project {
     }
 
     fn add_worktree(&mut self, worktree: &Model<Worktree>, cx: &mut ModelContext<Self>) {
+        if self.capability() == Capability::ReadOnly {
+            worktree.update(cx, |worktree, cx| worktree.set_read_only_by_role(true, cx));
+        }
         cx.observe(worktree, |_, _, cx| cx.notify()).detach();
         cx.subscribe(worktree, |this, worktree, event, cx| {
             let is_local = worktree.read(cx).is_local();
@@ -7798,6 +8298,10 @@ impl Project {
         let handle_id = worktree.entity_id();
         cx.observe_release(worktree, move |this, worktree, cx| {
             let _ = this.remove_worktree(worktree.id(), cx);
+            this.local_settings_files
+                .retain(|(worktree_id, _), _| *worktree_id != handle_id.as_u64() as usize);
+            cx.default_global::<EditorConfigSettings>()
+                .clear(handle_id.as_u64() as usize);
             cx.update_global::<SettingsStore, _>(|store, cx| {
                 store
                     .clear_local_settings(handle_id.as_u64() as usize, cx)
@@ -8027,6 +8531,9 @@ impl Project {
         cx: &mut ModelContext<Self>,
     ) {
         debug_assert!(worktree_handle.read(cx).is_local());
+        if Self::diffs_against_saved_file(cx) {
+            return;
+        }
 
         // Identify the loading buffers whose containing repository that has changed.
         let future_buffers = self
@@ -8186,6 +8693,21 @@ impl Project {
                 settings_contents.push(async move {
                     (
                         settings_dir,
+                        LocalSettingsKind::Settings,
+                        if removed {
+                            None
+                        } else {
+                            Some(async move { fs.load(&abs_path).await }.await)
+                        },
+                    )
+                });
+            } else if path.file_name() == Some(OsStr::new(EDITORCONFIG_FILE_NAME)) {
+                let settings_dir = Arc::from(path.parent().unwrap_or(Path::new("")));
+                let fs = self.fs.clone();
+                settings_contents.push(async move {
+                    (
+                        settings_dir,
+                        LocalSettingsKind::Editorconfig,
                         if removed {
                             None
                         } else {
@@ -8247,13 +8769,42 @@ impl Project {
         }
 
         let client = self.client.clone();
-        cx.spawn(move |_, cx| async move {
-            let settings_contents: Vec<(Arc<Path>, _)> =
+        cx.spawn(move |this, mut cx| async move {
+            let settings_contents: Vec<(Arc<Path>, LocalSettingsKind, _)> =
                 futures::future::join_all(settings_contents).await;
-            cx.update(|cx| {
+            this.update(&mut cx, |this, cx| {
+                let worktree_key = worktree_id.as_u64() as usize;
+                let settings_contents = settings_contents
+                    .into_iter()
+                    .map(|(directory, kind, file_content)| {
+                        let file_content = file_content.and_then(|content| content.log_err());
+                        let files = this
+                            .local_settings_files
+                            .entry((worktree_key, directory.clone()))
+                            .or_default();
+                        match kind {
+                            LocalSettingsKind::Settings => files.settings = file_content,
+                            LocalSettingsKind::Editorconfig => files.editorconfig = file_content,
+                        }
+                        let editorconfig_settings = files.editorconfig_settings(&this.languages);
+                        let file_content = files.content(editorconfig_settings.as_ref());
+                        if files.settings.is_none() && files.editorconfig.is_none() {
+                            this.local_settings_files
+                                .remove(&(worktree_key, directory.clone()));
+                        }
+                        (directory, file_content, editorconfig_settings)
+                    })
+                    .collect::<Vec<_>>();
+                let editorconfigs = cx.default_global::<EditorConfigSettings>();
+                let settings_contents = settings_contents
+                    .into_iter()
+                    .map(|(directory, file_content, editorconfig_settings)| {
+                        editorconfigs.set(worktree_key, directory.clone(), editorconfig_settings);
+                        (directory, file_content)
+                    })
+                    .collect::<Vec<_>>();
                 cx.update_global::<SettingsStore, _>(|store, cx| {
                     for (directory, file_content) in settings_contents {
-                        let file_content = file_content.and_then(|content| content.log_err());
                         store
                             .set_local_settings(
                                 worktree_id.as_u64() as usize,
@@ -9068,11 +9619,7 @@ impl Project {
         this.update(&mut cx, |this, cx| {
             let payload = envelope.payload.clone();
             let buffer_id = BufferId::new(payload.buffer_id)?;
-            let ops = payload
-                .operations
-                .into_iter()
-                .map(language::proto::deserialize_operation)
-                .collect::<Result<Vec<_>, _>>()?;
+            let ops = language::proto::deserialize_operations(payload.operations)?;
             let is_remote = this.is_remote();
             match this.opened_buffers.entry(buffer_id) {
                 hash_map::Entry::Occupied(mut e) => match e.get_mut() {
@@ -9114,17 +9661,21 @@ impl Project {
 
                     let buffer_result = maybe!({
                         let mut buffer_file = None;
+                        let mut capability = this.capability();
                         if let Some(file) = state.file.take() {
                             let worktree_id = WorktreeId::from_proto(file.worktree_id);
                             let worktree =
                                 this.worktree_for_id(worktree_id, cx).ok_or_else(|| {
                                     anyhow!("no worktree found for id {}", file.worktree_id)
                                 })?;
+                            if worktree.read(cx).is_read_only() {
+                                capability = Capability::ReadOnly;
+                            }
                             buffer_file =
                                 Some(Arc::new(File::from_proto(file, worktree.clone(), cx)?)
                                     as Arc<dyn language::File>);
                         }
-                        Buffer::from_proto(this.replica_id(), this.capability(), state, buffer_file)
+                        Buffer::from_proto(this.replica_id(), capability, state, buffer_file)
                     });
 
                     match buffer_result {
@@ -10024,6 +10575,58 @@ impl Project {
         .await
     }
 
+    async fn handle_stream_project_search(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::StreamProjectSearch>,
+        _: Arc<Client>,
+        mut cx: AsyncAppContext,
+    ) -> Result<impl Stream<Item = Result<proto::ProjectSearchResult>>> {
+        let worktree_id = envelope.payload.worktree_id.map(WorktreeId::from_proto);
+        let query = SearchQuery::from_proto(
+            envelope
+                .payload
+                .query
+                .ok_or_else(|| anyhow!("missing search query"))?,
+        )?;
+        let (tx, rx) = mpsc::unbounded();
+        let (search, search_handle) = this.update(&mut cx, |this, cx| {
+            let mut results = this.search(query, cx);
+            let search = cx.spawn(|_, cx| async move {
+                while let Some(result) = results.next().await {
+                    let result = match result {
+                        SearchResult::Buffer { buffer, ranges } => {
+                            let result = buffer.read_with(&cx, |buffer, _| {
+                                project_search_result(buffer, &ranges, worktree_id)
+                            });
+                            match result {
+                                Ok(Some(result)) => result,
+                                Ok(None) => continue,
+                                Err(_) => break,
+                            }
+                        }
+                        SearchResult::LimitReached => proto::ProjectSearchResult {
+                            limit_reached: true,
+                            ..Default::default()
+                        },
+                    };
+                    if tx.unbounded_send(Ok(result)).is_err() {
+                        break;
+                    }
+                }
+            });
+            (search, this.remote_searches.clone())
+        })?;
+        // The stream of results owns the search, so that the search stops when the guest
+        // cancels the stream, or goes away, rather than once it finds the next result.
+        Ok(stream::unfold(
+            (rx, search, search_handle),
+            |(mut rx, search, search_handle)| async move {
+                let result = rx.next().await?;
+                Some((result, (rx, search, search_handle)))
+            },
+        ))
+    }
+
     async fn handle_open_buffer_for_symbol(
         this: Model<Self>,
         envelope: TypedEnvelope<proto::OpenBufferForSymbol>,
@@ -10409,6 +11012,33 @@ impl Project {
             }
         }
 
+        // The host stopped sharing these worktrees, so their buffers can't be saved anymore. They
+        // are kept around read-only, so that the edits that weren't saved aren't lost.
+        for worktree in old_worktrees_by_id.values() {
+            worktree.update(cx, |worktree, _| {
+                if let Some(worktree) = worktree.as_remote_mut() {
+                    worktree.disconnected_from_host();
+                }
+            });
+        }
+        for open_buffer in self.opened_buffers.values_mut() {
+            let Some(buffer) = open_buffer.upgrade() else {
+                continue;
+            };
+            let worktree_id =
+                File::from_dyn(buffer.read(cx).file()).map(|file| file.worktree_id(cx));
+            if worktree_id.map_or(true, |id| !old_worktrees_by_id.contains_key(&id)) {
+                continue;
+            }
+            buffer.update(cx, |buffer, cx| {
+                buffer.give_up_waiting();
+                buffer.set_capability(Capability::ReadOnly, cx)
+            });
+            if let OpenBuffer::Strong(buffer) = open_buffer {
+                *open_buffer = OpenBuffer::Weak(buffer.downgrade());
+            }
+        }
+
         self.metadata_changed(cx);
         for id in old_worktrees_by_id.keys() {
             cx.emit(Event::WorktreeRemoved(*id));
@@ -11408,26 +12038,40 @@ impl<'a> fuzzy::PathMatchCandidateSet<'a> for PathMatchCandidateSet {
             } else {
                 self.snapshot.files(self.include_ignored, start)
             },
+            prepared_paths: self.snapshot.candidate_index().cursor(),
         }
     }
 }
 
 pub struct PathMatchCandidateSetIter<'a> {
     traversal: Traversal<'a>,
+    /// The prepared paths, which are looked up along with the entries since both are in the
+    /// same order.
+    prepared_paths: MatchCandidateCursor<'a>,
 }
 
 impl<'a> Iterator for PathMatchCandidateSetIter<'a> {
     type Item = fuzzy::PathMatchCandidate<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.traversal.next().map(|entry| match entry.kind {
+        let entry = self.traversal.next()?;
+        // Paths that aren't in the index yet are folded while matching them.
+        let prepared = self.prepared_paths.seek_forward(&entry.path);
+        Some(match entry.kind {
             EntryKind::Dir => fuzzy::PathMatchCandidate {
                 path: &entry.path,
-                char_bag: CharBag::from_iter(entry.path.to_string_lossy().to_lowercase().chars()),
+                char_bag: prepared.map_or_else(
+                    || CharBag::from_iter(entry.path.to_string_lossy().to_lowercase().chars()),
+                    |prepared| prepared.char_bag(),
+                ),
+                is_ignored: entry.is_ignored,
+                prepared,
             },
             EntryKind::File(char_bag) => fuzzy::PathMatchCandidate {
                 path: &entry.path,
                 char_bag,
+                is_ignored: entry.is_ignored,
+                prepared,
             },
             EntryKind::UnloadedDir | EntryKind::PendingDir => unreachable!(),
         })
@@ -11556,6 +12200,97 @@ impl LspAdapterDelegate for ProjectLspAdapterDelegate {
     }
 }
 
+/// The longest text of a line that gets sent along with the matches a search found in it.
+const MAX_SEARCH_EXCERPT_LEN: usize = 256;
+
+/// Describes the matches a search found in a buffer by their lines, for a guest to find them in
+/// its own copy of the buffer. Buffers that aren't files of the given worktree are left out,
+/// as guests open the buffers by their paths.
+fn project_search_result(
+    buffer: &Buffer,
+    ranges: &[Range<Anchor>],
+    worktree_id: Option<WorktreeId>,
+) -> Option<proto::ProjectSearchResult> {
+    let file = buffer.file()?;
+    let buffer_worktree_id = WorktreeId::from_usize(file.worktree_id());
+    if worktree_id.map_or(false, |worktree_id| worktree_id != buffer_worktree_id) {
+        return None;
+    }
+
+    let snapshot = buffer.snapshot();
+    let mut lines = Vec::<proto::ProjectSearchLine>::new();
+    for range in ranges {
+        let start = range.start.to_offset(&snapshot);
+        let end = range.end.to_offset(&snapshot);
+        let row = snapshot.offset_to_point(start).row;
+        let line_start = snapshot.point_to_offset(Point::new(row, 0));
+        let range = proto::ProjectSearchRange {
+            start: (start - line_start) as u32,
+            end: (end - line_start) as u32,
+        };
+        match lines.last_mut() {
+            Some(line) if line.line == row => line.ranges.push(range),
+            _ => lines.push(proto::ProjectSearchLine {
+                line: row,
+                ranges: vec![range],
+                excerpt: search_line_excerpt(&snapshot, row),
+            }),
+        }
+    }
+    Some(proto::ProjectSearchResult {
+        worktree_id: buffer_worktree_id.to_proto(),
+        path: file.path().to_string_lossy().to_string(),
+        lines,
+        limit_reached: false,
+    })
+}
+
+fn search_line_excerpt(snapshot: &BufferSnapshot, row: u32) -> String {
+    let line = Point::new(row, 0)..Point::new(row, snapshot.line_len(row));
+    let mut excerpt = String::new();
+    for chunk in snapshot.text_for_range(line) {
+        excerpt.push_str(chunk);
+        if excerpt.len() >= MAX_SEARCH_EXCERPT_LEN {
+            let mut len = MAX_SEARCH_EXCERPT_LEN;
+            while !excerpt.is_char_boundary(len) {
+                len -= 1;
+            }
+            excerpt.truncate(len);
+            break;
+        }
+    }
+    excerpt
+}
+
+/// Returns whether a guest's copy of a buffer still has the lines the host found matches in.
+fn remote_search_lines_match(
+    snapshot: &BufferSnapshot,
+    result: &proto::ProjectSearchResult,
+) -> bool {
+    result.lines.iter().all(|line| {
+        line.line <= snapshot.max_point().row
+            && search_line_excerpt(snapshot, line.line) == line.excerpt
+    })
+}
+
+fn remote_search_ranges(
+    snapshot: &BufferSnapshot,
+    result: &proto::ProjectSearchResult,
+) -> Vec<Range<Anchor>> {
+    let mut ranges = Vec::new();
+    for line in &result.lines {
+        let line_start = snapshot.point_to_offset(Point::new(line.line, 0));
+        for range in &line.ranges {
+            let start = (line_start + range.start as usize).min(snapshot.len());
+            let end = (line_start + range.end as usize).min(snapshot.len());
+            let start = snapshot.clip_offset(start, Bias::Left);
+            let end = snapshot.clip_offset(end, Bias::Right);
+            ranges.push(snapshot.anchor_before(start)..snapshot.anchor_after(end));
+        }
+    }
+    ranges
+}
+
 fn serialize_symbol(symbol: &Symbol) -> proto::Symbol {
     proto::Symbol {
         language_server_name: symbol.language_server_name.0.to_string(),
@@ -62,6 +62,8 @@ pub enum GitGutterSetting {
     /// Show git gutter in tracked files.
     #[default]
     TrackedFiles,
+    /// Show the changes made since the file was last saved, in every file.
+    SavedFile,
     /// Hide git gutter
     Hide,
 }
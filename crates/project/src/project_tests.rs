@@ -3,9 +3,10 @@ use fs::FakeFs;
 use futures::{future, StreamExt};
 use gpui::{AppContext, SemanticVersion, UpdateGlobal};
 use language::{
-    language_settings::{AllLanguageSettings, LanguageSettingsContent},
-    tree_sitter_rust, tree_sitter_typescript, Diagnostic, FakeLspAdapter, LanguageConfig,
-    LanguageMatcher, LineEnding, OffsetRangeExt, Point, ToPoint,
+    language_settings::{AllLanguageSettings, IndentSettingsSource, LanguageSettingsContent},
+    tree_sitter_rust, tree_sitter_typescript, Capability, Diagnostic, Encoding, FakeLspAdapter,
+    IndentResolution, IndentSize, IndentSource, LanguageConfig, LanguageMatcher, LineEnding,
+    OffsetRangeExt, Point, ToPoint,
 };
 use lsp::Url;
 use parking_lot::Mutex;
@@ -13,7 +14,7 @@ use pretty_assertions::assert_eq;
 use serde_json::json;
 #[cfg(not(windows))]
 use std::os;
-use std::task::Poll;
+use std::{num::NonZeroU32, task::Poll};
 use task::{ResolvedTask, TaskContext, TaskTemplate, TaskTemplates};
 use unindent::Unindent as _;
 use util::{assert_set_eq, paths::PathMatcher, test::temp_tree};
@@ -295,6 +296,221 @@ async fn test_managing_project_specific_settings(cx: &mut gpui::TestAppContext)
     );
 }
 
+#[gpui::test]
+async fn test_editorconfig_settings_precedence(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+    cx.update(|cx| {
+        SettingsStore::update_global(cx, |store, cx| {
+            store.update_user_settings::<AllLanguageSettings>(cx, |settings| {
+                settings.defaults.tab_size = NonZeroU32::new(3);
+                settings.defaults.preferred_line_length = Some(100);
+                settings.languages.insert(
+                    "JSON".into(),
+                    LanguageSettingsContent {
+                        tab_size: NonZeroU32::new(5),
+                        ensure_final_newline_on_save: Some(false),
+                        ..Default::default()
+                    },
+                );
+            });
+        });
+    });
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/the-root",
+        json!({
+            ".zed": {
+                "settings.json": r#"{ "tab_size": 8, "hard_tabs": true }"#,
+            },
+            "a.rs": "",
+            "b.json": "",
+            "sub": {
+                ".zed": {
+                    "settings.json": r#"{ "tab_size": 6 }"#,
+                },
+                "c.rs": "",
+            }
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs.clone(), ["/the-root".as_ref()], cx).await;
+    project.update(cx, |project, _| {
+        project.languages().add(rust_lang());
+        project.languages().add(json_lang());
+    });
+    fs.insert_file(
+        "/the-root/.editorconfig",
+        "[*]\nindent_size = 4\n\n[*.json]\nindent_size = 2\n".into(),
+    )
+    .await;
+    cx.executor().run_until_parked();
+
+    let worktree = project.update(cx, |project, _| project.worktrees().next().unwrap());
+    cx.update(|cx| {
+        let tree = worktree.read(cx);
+        let settings_for = |path: &str, language: Arc<Language>| {
+            let file =
+                File::for_entry(tree.entry_for_path(path).unwrap().clone(), worktree.clone());
+            language_settings(Some(&language), Some(&(file as _)), cx).clone()
+        };
+
+        // The .editorconfig file overrides the project settings of its directory,
+        // which override the user's settings.
+        let settings_a = settings_for("a.rs", rust_lang());
+        assert_eq!(settings_a.tab_size.get(), 4);
+        assert!(settings_a.hard_tabs);
+        assert_eq!(settings_a.preferred_line_length, 100);
+
+        // Its language sections override the user's language settings too.
+        let settings_b = settings_for("b.json", json_lang());
+        assert_eq!(settings_b.tab_size.get(), 2);
+        assert!(!settings_b.ensure_final_newline_on_save);
+
+        // Settings of a nested directory take precedence over the ones of its parents.
+        let settings_c = settings_for("sub/c.rs", rust_lang());
+        assert_eq!(settings_c.tab_size.get(), 6);
+    });
+
+    // Removing the .editorconfig file leaves the project settings.
+    fs.remove_file("/the-root/.editorconfig".as_ref(), Default::default())
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    cx.update(|cx| {
+        let tree = worktree.read(cx);
+        let file = File::for_entry(
+            tree.entry_for_path("b.json").unwrap().clone(),
+            worktree.clone(),
+        );
+        let settings_b = language_settings(Some(&json_lang()), Some(&(file as _)), cx);
+        assert_eq!(settings_b.tab_size.get(), 8);
+    });
+}
+
+#[gpui::test]
+async fn test_indent_resolution_precedence(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+    let set_user_settings = |settings: &str, cx: &mut gpui::TestAppContext| {
+        cx.update(|cx| {
+            SettingsStore::update_global(cx, |store, cx| {
+                store.set_user_settings(settings, cx).unwrap();
+            });
+        });
+        cx.executor().run_until_parked();
+    };
+    set_user_settings(
+        r#"{ "tab_size": 3, "languages": { "Rust": { "tab_size": 5 } } }"#,
+        cx,
+    );
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/the-root",
+        json!({
+            ".zed": {
+                "settings.json": r#"{ "tab_size": 8 }"#,
+            },
+            ".editorconfig": "[*.rs]\nindent_style = tab\n",
+            "a.rs": "fn a() {\n  if b {\n    c();\n  }\n}\n",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs.clone(), ["/the-root".as_ref()], cx).await;
+    project.update(cx, |project, _| project.languages().add(rust_lang()));
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/the-root/a.rs", cx)
+        })
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    let resolution = |cx: &mut gpui::TestAppContext| {
+        buffer.read_with(cx, |buffer, cx| buffer.indent_resolution(cx))
+    };
+    let settings = IndentSource::Settings;
+
+    // The indentation picked for the buffer wins over every other source.
+    buffer.update(cx, |buffer, cx| {
+        buffer.set_indent_override(Some(IndentSize::spaces(7)), cx)
+    });
+    assert_eq!(
+        resolution(cx),
+        IndentResolution {
+            indent: IndentSize::spaces(7),
+            source: IndentSource::Override,
+        }
+    );
+
+    // An .editorconfig file that sets the indentation turns detection off.
+    buffer.update(cx, |buffer, cx| buffer.set_indent_override(None, cx));
+    assert_eq!(
+        resolution(cx),
+        IndentResolution {
+            indent: IndentSize::tab(),
+            source: settings(IndentSettingsSource::Editorconfig),
+        }
+    );
+
+    // Without it, the indentation is detected from the contents.
+    fs.remove_file("/the-root/.editorconfig".as_ref(), Default::default())
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    assert_eq!(
+        resolution(cx),
+        IndentResolution {
+            indent: IndentSize::spaces(2),
+            source: IndentSource::Detected,
+        }
+    );
+
+    // Without detection, the project settings win over the user's settings.
+    set_user_settings(
+        r#"{ "tab_size": 3, "detect_indentation": false, "languages": { "Rust": { "tab_size": 5 } } }"#,
+        cx,
+    );
+    assert_eq!(
+        resolution(cx),
+        IndentResolution {
+            indent: IndentSize::spaces(8),
+            source: settings(IndentSettingsSource::Project),
+        }
+    );
+
+    fs.remove_file("/the-root/.zed/settings.json".as_ref(), Default::default())
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    assert_eq!(
+        resolution(cx),
+        IndentResolution {
+            indent: IndentSize::spaces(5),
+            source: settings(IndentSettingsSource::UserLanguage),
+        }
+    );
+
+    set_user_settings(r#"{ "tab_size": 3, "detect_indentation": false }"#, cx);
+    assert_eq!(
+        resolution(cx),
+        IndentResolution {
+            indent: IndentSize::spaces(3),
+            source: settings(IndentSettingsSource::User),
+        }
+    );
+
+    set_user_settings(r#"{ "detect_indentation": false }"#, cx);
+    assert_eq!(
+        resolution(cx),
+        IndentResolution {
+            indent: IndentSize::spaces(4),
+            source: settings(IndentSettingsSource::Default),
+        }
+    );
+}
+
 #[gpui::test]
 async fn test_managing_language_servers(cx: &mut gpui::TestAppContext) {
     init_test(cx);
@@ -952,6 +1168,150 @@ async fn test_single_file_worktrees_diagnostics(cx: &mut gpui::TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_worktree_root_canonicalization(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/private/tmp",
+        json!({
+            "proj": {
+                "src": {
+                    "main.rs": "fn main() {}",
+                },
+            },
+        }),
+    )
+    .await;
+    // Mirror the macOS `/tmp` -> `/private/tmp` alias.
+    fs.insert_symlink("/tmp", "/private/tmp".into()).await;
+
+    let project = Project::test(fs, [], cx).await;
+    let (worktree, relative_path) = project
+        .update(cx, |project, cx| {
+            project.find_or_create_local_worktree("/tmp/proj", true, cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(relative_path, PathBuf::new());
+    worktree.read_with(cx, |worktree, _| {
+        let worktree = worktree.as_local().unwrap();
+        assert_eq!(worktree.abs_path().as_ref(), Path::new("/tmp/proj"));
+        assert_eq!(worktree.display_path().as_ref(), Path::new("/tmp/proj"));
+        assert_eq!(
+            worktree.canonical_abs_path().as_ref(),
+            Path::new("/private/tmp/proj")
+        );
+    });
+
+    let (same_worktree, relative_path) = project
+        .update(cx, |project, cx| {
+            project.find_or_create_local_worktree("/private/tmp/proj", true, cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(same_worktree, worktree);
+    assert_eq!(relative_path, PathBuf::new());
+
+    let (same_worktree, relative_path) = project
+        .update(cx, |project, cx| {
+            project.find_or_create_local_worktree("/tmp/proj/src/../src/main.rs", true, cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(same_worktree, worktree);
+    assert_eq!(relative_path, Path::new("src/main.rs"));
+
+    let (same_worktree, relative_path) = project
+        .update(cx, |project, cx| {
+            project.find_or_create_local_worktree("/private/tmp/proj/src/main.rs", true, cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(same_worktree, worktree);
+    assert_eq!(relative_path, Path::new("src/main.rs"));
+
+    project.read_with(cx, |project, _| assert_eq!(project.worktrees().count(), 1));
+}
+
+#[gpui::test]
+async fn test_worktree_root_in_home_dir(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let home = util::paths::HOME.as_path();
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(home.join("proj"), json!({ "main.rs": "fn main() {}" }))
+        .await;
+
+    let project = Project::test(fs, [], cx).await;
+    let (worktree, relative_path) = project
+        .update(cx, |project, cx| {
+            project.find_or_create_local_worktree("~/proj", true, cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(relative_path, PathBuf::new());
+    worktree.read_with(cx, |worktree, _| {
+        let worktree = worktree.as_local().unwrap();
+        assert_eq!(worktree.abs_path().as_ref(), home.join("proj"));
+        assert_eq!(worktree.display_path().as_ref(), Path::new("~/proj"));
+    });
+
+    let (same_worktree, relative_path) = project
+        .update(cx, |project, cx| {
+            project.find_or_create_local_worktree("~/proj/main.rs", true, cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(same_worktree, worktree);
+    assert_eq!(relative_path, Path::new("main.rs"));
+}
+
+#[gpui::test]
+async fn test_read_only_worktree(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({ "a.rs": "a", "b.rs": "b" }))
+        .await;
+    fs.set_read_only("/dir/b.rs", true);
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+    let worktree_id = project.read_with(cx, |project, cx| {
+        project.worktrees().next().unwrap().read(cx).id()
+    });
+    let a = project
+        .update(cx, |project, cx| project.open_local_buffer("/dir/a.rs", cx))
+        .await
+        .unwrap();
+    let b = project
+        .update(cx, |project, cx| project.open_local_buffer("/dir/b.rs", cx))
+        .await
+        .unwrap();
+    assert_eq!(
+        a.read_with(cx, |a, _| a.capability()),
+        Capability::ReadWrite
+    );
+    assert_eq!(b.read_with(cx, |b, _| b.capability()), Capability::ReadOnly);
+
+    project.update(cx, |project, cx| {
+        project.set_worktree_read_only(worktree_id, true, cx)
+    });
+    assert_eq!(a.read_with(cx, |a, _| a.capability()), Capability::ReadOnly);
+    assert_eq!(b.read_with(cx, |b, _| b.capability()), Capability::ReadOnly);
+
+    // Making the worktree writable again leaves the file that can't be written to read-only.
+    project.update(cx, |project, cx| {
+        project.set_worktree_read_only(worktree_id, false, cx)
+    });
+    assert_eq!(
+        a.read_with(cx, |a, _| a.capability()),
+        Capability::ReadWrite
+    );
+    assert_eq!(b.read_with(cx, |b, _| b.capability()), Capability::ReadOnly);
+}
+
 #[gpui::test]
 async fn test_omitted_diagnostics(cx: &mut gpui::TestAppContext) {
     init_test(cx);
@@ -1419,6 +1779,106 @@ async fn test_restarted_server_reporting_invalid_buffer_version(cx: &mut gpui::T
     assert_eq!(notification.version, 0);
 }
 
+#[gpui::test]
+async fn test_language_overrides(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "Justfile": "build:\n    cargo build",
+            "a.rs": "fn a() {}",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    language_registry.add(json_lang());
+
+    let justfile = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/dir/Justfile", cx)
+        })
+        .await
+        .unwrap();
+    let rust_buffer = project
+        .update(cx, |project, cx| project.open_local_buffer("/dir/a.rs", cx))
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    justfile.update(cx, |buffer, _| assert_eq!(buffer.language(), None));
+
+    // Overriding the language of a buffer sets it, and records it for the buffer's file.
+    let events = Arc::new(Mutex::new(Vec::new()));
+    cx.update(|cx| {
+        let events = events.clone();
+        cx.subscribe(&project, move |_, event, _| {
+            if let Event::LanguageOverridesChanged = event {
+                events.lock().push(());
+            }
+        })
+        .detach();
+    });
+    let rust = language_registry.language_for_name("Rust").await.unwrap();
+    let json = language_registry.language_for_name("JSON").await.unwrap();
+    project.update(cx, |project, cx| {
+        project.set_language_override(&justfile, Some(rust.clone()), cx);
+        project.set_language_override(&rust_buffer, Some(json.clone()), cx);
+    });
+    cx.executor().run_until_parked();
+    justfile.update(cx, |buffer, _| {
+        assert_eq!(buffer.language().map(|l| l.name()), Some("Rust".into()));
+    });
+    rust_buffer.update(cx, |buffer, _| {
+        assert_eq!(buffer.language().map(|l| l.name()), Some("JSON".into()));
+    });
+    assert_eq!(events.lock().len(), 2);
+
+    // Detecting the language again, e.g. after the file gets renamed, keeps the override.
+    project.update(cx, |project, cx| {
+        project.detect_language_for_buffer(&rust_buffer, cx)
+    });
+    cx.executor().run_until_parked();
+    rust_buffer.update(cx, |buffer, _| {
+        assert_eq!(buffer.language().map(|l| l.name()), Some("JSON".into()));
+    });
+
+    // Clearing the override goes back to the detected language.
+    project.update(cx, |project, cx| {
+        project.set_language_override(&rust_buffer, None, cx);
+    });
+    cx.executor().run_until_parked();
+    rust_buffer.update(cx, |buffer, _| {
+        assert_eq!(buffer.language().map(|l| l.name()), Some("Rust".into()));
+    });
+    assert_eq!(events.lock().len(), 3);
+    let language_overrides =
+        project.read_with(cx, |project, _| project.language_overrides().clone());
+    assert_eq!(language_overrides.len(), 1);
+
+    // Restored overrides apply to the buffers of their files in another project.
+    let other_project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    other_project.read_with(cx, |project, _| project.languages().add(rust_lang()));
+    let other_justfile = other_project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/dir/Justfile", cx)
+        })
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    other_justfile.update(cx, |buffer, _| assert_eq!(buffer.language(), None));
+    other_project.update(cx, |project, cx| {
+        project.restore_language_overrides(language_overrides, cx);
+    });
+    cx.executor().run_until_parked();
+    other_justfile.update(cx, |buffer, _| {
+        assert_eq!(buffer.language().map(|l| l.name()), Some("Rust".into()));
+    });
+}
+
 #[gpui::test]
 async fn test_toggling_enable_language_server(cx: &mut gpui::TestAppContext) {
     init_test(cx);
@@ -2733,7 +3193,292 @@ async fn test_save_file(cx: &mut gpui::TestAppContext) {
     assert_eq!(new_text, buffer.update(cx, |buffer, _| buffer.text()));
 }
 
-#[gpui::test(iterations = 30)]
+#[gpui::test(iterations = 10)]
+async fn test_edits_during_save(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({ "file1": "abc" })).await;
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    let buffer = project
+        .update(cx, |p, cx| p.open_local_buffer("/dir/file1", cx))
+        .await
+        .unwrap();
+
+    buffer.update(cx, |buffer, cx| buffer.edit([(3..3, "d")], None, cx));
+    let saved_version = buffer.read_with(cx, |buffer, _| buffer.version());
+    let save = project.update(cx, |project, cx| {
+        project.save_buffer_with_progress(buffer.clone(), SaveProgress::default(), false, cx)
+    });
+
+    // An edit made while the file is written isn't part of the save, so the buffer stays
+    // dirty once the save is done.
+    buffer.update(cx, |buffer, cx| buffer.edit([(4..4, "e")], None, cx));
+    let SaveOutcome::Saved { version, .. } = save.await else {
+        panic!("the save didn't succeed");
+    };
+    assert_eq!(version, saved_version);
+    assert_eq!(fs.load("/dir/file1".as_ref()).await.unwrap(), "abcd");
+    cx.executor().run_until_parked();
+    buffer.read_with(cx, |buffer, _| {
+        assert!(buffer.is_dirty());
+        assert!(!buffer.has_conflict());
+        assert_eq!(buffer.saved_version(), &saved_version);
+    });
+
+    project
+        .update(cx, |project, cx| project.save_buffer(buffer.clone(), cx))
+        .await
+        .unwrap();
+    assert_eq!(fs.load("/dir/file1".as_ref()).await.unwrap(), "abcde");
+    buffer.read_with(cx, |buffer, _| assert!(!buffer.is_dirty()));
+}
+
+#[gpui::test]
+async fn test_save_outcomes(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({ "file1": "abc" })).await;
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    let buffer = project
+        .update(cx, |p, cx| p.open_local_buffer("/dir/file1", cx))
+        .await
+        .unwrap();
+    buffer.update(cx, |buffer, cx| buffer.edit([(3..3, "d")], None, cx));
+
+    // Cancelled saves leave the file alone.
+    let progress = SaveProgress::default();
+    let save = project.update(cx, |project, cx| {
+        project.save_buffer_with_progress(buffer.clone(), progress.clone(), false, cx)
+    });
+    progress.cancel();
+    assert!(matches!(save.await, SaveOutcome::Cancelled));
+    assert_eq!(fs.load("/dir/file1".as_ref()).await.unwrap(), "abc");
+    buffer.read_with(cx, |buffer, _| assert!(buffer.is_dirty()));
+
+    let progress = SaveProgress::default();
+    let outcome = project
+        .update(cx, |project, cx| {
+            project.save_buffer_with_progress(buffer.clone(), progress.clone(), false, cx)
+        })
+        .await;
+    assert!(matches!(outcome, SaveOutcome::Saved { .. }));
+    assert_eq!(progress.written(), (4, 4));
+    assert_eq!(fs.load("/dir/file1".as_ref()).await.unwrap(), "abcd");
+
+    // Buffers with edits of their own don't get saved over changes made on disk.
+    buffer.update(cx, |buffer, cx| buffer.edit([(4..4, "e")], None, cx));
+    fs.save("/dir/file1".as_ref(), &"xyz".into(), LineEnding::Unix)
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    buffer.read_with(cx, |buffer, _| assert!(buffer.has_conflict()));
+    let outcome = project
+        .update(cx, |project, cx| {
+            project.save_buffer_with_progress(buffer.clone(), SaveProgress::default(), false, cx)
+        })
+        .await;
+    assert!(matches!(outcome, SaveOutcome::Conflict));
+    assert_eq!(fs.load("/dir/file1".as_ref()).await.unwrap(), "xyz");
+
+    // Unless the user chose to overwrite them.
+    let outcome = project
+        .update(cx, |project, cx| {
+            project.save_buffer_with_progress(buffer.clone(), SaveProgress::default(), true, cx)
+        })
+        .await;
+    assert!(matches!(outcome, SaveOutcome::Saved { .. }));
+    assert_eq!(fs.load("/dir/file1".as_ref()).await.unwrap(), "abcde");
+}
+
+#[gpui::test]
+async fn test_diff_against_saved_file(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+    cx.update(|cx| {
+        SettingsStore::update_global(cx, |settings, cx| {
+            settings.update_user_settings::<ProjectSettings>(cx, |settings| {
+                settings.git.git_gutter = Some(GitGutterSetting::SavedFile);
+            });
+        });
+    });
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({ "file1": "one\ntwo\nthree\n" }))
+        .await;
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    let buffer = project
+        .update(cx, |p, cx| p.open_local_buffer("/dir/file1", cx))
+        .await
+        .unwrap();
+    let hunk_rows = |buffer: &Model<Buffer>, cx: &mut gpui::TestAppContext| {
+        buffer.update(cx, |buffer, _| {
+            buffer
+                .snapshot()
+                .git_diff_hunks_in_row_range(0..u32::MAX)
+                .map(|hunk| hunk.associated_range)
+                .collect::<Vec<_>>()
+        })
+    };
+    cx.executor().run_until_parked();
+    assert_eq!(hunk_rows(&buffer, cx), Vec::<Range<u32>>::new());
+
+    buffer.update(cx, |buffer, cx| {
+        buffer.edit([(4..7, "TWO"), (14..14, "four\n")], None, cx)
+    });
+    cx.executor().run_until_parked();
+    assert_eq!(hunk_rows(&buffer, cx), vec![1..2, 3..4]);
+
+    // Saving the buffer clears its hunks.
+    project
+        .update(cx, |project, cx| project.save_buffer(buffer.clone(), cx))
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    assert_eq!(hunk_rows(&buffer, cx), Vec::<Range<u32>>::new());
+
+    // So does reloading it after the file changed on disk.
+    buffer.update(cx, |buffer, cx| buffer.edit([(0..0, "zero\n")], None, cx));
+    cx.executor().run_until_parked();
+    assert_eq!(hunk_rows(&buffer, cx), vec![0..1]);
+    fs.save(
+        "/dir/file1".as_ref(),
+        &"changed on disk\n".into(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+    project
+        .update(cx, |project, cx| {
+            project.reload_buffers(HashSet::from_iter([buffer.clone()]), false, cx)
+        })
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    assert_eq!(
+        buffer.update(cx, |buffer, _| buffer.text()),
+        "changed on disk\n"
+    );
+    assert_eq!(hunk_rows(&buffer, cx), Vec::<Range<u32>>::new());
+}
+
+#[gpui::test]
+async fn test_write_protected_files_open_read_only(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({ "protected": "one", "writable": "two" }))
+        .await;
+    fs.set_read_only("/dir/protected", true);
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    let protected = project
+        .update(cx, |p, cx| p.open_local_buffer("/dir/protected", cx))
+        .await
+        .unwrap();
+    let writable = project
+        .update(cx, |p, cx| p.open_local_buffer("/dir/writable", cx))
+        .await
+        .unwrap();
+    protected.update(cx, |buffer, _| {
+        assert_eq!(buffer.capability(), Capability::ReadOnly)
+    });
+    writable.update(cx, |buffer, _| {
+        assert_eq!(buffer.capability(), Capability::ReadWrite)
+    });
+}
+
+#[gpui::test]
+async fn test_save_file_in_original_encoding(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({})).await;
+    fs.insert_file("/dir/latin1.txt", b"caf\xe9\n".to_vec())
+        .await;
+    fs.insert_file("/dir/utf16.txt", b"\xff\xfeh\0i\0".to_vec())
+        .await;
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    let latin1_buffer = project
+        .update(cx, |p, cx| p.open_local_buffer("/dir/latin1.txt", cx))
+        .await
+        .unwrap();
+    let utf16_buffer = project
+        .update(cx, |p, cx| p.open_local_buffer("/dir/utf16.txt", cx))
+        .await
+        .unwrap();
+    latin1_buffer.update(cx, |buffer, cx| {
+        assert_eq!(buffer.text(), "café\n");
+        assert_eq!(buffer.encoding(), Encoding::Windows1252);
+        assert!(!buffer.is_dirty());
+        buffer.edit([(buffer.len()..buffer.len(), "€5")], None, cx);
+    });
+    utf16_buffer.update(cx, |buffer, cx| {
+        assert_eq!(buffer.text(), "hi");
+        assert_eq!(buffer.encoding(), Encoding::Utf16Le);
+        buffer.edit([(buffer.len()..buffer.len(), "!")], None, cx);
+    });
+
+    for buffer in [&latin1_buffer, &utf16_buffer] {
+        project
+            .update(cx, |project, cx| project.save_buffer(buffer.clone(), cx))
+            .await
+            .unwrap();
+    }
+    assert_eq!(
+        fs.load_bytes(Path::new("/dir/latin1.txt")).await.unwrap(),
+        b"caf\xe9\n\x805"
+    );
+    assert_eq!(
+        fs.load_bytes(Path::new("/dir/utf16.txt")).await.unwrap(),
+        b"\xff\xfeh\0i\0!\0"
+    );
+
+    // Characters that the file's encoding can't represent fail the save.
+    latin1_buffer.update(cx, |buffer, cx| {
+        buffer.edit([(0..0, "ą")], None, cx);
+    });
+    project
+        .update(cx, |project, cx| {
+            project.save_buffer(latin1_buffer.clone(), cx)
+        })
+        .await
+        .unwrap_err();
+    latin1_buffer.update(cx, |buffer, cx| {
+        assert_eq!(buffer.unencodable_char(), Some('ą'));
+        buffer.set_encoding(Encoding::Utf8, cx);
+        assert_eq!(buffer.unencodable_char(), None);
+    });
+    project
+        .update(cx, |project, cx| {
+            project.save_buffer(latin1_buffer.clone(), cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        fs.load(Path::new("/dir/latin1.txt")).await.unwrap(),
+        "ącafé\n€5"
+    );
+
+    // Reopening a file with another encoding decodes it again.
+    latin1_buffer
+        .update(cx, |buffer, cx| {
+            buffer.reopen_with_encoding(Encoding::Windows1252, cx)
+        })
+        .await
+        .unwrap();
+    latin1_buffer.update(cx, |buffer, _| {
+        assert_eq!(buffer.text(), "Ä…cafÃ©\nâ‚¬5");
+        assert_eq!(buffer.encoding(), Encoding::Windows1252);
+        assert!(!buffer.is_dirty());
+    });
+}
+
+#[gpui::test(iterations = 30)]
 async fn test_file_changes_multiple_times_on_disk(cx: &mut gpui::TestAppContext) {
     init_test(cx);
 
@@ -3327,6 +4072,63 @@ async fn test_buffer_is_dirty(cx: &mut gpui::TestAppContext) {
     cx.update(|cx| assert!(buffer3.read(cx).is_dirty()));
 }
 
+#[gpui::test]
+async fn test_buffer_is_dirty_after_undo_and_redo(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({ "file": "abc" })).await;
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    let buffer = project
+        .update(cx, |p, cx| p.open_local_buffer("/dir/file", cx))
+        .await
+        .unwrap();
+    let mtime = fs
+        .metadata("/dir/file".as_ref())
+        .await
+        .unwrap()
+        .unwrap()
+        .mtime;
+
+    // Undoing back to the saved state makes the buffer clean again, and
+    // redoing past it makes it dirty.
+    buffer.update(cx, |buffer, cx| {
+        buffer.edit([(3..3, "d")], None, cx);
+        assert!(buffer.is_dirty());
+        buffer.undo(cx);
+        assert_eq!(buffer.text(), "abc");
+        assert!(!buffer.is_dirty());
+        buffer.redo(cx);
+        assert!(buffer.is_dirty());
+        buffer.undo(cx);
+        assert!(!buffer.is_dirty());
+    });
+
+    // Saving a buffer whose edits were undone doesn't write the file.
+    project
+        .update(cx, |project, cx| project.save_buffer(buffer.clone(), cx))
+        .await
+        .unwrap();
+    assert_eq!(
+        fs.metadata("/dir/file".as_ref())
+            .await
+            .unwrap()
+            .unwrap()
+            .mtime,
+        mtime
+    );
+    buffer.update(cx, |buffer, cx| {
+        assert_eq!(buffer.saved_version(), &buffer.version());
+        assert!(!buffer.is_dirty());
+
+        // Redoing the edit after the save still makes it dirty.
+        buffer.redo(cx);
+        assert_eq!(buffer.text(), "abcd");
+        assert!(buffer.is_dirty());
+    });
+}
+
 #[gpui::test]
 async fn test_buffer_file_changes_on_disk(cx: &mut gpui::TestAppContext) {
     init_test(cx);
@@ -3902,6 +4704,78 @@ async fn test_search(cx: &mut gpui::TestAppContext) {
     );
 }
 
+#[gpui::test]
+async fn test_search_non_ascii_case_insensitively(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "one.txt": "ÄPFEL äpfel Äpfelchen",
+            "two.txt": "apfel",
+        }),
+    )
+    .await;
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    assert_eq!(
+        search(
+            &project,
+            SearchQuery::text("äpfel", false, false, false, Vec::new(), Vec::new()).unwrap(),
+            cx
+        )
+        .await
+        .unwrap(),
+        HashMap::from_iter([("dir/one.txt".to_string(), vec![0..6, 7..13, 14..20])])
+    );
+    assert_eq!(
+        search(
+            &project,
+            SearchQuery::text("äpfel", true, false, false, Vec::new(), Vec::new()).unwrap(),
+            cx
+        )
+        .await
+        .unwrap(),
+        HashMap::from_iter([("dir/one.txt".to_string(), vec![0..6, 7..13])])
+    );
+    assert_eq!(
+        search(
+            &project,
+            SearchQuery::text("äpfel", false, true, false, Vec::new(), Vec::new()).unwrap(),
+            cx
+        )
+        .await
+        .unwrap(),
+        HashMap::from_iter([("dir/one.txt".to_string(), vec![7..13])])
+    );
+}
+
+#[gpui::test]
+async fn test_search_skips_binary_files(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "text.txt": "needle",
+            "binary.bin": "needle\0\x01\x02",
+        }),
+    )
+    .await;
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    assert_eq!(
+        search(
+            &project,
+            SearchQuery::text("needle", false, false, false, Vec::new(), Vec::new()).unwrap(),
+            cx
+        )
+        .await
+        .unwrap(),
+        HashMap::from_iter([("dir/text.txt".to_string(), vec![0..6])])
+    );
+}
+
 #[gpui::test]
 async fn test_search_with_inclusions(cx: &mut gpui::TestAppContext) {
     init_test(cx);
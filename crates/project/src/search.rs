@@ -2,7 +2,7 @@ use aho_corasick::{AhoCorasick, AhoCorasickBuilder};
 use anyhow::{Context, Result};
 use client::proto;
 use itertools::Itertools;
-use language::{char_kind, BufferSnapshot};
+use language::{is_word_boundary, BufferSnapshot, Rope};
 use regex::{Captures, Regex, RegexBuilder};
 use smol::future::yield_now;
 use std::{
@@ -16,6 +16,10 @@ use util::paths::PathMatcher;
 
 static TEXT_REPLACEMENT_SPECIAL_CHARACTERS_REGEX: OnceLock<Regex> = OnceLock::new();
 
+/// How many bytes from the start of a file are looked at for a NUL byte, to tell binary files
+/// apart from text files the same way git does.
+const BINARY_DETECTION_LEN: usize = 8192;
+
 #[derive(Clone, Debug)]
 pub struct SearchInputs {
     query: Arc<str>,
@@ -38,6 +42,9 @@ impl SearchInputs {
 pub enum SearchQuery {
     Text {
         search: Arc<AhoCorasick>,
+        /// Used instead of `search` for case insensitive queries that aren't ASCII,
+        /// because [`AhoCorasick`] only folds the case of ASCII characters.
+        folded_search: Option<Regex>,
         replacement: Option<String>,
         whole_word: bool,
         case_sensitive: bool,
@@ -69,6 +76,15 @@ impl SearchQuery {
         let search = AhoCorasickBuilder::new()
             .ascii_case_insensitive(!case_sensitive)
             .build(&[&query])?;
+        let folded_search = if case_sensitive || query.is_ascii() {
+            None
+        } else {
+            Some(
+                RegexBuilder::new(&regex::escape(&query))
+                    .case_insensitive(true)
+                    .build()?,
+            )
+        };
         let inner = SearchInputs {
             query: query.into(),
             files_to_exclude,
@@ -76,6 +92,7 @@ impl SearchQuery {
         };
         Ok(Self::Text {
             search: Arc::new(search),
+            folded_search,
             replacement: None,
             whole_word,
             case_sensitive,
@@ -180,12 +197,23 @@ impl SearchQuery {
         }
     }
 
+    /// Returns whether the given stream contains a match. Streams that look binary never do,
+    /// since their matches would be meaningless and can't be shown.
     pub fn detect<T: Read>(&self, stream: T) -> Result<bool> {
         if self.as_str().is_empty() {
             return Ok(false);
         }
 
+        let mut stream = BufReader::with_capacity(BINARY_DETECTION_LEN, stream);
+        if stream.fill_buf()?.contains(&0) {
+            return Ok(false);
+        }
+
         match self {
+            Self::Text {
+                folded_search: Some(regex),
+                ..
+            } => Self::detect_regex(regex, self.as_str().contains('\n'), stream),
             Self::Text { search, .. } => {
                 let mat = search.stream_find_iter(stream).next();
                 match mat {
@@ -196,25 +224,26 @@ impl SearchQuery {
             }
             Self::Regex {
                 regex, multiline, ..
-            } => {
-                let mut reader = BufReader::new(stream);
-                if *multiline {
-                    let mut text = String::new();
-                    if let Err(err) = reader.read_to_string(&mut text) {
-                        Err(err.into())
-                    } else {
-                        Ok(regex.find(&text).is_some())
-                    }
-                } else {
-                    for line in reader.lines() {
-                        let line = line?;
-                        if regex.find(&line).is_some() {
-                            return Ok(true);
-                        }
-                    }
-                    Ok(false)
+            } => Self::detect_regex(regex, *multiline, stream),
+        }
+    }
+
+    fn detect_regex<T: BufRead>(regex: &Regex, multiline: bool, mut reader: T) -> Result<bool> {
+        if multiline {
+            let mut text = String::new();
+            if let Err(err) = reader.read_to_string(&mut text) {
+                Err(err.into())
+            } else {
+                Ok(regex.find(&text).is_some())
+            }
+        } else {
+            for line in reader.lines() {
+                let line = line?;
+                if regex.find(&line).is_some() {
+                    return Ok(true);
                 }
             }
+            Ok(false)
         }
     }
     /// Returns the replacement text for this `SearchQuery`.
@@ -269,74 +298,95 @@ impl SearchQuery {
             buffer.as_rope().clone()
         };
 
-        let mut matches = Vec::new();
         match self {
             Self::Text {
-                search, whole_word, ..
+                search,
+                folded_search,
+                whole_word,
+                ..
             } => {
-                for (ix, mat) in search
-                    .stream_find_iter(rope.bytes_in_range(0..rope.len()))
-                    .enumerate()
-                {
-                    if (ix + 1) % YIELD_INTERVAL == 0 {
-                        yield_now().await;
-                    }
-
-                    let mat = mat.unwrap();
-                    if *whole_word {
-                        let scope = buffer.language_scope_at(range_offset + mat.start());
-                        let kind = |c| char_kind(&scope, c);
-
-                        let prev_kind = rope.reversed_chars_at(mat.start()).next().map(kind);
-                        let start_kind = kind(rope.chars_at(mat.start()).next().unwrap());
-                        let end_kind = kind(rope.reversed_chars_at(mat.end()).next().unwrap());
-                        let next_kind = rope.chars_at(mat.end()).next().map(kind);
-                        if Some(start_kind) == prev_kind || Some(end_kind) == next_kind {
-                            continue;
+                let matches = if let Some(regex) = folded_search {
+                    Self::regex_matches(regex, self.as_str().contains('\n'), &rope).await
+                } else {
+                    let mut matches = Vec::new();
+                    for (ix, mat) in search
+                        .stream_find_iter(rope.bytes_in_range(0..rope.len()))
+                        .enumerate()
+                    {
+                        if (ix + 1) % YIELD_INTERVAL == 0 {
+                            yield_now().await;
                         }
+
+                        let mat = mat.unwrap();
+                        matches.push(mat.start()..mat.end());
                     }
-                    matches.push(mat.start()..mat.end())
+                    matches
+                };
+
+                if !*whole_word {
+                    return matches;
                 }
+                matches
+                    .into_iter()
+                    .filter(|range| {
+                        let scope = buffer.language_scope_at(range_offset + range.start);
+                        let starts_word = rope
+                            .reversed_chars_at(range.start)
+                            .next()
+                            .zip(rope.chars_at(range.start).next())
+                            .map_or(true, |(prev, first)| is_word_boundary(&scope, prev, first));
+                        let ends_word = rope
+                            .reversed_chars_at(range.end)
+                            .next()
+                            .zip(rope.chars_at(range.end).next())
+                            .map_or(true, |(last, next)| is_word_boundary(&scope, last, next));
+                        starts_word && ends_word
+                    })
+                    .collect()
             }
 
             Self::Regex {
                 regex, multiline, ..
-            } => {
-                if *multiline {
-                    let text = rope.to_string();
-                    for (ix, mat) in regex.find_iter(&text).enumerate() {
-                        if (ix + 1) % YIELD_INTERVAL == 0 {
-                            yield_now().await;
-                        }
+            } => Self::regex_matches(regex, *multiline, &rope).await,
+        }
+    }
 
-                        matches.push(mat.start()..mat.end());
-                    }
-                } else {
-                    let mut line = String::new();
-                    let mut line_offset = 0;
-                    for (chunk_ix, chunk) in rope.chunks().chain(["\n"]).enumerate() {
-                        if (chunk_ix + 1) % YIELD_INTERVAL == 0 {
-                            yield_now().await;
-                        }
+    async fn regex_matches(regex: &Regex, multiline: bool, rope: &Rope) -> Vec<Range<usize>> {
+        const YIELD_INTERVAL: usize = 20000;
 
-                        for (newline_ix, text) in chunk.split('\n').enumerate() {
-                            if newline_ix > 0 {
-                                for mat in regex.find_iter(&line) {
-                                    let start = line_offset + mat.start();
-                                    let end = line_offset + mat.end();
-                                    matches.push(start..end);
-                                }
+        let mut matches = Vec::new();
+        if multiline {
+            let text = rope.to_string();
+            for (ix, mat) in regex.find_iter(&text).enumerate() {
+                if (ix + 1) % YIELD_INTERVAL == 0 {
+                    yield_now().await;
+                }
 
-                                line_offset += line.len() + 1;
-                                line.clear();
-                            }
-                            line.push_str(text);
+                matches.push(mat.start()..mat.end());
+            }
+        } else {
+            let mut line = String::new();
+            let mut line_offset = 0;
+            for (chunk_ix, chunk) in rope.chunks().chain(["\n"]).enumerate() {
+                if (chunk_ix + 1) % YIELD_INTERVAL == 0 {
+                    yield_now().await;
+                }
+
+                for (newline_ix, text) in chunk.split('\n').enumerate() {
+                    if newline_ix > 0 {
+                        for mat in regex.find_iter(&line) {
+                            let start = line_offset + mat.start();
+                            let end = line_offset + mat.end();
+                            matches.push(start..end);
                         }
+
+                        line_offset += line.len() + 1;
+                        line.clear();
                     }
+                    line.push_str(text);
                 }
             }
         }
-
         matches
     }
 
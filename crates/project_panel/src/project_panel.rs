@@ -36,7 +36,7 @@ use util::{maybe, NumericPrefixWithSuffix, ResultExt, TryFutureExt};
 use workspace::{
     dock::{DockPosition, Panel, PanelEvent},
     notifications::{DetachAndPromptErr, NotifyTaskExt},
-    DraggedSelection, OpenInTerminal, SelectedEntry, Workspace,
+    DraggedSelection, OpenInTerminal, SelectedEntry, Workspace, WorkspaceId,
 };
 use worktree::CreatedEntry;
 
@@ -63,6 +63,9 @@ pub struct ProjectPanel {
     workspace: WeakView<Workspace>,
     width: Option<Pixels>,
     pending_serialization: Task<Option<()>>,
+    /// The expanded directories restored with the workspace, whose entries weren't scanned yet.
+    pending_expanded_dirs: HashMap<PathBuf, Vec<Arc<Path>>>,
+    pending_expanded_dirs_serialization: Task<Option<()>>,
 }
 
 #[derive(Clone, Debug)]
@@ -127,6 +130,7 @@ actions!(
         CopyRelativePath,
         Duplicate,
         RevealInFinder,
+        OpenInDefaultApp,
         Cut,
         Paste,
         Rename,
@@ -175,6 +179,17 @@ struct SerializedProjectPanel {
     width: Option<Pixels>,
 }
 
+/// The expanded directories of a workspace, by the absolute path of their worktree.
+#[derive(Default, Serialize, Deserialize)]
+struct SerializedExpandedDirs(HashMap<PathBuf, Vec<Arc<Path>>>);
+
+fn expanded_dirs_key(workspace_id: WorkspaceId) -> String {
+    format!(
+        "{PROJECT_PANEL_KEY}-expanded-dirs-{}",
+        i64::from(workspace_id)
+    )
+}
+
 struct DraggedProjectEntryView {
     selection: SelectedEntry,
     details: EntryDetails,
@@ -210,6 +225,7 @@ impl ProjectPanel {
                 project::Event::WorktreeUpdatedEntries(_, _)
                 | project::Event::WorktreeAdded
                 | project::Event::WorktreeOrderChanged => {
+                    this.restore_expanded_dirs(cx);
                     this.update_visible_entries(None, cx);
                     cx.notify();
                 }
@@ -272,6 +288,8 @@ impl ProjectPanel {
                 workspace: workspace.weak_handle(),
                 width: None,
                 pending_serialization: Task::ready(None),
+                pending_expanded_dirs: Default::default(),
+                pending_expanded_dirs_serialization: Task::ready(None),
             };
             this.update_visible_entries(None, cx);
 
@@ -369,6 +387,22 @@ impl ProjectPanel {
         workspace: WeakView<Workspace>,
         mut cx: AsyncWindowContext,
     ) -> Result<View<Self>> {
+        let workspace_id = workspace.update(&mut cx, |workspace, _| workspace.database_id())?;
+        let expanded_dirs = cx
+            .background_executor()
+            .spawn(async move {
+                let Some(workspace_id) = workspace_id else {
+                    return Ok(None);
+                };
+                KEY_VALUE_STORE.read_kvp(&expanded_dirs_key(workspace_id))
+            })
+            .await
+            .log_err()
+            .flatten()
+            .map(|dirs| serde_json::from_str::<SerializedExpandedDirs>(&dirs))
+            .transpose()
+            .log_err()
+            .flatten();
         let serialized_panel = cx
             .background_executor()
             .spawn(async move { KEY_VALUE_STORE.read_kvp(PROJECT_PANEL_KEY) })
@@ -389,6 +423,14 @@ impl ProjectPanel {
                     cx.notify();
                 });
             }
+            if let Some(expanded_dirs) = expanded_dirs {
+                panel.update(cx, |panel, cx| {
+                    panel.pending_expanded_dirs = expanded_dirs.0;
+                    panel.restore_expanded_dirs(cx);
+                    panel.update_visible_entries(None, cx);
+                    cx.notify();
+                });
+            }
             panel
         })
     }
@@ -409,6 +451,89 @@ impl ProjectPanel {
         );
     }
 
+    fn serialize_expanded_dirs(&mut self, cx: &mut ViewContext<Self>) {
+        let Some(workspace_id) = self
+            .workspace
+            .upgrade()
+            .and_then(|workspace| workspace.read(cx).database_id())
+        else {
+            return;
+        };
+
+        let mut expanded_dirs = SerializedExpandedDirs(self.pending_expanded_dirs.clone());
+        for worktree in self.project.read(cx).visible_worktrees(cx) {
+            let worktree = worktree.read(cx);
+            let Some(expanded_dir_ids) = self.expanded_dir_ids.get(&worktree.id()) else {
+                continue;
+            };
+            expanded_dirs
+                .0
+                .entry(worktree.abs_path().to_path_buf())
+                .or_default()
+                .extend(
+                    expanded_dir_ids.iter().filter_map(|entry_id| {
+                        Some(worktree.entry_for_id(*entry_id)?.path.clone())
+                    }),
+                );
+        }
+
+        self.pending_expanded_dirs_serialization = cx.background_executor().spawn(
+            async move {
+                KEY_VALUE_STORE
+                    .write_kvp(
+                        expanded_dirs_key(workspace_id),
+                        serde_json::to_string(&expanded_dirs)?,
+                    )
+                    .await?;
+                anyhow::Ok(())
+            }
+            .log_err(),
+        );
+    }
+
+    /// Expands the restored directories whose entries were scanned since, loading those whose
+    /// contents weren't, like the ignored ones.
+    fn restore_expanded_dirs(&mut self, cx: &mut ViewContext<Self>) {
+        if self.pending_expanded_dirs.is_empty() {
+            return;
+        }
+
+        let mut entries_to_expand = Vec::new();
+        for worktree in self.project.read(cx).visible_worktrees(cx) {
+            let worktree = worktree.read(cx);
+            if worktree.root_entry().is_none() {
+                continue;
+            }
+            let abs_path = worktree.abs_path();
+            let Some(paths) = self.pending_expanded_dirs.get_mut(abs_path.as_ref()) else {
+                continue;
+            };
+
+            let expanded_dir_ids = self.expanded_dir_ids.entry(worktree.id()).or_default();
+            paths.retain(|path| {
+                let Some(entry) = worktree.entry_for_path(path) else {
+                    return true;
+                };
+                if entry.is_dir() {
+                    if let Err(ix) = expanded_dir_ids.binary_search(&entry.id) {
+                        expanded_dir_ids.insert(ix, entry.id);
+                    }
+                    entries_to_expand.push((worktree.id(), entry.id));
+                }
+                false
+            });
+            if paths.is_empty() {
+                self.pending_expanded_dirs.remove(abs_path.as_ref());
+            }
+        }
+
+        self.project.update(cx, |project, cx| {
+            for (worktree_id, entry_id) in entries_to_expand {
+                project.expand_entry(worktree_id, entry_id, cx);
+            }
+        });
+    }
+
     fn focus_in(&mut self, cx: &mut ViewContext<Self>) {
         if !self.focus_handle.contains_focused(cx) {
             cx.emit(Event::Focus);
@@ -443,7 +568,7 @@ impl ProjectPanel {
             let is_unfoldable = auto_fold_dirs && self.is_unfoldable(entry, worktree);
             let worktree_id = worktree.id();
             let is_local = project.is_local();
-            let is_read_only = project.is_read_only();
+            let is_read_only = project.is_read_only() || worktree.is_read_only();
             let is_remote = project.is_remote();
 
             let context_menu = ContextMenu::build(cx, |menu, cx| {
@@ -460,6 +585,9 @@ impl ProjectPanel {
                             .action("New Folder", Box::new(NewDirectory))
                             .separator()
                             .action("Reveal in Finder", Box::new(RevealInFinder))
+                            .when(!is_dir, |menu| {
+                                menu.action("Open in Default App", Box::new(OpenInDefaultApp))
+                            })
                             .action("Open in Terminal", Box::new(OpenInTerminal))
                             .when(is_dir, |menu| {
                                 menu.separator()
@@ -592,6 +720,7 @@ impl ProjectPanel {
 
                         expanded_dir_ids.insert(ix, entry_id);
                         self.update_visible_entries(None, cx);
+                        self.serialize_expanded_dirs(cx);
                         cx.notify();
                     }
                 }
@@ -615,6 +744,7 @@ impl ProjectPanel {
                     Ok(ix) => {
                         expanded_dir_ids.remove(ix);
                         self.update_visible_entries(Some((worktree_id, entry_id)), cx);
+                        self.serialize_expanded_dirs(cx);
                         cx.notify();
                         break;
                     }
@@ -638,6 +768,7 @@ impl ProjectPanel {
         self.expanded_dir_ids
             .retain(|_, expanded_entries| expanded_entries.is_empty());
         self.update_visible_entries(None, cx);
+        self.serialize_expanded_dirs(cx);
         cx.notify();
     }
 
@@ -656,6 +787,7 @@ impl ProjectPanel {
                     }
                 });
                 self.update_visible_entries(Some((worktree_id, entry_id)), cx);
+                self.serialize_expanded_dirs(cx);
                 cx.focus(&self.focus_handle);
                 cx.notify();
             }
@@ -1328,8 +1460,15 @@ impl ProjectPanel {
     }
 
     fn copy_relative_path(&mut self, _: &CopyRelativePath, cx: &mut ViewContext<Self>) {
-        if let Some((_, entry)) = self.selected_entry(cx) {
-            cx.write_to_clipboard(ClipboardItem::new(entry.path.to_string_lossy().to_string()));
+        if let Some((worktree, entry)) = self.selected_entry(cx) {
+            // With several worktrees, the root name tells which one the path is in.
+            let include_root = self.project.read(cx).visible_worktrees(cx).count() > 1;
+            let path = if include_root {
+                PathBuf::from(worktree.root_name()).join(&entry.path)
+            } else {
+                entry.path.to_path_buf()
+            };
+            cx.write_to_clipboard(ClipboardItem::new(path.to_string_lossy().to_string()));
         }
     }
 
@@ -1339,6 +1478,12 @@ impl ProjectPanel {
         }
     }
 
+    fn open_in_default_app(&mut self, _: &OpenInDefaultApp, cx: &mut ViewContext<Self>) {
+        if let Some((worktree, entry)) = self.selected_entry(cx) {
+            cx.open_with_system(&worktree.abs_path().join(&entry.path));
+        }
+    }
+
     fn open_in_terminal(&mut self, _: &OpenInTerminal, cx: &mut ViewContext<Self>) {
         if let Some((worktree, entry)) = self.selected_entry(cx) {
             let abs_path = worktree.abs_path().join(&entry.path);
@@ -2205,6 +2350,7 @@ impl Render for ProjectPanel {
                 })
                 .when(project.is_local(), |el| {
                     el.on_action(cx.listener(Self::reveal_in_finder))
+                        .on_action(cx.listener(Self::open_in_default_app))
                         .on_action(cx.listener(Self::open_in_terminal))
                 })
                 .on_mouse_down(
@@ -2492,6 +2638,66 @@ mod tests {
         );
     }
 
+    #[gpui::test]
+    async fn test_restoring_expanded_dirs(cx: &mut gpui::TestAppContext) {
+        init_test(cx);
+
+        let fs = FakeFs::new(cx.executor().clone());
+        fs.insert_tree(
+            "/root1",
+            json!({
+                "a": { "0": { "q": "" } },
+                "b": { "1": { "r": "" }, "2": { "s": "" } },
+            }),
+        )
+        .await;
+        fs.insert_tree("/root2", json!({ "c": { "t": "" } })).await;
+
+        let project = Project::test(fs.clone(), ["/root1".as_ref(), "/root2".as_ref()], cx).await;
+        let workspace = cx.add_window(|cx| Workspace::test_new(project.clone(), cx));
+        let cx = &mut VisualTestContext::from_window(*workspace, cx);
+        let panel = workspace
+            .update(cx, |workspace, cx| ProjectPanel::new(workspace, cx))
+            .unwrap();
+
+        // Directories that no longer exist stay pending, and the collapsed roots stay collapsed.
+        panel.update(cx, |panel, cx| {
+            panel.pending_expanded_dirs = HashMap::from_iter([
+                (
+                    PathBuf::from("/root1"),
+                    vec![
+                        Path::new("").into(),
+                        Path::new("b").into(),
+                        Path::new("b/2").into(),
+                        Path::new("deleted").into(),
+                    ],
+                ),
+                (PathBuf::from("/root2"), Vec::new()),
+            ]);
+            panel.expanded_dir_ids.clear();
+            panel.restore_expanded_dirs(cx);
+            panel.update_visible_entries(None, cx);
+        });
+        assert_eq!(
+            visible_entries_as_strings(&panel, 0..50, cx),
+            &[
+                "v root1",
+                "    > a",
+                "    v b",
+                "        > 1",
+                "        v 2",
+                "              s",
+                "> root2",
+            ]
+        );
+        panel.update(cx, |panel, _| {
+            assert_eq!(
+                panel.pending_expanded_dirs,
+                HashMap::from_iter([(PathBuf::from("/root1"), vec![Path::new("deleted").into()])])
+            );
+        });
+    }
+
     #[gpui::test]
     async fn test_exclusions_in_visible_list(cx: &mut gpui::TestAppContext) {
         init_test(cx);
@@ -16,6 +16,14 @@ macro_rules! messages {
             }
         }
 
+        /// Returns the name of the message in the given envelope, unless it's empty.
+        pub fn envelope_payload_type_name(envelope: &Envelope) -> Option<&'static str> {
+            match &envelope.payload {
+                $(Some(envelope::Payload::$name(_)) => Some($name::NAME),)*
+                _ => None
+            }
+        }
+
         $(
             impl EnvelopedMessage for $name {
                 const NAME: &'static str = std::stringify!($name);
@@ -151,6 +151,7 @@ messages!(
     (Call, Foreground),
     (CallCanceled, Foreground),
     (CancelCall, Foreground),
+    (CancelStream, Foreground),
     (ChannelMessageSent, Foreground),
     (ChannelMessageUpdate, Foreground),
     (CompleteWithLanguageModel, Background),
@@ -337,7 +338,11 @@ messages!(
     (OpenNewBuffer, Foreground),
     (RestartLanguageServers, Foreground),
     (LinkedEditingRange, Background),
-    (LinkedEditingRangeResponse, Background)
+    (LinkedEditingRangeResponse, Background),
+    (TelemetryBatch, Background),
+    (StreamProjectSearch, Background),
+    (ProjectSearchResult, Background),
+    (RefreshWorktree, Foreground)
 );
 
 request_messages!(
@@ -421,6 +426,8 @@ request_messages!(
     (RespondToContactRequest, Ack),
     (SaveBuffer, BufferSaved),
     (SearchProject, SearchProjectResponse),
+    (StreamProjectSearch, ProjectSearchResult),
+    (RefreshWorktree, Ack),
     (SendChannelMessage, SendChannelMessageResponse),
     (SetChannelMemberRole, Ack),
     (SetChannelVisibility, Ack),
@@ -496,6 +503,7 @@ entity_messages!(
     ResolveInlayHint,
     SaveBuffer,
     SearchProject,
+    StreamProjectSearch,
     StartLanguageServer,
     SynchronizeBuffers,
     TaskContextForLocation,
@@ -622,6 +630,7 @@ pub fn split_worktree_update(
             is_last_update: done_files && message.is_last_update,
             updated_repositories,
             removed_repositories,
+            is_snapshot: mem::take(&mut message.is_snapshot),
         })
     })
 }
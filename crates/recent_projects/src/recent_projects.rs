@@ -9,7 +9,7 @@ use feature_flags::FeatureFlagAppExt;
 use fuzzy::{StringMatch, StringMatchCandidate};
 use gpui::{
     Action, AnyElement, AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView,
-    Subscription, Task, View, ViewContext, WeakView,
+    Subscription, Task, View, ViewContext, WeakView, WindowHandle,
 };
 use ordered_float::OrderedFloat;
 use picker::{
@@ -282,6 +282,12 @@ impl PickerDelegate for RecentProjectsDelegate {
                 .update(cx, |workspace, cx| {
                     if workspace.database_id() == Some(*candidate_workspace_id) {
                         Task::ready(Ok(()))
+                    } else if let Some(window) = window_for_workspace(*candidate_workspace_id, cx)
+                    {
+                        window
+                            .update(cx, |_, cx| cx.activate_window())
+                            .log_err();
+                        Task::ready(Ok(()))
                     } else {
                         match candidate_workspace_location {
                             SerializedWorkspaceLocation::Local(paths, _) => {
@@ -528,6 +534,22 @@ impl PickerDelegate for RecentProjectsDelegate {
     }
 }
 
+/// Returns the window that a recent project is open in already, to switch to it instead of
+/// opening the project again.
+fn window_for_workspace(
+    workspace_id: WorkspaceId,
+    cx: &AppContext,
+) -> Option<WindowHandle<Workspace>> {
+    cx.windows()
+        .into_iter()
+        .filter_map(|window| window.downcast::<Workspace>())
+        .find(|window| {
+            window
+                .read(cx)
+                .is_ok_and(|workspace| workspace.database_id() == Some(workspace_id))
+        })
+}
+
 fn open_dev_server_project(
     replace_current_window: bool,
     dev_server_project_id: DevServerProjectId,
@@ -684,7 +706,7 @@ mod tests {
     use std::path::PathBuf;
 
     use editor::Editor;
-    use gpui::{TestAppContext, WindowHandle};
+    use gpui::TestAppContext;
     use project::Project;
     use serde_json::json;
     use workspace::{open_paths, AppState, LocalPaths};
@@ -792,6 +814,85 @@ mod tests {
             .unwrap();
     }
 
+    #[gpui::test]
+    async fn test_switches_to_window_of_open_project(cx: &mut TestAppContext) {
+        let app_state = init_test(cx);
+        app_state
+            .fs
+            .as_fake()
+            .insert_tree(
+                "/",
+                json!({ "dir1": { "a.txt": "a" }, "dir2": { "b.txt": "b" } }),
+            )
+            .await;
+        for path in ["/dir1", "/dir2"] {
+            cx.update(|cx| {
+                open_paths(
+                    &[PathBuf::from(path)],
+                    app_state.clone(),
+                    workspace::OpenOptions::default(),
+                    cx,
+                )
+            })
+            .await
+            .unwrap();
+        }
+        let windows = cx.update(|cx| {
+            cx.windows()
+                .into_iter()
+                .filter_map(|window| window.downcast::<Workspace>())
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(windows.len(), 2);
+        let root_names = |window: WindowHandle<Workspace>, cx: &mut TestAppContext| {
+            window
+                .read_with(cx, |workspace, cx| {
+                    workspace
+                        .worktrees(cx)
+                        .map(|worktree| worktree.read(cx).root_name().to_string())
+                        .collect::<Vec<_>>()
+                })
+                .unwrap()
+        };
+        let (dir1_window, dir2_window) = if root_names(windows[0], cx) == ["dir1"] {
+            (windows[0], windows[1])
+        } else {
+            (windows[1], windows[0])
+        };
+        let dir1_workspace_id = dir1_window
+            .read_with(cx, |workspace, _| workspace.database_id())
+            .unwrap()
+            .unwrap();
+
+        // Picking the project of another window switches to it, instead of opening it again in
+        // the current window.
+        let recent_projects_picker = open_recent_projects(&dir2_window, cx);
+        dir2_window
+            .update(cx, |_, cx| {
+                recent_projects_picker.update(cx, |picker, _| {
+                    let delegate = &mut picker.delegate;
+                    delegate.matches = vec![StringMatch {
+                        candidate_id: 0,
+                        score: 1.0,
+                        positions: Vec::new(),
+                        string: "dir1".to_string(),
+                    }];
+                    delegate.set_workspaces(vec![(
+                        dir1_workspace_id,
+                        LocalPaths::new(vec!["/dir1"]).into(),
+                    )]);
+                });
+            })
+            .unwrap();
+        cx.dispatch_action(*dir2_window, menu::Confirm);
+        cx.run_until_parked();
+
+        assert!(!cx.has_pending_prompt());
+        assert_eq!(cx.update(|cx| cx.windows().len()), 2);
+        assert_eq!(root_names(dir1_window, cx), ["dir1"]);
+        assert_eq!(root_names(dir2_window, cx), ["dir2"]);
+    }
+
     fn open_recent_projects(
         workspace: &WindowHandle<Workspace>,
         cx: &mut TestAppContext,
@@ -1,4 +1,4 @@
-use std::ops::Range;
+use std::{cmp, ops::Range};
 
 use criterion::{
     black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput,
@@ -173,5 +173,127 @@ fn rope_benchmarks(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, rope_benchmarks);
+fn large_rope_benchmarks(c: &mut Criterion) {
+    static SEED: u64 = 9999;
+    static MB: usize = 1024 * 1024;
+
+    let rng = StdRng::seed_from_u64(SEED);
+    let sizes = [MB, 50 * MB];
+
+    let mut group = c.benchmark_group("from_reader");
+    group.sample_size(10);
+    for size in sizes.iter() {
+        group.throughput(Throughput::Bytes(*size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, &size| {
+            let text = generate_random_text(rng.clone(), *size);
+
+            b.iter_with_large_drop(|| Rope::from_reader(text.as_bytes()).unwrap());
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("insert_char");
+    group.sample_size(10);
+    for size in sizes.iter() {
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, &size| {
+            let rope = generate_random_rope(rng.clone(), *size);
+            let mut rng = rng.clone();
+
+            b.iter_batched(
+                || {
+                    let offset = rope.clip_offset(rng.gen_range(0..=rope.len()), Bias::Left);
+                    (rope.clone(), offset)
+                },
+                |(mut rope, offset)| {
+                    rope.replace(offset..offset, "a");
+                    rope
+                },
+                BatchSize::LargeInput,
+            );
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("batch_edit");
+    group.sample_size(10);
+    for size in sizes.iter() {
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, &size| {
+            let rope = generate_random_rope(rng.clone(), *size);
+            let mut ranges = generate_random_rope_ranges(rng.clone(), &rope);
+            let step = cmp::max(ranges.len() / 10_000, 1);
+            ranges = ranges.into_iter().step_by(step).take(10_000).collect();
+
+            b.iter_with_large_drop(|| {
+                // Edit the way buffers do: copy everything between the edited ranges over.
+                let mut new_rope = Rope::new();
+                let mut cursor = rope.cursor(0);
+                for range in &ranges {
+                    new_rope.append(cursor.slice(range.start));
+                    cursor.seek_forward(range.end);
+                    new_rope.push("edit");
+                }
+                new_rope.append(cursor.suffix());
+                new_rope
+            });
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("point_to_offset");
+    for size in sizes.iter() {
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, &size| {
+            let rope = generate_random_rope(rng.clone(), *size);
+            let mut rng = rng.clone();
+            let points = (0..10_000)
+                .map(|_| {
+                    rope.offset_to_point(
+                        rope.clip_offset(rng.gen_range(0..=rope.len()), Bias::Left),
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            b.iter(|| {
+                for point in &points {
+                    black_box(rope.point_to_offset(*point));
+                }
+            });
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("offset_to_point");
+    for size in sizes.iter() {
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, &size| {
+            let rope = generate_random_rope(rng.clone(), *size);
+            let mut rng = rng.clone();
+            let offsets = (0..10_000)
+                .map(|_| rope.clip_offset(rng.gen_range(0..=rope.len()), Bias::Left))
+                .collect::<Vec<_>>();
+
+            b.iter(|| {
+                for offset in &offsets {
+                    black_box(rope.offset_to_point(*offset));
+                }
+            });
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("iterate_chunks");
+    group.sample_size(10);
+    for size in sizes.iter() {
+        group.throughput(Throughput::Bytes(*size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, &size| {
+            let rope = generate_random_rope(rng.clone(), *size);
+
+            b.iter(|| {
+                let len = rope.chunks().map(str::len).sum::<usize>();
+                assert_eq!(len, rope.len());
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, rope_benchmarks, large_rope_benchmarks);
 criterion_main!(benches);
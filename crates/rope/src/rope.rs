@@ -2,6 +2,8 @@ mod offset_utf16;
 mod point;
 mod point_utf16;
 mod unclipped;
+mod width;
+mod words;
 
 use arrayvec::ArrayString;
 use smallvec::SmallVec;
@@ -11,13 +13,18 @@ use std::{
     str,
 };
 use sum_tree::{Bias, Dimension, SumTree};
-use unicode_segmentation::GraphemeCursor;
+use unicode_segmentation::{GraphemeCursor, GraphemeIncomplete};
 use util::debug_panic;
 
 pub use offset_utf16::OffsetUtf16;
 pub use point::Point;
 pub use point_utf16::PointUtf16;
 pub use unclipped::Unclipped;
+pub use width::{cell_width, text_cell_width};
+pub use words::{
+    is_combining_mark, is_grapheme_extender, is_script_boundary, is_word_char, TextStats,
+    ZERO_WIDTH_JOINER,
+};
 
 #[cfg(test)]
 const CHUNK_BASE: usize = 6;
@@ -165,6 +172,73 @@ impl Rope {
 
         self.check_invariants();
     }
+
+    /// Builds a rope from everything the given reader yields, failing if it isn't valid UTF-8.
+    ///
+    /// Unlike pushing the text as it is read, this collects all the chunks first and then builds
+    /// the tree from them bottom-up, on multiple threads for large files, so that loading a
+    /// large file doesn't rebalance the tree over and over again.
+    pub fn from_reader(mut reader: impl io::Read) -> io::Result<Self> {
+        const READ_BUFFER_LEN: usize = 64 * 1024;
+
+        let mut chunks = Vec::new();
+        let mut buffer = vec![0; READ_BUFFER_LEN];
+        let mut pending_len = 0;
+        loop {
+            let read_len = match reader.read(&mut buffer[pending_len..]) {
+                Ok(read_len) => read_len,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(error) => return Err(error),
+            };
+            let is_eof = read_len == 0;
+            let len = pending_len + read_len;
+
+            let mut text = match str::from_utf8(&buffer[..len]) {
+                Ok(text) => text,
+                // A character that is cut off at the end of the buffer is completed by the
+                // next read.
+                Err(error) if error.error_len().is_none() && !is_eof => {
+                    str::from_utf8(&buffer[..error.valid_up_to()]).unwrap()
+                }
+                Err(error) => return Err(io::Error::new(io::ErrorKind::InvalidData, error)),
+            };
+            let valid_len = text.len();
+
+            // Only the last chunk may be shorter than the others, so anything that doesn't fill
+            // a whole chunk waits for the next read.
+            while text.len() >= 2 * CHUNK_BASE || (is_eof && !text.is_empty()) {
+                let mut split_ix = cmp::min(2 * CHUNK_BASE, text.len());
+                while !text.is_char_boundary(split_ix) {
+                    split_ix -= 1;
+                }
+                let (chunk, remainder) = text.split_at(split_ix);
+                chunks.push(Chunk(ArrayString::from(chunk).unwrap()));
+                text = remainder;
+            }
+
+            if is_eof {
+                break;
+            }
+            let consumed_len = valid_len - text.len();
+            buffer.copy_within(consumed_len..len, 0);
+            pending_len = len - consumed_len;
+        }
+
+        #[cfg(test)]
+        const PARALLEL_THRESHOLD: usize = 4;
+        #[cfg(not(test))]
+        const PARALLEL_THRESHOLD: usize = 4 * (2 * sum_tree::TREE_BASE);
+
+        let chunks = if chunks.len() >= PARALLEL_THRESHOLD {
+            SumTree::from_par_iter(chunks, &())
+        } else {
+            SumTree::from_iter(chunks, &())
+        };
+        let rope = Self { chunks };
+        rope.check_invariants();
+        Ok(rope)
+    }
+
     pub fn push_front(&mut self, text: &str) {
         let suffix = mem::replace(self, Rope::from(text));
         self.append(suffix);
@@ -192,6 +266,15 @@ impl Rope {
         self.chunks.extent(&())
     }
 
+    /// Returns the [`TextStats`] of the given range. They're computed by scanning all of its
+    /// text, so this is only meant for text that's being looked at, like a selection.
+    pub fn stats_in_range(&self, range: Range<usize>) -> TextStats {
+        self.chunks_in_range(range)
+            .fold(TextStats::default(), |stats, chunk| {
+                stats + &TextStats::from(chunk)
+            })
+    }
+
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
@@ -387,17 +470,72 @@ impl Rope {
         }
     }
 
+    /// Clips the given point to the nearest grapheme cluster boundary in the direction of
+    /// `bias`, so that it never lands inside of a character or of a cluster like an emoji
+    /// sequence or a letter with combining accents.
     pub fn clip_point(&self, point: Point, bias: Bias) -> Point {
         let mut cursor = self.chunks.cursor::<Point>();
         cursor.seek(&point, Bias::Right, &());
         if let Some(chunk) = cursor.item() {
             let overshoot = point - cursor.start();
-            *cursor.start() + chunk.clip_point(overshoot, bias)
+            // Chunks aren't split at grapheme cluster boundaries, so a cluster may continue
+            // in the neighboring chunks. A chunk that starts in the middle of a line doesn't
+            // even know where the clusters on that line start.
+            let chunk_starts_mid_line = overshoot.row == 0 && cursor.start().column > 0;
+            let clipped = if chunk_starts_mid_line {
+                *cursor.start() + chunk.clip_first_line_column(overshoot.column, bias)
+            } else {
+                *cursor.start() + chunk.clip_point(overshoot, bias)
+            };
+
+            if chunk_starts_mid_line || clipped == cursor.end(&()) {
+                let mut offset = self.point_to_offset(clipped);
+                while !self.is_grapheme_boundary(offset) {
+                    let ch = match bias {
+                        Bias::Left => self.reversed_chars_at(offset).next(),
+                        Bias::Right => self.chars_at(offset).next(),
+                    };
+                    match (ch, bias) {
+                        (Some(ch), Bias::Left) if ch != '\n' => offset -= ch.len_utf8(),
+                        (Some(ch), Bias::Right) if ch != '\n' => offset += ch.len_utf8(),
+                        _ => break,
+                    }
+                }
+                return self.offset_to_point(offset);
+            }
+
+            clipped
         } else {
             self.summary().lines
         }
     }
 
+    /// Returns whether the given offset lies between two grapheme clusters, looking at as
+    /// many chunks as needed to tell.
+    fn is_grapheme_boundary(&self, offset: usize) -> bool {
+        let mut grapheme_cursor = GraphemeCursor::new(offset, self.len(), true);
+        let mut cursor = self.chunks.cursor::<usize>();
+        cursor.seek(&offset, Bias::Right, &());
+        loop {
+            let Some(chunk) = cursor.item() else {
+                return true;
+            };
+            match grapheme_cursor.is_boundary(&chunk.0, *cursor.start()) {
+                Ok(is_boundary) => return is_boundary,
+                Err(GraphemeIncomplete::PreContext(context_end)) => {
+                    let mut context_cursor = self.chunks.cursor::<usize>();
+                    context_cursor.seek(&context_end, Bias::Left, &());
+                    let Some(context) = context_cursor.item() else {
+                        return true;
+                    };
+                    grapheme_cursor.provide_context(&context.0, *context_cursor.start());
+                }
+                Err(GraphemeIncomplete::NextChunk) => cursor.next(&()),
+                Err(_) => return true,
+            }
+        }
+    }
+
     pub fn clip_point_utf16(&self, point: Unclipped<PointUtf16>, bias: Bias) -> PointUtf16 {
         let mut cursor = self.chunks.cursor::<PointUtf16>();
         cursor.seek(&point.0, Bias::Right, &());
@@ -1005,6 +1143,19 @@ impl Chunk {
         unreachable!()
     }
 
+    /// Clips a column on the chunk's first line to a character boundary.
+    fn clip_first_line_column(&self, column: u32, bias: Bias) -> Point {
+        let line = self.0.split('\n').next().unwrap_or_default();
+        let mut column = (column as usize).min(line.len());
+        while !line.is_char_boundary(column) {
+            match bias {
+                Bias::Left => column -= 1,
+                Bias::Right => column += 1,
+            }
+        }
+        Point::new(0, column as u32)
+    }
+
     fn clip_point_utf16(&self, target: Unclipped<PointUtf16>, bias: Bias) -> PointUtf16 {
         for (row, line) in self.0.split('\n').enumerate() {
             if row == target.0.row as usize {
@@ -1296,6 +1447,7 @@ mod tests {
     use super::*;
     use rand::prelude::*;
     use std::{cmp::Ordering, env, io::Read};
+    use unicode_segmentation::UnicodeSegmentation as _;
     use util::RandomCharIter;
     use Bias::{Left, Right};
 
@@ -1355,6 +1507,41 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_clip_point_to_grapheme_clusters() {
+        let text = "ab👩\u{200D}💻cd🇺🇸🇫🇷e\u{301}f\nहिंदी x👍\u{1F3FD}\n🇩🇪🇫🇷🇮🇹";
+        let mut pushed_by_char = Rope::new();
+        for ch in text.chars() {
+            pushed_by_char.push(ch.encode_utf8(&mut [0; 4]));
+        }
+
+        for rope in [Rope::from(text), pushed_by_char] {
+            assert_eq!(rope.text(), text);
+            for (row, line) in text.split('\n').enumerate() {
+                let boundaries = line
+                    .grapheme_indices(true)
+                    .map(|(ix, _)| ix)
+                    .chain([line.len()])
+                    .collect::<Vec<_>>();
+                for column in 0..=line.len() {
+                    let left = *boundaries.iter().rev().find(|ix| **ix <= column).unwrap();
+                    let right = *boundaries.iter().find(|ix| **ix >= column).unwrap();
+                    let point = Point::new(row as u32, column as u32);
+                    assert_eq!(
+                        rope.clip_point(point, Bias::Left),
+                        Point::new(row as u32, left as u32),
+                        "clip_point({point:?}, Left)"
+                    );
+                    assert_eq!(
+                        rope.clip_point(point, Bias::Right),
+                        Point::new(row as u32, right as u32),
+                        "clip_point({point:?}, Right)"
+                    );
+                }
+            }
+        }
+    }
+
     #[test]
     fn test_lines() {
         let rope = Rope::from("abc\ndefg\nhi");
@@ -1438,6 +1625,11 @@ mod tests {
                         .collect::<String>(),
                     &expected[start_ix..end_ix]
                 );
+
+                assert_eq!(
+                    actual.stats_in_range(start_ix..end_ix),
+                    TextStats::from(&expected[start_ix..end_ix])
+                );
             }
 
             let mut offset_utf16 = OffsetUtf16(0);
@@ -1553,6 +1745,47 @@ mod tests {
         }
     }
 
+    #[gpui::test(iterations = 100)]
+    fn test_random_from_reader(mut rng: StdRng) {
+        /// Hands out its text in reads of random lengths, which split characters apart.
+        struct RandomReader<'a> {
+            text: &'a [u8],
+            rng: StdRng,
+        }
+
+        impl io::Read for RandomReader<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                if self.text.is_empty() {
+                    return Ok(0);
+                }
+                if self.rng.gen_bool(0.1) {
+                    return Err(io::ErrorKind::Interrupted.into());
+                }
+                let len = self
+                    .rng
+                    .gen_range(1..=buf.len().min(self.text.len()).min(20));
+                buf[..len].copy_from_slice(&self.text[..len]);
+                self.text = &self.text[len..];
+                Ok(len)
+            }
+        }
+
+        let len = rng.gen_range(0..500);
+        let text = RandomCharIter::new(&mut rng).take(len).collect::<String>();
+        let reader = RandomReader {
+            text: text.as_bytes(),
+            rng: StdRng::seed_from_u64(rng.gen()),
+        };
+        let rope = Rope::from_reader(reader).unwrap();
+        assert_eq!(rope.text(), text);
+        assert_eq!(rope.summary(), Rope::from(text.as_str()).summary());
+
+        let mut invalid = text.into_bytes();
+        invalid.push(0xF0);
+        let error = Rope::from_reader(invalid.as_slice()).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
     fn clip_offset(text: &str, mut offset: usize, bias: Bias) -> usize {
         while !text.is_char_boundary(offset) {
             match bias {
@@ -0,0 +1,56 @@
+use crate::words::{is_grapheme_extender, is_pictographic, ZERO_WIDTH_JOINER};
+use unicode_width::UnicodeWidthChar as _;
+
+const EMOJI_PRESENTATION_SELECTOR: char = '\u{FE0F}';
+
+/// Returns how many cells of a monospace font the given character takes up when it follows
+/// `previous` on the same line, so that each grapheme cluster counts as wide as it renders:
+/// East Asian wide characters and emoji take up two cells, and the characters that extend a
+/// cluster, like combining accents or the emoji joined to a sequence, take up none.
+pub fn cell_width(c: char, previous: Option<char>) -> u32 {
+    if is_grapheme_extender(c) {
+        // The emoji presentation of a symbol is wide even if the symbol itself isn't.
+        let widens_previous = c == EMOJI_PRESENTATION_SELECTOR
+            && previous.map_or(false, |previous| {
+                is_pictographic(previous) && previous.width() == Some(1)
+            });
+        widens_previous as u32
+    } else if previous == Some(ZERO_WIDTH_JOINER) && is_pictographic(c) {
+        0
+    } else {
+        // Control characters are displayed in a single cell.
+        c.width().unwrap_or(1) as u32
+    }
+}
+
+/// Returns how many cells of a monospace font the given text takes up, with tabs as well as
+/// newlines taking up a single cell.
+pub fn text_cell_width(text: &str) -> u32 {
+    let mut previous = None;
+    let mut width = 0;
+    for c in text.chars() {
+        width += cell_width(c, previous);
+        previous = Some(c);
+    }
+    width
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cell_width() {
+        assert_eq!(text_cell_width("abc"), 3);
+        assert_eq!(text_cell_width("中文"), 4);
+        assert_eq!(text_cell_width("ｱｲ"), 2);
+        assert_eq!(text_cell_width("🏀"), 2);
+        assert_eq!(text_cell_width("e\u{301}"), 1);
+        assert_eq!(text_cell_width("👨‍👩‍👧"), 2);
+        assert_eq!(text_cell_width("👍🏽"), 2);
+        assert_eq!(text_cell_width("✔"), 1);
+        assert_eq!(text_cell_width("✔\u{FE0F}"), 2);
+        assert_eq!(text_cell_width("1\u{FE0F}"), 1);
+        assert_eq!(text_cell_width("a\u{200D}中"), 3);
+    }
+}
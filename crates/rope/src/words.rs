@@ -0,0 +1,168 @@
+use std::ops::{Add, AddAssign};
+
+pub const ZERO_WIDTH_JOINER: char = '\u{200D}';
+
+/// Returns whether the given character belongs to a word by default, i.e. without any
+/// language-specific word characters.
+pub fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || is_combining_mark(c)
+}
+
+pub fn is_combining_mark(c: char) -> bool {
+    matches!(
+        c,
+        '\u{0300}'..='\u{036F}'
+            | '\u{1AB0}'..='\u{1AFF}'
+            | '\u{1DC0}'..='\u{1DFF}'
+            | '\u{20D0}'..='\u{20FF}'
+            | '\u{FE20}'..='\u{FE2F}'
+    )
+}
+
+/// Characters that extend the grapheme cluster that precedes them.
+pub fn is_grapheme_extender(c: char) -> bool {
+    is_combining_mark(c)
+        || matches!(
+            c,
+            ZERO_WIDTH_JOINER
+                | '\u{FE00}'..='\u{FE0F}'
+                | '\u{1F3FB}'..='\u{1F3FF}'
+                | '\u{E0020}'..='\u{E007F}'
+        )
+}
+
+/// Returns whether the given character is an emoji, roughly.
+pub fn is_pictographic(c: char) -> bool {
+    matches!(c, '\u{2600}'..='\u{27BF}' | '\u{1F000}'..='\u{1FAFF}')
+}
+
+/// Returns whether a word is split between two adjacent characters of the same kind, because
+/// they belong to scripts that don't separate their words with spaces.
+pub fn is_script_boundary(left: char, right: char) -> bool {
+    !is_grapheme_extender(right)
+        && left != ZERO_WIDTH_JOINER
+        && WordScript::of(left) != WordScript::of(right)
+}
+
+/// The scripts between which a word is split even though there is no change
+/// of character kind, because they don't separate their words with spaces.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum WordScript {
+    Han,
+    Hiragana,
+    Katakana,
+    Hangul,
+    Emoji,
+    Other,
+}
+
+impl WordScript {
+    fn of(c: char) -> Self {
+        match c {
+            '\u{2E80}'..='\u{2FDF}'
+            | '\u{3005}'
+            | '\u{3007}'
+            | '\u{3400}'..='\u{4DBF}'
+            | '\u{4E00}'..='\u{9FFF}'
+            | '\u{F900}'..='\u{FAFF}'
+            | '\u{20000}'..='\u{3FFFF}' => WordScript::Han,
+            '\u{3040}'..='\u{309F}' => WordScript::Hiragana,
+            '\u{30A0}'..='\u{30FF}' | '\u{31F0}'..='\u{31FF}' | '\u{FF66}'..='\u{FF9F}' => {
+                WordScript::Katakana
+            }
+            '\u{1100}'..='\u{11FF}' | '\u{3130}'..='\u{318F}' | '\u{AC00}'..='\u{D7AF}' => {
+                WordScript::Hangul
+            }
+            _ if is_pictographic(c) || c == ZERO_WIDTH_JOINER => WordScript::Emoji,
+            _ if is_grapheme_extender(c) && !is_combining_mark(c) => WordScript::Emoji,
+            _ => WordScript::Other,
+        }
+    }
+}
+
+/// The number of characters and words in a piece of text, with words counted by the same rules
+/// word movement uses. The stats of adjacent pieces of text can be added up, joining the words
+/// that span them.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct TextStats {
+    pub chars: usize,
+    pub words: usize,
+    first_char: Option<char>,
+    last_char: Option<char>,
+}
+
+impl<'a> From<&'a str> for TextStats {
+    fn from(text: &'a str) -> Self {
+        let mut chars = 0;
+        let mut words = 0;
+        let mut last_char = None;
+        for c in text.chars() {
+            chars += 1;
+            if is_word_char(c) && !last_char.map_or(false, |last| continues_word(last, c)) {
+                words += 1;
+            }
+            last_char = Some(c);
+        }
+        Self {
+            chars,
+            words,
+            first_char: text.chars().next(),
+            last_char,
+        }
+    }
+}
+
+fn continues_word(left: char, right: char) -> bool {
+    is_word_char(left) && is_word_char(right) && !is_script_boundary(left, right)
+}
+
+impl<'a> AddAssign<&'a Self> for TextStats {
+    fn add_assign(&mut self, other: &'a Self) {
+        self.chars += other.chars;
+        self.words += other.words;
+        if let Some((left, right)) = self.last_char.zip(other.first_char) {
+            if continues_word(left, right) {
+                self.words -= 1;
+            }
+        }
+        self.first_char = self.first_char.or(other.first_char);
+        self.last_char = other.last_char.or(self.last_char);
+    }
+}
+
+impl<'a> Add<&'a Self> for TextStats {
+    type Output = Self;
+
+    fn add(mut self, other: &'a Self) -> Self::Output {
+        self += other;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_stats() {
+        assert_eq!(TextStats::from("").words, 0);
+        assert_eq!(TextStats::from("  ").words, 0);
+        assert_eq!(TextStats::from("one two_three, four").words, 3);
+        assert_eq!(TextStats::from("foo.bar(baz)").words, 3);
+        assert_eq!(TextStats::from("e\u{301}te\u{301}").words, 1);
+        assert_eq!(TextStats::from("日本語のテキスト").words, 3);
+
+        assert_eq!(TextStats::from("e\u{301}te\u{301} 日本").chars, 8);
+
+        // Splitting the text anywhere gives the same count once the pieces are added up.
+        let text = "one two_three, e\u{301}te\u{301} 日本語のテキスト";
+        for (ix, _) in text.char_indices() {
+            let (left, right) = text.split_at(ix);
+            assert_eq!(
+                TextStats::from(left) + &TextStats::from(right),
+                TextStats::from(text),
+                "split at {ix}"
+            );
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use std::time::{Duration, Instant};
+
+use rand::prelude::*;
+use rope::Rope;
+use sum_tree::Bias;
+use util::RandomCharIter;
+
+/// Loose bounds that only catch order-of-magnitude regressions, such as edits that copy the whole
+/// rope. The actual numbers come from the rope benchmarks.
+#[test]
+fn test_edit_performance() {
+    const TEXT_LEN: usize = 1024 * 1024;
+    const EDIT_COUNT: usize = 1000;
+
+    let mut rng = StdRng::seed_from_u64(0);
+    let text = RandomCharIter::new(&mut rng)
+        .take(TEXT_LEN)
+        .collect::<String>();
+
+    let start = Instant::now();
+    let mut rope = Rope::from_reader(text.as_bytes()).unwrap();
+    assert_eq!(rope.len(), text.len());
+    let elapsed = start.elapsed();
+    assert!(
+        elapsed < Duration::from_secs(5),
+        "reading {} bytes took {elapsed:?}",
+        text.len()
+    );
+
+    let start = Instant::now();
+    for _ in 0..EDIT_COUNT {
+        let offset = rope.clip_offset(rng.gen_range(0..=rope.len()), Bias::Left);
+        rope.replace(offset..offset, "a");
+    }
+    let elapsed = start.elapsed();
+    assert!(
+        elapsed < Duration::from_secs(5),
+        "{EDIT_COUNT} single character inserts took {elapsed:?}"
+    );
+
+    let start = Instant::now();
+    for _ in 0..EDIT_COUNT {
+        let offset = rope.clip_offset(rng.gen_range(0..=rope.len()), Bias::Left);
+        let point = rope.offset_to_point(offset);
+        assert_eq!(rope.point_to_offset(point), offset);
+    }
+    let elapsed = start.elapsed();
+    assert!(
+        elapsed < Duration::from_secs(5),
+        "{EDIT_COUNT} offset to point conversions took {elapsed:?}"
+    );
+}
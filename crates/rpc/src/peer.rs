@@ -9,6 +9,7 @@ use anyhow::{anyhow, Context, Result};
 use collections::HashMap;
 use futures::{
     channel::{mpsc, oneshot},
+    future::{BoxFuture, Either, Fuse, FusedFuture, Shared},
     stream::BoxStream,
     FutureExt, SinkExt, Stream, StreamExt, TryFutureExt,
 };
@@ -20,9 +21,10 @@ use std::{
     future::Future,
     sync::atomic::Ordering::SeqCst,
     sync::{
-        atomic::{self, AtomicU32},
-        Arc,
+        atomic::{self, AtomicU32, AtomicU64, AtomicUsize},
+        Arc, Weak,
     },
+    task::{Context as TaskContext, Poll},
     time::Duration,
     time::Instant,
 };
@@ -62,12 +64,183 @@ pub struct Peer {
     epoch: AtomicU32,
     pub connections: RwLock<HashMap<ConnectionId, ConnectionState>>,
     next_connection_id: AtomicU32,
+    keepalive: Mutex<Keepalive>,
+    write_batching: Mutex<Option<WriteBatching>>,
+    observer: RwLock<Option<Arc<dyn PeerObserver>>>,
+    metrics: Arc<Metrics>,
+}
+
+/// Lets the messages that get sent over a connection accumulate before they're flushed, so
+/// that many small ones don't each need their own write. The messages are flushed once they
+/// add up to `max_len`, or `delay` after the first of them, whichever comes first. Requests
+/// are flushed right away, along with the messages before them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WriteBatching {
+    pub max_len: usize,
+    pub delay: Duration,
+}
+
+impl Default for WriteBatching {
+    fn default() -> Self {
+        Self {
+            max_len: 8 * 1024,
+            delay: Duration::from_millis(5),
+        }
+    }
+}
+
+/// Gets told about the activity of a [`Peer`], so that it can be measured. It's called from
+/// the peer's hot paths, including the one that handles every message, so it has to be cheap.
+pub trait PeerObserver: Send + Sync {
+    /// A request was sent, and is waiting for its response.
+    fn request_started(
+        &self,
+        _connection_id: ConnectionId,
+        _message_id: u32,
+        _message_type: &'static str,
+    ) {
+    }
+
+    /// A request got its response, failed, or got dropped before it got its response.
+    fn request_ended(
+        &self,
+        _connection_id: ConnectionId,
+        _message_id: u32,
+        _message_type: &'static str,
+        _duration: Duration,
+        _result: Result<(), &anyhow::Error>,
+    ) {
+    }
+
+    /// An envelope of the given size got written to a connection.
+    fn message_sent(&self, _connection_id: ConnectionId, _len: usize) {}
+
+    /// An envelope of the given size got read from a connection.
+    fn message_received(&self, _connection_id: ConnectionId, _len: usize) {}
+
+    /// One of the responses to a streaming request got picked up by the requester.
+    fn stream_response_received(
+        &self,
+        _connection_id: ConnectionId,
+        _message_id: u32,
+        _message_type: &'static str,
+    ) {
+    }
+}
+
+/// A snapshot of the counters of a [`Peer`], across all of its connections. Only requests
+/// that expect a single response are counted as requests, not streaming ones.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PeerMetrics {
+    pub requests_sent: usize,
+    pub responses_received: usize,
+    pub requests_in_flight: usize,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+}
+
+#[derive(Default)]
+struct Metrics {
+    requests_sent: AtomicUsize,
+    responses_received: AtomicUsize,
+    requests_in_flight: AtomicUsize,
+    bytes_sent: AtomicU64,
+    bytes_received: AtomicU64,
+}
+
+/// Reports a request to the observer and the metrics of its [`Peer`], from when it gets sent
+/// until it ends, which includes getting dropped before its response arrived.
+struct RequestObservation {
+    connection_id: ConnectionId,
+    message_id: u32,
+    message_type: &'static str,
+    started_at: Instant,
+    metrics: Arc<Metrics>,
+    observer: Option<Arc<dyn PeerObserver>>,
+    ended: bool,
+}
+
+impl RequestObservation {
+    fn start(
+        peer: &Peer,
+        connection_id: ConnectionId,
+        message_id: u32,
+        message_type: &'static str,
+    ) -> Self {
+        peer.metrics.requests_sent.fetch_add(1, SeqCst);
+        peer.metrics.requests_in_flight.fetch_add(1, SeqCst);
+        let observer = peer.observer.read().clone();
+        if let Some(observer) = &observer {
+            observer.request_started(connection_id, message_id, message_type);
+        }
+        Self {
+            connection_id,
+            message_id,
+            message_type,
+            started_at: Instant::now(),
+            metrics: peer.metrics.clone(),
+            observer,
+            ended: false,
+        }
+    }
+
+    fn end(mut self, result: Result<(), &anyhow::Error>) {
+        self.finish(result);
+    }
+
+    fn finish(&mut self, result: Result<(), &anyhow::Error>) {
+        self.ended = true;
+        self.metrics.requests_in_flight.fetch_sub(1, SeqCst);
+        if let Some(observer) = &self.observer {
+            observer.request_ended(
+                self.connection_id,
+                self.message_id,
+                self.message_type,
+                self.started_at.elapsed(),
+                result,
+            );
+        }
+    }
+}
+
+impl Drop for RequestObservation {
+    fn drop(&mut self) {
+        if !self.ended {
+            self.finish(Err(&anyhow!("request was dropped")));
+        }
+    }
+}
+
+/// How a connection is kept alive, and how long it can stay silent before it's considered
+/// dead, such as when the other end went away without closing it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Keepalive {
+    /// How long the connection can go without writing anything before a ping gets sent.
+    pub interval: Duration,
+    /// How long the connection can go without reading anything, which includes the answers
+    /// to its pings, before it gets closed and its pending requests fail.
+    pub receive_timeout: Duration,
+}
+
+impl Default for Keepalive {
+    fn default() -> Self {
+        Self {
+            interval: KEEPALIVE_INTERVAL,
+            receive_timeout: RECEIVE_TIMEOUT,
+        }
+    }
 }
 
 #[derive(Clone, Serialize)]
 pub struct ConnectionState {
+    /// Kept in an `Arc`, so that the streams of responses can send messages without keeping
+    /// the connection open.
     #[serde(skip)]
-    outgoing_tx: mpsc::UnboundedSender<proto::Message>,
+    outgoing_tx: Arc<mpsc::UnboundedSender<proto::Message>>,
+    /// Asks for the messages sent before to be flushed, which only makes a difference when
+    /// the connection batches its writes. The sender, if any, is told once they have been.
+    #[serde(skip)]
+    flush_tx: mpsc::UnboundedSender<Option<oneshot::Sender<()>>>,
     next_message_id: Arc<AtomicU32>,
     #[allow(clippy::type_complexity)]
     #[serde(skip)]
@@ -81,20 +254,125 @@ pub struct ConnectionState {
             >,
         >,
     >,
-    #[allow(clippy::type_complexity)]
     #[serde(skip)]
-    stream_response_channels: Arc<
-        Mutex<
-            Option<
-                HashMap<u32, mpsc::UnboundedSender<(Result<proto::Envelope>, oneshot::Sender<()>)>>,
-            >,
-        >,
-    >,
+    stream_response_channels: Arc<StreamResponseChannels>,
+    /// The streaming requests from the other end that it no longer wants responses to, along
+    /// with when they were cancelled.
+    #[serde(skip)]
+    cancelled_streams: Arc<CancelledStreams>,
+    #[serde(skip)]
+    create_timer: CreateTimer,
+    /// Resolves once the connection's IO future has ended.
+    #[serde(skip)]
+    io_ended: Shared<oneshot::Receiver<()>>,
+}
+
+type StreamResponseChannels = Mutex<Option<HashMap<u32, StreamResponseChannel>>>;
+
+type CancelledStreams = Mutex<HashMap<u32, Instant>>;
+
+/// Remembers that the other end cancelled the given streaming request, forgetting about the
+/// cancellations that are older than [`CANCELLED_STREAM_EXPIRY`].
+fn insert_cancelled_stream(cancelled_streams: &CancelledStreams, request_id: u32, now: Instant) {
+    let mut cancelled_streams = cancelled_streams.lock();
+    cancelled_streams.retain(|_, cancelled_at| {
+        now.saturating_duration_since(*cancelled_at) < CANCELLED_STREAM_EXPIRY
+    });
+    cancelled_streams.insert(request_id, now);
+}
+
+/// Where the responses to a streaming request go. Unlike the response to a request, they're
+/// queued without waiting for the requester to pick them up, so that a requester that's slow
+/// to consume them doesn't hold up the other messages on the connection. Once more than
+/// `buffer_size` of them are queued, that gets logged.
+#[derive(Clone)]
+struct StreamResponseChannel {
+    tx: mpsc::UnboundedSender<Result<proto::Envelope>>,
+    queued: Arc<AtomicUsize>,
+    buffer_size: usize,
+}
+
+impl StreamResponseChannel {
+    /// Queues a response, and returns how many are queued, unless the requester went away.
+    fn send(&self, response: Result<proto::Envelope>) -> Option<usize> {
+        let queued = self.queued.fetch_add(1, SeqCst) + 1;
+        self.tx.unbounded_send(response).ok()?;
+        Some(queued)
+    }
+}
+
+type CreateTimer = Arc<dyn Send + Sync + Fn(Duration) -> BoxFuture<'static, ()>>;
+
+/// The responses to a streaming request.
+struct ResponseStream<S> {
+    responses: S,
+    _cancellation: StreamCancellation,
+}
+
+impl<S: Unpin + Stream> Stream for ResponseStream<S> {
+    type Item = S::Item;
+
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut TaskContext,
+    ) -> Poll<Option<S::Item>> {
+        self.responses.poll_next_unpin(cx)
+    }
+}
+
+/// Stops a streaming request on the other end when its responses are dropped before the
+/// stream ended, and forgets about the request, so that the responses that are still on their
+/// way get dropped.
+///
+/// The other end might have ended the stream by the time the cancellation reaches it, in which
+/// case it keeps the cancelled request's id around until [`CANCELLED_STREAM_EXPIRY`] passed.
+struct StreamCancellation {
+    message_id: u32,
+    stream_response_channels: Weak<StreamResponseChannels>,
+    outgoing_tx: Weak<mpsc::UnboundedSender<proto::Message>>,
+    next_message_id: Arc<AtomicU32>,
+}
+
+impl Drop for StreamCancellation {
+    fn drop(&mut self) {
+        let Some(channels) = self.stream_response_channels.upgrade() else {
+            return;
+        };
+        // The stream has already ended if its channel is gone.
+        let channel = channels
+            .lock()
+            .as_mut()
+            .and_then(|channels| channels.remove(&self.message_id));
+        if let Some((_, outgoing_tx)) = channel.zip(self.outgoing_tx.upgrade()) {
+            let message_id = self.next_message_id.fetch_add(1, SeqCst);
+            let cancel = proto::CancelStream {
+                request_id: self.message_id,
+            };
+            outgoing_tx
+                .unbounded_send(proto::Message::Envelope(
+                    cancel.into_envelope(message_id, None, None),
+                ))
+                .ok();
+        }
+    }
+}
+
+impl ConnectionState {
+    /// Message ids wrap around, as they only need to be unique among the requests that
+    /// are still waiting for a response.
+    fn next_message_id(&self) -> u32 {
+        self.next_message_id.fetch_add(1, SeqCst)
+    }
 }
 
 const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(1);
 const WRITE_TIMEOUT: Duration = Duration::from_secs(2);
 pub const RECEIVE_TIMEOUT: Duration = Duration::from_secs(10);
+pub const STREAM_BUFFER_SIZE: usize = 256;
+/// How long the ids of streaming requests that the other end cancelled are kept. Streams are
+/// usually ended right after they're cancelled, which forgets about the cancellation, but a
+/// cancellation that crossed the end of its stream is only dropped once it's this old.
+const CANCELLED_STREAM_EXPIRY: Duration = Duration::from_secs(60);
 
 impl Peer {
     pub fn new(epoch: u32) -> Arc<Self> {
@@ -102,6 +380,10 @@ impl Peer {
             epoch: AtomicU32::new(epoch),
             connections: Default::default(),
             next_connection_id: Default::default(),
+            keepalive: Default::default(),
+            write_batching: Default::default(),
+            observer: Default::default(),
+            metrics: Default::default(),
         })
     }
 
@@ -109,6 +391,45 @@ impl Peer {
         self.epoch.load(SeqCst)
     }
 
+    /// Changes how the connections that get added from now on are kept alive.
+    pub fn set_keepalive(&self, keepalive: Keepalive) {
+        *self.keepalive.lock() = keepalive;
+    }
+
+    /// Changes whether the connections that get added from now on batch their writes, which
+    /// they don't by default.
+    pub fn set_write_batching(&self, write_batching: Option<WriteBatching>) {
+        *self.write_batching.lock() = write_batching;
+    }
+
+    pub fn set_observer(&self, observer: Arc<dyn PeerObserver>) {
+        *self.observer.write() = Some(observer);
+    }
+
+    pub fn metrics(&self) -> PeerMetrics {
+        PeerMetrics {
+            requests_sent: self.metrics.requests_sent.load(SeqCst),
+            responses_received: self.metrics.responses_received.load(SeqCst),
+            requests_in_flight: self.metrics.requests_in_flight.load(SeqCst),
+            bytes_sent: self.metrics.bytes_sent.load(SeqCst),
+            bytes_received: self.metrics.bytes_received.load(SeqCst),
+        }
+    }
+
+    fn message_sent(&self, connection_id: ConnectionId, len: usize) {
+        self.metrics.bytes_sent.fetch_add(len as u64, SeqCst);
+        if let Some(observer) = self.observer.read().as_ref() {
+            observer.message_sent(connection_id, len);
+        }
+    }
+
+    fn message_received(&self, connection_id: ConnectionId, len: usize) {
+        self.metrics.bytes_received.fetch_add(len as u64, SeqCst);
+        if let Some(observer) = self.observer.read().as_ref() {
+            observer.message_received(connection_id, len);
+        }
+    }
+
     #[instrument(skip_all)]
     pub fn add_connection<F, Fut, Out>(
         self: &Arc<Self>,
@@ -120,10 +441,13 @@ impl Peer {
         BoxStream<'static, Box<dyn AnyTypedEnvelope>>,
     )
     where
-        F: Send + Fn(Duration) -> Fut,
-        Fut: Send + Future<Output = Out>,
+        F: 'static + Send + Sync + Fn(Duration) -> Fut,
+        Fut: 'static + Send + Future<Output = Out>,
         Out: Send,
     {
+        let create_timer: CreateTimer =
+            Arc::new(move |duration| create_timer(duration).map(drop).boxed());
+
         // For outgoing messages, use an unbounded channel so that application code
         // can always send messages without yielding. For incoming messages, use a
         // bounded channel so that other peers will receive backpressure if they send
@@ -134,17 +458,28 @@ impl Peer {
         const INCOMING_BUFFER_SIZE: usize = 256;
         let (mut incoming_tx, incoming_rx) = mpsc::channel(INCOMING_BUFFER_SIZE);
         let (outgoing_tx, mut outgoing_rx) = mpsc::unbounded();
+        let (flush_tx, mut flush_rx) = mpsc::unbounded();
+        let (io_ended_tx, io_ended_rx) = oneshot::channel::<()>();
 
         let connection_id = ConnectionId {
             owner_id: self.epoch.load(SeqCst),
             id: self.next_connection_id.fetch_add(1, SeqCst),
         };
         let connection_state = ConnectionState {
-            outgoing_tx,
+            outgoing_tx: Arc::new(outgoing_tx),
+            flush_tx,
             next_message_id: Default::default(),
             response_channels: Arc::new(Mutex::new(Some(Default::default()))),
             stream_response_channels: Arc::new(Mutex::new(Some(Default::default()))),
+            cancelled_streams: Default::default(),
+            create_timer: create_timer.clone(),
+            io_ended: io_ended_rx.shared(),
         };
+        let Keepalive {
+            interval: keepalive_interval,
+            receive_timeout,
+        } = *self.keepalive.lock();
+        let write_batching = *self.write_batching.lock();
         let mut writer = MessageStream::new(connection.tx);
         let mut reader = MessageStream::new(connection.rx);
 
@@ -159,23 +494,28 @@ impl Peer {
                 response_channels.lock().take();
                 if let Some(channels) = stream_response_channels.lock().take() {
                     for channel in channels.values() {
-                        let _ = channel.unbounded_send((
-                            Err(anyhow!("connection closed")),
-                            oneshot::channel().0,
-                        ));
+                        channel.send(Err(connection_closed("connection closed")));
                     }
                 }
                 this.connections.write().remove(&connection_id);
+                drop(io_ended_tx);
                 tracing::trace!(%connection_id, "handle io future: end");
             });
 
             // Send messages on this frequency so the connection isn't closed.
-            let keepalive_timer = create_timer(KEEPALIVE_INTERVAL).fuse();
+            let keepalive_timer = create_timer(keepalive_interval).fuse();
             futures::pin_mut!(keepalive_timer);
 
             // Disconnect if we don't receive messages at least this frequently.
-            let receive_timeout = create_timer(RECEIVE_TIMEOUT).fuse();
-            futures::pin_mut!(receive_timeout);
+            let receive_timer = create_timer(receive_timeout).fuse();
+            futures::pin_mut!(receive_timer);
+
+            // When batching writes, flush the messages at the latest once this goes off.
+            let flush_timer = Fuse::terminated();
+            futures::pin_mut!(flush_timer);
+            let mut unflushed_len = 0;
+            // The flush requests that are waiting for the messages sent before them.
+            let mut flush_requests = Vec::new();
 
             loop {
                 tracing::trace!(%connection_id, "outer loop iteration start");
@@ -184,16 +524,43 @@ impl Peer {
 
                 loop {
                     tracing::trace!(%connection_id, "inner loop iteration start");
+                    // Outgoing messages are written first, so once this gets polled, the
+                    // messages sent before the flush requests have all been written.
+                    let mut flush_requested = if flush_requests.is_empty() {
+                        Fuse::terminated()
+                    } else {
+                        future::ready(()).fuse()
+                    };
                     futures::select_biased! {
                         outgoing = outgoing_rx.next().fuse() => match outgoing {
                             Some(outgoing) => {
                                 tracing::trace!(%connection_id, "outgoing rpc message: writing");
+                                let write = async {
+                                    match write_batching {
+                                        Some(write_batching) => {
+                                            let len = writer.feed(outgoing).await?;
+                                            unflushed_len += len;
+                                            if unflushed_len >= write_batching.max_len {
+                                                writer.flush().await?;
+                                                unflushed_len = 0;
+                                                flush_timer.set(Fuse::terminated());
+                                            } else if flush_timer.is_terminated() {
+                                                flush_timer.set(
+                                                    create_timer(write_batching.delay).fuse(),
+                                                );
+                                            }
+                                            anyhow::Ok(len)
+                                        }
+                                        None => writer.write(outgoing).await,
+                                    }
+                                };
                                 futures::select_biased! {
-                                    result = writer.write(outgoing).fuse() => {
+                                    result = write.fuse() => {
                                         tracing::trace!(%connection_id, "outgoing rpc message: done writing");
-                                        result.context("failed to write RPC message")?;
+                                        let len = result.context("failed to write RPC message")?;
+                                        this.message_sent(connection_id, len);
                                         tracing::trace!(%connection_id, "keepalive interval: resetting after sending message");
-                                        keepalive_timer.set(create_timer(KEEPALIVE_INTERVAL).fuse());
+                                        keepalive_timer.set(create_timer(keepalive_interval).fuse());
                                     }
                                     _ = create_timer(WRITE_TIMEOUT).fuse() => {
                                         tracing::trace!(%connection_id, "outgoing rpc message: writing timed out");
@@ -203,17 +570,65 @@ impl Peer {
                             }
                             None => {
                                 tracing::trace!(%connection_id, "outgoing rpc message: channel closed");
+                                futures::select_biased! {
+                                    result = writer.close().fuse() => {
+                                        tracing::trace!(%connection_id, "outgoing rpc message: done closing");
+                                        result.context("failed to close connection")?;
+                                    }
+                                    _ = create_timer(WRITE_TIMEOUT).fuse() => {
+                                        tracing::trace!(%connection_id, "outgoing rpc message: closing timed out");
+                                        Err(anyhow!("timed out closing connection"))?;
+                                    }
+                                }
                                 return Ok(())
                             },
                         },
+                        flush_request = flush_rx.select_next_some() => {
+                            tracing::trace!(%connection_id, "flush requested");
+                            flush_requests.push(flush_request);
+                        }
+                        _ = flush_requested => {
+                            tracing::trace!(%connection_id, "flush requested: flushing");
+                            futures::select_biased! {
+                                result = writer.flush().fuse() => {
+                                    tracing::trace!(%connection_id, "flush requested: done flushing");
+                                    result.context("failed to flush RPC messages")?;
+                                    unflushed_len = 0;
+                                    flush_timer.set(Fuse::terminated());
+                                    for flushed in flush_requests.drain(..).flatten() {
+                                        flushed.send(()).ok();
+                                    }
+                                }
+                                _ = create_timer(WRITE_TIMEOUT).fuse() => {
+                                    tracing::trace!(%connection_id, "flush requested: flushing timed out");
+                                    Err(anyhow!("timed out flushing messages"))?;
+                                }
+                            }
+                        }
+                        _ = flush_timer => {
+                            tracing::trace!(%connection_id, "flush timer: flushing");
+                            futures::select_biased! {
+                                result = writer.flush().fuse() => {
+                                    tracing::trace!(%connection_id, "flush timer: done flushing");
+                                    result.context("failed to flush RPC messages")?;
+                                    unflushed_len = 0;
+                                }
+                                _ = create_timer(WRITE_TIMEOUT).fuse() => {
+                                    tracing::trace!(%connection_id, "flush timer: flushing timed out");
+                                    Err(anyhow!("timed out flushing messages"))?;
+                                }
+                            }
+                        }
                         _ = keepalive_timer => {
                             tracing::trace!(%connection_id, "keepalive interval: pinging");
                             futures::select_biased! {
                                 result = writer.write(proto::Message::Ping).fuse() => {
                                     tracing::trace!(%connection_id, "keepalive interval: done pinging");
                                     result.context("failed to send keepalive")?;
+                                    unflushed_len = 0;
+                                    flush_timer.set(Fuse::terminated());
                                     tracing::trace!(%connection_id, "keepalive interval: resetting after pinging");
-                                    keepalive_timer.set(create_timer(KEEPALIVE_INTERVAL).fuse());
+                                    keepalive_timer.set(create_timer(keepalive_interval).fuse());
                                 }
                                 _ = create_timer(WRITE_TIMEOUT).fuse() => {
                                     tracing::trace!(%connection_id, "keepalive interval: pinging timed out");
@@ -225,8 +640,9 @@ impl Peer {
                             let incoming = incoming.context("error reading rpc message from socket")?;
                             tracing::trace!(%connection_id, "incoming rpc message: received");
                             tracing::trace!(%connection_id, "receive timeout: resetting");
-                            receive_timeout.set(create_timer(RECEIVE_TIMEOUT).fuse());
-                            if let (proto::Message::Envelope(incoming), received_at) = incoming {
+                            receive_timer.set(create_timer(receive_timeout).fuse());
+                            if let (proto::Message::Envelope(incoming), received_at, len) = incoming {
+                                this.message_received(connection_id, len);
                                 tracing::trace!(%connection_id, "incoming rpc message: processing");
                                 futures::select_biased! {
                                     result = incoming_tx.send((incoming, received_at)).fuse() => match result {
@@ -246,7 +662,7 @@ impl Peer {
                             }
                             break;
                         },
-                        _ = receive_timeout => {
+                        _ = receive_timer => {
                             tracing::trace!(%connection_id, "receive timeout: delay between messages too long");
                             Err(anyhow!("delay between messages too long"))?
                         }
@@ -257,6 +673,7 @@ impl Peer {
 
         let response_channels = connection_state.response_channels.clone();
         let stream_response_channels = connection_state.stream_response_channels.clone();
+        let cancelled_streams = connection_state.cancelled_streams.clone();
         self.connections
             .write()
             .insert(connection_id, connection_state);
@@ -264,6 +681,7 @@ impl Peer {
         let incoming_rx = incoming_rx.filter_map(move |(incoming, received_at)| {
             let response_channels = response_channels.clone();
             let stream_response_channels = stream_response_channels.clone();
+            let cancelled_streams = cancelled_streams.clone();
             async move {
                 let message_id = incoming.id;
                 tracing::trace!(?incoming, "incoming message future: start");
@@ -271,6 +689,17 @@ impl Peer {
                     tracing::trace!(%connection_id, message_id, "incoming message future: end");
                 });
 
+                if let Some(proto::envelope::Payload::CancelStream(cancel)) = &incoming.payload {
+                    tracing::trace!(
+                        %connection_id,
+                        message_id,
+                        request_id = cancel.request_id,
+                        "incoming stream cancellation"
+                    );
+                    insert_cancelled_stream(&cancelled_streams, cancel.request_id, received_at);
+                    return None;
+                }
+
                 if let Some(responding_to) = incoming.responding_to {
                     tracing::trace!(
                         %connection_id,
@@ -311,31 +740,35 @@ impl Peer {
                             responding_to,
                             "incoming response: requester resumed"
                         );
-                    } else if let Some(tx) = stream_response_channel {
-                        let requester_resumed = oneshot::channel();
-                        if let Err(error) = tx.unbounded_send((Ok(incoming), requester_resumed.0)) {
+                    } else if let Some(channel) = stream_response_channel {
+                        if let Some(queued) = channel.send(Ok(incoming)) {
                             tracing::debug!(
                                 %connection_id,
                                 message_id,
-                                responding_to = responding_to,
-                                ?error,
+                                responding_to,
+                                queued,
+                                "incoming stream response: queued"
+                            );
+                            if queued == channel.buffer_size + 1 {
+                                tracing::warn!(
+                                    %connection_id,
+                                    message_id,
+                                    responding_to,
+                                    buffer_size = channel.buffer_size,
+                                    "incoming stream response: requester is falling behind"
+                                );
+                            }
+                        } else {
+                            tracing::debug!(
+                                %connection_id,
+                                message_id,
+                                responding_to,
                                 "incoming stream response: request future dropped",
                             );
+                            if let Some(channels) = stream_response_channels.lock().as_mut() {
+                                channels.remove(&responding_to);
+                            }
                         }
-
-                        tracing::debug!(
-                            %connection_id,
-                            message_id,
-                            responding_to,
-                            "incoming stream response: waiting to resume requester"
-                        );
-                        let _ = requester_resumed.1.await;
-                        tracing::debug!(
-                            %connection_id,
-                            message_id,
-                            responding_to,
-                            "incoming stream response: requester resumed"
-                        );
                     } else {
                         let message_type = proto::build_typed_envelope(
                             connection_id.into(),
@@ -388,6 +821,41 @@ impl Peer {
         self.connections.write().remove(&connection_id);
     }
 
+    /// Resolves once the messages that were sent before have been flushed to the connection,
+    /// which only makes a difference when it batches its writes.
+    pub fn flush(&self, connection_id: ConnectionId) -> impl Future<Output = Result<()>> {
+        let (tx, rx) = oneshot::channel();
+        let send = self.connection_state(connection_id).and_then(|connection| {
+            connection
+                .flush_tx
+                .unbounded_send(Some(tx))
+                .map_err(|_| connection_closed("connection was closed"))
+        });
+        async move {
+            send?;
+            rx.await
+                .map_err(|_| connection_closed("connection was closed"))
+        }
+    }
+
+    /// Disconnects, and resolves once the messages that were already sent have been written,
+    /// and the connection has been closed. The requests that are still waiting for a response
+    /// by then fail, and so do the ones that are made afterwards.
+    ///
+    /// The connection's IO future needs to be running for this to resolve.
+    pub fn close(&self, connection_id: ConnectionId) -> impl Future<Output = ()> {
+        let io_ended = self
+            .connections
+            .write()
+            .remove(&connection_id)
+            .map(|connection| connection.io_ended);
+        async move {
+            if let Some(io_ended) = io_ended {
+                io_ended.await.ok();
+            }
+        }
+    }
+
     #[cfg(any(test, feature = "test-support"))]
     pub fn reset(&self, epoch: u32) {
         self.next_connection_id.store(0, SeqCst);
@@ -408,6 +876,19 @@ impl Peer {
             .map_ok(|envelope| envelope.payload)
     }
 
+    /// Make a request and wait for a response, failing if the response doesn't arrive within
+    /// the given timeout. The request stops waiting for its response then, so a response that
+    /// arrives later gets logged and dropped.
+    pub fn request_with_timeout<T: RequestMessage>(
+        &self,
+        receiver_id: ConnectionId,
+        request: T,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<T::Response>> {
+        self.request_internal_with_timeout(None, receiver_id, request, Some(timeout))
+            .map_ok(|envelope| envelope.payload)
+    }
+
     pub fn request_envelope<T: RequestMessage>(
         &self,
         receiver_id: ConnectionId,
@@ -431,17 +912,26 @@ impl Peer {
         original_sender_id: Option<ConnectionId>,
         receiver_id: ConnectionId,
         request: T,
+    ) -> impl Future<Output = Result<TypedEnvelope<T::Response>>> {
+        self.request_internal_with_timeout(original_sender_id, receiver_id, request, None)
+    }
+
+    fn request_internal_with_timeout<T: RequestMessage>(
+        &self,
+        original_sender_id: Option<ConnectionId>,
+        receiver_id: ConnectionId,
+        request: T,
+        timeout: Option<Duration>,
     ) -> impl Future<Output = Result<TypedEnvelope<T::Response>>> {
         let envelope = request.into_envelope(0, None, original_sender_id.map(Into::into));
-        let response = self.request_dynamic(receiver_id, envelope, T::NAME);
+        let response = self.request_dynamic_internal(receiver_id, envelope, T::NAME, timeout);
         async move {
             let (response, received_at) = response.await?;
             Ok(TypedEnvelope {
                 message_id: response.id,
                 sender_id: receiver_id.into(),
                 original_sender_id: response.original_sender_id,
-                payload: T::Response::from_envelope(response)
-                    .ok_or_else(|| anyhow!("received response of the wrong type"))?,
+                payload: response_from_envelope::<T>(response)?,
                 received_at,
             })
         }
@@ -453,34 +943,85 @@ impl Peer {
     /// response type. This interface is only useful in trait objects, where
     /// generics can't be used. If you have a concrete type, use `request`.
     pub fn request_dynamic(
+        &self,
+        receiver_id: ConnectionId,
+        envelope: proto::Envelope,
+        type_name: &'static str,
+    ) -> impl Future<Output = Result<(proto::Envelope, Instant)>> {
+        self.request_dynamic_internal(receiver_id, envelope, type_name, None)
+    }
+
+    fn request_dynamic_internal(
         &self,
         receiver_id: ConnectionId,
         mut envelope: proto::Envelope,
         type_name: &'static str,
+        timeout: Option<Duration>,
     ) -> impl Future<Output = Result<(proto::Envelope, Instant)>> {
         let (tx, rx) = oneshot::channel();
-        let send = self.connection_state(receiver_id).and_then(|connection| {
-            envelope.id = connection.next_message_id.fetch_add(1, SeqCst);
-            connection
+        let original_sender_id = envelope.original_sender_id.map(ConnectionId::from);
+        let connection = self.receiver_connection_state(receiver_id, original_sender_id);
+        let send = connection.and_then(|connection| {
+            envelope.id = connection.next_message_id();
+            let message_id = envelope.id;
+            let previous_request = connection
                 .response_channels
                 .lock()
                 .as_mut()
-                .ok_or_else(|| anyhow!("connection was closed"))?
+                .ok_or_else(|| connection_closed("connection was closed"))?
                 .insert(envelope.id, tx);
+            debug_assert!(
+                previous_request.is_none(),
+                "message id {} is still awaiting a response",
+                envelope.id
+            );
             connection
                 .outgoing_tx
                 .unbounded_send(proto::Message::Envelope(envelope))
-                .map_err(|_| anyhow!("connection was closed"))?;
-            Ok(())
+                .map_err(|_| connection_closed("connection was closed"))?;
+            // Don't let batching delay the request.
+            connection.flush_tx.unbounded_send(None).ok();
+            // The deadline starts when the request gets sent.
+            let timeout = timeout.map(|timeout| (timeout, (connection.create_timer)(timeout)));
+            let observation = RequestObservation::start(self, receiver_id, message_id, type_name);
+            Ok((
+                message_id,
+                Arc::downgrade(&connection.response_channels),
+                timeout,
+                observation,
+            ))
         });
         async move {
-            send?;
-            let (response, received_at, _barrier) =
-                rx.await.map_err(|_| anyhow!("connection was closed"))?;
-            if let Some(proto::envelope::Payload::Error(error)) = &response.payload {
-                return Err(RpcError::from_proto(&error, type_name));
+            let (message_id, response_channels, timeout, observation) = send?;
+            let metrics = observation.metrics.clone();
+            let result = async move {
+                let response = match timeout {
+                    Some((timeout, timer)) => match futures::future::select(rx, timer).await {
+                        Either::Left((response, _)) => response,
+                        Either::Right(_) => {
+                            // Forget the request, so that its response is treated like the one of
+                            // an unknown request if it still arrives.
+                            if let Some(channels) = response_channels.upgrade() {
+                                if let Some(channels) = channels.lock().as_mut() {
+                                    channels.remove(&message_id);
+                                }
+                            }
+                            return Err(anyhow!("{type_name} request timed out after {timeout:?}"));
+                        }
+                    },
+                    None => rx.await,
+                };
+                let (response, received_at, _barrier) =
+                    response.map_err(|_| connection_closed("connection was closed"))?;
+                metrics.responses_received.fetch_add(1, SeqCst);
+                if let Some(proto::envelope::Payload::Error(error)) = &response.payload {
+                    return Err(RpcError::from_proto(&error, type_name));
+                }
+                Ok((response, received_at))
             }
-            Ok((response, received_at))
+            .await;
+            observation.end(result.as_ref().map(drop));
+            result
         }
     }
 
@@ -488,39 +1029,97 @@ impl Peer {
         &self,
         receiver_id: ConnectionId,
         request: T,
+    ) -> impl Future<Output = Result<impl Unpin + Stream<Item = Result<T::Response>>>> {
+        self.request_stream_internal(None, receiver_id, request, STREAM_BUFFER_SIZE)
+    }
+
+    /// Make a request, and stream the responses, logging when more than `buffer_size` of
+    /// them are waiting to be consumed.
+    pub fn request_stream_with_buffer_size<T: RequestMessage>(
+        &self,
+        receiver_id: ConnectionId,
+        request: T,
+        buffer_size: usize,
+    ) -> impl Future<Output = Result<impl Unpin + Stream<Item = Result<T::Response>>>> {
+        self.request_stream_internal(None, receiver_id, request, buffer_size)
+    }
+
+    /// Make a request on behalf of another peer, and stream the responses to it.
+    pub fn forward_request_stream<T: RequestMessage>(
+        &self,
+        sender_id: ConnectionId,
+        receiver_id: ConnectionId,
+        request: T,
+    ) -> impl Future<Output = Result<impl Unpin + Stream<Item = Result<T::Response>>>> {
+        self.request_stream_internal(Some(sender_id), receiver_id, request, STREAM_BUFFER_SIZE)
+    }
+
+    fn request_stream_internal<T: RequestMessage>(
+        &self,
+        original_sender_id: Option<ConnectionId>,
+        receiver_id: ConnectionId,
+        request: T,
+        buffer_size: usize,
     ) -> impl Future<Output = Result<impl Unpin + Stream<Item = Result<T::Response>>>> {
         let (tx, rx) = mpsc::unbounded();
-        let send = self.connection_state(receiver_id).and_then(|connection| {
-            let message_id = connection.next_message_id.fetch_add(1, SeqCst);
+        let queued = Arc::new(AtomicUsize::new(0));
+        let channel = StreamResponseChannel {
+            tx,
+            queued: queued.clone(),
+            buffer_size,
+        };
+        let observer = self.observer.read().clone();
+        let connection = self.receiver_connection_state(receiver_id, original_sender_id);
+        let send = connection.and_then(|connection| {
+            let message_id = connection.next_message_id();
             let stream_response_channels = connection.stream_response_channels.clone();
-            stream_response_channels
+            let previous_request = stream_response_channels
                 .lock()
                 .as_mut()
-                .ok_or_else(|| anyhow!("connection was closed"))?
-                .insert(message_id, tx);
+                .ok_or_else(|| connection_closed("connection was closed"))?
+                .insert(message_id, channel);
+            debug_assert!(
+                previous_request.is_none(),
+                "message id {message_id} is still awaiting a response"
+            );
             connection
                 .outgoing_tx
-                .unbounded_send(proto::Message::Envelope(
-                    request.into_envelope(message_id, None, None),
-                ))
-                .map_err(|_| anyhow!("connection was closed"))?;
-            Ok((message_id, stream_response_channels))
+                .unbounded_send(proto::Message::Envelope(request.into_envelope(
+                    message_id,
+                    None,
+                    original_sender_id.map(Into::into),
+                )))
+                .map_err(|_| connection_closed("connection was closed"))?;
+            connection.flush_tx.unbounded_send(None).ok();
+            Ok(StreamCancellation {
+                message_id,
+                stream_response_channels: Arc::downgrade(&stream_response_channels),
+                outgoing_tx: Arc::downgrade(&connection.outgoing_tx),
+                next_message_id: connection.next_message_id.clone(),
+            })
         });
 
         async move {
-            let (message_id, stream_response_channels) = send?;
-            let stream_response_channels = Arc::downgrade(&stream_response_channels);
-
-            Ok(rx.filter_map(move |(response, _barrier)| {
+            let cancellation = send?;
+            let message_id = cancellation.message_id;
+            let stream_response_channels = cancellation.stream_response_channels.clone();
+
+            let responses = rx.filter_map(move |response| {
+                queued.fetch_sub(1, SeqCst);
+                if let Some(observer) = observer.as_ref().filter(|_| response.is_ok()) {
+                    observer.stream_response_received(receiver_id, message_id, T::NAME);
+                }
                 let stream_response_channels = stream_response_channels.clone();
                 future::ready(match response {
                     Ok(response) => {
                         if let Some(proto::envelope::Payload::Error(error)) = &response.payload {
-                            Some(Err(anyhow!(
-                                "RPC request {} failed - {}",
-                                T::NAME,
-                                error.message
-                            )))
+                            // An error ends the stream.
+                            if let Some(channels) = stream_response_channels.upgrade() {
+                                if let Some(channels) = channels.lock().as_mut() {
+                                    channels.remove(&message_id);
+                                }
+                            }
+                            Some(Err(RpcError::from_proto(error, T::NAME)))
                         } else if let Some(proto::envelope::Payload::EndStream(_)) =
                             &response.payload
                         {
@@ -532,23 +1131,22 @@ impl Peer {
                             }
                             None
                         } else {
-                            Some(
-                                T::Response::from_envelope(response)
-                                    .ok_or_else(|| anyhow!("received response of the wrong type")),
-                            )
+                            Some(response_from_envelope::<T>(response))
                         }
                     }
                     Err(error) => Some(Err(error)),
                 })
-            }))
+            });
+            Ok(ResponseStream {
+                responses,
+                _cancellation: cancellation,
+            })
         }
     }
 
     pub fn send<T: EnvelopedMessage>(&self, receiver_id: ConnectionId, message: T) -> Result<()> {
         let connection = self.connection_state(receiver_id)?;
-        let message_id = connection
-            .next_message_id
-            .fetch_add(1, atomic::Ordering::SeqCst);
+        let message_id = connection.next_message_id();
         connection
             .outgoing_tx
             .unbounded_send(proto::Message::Envelope(
@@ -563,10 +1161,8 @@ impl Peer {
         receiver_id: ConnectionId,
         message: T,
     ) -> Result<()> {
-        let connection = self.connection_state(receiver_id)?;
-        let message_id = connection
-            .next_message_id
-            .fetch_add(1, atomic::Ordering::SeqCst);
+        let connection = self.receiver_connection_state(receiver_id, Some(sender_id))?;
+        let message_id = connection.next_message_id();
         connection
             .outgoing_tx
             .unbounded_send(proto::Message::Envelope(message.into_envelope(
@@ -583,9 +1179,14 @@ impl Peer {
         response: T::Response,
     ) -> Result<()> {
         let connection = self.connection_state(receipt.sender_id.into())?;
-        let message_id = connection
-            .next_message_id
-            .fetch_add(1, atomic::Ordering::SeqCst);
+        if connection
+            .cancelled_streams
+            .lock()
+            .contains_key(&receipt.message_id)
+        {
+            return Err(anyhow!("stream was cancelled"));
+        }
+        let message_id = connection.next_message_id();
         connection
             .outgoing_tx
             .unbounded_send(proto::Message::Envelope(response.into_envelope(
@@ -598,9 +1199,15 @@ impl Peer {
 
     pub fn end_stream<T: RequestMessage>(&self, receipt: Receipt<T>) -> Result<()> {
         let connection = self.connection_state(receipt.sender_id.into())?;
-        let message_id = connection
-            .next_message_id
-            .fetch_add(1, atomic::Ordering::SeqCst);
+        if connection
+            .cancelled_streams
+            .lock()
+            .remove(&receipt.message_id)
+            .is_some()
+        {
+            return Ok(());
+        }
+        let message_id = connection.next_message_id();
 
         let message = proto::EndStream {};
 
@@ -620,9 +1227,15 @@ impl Peer {
         response: proto::Error,
     ) -> Result<()> {
         let connection = self.connection_state(receipt.sender_id.into())?;
-        let message_id = connection
-            .next_message_id
-            .fetch_add(1, atomic::Ordering::SeqCst);
+        if connection
+            .cancelled_streams
+            .lock()
+            .remove(&receipt.message_id)
+            .is_some()
+        {
+            return Ok(());
+        }
+        let message_id = connection.next_message_id();
         connection
             .outgoing_tx
             .unbounded_send(proto::Message::Envelope(response.into_envelope(
@@ -644,9 +1257,7 @@ impl Peer {
                 envelope.payload_type_name()
             ))
             .to_proto();
-        let message_id = connection
-            .next_message_id
-            .fetch_add(1, atomic::Ordering::SeqCst);
+        let message_id = connection.next_message_id();
         connection
             .outgoing_tx
             .unbounded_send(proto::Message::Envelope(response.into_envelope(
@@ -661,14 +1272,52 @@ impl Peer {
         let connections = self.connections.read();
         let connection = connections
             .get(&connection_id)
-            .ok_or_else(|| anyhow!("no such connection: {}", connection_id))?;
+            .ok_or_else(|| connection_closed(&format!("no such connection: {}", connection_id)))?;
+        Ok(connection.clone())
+    }
+
+    /// Returns the state of the connection that a message gets sent over. Its absence only
+    /// counts as a disconnection for the messages of this peer's own. For those forwarded on
+    /// behalf of `original_sender_id`, it's the receiver that's gone rather than the sender's
+    /// connection, so the sender mustn't take it as having lost its own.
+    fn receiver_connection_state(
+        &self,
+        receiver_id: ConnectionId,
+        original_sender_id: Option<ConnectionId>,
+    ) -> Result<ConnectionState> {
+        if original_sender_id.is_none() {
+            return self.connection_state(receiver_id);
+        }
+        let connections = self.connections.read();
+        let connection = connections
+            .get(&receiver_id)
+            .ok_or_else(|| anyhow!("no such connection: {}", receiver_id))?;
         Ok(connection.clone())
     }
 }
 
-impl Serialize for Peer {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
+fn response_from_envelope<T: RequestMessage>(envelope: proto::Envelope) -> Result<T::Response> {
+    let type_name = proto::envelope_payload_type_name(&envelope);
+    T::Response::from_envelope(envelope).ok_or_else(|| {
+        anyhow!(
+            "received response of the wrong type to {} request, expected {} but got {}",
+            T::NAME,
+            T::Response::NAME,
+            type_name.unwrap_or("nothing")
+        )
+    })
+}
+
+/// The error of the requests that were waiting on a connection when it got lost, as well as
+/// the ones that are sent after it's gone. It's tagged as a disconnection, so that callers can
+/// tell it apart from the request failing, and retry once the connection is re-established.
+fn connection_closed(message: &str) -> anyhow::Error {
+    ErrorCode::Disconnected.message(message.into()).anyhow()
+}
+
+impl Serialize for Peer {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
         S: serde::Serializer,
     {
         let mut state = serializer.serialize_struct("Peer", 2)?;
@@ -680,8 +1329,11 @@ impl Serialize for Peer {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test::FakeServer;
     use async_tungstenite::tungstenite::Message as WebSocketMessage;
+    use futures::TryStreamExt as _;
     use gpui::TestAppContext;
+    use rand::Rng as _;
 
     fn init_logger() {
         if std::env::var("RUST_LOG").is_ok() {
@@ -787,6 +1439,259 @@ mod tests {
         }
     }
 
+    #[gpui::test(iterations = 10)]
+    async fn test_message_ids_wrapping_around(cx: &mut TestAppContext) {
+        let executor = cx.executor();
+        let client = Peer::new(0);
+        let (connection_id, _incoming, server) = FakeServer::connect(&client, executor.clone());
+
+        client.connections.read()[&connection_id]
+            .next_message_id
+            .store(u32::MAX - 1, SeqCst);
+
+        // Responses still reach the right requests once the ids wrap around.
+        let requests = (0..4)
+            .map(|id| client.request(connection_id, proto::Test { id }))
+            .collect::<Vec<_>>();
+        for _ in 0..4 {
+            let request = server.receive::<proto::Test>().await.unwrap();
+            server.respond(request.receipt(), request.payload.clone());
+        }
+        for (id, request) in requests.into_iter().enumerate() {
+            assert_eq!(request.await.unwrap(), proto::Test { id: id as u64 });
+        }
+    }
+
+    #[gpui::test(iterations = 50)]
+    async fn test_concurrent_requests_answered_out_of_order(cx: &mut TestAppContext) {
+        let executor = cx.executor();
+        let client = Peer::new(0);
+        let (connection_id, _incoming, server) = FakeServer::connect(&client, executor.clone());
+
+        let requests = (1..=2)
+            .map(|id| {
+                let client = client.clone();
+                executor
+                    .spawn(async move { client.request(connection_id, proto::Test { id }).await })
+            })
+            .collect::<Vec<_>>();
+
+        // Answer both requests once they've both arrived, the last one first.
+        let mut received = Vec::new();
+        for _ in 0..2 {
+            received.push(server.receive::<proto::Test>().await.unwrap());
+        }
+        for request in received.into_iter().rev() {
+            server.respond(request.receipt(), request.payload.clone());
+        }
+
+        for (id, request) in (1..=2).zip(requests) {
+            assert_eq!(request.await.unwrap(), proto::Test { id });
+        }
+    }
+
+    #[gpui::test]
+    async fn test_fake_server(cx: &mut TestAppContext) {
+        let executor = cx.executor();
+        let client = Peer::new(0);
+        let (connection_id, mut incoming, server) = FakeServer::connect(&client, executor.clone());
+
+        // Messages that aren't responses arrive on the incoming stream.
+        server.send(proto::Test { id: 1 });
+        let message = incoming
+            .next()
+            .await
+            .unwrap()
+            .into_any()
+            .downcast::<TypedEnvelope<proto::Test>>()
+            .unwrap();
+        assert_eq!(message.payload, proto::Test { id: 1 });
+
+        let response = client.request(connection_id, proto::Test { id: 2 });
+        let request = server.receive::<proto::Test>().await.unwrap();
+        server.respond_with_error(
+            request.receipt(),
+            ErrorCode::NoSuchProject
+                .message("no such project".into())
+                .to_proto(),
+        );
+        assert_eq!(
+            response.await.unwrap_err().error_code(),
+            ErrorCode::NoSuchProject
+        );
+
+        // Requests that are pending when the server hangs up fail as disconnected.
+        let response = client.request(connection_id, proto::Test { id: 3 });
+        server.receive::<proto::Test>().await.unwrap();
+        server.disconnect();
+        assert_eq!(
+            response.await.unwrap_err().error_code(),
+            ErrorCode::Disconnected
+        );
+        assert!(incoming.next().await.is_none());
+    }
+
+    #[gpui::test]
+    async fn test_fake_server_per_client(cx: &mut TestAppContext) {
+        let executor = cx.executor();
+        let client1 = Peer::new(0);
+        let client2 = Peer::new(0);
+        let (client1_conn_id, _client1_incoming, server1) =
+            FakeServer::connect(&client1, executor.clone());
+        let (client2_conn_id, _client2_incoming, server2) =
+            FakeServer::connect(&client2, executor.clone());
+
+        // Each server only sees the requests of the client connected to it.
+        let response1 = client1.request(client1_conn_id, proto::Test { id: 1 });
+        let response2 = client2.request(client2_conn_id, proto::Test { id: 2 });
+        let request2 = server2.receive::<proto::Test>().await.unwrap();
+        let request1 = server1.receive::<proto::Test>().await.unwrap();
+        assert_eq!(request1.payload, proto::Test { id: 1 });
+        assert_eq!(request2.payload, proto::Test { id: 2 });
+        server2.respond(request2.receipt(), request2.payload.clone());
+        server1.respond(request1.receipt(), request1.payload.clone());
+        assert_eq!(response1.await.unwrap(), proto::Test { id: 1 });
+        assert_eq!(response2.await.unwrap(), proto::Test { id: 2 });
+    }
+
+    #[gpui::test]
+    async fn test_fake_server_disconnect(cx: &mut TestAppContext) {
+        let executor = cx.executor();
+        let client = Peer::new(0);
+        let (connection_id, mut incoming, server) = FakeServer::connect(&client, executor.clone());
+
+        // Once the client hangs up, the server's pending receive fails.
+        let receive = server.receive::<proto::Ping>();
+        client.disconnect(connection_id);
+        assert_eq!(receive.await.unwrap_err().to_string(), "other half hung up");
+        assert!(incoming.next().await.is_none());
+
+        // Receiving after the server hung up is an error too.
+        server.disconnect();
+        assert!(server.receive::<proto::Ping>().await.is_err());
+    }
+
+    #[gpui::test(iterations = 10)]
+    async fn test_observer(cx: &mut TestAppContext) {
+        #[derive(Debug, PartialEq)]
+        enum Event {
+            RequestStarted(u32, &'static str),
+            RequestEnded(u32, &'static str, bool),
+            MessageSent(usize),
+            MessageReceived(usize),
+        }
+
+        #[derive(Default)]
+        struct TestObserver {
+            events: Mutex<Vec<Event>>,
+            durations: Mutex<Vec<Duration>>,
+        }
+
+        impl PeerObserver for TestObserver {
+            fn request_started(
+                &self,
+                _: ConnectionId,
+                message_id: u32,
+                message_type: &'static str,
+            ) {
+                self.events
+                    .lock()
+                    .push(Event::RequestStarted(message_id, message_type));
+            }
+
+            fn request_ended(
+                &self,
+                _: ConnectionId,
+                message_id: u32,
+                message_type: &'static str,
+                duration: Duration,
+                result: Result<(), &anyhow::Error>,
+            ) {
+                self.events.lock().push(Event::RequestEnded(
+                    message_id,
+                    message_type,
+                    result.is_ok(),
+                ));
+                self.durations.lock().push(duration);
+            }
+
+            fn message_sent(&self, _: ConnectionId, len: usize) {
+                self.events.lock().push(Event::MessageSent(len));
+            }
+
+            fn message_received(&self, _: ConnectionId, len: usize) {
+                self.events.lock().push(Event::MessageReceived(len));
+            }
+        }
+
+        let executor = cx.executor();
+        let (client_conn, server_conn, _kill) = Connection::in_memory(executor.clone());
+        let mut server_reader = MessageStream::new(server_conn.rx);
+        let mut server_writer = MessageStream::new(server_conn.tx);
+
+        let client = Peer::new(0);
+        let observer = Arc::new(TestObserver::default());
+        client.set_observer(observer.clone());
+        let (connection_id, io_handler, mut incoming) =
+            client.add_test_connection(client_conn, executor.clone());
+        executor.spawn(io_handler).detach();
+        executor
+            .spawn(async move { incoming.next().await })
+            .detach();
+
+        let started_at = Instant::now();
+        let response = executor.spawn(client.request(connection_id, proto::Ping {}));
+        let (request, _, request_len) = server_reader.read().await.unwrap();
+        let proto::Message::Envelope(request) = request else {
+            panic!("expected an envelope, got {request:?}");
+        };
+        let response_len = server_writer
+            .write(proto::Message::Envelope(proto::Ack {}.into_envelope(
+                0,
+                Some(request.id),
+                None,
+            )))
+            .await
+            .unwrap();
+        response.await.unwrap();
+
+        assert_eq!(
+            *observer.events.lock(),
+            [
+                Event::RequestStarted(request.id, "Ping"),
+                Event::MessageSent(request_len),
+                Event::MessageReceived(response_len),
+                Event::RequestEnded(request.id, "Ping", true),
+            ]
+        );
+        assert!(observer.durations.lock()[0] <= started_at.elapsed());
+        assert_eq!(
+            client.metrics(),
+            PeerMetrics {
+                requests_sent: 1,
+                responses_received: 1,
+                requests_in_flight: 0,
+                bytes_sent: request_len as u64,
+                bytes_received: response_len as u64,
+            }
+        );
+
+        // A request that gets dropped before its response arrives still ends.
+        observer.events.lock().clear();
+        let request = client.request(connection_id, proto::Ping {});
+        assert_eq!(client.metrics().requests_in_flight, 1);
+        drop(request);
+        assert_eq!(client.metrics().requests_in_flight, 0);
+        let events = observer.events.lock();
+        assert!(matches!(
+            events.as_slice(),
+            [
+                Event::RequestStarted(message_id, "Ping"),
+                Event::RequestEnded(ended_message_id, "Ping", false),
+            ] if message_id == ended_message_id
+        ));
+    }
+
     #[gpui::test(iterations = 50)]
     async fn test_order_of_response_and_incoming(cx: &mut TestAppContext) {
         let executor = cx.executor();
@@ -983,7 +1888,9 @@ mod tests {
             .detach();
 
         // Allow the request to make some progress before dropping it.
-        cx.executor().simulate_random_delay().await;
+        for _ in 0..cx.executor().rng().gen_range(0..10) {
+            cx.step();
+        }
         drop(request1_task);
 
         request2_task.await;
@@ -997,6 +1904,128 @@ mod tests {
         );
     }
 
+    #[gpui::test]
+    async fn test_write_batching(cx: &mut TestAppContext) {
+        /// Only passes on what's written to it once it gets flushed, counting how many times
+        /// that happened.
+        struct FlushCountingSink {
+            tx: mpsc::UnboundedSender<WebSocketMessage>,
+            buffer: Vec<WebSocketMessage>,
+            flushes: Arc<AtomicUsize>,
+        }
+
+        impl futures::Sink<WebSocketMessage> for FlushCountingSink {
+            type Error = anyhow::Error;
+
+            fn poll_ready(self: std::pin::Pin<&mut Self>, _: &mut TaskContext) -> Poll<Result<()>> {
+                Poll::Ready(Ok(()))
+            }
+
+            fn start_send(self: std::pin::Pin<&mut Self>, message: WebSocketMessage) -> Result<()> {
+                self.get_mut().buffer.push(message);
+                Ok(())
+            }
+
+            fn poll_flush(self: std::pin::Pin<&mut Self>, _: &mut TaskContext) -> Poll<Result<()>> {
+                let this = self.get_mut();
+                if !this.buffer.is_empty() {
+                    this.flushes.fetch_add(1, SeqCst);
+                    for message in this.buffer.drain(..) {
+                        this.tx.unbounded_send(message).ok();
+                    }
+                }
+                Poll::Ready(Ok(()))
+            }
+
+            fn poll_close(
+                self: std::pin::Pin<&mut Self>,
+                cx: &mut TaskContext,
+            ) -> Poll<Result<()>> {
+                self.poll_flush(cx)
+            }
+        }
+
+        let executor = cx.executor();
+        let (tx, rx) = mpsc::unbounded();
+        let flushes = Arc::new(AtomicUsize::new(0));
+        let connection = Connection {
+            tx: Box::new(FlushCountingSink {
+                tx,
+                buffer: Vec::new(),
+                flushes: flushes.clone(),
+            }),
+            rx: Box::new(futures::stream::pending()),
+        };
+        let mut server_reader = MessageStream::new(rx.map(anyhow::Ok));
+        let mut read_envelope = || {
+            let (message, _, _) = server_reader.read().now_or_never().unwrap().unwrap();
+            let proto::Message::Envelope(envelope) = message else {
+                panic!("expected an envelope, got {message:?}");
+            };
+            envelope
+        };
+
+        let client = Peer::new(0);
+        client.set_write_batching(Some(WriteBatching {
+            max_len: 8 * 1024,
+            delay: Duration::from_millis(5),
+        }));
+        let (connection_id, io_handler, mut incoming) =
+            client.add_test_connection(connection, executor.clone());
+        executor.spawn(io_handler).detach();
+        executor
+            .spawn(async move { incoming.next().await })
+            .detach();
+
+        // Messages sent in quick succession get flushed together once the delay is over.
+        for id in 0..100 {
+            client.send(connection_id, proto::Test { id }).unwrap();
+        }
+        executor.run_until_parked();
+        assert_eq!(flushes.load(SeqCst), 0);
+        executor.advance_clock(Duration::from_millis(5));
+        assert_eq!(flushes.load(SeqCst), 1);
+        for id in 0..100 {
+            assert_eq!(
+                proto::Test::from_envelope(read_envelope()),
+                Some(proto::Test { id })
+            );
+        }
+
+        // A request gets flushed right away, along with the messages before it.
+        client.send(connection_id, proto::Test { id: 100 }).unwrap();
+        let _response = client.request(connection_id, proto::Ping {});
+        executor.run_until_parked();
+        assert_eq!(flushes.load(SeqCst), 2);
+        assert_eq!(
+            proto::Test::from_envelope(read_envelope()),
+            Some(proto::Test { id: 100 })
+        );
+        assert!(proto::Ping::from_envelope(read_envelope()).is_some());
+
+        // So do the messages before an explicit flush.
+        client.send(connection_id, proto::Test { id: 101 }).unwrap();
+        client.flush(connection_id).await.unwrap();
+        assert_eq!(flushes.load(SeqCst), 3);
+        assert_eq!(
+            proto::Test::from_envelope(read_envelope()),
+            Some(proto::Test { id: 101 })
+        );
+
+        // Flushing cancels the pending delayed flush, so the next message waits for a delay
+        // of its own.
+        executor.advance_clock(Duration::from_millis(4));
+        client.send(connection_id, proto::Test { id: 102 }).unwrap();
+        executor.advance_clock(Duration::from_millis(1));
+        assert_eq!(flushes.load(SeqCst), 3);
+        executor.advance_clock(Duration::from_millis(4));
+        assert_eq!(flushes.load(SeqCst), 4);
+        assert_eq!(
+            proto::Test::from_envelope(read_envelope()),
+            Some(proto::Test { id: 102 })
+        );
+    }
+
     #[gpui::test(iterations = 50)]
     async fn test_disconnect(cx: &mut TestAppContext) {
         let executor = cx.executor();
@@ -1025,14 +2054,114 @@ mod tests {
 
         client.disconnect(connection_id);
 
-        let _ = io_ended_rx.await;
-        let _ = messages_ended_rx.await;
+        assert_eq!(cx.run_until(io_ended_rx), Some(Ok(())));
+        assert_eq!(cx.run_until(messages_ended_rx), Some(Ok(())));
         assert!(server_conn
             .send(WebSocketMessage::Binary(vec![]))
             .await
             .is_err());
     }
 
+    #[gpui::test(iterations = 50)]
+    async fn test_close(cx: &mut TestAppContext) {
+        let executor = cx.executor();
+        let (client_conn, mut server_conn, _kill) = Connection::in_memory(executor.clone());
+
+        let client = Peer::new(0);
+        let (connection_id, io_handler, mut incoming) =
+            client.add_test_connection(client_conn, executor.clone());
+        executor.spawn(io_handler).detach();
+        executor
+            .spawn(async move { incoming.next().await })
+            .detach();
+
+        let response = executor.spawn(client.request(connection_id, proto::Ping {}));
+        let close = client.close(connection_id);
+        let error = client
+            .request(connection_id, proto::Ping {})
+            .await
+            .unwrap_err();
+        assert_eq!(error.error_code(), ErrorCode::Disconnected);
+        assert_eq!(cx.run_until(close), Some(()));
+
+        // The request that was made before closing got written before the connection closed,
+        // and fails as it never got answered.
+        assert!(matches!(
+            cx.run_until(server_conn.rx.next()),
+            Some(Some(Ok(WebSocketMessage::Binary(_))))
+        ));
+        assert!(matches!(cx.run_until(server_conn.rx.next()), Some(None)));
+        let error = cx
+            .run_until(response)
+            .expect("request never completed")
+            .unwrap_err();
+        assert_eq!(error.error_code(), ErrorCode::Disconnected);
+    }
+
+    #[test]
+    fn test_request_over_websocket() {
+        smol::block_on(async {
+            // Unlike the in-memory connections, this goes through the framing of actual
+            // websockets, over a socket.
+            let listener = smol::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let address = listener.local_addr().unwrap();
+            let accept = smol::spawn(async move {
+                let (stream, _) = listener.accept().await.unwrap();
+                async_tungstenite::accept_async(stream).await.unwrap()
+            });
+            let stream = smol::net::TcpStream::connect(address).await.unwrap();
+            let (client_stream, _) =
+                async_tungstenite::client_async(format!("ws://{address}/rpc"), stream)
+                    .await
+                    .unwrap();
+            let server_stream = accept.await;
+
+            let client = Peer::new(0);
+            let server = Peer::new(0);
+            let (client_conn_id, io_task1, mut client_incoming) =
+                client.add_connection(websocket_connection(client_stream), smol::Timer::after);
+            let (_, io_task2, mut server_incoming) =
+                server.add_connection(websocket_connection(server_stream), smol::Timer::after);
+            smol::spawn(io_task1).detach();
+            smol::spawn(io_task2).detach();
+            smol::spawn(async move { client_incoming.next().await }).detach();
+            smol::spawn({
+                let server = server.clone();
+                async move {
+                    while let Some(envelope) = server_incoming.next().await {
+                        let envelope = envelope
+                            .into_any()
+                            .downcast::<TypedEnvelope<proto::Test>>()
+                            .unwrap();
+                        server
+                            .respond(envelope.receipt(), envelope.payload.clone())
+                            .unwrap();
+                    }
+                }
+            })
+            .detach();
+
+            for id in 0..3 {
+                let response = client
+                    .request(client_conn_id, proto::Test { id })
+                    .await
+                    .unwrap();
+                assert_eq!(response, proto::Test { id });
+            }
+        });
+
+        fn websocket_connection<S>(stream: async_tungstenite::WebSocketStream<S>) -> Connection
+        where
+            S: 'static + Send + Unpin + futures::AsyncRead + futures::AsyncWrite,
+        {
+            Connection::new(
+                stream
+                    .map_err(|error| anyhow!(error))
+                    .sink_map_err(|error| anyhow!(error)),
+            )
+        }
+    }
+
     #[gpui::test(iterations = 50)]
     async fn test_io_error(cx: &mut TestAppContext) {
         let executor = cx.executor();
@@ -1047,12 +2176,410 @@ mod tests {
             .detach();
 
         let response = executor.spawn(client.request(connection_id, proto::Ping {}));
-        let _request = server_conn.rx.next().await.unwrap().unwrap();
+        let _request = cx
+            .run_until(server_conn.rx.next())
+            .flatten()
+            .unwrap()
+            .unwrap();
+
+        drop(server_conn);
+        let response = cx.run_until(response).expect("request never completed");
+        let error = response.unwrap_err();
+        assert_eq!(error.to_string(), "connection was closed");
+        assert_eq!(error.error_code(), ErrorCode::Disconnected);
+
+        // Requests that are sent after the connection is gone fail right away, the same way.
+        executor.run_until_parked();
+        let error = client
+            .request(connection_id, proto::Ping {})
+            .now_or_never()
+            .expect("request should fail right away")
+            .unwrap_err();
+        assert_eq!(error.error_code(), ErrorCode::Disconnected);
+    }
+
+    #[gpui::test]
+    async fn test_forwarding_to_missing_connection(cx: &mut TestAppContext) {
+        let executor = cx.executor();
+        let (client_conn, _server_conn, _kill) = Connection::in_memory(executor.clone());
+        let server = Peer::new(0);
+        let (sender_id, io_handler, _incoming) =
+            server.add_test_connection(client_conn, executor.clone());
+        executor.spawn(io_handler).detach();
+        let receiver_id = ConnectionId {
+            owner_id: 0,
+            id: sender_id.id + 1,
+        };
+
+        // It's the receiver that's gone, not the connection of the peer the request came from.
+        let error = server
+            .forward_request(sender_id, receiver_id, proto::Ping {})
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!("no such connection: {receiver_id}")
+        );
+        assert_ne!(error.error_code(), ErrorCode::Disconnected);
+        let error = server
+            .forward_send(sender_id, receiver_id, proto::Ping {})
+            .unwrap_err();
+        assert_ne!(error.error_code(), ErrorCode::Disconnected);
+
+        // Requests of the server's own still count as disconnected.
+        let error = server
+            .request(receiver_id, proto::Ping {})
+            .await
+            .unwrap_err();
+        assert_eq!(error.error_code(), ErrorCode::Disconnected);
+    }
+
+    #[gpui::test(iterations = 50)]
+    async fn test_receive_timeout(cx: &mut TestAppContext) {
+        let executor = cx.executor();
+        let (client_conn, mut server_conn, _kill) = Connection::in_memory(executor.clone());
+
+        let client = Peer::new(0);
+        client.set_keepalive(Keepalive {
+            interval: Duration::from_secs(1),
+            receive_timeout: Duration::from_secs(3),
+        });
+        let (connection_id, io_handler, mut incoming) =
+            client.add_test_connection(client_conn, executor.clone());
+        executor.spawn(io_handler).detach();
+        executor
+            .spawn(async move { incoming.next().await })
+            .detach();
+
+        // The other end receives the request and the pings, but never answers anything.
+        let mut response = executor.spawn(client.request(connection_id, proto::Ping {}));
+        let _request = cx
+            .run_until(server_conn.rx.next())
+            .flatten()
+            .unwrap()
+            .unwrap();
+
+        executor.advance_clock(Duration::from_secs(2));
+        assert!((&mut response).now_or_never().is_none());
+
+        executor.advance_clock(Duration::from_secs(1));
+        let error = cx
+            .run_until(response)
+            .expect("request never completed")
+            .unwrap_err();
+        assert_eq!(error.error_code(), ErrorCode::Disconnected);
+    }
+
+    #[gpui::test(iterations = 50)]
+    async fn test_io_error_during_stream(cx: &mut TestAppContext) {
+        let executor = cx.executor();
+        let (client_conn, mut server_conn, _kill) = Connection::in_memory(executor.clone());
+
+        let client = Peer::new(0);
+        let (connection_id, io_handler, mut incoming) =
+            client.add_test_connection(client_conn, executor.clone());
+        executor.spawn(io_handler).detach();
+        executor
+            .spawn(async move { incoming.next().await })
+            .detach();
+
+        let stream = client.request_stream(connection_id, proto::Ping {});
+        let response = executor.spawn(async move { stream.await?.next().await.unwrap() });
+        let _request = cx
+            .run_until(server_conn.rx.next())
+            .flatten()
+            .unwrap()
+            .unwrap();
 
         drop(server_conn);
+        let response = cx.run_until(response).expect("stream never ended");
+        let error = response.unwrap_err();
+        assert_eq!(error.to_string(), "connection closed");
+        assert_eq!(error.error_code(), ErrorCode::Disconnected);
+    }
+
+    #[gpui::test(iterations = 50)]
+    async fn test_error_responses(cx: &mut TestAppContext) {
+        let executor = cx.executor();
+        let server = Peer::new(0);
+        let client = Peer::new(0);
+
+        let (client_to_server_conn, server_to_client_conn, _kill) =
+            Connection::in_memory(executor.clone());
+        let (client_to_server_conn_id, io_task1, mut client_incoming) =
+            client.add_test_connection(client_to_server_conn, executor.clone());
+        let (_, io_task2, mut server_incoming) =
+            server.add_test_connection(server_to_client_conn, executor.clone());
+        executor.spawn(io_task1).detach();
+        executor.spawn(io_task2).detach();
+        executor
+            .spawn(async move { client_incoming.next().await })
+            .detach();
+
+        let response = client.request(client_to_server_conn_id, proto::Test { id: 1 });
+        let request = server_incoming
+            .next()
+            .await
+            .unwrap()
+            .into_any()
+            .downcast::<TypedEnvelope<proto::Test>>()
+            .unwrap();
+        server
+            .respond_with_error(
+                request.receipt(),
+                ErrorCode::NoSuchProject
+                    .message("project 1 not found".into())
+                    .to_proto(),
+            )
+            .unwrap();
+        let error = response.await.unwrap_err();
+        assert_eq!(error.error_code(), ErrorCode::NoSuchProject);
+        assert_eq!(
+            error.to_string(),
+            "RPC request Test failed: project 1 not found"
+        );
+
+        // An error ends the stream of responses.
+        let responses = client.request_stream(client_to_server_conn_id, proto::Test { id: 2 });
+        let request = server_incoming
+            .next()
+            .await
+            .unwrap()
+            .into_any()
+            .downcast::<TypedEnvelope<proto::Test>>()
+            .unwrap();
+        executor
+            .spawn(async move { server_incoming.next().await })
+            .detach();
+        server
+            .respond(request.receipt(), proto::Test { id: 3 })
+            .unwrap();
+        server
+            .respond_with_error(
+                request.receipt(),
+                ErrorCode::Forbidden
+                    .message("not a member".into())
+                    .to_proto(),
+            )
+            .unwrap();
+        let mut responses = responses.await.unwrap();
+        assert_eq!(
+            responses.next().await.unwrap().unwrap(),
+            proto::Test { id: 3 }
+        );
+        let error = responses.next().await.unwrap().unwrap_err();
+        assert_eq!(error.error_code(), ErrorCode::Forbidden);
+        assert!(responses.next().await.is_none());
+    }
+
+    #[gpui::test(iterations = 50)]
+    async fn test_stalled_response_stream(cx: &mut TestAppContext) {
+        let executor = cx.executor();
+        let server = Peer::new(0);
+        let client = Peer::new(0);
+
+        let (client_to_server_conn, server_to_client_conn, _kill) =
+            Connection::in_memory(executor.clone());
+        let (client_to_server_conn_id, io_task1, mut client_incoming) =
+            client.add_test_connection(client_to_server_conn, executor.clone());
+        let (_, io_task2, mut server_incoming) =
+            server.add_test_connection(server_to_client_conn, executor.clone());
+        executor.spawn(io_task1).detach();
+        executor.spawn(io_task2).detach();
+        executor
+            .spawn(async move { client_incoming.next().await })
+            .detach();
+
+        // The responses to the stream go beyond its buffer, without it being read.
+        let responses = client.request_stream_with_buffer_size(
+            client_to_server_conn_id,
+            proto::Test { id: 0 },
+            1,
+        );
+        let stream_request = server_incoming
+            .next()
+            .await
+            .unwrap()
+            .into_any()
+            .downcast::<TypedEnvelope<proto::Test>>()
+            .unwrap();
+        for id in 1..=3 {
+            server
+                .respond(stream_request.receipt(), proto::Test { id })
+                .unwrap();
+        }
+        server.end_stream(stream_request.receipt()).unwrap();
+
+        // Another request still gets its response while the stream is stalled.
+        let response = client.request(client_to_server_conn_id, proto::Ping {});
+        let request = server_incoming
+            .next()
+            .await
+            .unwrap()
+            .into_any()
+            .downcast::<TypedEnvelope<proto::Ping>>()
+            .unwrap();
+        server.respond(request.receipt(), proto::Ack {}).unwrap();
+        executor
+            .spawn(async move { server_incoming.next().await })
+            .detach();
+        assert_eq!(response.await.unwrap(), proto::Ack {});
+
+        let responses = responses.await.unwrap().collect::<Vec<_>>().await;
+        assert_eq!(
+            responses
+                .into_iter()
+                .map(|response| response.unwrap().id)
+                .collect::<Vec<_>>(),
+            [1, 2, 3]
+        );
+    }
+
+    #[gpui::test(iterations = 50)]
+    async fn test_dropping_response_stream(cx: &mut TestAppContext) {
+        let executor = cx.executor();
+        let server = Peer::new(0);
+        let client = Peer::new(0);
+
+        let (client_to_server_conn, server_to_client_conn, _kill) =
+            Connection::in_memory(executor.clone());
+        let (client_to_server_conn_id, io_task1, mut client_incoming) =
+            client.add_test_connection(client_to_server_conn, executor.clone());
+        let (server_to_client_conn_id, io_task2, mut server_incoming) =
+            server.add_test_connection(server_to_client_conn, executor.clone());
+        executor.spawn(io_task1).detach();
+        executor.spawn(io_task2).detach();
+        executor
+            .spawn(async move { client_incoming.next().await })
+            .detach();
+
+        let responses = client.request_stream(client_to_server_conn_id, proto::Test { id: 0 });
+        let request = server_incoming
+            .next()
+            .await
+            .unwrap()
+            .into_any()
+            .downcast::<TypedEnvelope<proto::Test>>()
+            .unwrap();
+        executor
+            .spawn(async move { server_incoming.next().await })
+            .detach();
+
+        server
+            .respond(request.receipt(), proto::Test { id: 1 })
+            .unwrap();
+        server
+            .respond(request.receipt(), proto::Test { id: 2 })
+            .unwrap();
+        let mut responses = responses.await.unwrap();
+        assert_eq!(
+            responses.next().await.unwrap().unwrap(),
+            proto::Test { id: 1 }
+        );
+        assert_eq!(
+            responses.next().await.unwrap().unwrap(),
+            proto::Test { id: 2 }
+        );
+
+        // Dropping the responses forgets about the request, and cancels it on the server.
+        drop(responses);
+        assert!(client.connections.read()[&client_to_server_conn_id]
+            .stream_response_channels
+            .lock()
+            .as_ref()
+            .unwrap()
+            .is_empty());
+        executor.run_until_parked();
+        assert_eq!(
+            server
+                .respond(request.receipt(), proto::Test { id: 3 })
+                .unwrap_err()
+                .to_string(),
+            "stream was cancelled"
+        );
+
+        // Ending the cancelled stream doesn't send anything, and forgets about the cancellation.
+        server.end_stream(request.receipt()).unwrap();
+        assert!(server.connections.read()[&server_to_client_conn_id]
+            .cancelled_streams
+            .lock()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_cancelled_streams_expire() {
+        // Cancellations that crossed the end of their stream are never removed by ending it.
+        let cancelled_streams = CancelledStreams::default();
+        let start = Instant::now();
+        insert_cancelled_stream(&cancelled_streams, 1, start);
+        insert_cancelled_stream(&cancelled_streams, 2, start + CANCELLED_STREAM_EXPIRY / 2);
+        assert_eq!(cancelled_streams.lock().len(), 2);
+
+        insert_cancelled_stream(&cancelled_streams, 3, start + CANCELLED_STREAM_EXPIRY);
+        let mut ids = cancelled_streams.lock().keys().copied().collect::<Vec<_>>();
+        ids.sort();
+        assert_eq!(ids, [2, 3]);
+    }
+
+    #[gpui::test(iterations = 10)]
+    async fn test_request_timeout(cx: &mut TestAppContext) {
+        let executor = cx.executor();
+        let server = Peer::new(0);
+        let client = Peer::new(0);
+
+        let (client_to_server_conn, server_to_client_conn, _kill) =
+            Connection::in_memory(executor.clone());
+        let (client_to_server_conn_id, io_task1, mut client_incoming) =
+            client.add_test_connection(client_to_server_conn, executor.clone());
+        let (_, io_task2, mut server_incoming) =
+            server.add_test_connection(server_to_client_conn, executor.clone());
+        executor.spawn(io_task1).detach();
+        executor.spawn(io_task2).detach();
+        executor
+            .spawn(async move { client_incoming.next().await })
+            .detach();
+
+        let timeout = Duration::from_secs(5);
+        let response = executor.spawn(client.request_with_timeout(
+            client_to_server_conn_id,
+            proto::Ping {},
+            timeout,
+        ));
+        let request = server_incoming
+            .next()
+            .await
+            .unwrap()
+            .into_any()
+            .downcast::<TypedEnvelope<proto::Ping>>()
+            .unwrap();
+
+        executor.advance_clock(timeout);
         assert_eq!(
             response.await.unwrap_err().to_string(),
-            "connection was closed"
+            "Ping request timed out after 5s"
         );
+        assert!(client.connections.read()[&client_to_server_conn_id]
+            .response_channels
+            .lock()
+            .as_ref()
+            .unwrap()
+            .is_empty());
+
+        // The response that arrives too late gets dropped, without affecting other requests.
+        server.respond(request.receipt(), proto::Ack {}).unwrap();
+        let response = executor.spawn(client.request_with_timeout(
+            client_to_server_conn_id,
+            proto::Ping {},
+            timeout,
+        ));
+        let request = server_incoming
+            .next()
+            .await
+            .unwrap()
+            .into_any()
+            .downcast::<TypedEnvelope<proto::Ping>>()
+            .unwrap();
+        server.respond(request.receipt(), proto::Ack {}).unwrap();
+        assert_eq!(response.await.unwrap(), proto::Ack {});
     }
 }
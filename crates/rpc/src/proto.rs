@@ -42,7 +42,17 @@ impl<S> MessageStream<S>
 where
     S: futures::Sink<WebSocketMessage, Error = anyhow::Error> + Unpin,
 {
-    pub async fn write(&mut self, message: Message) -> Result<(), anyhow::Error> {
+    /// Writes a message, and returns the size of what got written for it, which is only the
+    /// size of the envelopes, as pings and pongs are empty.
+    pub async fn write(&mut self, message: Message) -> Result<usize, anyhow::Error> {
+        let len = self.feed(message).await?;
+        self.stream.flush().await?;
+        Ok(len)
+    }
+
+    /// Like [`MessageStream::write`], but leaves the message buffered in the underlying stream
+    /// until it gets flushed, so that multiple messages can be flushed at once.
+    pub async fn feed(&mut self, message: Message) -> Result<usize, anyhow::Error> {
         #[cfg(any(test, feature = "test-support"))]
         const COMPRESSION_LEVEL: i32 = -7;
 
@@ -61,21 +71,32 @@ where
 
                 self.encoding_buffer.clear();
                 self.encoding_buffer.shrink_to(MAX_BUFFER_LEN);
-                self.stream.send(WebSocketMessage::Binary(buffer)).await?;
+                let len = buffer.len();
+                self.stream.feed(WebSocketMessage::Binary(buffer)).await?;
+                Ok(len)
             }
             Message::Ping => {
                 self.stream
-                    .send(WebSocketMessage::Ping(Default::default()))
+                    .feed(WebSocketMessage::Ping(Default::default()))
                     .await?;
+                Ok(0)
             }
             Message::Pong => {
                 self.stream
-                    .send(WebSocketMessage::Pong(Default::default()))
+                    .feed(WebSocketMessage::Pong(Default::default()))
                     .await?;
+                Ok(0)
             }
         }
+    }
+
+    pub async fn flush(&mut self) -> Result<(), anyhow::Error> {
+        self.stream.flush().await
+    }
 
-        Ok(())
+    /// Flushes the messages that were written, and closes the underlying stream.
+    pub async fn close(&mut self) -> Result<(), anyhow::Error> {
+        self.stream.close().await
     }
 }
 
@@ -83,21 +104,24 @@ impl<S> MessageStream<S>
 where
     S: futures::Stream<Item = Result<WebSocketMessage, anyhow::Error>> + Unpin,
 {
-    pub async fn read(&mut self) -> Result<(Message, Instant), anyhow::Error> {
+    /// Reads a message, along with when it was received and the size of what was read for it,
+    /// which is only the size of the envelopes, as pings and pongs are empty.
+    pub async fn read(&mut self) -> Result<(Message, Instant, usize), anyhow::Error> {
         while let Some(bytes) = self.stream.next().await {
             let received_at = Instant::now();
             match bytes? {
                 WebSocketMessage::Binary(bytes) => {
+                    let len = bytes.len();
                     zstd::stream::copy_decode(bytes.as_slice(), &mut self.encoding_buffer).unwrap();
                     let envelope = Envelope::decode(self.encoding_buffer.as_slice())
                         .map_err(io::Error::from)?;
 
                     self.encoding_buffer.clear();
                     self.encoding_buffer.shrink_to(MAX_BUFFER_LEN);
-                    return Ok((Message::Envelope(envelope), received_at));
+                    return Ok((Message::Envelope(envelope), received_at, len));
                 }
-                WebSocketMessage::Ping(_) => return Ok((Message::Ping, received_at)),
-                WebSocketMessage::Pong(_) => return Ok((Message::Pong, received_at)),
+                WebSocketMessage::Ping(_) => return Ok((Message::Ping, received_at, 0)),
+                WebSocketMessage::Pong(_) => return Ok((Message::Pong, received_at, 0)),
                 WebSocketMessage::Close(_) => break,
                 _ => {}
             }
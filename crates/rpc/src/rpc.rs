@@ -4,12 +4,16 @@ mod extension;
 mod notification;
 mod peer;
 pub mod proto;
+mod snapshot_subscription;
+#[cfg(any(test, feature = "test-support"))]
+pub mod test;
 
 pub use conn::Connection;
 pub use extension::*;
 pub use notification::*;
 pub use peer::*;
 pub use proto::{error::*, Receipt, TypedEnvelope};
+pub use snapshot_subscription::*;
 mod macros;
 
-pub const PROTOCOL_VERSION: u32 = 68;
+pub const PROTOCOL_VERSION: u32 = 70;
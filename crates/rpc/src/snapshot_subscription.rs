@@ -0,0 +1,183 @@
+use crate::proto;
+use anyhow::{anyhow, Result};
+use futures::{Stream, StreamExt as _};
+use std::{
+    any::type_name,
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// A message of a subscription whose first message carries a snapshot of some state, and
+/// whose later messages carry deltas to apply on top of it.
+pub trait SnapshotSubscription: Sized {
+    /// The state carried by a snapshot.
+    type Snapshot;
+    /// The change carried by a delta.
+    type Delta;
+
+    /// Returns whether this message carries a snapshot or a delta.
+    fn classify(self) -> SubscriptionMessage<Self::Snapshot, Self::Delta>;
+}
+
+/// The contents of a message of a [`SnapshotSubscription`].
+pub enum SubscriptionMessage<S, D> {
+    Snapshot(S),
+    Delta(D),
+}
+
+/// An update yielded by [`subscribe_with_snapshot`] after the initial snapshot.
+#[derive(Debug, PartialEq)]
+pub enum SnapshotUpdate<S, D> {
+    /// A change to apply on top of the current state.
+    Delta(D),
+    /// A snapshot that replaces the current state. It's sent after reconnecting, when the
+    /// backfill has a gap and so can't replay the deltas that were missed in the meantime.
+    Resync(S),
+}
+
+/// Waits for the initial snapshot of a subscription, and returns it along with a stream of
+/// the updates that follow it.
+///
+/// Fails if the messages end or start with a delta, as there is nothing to apply it to.
+pub async fn subscribe_with_snapshot<T, S>(
+    mut messages: S,
+) -> Result<(T::Snapshot, SnapshotUpdates<T, S>)>
+where
+    T: SnapshotSubscription,
+    S: Stream<Item = T> + Unpin,
+{
+    let message = messages
+        .next()
+        .await
+        .ok_or_else(|| anyhow!("{} ended before its initial snapshot", type_name::<T>()))?;
+    match message.classify() {
+        SubscriptionMessage::Snapshot(snapshot) => Ok((
+            snapshot,
+            SnapshotUpdates {
+                messages,
+                _message: PhantomData,
+            },
+        )),
+        SubscriptionMessage::Delta(_) => Err(anyhow!(
+            "{} started with a delta instead of a snapshot",
+            type_name::<T>()
+        )),
+    }
+}
+
+/// The updates returned by [`subscribe_with_snapshot`].
+pub struct SnapshotUpdates<T, S> {
+    messages: S,
+    _message: PhantomData<fn() -> T>,
+}
+
+impl<T, S> Stream for SnapshotUpdates<T, S>
+where
+    T: SnapshotSubscription,
+    S: Stream<Item = T> + Unpin,
+{
+    type Item = SnapshotUpdate<T::Snapshot, T::Delta>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        self.messages.poll_next_unpin(cx).map(|message| {
+            message.map(|message| match message.classify() {
+                SubscriptionMessage::Snapshot(snapshot) => SnapshotUpdate::Resync(snapshot),
+                SubscriptionMessage::Delta(delta) => SnapshotUpdate::Delta(delta),
+            })
+        })
+    }
+}
+
+impl SnapshotSubscription for proto::UpdateContacts {
+    type Snapshot = Self;
+    type Delta = Self;
+
+    fn classify(self) -> SubscriptionMessage<Self, Self> {
+        if self.is_snapshot {
+            SubscriptionMessage::Snapshot(self)
+        } else {
+            SubscriptionMessage::Delta(self)
+        }
+    }
+}
+
+impl SnapshotSubscription for proto::UpdateWorktree {
+    type Snapshot = Self;
+    type Delta = Self;
+
+    fn classify(self) -> SubscriptionMessage<Self, Self> {
+        if self.is_snapshot {
+            SubscriptionMessage::Snapshot(self)
+        } else {
+            SubscriptionMessage::Delta(self)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{channel::mpsc, FutureExt as _};
+
+    fn contacts(outgoing_requests: &[u64], is_snapshot: bool) -> proto::UpdateContacts {
+        proto::UpdateContacts {
+            outgoing_requests: outgoing_requests.to_vec(),
+            is_snapshot,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_subscribe_with_snapshot() {
+        let (tx, rx) = mpsc::unbounded();
+        tx.unbounded_send(contacts(&[1], true)).unwrap();
+        tx.unbounded_send(contacts(&[2], false)).unwrap();
+        let (snapshot, mut updates) = subscribe_with_snapshot(rx).now_or_never().unwrap().unwrap();
+        assert_eq!(snapshot, contacts(&[1], true));
+        assert_eq!(
+            updates.next().now_or_never(),
+            Some(Some(SnapshotUpdate::Delta(contacts(&[2], false))))
+        );
+        assert_eq!(updates.next().now_or_never(), None);
+
+        // A snapshot after a gap in the backfill replaces the state.
+        tx.unbounded_send(contacts(&[3], true)).unwrap();
+        tx.unbounded_send(contacts(&[4], false)).unwrap();
+        assert_eq!(
+            updates.next().now_or_never(),
+            Some(Some(SnapshotUpdate::Resync(contacts(&[3], true))))
+        );
+        assert_eq!(
+            updates.next().now_or_never(),
+            Some(Some(SnapshotUpdate::Delta(contacts(&[4], false))))
+        );
+
+        drop(tx);
+        assert_eq!(updates.next().now_or_never(), Some(None));
+    }
+
+    #[test]
+    fn test_subscribe_without_snapshot() {
+        let (tx, rx) = mpsc::unbounded();
+        tx.unbounded_send(contacts(&[1], false)).unwrap();
+        tx.unbounded_send(contacts(&[2], true)).unwrap();
+        let error = subscribe_with_snapshot(rx)
+            .now_or_never()
+            .unwrap()
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("started with a delta"));
+
+        let (tx, rx) = mpsc::unbounded::<proto::UpdateContacts>();
+        drop(tx);
+        let error = subscribe_with_snapshot(rx)
+            .now_or_never()
+            .unwrap()
+            .err()
+            .unwrap();
+        assert!(error
+            .to_string()
+            .contains("ended before its initial snapshot"));
+    }
+}
@@ -0,0 +1,118 @@
+use crate::{
+    proto::{self, AnyTypedEnvelope, EnvelopedMessage, RequestMessage},
+    Connection, ConnectionId, Peer, Receipt, TypedEnvelope,
+};
+use anyhow::{anyhow, Result};
+use futures::{stream::BoxStream, StreamExt as _};
+use gpui::BackgroundExecutor;
+use parking_lot::Mutex;
+use std::sync::Arc;
+
+/// The other end of a peer's connection, for testing code that talks to a server through a
+/// [`Peer`] without running one. It's connected to the peer over an in-memory connection.
+pub struct FakeServer {
+    peer: Arc<Peer>,
+    state: Mutex<FakeServerState>,
+    executor: BackgroundExecutor,
+}
+
+struct FakeServerState {
+    connection_id: Option<ConnectionId>,
+    incoming: Option<BoxStream<'static, Box<dyn AnyTypedEnvelope>>>,
+}
+
+impl FakeServer {
+    /// Connects the given peer to a new fake server, returning the id of the peer's connection
+    /// along with the messages that the server sends on it which aren't responses.
+    pub fn connect(
+        client: &Arc<Peer>,
+        executor: BackgroundExecutor,
+    ) -> (
+        ConnectionId,
+        BoxStream<'static, Box<dyn AnyTypedEnvelope>>,
+        Self,
+    ) {
+        let (client_conn, server_conn, _) = Connection::in_memory(executor.clone());
+        let (client_connection_id, client_io, client_incoming) =
+            client.add_test_connection(client_conn, executor.clone());
+        executor.spawn(client_io).detach();
+
+        let peer = Peer::new(0);
+        let (connection_id, io, incoming) = peer.add_test_connection(server_conn, executor.clone());
+        executor.spawn(io).detach();
+
+        let server = Self {
+            peer,
+            state: Mutex::new(FakeServerState {
+                connection_id: Some(connection_id),
+                incoming: Some(incoming),
+            }),
+            executor,
+        };
+        (client_connection_id, client_incoming, server)
+    }
+
+    /// Sends a message to the peer that isn't a response to any of its requests.
+    pub fn send<T: EnvelopedMessage>(&self, message: T) {
+        self.peer.send(self.connection_id(), message).unwrap();
+    }
+
+    /// Waits for the next message from the peer, which has to be of the given type. Fails if the
+    /// server has hung up.
+    pub async fn receive<M: EnvelopedMessage>(&self) -> Result<TypedEnvelope<M>> {
+        // The stream is taken out while waiting on it, so that the lock isn't held across the
+        // wait, and only put back if the server didn't hang up in the meantime.
+        let mut incoming = self
+            .state
+            .lock()
+            .incoming
+            .take()
+            .ok_or_else(|| anyhow!("not connected, or already receiving"))?;
+        self.executor.start_waiting();
+        let message = incoming.next().await;
+        self.executor.finish_waiting();
+        let mut state = self.state.lock();
+        if state.connection_id.is_some() {
+            state.incoming = Some(incoming);
+        }
+        drop(state);
+        let message = message.ok_or_else(|| anyhow!("other half hung up"))?;
+
+        let type_name = message.payload_type_name();
+        match message.into_any().downcast::<TypedEnvelope<M>>() {
+            Ok(message) => Ok(*message),
+            Err(_) => panic!(
+                "fake server received unexpected message type: {:?}, expected {:?}",
+                type_name,
+                M::NAME
+            ),
+        }
+    }
+
+    pub fn respond<T: RequestMessage>(&self, receipt: Receipt<T>, response: T::Response) {
+        self.peer.respond(receipt, response).unwrap()
+    }
+
+    pub fn respond_with_error<T: RequestMessage>(&self, receipt: Receipt<T>, error: proto::Error) {
+        self.peer.respond_with_error(receipt, error).unwrap()
+    }
+
+    /// Hangs up on the peer, as a server going away would.
+    pub fn disconnect(&self) {
+        let mut state = self.state.lock();
+        if let Some(connection_id) = state.connection_id.take() {
+            self.peer.disconnect(connection_id);
+            state.incoming.take();
+        }
+    }
+
+    fn connection_id(&self) -> ConnectionId {
+        self.state.lock().connection_id.expect("not connected")
+    }
+}
+
+impl Drop for FakeServer {
+    fn drop(&mut self) {
+        self.disconnect();
+    }
+}
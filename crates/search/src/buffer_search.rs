@@ -1121,9 +1121,7 @@ impl BufferSearchBar {
                             .as_ref()
                             .clone()
                             .with_replacement(self.replacement(cx));
-                        for m in matches {
-                            searchable_item.replace(m, &query, cx);
-                        }
+                        searchable_item.replace_all(&mut matches.iter(), &query, cx);
                     }
                 }
             }
@@ -1141,7 +1139,7 @@ mod tests {
     use std::ops::Range;
 
     use super::*;
-    use editor::{display_map::DisplayRow, DisplayPoint, Editor, MultiBuffer};
+    use editor::{actions::Undo, display_map::DisplayRow, DisplayPoint, Editor, MultiBuffer};
     use gpui::{Context, Hsla, TestAppContext, VisualTestContext};
     use language::{Buffer, Point};
     use project::Project;
@@ -2086,6 +2084,36 @@ mod tests {
         .await;
     }
 
+    #[gpui::test]
+    async fn test_replace_all_is_undone_at_once(cx: &mut TestAppContext) {
+        let (editor, search_bar, cx) = init_test(cx);
+        let original_text = editor.update(cx, |editor, cx| editor.text(cx));
+
+        run_replacement_test(ReplacementTestParams {
+            editor: &editor,
+            search_bar: &search_bar,
+            cx,
+            search_text: r"\[(\d)\]",
+            search_options: Some(SearchOptions::REGEX),
+            replacement_text: "($1)",
+            replace_all: true,
+            expected_text: r#"
+            A regular expression (shortened as regex or regexp;(1) also referred to as
+            rational expression(2)(3)) is a sequence of characters that specifies a search
+            pattern in text. Usually such patterns are used by string-searching algorithms
+            for "find" or "find and replace" operations on strings, or for input validation.
+            "#
+            .unindent(),
+        })
+        .await;
+
+        editor.update(cx, |editor, cx| editor.undo(&Undo, cx));
+        assert_eq!(
+            editor.update(cx, |editor, cx| editor.text(cx)),
+            original_text
+        );
+    }
+
     #[gpui::test]
     async fn test_find_matches_in_selections_singleton_buffer_multiple_selections(
         cx: &mut TestAppContext,
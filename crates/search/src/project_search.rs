@@ -1,3 +1,5 @@
+mod persistence;
+
 use crate::{
     FocusSearch, NextHistoryQuery, PreviousHistoryQuery, ReplaceAll, ReplaceNext, SearchOptions,
     SelectNextMatch, SelectPrevMatch, ToggleCaseSensitive, ToggleIncludeIgnored, ToggleRegex,
@@ -7,18 +9,22 @@ use anyhow::Context as _;
 use collections::{HashMap, HashSet};
 use editor::{
     actions::SelectAll,
+    display_map::{Flap, FlapId},
     items::active_match_index,
     scroll::{Autoscroll, Axis},
-    Anchor, Editor, EditorElement, EditorEvent, EditorStyle, MultiBuffer, MAX_TAB_TITLE_LEN,
+    Anchor, Editor, EditorElement, EditorEvent, EditorStyle, FoldPlaceholder, MultiBuffer, ToPoint,
+    MAX_TAB_TITLE_LEN,
 };
 use gpui::{
-    actions, div, Action, AnyElement, AnyView, AppContext, Context as _, Element, EntityId,
+    actions, div, Action, AnyElement, AnyView, AppContext, Context as _, Element, Empty, EntityId,
     EventEmitter, FocusHandle, FocusableView, FontStyle, Global, Hsla, InteractiveElement,
     IntoElement, Model, ModelContext, ParentElement, Point, Render, SharedString, Styled,
     Subscription, Task, TextStyle, UpdateGlobal, View, ViewContext, VisualContext, WeakModel,
     WeakView, WhiteSpace, WindowContext,
 };
+use language::{Buffer, BufferId};
 use menu::Confirm;
+use persistence::PROJECT_SEARCH_DB;
 use project::{search::SearchQuery, search_history::SearchHistoryCursor, Project, ProjectPath};
 use settings::Settings;
 use smol::stream::StreamExt;
@@ -27,26 +33,37 @@ use std::{
     mem,
     ops::{Not, Range},
     path::Path,
+    sync::Arc,
+    time::SystemTime,
 };
 use theme::ThemeSettings;
 use ui::{
-    h_flex, prelude::*, v_flex, Icon, IconButton, IconName, Label, LabelCommon, LabelSize,
-    Selectable, Tooltip,
+    h_flex, prelude::*, v_flex, Checkbox, Icon, IconButton, IconName, Label, LabelCommon,
+    LabelSize, Selectable, Selection, Tooltip,
 };
-use util::paths::PathMatcher;
+use util::{paths::PathMatcher, ResultExt as _};
 use workspace::{
+    background_tasks::{BackgroundTaskId, BackgroundTasks, NewBackgroundTask},
     item::{BreadcrumbText, Item, ItemEvent, ItemHandle, TabContentParams},
     searchable::{Direction, SearchableItem, SearchableItemHandle},
-    DeploySearch, ItemNavHistory, NewSearch, Pane, ToolbarItemEvent, ToolbarItemLocation,
+    DeploySearch, ItemId, ItemNavHistory, NewSearch, Pane, ToolbarItemEvent, ToolbarItemLocation,
     ToolbarItemView, Workspace, WorkspaceId,
 };
 
+const PROJECT_SEARCH_KIND: &str = "ProjectSearchView";
 const MIN_INPUT_WIDTH_REMS: f32 = 15.;
 const MAX_INPUT_WIDTH_REMS: f32 = 30.;
 
 actions!(
     project_search,
-    [SearchInNew, ToggleFocus, NextField, ToggleFilters]
+    [
+        SearchInNew,
+        ToggleFocus,
+        NextField,
+        ToggleFilters,
+        ToggleMatchIncluded,
+        CancelReplaceAll,
+    ]
 );
 
 #[derive(Default)]
@@ -56,6 +73,7 @@ impl Global for ActiveSettings {}
 
 pub fn init(cx: &mut AppContext) {
     cx.set_global(ActiveSettings::default());
+    workspace::register_deserializable_item::<ProjectSearchView>(cx);
     cx.observe_new_views(|workspace: &mut Workspace, _cx| {
         register_workspace_action(workspace, move |search_bar, _: &FocusSearch, cx| {
             search_bar.focus_search(cx);
@@ -75,6 +93,12 @@ pub fn init(cx: &mut AppContext) {
         register_workspace_action(workspace, move |search_bar, action: &ToggleReplace, cx| {
             search_bar.toggle_replace(action, cx)
         });
+        register_workspace_action(workspace, move |search_bar, _: &ToggleMatchIncluded, cx| {
+            search_bar.toggle_match_included(cx);
+        });
+        register_workspace_action(workspace, move |search_bar, _: &CancelReplaceAll, cx| {
+            search_bar.cancel_replace_all(cx);
+        });
         register_workspace_action(
             workspace,
             move |search_bar, action: &SelectPrevMatch, cx| {
@@ -125,6 +149,14 @@ struct ProjectSearch {
     no_results: Option<bool>,
     limit_reached: bool,
     search_history_cursor: SearchHistoryCursor,
+    /// The matches that replacing all of them leaves alone, by their index in `match_ranges`.
+    excluded_matches: HashSet<usize>,
+    /// The mtimes of the files with matches as of when they were searched, so that replacing
+    /// skips the files that changed on disk since.
+    searched_mtimes: HashMap<BufferId, Option<SystemTime>>,
+    /// The buffers of files that weren't open before they were searched. Replacing in them
+    /// saves them right away, as there's no editor of theirs to save them from.
+    unopened_buffers: HashSet<BufferId>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -149,9 +181,87 @@ pub struct ProjectSearchView {
     excluded_files_editor: View<Editor>,
     filters_enabled: bool,
     replace_enabled: bool,
+    pending_replace: Option<Task<Option<()>>>,
+    /// Whether replacing all matches stops once the file being replaced in is done.
+    replace_cancelled: bool,
+    replace_summary: Option<ReplaceSummary>,
+    /// The flaps of the rows of the results with matches, which show whether replacing all
+    /// matches replaces theirs.
+    match_flaps: Vec<FlapId>,
+    /// How many of the matches have the flap of their row.
+    flapped_match_count: usize,
+    /// The background tasks of the workspace the view was added to, which list its search and
+    /// its replacing.
+    background_tasks: Option<WeakModel<BackgroundTasks>>,
+    search_task: Option<BackgroundTaskId>,
+    replace_task: Option<BackgroundTaskId>,
+    /// The workspace the view was added to, which its query is persisted in.
+    workspace_id: Option<WorkspaceId>,
     _subscriptions: Vec<Subscription>,
 }
 
+/// What replacing all matches did, shown until the next search.
+#[derive(Debug, Default, PartialEq)]
+struct ReplaceSummary {
+    replaced_matches: usize,
+    replaced_files: usize,
+    /// The files left alone because they changed on disk since they were searched.
+    skipped_paths: Vec<Arc<Path>>,
+    /// The files whose replacements couldn't be saved, along with why.
+    failures: Vec<(Arc<Path>, String)>,
+    cancelled: bool,
+}
+
+impl ReplaceSummary {
+    fn message(&self) -> String {
+        let matches = if self.replaced_matches == 1 {
+            "match"
+        } else {
+            "matches"
+        };
+        let files = if self.replaced_files == 1 {
+            "file"
+        } else {
+            "files"
+        };
+        let mut message = format!(
+            "Replaced {} {matches} in {} {files}",
+            self.replaced_matches, self.replaced_files
+        );
+        if self.cancelled {
+            message.push_str(" before being cancelled");
+        }
+        message
+    }
+
+    fn skipped_message(&self) -> Option<String> {
+        if self.skipped_paths.is_empty() {
+            return None;
+        }
+        let paths = self
+            .skipped_paths
+            .iter()
+            .map(|path| path.to_string_lossy())
+            .collect::<Vec<_>>();
+        Some(format!(
+            "Skipped {}, which changed on disk since the search",
+            paths.join(", ")
+        ))
+    }
+
+    fn failures_message(&self) -> Option<String> {
+        if self.failures.is_empty() {
+            return None;
+        }
+        let failures = self
+            .failures
+            .iter()
+            .map(|(path, error)| format!("{}: {error}", path.to_string_lossy()))
+            .collect::<Vec<_>>();
+        Some(format!("Failed to save {}", failures.join(", ")))
+    }
+}
+
 #[derive(Debug, Clone)]
 struct ProjectSearchSettings {
     search_options: SearchOptions,
@@ -179,6 +289,9 @@ impl ProjectSearch {
             no_results: None,
             limit_reached: false,
             search_history_cursor: Default::default(),
+            excluded_matches: Default::default(),
+            searched_mtimes: Default::default(),
+            unopened_buffers: Default::default(),
         }
     }
 
@@ -196,20 +309,31 @@ impl ProjectSearch {
             no_results: self.no_results,
             limit_reached: self.limit_reached,
             search_history_cursor: self.search_history_cursor.clone(),
+            excluded_matches: self.excluded_matches.clone(),
+            searched_mtimes: self.searched_mtimes.clone(),
+            unopened_buffers: self.unopened_buffers.clone(),
         })
     }
 
     fn search(&mut self, query: SearchQuery, cx: &mut ModelContext<Self>) {
-        let search = self.project.update(cx, |project, cx| {
+        let (search, open_buffers) = self.project.update(cx, |project, cx| {
             project
                 .search_history_mut()
                 .add(&mut self.search_history_cursor, query.as_str().to_string());
-            project.search(query.clone(), cx)
+            let open_buffers = project
+                .opened_buffers()
+                .iter()
+                .map(|buffer| buffer.read(cx).remote_id())
+                .collect::<HashSet<_>>();
+            (project.search(query.clone(), cx), open_buffers)
         });
         self.last_search_query_text = Some(query.as_str().to_string());
         self.search_id += 1;
         self.active_query = Some(query);
         self.match_ranges.clear();
+        self.excluded_matches.clear();
+        self.searched_mtimes.clear();
+        self.unopened_buffers.clear();
         self.pending_search = Some(cx.spawn(|this, mut cx| async move {
             let mut matches = search;
             let this = this.upgrade()?;
@@ -228,6 +352,14 @@ impl ProjectSearch {
                         let mut match_ranges = this
                             .update(&mut cx, |this, cx| {
                                 this.no_results = Some(false);
+                                let (buffer_id, mtime) = {
+                                    let buffer = buffer.read(cx);
+                                    (buffer.remote_id(), buffer.file().and_then(|f| f.mtime()))
+                                };
+                                this.searched_mtimes.insert(buffer_id, mtime);
+                                if !open_buffers.contains(&buffer_id) {
+                                    this.unopened_buffers.insert(buffer_id);
+                                }
                                 this.excerpts.update(cx, |excerpts, cx| {
                                     excerpts.stream_excerpts_with_context_lines(
                                         buffer,
@@ -262,6 +394,12 @@ impl ProjectSearch {
         }));
         cx.notify();
     }
+
+    fn cancel_search(&mut self, cx: &mut ModelContext<Self>) {
+        if self.pending_search.take().is_some() {
+            cx.notify();
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -467,6 +605,9 @@ impl Item for ProjectSearchView {
     }
 
     fn added_to_workspace(&mut self, workspace: &mut Workspace, cx: &mut ViewContext<Self>) {
+        self.background_tasks = Some(workspace.background_tasks().downgrade());
+        self.workspace_id = workspace.database_id();
+        self.serialize(cx);
         self.results_editor
             .update(cx, |editor, cx| editor.added_to_workspace(workspace, cx));
     }
@@ -509,17 +650,38 @@ impl Item for ProjectSearchView {
     }
 
     fn serialized_item_kind() -> Option<&'static str> {
-        None
+        Some(PROJECT_SEARCH_KIND)
     }
 
     fn deserialize(
-        _project: Model<Project>,
+        project: Model<Project>,
         _workspace: WeakView<Workspace>,
-        _workspace_id: workspace::WorkspaceId,
-        _item_id: workspace::ItemId,
-        _cx: &mut ViewContext<Pane>,
+        workspace_id: WorkspaceId,
+        item_id: ItemId,
+        cx: &mut ViewContext<Pane>,
     ) -> Task<anyhow::Result<View<Self>>> {
-        unimplemented!()
+        cx.spawn(|_pane, mut cx| async move {
+            let (query, included_files, excluded_files, search_options, filters_enabled) =
+                PROJECT_SEARCH_DB
+                    .get_search(item_id, workspace_id)?
+                    .context("no project search found")?;
+            let settings = ProjectSearchSettings {
+                search_options: SearchOptions::from_bits_truncate(search_options as u8),
+                filters_enabled,
+            };
+
+            let model = cx.new_model(|cx| ProjectSearch::new(project, cx))?;
+            cx.new_view(|cx| {
+                let mut view = Self::new(model, cx, Some(settings));
+                view.set_query(&query, cx);
+                view.included_files_editor
+                    .update(cx, |editor, cx| editor.set_text(included_files, cx));
+                view.excluded_files_editor
+                    .update(cx, |editor, cx| editor.set_text(excluded_files, cx));
+                view.search(cx);
+                view
+            })
+        })
     }
 }
 
@@ -534,6 +696,36 @@ impl ProjectSearchView {
         });
     }
 
+    /// Persists the query of the view, so that it's searched again when the workspace is
+    /// restored.
+    fn serialize(&self, cx: &mut ViewContext<Self>) {
+        let Some(workspace_id) = self.workspace_id else {
+            return;
+        };
+        let item_id = cx.entity_id().as_u64();
+        let query = self.query_editor.read(cx).text(cx);
+        let included_files = self.included_files_editor.read(cx).text(cx);
+        let excluded_files = self.excluded_files_editor.read(cx).text(cx);
+        let search_options = self.search_options.bits() as u32;
+        let filters_enabled = self.filters_enabled;
+        cx.background_executor()
+            .spawn(async move {
+                PROJECT_SEARCH_DB
+                    .save_search(
+                        item_id,
+                        workspace_id,
+                        query,
+                        included_files,
+                        excluded_files,
+                        search_options,
+                        filters_enabled,
+                    )
+                    .await
+                    .log_err();
+            })
+            .detach();
+    }
+
     fn current_settings(&self) -> ProjectSearchSettings {
         ProjectSearchSettings {
             search_options: self.search_options,
@@ -574,8 +766,11 @@ impl ProjectSearchView {
     pub fn replacement(&self, cx: &AppContext) -> String {
         self.replacement_editor.read(cx).text(cx)
     }
+    /// Replaces the matches that weren't left out, one file at a time. Open buffers are edited in
+    /// memory, while the files that weren't open before the search get saved too. Files that
+    /// changed on disk since the search are skipped.
     fn replace_all(&mut self, _: &ReplaceAll, cx: &mut ViewContext<Self>) {
-        if self.active_match_index.is_none() {
+        if self.active_match_index.is_none() || self.pending_replace.is_some() {
             return;
         }
 
@@ -584,24 +779,337 @@ impl ProjectSearchView {
         };
         let query = query.clone().with_replacement(self.replacement(cx));
 
-        let match_ranges = self
-            .model
-            .update(cx, |model, _| mem::take(&mut model.match_ranges));
-        if match_ranges.is_empty() {
+        // The matches of each buffer are next to each other, as they're streamed in per buffer.
+        let model = self.model.read(cx);
+        let mut matches_by_buffer = Vec::<(BufferId, Vec<Range<Anchor>>)>::new();
+        for (ix, range) in model.match_ranges.iter().enumerate() {
+            if model.excluded_matches.contains(&ix) {
+                continue;
+            }
+            let Some(buffer_id) = range.start.buffer_id else {
+                continue;
+            };
+            match matches_by_buffer.last_mut() {
+                Some((last_buffer_id, ranges)) if *last_buffer_id == buffer_id => {
+                    ranges.push(range.clone())
+                }
+                _ => matches_by_buffer.push((buffer_id, vec![range.clone()])),
+            }
+        }
+        if matches_by_buffer.is_empty() {
             return;
         }
 
+        let file_count = matches_by_buffer.len();
+        let window = cx.window_handle();
+        let this = cx.view().downgrade();
+        let task = NewBackgroundTask::new("Replacing matches")
+            .on_cancel(move |cx| {
+                window
+                    .update(cx, |_, cx| {
+                        this.update(cx, |this, cx| this.cancel_replace_all(cx)).ok();
+                    })
+                    .ok();
+            })
+            .notify_on_finish();
+        self.replace_task = self.update_background_tasks(cx, |tasks, cx| {
+            let id = tasks.register(task, cx);
+            tasks.set_progress(id, 0, file_count, cx);
+            id
+        });
+
+        self.replace_summary = Some(ReplaceSummary::default());
+        self.replace_cancelled = false;
+        self.pending_replace = Some(cx.spawn(|this, mut cx| async move {
+            for (ix, (buffer_id, ranges)) in matches_by_buffer.into_iter().enumerate() {
+                // Cancelling leaves every file either replaced in or untouched, so it's only
+                // checked in between files, after the save of the previous one finished.
+                if this
+                    .update(&mut cx, |this, _| this.replace_cancelled)
+                    .ok()?
+                {
+                    break;
+                }
+                let save = this
+                    .update(&mut cx, |this, cx| {
+                        this.replace_in_buffer(buffer_id, &ranges, &query, cx)
+                    })
+                    .ok()?;
+                if let Some((buffer, save)) = save {
+                    let result = save.await;
+                    this.update(&mut cx, |this, cx| {
+                        this.replaced_buffer_saved(buffer, result, cx)
+                    })
+                    .ok()?;
+                }
+                this.update(&mut cx, |this, cx| {
+                    if let Some(id) = this.replace_task {
+                        this.update_background_tasks(cx, |tasks, cx| {
+                            tasks.set_progress(id, ix + 1, file_count, cx)
+                        });
+                    }
+                })
+                .ok()?;
+                smol::future::yield_now().await;
+            }
+            this.update(&mut cx, |this, cx| {
+                this.pending_replace = None;
+                this.replace_cancelled = false;
+                this.replace_finished(cx);
+                cx.notify();
+            })
+            .ok()
+        }));
+        cx.notify();
+    }
+
+    /// Replaces the given matches of a buffer in a single edit, and returns the save of the
+    /// buffer if its file wasn't open before the search.
+    fn replace_in_buffer(
+        &mut self,
+        buffer_id: BufferId,
+        ranges: &[Range<Anchor>],
+        query: &SearchQuery,
+        cx: &mut ViewContext<Self>,
+    ) -> Option<(Model<Buffer>, Task<anyhow::Result<()>>)> {
+        let model = self.model.read(cx);
+        let buffer = model.excerpts.read(cx).buffer(buffer_id)?;
+        let (path, changed_on_disk, was_dirty) = {
+            let buffer = buffer.read(cx);
+            let file = buffer.file();
+            let changed_on_disk = buffer.has_conflict()
+                || file.map_or(false, |file| file.is_deleted())
+                || model
+                    .searched_mtimes
+                    .get(&buffer_id)
+                    .map_or(false, |mtime| *mtime != file.and_then(|file| file.mtime()));
+            (
+                file.map(|file| file.path().clone()),
+                changed_on_disk,
+                buffer.is_dirty(),
+            )
+        };
+        let should_save = !was_dirty && model.unopened_buffers.contains(&buffer_id);
+        let project = model.project.clone();
+
+        let summary = self.replace_summary.get_or_insert_with(Default::default);
+        if changed_on_disk {
+            summary.skipped_paths.extend(path);
+            cx.notify();
+            return None;
+        }
+        summary.replaced_matches += ranges.len();
+        summary.replaced_files += 1;
         self.results_editor.update(cx, |editor, cx| {
-            for item in &match_ranges {
-                editor.replace(item, &query, cx);
+            editor.replace_all(&mut ranges.iter(), query, cx);
+        });
+        cx.notify();
+
+        if should_save {
+            let save = project.update(cx, |project, cx| project.save_buffer(buffer.clone(), cx));
+            Some((buffer, save))
+        } else {
+            None
+        }
+    }
+
+    fn replaced_buffer_saved(
+        &mut self,
+        buffer: Model<Buffer>,
+        result: anyhow::Result<()>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let (buffer_id, file) = {
+            let buffer = buffer.read(cx);
+            (buffer.remote_id(), buffer.file().cloned())
+        };
+        match result {
+            Ok(()) => {
+                // The file changed on disk because of the save, which later replacements in it
+                // shouldn't mistake for a change made elsewhere.
+                let mtime = file.and_then(|file| file.mtime());
+                self.model.update(cx, |model, _| {
+                    model.searched_mtimes.insert(buffer_id, mtime);
+                });
             }
+            Err(error) => {
+                if let Some(path) = file.map(|file| file.path().clone()) {
+                    self.replace_summary
+                        .get_or_insert_with(Default::default)
+                        .failures
+                        .push((path, error.to_string()));
+                }
+            }
+        }
+        cx.notify();
+    }
+
+    /// Ends the background task of replacing all matches, failing it if some files couldn't be
+    /// saved.
+    fn replace_finished(&mut self, cx: &mut ViewContext<Self>) {
+        let Some(id) = self.replace_task.take() else {
+            return;
+        };
+        let failures = self
+            .replace_summary
+            .as_ref()
+            .map_or(&[][..], |summary| &summary.failures);
+        let error = match failures {
+            [] => None,
+            [(path, _)] => Some(format!("couldn't save {}", path.to_string_lossy())),
+            failures => Some(format!("couldn't save {} files", failures.len())),
+        };
+        self.update_background_tasks(cx, |tasks, cx| match error {
+            Some(error) => tasks.fail(id, error, cx),
+            None => tasks.finish(id, cx),
         });
+    }
+
+    /// Stops replacing all matches once the file being replaced in is done, leaving the files
+    /// that were already replaced in as they are.
+    pub fn cancel_replace_all(&mut self, cx: &mut ViewContext<Self>) {
+        if self.pending_replace.is_some() && !self.replace_cancelled {
+            self.replace_cancelled = true;
+            if let Some(summary) = self.replace_summary.as_mut() {
+                summary.cancelled = true;
+            }
+            if let Some(id) = self.replace_task.take() {
+                self.update_background_tasks(cx, |tasks, cx| tasks.cancel(id, cx));
+            }
+            cx.notify();
+        }
+    }
+
+    fn update_background_tasks<R>(
+        &self,
+        cx: &mut ViewContext<Self>,
+        update: impl FnOnce(&mut BackgroundTasks, &mut ModelContext<BackgroundTasks>) -> R,
+    ) -> Option<R> {
+        let background_tasks = self.background_tasks.as_ref()?.upgrade()?;
+        Some(background_tasks.update(cx, update))
+    }
 
-        self.model.update(cx, |model, _cx| {
-            model.match_ranges = match_ranges;
+    pub fn is_match_included(&self, index: usize, cx: &AppContext) -> bool {
+        !self.model.read(cx).excluded_matches.contains(&index)
+    }
+
+    /// Sets whether replacing all matches replaces the match with the given index.
+    pub fn set_match_included(&mut self, index: usize, included: bool, cx: &mut ViewContext<Self>) {
+        self.model.update(cx, |model, cx| {
+            let changed = if included {
+                model.excluded_matches.remove(&index)
+            } else {
+                model.excluded_matches.insert(index)
+            };
+            if changed {
+                cx.notify();
+            }
         });
     }
 
+    fn toggle_match_included(&mut self, cx: &mut ViewContext<Self>) {
+        if let Some(index) = self.active_match_index {
+            let included = self.is_match_included(index, cx);
+            self.set_match_included(index, !included, cx);
+        }
+    }
+
+    /// The indices of the matches that start on the given row of the results.
+    fn matches_in_row(&self, row: u32, cx: &AppContext) -> Range<usize> {
+        let snapshot = self.results_editor.read(cx).buffer().read(cx).snapshot(cx);
+        let match_ranges = &self.model.read(cx).match_ranges;
+        let row_of = |range: &Range<Anchor>| range.start.to_point(&snapshot).row;
+        let start = match_ranges.partition_point(|range| row_of(range) < row);
+        let len = match_ranges[start..]
+            .iter()
+            .take_while(|range| row_of(range) == row)
+            .count();
+        start..start + len
+    }
+
+    /// Includes all the matches of a row in replacing all matches, or leaves all of them out
+    /// when they're all included already.
+    fn toggle_matches_in_row_included(&mut self, row: u32, cx: &mut ViewContext<Self>) {
+        let matches = self.matches_in_row(row, cx);
+        let included = !matches.clone().all(|ix| self.is_match_included(ix, cx));
+        for ix in matches {
+            self.set_match_included(ix, included, cx);
+        }
+    }
+
+    /// Adds a flap to the rows of the new matches, with a checkbox for including their matches
+    /// in replacing all matches.
+    fn update_match_flaps(&mut self, cx: &mut ViewContext<Self>) {
+        let match_ranges = self.model.read(cx).match_ranges.clone();
+        if match_ranges.len() < self.flapped_match_count {
+            let flaps = mem::take(&mut self.match_flaps);
+            self.results_editor
+                .update(cx, |editor, cx| editor.remove_flaps(flaps, cx));
+            self.flapped_match_count = 0;
+        }
+        if match_ranges.len() == self.flapped_match_count {
+            return;
+        }
+
+        let snapshot = self.results_editor.read(cx).buffer().read(cx).snapshot(cx);
+        let mut last_row = self
+            .flapped_match_count
+            .checked_sub(1)
+            .map(|ix| match_ranges[ix].start.to_point(&snapshot).row);
+        let mut flaps = Vec::new();
+        for range in &match_ranges[self.flapped_match_count..] {
+            let row = range.start.to_point(&snapshot).row;
+            if last_row == Some(row) {
+                continue;
+            }
+            last_row = Some(row);
+
+            let view = cx.view().downgrade();
+            flaps.push(Flap::new(
+                range.clone(),
+                FoldPlaceholder {
+                    render: Arc::new(|_, _, _| Empty.into_any_element()),
+                    constrain_width: true,
+                    merge_adjacent: false,
+                },
+                |_, _, _, _| Empty.into_any_element(),
+                move |row, _, cx: &mut WindowContext| {
+                    let Some(view) = view.upgrade() else {
+                        return Empty.into_any_element();
+                    };
+                    let search = view.read(cx);
+                    if !search.replace_enabled {
+                        return Empty.into_any_element();
+                    }
+                    let matches = search.matches_in_row(row.0, cx);
+                    let included = matches
+                        .clone()
+                        .filter(|ix| search.is_match_included(*ix, cx))
+                        .count();
+                    let selection = if included == matches.len() {
+                        Selection::Selected
+                    } else if included == 0 {
+                        Selection::Unselected
+                    } else {
+                        Selection::Indeterminate
+                    };
+                    Checkbox::new(("project-search-include-match", row.0 as usize), selection)
+                        .on_click(move |_, cx| {
+                            view.update(cx, |search, cx| {
+                                search.toggle_matches_in_row_included(row.0, cx)
+                            })
+                        })
+                        .into_any_element()
+                },
+            ));
+        }
+        self.flapped_match_count = match_ranges.len();
+        let ids = self
+            .results_editor
+            .update(cx, |editor, cx| editor.insert_flaps(flaps, cx));
+        self.match_flaps.extend(ids);
+    }
+
     fn new(
         model: Model<ProjectSearch>,
         cx: &mut ViewContext<Self>,
@@ -695,6 +1203,25 @@ impl ProjectSearchView {
             }),
         );
 
+        // The tasks of a view that's gone would never end.
+        cx.on_release(|this, _, cx| {
+            let Some(background_tasks) = this.background_tasks.as_ref().and_then(|t| t.upgrade())
+            else {
+                return;
+            };
+            background_tasks.update(cx, |tasks, cx| {
+                for id in this
+                    .search_task
+                    .take()
+                    .into_iter()
+                    .chain(this.replace_task.take())
+                {
+                    tasks.remove(id, cx);
+                }
+            });
+        })
+        .detach();
+
         let focus_handle = cx.focus_handle();
         subscriptions.push(cx.on_focus_in(&focus_handle, |this, cx| {
             if this.focus_handle.is_focused(cx) {
@@ -722,6 +1249,15 @@ impl ProjectSearchView {
             excluded_files_editor,
             filters_enabled,
             replace_enabled: false,
+            pending_replace: None,
+            replace_cancelled: false,
+            replace_summary: None,
+            match_flaps: Vec::new(),
+            flapped_match_count: 0,
+            background_tasks: None,
+            search_task: None,
+            replace_task: None,
+            workspace_id: None,
             _subscriptions: subscriptions,
         };
         this.model_changed(cx);
@@ -855,7 +1391,23 @@ impl ProjectSearchView {
 
     fn search(&mut self, cx: &mut ViewContext<Self>) {
         if let Some(query) = self.build_search_query(cx) {
+            self.cancel_replace_all(cx);
+            self.replace_summary = None;
+            self.serialize(cx);
+            let title = format!("Searching for \"{}\"", query.as_str());
             self.model.update(cx, |model, cx| model.search(query, cx));
+
+            let model = self.model.downgrade();
+            let task = NewBackgroundTask::new(title).on_cancel(move |cx| {
+                model.update(cx, |model, cx| model.cancel_search(cx)).ok();
+            });
+            let previous_task = self.search_task.take();
+            self.search_task = self.update_background_tasks(cx, |tasks, cx| {
+                if let Some(id) = previous_task {
+                    tasks.remove(id, cx);
+                }
+                tasks.register(task, cx)
+            });
         }
     }
 
@@ -982,7 +1534,7 @@ impl ProjectSearchView {
             self.results_editor.update(cx, |editor, cx| {
                 let range_to_select = editor.range_for_match(&range_to_select);
                 editor.unfold_ranges([range_to_select.clone()], false, true, cx);
-                editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
+                editor.change_selections(Some(Autoscroll::center_if_offscreen()), cx, |s| {
                     s.select_ranges([range_to_select])
                 });
             });
@@ -1014,7 +1566,13 @@ impl ProjectSearchView {
     }
 
     fn model_changed(&mut self, cx: &mut ViewContext<Self>) {
+        if self.model.read(cx).pending_search.is_none() {
+            if let Some(id) = self.search_task.take() {
+                self.update_background_tasks(cx, |tasks, cx| tasks.finish(id, cx));
+            }
+        }
         let match_ranges = self.model.read(cx).match_ranges.clone();
+        let excluded_matches = self.model.read(cx).excluded_matches.clone();
         if match_ranges.is_empty() {
             self.active_match_index = None;
         } else {
@@ -1032,8 +1590,15 @@ impl ProjectSearchView {
                     });
                     editor.scroll(Point::default(), Some(Axis::Vertical), cx);
                 }
+                // The matches left out of replacing all of them aren't highlighted.
+                let included_ranges = match_ranges
+                    .iter()
+                    .enumerate()
+                    .filter(|(ix, _)| !excluded_matches.contains(ix))
+                    .map(|(_, range)| range.clone())
+                    .collect::<Vec<_>>();
                 editor.highlight_background::<Self>(
-                    &match_ranges,
+                    &included_ranges,
                     |theme| theme.search_match_background,
                     cx,
                 );
@@ -1042,6 +1607,7 @@ impl ProjectSearchView {
                 self.focus_results_editor(cx);
             }
         }
+        self.update_match_flaps(cx);
 
         cx.emit(ViewEvent::UpdateTab);
         cx.notify();
@@ -1284,6 +1850,18 @@ impl ProjectSearchBar {
         }
     }
 
+    fn toggle_match_included(&mut self, cx: &mut ViewContext<Self>) {
+        if let Some(search) = self.active_project_search.as_ref() {
+            search.update(cx, |search, cx| search.toggle_match_included(cx));
+        }
+    }
+
+    fn cancel_replace_all(&mut self, cx: &mut ViewContext<Self>) {
+        if let Some(search) = self.active_project_search.as_ref() {
+            search.update(cx, |search, cx| search.cancel_replace_all(cx));
+        }
+    }
+
     fn select_next_match(&mut self, _: &SelectNextMatch, cx: &mut ViewContext<Self>) {
         if let Some(search) = self.active_project_search.as_ref() {
             search.update(cx, |this, cx| {
@@ -1486,6 +2064,7 @@ impl Render for ProjectSearchBar {
                 .border_color(cx.theme().colors().border)
                 .rounded_lg()
                 .child(self.render_text_input(&search.replacement_editor, cx));
+            let is_replacing = search.pending_replace.is_some() && !search.replace_cancelled;
             let replace_actions = h_flex().when(search.replace_enabled, |this| {
                 this.child(
                     IconButton::new("project-search-replace-next", IconName::ReplaceNext)
@@ -1509,11 +2088,36 @@ impl Render for ProjectSearchBar {
                         }))
                         .tooltip(|cx| Tooltip::for_action("Replace all matches", &ReplaceAll, cx)),
                 )
+                .when(is_replacing, |this| {
+                    this.child(
+                        IconButton::new("project-search-cancel-replace-all", IconName::Stop)
+                            .on_click(cx.listener(|this, _, cx| this.cancel_replace_all(cx)))
+                            .tooltip(|cx| {
+                                Tooltip::for_action("Cancel replacing", &CancelReplaceAll, cx)
+                            }),
+                    )
+                })
+            });
+            let replace_summary = search.replace_summary.as_ref().map(|summary| {
+                h_flex()
+                    .gap_2()
+                    .child(Label::new(summary.message()).color(Color::Muted))
+                    .children(
+                        summary
+                            .skipped_message()
+                            .map(|message| Label::new(message).color(Color::Warning)),
+                    )
+                    .children(
+                        summary
+                            .failures_message()
+                            .map(|message| Label::new(message).color(Color::Error)),
+                    )
             });
             h_flex()
                 .gap_2()
                 .child(replace_column)
                 .child(replace_actions)
+                .children(replace_summary)
         });
 
         let filter_line = search.filters_enabled.then(|| {
@@ -1694,7 +2298,6 @@ pub mod tests {
     use project::FakeFs;
     use serde_json::json;
     use settings::SettingsStore;
-    use std::sync::Arc;
     use workspace::DeploySearch;
 
     #[gpui::test]
@@ -3225,6 +3828,322 @@ pub mod tests {
             .expect("unable to update search view");
     }
 
+    #[gpui::test]
+    async fn test_replace_all(cx: &mut TestAppContext) {
+        init_test(cx);
+
+        let fs = FakeFs::new(cx.background_executor.clone());
+        fs.insert_tree(
+            "/dir",
+            json!({
+                "one.rs": "const ONE: usize = 1;",
+                "two.rs": "const TWO: usize = one::ONE + one::ONE;",
+                "three.rs": "const THREE: usize = one::ONE + two::TWO;",
+                "four.rs": "const FOUR: usize = one::ONE + three::THREE;",
+            }),
+        )
+        .await;
+        let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+        let three_buffer = project
+            .update(cx, |project, cx| {
+                project.open_local_buffer("/dir/three.rs", cx)
+            })
+            .await
+            .unwrap();
+        let search = cx.new_model(|cx| ProjectSearch::new(project, cx));
+        let search_view = cx.add_window(|cx| ProjectSearchView::new(search.clone(), cx, None));
+
+        search_view
+            .update(cx, |search_view, cx| {
+                search_view.search_options = SearchOptions::REGEX;
+                search_view
+                    .replacement_editor
+                    .update(cx, |editor, cx| editor.set_text("uno::$1", cx));
+            })
+            .unwrap();
+        perform_search(search_view, r"one::(\w+)", cx);
+
+        // Leave out the second match of two.rs, and change four.rs on disk after the search.
+        search_view
+            .update(cx, |search_view, cx| {
+                let match_paths = paths_of_matches(search_view, cx);
+                let second_two_match = match_paths
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, path)| path.as_ref() == Path::new("two.rs"))
+                    .nth(1)
+                    .map(|(ix, _)| ix)
+                    .unwrap();
+                search_view.set_match_included(second_two_match, false, cx);
+                assert!(!search_view.is_match_included(second_two_match, cx));
+
+                // Each row with matches has a checkbox, which includes all of its matches
+                // unless they all are already.
+                assert_eq!(search_view.match_flaps.len(), 3);
+                let snapshot = search_view
+                    .results_editor
+                    .read(cx)
+                    .buffer()
+                    .read(cx)
+                    .snapshot(cx);
+                let row = search_view.model.read(cx).match_ranges[second_two_match]
+                    .start
+                    .to_point(&snapshot)
+                    .row;
+                assert_eq!(
+                    search_view.matches_in_row(row, cx),
+                    second_two_match - 1..second_two_match + 1
+                );
+                search_view.toggle_matches_in_row_included(row, cx);
+                assert!(search_view.is_match_included(second_two_match, cx));
+                search_view.toggle_matches_in_row_included(row, cx);
+                assert!(!search_view.is_match_included(second_two_match - 1, cx));
+                assert!(!search_view.is_match_included(second_two_match, cx));
+                search_view.set_match_included(second_two_match - 1, true, cx);
+            })
+            .unwrap();
+        fs.insert_file(
+            "/dir/four.rs",
+            b"const FOUR: usize = one::ONE + 4;".to_vec(),
+        )
+        .await;
+        cx.background_executor.run_until_parked();
+
+        search_view
+            .update(cx, |search_view, cx| {
+                search_view.replace_all(&ReplaceAll, cx)
+            })
+            .unwrap();
+        cx.background_executor.run_until_parked();
+
+        search_view
+            .update(cx, |search_view, _| {
+                assert!(search_view.pending_replace.is_none());
+                assert_eq!(
+                    search_view.replace_summary,
+                    Some(ReplaceSummary {
+                        replaced_matches: 2,
+                        replaced_files: 2,
+                        skipped_paths: vec![Path::new("four.rs").into()],
+                        failures: Vec::new(),
+                        cancelled: false,
+                    })
+                );
+            })
+            .unwrap();
+        // The file that wasn't open got saved, while the open buffer was only edited.
+        assert_eq!(
+            fs.load("/dir/two.rs".as_ref()).await.unwrap(),
+            "const TWO: usize = uno::ONE + one::ONE;"
+        );
+        three_buffer.read_with(cx, |buffer, _| {
+            assert_eq!(buffer.text(), "const THREE: usize = uno::ONE + two::TWO;");
+            assert!(buffer.is_dirty());
+        });
+        assert_eq!(
+            fs.load("/dir/three.rs".as_ref()).await.unwrap(),
+            "const THREE: usize = one::ONE + two::TWO;"
+        );
+        assert_eq!(
+            fs.load("/dir/four.rs".as_ref()).await.unwrap(),
+            "const FOUR: usize = one::ONE + 4;"
+        );
+    }
+
+    #[gpui::test]
+    async fn test_cancel_replace_all(cx: &mut TestAppContext) {
+        init_test(cx);
+
+        let fs = FakeFs::new(cx.background_executor.clone());
+        fs.insert_tree(
+            "/dir",
+            json!({
+                "one.rs": "const ONE: usize = 1;",
+                "two.rs": "const TWO: usize = one::ONE + one::ONE;",
+                "three.rs": "const THREE: usize = one::ONE + two::TWO;",
+            }),
+        )
+        .await;
+        let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+        for path in ["/dir/two.rs", "/dir/three.rs"] {
+            project
+                .update(cx, |project, cx| project.open_local_buffer(path, cx))
+                .await
+                .unwrap();
+        }
+        let search = cx.new_model(|cx| ProjectSearch::new(project, cx));
+        let search_view = cx.add_window(|cx| ProjectSearchView::new(search.clone(), cx, None));
+
+        search_view
+            .update(cx, |search_view, cx| {
+                search_view
+                    .replacement_editor
+                    .update(cx, |editor, cx| editor.set_text("uno::ONE", cx));
+            })
+            .unwrap();
+        perform_search(search_view, "one::ONE", cx);
+
+        // Cancel after replacing in the first of the two files.
+        search_view
+            .update(cx, |search_view, cx| {
+                search_view.replace_all(&ReplaceAll, cx)
+            })
+            .unwrap();
+        while search_view
+            .update(cx, |search_view, _| {
+                search_view.replace_summary.as_ref().unwrap().replaced_files == 0
+            })
+            .unwrap()
+        {
+            cx.background_executor.tick();
+        }
+        search_view
+            .update(cx, |search_view, cx| search_view.cancel_replace_all(cx))
+            .unwrap();
+        cx.background_executor.run_until_parked();
+
+        search_view
+            .update(cx, |search_view, cx| {
+                assert!(search_view.pending_replace.is_none());
+                let summary = search_view.replace_summary.as_ref().unwrap();
+                assert!(summary.cancelled);
+                assert_eq!(summary.replaced_files, 1);
+                assert_eq!(
+                    summary.message(),
+                    format!(
+                        "Replaced {} {} in 1 file before being cancelled",
+                        summary.replaced_matches,
+                        if summary.replaced_matches == 1 {
+                            "match"
+                        } else {
+                            "matches"
+                        }
+                    )
+                );
+                let texts = search_view.results_editor.update(cx, |editor, cx| {
+                    editor
+                        .buffer()
+                        .read(cx)
+                        .all_buffers()
+                        .into_iter()
+                        .map(|buffer| buffer.read(cx).text())
+                        .collect::<Vec<_>>()
+                });
+                // Each file is either replaced in entirely or not at all.
+                let replaced = texts
+                    .iter()
+                    .filter(|text| !text.contains("one::ONE"))
+                    .count();
+                let untouched = texts
+                    .iter()
+                    .filter(|text| !text.contains("uno::ONE"))
+                    .count();
+                assert_eq!((replaced, untouched), (1, 1));
+            })
+            .unwrap();
+    }
+
+    #[gpui::test]
+    async fn test_project_search_serialization(cx: &mut TestAppContext) {
+        init_test(cx);
+
+        let fs = FakeFs::new(cx.background_executor.clone());
+        fs.insert_tree(
+            "/dir",
+            json!({
+                "a": {
+                    "one.rs": "const ONE: usize = 1;",
+                    "two.rs": "const TWO: usize = one::ONE + one::ONE;",
+                },
+                "b": {
+                    "three.rs": "const THREE: usize = one::ONE + two::TWO;",
+                },
+            }),
+        )
+        .await;
+        let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+        let window = cx.add_window(|cx| Workspace::test_new(project.clone(), cx));
+        let workspace = window.root(cx).unwrap();
+        let workspace_id = PROJECT_SEARCH_DB
+            .write(|connection| {
+                connection
+                    .select_row::<WorkspaceId>(
+                        "INSERT INTO workspaces DEFAULT VALUES RETURNING workspace_id",
+                    )
+                    .unwrap()()
+                .unwrap()
+                .unwrap()
+            })
+            .await;
+
+        let search_view = window
+            .update(cx, |workspace, cx| {
+                let model = cx.new_model(|cx| ProjectSearch::new(project.clone(), cx));
+                let search_view = cx.new_view(|cx| ProjectSearchView::new(model, cx, None));
+                workspace.add_item_to_active_pane(Box::new(search_view.clone()), None, cx);
+                search_view
+            })
+            .unwrap();
+        window
+            .update(cx, |_, cx| {
+                search_view.update(cx, |search_view, cx| {
+                    search_view.workspace_id = Some(workspace_id);
+                    search_view.filters_enabled = true;
+                    search_view.search_options = SearchOptions::CASE_SENSITIVE;
+                    search_view
+                        .included_files_editor
+                        .update(cx, |editor, cx| editor.set_text("a", cx));
+                    search_view.set_query("ONE", cx);
+                    search_view.search(cx);
+                })
+            })
+            .unwrap();
+        cx.background_executor.run_until_parked();
+        let item_id = search_view.entity_id().as_u64();
+        let paths = cx.read(|cx| paths_of_matches(search_view.read(cx), cx));
+        assert_eq!(paths.len(), 3);
+
+        // Restoring the workspace searches again with the same query, filters and options.
+        let pane = workspace.read_with(cx, |workspace, _| workspace.active_pane().clone());
+        let restored_view = window
+            .update(cx, |workspace, cx| {
+                pane.update(cx, |_, cx| {
+                    ProjectSearchView::deserialize(
+                        project.clone(),
+                        workspace.weak_handle(),
+                        workspace_id,
+                        item_id,
+                        cx,
+                    )
+                })
+            })
+            .unwrap()
+            .await
+            .unwrap();
+        cx.background_executor.run_until_parked();
+        cx.read(|cx| {
+            let restored_view = restored_view.read(cx);
+            assert_eq!(restored_view.query_editor.read(cx).text(cx), "ONE");
+            assert_eq!(restored_view.included_files_editor.read(cx).text(cx), "a");
+            assert!(restored_view.filters_enabled);
+            assert_eq!(restored_view.search_options, SearchOptions::CASE_SENSITIVE);
+            assert_eq!(paths_of_matches(restored_view, cx), paths);
+        });
+    }
+
+    fn paths_of_matches(search_view: &ProjectSearchView, cx: &AppContext) -> Vec<Arc<Path>> {
+        let model = search_view.model.read(cx);
+        let excerpts = model.excerpts.read(cx);
+        model
+            .match_ranges
+            .iter()
+            .map(|range| {
+                let buffer = excerpts.buffer(range.start.buffer_id.unwrap()).unwrap();
+                buffer.read(cx).file().unwrap().path().clone()
+            })
+            .collect()
+    }
+
     fn init_test(cx: &mut TestAppContext) {
         cx.update(|cx| {
             let settings = SettingsStore::test(cx);
@@ -0,0 +1,51 @@
+use db::{define_connection, query, sqlez_macros::sql};
+use workspace::{ItemId, WorkspaceDb, WorkspaceId};
+
+define_connection! {
+    pub static ref PROJECT_SEARCH_DB: ProjectSearchDb<WorkspaceDb> =
+        &[sql!(
+            CREATE TABLE project_searches (
+                workspace_id INTEGER,
+                item_id INTEGER,
+                query TEXT NOT NULL,
+                included_files TEXT NOT NULL,
+                excluded_files TEXT NOT NULL,
+                search_options INTEGER NOT NULL,
+                filters_enabled INTEGER NOT NULL,
+                PRIMARY KEY(workspace_id, item_id),
+                FOREIGN KEY(workspace_id) REFERENCES workspaces(workspace_id)
+                ON DELETE CASCADE
+            ) STRICT;
+        )];
+}
+
+impl ProjectSearchDb {
+    query! {
+        pub async fn save_search(
+            item_id: ItemId,
+            workspace_id: WorkspaceId,
+            query: String,
+            included_files: String,
+            excluded_files: String,
+            search_options: u32,
+            filters_enabled: bool
+        ) -> Result<()> {
+            INSERT OR REPLACE INTO project_searches(
+                item_id, workspace_id, query, included_files, excluded_files, search_options,
+                filters_enabled
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+        }
+    }
+
+    query! {
+        pub fn get_search(
+            item_id: ItemId,
+            workspace_id: WorkspaceId
+        ) -> Result<Option<(String, String, String, u32, bool)>> {
+            SELECT query, included_files, excluded_files, search_options, filters_enabled
+            FROM project_searches
+            WHERE item_id = ? AND workspace_id = ?
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use crate::{settings_store::parse_json_with_comments, SettingsAssets};
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, Result};
 use collections::BTreeMap;
 use gpui::{Action, AppContext, KeyBinding, SharedString};
 use schemars::{
@@ -9,7 +9,7 @@ use schemars::{
 };
 use serde::Deserialize;
 use serde_json::Value;
-use util::{asset_str, ResultExt};
+use util::asset_str;
 
 #[derive(Debug, Deserialize, Default, Clone, JsonSchema)]
 #[serde(transparent)]
@@ -20,6 +20,9 @@ pub struct KeymapBlock {
     #[serde(default)]
     context: Option<String>,
     bindings: BTreeMap<String, KeymapAction>,
+    /// Where each of the bindings was defined, by their keystrokes.
+    #[serde(skip)]
+    binding_sources: BTreeMap<String, SharedString>,
 }
 
 #[derive(Debug, Deserialize, Default, Clone)]
@@ -43,7 +46,9 @@ impl KeymapFile {
     pub fn load_asset(asset_path: &str, cx: &mut AppContext) -> Result<()> {
         let content = asset_str::<SettingsAssets>(asset_path);
 
-        Self::parse(content.as_ref())?.add_to_cx(cx)
+        Self::parse(content.as_ref())?
+            .with_source(asset_path, content.as_ref())
+            .add_to_cx(cx)
     }
 
     pub fn parse(content: &str) -> Result<Self> {
@@ -53,8 +58,60 @@ impl KeymapFile {
         parse_json_with_comments::<Self>(content)
     }
 
+    /// Describes the source of each binding as the given file name, followed by the line of
+    /// the binding within the given content, which the keymap has to be parsed from. The
+    /// bindings whose line can't be found are described by the file name alone.
+    pub fn with_source(mut self, file_name: &str, content: &str) -> Self {
+        let line_starts = content
+            .match_indices('\n')
+            .map(|(ix, _)| ix + 1)
+            .collect::<Vec<_>>();
+        // Each block has a single bindings key, so the blocks can be told apart by them, unless
+        // the comments mention other ones.
+        let block_starts = content
+            .match_indices("\"bindings\"")
+            .map(|(ix, _)| ix)
+            .collect::<Vec<_>>();
+        let block_count = self.0.len();
+        for (block_ix, block) in self.0.iter_mut().enumerate() {
+            let block_range = if block_starts.len() == block_count {
+                block_starts[block_ix]
+                    ..block_starts
+                        .get(block_ix + 1)
+                        .copied()
+                        .unwrap_or(content.len())
+            } else {
+                0..content.len()
+            };
+            for keystrokes in block.bindings.keys() {
+                let source = match binding_offset(&content[block_range.clone()], keystrokes) {
+                    Some(offset) => {
+                        let line = match line_starts.binary_search(&(block_range.start + offset)) {
+                            Ok(ix) => ix + 2,
+                            Err(ix) => ix + 1,
+                        };
+                        format!("{file_name}:{line}")
+                    }
+                    None => file_name.to_string(),
+                };
+                block
+                    .binding_sources
+                    .insert(keystrokes.clone(), source.into());
+            }
+        }
+        self
+    }
+
+    /// Binds the keymap's bindings. The bindings whose action or keystrokes are invalid are
+    /// skipped, and listed in the returned error once all the others were bound.
     pub fn add_to_cx(self, cx: &mut AppContext) -> Result<()> {
-        for KeymapBlock { context, bindings } in self.0 {
+        let mut errors = Vec::new();
+        for KeymapBlock {
+            context,
+            bindings,
+            binding_sources,
+        } in self.0
+        {
             let bindings = bindings
                 .into_iter()
                 .filter_map(|(keystroke, action)| {
@@ -64,39 +121,39 @@ impl KeymapFile {
                     // We want to deserialize the action data as a `RawValue` so that we can
                     // deserialize the action itself dynamically directly from the JSON
                     // string. But `RawValue` currently does not work inside of an untagged enum.
-                    match action {
-                        Value::Array(items) => {
-                            let Ok([name, data]): Result<[serde_json::Value; 2], _> =
-                                items.try_into()
-                            else {
-                                return Some(Err(anyhow!("Expected array of length 2")));
-                            };
-                            let serde_json::Value::String(name) = name else {
-                                return Some(Err(anyhow!(
-                                    "Expected first item in array to be a string."
-                                )));
-                            };
-                            cx.build_action(&name, Some(data))
-                        }
+                    let action = match action {
+                        Value::Array(items) => match <[Value; 2]>::try_from(items) {
+                            Ok([Value::String(name), data]) => cx.build_action(&name, Some(data)),
+                            Ok(_) => Err(anyhow!("Expected first item in array to be a string.")),
+                            Err(_) => Err(anyhow!("Expected array of length 2")),
+                        },
                         Value::String(name) => cx.build_action(&name, None),
                         Value::Null => Ok(no_action()),
-                        _ => {
-                            return Some(Err(anyhow!("Expected two-element array, got {action:?}")))
-                        }
-                    }
-                    .with_context(|| {
-                        format!(
-                            "invalid binding value for keystroke {keystroke}, context {context:?}"
-                        )
-                    })
-                    .log_err()
-                    .map(|action| KeyBinding::load(&keystroke, action, context.as_deref()))
+                        _ => Err(anyhow!("Expected two-element array, got {action:?}")),
+                    };
+                    action
+                        .and_then(|action| KeyBinding::load(&keystroke, action, context.as_deref()))
+                        .map(|binding| match binding_sources.get(&keystroke) {
+                            Some(source) => binding.with_source(source.clone()),
+                            None => binding,
+                        })
+                        .map_err(|error| {
+                            errors.push(format!(
+                                "keystroke {keystroke}, context {context:?}: {error:#}"
+                            ))
+                        })
+                        .ok()
                 })
-                .collect::<Result<Vec<_>>>()?;
+                .collect::<Vec<_>>();
 
             cx.bind_keys(bindings);
         }
-        Ok(())
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!("invalid key bindings:\n{}", errors.join("\n")))
+        }
     }
 
     pub fn generate_json_schema(action_names: &[SharedString]) -> serde_json::Value {
@@ -140,6 +197,18 @@ impl KeymapFile {
     }
 }
 
+/// Finds the offset of the key that binds the given keystrokes within the given part of a
+/// keymap file.
+fn binding_offset(content: &str, keystrokes: &str) -> Option<usize> {
+    let key = serde_json::to_string(keystrokes).ok()?;
+    content.match_indices(&key).find_map(|(offset, _)| {
+        content[offset + key.len()..]
+            .trim_start()
+            .starts_with(':')
+            .then_some(offset)
+    })
+}
+
 fn no_action() -> Box<dyn gpui::Action> {
     gpui::NoAction.boxed_clone()
 }
@@ -163,4 +232,71 @@ mod tests {
         };
         KeymapFile::parse(json).unwrap();
     }
+
+    #[gpui::test]
+    fn test_invalid_bindings_are_listed(cx: &mut gpui::AppContext) {
+        let json = indoc::indoc! {r#"[
+              {
+                "context": "Editor",
+                "bindings": {
+                  "ctrl-k ctrl-s": "zed::NoAction",
+                  "ctrl-x": "zed::DoesNotExist",
+                  "ctrl-a-b": "zed::NoAction",
+                  "ctrl-y": null,
+                },
+              },
+            ]"#
+        };
+        let error = KeymapFile::parse(json)
+            .unwrap()
+            .add_to_cx(cx)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("keystroke ctrl-x"), "{error}");
+        assert!(error.contains("keystroke ctrl-a-b"), "{error}");
+        assert!(!error.contains("keystroke ctrl-k ctrl-s"), "{error}");
+        assert!(!error.contains("keystroke ctrl-y"), "{error}");
+    }
+
+    #[gpui::test]
+    fn test_binding_sources(cx: &mut gpui::AppContext) {
+        let json = indoc::indoc! {r#"[
+              {
+                // The bindings all other ones build on
+                "bindings": {
+                  "ctrl-a": "zed::NoAction",
+                  "ctrl-b": null,
+                },
+              },
+              {
+                "context": "Editor",
+                "bindings": {
+                  "ctrl-b": "zed::NoAction",
+                  "ctrl-c": "zed::NoAction",
+                },
+              },
+            ]"#
+        };
+        let keymap = KeymapFile::parse(json)
+            .unwrap()
+            .with_source("keymap.json", json);
+        let sources = keymap
+            .0
+            .iter()
+            .map(|block| {
+                block
+                    .binding_sources
+                    .iter()
+                    .map(|(keystrokes, source)| (keystrokes.as_str(), source.as_ref()))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sources,
+            [
+                vec![("ctrl-a", "keymap.json:5"), ("ctrl-b", "keymap.json:6")],
+                vec![("ctrl-b", "keymap.json:12"), ("ctrl-c", "keymap.json:13")],
+            ]
+        );
+    }
 }
@@ -1,6 +1,7 @@
 mod keymap_file;
 mod settings_file;
 mod settings_store;
+mod settings_validation;
 
 use gpui::AppContext;
 use rust_embed::RustEmbed;
@@ -10,8 +11,10 @@ use util::asset_str;
 pub use keymap_file::KeymapFile;
 pub use settings_file::*;
 pub use settings_store::{
-    Settings, SettingsJsonSchemaParams, SettingsLocation, SettingsSources, SettingsStore,
+    parse_json_with_comments, Settings, SettingsJsonSchemaParams, SettingsLocation,
+    SettingsSources, SettingsStore,
 };
+pub use settings_validation::{SettingsProblem, SettingsProblemKind};
 
 #[derive(RustEmbed)]
 #[folder = "../../assets"]
@@ -4,7 +4,7 @@ use fs::Fs;
 use futures::{channel::mpsc, StreamExt};
 use gpui::{AppContext, BackgroundExecutor, UpdateGlobal};
 use std::{io::ErrorKind, path::PathBuf, sync::Arc, time::Duration};
-use util::{paths, ResultExt};
+use util::paths;
 
 pub const EMPTY_THEME_NAME: &str = "empty-theme";
 
@@ -62,29 +62,37 @@ pub fn watch_config_file(
     rx
 }
 
+/// Applies the user settings file, and every later version of it, to the [`SettingsStore`].
+///
+/// A version that fails to parse leaves the previous settings in place. Either way,
+/// `settings_changed` is told about the outcome, so that the error can be shown.
 pub fn handle_settings_file_changes(
     mut user_settings_file_rx: mpsc::UnboundedReceiver<String>,
     cx: &mut AppContext,
+    settings_changed: impl Fn(Option<anyhow::Error>, &mut AppContext) + 'static,
 ) {
     let user_settings_content = cx
         .background_executor()
         .block(user_settings_file_rx.next())
         .unwrap();
-    SettingsStore::update_global(cx, |store, cx| {
-        store
-            .set_user_settings(&user_settings_content, cx)
-            .log_err();
+    let result = SettingsStore::update_global(cx, |store, cx| {
+        store.set_user_settings(&user_settings_content, cx)
     });
+    settings_changed(result.err(), cx);
     cx.spawn(move |mut cx| async move {
         while let Some(user_settings_content) = user_settings_file_rx.next().await {
             let result = cx.update_global(|store: &mut SettingsStore, cx| {
-                store
-                    .set_user_settings(&user_settings_content, cx)
-                    .log_err();
+                let result = store.set_user_settings(&user_settings_content, cx);
                 cx.refresh();
+                result
             });
-            if result.is_err() {
-                break; // App dropped
+            match result {
+                Ok(result) => {
+                    if cx.update(|cx| settings_changed(result.err(), cx)).is_err() {
+                        break;
+                    }
+                }
+                Err(_) => break, // App dropped
             }
         }
     })
@@ -15,6 +15,11 @@ use std::{
 };
 use util::{merge_non_null_json_value_into, RangeExt, ResultExt as _};
 
+use crate::settings_validation::{validate_settings, SettingsProblem, SettingsProblemKind};
+
+/// The keys of the user settings that hold overrides for a release channel.
+const RELEASE_STAGES: [&str; 4] = ["dev", "nightly", "stable", "preview"];
+
 /// A value that can be defined as a user setting.
 ///
 /// Settings can be loaded from a combination of multiple JSON files.
@@ -231,18 +236,16 @@ impl SettingsStore {
             .deserialize_setting(&self.raw_default_settings)
             .log_err()
         {
-            let user_value = setting_value
-                .deserialize_setting(&self.raw_user_settings)
-                .log_err();
+            let user_value =
+                deserialize_user_setting(&**setting_value, &self.raw_user_settings, cx);
 
             let mut release_channel_value = None;
             if let Some(release_settings) = &self
                 .raw_user_settings
                 .get(release_channel::RELEASE_CHANNEL.dev_name())
             {
-                release_channel_value = setting_value
-                    .deserialize_setting(release_settings)
-                    .log_err();
+                release_channel_value =
+                    deserialize_user_setting(&**setting_value, release_settings, cx);
             }
 
             let extension_value = setting_value
@@ -444,6 +447,53 @@ impl SettingsStore {
         }
     }
 
+    /// Returns all of the problems with the user settings, found by validating them against the
+    /// schema of the registered settings.
+    ///
+    /// Keys that only unregistered settings read aren't reported as long as the default settings
+    /// have them. The values that don't match the schema are left out when loading the settings
+    /// that contain them, so the rest of those settings still apply.
+    pub fn user_settings_problems(&self, cx: &AppContext) -> Vec<SettingsProblem> {
+        let language_names = language_names_in(&self.raw_user_settings);
+        let schema = self.root_schema(&validation_schema_params(&language_names), cx);
+        let mut user_settings = self.raw_user_settings.clone();
+        let mut problems = validate_settings(&mut user_settings, &schema);
+        problems.retain(|problem| {
+            !matches!(problem.kind, SettingsProblemKind::UnknownKey { .. })
+                || problem.path.contains('.')
+                || self.raw_default_settings.get(&problem.path).is_none()
+        });
+        problems
+    }
+
+    /// Returns the top-level keys of the user settings that no setting reads.
+    pub fn unknown_user_settings_keys(&self) -> Vec<String> {
+        use release_channel::ReleaseChannel;
+
+        let Some(user_settings) = self.raw_user_settings.as_object() else {
+            return Vec::new();
+        };
+        let release_channel_names = [
+            ReleaseChannel::Dev,
+            ReleaseChannel::Nightly,
+            ReleaseChannel::Preview,
+            ReleaseChannel::Stable,
+        ]
+        .map(|channel| channel.dev_name());
+        user_settings
+            .keys()
+            .filter(|key| {
+                !release_channel_names.contains(&key.as_str())
+                    && self.raw_default_settings.get(key.as_str()).is_none()
+                    && !self
+                        .setting_values
+                        .values()
+                        .any(|setting| setting.key() == Some(key.as_str()))
+            })
+            .cloned()
+            .collect()
+    }
+
     /// Add or remove a set of local settings via a JSON string.
     pub fn set_local_settings(
         &mut self,
@@ -497,15 +547,13 @@ impl SettingsStore {
         schema_params: &SettingsJsonSchemaParams,
         cx: &AppContext,
     ) -> serde_json::Value {
-        use schemars::{
-            gen::SchemaSettings,
-            schema::{Schema, SchemaObject},
-        };
+        serde_json::to_value(self.root_schema(schema_params, cx)).unwrap()
+    }
 
-        let settings = SchemaSettings::draft07().with(|settings| {
-            settings.option_add_null_type = false;
-        });
-        let mut generator = SchemaGenerator::new(settings);
+    fn root_schema(&self, schema_params: &SettingsJsonSchemaParams, cx: &AppContext) -> RootSchema {
+        use schemars::schema::{Schema, SchemaObject};
+
+        let mut generator = schema_generator();
         let mut combined_schema = RootSchema::default();
 
         for setting_value in self.setting_values.values() {
@@ -587,7 +635,7 @@ impl SettingsStore {
             }
         }
 
-        for release_stage in ["dev", "nightly", "stable", "preview"] {
+        for release_stage in RELEASE_STAGES {
             let schema = combined_schema.schema.clone();
             combined_schema
                 .schema
@@ -596,7 +644,7 @@ impl SettingsStore {
                 .insert(release_stage.to_string(), schema.into());
         }
 
-        serde_json::to_value(&combined_schema).unwrap()
+        combined_schema
     }
 
     fn recompute_values(
@@ -614,21 +662,16 @@ impl SettingsStore {
                 .deserialize_setting(&self.raw_extension_settings)
                 .log_err();
 
-            let user_settings = setting_value
-                .deserialize_setting(&self.raw_user_settings)
-                .log_err();
+            let user_settings =
+                deserialize_user_setting(&**setting_value, &self.raw_user_settings, cx);
 
             let mut release_channel_settings = None;
             if let Some(release_settings) = &self
                 .raw_user_settings
                 .get(release_channel::RELEASE_CHANNEL.dev_name())
             {
-                if let Some(release_settings) = setting_value
-                    .deserialize_setting(release_settings)
-                    .log_err()
-                {
-                    release_channel_settings = Some(release_settings);
-                }
+                release_channel_settings =
+                    deserialize_user_setting(&**setting_value, release_settings, cx);
             }
 
             // If the global settings file changed, reload the global value for the field.
@@ -1026,6 +1069,84 @@ fn to_pretty_json(value: &impl Serialize, indent_size: usize, indent_prefix_len:
     adjusted_text
 }
 
+fn schema_generator() -> SchemaGenerator {
+    let settings = schemars::gen::SchemaSettings::draft07().with(|settings| {
+        settings.option_add_null_type = false;
+    });
+    SchemaGenerator::new(settings)
+}
+
+/// Returns the parameters of the schema that settings get validated against. All themes are
+/// allowed, and any font, as the fonts aren't known to the settings.
+fn validation_schema_params(language_names: &[String]) -> SettingsJsonSchemaParams<'_> {
+    SettingsJsonSchemaParams {
+        staff_mode: true,
+        language_names,
+        font_names: &[],
+    }
+}
+
+/// Returns the names of the languages that the given settings override settings for, so that
+/// those overrides get validated even for the languages that aren't loaded.
+fn language_names_in(settings: &serde_json::Value) -> Vec<String> {
+    let mut language_names = Vec::new();
+    let release_settings = RELEASE_STAGES
+        .iter()
+        .filter_map(|stage| settings.get(stage));
+    for settings in std::iter::once(settings).chain(release_settings) {
+        for key in ["languages", "language_overrides"] {
+            if let Some(languages) = settings
+                .get(key)
+                .and_then(|languages| languages.as_object())
+            {
+                language_names.extend(languages.keys().cloned());
+            }
+        }
+    }
+    language_names
+}
+
+/// Deserializes a setting from the user settings. If they don't deserialize, the values in them
+/// that don't match the schema of the setting are left out, so that its other values still apply.
+fn deserialize_user_setting(
+    setting_value: &dyn AnySettingValue,
+    user_settings: &serde_json::Value,
+    cx: &AppContext,
+) -> Option<DeserializedSetting> {
+    let error = match setting_value.deserialize_setting(user_settings) {
+        Ok(setting) => return Some(setting),
+        Err(error) => error,
+    };
+
+    let language_names = language_names_in(user_settings);
+    let schema = setting_value.json_schema(
+        &mut schema_generator(),
+        &validation_schema_params(&language_names),
+        cx,
+    );
+    let mut valid_settings = user_settings.clone();
+    match setting_value.key() {
+        Some(key) => {
+            if let Some(settings) = valid_settings.get_mut(key) {
+                let problems = validate_settings(settings, &schema);
+                // A setting whose whole value is invalid is left unset.
+                if problems.iter().any(|problem| problem.path.is_empty()) {
+                    if let Some(settings) = valid_settings.as_object_mut() {
+                        settings.remove(key);
+                    }
+                }
+            }
+        }
+        None => {
+            validate_settings(&mut valid_settings, &schema);
+        }
+    }
+    setting_value
+        .deserialize_setting(&valid_settings)
+        .map_err(|_| error)
+        .log_err()
+}
+
 pub fn parse_json_with_comments<T: DeserializeOwned>(content: &str) -> Result<T> {
     Ok(serde_json_lenient::from_str(content)?)
 }
@@ -1165,6 +1286,107 @@ mod tests {
         );
     }
 
+    #[gpui::test]
+    fn test_unknown_user_settings_keys(cx: &mut AppContext) {
+        let mut store = SettingsStore::default();
+        store.register_setting::<UserSettings>(cx);
+        store.register_setting::<TurboSetting>(cx);
+        store.register_setting::<MultiKeySettings>(cx);
+        store
+            .set_default_settings(r#"{ "turbo": false, "key1": "" }"#, cx)
+            .unwrap();
+
+        // Misspelled keys are reported, but don't keep the rest from applying.
+        store
+            .set_user_settings(
+                r#"{
+                    "turbo": true,
+                    "trubo": false,
+                    "user": { "age": 31 },
+                    "key1": "a",
+                    "dev": { "turbo": true }
+                }"#,
+                cx,
+            )
+            .unwrap();
+        assert_eq!(
+            store.unknown_user_settings_keys(),
+            vec!["trubo".to_string()]
+        );
+        assert_eq!(store.get::<TurboSetting>(None), &TurboSetting(true));
+
+        // A file that fails to parse leaves the previous settings in place.
+        assert!(store.set_user_settings(r#"{ "turbo": false"#, cx).is_err());
+        assert_eq!(store.get::<TurboSetting>(None), &TurboSetting(true));
+    }
+
+    #[gpui::test]
+    fn test_user_settings_problems(cx: &mut AppContext) {
+        let mut store = SettingsStore::default();
+        store.register_setting::<UserSettings>(cx);
+        store.register_setting::<TurboSetting>(cx);
+        store.register_setting::<JournalSettings>(cx);
+        store
+            .set_default_settings(
+                r#"{
+                    "turbo": false,
+                    "user": { "name": "John Doe", "age": 30, "staff": false },
+                    "journal": { "path": "~", "hour_format": "hour12" },
+                    "key1": ""
+                }"#,
+                cx,
+            )
+            .unwrap();
+
+        // All of the problems are reported, and the valid values around them still apply.
+        store
+            .set_user_settings(
+                r#"{
+                    "turbo": "yes",
+                    "user": { "name": "Jane Doe", "age": "old", "staf": true },
+                    "journal": { "path": "/journal", "hour_format": "hour_13" },
+                    "key1": "a",
+                    "jornal": {}
+                }"#,
+                cx,
+            )
+            .unwrap();
+        let problems = store
+            .user_settings_problems(cx)
+            .into_iter()
+            .map(|problem| problem.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            problems,
+            [
+                "`turbo` should be a boolean, but is \"yes\"",
+                "`user.age` should be an integer, but is \"old\"",
+                "unknown setting `user.staf`, did you mean `staff`?",
+                "`journal.hour_format` should be one of \"hour12\", \"hour24\", but is \"hour_13\", did you mean \"hour12\"?",
+                "unknown setting `jornal`, did you mean `journal`?",
+            ]
+        );
+        assert_eq!(store.get::<TurboSetting>(None), &TurboSetting(false));
+        assert_eq!(
+            store.get::<UserSettings>(None),
+            &UserSettings {
+                name: "Jane Doe".to_string(),
+                age: 30,
+                staff: false,
+            }
+        );
+        assert_eq!(store.get::<JournalSettings>(None).path, "/journal");
+        assert!(matches!(
+            store.get::<JournalSettings>(None).hour_format,
+            HourFormat::Hour12
+        ));
+
+        store
+            .set_user_settings(r#"{ "user": { "staff": true } }"#, cx)
+            .unwrap();
+        assert_eq!(store.user_settings_problems(cx), Vec::new());
+    }
+
     #[gpui::test]
     fn test_setting_store_assign_json_before_register(cx: &mut AppContext) {
         let mut store = SettingsStore::default();
@@ -0,0 +1,549 @@
+use schemars::schema::{
+    InstanceType, ObjectValidation, RootSchema, Schema, SchemaObject, SingleOrVec,
+};
+use serde_json::Value;
+use std::fmt;
+
+/// The most allowed values listed in the message of an [`SettingsProblemKind::InvalidValue`].
+const MAX_LISTED_VALUES: usize = 8;
+/// The longest a value gets shown in the message of a problem, in characters.
+const MAX_SHOWN_VALUE_LEN: usize = 40;
+
+/// Something wrong with a settings file, at the given path of keys within it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SettingsProblem {
+    /// The keys leading to the problematic value, joined by dots, like `languages.Rust.tab_size`.
+    pub path: String,
+    pub kind: SettingsProblemKind,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SettingsProblemKind {
+    /// No setting reads the key, along with the nearest key that one does, if there's one close
+    /// enough to be a typo of it.
+    UnknownKey { suggestion: Option<String> },
+    /// The value has a different type than the setting expects.
+    TypeMismatch { expected: String, found: Value },
+    /// The value is none of the ones the setting allows, along with the nearest allowed one.
+    InvalidValue {
+        allowed: Vec<String>,
+        found: Value,
+        suggestion: Option<String>,
+    },
+}
+
+impl fmt::Display for SettingsProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            SettingsProblemKind::UnknownKey { suggestion } => {
+                write!(f, "unknown setting `{}`", self.path)?;
+                if let Some(suggestion) = suggestion {
+                    write!(f, ", did you mean `{suggestion}`?")?;
+                }
+                Ok(())
+            }
+            SettingsProblemKind::TypeMismatch { expected, found } => {
+                write!(
+                    f,
+                    "`{}` should be {expected}, but is {}",
+                    self.path,
+                    shown_value(found)
+                )
+            }
+            SettingsProblemKind::InvalidValue {
+                allowed,
+                found,
+                suggestion,
+            } => {
+                write!(f, "`{}` should be one of ", self.path)?;
+                for (ix, value) in allowed.iter().take(MAX_LISTED_VALUES).enumerate() {
+                    if ix > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{value:?}")?;
+                }
+                if allowed.len() > MAX_LISTED_VALUES {
+                    write!(f, ", ...")?;
+                }
+                write!(f, ", but is {}", shown_value(found))?;
+                if let Some(suggestion) = suggestion {
+                    write!(f, ", did you mean {suggestion:?}?")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn shown_value(value: &Value) -> String {
+    let text = value.to_string();
+    if text.chars().count() > MAX_SHOWN_VALUE_LEN {
+        let mut shown = text.chars().take(MAX_SHOWN_VALUE_LEN).collect::<String>();
+        shown.push('…');
+        shown
+    } else {
+        text
+    }
+}
+
+/// Checks a settings document against the schema of the settings, and returns all of the
+/// problems found in it.
+///
+/// The values that don't match their schema get removed from the document, so that the rest of
+/// it can still be deserialized. Unknown keys are left in place, as they don't keep anything
+/// from deserializing. Enums without any values are treated as allowing any value, for the
+/// lists of names that aren't known when validating.
+pub fn validate_settings(settings: &mut Value, schema: &RootSchema) -> Vec<SettingsProblem> {
+    let mut validator = Validator {
+        root: schema,
+        problems: Vec::new(),
+    };
+    validator.validate(settings, &schema.schema, &mut Vec::new());
+    validator.problems
+}
+
+struct Validator<'a> {
+    root: &'a RootSchema,
+    problems: Vec<SettingsProblem>,
+}
+
+impl<'a> Validator<'a> {
+    /// Returns the schema object a schema refers to, or `None` for a schema that allows anything.
+    fn resolve(&self, schema: &'a Schema) -> Option<&'a SchemaObject> {
+        match schema {
+            Schema::Object(object) => self.resolve_object(object),
+            Schema::Bool(_) => None,
+        }
+    }
+
+    fn resolve_object(&self, mut object: &'a SchemaObject) -> Option<&'a SchemaObject> {
+        while let Some(reference) = &object.reference {
+            let name = reference.strip_prefix("#/definitions/")?;
+            match self.root.definitions.get(name)? {
+                Schema::Object(definition) => object = definition,
+                Schema::Bool(_) => return None,
+            }
+        }
+        Some(object)
+    }
+
+    fn report(&mut self, path: &[String], kind: SettingsProblemKind) {
+        self.problems.push(SettingsProblem {
+            path: path.join("."),
+            kind,
+        });
+    }
+
+    /// Validates a value and what it contains, removing the contents that don't match their
+    /// schema. Returns whether the value itself matches the schema.
+    fn validate(
+        &mut self,
+        value: &mut Value,
+        schema: &'a SchemaObject,
+        path: &mut Vec<String>,
+    ) -> bool {
+        let Some(schema) = self.resolve_object(schema) else {
+            return true;
+        };
+        // Settings that are written as null are left unset.
+        if value.is_null() {
+            return true;
+        }
+
+        if let Some(subschemas) = &schema.subschemas {
+            for subschema in subschemas.all_of.iter().flatten() {
+                if let Some(subschema) = self.resolve(subschema) {
+                    if !self.validate(value, subschema, path) {
+                        return false;
+                    }
+                }
+            }
+            let variants = subschemas
+                .any_of
+                .iter()
+                .chain(&subschemas.one_of)
+                .flatten()
+                .map(|variant| self.resolve(variant))
+                .collect::<Option<Vec<_>>>();
+            if let Some(variants) = variants.filter(|variants| !variants.is_empty()) {
+                if !self.validate_variants(value, &variants, path) {
+                    return false;
+                }
+            }
+        }
+
+        if let Some(instance_type) = &schema.instance_type {
+            let types = match instance_type {
+                SingleOrVec::Single(instance_type) => std::slice::from_ref(instance_type.as_ref()),
+                SingleOrVec::Vec(types) => types.as_slice(),
+            };
+            if !types
+                .iter()
+                .any(|instance_type| has_type(value, *instance_type))
+            {
+                self.report(
+                    path,
+                    SettingsProblemKind::TypeMismatch {
+                        expected: describe_types(types.iter().copied()),
+                        found: value.clone(),
+                    },
+                );
+                return false;
+            }
+        }
+
+        let allowed = allowed_values(schema);
+        if !allowed.is_empty() && !allowed.contains(&&*value) {
+            self.report_invalid_value(value, allowed, path);
+            return false;
+        }
+
+        match value {
+            Value::Object(map) => {
+                if let Some(object) = &schema.object {
+                    self.validate_object(map, object, path);
+                }
+            }
+            Value::Array(items) => {
+                let item_schema = schema.array.as_ref().and_then(|array| array.items.as_ref());
+                if let Some(SingleOrVec::Single(item_schema)) = item_schema {
+                    if let Some(item_schema) = self.resolve(item_schema) {
+                        let mut ix = 0;
+                        items.retain_mut(|item| {
+                            path.push(ix.to_string());
+                            ix += 1;
+                            let is_valid = self.validate(item, item_schema, path);
+                            path.pop();
+                            is_valid
+                        });
+                    }
+                }
+            }
+            _ => {}
+        }
+        true
+    }
+
+    fn validate_object(
+        &mut self,
+        map: &mut serde_json::Map<String, Value>,
+        object: &'a ObjectValidation,
+        path: &mut Vec<String>,
+    ) {
+        let additional_properties = object
+            .additional_properties
+            .as_deref()
+            .filter(|schema| !matches!(schema, Schema::Bool(false)));
+        // Objects without properties, like the ones keyed by names, can't be checked for unknown
+        // keys. Neither can ones with patterns for their keys.
+        let checks_keys = !object.properties.is_empty() && object.pattern_properties.is_empty();
+
+        let keys = map.keys().cloned().collect::<Vec<_>>();
+        for key in keys {
+            path.push(key.clone());
+            match object.properties.get(&key).or(additional_properties) {
+                Some(property_schema) => {
+                    if let Some(property_schema) = self.resolve(property_schema) {
+                        let is_valid = map
+                            .get_mut(&key)
+                            .map_or(true, |value| self.validate(value, property_schema, path));
+                        if !is_valid {
+                            map.remove(&key);
+                        }
+                    }
+                }
+                None if checks_keys => {
+                    let suggestion = closest_match(&key, object.properties.keys());
+                    self.report(path, SettingsProblemKind::UnknownKey { suggestion });
+                }
+                None => {}
+            }
+            path.pop();
+        }
+    }
+
+    /// Validates a value against the first of the variants it fully matches, or else the first
+    /// one it has the type of, so that the problems within it get reported from the variant it
+    /// was most likely meant to be.
+    fn validate_variants(
+        &mut self,
+        value: &mut Value,
+        variants: &[&'a SchemaObject],
+        path: &mut Vec<String>,
+    ) -> bool {
+        let mut best_variant = None;
+        for variant in variants {
+            let mut trial = Validator {
+                root: self.root,
+                problems: Vec::new(),
+            };
+            if trial.validate(&mut value.clone(), variant, &mut path.clone()) {
+                if trial.problems.is_empty() {
+                    best_variant = Some(*variant);
+                    break;
+                }
+                best_variant.get_or_insert(*variant);
+            }
+        }
+        if let Some(variant) = best_variant {
+            return self.validate(value, variant, path);
+        }
+
+        let allowed = variants
+            .iter()
+            .flat_map(|variant| allowed_values(variant))
+            .collect::<Vec<_>>();
+        if value.is_string() && allowed.iter().any(|allowed| allowed.is_string()) {
+            self.report_invalid_value(value, allowed, path);
+        } else {
+            let mut types = Vec::new();
+            for variant in variants {
+                match &variant.instance_type {
+                    Some(SingleOrVec::Single(instance_type)) => types.push(**instance_type),
+                    Some(SingleOrVec::Vec(variant_types)) => types.extend(variant_types),
+                    None => types.extend(allowed_values(variant).into_iter().map(type_of)),
+                }
+            }
+            types.sort();
+            types.dedup();
+            self.report(
+                path,
+                SettingsProblemKind::TypeMismatch {
+                    expected: describe_types(types),
+                    found: value.clone(),
+                },
+            );
+        }
+        false
+    }
+
+    fn report_invalid_value(&mut self, value: &Value, allowed: Vec<&Value>, path: &[String]) {
+        let allowed = allowed
+            .into_iter()
+            .map(|allowed| match allowed {
+                Value::String(allowed) => allowed.clone(),
+                allowed => allowed.to_string(),
+            })
+            .collect::<Vec<_>>();
+        let suggestion = value
+            .as_str()
+            .and_then(|value| closest_match(value, allowed.iter()));
+        self.report(
+            path,
+            SettingsProblemKind::InvalidValue {
+                allowed,
+                found: value.clone(),
+                suggestion,
+            },
+        );
+    }
+}
+
+fn allowed_values(schema: &SchemaObject) -> Vec<&Value> {
+    schema
+        .enum_values
+        .iter()
+        .flatten()
+        .chain(&schema.const_value)
+        .collect()
+}
+
+fn has_type(value: &Value, instance_type: InstanceType) -> bool {
+    match instance_type {
+        InstanceType::Null => value.is_null(),
+        InstanceType::Boolean => value.is_boolean(),
+        InstanceType::Object => value.is_object(),
+        InstanceType::Array => value.is_array(),
+        InstanceType::Number => value.is_number(),
+        InstanceType::String => value.is_string(),
+        InstanceType::Integer => value.is_i64() || value.is_u64(),
+    }
+}
+
+fn type_of(value: &Value) -> InstanceType {
+    match value {
+        Value::Null => InstanceType::Null,
+        Value::Bool(_) => InstanceType::Boolean,
+        Value::Number(_) => InstanceType::Number,
+        Value::String(_) => InstanceType::String,
+        Value::Array(_) => InstanceType::Array,
+        Value::Object(_) => InstanceType::Object,
+    }
+}
+
+fn describe_types(types: impl IntoIterator<Item = InstanceType>) -> String {
+    let descriptions = types
+        .into_iter()
+        .map(|instance_type| match instance_type {
+            InstanceType::Null => "null",
+            InstanceType::Boolean => "a boolean",
+            InstanceType::Object => "an object",
+            InstanceType::Array => "an array",
+            InstanceType::Number => "a number",
+            InstanceType::String => "a string",
+            InstanceType::Integer => "an integer",
+        })
+        .collect::<Vec<_>>();
+    if descriptions.is_empty() {
+        "a different value".to_string()
+    } else {
+        descriptions.join(" or ")
+    }
+}
+
+/// Returns the candidate nearest to the given text, if it's close enough for the text to be a
+/// typo of it.
+fn closest_match<'b>(text: &str, candidates: impl Iterator<Item = &'b String>) -> Option<String> {
+    let max_distance = (text.chars().count() / 3).max(1);
+    candidates
+        .map(|candidate| (edit_distance(text, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate.clone())
+}
+
+/// Returns the number of insertions, deletions, substitutions and transpositions of adjacent
+/// characters it takes to turn one text into the other.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+    let mut rows_before = vec![0; b.len() + 1];
+    let mut previous_row = (0..=b.len()).collect::<Vec<_>>();
+    for i in 1..=a.len() {
+        let mut row = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let substitution_cost = (a[i - 1] != b[j - 1]) as usize;
+            row[j] = (previous_row[j] + 1)
+                .min(row[j - 1] + 1)
+                .min(previous_row[j - 1] + substitution_cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(rows_before[j - 2] + 1);
+            }
+        }
+        rows_before = std::mem::replace(&mut previous_row, row);
+    }
+    previous_row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use collections::HashMap;
+    use schemars::{gen::SchemaSettings, JsonSchema};
+    use serde_derive::Deserialize;
+    use serde_json::json;
+
+    #[allow(dead_code)]
+    #[derive(Deserialize, JsonSchema)]
+    struct TestSettings {
+        tab_size: Option<u32>,
+        /// Whether to save automatically.
+        autosave: Option<Autosave>,
+        line_ending: Option<LineEnding>,
+        formatter: Option<Vec<String>>,
+        languages: Option<HashMap<String, TestLanguageSettings>>,
+    }
+
+    #[allow(dead_code)]
+    #[derive(Deserialize, JsonSchema)]
+    #[serde(rename_all = "snake_case")]
+    enum Autosave {
+        /// Never save automatically.
+        Off,
+        AfterDelay {
+            milliseconds: u64,
+        },
+        OnFocusChange,
+    }
+
+    #[allow(dead_code)]
+    #[derive(Deserialize, JsonSchema)]
+    #[serde(rename_all = "snake_case")]
+    enum LineEnding {
+        Unix,
+        Windows,
+    }
+
+    #[allow(dead_code)]
+    #[derive(Deserialize, JsonSchema)]
+    struct TestLanguageSettings {
+        tab_size: Option<u32>,
+        hard_tabs: Option<bool>,
+    }
+
+    fn validate(mut settings: Value) -> (Value, Vec<String>) {
+        let generator = SchemaSettings::draft07()
+            .with(|settings| settings.option_add_null_type = false)
+            .into_generator();
+        let schema = generator.into_root_schema_for::<TestSettings>();
+        let mut problems = validate_settings(&mut settings, &schema)
+            .into_iter()
+            .map(|problem| problem.to_string())
+            .collect::<Vec<_>>();
+        problems.sort();
+        (settings, problems)
+    }
+
+    #[test]
+    fn test_validate_settings() {
+        let (settings, problems) = validate(json!({
+            "tab_size": 2,
+            "autosave": { "after_delay": { "milliseconds": 500 } },
+            "line_ending": "windows",
+            "formatter": ["rustfmt"],
+            "languages": { "Rust": { "hard_tabs": true, "tab_size": null } },
+        }));
+        assert_eq!(problems, Vec::<String>::new());
+        assert_eq!(settings["autosave"]["after_delay"]["milliseconds"], 500);
+        assert_eq!(settings["languages"]["Rust"]["hard_tabs"], true);
+    }
+
+    #[test]
+    fn test_validate_settings_problems() {
+        let (settings, problems) = validate(json!({
+            "tab_sise": 2,
+            "autosave": "of",
+            "line_ending": "mac",
+            "formatter": ["rustfmt", 3],
+            "languages": {
+                "Rust": { "tab_size": "four", "hard_tab": true, "hard_tabs": false }
+            },
+        }));
+        assert_eq!(
+            problems,
+            [
+                "`autosave` should be one of \"off\", \"on_focus_change\", but is \"of\", did you mean \"off\"?",
+                "`formatter.1` should be a string, but is 3",
+                "`languages.Rust.tab_size` should be an integer, but is \"four\"",
+                "`line_ending` should be one of \"unix\", \"windows\", but is \"mac\"",
+                "unknown setting `languages.Rust.hard_tab`, did you mean `hard_tabs`?",
+                "unknown setting `tab_sise`, did you mean `tab_size`?",
+            ]
+        );
+
+        // The invalid values are dropped, and the rest of the settings are kept.
+        assert_eq!(
+            settings,
+            json!({
+                "tab_sise": 2,
+                "formatter": ["rustfmt"],
+                "languages": { "Rust": { "hard_tab": true, "hard_tabs": false } },
+            })
+        );
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("turbo", "turbo"), 0);
+        assert_eq!(edit_distance("trubo", "turbo"), 1);
+        assert_eq!(edit_distance("vim_mod", "vim_mode"), 1);
+        assert_eq!(edit_distance("tab_size", "tab_sise"), 1);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(
+            closest_match("thme", ["theme".into(), "tab_size".into()].iter()),
+            Some("theme".to_string())
+        );
+        assert_eq!(closest_match("xyz", ["theme".to_string()].iter()), None);
+    }
+}
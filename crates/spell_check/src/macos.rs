@@ -0,0 +1,115 @@
+use crate::SpellChecker;
+use cocoa::{
+    base::{id, nil},
+    foundation::{NSAutoreleasePool, NSInteger, NSRange, NSString, NSUInteger},
+};
+use objc::{class, msg_send, sel, sel_impl};
+use std::{ffi::CStr, ops::Range, ptr};
+
+/// `NSTextCheckingTypeSpelling`.
+const TEXT_CHECKING_TYPE_SPELLING: u64 = 1 << 1;
+
+/// Checks spelling with `NSSpellChecker`, in the languages set up in the system's settings.
+pub(crate) struct SystemSpellChecker;
+
+impl SpellChecker for SystemSpellChecker {
+    fn misspelled_words(&self, text: &str) -> Vec<Range<usize>> {
+        let mut utf16_ranges = Vec::new();
+        unsafe {
+            let pool = NSAutoreleasePool::new(nil);
+            let checker: id = msg_send![class!(NSSpellChecker), sharedSpellChecker];
+            let string = NSString::alloc(nil).init_str(text).autorelease();
+            let length: NSUInteger = msg_send![string, length];
+            // Unlike most methods of `NSSpellChecker`, this one can be called from any thread,
+            // which lets large buffers get checked in the background.
+            let results: id = msg_send![
+                checker,
+                checkString: string
+                range: NSRange::new(0, length)
+                types: TEXT_CHECKING_TYPE_SPELLING
+                options: nil
+                inSpellDocumentWithTag: 0 as NSInteger
+                orthography: ptr::null_mut::<id>()
+                wordCount: ptr::null_mut::<NSInteger>()
+            ];
+            let count: NSUInteger = msg_send![results, count];
+            for ix in 0..count {
+                let result: id = msg_send![results, objectAtIndex: ix];
+                let range: NSRange = msg_send![result, range];
+                let start = range.location as usize;
+                utf16_ranges.push(start..start + range.length as usize);
+            }
+            pool.drain();
+        }
+        byte_ranges(text, &utf16_ranges)
+    }
+
+    fn suggestions(&self, word: &str) -> Vec<String> {
+        let mut suggestions = Vec::new();
+        unsafe {
+            let pool = NSAutoreleasePool::new(nil);
+            let checker: id = msg_send![class!(NSSpellChecker), sharedSpellChecker];
+            let string = NSString::alloc(nil).init_str(word).autorelease();
+            let length: NSUInteger = msg_send![string, length];
+            let guesses: id = msg_send![
+                checker,
+                guessesForWordRange: NSRange::new(0, length)
+                inString: string
+                language: nil
+                inSpellDocumentWithTag: 0 as NSInteger
+            ];
+            if guesses != nil {
+                let count: NSUInteger = msg_send![guesses, count];
+                for ix in 0..count {
+                    let guess: id = msg_send![guesses, objectAtIndex: ix];
+                    let guess = CStr::from_ptr(guess.UTF8String());
+                    suggestions.push(guess.to_string_lossy().into_owned());
+                }
+            }
+            pool.drain();
+        }
+        suggestions
+    }
+}
+
+/// Converts sorted ranges of UTF-16 offsets into the text, which is how `NSString` measures
+/// text, into byte ranges.
+fn byte_ranges(text: &str, utf16_ranges: &[Range<usize>]) -> Vec<Range<usize>> {
+    let mut chars = text.char_indices();
+    let mut utf16_offset = 0;
+    let mut byte_offset = 0;
+    let mut byte_offset_for = |target: usize| {
+        while utf16_offset < target {
+            let Some((ix, c)) = chars.next() else {
+                break;
+            };
+            utf16_offset += c.len_utf16();
+            byte_offset = ix + c.len_utf8();
+        }
+        byte_offset
+    };
+    utf16_ranges
+        .iter()
+        .map(|range| {
+            let start = byte_offset_for(range.start);
+            start..byte_offset_for(range.end)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_byte_ranges() {
+        let text = "🦀 crabz and naïve wrods";
+        assert_eq!(
+            byte_ranges(text, &[3..8, 19..24])
+                .into_iter()
+                .map(|range| &text[range])
+                .collect::<Vec<_>>(),
+            ["crabz", "wrods"]
+        );
+    }
+}
@@ -0,0 +1,244 @@
+#[cfg(target_os = "macos")]
+mod macos;
+
+use anyhow::{anyhow, Result};
+use collections::HashSet;
+use fs::Fs;
+use futures::{
+    channel::{mpsc, oneshot},
+    StreamExt as _,
+};
+use gpui::{AppContext, Global, Task};
+use std::{ops::Range, sync::Arc};
+use util::{paths, ResultExt as _};
+
+/// Finds the misspelled words of some text, and suggests what they were meant to be.
+pub trait SpellChecker: Send + Sync {
+    /// Returns the byte ranges of the misspelled words in the text, in order.
+    fn misspelled_words(&self, text: &str) -> Vec<Range<usize>>;
+
+    /// Returns the words that a misspelled word might have been meant to be, likeliest first.
+    fn suggestions(&self, word: &str) -> Vec<String>;
+}
+
+struct GlobalSpellChecker(Arc<dyn SpellChecker>);
+
+impl Global for GlobalSpellChecker {}
+
+/// Sets up the spell checker of the system, if there is one, along with the user's dictionary.
+pub fn init(fs: Arc<dyn Fs>, cx: &mut AppContext) {
+    #[cfg(target_os = "macos")]
+    set_global(Arc::new(macos::SystemSpellChecker), cx);
+
+    let (save_tx, mut save_rx) = mpsc::unbounded::<oneshot::Sender<Result<()>>>();
+    cx.set_global(UserDictionary {
+        words: Default::default(),
+        save_tx: Some(save_tx),
+    });
+    cx.spawn(|cx| async move {
+        // The words added before the dictionary got loaded are kept, and saved along with it.
+        let words = UserDictionary::load(fs.as_ref())
+            .await
+            .log_err()
+            .unwrap_or_default();
+        cx.update_global::<UserDictionary, _>(|dictionary, _| {
+            Arc::make_mut(&mut dictionary.words).extend(words);
+        })
+        .ok();
+
+        // Saves happen one after the other and write the words as of when they start, so that
+        // an older save never overwrites a newer one.
+        while let Some(done) = save_rx.next().await {
+            let Ok(text) = cx.read_global::<UserDictionary, _>(|dictionary, _| dictionary.text())
+            else {
+                break;
+            };
+            let result = async {
+                fs.create_dir(&paths::CONFIG_DIR).await?;
+                fs.atomic_write(paths::DICTIONARY.clone(), text).await
+            }
+            .await;
+            done.send(result).ok();
+        }
+    })
+    .detach();
+}
+
+/// Returns the spell checker that editors use, if there is one.
+pub fn global(cx: &AppContext) -> Option<Arc<dyn SpellChecker>> {
+    cx.try_global::<GlobalSpellChecker>()
+        .map(|checker| checker.0.clone())
+}
+
+pub fn set_global(checker: Arc<dyn SpellChecker>, cx: &mut AppContext) {
+    cx.set_global(GlobalSpellChecker(checker));
+}
+
+/// The words that the user added to their dictionary, which never count as misspelled. They're
+/// stored one per line, in lowercase.
+#[derive(Default)]
+pub struct UserDictionary {
+    words: Arc<HashSet<String>>,
+    /// Requests a save from the task that saves the dictionary.
+    save_tx: Option<mpsc::UnboundedSender<oneshot::Sender<Result<()>>>>,
+}
+
+impl Global for UserDictionary {}
+
+impl UserDictionary {
+    async fn load(fs: &dyn Fs) -> Result<HashSet<String>> {
+        if !fs.is_file(&paths::DICTIONARY).await {
+            return Ok(HashSet::default());
+        }
+        let text = fs.load(&paths::DICTIONARY).await?;
+        Ok(text
+            .lines()
+            .map(|line| line.trim().to_lowercase())
+            .filter(|word| !word.is_empty())
+            .collect())
+    }
+
+    pub fn words(cx: &AppContext) -> Arc<HashSet<String>> {
+        cx.try_global::<Self>()
+            .map(|dictionary| dictionary.words.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn contains(words: &HashSet<String>, word: &str) -> bool {
+        words.contains(&word.to_lowercase())
+    }
+
+    fn text(&self) -> String {
+        let mut words = self.words.iter().cloned().collect::<Vec<_>>();
+        words.sort_unstable();
+        let mut text = words.join("\n");
+        text.push('\n');
+        text
+    }
+
+    /// Adds a word to the dictionary, and saves the dictionary.
+    pub fn add_word(word: &str, cx: &mut AppContext) -> Task<Result<()>> {
+        let word = word.trim().to_lowercase();
+        if word.is_empty() {
+            return Task::ready(Ok(()));
+        }
+        let dictionary = cx.default_global::<Self>();
+        if !Arc::make_mut(&mut dictionary.words).insert(word) {
+            return Task::ready(Ok(()));
+        }
+        let Some(save_tx) = dictionary.save_tx.as_ref() else {
+            return Task::ready(Ok(()));
+        };
+
+        let (done_tx, done_rx) = oneshot::channel();
+        if save_tx.unbounded_send(done_tx).is_err() {
+            return Task::ready(Err(anyhow!("the dictionary is no longer being saved")));
+        }
+        cx.background_executor()
+            .spawn(async move { done_rx.await? })
+    }
+}
+
+/// A spell checker that knows a fixed set of words, for tests.
+#[cfg(any(test, feature = "test-support"))]
+pub struct FakeSpellChecker {
+    words: HashSet<String>,
+    suggestions: collections::HashMap<String, Vec<String>>,
+}
+
+#[cfg(any(test, feature = "test-support"))]
+impl FakeSpellChecker {
+    pub fn new<'a>(words: impl IntoIterator<Item = &'a str>) -> Self {
+        Self {
+            words: words.into_iter().map(|word| word.to_lowercase()).collect(),
+            suggestions: Default::default(),
+        }
+    }
+
+    pub fn with_suggestions(mut self, word: &str, suggestions: &[&str]) -> Self {
+        self.suggestions.insert(
+            word.to_string(),
+            suggestions.iter().map(|s| s.to_string()).collect(),
+        );
+        self
+    }
+}
+
+#[cfg(any(test, feature = "test-support"))]
+impl SpellChecker for FakeSpellChecker {
+    fn misspelled_words(&self, text: &str) -> Vec<Range<usize>> {
+        let mut ranges = Vec::new();
+        let mut word_start = None;
+        for (ix, c) in text.char_indices().chain([(text.len(), ' ')]) {
+            if c.is_alphabetic() {
+                word_start.get_or_insert(ix);
+            } else if let Some(start) = word_start.take() {
+                if !self.words.contains(&text[start..ix].to_lowercase()) {
+                    ranges.push(start..ix);
+                }
+            }
+        }
+        ranges
+    }
+
+    fn suggestions(&self, word: &str) -> Vec<String> {
+        self.suggestions.get(word).cloned().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fs::FakeFs;
+    use gpui::TestAppContext;
+
+    #[test]
+    fn test_fake_spell_checker() {
+        let checker = FakeSpellChecker::new(["the", "quick", "fox"]);
+        let text = "The quikc brown fox";
+        let ranges = checker.misspelled_words(text);
+        assert_eq!(
+            ranges
+                .into_iter()
+                .map(|range| &text[range])
+                .collect::<Vec<_>>(),
+            ["quikc", "brown"]
+        );
+    }
+
+    #[gpui::test]
+    async fn test_user_dictionary(cx: &mut TestAppContext) {
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_file(&*paths::DICTIONARY, b"Zed\n\ngpui\n".to_vec())
+            .await;
+        cx.update(|cx| init(fs.clone(), cx));
+        cx.run_until_parked();
+
+        cx.update(|cx| {
+            let words = UserDictionary::words(cx);
+            assert!(UserDictionary::contains(&words, "zed"));
+            assert!(UserDictionary::contains(&words, "GPUI"));
+            assert!(!UserDictionary::contains(&words, "tokio"));
+        });
+
+        // Words added at the same time all get saved.
+        let (first_save, second_save) = cx.update(|cx| {
+            (
+                UserDictionary::add_word("Tokio", cx),
+                UserDictionary::add_word("smol", cx),
+            )
+        });
+        first_save.await.unwrap();
+        second_save.await.unwrap();
+        cx.update(|cx| {
+            assert!(UserDictionary::contains(
+                &UserDictionary::words(cx),
+                "tokio"
+            ));
+        });
+        assert_eq!(
+            fs.load(&paths::DICTIONARY).await.unwrap(),
+            "gpui\nsmol\ntokio\nzed\n"
+        );
+    }
+}
@@ -0,0 +1,97 @@
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use std::cmp;
+use sum_tree::{Dimension, Item, KeyedItem, SumTree, Summary};
+
+#[derive(Clone, Debug)]
+struct Entry(u64);
+
+#[derive(Clone, Debug, Default)]
+struct EntrySummary {
+    count: usize,
+    max: u64,
+}
+
+impl Item for Entry {
+    type Summary = EntrySummary;
+
+    fn summary(&self) -> Self::Summary {
+        EntrySummary {
+            count: 1,
+            max: self.0,
+        }
+    }
+}
+
+impl KeyedItem for Entry {
+    type Key = u64;
+
+    fn key(&self) -> Self::Key {
+        self.0
+    }
+}
+
+impl Summary for EntrySummary {
+    type Context = ();
+
+    fn add_summary(&mut self, summary: &Self, _: &()) {
+        self.count += summary.count;
+        self.max = cmp::max(self.max, summary.max);
+    }
+}
+
+impl<'a> Dimension<'a, EntrySummary> for u64 {
+    fn add_summary(&mut self, summary: &'a EntrySummary, _: &()) {
+        *self = summary.max;
+    }
+}
+
+fn construction_benchmarks(c: &mut Criterion) {
+    let sizes = [10_000, 1_000_000];
+
+    let mut group = c.benchmark_group("construction");
+    group.sample_size(10);
+    for size in sizes {
+        group.throughput(Throughput::Elements(size));
+        let items = || (0..size).map(Entry).collect::<Vec<_>>();
+
+        group.bench_with_input(BenchmarkId::new("push", size), &size, |b, _| {
+            b.iter_batched(
+                items,
+                |items| {
+                    let mut tree = SumTree::new();
+                    for item in items {
+                        tree.push(item, &());
+                    }
+                    tree
+                },
+                BatchSize::LargeInput,
+            );
+        });
+        group.bench_with_input(
+            BenchmarkId::new("from_sorted_items", size),
+            &size,
+            |b, _| {
+                b.iter_batched(
+                    items,
+                    |items| SumTree::from_sorted_items(items, &()),
+                    BatchSize::LargeInput,
+                );
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("from_sorted_items_par", size),
+            &size,
+            |b, _| {
+                b.iter_batched(
+                    items,
+                    |items| SumTree::from_sorted_items_par(items, &()),
+                    BatchSize::LargeInput,
+                );
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, construction_benchmarks);
+criterion_main!(benches);
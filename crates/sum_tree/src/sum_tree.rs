@@ -6,14 +6,20 @@ pub use cursor::{Cursor, FilterCursor, Iter};
 use rayon::prelude::*;
 use std::marker::PhantomData;
 use std::mem;
-use std::{cmp::Ordering, fmt, iter::FromIterator, sync::Arc};
-pub use tree_map::{MapSeekTarget, TreeMap, TreeSet};
+use std::{cmp::Ordering, fmt, iter::FromIterator, ops::Range, sync::Arc};
+pub use tree_map::{MapSeekTarget, TreeMap, TreeMapCursor, TreeSet};
 
 #[cfg(test)]
 pub const TREE_BASE: usize = 2;
 #[cfg(not(test))]
 pub const TREE_BASE: usize = 6;
 
+/// Below this many items, building a tree in parallel costs more than it saves.
+#[cfg(test)]
+const PARALLEL_BUILD_THRESHOLD: usize = 4;
+#[cfg(not(test))]
+const PARALLEL_BUILD_THRESHOLD: usize = 1024;
+
 /// An item that can be stored in a [`SumTree`]
 ///
 /// Must be summarized by a type that implements [`Summary`]
@@ -405,6 +411,42 @@ impl<T: Item> SumTree<T> {
         }
     }
 
+    /// Returns a tree with the items between the two ends of `range`, both of which are
+    /// sought like [`Cursor::seek`] does. Subtrees that lie entirely within the range are
+    /// shared with this tree rather than cloned.
+    pub fn slice<'a, D, Target>(
+        &'a self,
+        range: &Range<Target>,
+        bias: Bias,
+        cx: &<T::Summary as Summary>::Context,
+    ) -> Self
+    where
+        D: Dimension<'a, T::Summary>,
+        Target: SeekTarget<'a, T::Summary, D>,
+    {
+        let mut cursor = self.cursor::<D>();
+        cursor.seek(&range.start, bias, cx);
+        cursor.slice(&range.end, bias, cx)
+    }
+
+    /// Returns the aggregate of the items between the two ends of `range`, which are sought
+    /// like [`Cursor::seek`] does. Only the nodes along the edges of the range are visited.
+    pub fn range_summary<'a, D, Target, Output>(
+        &'a self,
+        range: &Range<Target>,
+        bias: Bias,
+        cx: &<T::Summary as Summary>::Context,
+    ) -> Output
+    where
+        D: Dimension<'a, T::Summary>,
+        Target: SeekTarget<'a, T::Summary, D>,
+        Output: Dimension<'a, T::Summary>,
+    {
+        let mut cursor = self.cursor::<D>();
+        cursor.seek(&range.start, bias, cx);
+        cursor.summary(&range.end, bias, cx)
+    }
+
     pub fn is_empty(&self) -> bool {
         match self.0.as_ref() {
             Node::Internal { .. } => false,
@@ -629,6 +671,46 @@ impl<T: Item + PartialEq> PartialEq for SumTree<T> {
 impl<T: Item + Eq> Eq for SumTree<T> {}
 
 impl<T: KeyedItem> SumTree<T> {
+    /// Builds a tree from items sorted by their keys, bottom-up in O(n). This is much
+    /// cheaper than inserting the items one at a time.
+    pub fn from_sorted_items<I: IntoIterator<Item = T>>(
+        items: I,
+        cx: &<T::Summary as Summary>::Context,
+    ) -> Self {
+        let tree = Self::from_iter(items, cx);
+        tree.debug_assert_sorted();
+        tree
+    }
+
+    /// Like [`SumTree::from_sorted_items`], but builds the levels of large trees on
+    /// multiple threads.
+    pub fn from_sorted_items_par(items: Vec<T>, cx: &<T::Summary as Summary>::Context) -> Self
+    where
+        T: Send + Sync,
+        T::Summary: Send + Sync,
+        <T::Summary as Summary>::Context: Sync,
+    {
+        if items.len() < PARALLEL_BUILD_THRESHOLD {
+            return Self::from_sorted_items(items, cx);
+        }
+        let tree = Self::from_par_iter(items, cx);
+        tree.debug_assert_sorted();
+        tree
+    }
+
+    fn debug_assert_sorted(&self) {
+        #[cfg(debug_assertions)]
+        {
+            let mut items = self.iter();
+            if let Some(mut previous) = items.next() {
+                for item in items {
+                    debug_assert!(previous.key() < item.key(), "items are not sorted by key");
+                    previous = item;
+                }
+            }
+        }
+    }
+
     pub fn insert_or_replace(
         &mut self,
         item: T,
@@ -1027,6 +1109,195 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_random_range_queries() {
+        let mut starting_seed = 0;
+        if let Ok(value) = std::env::var("SEED") {
+            starting_seed = value.parse().expect("invalid SEED variable");
+        }
+        let mut num_iterations = 100;
+        if let Ok(value) = std::env::var("ITERATIONS") {
+            num_iterations = value.parse().expect("invalid ITERATIONS variable");
+        }
+
+        // The index of the item that seeking to `target` stops at, found linearly.
+        fn reference_seek(items: &[u8], target: usize, bias: Bias) -> usize {
+            let mut end = 0;
+            for (ix, item) in items.iter().enumerate() {
+                end += *item as usize;
+                if target < end || (target == end && bias == Bias::Left) {
+                    return ix;
+                }
+            }
+            items.len()
+        }
+
+        for seed in starting_seed..(starting_seed + num_iterations) {
+            eprintln!("seed = {}", seed);
+            let mut rng = StdRng::seed_from_u64(seed);
+
+            // Zero-sized items make the bias matter, so generate many of them.
+            let count = rng.gen_range(0..100);
+            let items = (0..count)
+                .map(|_| {
+                    if rng.gen_bool(0.3) {
+                        0
+                    } else {
+                        rng.gen_range(1..5)
+                    }
+                })
+                .collect::<Vec<u8>>();
+            let tree = SumTree::from_iter(items.iter().copied(), &());
+            let total = tree.extent::<Sum>(&()).0;
+            log::info!("tree items: {:?}", items);
+
+            for _ in 0..20 {
+                let bias = if rng.gen() { Bias::Left } else { Bias::Right };
+                let end = rng.gen_range(0..=total);
+                let start = rng.gen_range(0..=end);
+
+                let mut cursor = tree.cursor::<Sum>();
+                cursor.seek(&Sum(start), bias, &());
+                let start_ix = reference_seek(&items, start, bias);
+                assert_eq!(cursor.item(), items.get(start_ix));
+                assert_eq!(
+                    cursor.start().0,
+                    items[..start_ix]
+                        .iter()
+                        .map(|item| *item as usize)
+                        .sum::<usize>()
+                );
+
+                let end_ix = reference_seek(&items, end, bias).max(start_ix);
+                let expected_items = &items[start_ix..end_ix];
+                let slice = tree.slice::<Sum, _>(&(Sum(start)..Sum(end)), bias, &());
+                assert_eq!(slice.items(&()), expected_items);
+
+                let summary = tree.range_summary::<Sum, _, IntegersSummary>(
+                    &(Sum(start)..Sum(end)),
+                    bias,
+                    &(),
+                );
+                assert_eq!(summary.count, expected_items.len());
+                assert_eq!(
+                    summary.sum,
+                    expected_items
+                        .iter()
+                        .map(|item| *item as usize)
+                        .sum::<usize>()
+                );
+                assert_eq!(
+                    summary.max,
+                    expected_items.iter().copied().max().unwrap_or_default()
+                );
+
+                let end = rng.gen_range(0..=items.len());
+                let start = rng.gen_range(0..=end);
+                let slice = tree.slice::<Count, _>(&(Count(start)..Count(end)), bias, &());
+                assert_eq!(slice.items(&()), &items[start..end]);
+                assert_eq!(
+                    tree.range_summary::<Count, _, Sum>(&(Count(start)..Count(end)), bias, &())
+                        .0,
+                    items[start..end]
+                        .iter()
+                        .map(|item| *item as usize)
+                        .sum::<usize>()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_random_bulk_construction() {
+        let mut starting_seed = 0;
+        if let Ok(value) = std::env::var("SEED") {
+            starting_seed = value.parse().expect("invalid SEED variable");
+        }
+        let mut num_iterations = 100;
+        if let Ok(value) = std::env::var("ITERATIONS") {
+            num_iterations = value.parse().expect("invalid ITERATIONS variable");
+        }
+
+        for seed in starting_seed..(starting_seed + num_iterations) {
+            eprintln!("seed = {}", seed);
+            let mut rng = StdRng::seed_from_u64(seed);
+
+            let density = rng.gen_range(0.0..=1.0);
+            let items = (0..=u8::MAX)
+                .filter(|_| rng.gen_bool(density))
+                .collect::<Vec<_>>();
+            log::info!("items: {:?}", items);
+
+            let mut incremental_tree = SumTree::new();
+            for item in &items {
+                incremental_tree.push(*item, &());
+            }
+            let sorted_tree = SumTree::from_sorted_items(items.iter().copied(), &());
+            let parallel_tree = SumTree::from_sorted_items_par(items.clone(), &());
+
+            for tree in [&sorted_tree, &parallel_tree] {
+                assert_valid_structure(tree);
+                assert_eq!(
+                    tree.iter().collect::<Vec<_>>(),
+                    incremental_tree.iter().collect::<Vec<_>>()
+                );
+                assert_eq!(tree.summary().count, incremental_tree.summary().count);
+                assert_eq!(tree.summary().sum, incremental_tree.summary().sum);
+                assert_eq!(tree.summary().max, incremental_tree.summary().max);
+                for item in &items {
+                    assert_eq!(tree.get(item, &()), Some(item));
+                }
+            }
+        }
+    }
+
+    /// Checks that all leaves are at the same depth, that no node has too many children,
+    /// and that the cached summaries match the ones of the children.
+    fn assert_valid_structure(tree: &SumTree<u8>) {
+        fn check(tree: &SumTree<u8>, is_root: bool) -> (u8, IntegersSummary) {
+            match tree.0.as_ref() {
+                Node::Leaf {
+                    items,
+                    item_summaries,
+                    summary,
+                } => {
+                    assert!(items.len() <= 2 * TREE_BASE);
+                    assert!(is_root || !items.is_empty(), "empty leaf below the root");
+                    assert_eq!(items.len(), item_summaries.len());
+                    let mut expected_summary = IntegersSummary::default();
+                    for item in items {
+                        Summary::add_summary(&mut expected_summary, &item.summary(), &());
+                    }
+                    assert_eq!(summary.count, expected_summary.count);
+                    assert_eq!(summary.sum, expected_summary.sum);
+                    (0, expected_summary)
+                }
+                Node::Internal {
+                    height,
+                    summary,
+                    child_summaries,
+                    child_trees,
+                } => {
+                    assert!(!child_trees.is_empty() && child_trees.len() <= 2 * TREE_BASE);
+                    assert_eq!(child_trees.len(), child_summaries.len());
+                    let mut expected_summary = IntegersSummary::default();
+                    for (child_tree, child_summary) in child_trees.iter().zip(child_summaries) {
+                        let (child_height, actual_child_summary) = check(child_tree, false);
+                        assert_eq!(child_height + 1, *height);
+                        assert_eq!(child_summary.count, actual_child_summary.count);
+                        assert_eq!(child_summary.sum, actual_child_summary.sum);
+                        Summary::add_summary(&mut expected_summary, child_summary, &());
+                    }
+                    assert_eq!(summary.count, expected_summary.count);
+                    assert_eq!(summary.sum, expected_summary.sum);
+                    (*height, expected_summary)
+                }
+            }
+        }
+
+        check(tree, true);
+    }
+
     #[test]
     fn test_cursor() {
         // Empty tree
@@ -1,6 +1,6 @@
 use std::{cmp::Ordering, fmt::Debug};
 
-use crate::{Bias, Dimension, Edit, Item, KeyedItem, SeekTarget, SumTree, Summary};
+use crate::{Bias, Cursor, Dimension, Edit, Item, KeyedItem, SeekTarget, SumTree, Summary};
 
 #[derive(Clone, PartialEq, Eq)]
 pub struct TreeMap<K, V>(SumTree<MapEntry<K, V>>)
@@ -32,6 +32,13 @@ impl<'a, K> Default for MapKeyRef<'a, K> {
     }
 }
 
+/// Looks up keys of a map in ascending order, seeking forward from the previous key instead of
+/// from the start of the map.
+pub struct TreeMapCursor<'a, K, V>(Cursor<'a, MapEntry<K, V>, MapKeyRef<'a, K>>)
+where
+    K: Clone + Debug + Ord,
+    V: Clone + Debug;
+
 #[derive(Clone)]
 pub struct TreeSet<K>(TreeMap<K, ()>)
 where
@@ -105,6 +112,10 @@ impl<K: Clone + Debug + Ord, V: Clone + Debug> TreeMap<K, V> {
         cursor.item().map(|item| (&item.key, &item.value))
     }
 
+    pub fn cursor(&self) -> TreeMapCursor<'_, K, V> {
+        TreeMapCursor(self.0.cursor::<MapKeyRef<'_, K>>())
+    }
+
     pub fn iter_from<'a>(&'a self, from: &'a K) -> impl Iterator<Item = (&K, &V)> + '_ {
         let mut cursor = self.0.cursor::<MapKeyRef<'_, K>>();
         let from_key = MapKeyRef(Some(from));
@@ -172,6 +183,17 @@ impl<K: Clone + Debug + Ord, V: Clone + Debug> TreeMap<K, V> {
     }
 }
 
+impl<'a, K: Clone + Debug + Ord, V: Clone + Debug> TreeMapCursor<'a, K, V> {
+    /// Returns the value of the given key, which can't be less than the keys looked up before.
+    pub fn seek_forward(&mut self, key: &K) -> Option<&'a V> {
+        self.0.seek_forward(&MapKeyRef(Some(key)), Bias::Left, &());
+        self.0
+            .item()
+            .filter(|item| &item.key == key)
+            .map(|item| &item.value)
+    }
+}
+
 impl<K: Debug, V: Debug> Debug for TreeMap<K, V>
 where
     K: Clone + Debug + Ord,
@@ -306,6 +328,19 @@ where
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_cursor() {
+        let map = TreeMap::from_ordered_entries([(1, "a"), (3, "c"), (5, "e"), (7, "g")]);
+        let mut cursor = map.cursor();
+        assert_eq!(cursor.seek_forward(&0), None);
+        assert_eq!(cursor.seek_forward(&1), Some(&"a"));
+        assert_eq!(cursor.seek_forward(&1), Some(&"a"));
+        assert_eq!(cursor.seek_forward(&4), None);
+        assert_eq!(cursor.seek_forward(&5), Some(&"e"));
+        assert_eq!(cursor.seek_forward(&7), Some(&"g"));
+        assert_eq!(cursor.seek_forward(&8), None);
+    }
+
     #[test]
     fn test_basic() {
         let mut map = TreeMap::default();
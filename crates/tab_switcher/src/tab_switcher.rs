@@ -2,6 +2,7 @@
 mod tab_switcher_tests;
 
 use collections::HashMap;
+use fuzzy::{match_strings, StringMatchCandidate};
 use gpui::{
     actions, impl_actions, rems, Action, AnyElement, AppContext, DismissEvent, EntityId,
     EventEmitter, FocusHandle, FocusableView, Modifiers, ModifiersChangedEvent, MouseButton,
@@ -9,7 +10,7 @@ use gpui::{
 };
 use picker::{Picker, PickerDelegate};
 use serde::Deserialize;
-use std::sync::Arc;
+use std::{cmp, sync::Arc};
 use ui::{prelude::*, ListItem, ListItemSpacing, Tooltip};
 use util::ResultExt;
 use workspace::{
@@ -19,6 +20,7 @@ use workspace::{
 };
 
 const PANEL_WIDTH_REMS: f32 = 28.;
+const MAX_SEARCH_MATCHES: usize = 100;
 
 #[derive(PartialEq, Clone, Deserialize, Default)]
 pub struct Toggle {
@@ -64,18 +66,29 @@ impl TabSwitcher {
                 .contains_focused(cx)
                 .then(|| terminal.read(cx).pane())
         });
-        let weak_pane = terminal_pane
-            .unwrap_or_else(|| workspace.active_pane())
-            .downgrade();
+        // The terminal panel's tabs are switched between on their own, the other ones are the
+        // items of all the panes in the center.
+        let (weak_pane, panes) = match terminal_pane {
+            Some(terminal_pane) => (terminal_pane.downgrade(), vec![terminal_pane.downgrade()]),
+            None => (
+                workspace.active_pane().downgrade(),
+                workspace
+                    .panes()
+                    .iter()
+                    .map(|pane| pane.downgrade())
+                    .collect(),
+            ),
+        };
         workspace.toggle_modal(cx, |cx| {
-            let delegate = TabSwitcherDelegate::new(action, cx.view().downgrade(), weak_pane, cx);
+            let delegate =
+                TabSwitcherDelegate::new(action, cx.view().downgrade(), weak_pane, panes, cx);
             TabSwitcher::new(delegate, cx)
         });
     }
 
     fn new(delegate: TabSwitcherDelegate, cx: &mut ViewContext<Self>) -> Self {
         Self {
-            picker: cx.new_view(|cx| Picker::nonsearchable_uniform_list(delegate, cx)),
+            picker: cx.new_view(|cx| Picker::uniform_list(delegate, cx)),
             init_modifiers: cx.modifiers().modified().then_some(cx.modifiers()),
         }
     }
@@ -127,17 +140,30 @@ impl Render for TabSwitcher {
 }
 
 struct TabMatch {
-    item_index: usize,
+    pane: WeakView<Pane>,
     item: Box<dyn ItemHandle>,
     detail: usize,
     preview: bool,
 }
 
+impl TabMatch {
+    /// The text the query is matched against: the item's path within the project when
+    /// it has one, its title otherwise, so that untitled buffers can be found too.
+    fn search_text(&self, cx: &AppContext) -> Option<String> {
+        self.item
+            .project_path(cx)
+            .map(|project_path| project_path.path.to_string_lossy().into_owned())
+            .or_else(|| Some(self.item.tab_content_text(cx)?.to_string()))
+    }
+}
+
 pub struct TabSwitcherDelegate {
     select_last: bool,
     tab_switcher: WeakView<TabSwitcher>,
     selected_index: usize,
+    /// The pane the switcher was opened from, whose active item is the current one.
     pane: WeakView<Pane>,
+    panes: Vec<WeakView<Pane>>,
     matches: Vec<TabMatch>,
 }
 
@@ -146,14 +172,18 @@ impl TabSwitcherDelegate {
         action: &Toggle,
         tab_switcher: WeakView<TabSwitcher>,
         pane: WeakView<Pane>,
+        panes: Vec<WeakView<Pane>>,
         cx: &mut ViewContext<TabSwitcher>,
     ) -> Self {
-        Self::subscribe_to_updates(&pane, cx);
+        for pane in &panes {
+            Self::subscribe_to_updates(pane, cx);
+        }
         Self {
             select_last: action.select_last,
             tab_switcher,
             selected_index: 0,
             pane,
+            panes,
             matches: Vec::new(),
         }
     }
@@ -166,6 +196,10 @@ impl TabSwitcherDelegate {
             match event {
                 PaneEvent::AddItem { .. } | PaneEvent::RemoveItem { .. } | PaneEvent::Remove => {
                     tab_switcher.picker.update(cx, |picker, cx| {
+                        if !picker.query(cx).trim().is_empty() {
+                            picker.refresh(cx);
+                            return;
+                        }
                         let selected_item_id = picker.delegate.selected_item_id();
                         picker.delegate.update_matches(cx);
                         if let Some(item_id) = selected_item_id {
@@ -180,51 +214,64 @@ impl TabSwitcherDelegate {
         .detach();
     }
 
+    /// Lists all the items but the current one, so that the first one is the item to go back
+    /// to.
     fn update_matches(&mut self, cx: &mut WindowContext) {
-        self.matches.clear();
-        let Some(pane) = self.pane.upgrade() else {
-            return;
+        let active_item_id = self
+            .pane
+            .upgrade()
+            .and_then(|pane| pane.read(cx).active_item())
+            .map(|item| item.item_id());
+        self.matches = self.tab_matches_by_recency(cx);
+        self.matches
+            .retain(|tab_match| Some(tab_match.item.item_id()) != active_item_id);
+
+        self.selected_index = if self.select_last {
+            self.matches.len().saturating_sub(1)
+        } else {
+            0
         };
+    }
 
-        let pane = pane.read(cx);
-        let mut history_indices = HashMap::default();
-        pane.activation_history().iter().rev().enumerate().for_each(
-            |(history_index, history_entry)| {
-                history_indices.insert(history_entry.entity_id, history_index);
-            },
-        );
+    /// Returns all the items of the panes, the most recently activated ones first. The ones
+    /// that were never activated come last, in the order of their tabs.
+    fn tab_matches_by_recency(&self, cx: &mut WindowContext) -> Vec<TabMatch> {
+        let mut tab_matches = Vec::new();
+        for weak_pane in &self.panes {
+            let Some(pane) = weak_pane.upgrade() else {
+                continue;
+            };
+            let pane = pane.read(cx);
+            let activation_timestamps = pane
+                .activation_history()
+                .iter()
+                .map(|entry| (entry.entity_id, entry.timestamp))
+                .collect::<HashMap<_, _>>();
+            for item in pane.items() {
+                let timestamp = activation_timestamps.get(&item.item_id()).copied();
+                let tab_match = TabMatch {
+                    pane: weak_pane.clone(),
+                    item: item.boxed_clone(),
+                    detail: 0,
+                    preview: pane.is_active_preview_item(item.item_id()),
+                };
+                tab_matches.push((timestamp, tab_match));
+            }
+        }
+        tab_matches.sort_by_key(|(timestamp, _)| cmp::Reverse(*timestamp));
 
-        let items: Vec<Box<dyn ItemHandle>> = pane.items().map(|item| item.boxed_clone()).collect();
-        items
+        let items = tab_matches
             .iter()
-            .enumerate()
+            .map(|(_, tab_match)| tab_match.item.boxed_clone())
+            .collect::<Vec<_>>();
+        tab_matches
+            .into_iter()
             .zip(tab_details(&items, cx))
-            .map(|((item_index, item), detail)| TabMatch {
-                item_index,
-                item: item.boxed_clone(),
+            .map(|((_, tab_match), detail)| TabMatch {
                 detail,
-                preview: pane.is_active_preview_item(item.item_id()),
+                ..tab_match
             })
-            .for_each(|tab_match| self.matches.push(tab_match));
-
-        let non_history_base = history_indices.len();
-        self.matches.sort_by(move |a, b| {
-            let a_score = *history_indices
-                .get(&a.item.item_id())
-                .unwrap_or(&(a.item_index + non_history_base));
-            let b_score = *history_indices
-                .get(&b.item.item_id())
-                .unwrap_or(&(b.item_index + non_history_base));
-            a_score.cmp(&b_score)
-        });
-
-        if self.matches.len() > 1 {
-            if self.select_last {
-                self.selected_index = self.matches.len() - 1;
-            } else {
-                self.selected_index = 1;
-            }
-        }
+            .collect()
     }
 
     fn selected_item_id(&self) -> Option<EntityId> {
@@ -250,7 +297,7 @@ impl TabSwitcherDelegate {
         let Some(tab_match) = self.matches.get(ix) else {
             return;
         };
-        let Some(pane) = self.pane.upgrade() else {
+        let Some(pane) = tab_match.pane.upgrade() else {
             return;
         };
         pane.update(cx, |pane, cx| {
@@ -264,7 +311,7 @@ impl PickerDelegate for TabSwitcherDelegate {
     type ListItem = ListItem;
 
     fn placeholder_text(&self, _cx: &mut WindowContext) -> Arc<str> {
-        "".into()
+        "Search open tabs…".into()
     }
 
     fn no_matches_text(&self, _cx: &mut WindowContext) -> SharedString {
@@ -290,22 +337,67 @@ impl PickerDelegate for TabSwitcherDelegate {
 
     fn update_matches(
         &mut self,
-        _raw_query: String,
+        raw_query: String,
         cx: &mut ViewContext<Picker<Self>>,
     ) -> Task<()> {
-        self.update_matches(cx);
-        Task::ready(())
+        let query = raw_query.trim().to_string();
+        if query.is_empty() {
+            self.update_matches(cx);
+            return Task::ready(());
+        }
+
+        let tab_matches = self.tab_matches_by_recency(cx);
+        let candidates = tab_matches
+            .iter()
+            .enumerate()
+            .filter_map(|(id, tab_match)| {
+                Some(StringMatchCandidate::new(id, tab_match.search_text(cx)?))
+            })
+            .collect::<Vec<_>>();
+        let mut tab_matches = tab_matches.into_iter().map(Some).collect::<Vec<_>>();
+        let background = cx.background_executor().clone();
+        cx.spawn(|picker, mut cx| async move {
+            let mut string_matches = match_strings(
+                &candidates,
+                &query,
+                false,
+                MAX_SEARCH_MATCHES,
+                &Default::default(),
+                background,
+            )
+            .await;
+            // Equally good matches keep their recency order.
+            string_matches.sort_by(|a, b| {
+                b.score
+                    .partial_cmp(&a.score)
+                    .unwrap_or(cmp::Ordering::Equal)
+                    .then_with(|| a.candidate_id.cmp(&b.candidate_id))
+            });
+
+            picker
+                .update(&mut cx, |picker, cx| {
+                    picker.delegate.matches = string_matches
+                        .iter()
+                        .filter_map(|string_match| tab_matches[string_match.candidate_id].take())
+                        .collect();
+                    picker.delegate.selected_index = 0;
+                    cx.notify();
+                })
+                .log_err();
+        })
     }
 
     fn confirm(&mut self, _secondary: bool, cx: &mut ViewContext<Picker<TabSwitcherDelegate>>) {
-        let Some(pane) = self.pane.upgrade() else {
+        let Some(selected_match) = self.matches.get(self.selected_index()) else {
             return;
         };
-        let Some(selected_match) = self.matches.get(self.selected_index()) else {
+        let Some(pane) = selected_match.pane.upgrade() else {
             return;
         };
         pane.update(cx, |pane, cx| {
-            pane.activate_item(selected_match.item_index, true, true, cx);
+            if let Some(index) = pane.index_for_item(selected_match.item.as_ref()) {
+                pane.activate_item(index, true, true, cx);
+            }
         });
     }
 
@@ -338,11 +430,9 @@ impl PickerDelegate for TabSwitcherDelegate {
         } else {
             Color::default()
         };
-        let indicator = h_flex()
-            .flex_shrink_0()
-            .children(indicator)
-            .child(div().w_2())
-            .into_any_element();
+        // Dirty and conflicted items are marked next to their label, so that it stays visible
+        // when the close button is shown.
+        let indicator = h_flex().flex_shrink_0().children(indicator);
         let close_button = div()
             // We need this on_mouse_up here instead of on_click on the close
             // button because Picker intercepts the same events and handles them
@@ -368,13 +458,12 @@ impl PickerDelegate for TabSwitcherDelegate {
                 .spacing(ListItemSpacing::Sparse)
                 .inset(true)
                 .selected(selected)
-                .child(h_flex().w_full().child(label))
+                .child(h_flex().w_full().gap_2().child(label).child(indicator))
                 .map(|el| {
                     if self.selected_index == ix {
                         el.end_slot::<AnyElement>(close_button)
                     } else {
-                        el.end_slot::<AnyElement>(indicator)
-                            .end_hover_slot::<AnyElement>(close_button)
+                        el.end_hover_slot::<AnyElement>(close_button)
                     }
                 }),
         )
@@ -5,7 +5,7 @@ use menu::SelectPrev;
 use project::{Project, ProjectPath};
 use serde_json::json;
 use std::path::Path;
-use workspace::{AppState, Workspace};
+use workspace::{AppState, SplitDirection, Workspace};
 
 #[ctor::ctor]
 fn init_logger() {
@@ -42,33 +42,34 @@ async fn test_open_with_prev_tab_selected_and_cycle_on_toggle_action(
     let tab_3 = open_buffer("3.txt", &workspace, cx).await;
     let tab_4 = open_buffer("4.txt", &workspace, cx).await;
 
-    // Starts with the previously opened item selected
+    // Starts with the previously opened item selected, leaving out the current one
     let tab_switcher = open_tab_switcher(false, &workspace, cx);
     tab_switcher.update(cx, |tab_switcher, _| {
-        assert_eq!(tab_switcher.delegate.matches.len(), 4);
-        assert_match_at_position(tab_switcher, 0, tab_4.boxed_clone());
-        assert_match_selection(tab_switcher, 1, tab_3.boxed_clone());
-        assert_match_at_position(tab_switcher, 2, tab_2.boxed_clone());
-        assert_match_at_position(tab_switcher, 3, tab_1.boxed_clone());
+        assert_eq!(tab_switcher.delegate.matches.len(), 3);
+        assert_match_selection(tab_switcher, 0, tab_3.boxed_clone());
+        assert_match_at_position(tab_switcher, 1, tab_2.boxed_clone());
+        assert_match_at_position(tab_switcher, 2, tab_1.boxed_clone());
     });
 
     cx.dispatch_action(Toggle { select_last: false });
     cx.dispatch_action(Toggle { select_last: false });
     tab_switcher.update(cx, |tab_switcher, _| {
-        assert_eq!(tab_switcher.delegate.matches.len(), 4);
-        assert_match_at_position(tab_switcher, 0, tab_4.boxed_clone());
-        assert_match_at_position(tab_switcher, 1, tab_3.boxed_clone());
-        assert_match_at_position(tab_switcher, 2, tab_2.boxed_clone());
-        assert_match_selection(tab_switcher, 3, tab_1.boxed_clone());
+        assert_eq!(tab_switcher.delegate.matches.len(), 3);
+        assert_match_at_position(tab_switcher, 0, tab_3.boxed_clone());
+        assert_match_at_position(tab_switcher, 1, tab_2.boxed_clone());
+        assert_match_selection(tab_switcher, 2, tab_1.boxed_clone());
     });
 
     cx.dispatch_action(SelectPrev);
     tab_switcher.update(cx, |tab_switcher, _| {
-        assert_eq!(tab_switcher.delegate.matches.len(), 4);
-        assert_match_at_position(tab_switcher, 0, tab_4.boxed_clone());
-        assert_match_at_position(tab_switcher, 1, tab_3.boxed_clone());
-        assert_match_selection(tab_switcher, 2, tab_2.boxed_clone());
-        assert_match_at_position(tab_switcher, 3, tab_1.boxed_clone());
+        assert_eq!(tab_switcher.delegate.matches.len(), 3);
+        assert_match_at_position(tab_switcher, 0, tab_3.boxed_clone());
+        assert_match_selection(tab_switcher, 1, tab_2.boxed_clone());
+        assert_match_at_position(tab_switcher, 2, tab_1.boxed_clone());
+    });
+    cx.read(|cx| {
+        let active_item = workspace.read(cx).active_item(cx).unwrap();
+        assert_eq!(active_item.item_id(), tab_4.item_id());
     });
 }
 
@@ -94,15 +95,14 @@ async fn test_open_with_last_tab_selected(cx: &mut gpui::TestAppContext) {
 
     let tab_1 = open_buffer("1.txt", &workspace, cx).await;
     let tab_2 = open_buffer("2.txt", &workspace, cx).await;
-    let tab_3 = open_buffer("3.txt", &workspace, cx).await;
+    open_buffer("3.txt", &workspace, cx).await;
 
     // Starts with the last item selected
     let tab_switcher = open_tab_switcher(true, &workspace, cx);
     tab_switcher.update(cx, |tab_switcher, _| {
-        assert_eq!(tab_switcher.delegate.matches.len(), 3);
-        assert_match_at_position(tab_switcher, 0, tab_3);
-        assert_match_at_position(tab_switcher, 1, tab_2);
-        assert_match_selection(tab_switcher, 2, tab_1);
+        assert_eq!(tab_switcher.delegate.matches.len(), 2);
+        assert_match_at_position(tab_switcher, 0, tab_2);
+        assert_match_selection(tab_switcher, 1, tab_1);
     });
 }
 
@@ -126,14 +126,13 @@ async fn test_open_item_on_modifiers_release(cx: &mut gpui::TestAppContext) {
     let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project.clone(), cx));
 
     let tab_1 = open_buffer("1.txt", &workspace, cx).await;
-    let tab_2 = open_buffer("2.txt", &workspace, cx).await;
+    open_buffer("2.txt", &workspace, cx).await;
 
     cx.simulate_modifiers_change(Modifiers::control());
     let tab_switcher = open_tab_switcher(false, &workspace, cx);
     tab_switcher.update(cx, |tab_switcher, _| {
-        assert_eq!(tab_switcher.delegate.matches.len(), 2);
-        assert_match_at_position(tab_switcher, 0, tab_2.boxed_clone());
-        assert_match_selection(tab_switcher, 1, tab_1.boxed_clone());
+        assert_eq!(tab_switcher.delegate.matches.len(), 1);
+        assert_match_selection(tab_switcher, 0, tab_1.boxed_clone());
     });
 
     cx.simulate_modifiers_change(Modifiers::none());
@@ -174,12 +173,12 @@ async fn test_open_with_single_item(cx: &mut gpui::TestAppContext) {
     let project = Project::test(app_state.fs.clone(), ["/root".as_ref()], cx).await;
     let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project.clone(), cx));
 
-    let tab = open_buffer("1.txt", &workspace, cx).await;
+    open_buffer("1.txt", &workspace, cx).await;
 
+    // The only item is the current one, so there's nothing to switch to.
     let tab_switcher = open_tab_switcher(false, &workspace, cx);
     tab_switcher.update(cx, |tab_switcher, _| {
-        assert_eq!(tab_switcher.delegate.matches.len(), 1);
-        assert_match_selection(tab_switcher, 0, tab);
+        assert!(tab_switcher.delegate.matches.is_empty());
     });
 }
 
@@ -194,6 +193,7 @@ async fn test_close_selected_item(cx: &mut gpui::TestAppContext) {
             json!({
                 "1.txt": "First file",
                 "2.txt": "Second file",
+                "3.txt": "Third file",
             }),
         )
         .await;
@@ -203,27 +203,152 @@ async fn test_close_selected_item(cx: &mut gpui::TestAppContext) {
 
     let tab_1 = open_buffer("1.txt", &workspace, cx).await;
     let tab_2 = open_buffer("2.txt", &workspace, cx).await;
+    let tab_3 = open_buffer("3.txt", &workspace, cx).await;
 
     cx.simulate_modifiers_change(Modifiers::control());
     let tab_switcher = open_tab_switcher(false, &workspace, cx);
     tab_switcher.update(cx, |tab_switcher, _| {
         assert_eq!(tab_switcher.delegate.matches.len(), 2);
-        assert_match_at_position(tab_switcher, 0, tab_2.boxed_clone());
-        assert_match_selection(tab_switcher, 1, tab_1.boxed_clone());
+        assert_match_selection(tab_switcher, 0, tab_2.boxed_clone());
+        assert_match_at_position(tab_switcher, 1, tab_1.boxed_clone());
     });
 
     cx.simulate_modifiers_change(Modifiers::control());
     cx.dispatch_action(CloseSelectedItem);
     tab_switcher.update(cx, |tab_switcher, _| {
         assert_eq!(tab_switcher.delegate.matches.len(), 1);
-        assert_match_selection(tab_switcher, 0, tab_2);
+        assert_match_selection(tab_switcher, 0, tab_1);
+    });
+    cx.read(|cx| {
+        let active_item = workspace.read(cx).active_item(cx).unwrap();
+        assert_eq!(active_item.item_id(), tab_3.item_id());
     });
 
     // Still switches tab on modifiers release
     cx.simulate_modifiers_change(Modifiers::none());
     cx.read(|cx| {
         let active_editor = workspace.read(cx).active_item_as::<Editor>(cx).unwrap();
-        assert_eq!(active_editor.read(cx).title(cx), "2.txt");
+        assert_eq!(active_editor.read(cx).title(cx), "1.txt");
+    });
+    assert_tab_switcher_is_closed(workspace, cx);
+}
+
+#[gpui::test]
+async fn test_search_open_tabs(cx: &mut gpui::TestAppContext) {
+    let app_state = init_test(cx);
+    app_state
+        .fs
+        .as_fake()
+        .insert_tree(
+            "/root",
+            json!({
+                "main.rs": "",
+                "lib.rs": "",
+                "src": {
+                    "main_helpers.rs": "",
+                },
+            }),
+        )
+        .await;
+
+    let project = Project::test(app_state.fs.clone(), ["/root".as_ref()], cx).await;
+    let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project.clone(), cx));
+
+    let main_helpers = open_buffer("src/main_helpers.rs", &workspace, cx).await;
+    let main = open_buffer("main.rs", &workspace, cx).await;
+    let lib = open_buffer("lib.rs", &workspace, cx).await;
+    let untitled = workspace.update(cx, |workspace, cx| {
+        let editor = cx.new_view(Editor::multi_line);
+        workspace.add_item_to_active_pane(Box::new(editor.clone()), None, cx);
+        Box::new(editor) as Box<dyn ItemHandle>
+    });
+
+    let tab_switcher = open_tab_switcher(false, &workspace, cx);
+    cx.simulate_input("main");
+    tab_switcher.update(cx, |tab_switcher, _| {
+        assert_eq!(tab_switcher.delegate.matches.len(), 2);
+        assert_match_selection(tab_switcher, 0, main.boxed_clone());
+        assert_match_at_position(tab_switcher, 1, main_helpers.boxed_clone());
+    });
+
+    // Items without a path are matched by their title.
+    tab_switcher.update(cx, |tab_switcher, cx| {
+        tab_switcher.set_query("untitled", cx)
+    });
+    cx.run_until_parked();
+    tab_switcher.update(cx, |tab_switcher, _| {
+        assert_eq!(tab_switcher.delegate.matches.len(), 1);
+        assert_match_selection(tab_switcher, 0, untitled.boxed_clone());
+    });
+
+    // Clearing the query lists the other items again, the most recent ones first.
+    tab_switcher.update(cx, |tab_switcher, cx| tab_switcher.set_query("", cx));
+    cx.run_until_parked();
+    tab_switcher.update(cx, |tab_switcher, _| {
+        assert_eq!(tab_switcher.delegate.matches.len(), 3);
+        assert_match_selection(tab_switcher, 0, lib.boxed_clone());
+        assert_match_at_position(tab_switcher, 1, main.boxed_clone());
+        assert_match_at_position(tab_switcher, 2, main_helpers.boxed_clone());
+    });
+
+    cx.dispatch_action(menu::Confirm);
+    cx.read(|cx| {
+        let active_editor = workspace.read(cx).active_item_as::<Editor>(cx).unwrap();
+        assert_eq!(active_editor.read(cx).title(cx), "lib.rs");
+    });
+    assert_tab_switcher_is_closed(workspace, cx);
+}
+
+#[gpui::test]
+async fn test_switch_between_panes(cx: &mut gpui::TestAppContext) {
+    let app_state = init_test(cx);
+    app_state
+        .fs
+        .as_fake()
+        .insert_tree(
+            "/root",
+            json!({
+                "1.txt": "First file",
+                "2.txt": "Second file",
+                "3.txt": "Third file",
+            }),
+        )
+        .await;
+
+    let project = Project::test(app_state.fs.clone(), ["/root".as_ref()], cx).await;
+    let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project.clone(), cx));
+
+    let tab_1 = open_buffer("1.txt", &workspace, cx).await;
+    let left_pane = workspace.update(cx, |workspace, _| workspace.active_pane().clone());
+    let tab_2 = open_buffer("2.txt", &workspace, cx).await;
+    let right_pane = workspace.update(cx, |workspace, cx| {
+        workspace.split_pane(left_pane.clone(), SplitDirection::Right, cx)
+    });
+    let tab_3 = open_buffer("3.txt", &workspace, cx).await;
+
+    // The items of all the panes are listed, the most recent ones first.
+    let tab_switcher = open_tab_switcher(false, &workspace, cx);
+    tab_switcher.update(cx, |tab_switcher, _| {
+        assert_eq!(tab_switcher.delegate.matches.len(), 2);
+        assert_match_selection(tab_switcher, 0, tab_2.boxed_clone());
+        assert_match_at_position(tab_switcher, 1, tab_1.boxed_clone());
+    });
+
+    // Choosing an item of another pane goes to that pane instead of opening it again.
+    cx.dispatch_action(menu::SelectNext);
+    cx.dispatch_action(menu::Confirm);
+    cx.read(|cx| {
+        let workspace = workspace.read(cx);
+        assert_eq!(workspace.active_pane(), &left_pane);
+        assert_eq!(
+            workspace.active_item(cx).unwrap().item_id(),
+            tab_1.item_id()
+        );
+        assert_eq!(right_pane.read(cx).items_len(), 1);
+        assert_eq!(
+            right_pane.read(cx).active_item().unwrap().item_id(),
+            tab_3.item_id()
+        );
     });
     assert_tab_switcher_is_closed(workspace, cx);
 }
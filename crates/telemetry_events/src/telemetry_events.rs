@@ -2,6 +2,24 @@ use semantic_version::SemanticVersion;
 use serde::{Deserialize, Serialize};
 use std::{fmt::Display, sync::Arc, time::Duration};
 
+/// The most usage events that a client sends in one batch over its RPC connection. Larger
+/// batches are rejected by the server.
+pub const MAX_USAGE_EVENTS_PER_BATCH: usize = 1000;
+
+/// The properties that usage events may carry. Properties with any other key are dropped by
+/// the client when the event is reported, and by the server when it's received, so that paths,
+/// buffer contents and other user data can't be attached by accident. Only add keys here whose
+/// values can never contain such data.
+pub const USAGE_EVENT_PROPERTIES: &[&str] = &[
+    "action",
+    "enabled",
+    "kind",
+    "language",
+    "operation",
+    "provider",
+    "source",
+];
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct EventRequestBody {
     pub installation_id: Option<String>,
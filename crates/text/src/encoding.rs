@@ -0,0 +1,188 @@
+use anyhow::{anyhow, Result};
+
+/// The characters that windows-1252 maps the bytes `0x80..=0x9F` to. The bytes that the
+/// encoding leaves undefined map to the C1 control character with the same value, so that
+/// decoding and encoding them round-trips.
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '\u{20AC}', '\u{0081}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02C6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{008D}', '\u{017D}', '\u{008F}',
+    '\u{0090}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}', '\u{0153}', '\u{009D}', '\u{017E}', '\u{0178}',
+];
+
+/// The character encoding of a file on disk. Buffers always hold UTF-8 text, this is only
+/// used to decode a file when it is loaded and to encode it again when it is saved.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    /// UTF-8 with a leading byte order mark.
+    Utf8Bom,
+    Utf16Le,
+    Utf16Be,
+    Windows1252,
+}
+
+impl Encoding {
+    pub const ALL: [Encoding; 5] = [
+        Encoding::Utf8,
+        Encoding::Utf8Bom,
+        Encoding::Utf16Le,
+        Encoding::Utf16Be,
+        Encoding::Windows1252,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf8Bom => "UTF-8 with BOM",
+            Encoding::Utf16Le => "UTF-16 LE",
+            Encoding::Utf16Be => "UTF-16 BE",
+            Encoding::Windows1252 => "Windows-1252",
+        }
+    }
+
+    /// Looks up an encoding by one of its common labels, e.g. `utf-8`, `utf-16le` or `latin1`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name
+            .trim()
+            .to_ascii_lowercase()
+            .replace(['-', '_', ' '], "");
+        match name.as_str() {
+            "utf8" => Some(Encoding::Utf8),
+            "utf8bom" | "utf8withbom" => Some(Encoding::Utf8Bom),
+            "utf16" | "utf16le" => Some(Encoding::Utf16Le),
+            "utf16be" => Some(Encoding::Utf16Be),
+            "windows1252" | "cp1252" | "latin1" | "iso88591" => Some(Encoding::Windows1252),
+            _ => None,
+        }
+    }
+
+    fn bom(&self) -> &'static [u8] {
+        match self {
+            Encoding::Utf8 | Encoding::Windows1252 => &[],
+            Encoding::Utf8Bom => &[0xEF, 0xBB, 0xBF],
+            Encoding::Utf16Le => &[0xFF, 0xFE],
+            Encoding::Utf16Be => &[0xFE, 0xFF],
+        }
+    }
+
+    /// Detects the encoding of the given file contents and decodes them.
+    ///
+    /// A byte order mark always wins. Otherwise, contents that look like BOM-less UTF-16,
+    /// because most of every other byte is zero, are read as that, and contents that are
+    /// valid UTF-8 are read as such. Anything else is decoded with the `fallback` encoding,
+    /// unless it contains a zero byte, which no text in a single-byte encoding does, in which
+    /// case it's a binary file and an error is returned.
+    pub fn detect(bytes: &[u8], fallback: Encoding) -> Result<(String, Encoding)> {
+        let encoding = [Encoding::Utf8Bom, Encoding::Utf16Le, Encoding::Utf16Be]
+            .into_iter()
+            .find(|encoding| bytes.starts_with(encoding.bom()))
+            .or_else(|| Self::detect_utf16_without_bom(bytes))
+            .or_else(|| std::str::from_utf8(bytes).is_ok().then_some(Encoding::Utf8));
+        let encoding = match encoding {
+            Some(encoding) => encoding,
+            None if bytes.contains(&0) => return Err(anyhow!("binary file")),
+            None => fallback,
+        };
+        Ok((encoding.decode(bytes), encoding))
+    }
+
+    fn detect_utf16_without_bom(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < 2 || bytes.len() % 2 != 0 {
+            return None;
+        }
+
+        let units = bytes.len() / 2;
+        let zeros_at = |parity: usize| {
+            bytes
+                .iter()
+                .skip(parity)
+                .step_by(2)
+                .filter(|byte| **byte == 0)
+                .count()
+        };
+        if zeros_at(1) * 2 > units && zeros_at(0) == 0 {
+            Some(Encoding::Utf16Le)
+        } else if zeros_at(0) * 2 > units && zeros_at(1) == 0 {
+            Some(Encoding::Utf16Be)
+        } else {
+            None
+        }
+    }
+
+    /// Decodes the given bytes, skipping this encoding's byte order mark if they start
+    /// with it. Invalid sequences are replaced with `U+FFFD`.
+    pub fn decode(&self, bytes: &[u8]) -> String {
+        let bytes = bytes.strip_prefix(self.bom()).unwrap_or(bytes);
+        match self {
+            Encoding::Utf8 | Encoding::Utf8Bom => String::from_utf8_lossy(bytes).into_owned(),
+            Encoding::Utf16Le | Encoding::Utf16Be => {
+                let units = bytes.chunks(2).map(|unit| match (unit, self) {
+                    ([low, high], Encoding::Utf16Le) => u16::from_le_bytes([*low, *high]),
+                    ([high, low], _) => u16::from_be_bytes([*high, *low]),
+                    _ => 0xFFFD,
+                });
+                char::decode_utf16(units)
+                    .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+                    .collect()
+            }
+            Encoding::Windows1252 => bytes
+                .iter()
+                .map(|byte| match byte {
+                    0x80..=0x9F => WINDOWS_1252_HIGH[*byte as usize - 0x80],
+                    _ => *byte as char,
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns whether the given character can be saved in this encoding.
+    pub fn can_encode(&self, c: char) -> bool {
+        match self {
+            Encoding::Windows1252 => {
+                matches!(c, '\0'..='\u{7F}' | '\u{A0}'..='\u{FF}') || WINDOWS_1252_HIGH.contains(&c)
+            }
+            _ => true,
+        }
+    }
+
+    /// Appends this encoding's byte order mark, if it has one, to `bytes`.
+    pub fn write_bom(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(self.bom());
+    }
+
+    /// Appends the given text, encoded in this encoding, to `bytes`. Fails if the text
+    /// contains a character this encoding can't represent.
+    pub fn encode(&self, text: &str, bytes: &mut Vec<u8>) -> Result<()> {
+        match self {
+            Encoding::Utf8 | Encoding::Utf8Bom => bytes.extend_from_slice(text.as_bytes()),
+            Encoding::Utf16Le => {
+                for unit in text.encode_utf16() {
+                    bytes.extend_from_slice(&unit.to_le_bytes());
+                }
+            }
+            Encoding::Utf16Be => {
+                for unit in text.encode_utf16() {
+                    bytes.extend_from_slice(&unit.to_be_bytes());
+                }
+            }
+            Encoding::Windows1252 => {
+                for c in text.chars() {
+                    let byte = match c {
+                        '\0'..='\u{7F}' | '\u{A0}'..='\u{FF}' => c as u8,
+                        _ => WINDOWS_1252_HIGH
+                            .iter()
+                            .position(|high| *high == c)
+                            .map(|ix| 0x80 + ix as u8)
+                            .ok_or_else(|| {
+                                anyhow!("{c:?} can't be represented in {}", self.name())
+                            })?,
+                    };
+                    bytes.push(byte);
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -8,8 +8,25 @@ pub trait Operation: Clone + Debug {
 #[derive(Clone, Debug)]
 struct OperationItem<T>(T);
 
+/// Operations that are waiting to be applied.
+///
+/// Operations of a higher [`OperationPriority`] are drained first, and operations of the same
+/// priority are drained in timestamp order.
 #[derive(Clone, Debug)]
-pub struct OperationQueue<T: Operation>(SumTree<OperationItem<T>>);
+pub struct OperationQueue<T: Operation> {
+    live: SumTree<OperationItem<T>>,
+    backfill: SumTree<OperationItem<T>>,
+}
+
+/// How urgently a queued operation should be applied.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum OperationPriority {
+    /// Operations that catch up on history, such as the ones received after reconnecting.
+    Backfill,
+    /// Operations that collaborators have just made.
+    #[default]
+    Live,
+}
 
 #[derive(Clone, Copy, Debug, Default, Eq, Ord, PartialEq, PartialOrd)]
 pub struct OperationKey(clock::Lamport);
@@ -34,21 +51,42 @@ impl<T: Operation> Default for OperationQueue<T> {
 
 impl<T: Operation> OperationQueue<T> {
     pub fn new() -> Self {
-        OperationQueue(SumTree::new())
+        OperationQueue {
+            live: SumTree::new(),
+            backfill: SumTree::new(),
+        }
     }
 
     pub fn len(&self) -> usize {
-        self.0.summary().len
+        self.live.summary().len + self.backfill.summary().len
     }
 
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
 
-    pub fn insert(&mut self, mut ops: Vec<T>) {
+    pub fn insert(&mut self, ops: Vec<T>) {
+        self.insert_with_priority(ops, OperationPriority::Live);
+    }
+
+    /// Queues the given operations. An operation that is already queued takes the new
+    /// priority.
+    pub fn insert_with_priority(&mut self, mut ops: Vec<T>, priority: OperationPriority) {
         ops.sort_by_key(|op| op.lamport_timestamp());
         ops.dedup_by_key(|op| op.lamport_timestamp());
-        self.0.edit(
+        let (tree, other_tree) = match priority {
+            OperationPriority::Live => (&mut self.live, &mut self.backfill),
+            OperationPriority::Backfill => (&mut self.backfill, &mut self.live),
+        };
+        if !other_tree.is_empty() {
+            other_tree.edit(
+                ops.iter()
+                    .map(|op| Edit::Remove(OperationKey::new(op.lamport_timestamp())))
+                    .collect(),
+                &(),
+            );
+        }
+        tree.edit(
             ops.into_iter()
                 .map(|op| Edit::Insert(OperationItem(op)))
                 .collect(),
@@ -58,12 +96,43 @@ impl<T: Operation> OperationQueue<T> {
 
     pub fn drain(&mut self) -> Self {
         let clone = self.clone();
-        self.0 = SumTree::new();
+        self.live = SumTree::new();
+        self.backfill = SumTree::new();
         clone
     }
 
+    /// Removes and returns up to `budget` operations that `is_ready` accepts, in the order
+    /// in which they are drained.
+    ///
+    /// Operations that aren't ready, e.g. because an operation they depend on hasn't
+    /// arrived yet, stay parked in the queue without holding back the ones after them.
+    /// As applying the returned operations can make parked ones ready, callers should
+    /// drain again until no operations are returned.
+    pub fn drain_ready(&mut self, budget: usize, mut is_ready: impl FnMut(&T) -> bool) -> Vec<T> {
+        let mut ready = Vec::new();
+        for tree in [&mut self.live, &mut self.backfill] {
+            if ready.len() == budget {
+                break;
+            }
+
+            let mut ready_keys = Vec::new();
+            for item in tree.iter() {
+                if ready.len() == budget {
+                    break;
+                }
+                if is_ready(&item.0) {
+                    ready_keys.push(Edit::Remove(item.key()));
+                    ready.push(item.0.clone());
+                }
+            }
+            tree.edit(ready_keys, &());
+        }
+        ready
+    }
+
+    /// Iterates over the queued operations in the order in which they would be drained.
     pub fn iter(&self) -> impl Iterator<Item = &T> {
-        self.0.iter().map(|i| &i.0)
+        self.live.iter().chain(self.backfill.iter()).map(|i| &i.0)
     }
 }
 
@@ -142,7 +211,97 @@ mod tests {
         assert_eq!(queue.len(), 1);
     }
 
-    #[derive(Clone, Debug, Eq, PartialEq)]
+    #[test]
+    fn test_priority_order() {
+        let mut clock = clock::Lamport::new(0);
+        let op1 = TestOperation(clock.tick());
+        let op2 = TestOperation(clock.tick());
+        let op3 = TestOperation(clock.tick());
+        let op4 = TestOperation(clock.tick());
+
+        let mut queue = OperationQueue::new();
+        queue.insert_with_priority(vec![op2, op1], OperationPriority::Backfill);
+        queue.insert(vec![op4, op3]);
+        assert_eq!(
+            queue.iter().copied().collect::<Vec<_>>(),
+            [op3, op4, op1, op2]
+        );
+
+        // Queueing an operation again moves it to the new priority.
+        queue.insert(vec![op2]);
+        assert_eq!(queue.len(), 4);
+        assert_eq!(
+            queue.drain_ready(usize::MAX, |_| true),
+            [op2, op3, op4, op1]
+        );
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_budgeted_draining() {
+        let mut clock = clock::Lamport::new(0);
+        let ops = (0..5)
+            .map(|_| TestOperation(clock.tick()))
+            .collect::<Vec<_>>();
+
+        let mut queue = OperationQueue::new();
+        queue.insert_with_priority(ops[..3].to_vec(), OperationPriority::Backfill);
+        queue.insert(ops[3..].to_vec());
+
+        assert_eq!(queue.drain_ready(3, |_| true), [ops[3], ops[4], ops[0]]);
+        assert_eq!(queue.len(), 2);
+        assert!(queue.drain_ready(0, |_| true).is_empty());
+
+        // A live operation arriving between batches is drained before the remaining backfill.
+        let live_op = TestOperation(clock.tick());
+        queue.insert(vec![live_op]);
+        assert_eq!(queue.drain_ready(2, |_| true), [live_op, ops[1]]);
+        assert_eq!(queue.drain_ready(2, |_| true), [ops[2]]);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_parked_operations() {
+        let mut clock = clock::Lamport::new(0);
+        let op1 = TestOperation(clock.tick());
+        let op2 = TestOperation(clock.tick());
+        let op3 = TestOperation(clock.tick());
+        // The first two operations are causal predecessors of the last one.
+        let dependencies = [(op3, op1), (op3, op2)];
+
+        let mut applied = Vec::new();
+        let drain = |queue: &mut OperationQueue<TestOperation>,
+                     applied: &mut Vec<TestOperation>| {
+            let mut drained = Vec::new();
+            loop {
+                let ops = queue.drain_ready(usize::MAX, |op| {
+                    dependencies
+                        .iter()
+                        .filter(|(dependent, _)| dependent == op)
+                        .all(|(_, dependency)| applied.contains(dependency))
+                });
+                if ops.is_empty() {
+                    break;
+                }
+                applied.extend(ops.iter().copied());
+                drained.extend(ops);
+            }
+            drained
+        };
+
+        let mut queue = OperationQueue::new();
+        queue.insert(vec![op3, op1]);
+        assert_eq!(drain(&mut queue, &mut applied), [op1]);
+        assert_eq!(queue.iter().copied().collect::<Vec<_>>(), [op3]);
+
+        // Once the missing predecessor arrives, the parked operation is released after it.
+        queue.insert_with_priority(vec![op2], OperationPriority::Backfill);
+        assert_eq!(drain(&mut queue, &mut applied), [op2, op3]);
+        assert!(queue.is_empty());
+        assert_eq!(applied, [op1, op2, op3]);
+    }
+
+    #[derive(Clone, Copy, Debug, Eq, PartialEq)]
     struct TestOperation(clock::Lamport);
 
     impl Operation for TestOperation {
@@ -1,10 +1,11 @@
 use super::{network::Network, *};
-use clock::ReplicaId;
+use clock::{DecodedVersion, EncodedVersion, ReplicaId, VersionDecoder, VersionEncoder};
 use rand::prelude::*;
 use std::{
     cmp::Ordering,
     env,
     iter::Iterator,
+    mem,
     time::{Duration, Instant},
 };
 
@@ -189,6 +190,97 @@ fn test_line_endings() {
     buffer.check_invariants();
 }
 
+#[test]
+fn test_mixed_line_endings() {
+    assert_eq!(
+        LineEnding::detect(&format!("a\n{}", "b\r\n".repeat(3))),
+        LineEnding::Windows
+    );
+    assert_eq!(
+        LineEnding::detect(&format!("a\r\n{}", "b\n".repeat(3))),
+        LineEnding::Unix
+    );
+    assert!(!LineEnding::is_mixed("a\r\nb\r\n"));
+    assert!(!LineEnding::is_mixed("a\nb\n"));
+    assert!(LineEnding::is_mixed("a\r\nb\n"));
+    assert!(LineEnding::is_mixed("a\rb\n"));
+    assert_eq!(
+        LineEnding::detect_mixed("a\r\nb\nc\r\n"),
+        (LineEnding::Windows, true)
+    );
+
+    let mut buffer = Buffer::new(0, BufferId::new(1).unwrap(), "a\r\nb\nc\r\nd".into());
+    assert_eq!(buffer.text(), "a\nb\nc\nd");
+    assert_eq!(buffer.max_point(), Point::new(3, 1));
+    assert_eq!(buffer.line_ending(), LineEnding::Windows);
+    assert!(buffer.has_mixed_line_endings());
+
+    buffer.set_line_ending(LineEnding::Unix);
+    assert_eq!(buffer.line_ending(), LineEnding::Unix);
+    assert!(buffer.has_mixed_line_endings());
+    buffer.set_has_mixed_line_endings(false);
+    assert!(!buffer.has_mixed_line_endings());
+}
+
+#[test]
+fn test_encodings() {
+    assert_eq!(
+        Encoding::detect(b"caf\xc3\xa9", Encoding::Windows1252).unwrap(),
+        ("café".to_string(), Encoding::Utf8)
+    );
+    assert_eq!(
+        Encoding::detect(b"\xef\xbb\xbfcaf\xc3\xa9", Encoding::Windows1252).unwrap(),
+        ("café".to_string(), Encoding::Utf8Bom)
+    );
+    assert_eq!(
+        Encoding::detect(b"caf\xe9 \x80", Encoding::Windows1252).unwrap(),
+        ("café €".to_string(), Encoding::Windows1252)
+    );
+    assert_eq!(
+        Encoding::detect(b"\xff\xfeh\0i\0", Encoding::Windows1252).unwrap(),
+        ("hi".to_string(), Encoding::Utf16Le)
+    );
+    assert_eq!(
+        Encoding::detect(b"\xfe\xff\0h\0i", Encoding::Windows1252).unwrap(),
+        ("hi".to_string(), Encoding::Utf16Be)
+    );
+    assert_eq!(
+        Encoding::detect(b"h\0i\0", Encoding::Windows1252).unwrap(),
+        ("hi".to_string(), Encoding::Utf16Le)
+    );
+    assert_eq!(
+        Encoding::detect(b"caf\xe9", Encoding::Utf8).unwrap(),
+        ("caf\u{FFFD}".to_string(), Encoding::Utf8)
+    );
+    assert!(Encoding::detect(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR", Encoding::Windows1252).is_err());
+    assert_eq!(
+        Encoding::detect(b"a\0b", Encoding::Windows1252).unwrap(),
+        ("a\0b".to_string(), Encoding::Utf8)
+    );
+
+    for encoding in Encoding::ALL {
+        let mut bytes = Vec::new();
+        encoding.write_bom(&mut bytes);
+        encoding.encode("zaïve “quotes”", &mut bytes).unwrap();
+        assert_eq!(
+            Encoding::detect(&bytes, Encoding::Windows1252).unwrap(),
+            ("zaïve “quotes”".to_string(), encoding)
+        );
+    }
+
+    for byte in 0..=u8::MAX {
+        let text = Encoding::Windows1252.decode(&[byte]);
+        let mut bytes = Vec::new();
+        Encoding::Windows1252.encode(&text, &mut bytes).unwrap();
+        assert_eq!(bytes, [byte]);
+    }
+    assert!(!Encoding::Windows1252.can_encode('ą'));
+    assert!(Encoding::Windows1252.encode("ą", &mut Vec::new()).is_err());
+    assert_eq!(Encoding::from_name("Latin-1"), Some(Encoding::Windows1252));
+    assert_eq!(Encoding::from_name("utf-16le"), Some(Encoding::Utf16Le));
+    assert_eq!(Encoding::from_name("ebcdic"), None);
+}
+
 #[test]
 fn test_line_len() {
     let mut buffer = Buffer::new(0, BufferId::new(1).unwrap(), "".into());
@@ -205,6 +297,94 @@ fn test_line_len() {
     assert_eq!(buffer.line_len(5), 0);
 }
 
+#[test]
+fn test_clipped_conversions() {
+    use Bias::{Left, Right};
+
+    let buffer = Buffer::new(0, BufferId::new(1).unwrap(), "".into());
+    for bias in [Left, Right] {
+        assert_eq!(buffer.point_for_offset(0, bias), Point::zero());
+        assert_eq!(buffer.point_for_offset(5, bias), Point::zero());
+        assert_eq!(buffer.offset_for_point(Point::new(3, 4), bias), 0);
+        assert_eq!(
+            buffer.point_utf16_for_point(Point::new(2, 0), bias),
+            PointUtf16::zero()
+        );
+        assert_eq!(
+            buffer.point_for_point_utf16(Unclipped(PointUtf16::new(0, 3)), bias),
+            Point::zero()
+        );
+    }
+    assert_eq!(buffer.checked_offset_for_point(Point::zero()), Some(0));
+    assert_eq!(buffer.checked_offset_for_point(Point::new(0, 1)), None);
+
+    // "é" takes 2 bytes and 1 UTF-16 code unit, "😀" takes 4 bytes and 2 code units.
+    let buffer = Buffer::new(0, BufferId::new(1).unwrap(), "aé😀\nb".into());
+
+    // Offsets inside of characters and past the end of the buffer.
+    assert_eq!(buffer.point_for_offset(2, Left), Point::new(0, 1));
+    assert_eq!(buffer.point_for_offset(2, Right), Point::new(0, 3));
+    assert_eq!(buffer.point_for_offset(5, Left), Point::new(0, 3));
+    assert_eq!(buffer.point_for_offset(5, Right), Point::new(0, 7));
+    assert_eq!(buffer.point_for_offset(8, Left), Point::new(1, 0));
+    assert_eq!(buffer.point_for_offset(100, Left), Point::new(1, 1));
+
+    // Points inside of characters, past the end of a line and past the end of the buffer.
+    assert_eq!(buffer.offset_for_point(Point::new(0, 2), Left), 1);
+    assert_eq!(buffer.offset_for_point(Point::new(0, 2), Right), 3);
+    assert_eq!(buffer.offset_for_point(Point::new(0, 100), Left), 7);
+    assert_eq!(buffer.offset_for_point(Point::new(1, 100), Right), 9);
+    assert_eq!(buffer.offset_for_point(Point::new(5, 0), Left), 9);
+
+    assert_eq!(buffer.checked_offset_for_point(Point::new(0, 3)), Some(3));
+    assert_eq!(buffer.checked_offset_for_point(Point::new(1, 1)), Some(9));
+    assert_eq!(buffer.checked_offset_for_point(Point::new(0, 2)), None);
+    assert_eq!(buffer.checked_offset_for_point(Point::new(0, 100)), None);
+    assert_eq!(buffer.checked_offset_for_point(Point::new(5, 0)), None);
+
+    // UTF-16 columns.
+    assert_eq!(
+        buffer.point_utf16_for_point(Point::new(0, 7), Left),
+        PointUtf16::new(0, 4)
+    );
+    assert_eq!(
+        buffer.point_utf16_for_point(Point::new(0, 5), Left),
+        PointUtf16::new(0, 2)
+    );
+    assert_eq!(
+        buffer.point_utf16_for_point(Point::new(0, 5), Right),
+        PointUtf16::new(0, 4)
+    );
+    assert_eq!(
+        buffer.point_utf16_for_point(Point::new(0, 100), Left),
+        PointUtf16::new(0, 4)
+    );
+    assert_eq!(
+        buffer.point_utf16_for_point(Point::new(9, 9), Left),
+        PointUtf16::new(1, 1)
+    );
+    assert_eq!(
+        buffer.point_for_point_utf16(Unclipped(PointUtf16::new(0, 2)), Left),
+        Point::new(0, 3)
+    );
+    assert_eq!(
+        buffer.point_for_point_utf16(Unclipped(PointUtf16::new(0, 3)), Left),
+        Point::new(0, 3)
+    );
+    assert_eq!(
+        buffer.point_for_point_utf16(Unclipped(PointUtf16::new(0, 3)), Right),
+        Point::new(0, 7)
+    );
+    assert_eq!(
+        buffer.point_for_point_utf16(Unclipped(PointUtf16::new(0, 100)), Left),
+        Point::new(0, 7)
+    );
+    assert_eq!(
+        buffer.point_for_point_utf16(Unclipped(PointUtf16::new(5, 0)), Left),
+        Point::new(1, 1)
+    );
+}
+
 #[test]
 fn test_common_prefix_at_position() {
     let text = "a = str; b = δα";
@@ -501,6 +681,114 @@ fn test_anchors_at_start_and_end() {
     assert_eq!(after_end_anchor.to_offset(&buffer), 9);
 }
 
+#[test]
+fn test_resolve_anchors() {
+    let mut buffer = Buffer::new(0, BufferId::new(1).unwrap(), "abcdef".into());
+    let anchors = [
+        buffer.anchor_after(4),
+        buffer.anchor_before(1),
+        Anchor::MAX,
+        buffer.anchor_after(1),
+        Anchor::MIN,
+    ];
+
+    buffer.edit([(1..1, "XY"), (3..5, "")]);
+    assert_eq!(buffer.text(), "aXYbcf");
+    assert_eq!(buffer.resolve_anchors::<usize>(&anchors), [5, 1, 6, 3, 0]);
+    assert_eq!(
+        buffer.resolve_anchors::<usize>(&anchors),
+        anchors
+            .iter()
+            .map(|anchor| anchor.to_offset(&buffer))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        buffer.resolve_anchors::<Point>(&anchors[..2]),
+        [Point::new(0, 5), Point::new(0, 1)]
+    );
+}
+
+#[gpui::test(iterations = 100)]
+fn test_random_anchor_biases(mut rng: StdRng) {
+    let operations = env::var("OPERATIONS")
+        .map(|i| i.parse().expect("invalid `OPERATIONS` variable"))
+        .unwrap_or(10);
+
+    let base_text_len = rng.gen_range(0..20);
+    let base_text = RandomCharIter::new(&mut rng)
+        .take(base_text_len)
+        .collect::<String>();
+    let mut network = Network::new(rng.clone());
+    let mut buffers = Vec::new();
+    for replica_id in 0..2 {
+        let buffer = Buffer::new(replica_id, BufferId::new(1).unwrap(), base_text.clone());
+        buffers.push(buffer);
+        network.add_peer(replica_id);
+    }
+
+    // Pairs of anchors that start out at the same offset, one biased to each side.
+    let anchor_pairs = (0..10)
+        .map(|_| {
+            let offset = buffers[0].clip_offset(rng.gen_range(0..=base_text_len), Bias::Left);
+            (
+                buffers[0].anchor_before(offset),
+                buffers[0].anchor_after(offset),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let mut mutation_count = operations;
+    loop {
+        let replica_id = rng.gen_range(0..2);
+        let buffer = &mut buffers[replica_id as usize];
+        match rng.gen_range(0..=100) {
+            0..=60 if mutation_count != 0 => {
+                let op = buffer.randomly_edit(&mut rng, 3).1;
+                network.broadcast(replica_id, vec![op]);
+                mutation_count -= 1;
+            }
+            61..=100 if network.has_unreceived(replica_id) => {
+                buffer.apply_ops(network.receive(replica_id)).unwrap();
+            }
+            _ => {}
+        }
+
+        if mutation_count == 0 && network.is_idle() {
+            break;
+        }
+    }
+    assert_eq!(buffers[0].text(), buffers[1].text());
+
+    let anchors = anchor_pairs
+        .iter()
+        .flat_map(|(left, right)| [*left, *right])
+        .collect::<Vec<_>>();
+    let offsets = buffers[0].resolve_anchors::<usize>(&anchors);
+    for buffer in &buffers {
+        // Both replicas agree on where every anchor is, batched or not.
+        assert_eq!(buffer.resolve_anchors::<usize>(&anchors), offsets);
+        for (anchor, offset) in anchors.iter().zip(&offsets) {
+            assert_eq!(anchor.to_offset(buffer), *offset);
+        }
+
+        // Text inserted between the two anchors of a pair lands after the left-biased
+        // one and before the right-biased one.
+        for (left, right) in &anchor_pairs {
+            assert_eq!(left.cmp(right, buffer), Ordering::Less);
+            assert!(left.to_offset(buffer) <= right.to_offset(buffer));
+        }
+
+        // Comparing anchors agrees with comparing the offsets they resolve to.
+        for (a, a_offset) in anchors.iter().zip(&offsets) {
+            for (b, b_offset) in anchors.iter().zip(&offsets) {
+                if a.cmp(b, buffer) == Ordering::Less {
+                    assert!(a_offset <= b_offset);
+                }
+            }
+        }
+    }
+}
+
 #[test]
 fn test_undo_redo() {
     let mut buffer = Buffer::new(0, BufferId::new(1).unwrap(), "1234".into());
@@ -639,6 +927,45 @@ fn test_finalize_last_transaction() {
     assert_eq!(buffer.text(), "ab2cde6");
 }
 
+#[test]
+fn test_nested_transactions_with_remote_edits() {
+    let now = Instant::now();
+    let mut buffer1 = Buffer::new(1, BufferId::new(1).unwrap(), "abc".into());
+    let mut buffer2 = Buffer::new(2, BufferId::new(1).unwrap(), "abc".into());
+
+    assert!(buffer1.start_transaction_at(now).is_some());
+    let local_op_1 = buffer1.edit([(0..0, "1")]);
+    assert!(buffer1.start_transaction_at(now).is_none());
+    let remote_op = buffer2.edit([(3..3, "2")]);
+    buffer1.apply_op(remote_op.clone()).unwrap();
+    let local_op_2 = buffer1.edit([(2..2, "3")]);
+    assert!(buffer1.end_transaction_at(now).is_none());
+    assert!(buffer1.end_transaction_at(now).is_some());
+    assert_eq!(buffer1.text(), "1a3bc2");
+
+    // The nested transaction is undone along with the outer one, while the remote edit
+    // made in between is kept.
+    let (_, undo_op) = buffer1.undo().unwrap();
+    assert_eq!(buffer1.text(), "abc2");
+    assert!(buffer1.undo().is_none());
+    assert_eq!(buffer1.text(), "abc2");
+
+    buffer2.apply_op(local_op_1).unwrap();
+    buffer2.apply_op(local_op_2).unwrap();
+    buffer2.apply_op(undo_op).unwrap();
+    assert_eq!(buffer2.text(), "abc2");
+
+    let (_, undo_op) = buffer2.undo().unwrap();
+    assert_eq!(buffer2.text(), "abc");
+    buffer1.apply_op(undo_op).unwrap();
+    assert_eq!(buffer1.text(), "abc");
+
+    let (_, redo_op) = buffer1.redo().unwrap();
+    assert_eq!(buffer1.text(), "1a3bc");
+    buffer2.apply_op(redo_op).unwrap();
+    assert_eq!(buffer2.text(), "1a3bc");
+}
+
 #[test]
 fn test_edited_ranges_for_transaction() {
     let now = Instant::now();
@@ -704,6 +1031,47 @@ fn test_concurrent_edits() {
     assert_eq!(buffer3.text(), "a12c34e56");
 }
 
+#[test]
+fn test_snapshots_on_background_threads() {
+    fn assert_send_and_sync<T: Send + Sync>() {}
+    assert_send_and_sync::<BufferSnapshot>();
+
+    let mut buffer = Buffer::new(0, BufferId::new(1).unwrap(), "one two three\n".repeat(100));
+    let snapshot = buffer.snapshot();
+    let expected_text = snapshot.text();
+
+    // Find the words on a background thread while the buffer keeps getting edited. The words
+    // are reported as anchors, so they can be resolved against whatever the buffer is by then.
+    let consumer = std::thread::spawn(move || {
+        let mut words = Vec::new();
+        let mut offset = 0;
+        for line in snapshot.text().split_inclusive('\n') {
+            for word in line.split_inclusive([' ', '\n']) {
+                let len = word.trim_end().len();
+                words.push(snapshot.anchor_after(offset)..snapshot.anchor_before(offset + len));
+                offset += word.len();
+            }
+        }
+        (snapshot, words)
+    });
+    for ix in 0..100 {
+        buffer.edit([(0..0, format!("edit {ix}\n"))]);
+    }
+    let (snapshot, words) = consumer.join().unwrap();
+
+    assert_eq!(snapshot.text(), expected_text);
+    assert!(buffer.has_edits_since(snapshot.version()));
+    assert_eq!(words.len(), 300);
+    assert!(words.iter().all(|word| {
+        let text = buffer.text_for_range(word.clone()).collect::<String>();
+        ["one", "two", "three"].contains(&text.as_str())
+    }));
+
+    // A snapshot taken now reflects every edit made so far.
+    assert_eq!(buffer.snapshot().text(), buffer.text());
+    assert!(!buffer.has_edits_since(buffer.snapshot().version()));
+}
+
 #[gpui::test(iterations = 100)]
 fn test_random_concurrent_edits(mut rng: StdRng) {
     let peers = env::var("PEERS")
@@ -720,6 +1088,7 @@ fn test_random_concurrent_edits(mut rng: StdRng) {
     let mut replica_ids = Vec::new();
     let mut buffers = Vec::new();
     let mut network = Network::new(rng.clone());
+    let mut codec = VersionCodec::default();
 
     for i in 0..peers {
         let mut buffer = Buffer::new(i as ReplicaId, BufferId::new(1).unwrap(), base_text.clone());
@@ -739,17 +1108,17 @@ fn test_random_concurrent_edits(mut rng: StdRng) {
         match rng.gen_range(0..=100) {
             0..=50 if mutation_count != 0 => {
                 let op = buffer.randomly_edit(&mut rng, 5).1;
-                network.broadcast(buffer.replica_id, vec![op]);
+                network.broadcast(buffer.replica_id, codec.encode(buffer.replica_id, vec![op]));
                 log::info!("buffer {} text: {:?}", buffer.replica_id, buffer.text());
                 mutation_count -= 1;
             }
             51..=70 if mutation_count != 0 => {
                 let ops = buffer.randomly_undo_redo(&mut rng);
-                network.broadcast(buffer.replica_id, ops);
+                network.broadcast(buffer.replica_id, codec.encode(buffer.replica_id, ops));
                 mutation_count -= 1;
             }
             71..=100 if network.has_unreceived(replica_id) => {
-                let ops = network.receive(replica_id);
+                let ops = codec.decode(replica_id, network.receive(replica_id));
                 if !ops.is_empty() {
                     log::info!(
                         "peer {} applying {} ops from the network.",
@@ -768,6 +1137,8 @@ fn test_random_concurrent_edits(mut rng: StdRng) {
         }
     }
 
+    assert!(codec.pending.values().all(Vec::is_empty));
+
     let first_buffer = &buffers[0];
     for buffer in &buffers[1..] {
         assert_eq!(
@@ -776,6 +1147,81 @@ fn test_random_concurrent_edits(mut rng: StdRng) {
             "Replica {} text != Replica 0 text",
             buffer.replica_id
         );
+        assert_eq!(
+            buffer.deleted_text(),
+            first_buffer.deleted_text(),
+            "Replica {} deleted text != Replica 0 deleted text",
+            buffer.replica_id
+        );
         buffer.check_invariants();
     }
 }
+
+/// Sends operations with their versions encoded like on the wire, on one stream per sender.
+#[derive(Default)]
+struct VersionCodec {
+    encoders: HashMap<ReplicaId, VersionEncoder>,
+    decoders: HashMap<(ReplicaId, ReplicaId), VersionDecoder>,
+    /// Operations whose versions can't be decoded yet, by receiver.
+    pending: HashMap<ReplicaId, Vec<EncodedOperation>>,
+}
+
+#[derive(Clone)]
+struct EncodedOperation {
+    sender: ReplicaId,
+    version: EncodedVersion,
+    operation: Operation,
+}
+
+impl VersionCodec {
+    fn encode(&mut self, sender: ReplicaId, operations: Vec<Operation>) -> Vec<EncodedOperation> {
+        let encoder = self.encoders.entry(sender).or_default();
+        operations
+            .into_iter()
+            .map(|mut operation| {
+                let version = mem::take(operation_version_mut(&mut operation));
+                EncodedOperation {
+                    sender,
+                    version: encoder.encode(&version),
+                    operation,
+                }
+            })
+            .collect()
+    }
+
+    fn decode(&mut self, receiver: ReplicaId, received: Vec<EncodedOperation>) -> Vec<Operation> {
+        let mut undecoded = self.pending.remove(&receiver).unwrap_or_default();
+        undecoded.extend(received);
+
+        // Decoding a version can make the ones after it on the same stream decodable.
+        let mut operations = Vec::new();
+        loop {
+            let mut pending = Vec::new();
+            let decoded_count = operations.len();
+            for mut encoded in undecoded {
+                let decoder = self.decoders.entry((receiver, encoded.sender)).or_default();
+                match decoder.decode(&encoded.version) {
+                    DecodedVersion::Version(version) => {
+                        *operation_version_mut(&mut encoded.operation) = version;
+                        operations.push(encoded.operation);
+                    }
+                    DecodedVersion::Pending => pending.push(encoded),
+                    DecodedVersion::Duplicate => {}
+                }
+            }
+            undecoded = pending;
+            if operations.len() == decoded_count || undecoded.is_empty() {
+                break;
+            }
+        }
+        self.pending.insert(receiver, undecoded);
+        operations
+    }
+}
+
+fn operation_version_mut(operation: &mut Operation) -> &mut clock::Global {
+    match operation {
+        Operation::Edit(edit) => &mut edit.version,
+        Operation::Undo(undo) => &mut undo.version,
+    }
+}
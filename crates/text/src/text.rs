@@ -1,4 +1,5 @@
 mod anchor;
+mod encoding;
 pub mod locator;
 #[cfg(any(test, feature = "test-support"))]
 pub mod network;
@@ -14,6 +15,7 @@ pub use anchor::*;
 use anyhow::{anyhow, Context as _, Result};
 pub use clock::ReplicaId;
 use collections::{HashMap, HashSet};
+pub use encoding::Encoding;
 use locator::Locator;
 use operation_queue::OperationQueue;
 pub use patch::Patch;
@@ -29,6 +31,7 @@ use std::{
     fmt::Display,
     future::Future,
     iter::Iterator,
+    mem,
     num::NonZeroU64,
     ops::{self, Deref, Range, Sub},
     str,
@@ -59,6 +62,7 @@ pub struct Buffer {
     subscriptions: Topic,
     edit_id_resolvers: HashMap<clock::Lamport, Vec<oneshot::Sender<()>>>,
     wait_for_version_txs: Vec<(clock::Global, oneshot::Sender<()>)>,
+    has_mixed_line_endings: bool,
 }
 
 #[repr(transparent)]
@@ -614,9 +618,12 @@ impl FromIterator<char> for LineIndent {
 
 impl Buffer {
     pub fn new(replica_id: u16, remote_id: BufferId, mut base_text: String) -> Buffer {
-        let line_ending = LineEnding::detect(&base_text);
+        let (line_ending, has_mixed_line_endings) = LineEnding::detect_mixed(&base_text);
         LineEnding::normalize(&mut base_text);
-        Self::new_normalized(replica_id, remote_id, line_ending, Rope::from(base_text))
+        let mut buffer =
+            Self::new_normalized(replica_id, remote_id, line_ending, Rope::from(base_text));
+        buffer.has_mixed_line_endings = has_mixed_line_endings;
+        buffer
     }
 
     pub fn new_normalized(
@@ -673,6 +680,7 @@ impl Buffer {
             subscriptions: Default::default(),
             edit_id_resolvers: Default::default(),
             wait_for_version_txs: Default::default(),
+            has_mixed_line_endings: false,
         }
     }
 
@@ -680,6 +688,10 @@ impl Buffer {
         self.version.clone()
     }
 
+    /// Returns an immutable view of the buffer's current state. Snapshots share the buffer's
+    /// trees, so taking one is cheap and later edits don't affect it. They are `Send`, for
+    /// reading the text on a background thread, and their [`BufferSnapshot::version`] tells
+    /// whether the results computed from them are still up to date.
     pub fn snapshot(&self) -> BufferSnapshot {
         self.snapshot.clone()
     }
@@ -895,6 +907,16 @@ impl Buffer {
         self.snapshot.line_ending = line_ending;
     }
 
+    /// Whether the text this buffer was loaded from used more than one kind of line
+    /// ending. All of them are saved as [`Self::line_ending`].
+    pub fn has_mixed_line_endings(&self) -> bool {
+        self.has_mixed_line_endings
+    }
+
+    pub fn set_has_mixed_line_endings(&mut self, has_mixed_line_endings: bool) {
+        self.has_mixed_line_endings = has_mixed_line_endings;
+    }
+
     pub fn apply_ops<I: IntoIterator<Item = Operation>>(&mut self, ops: I) -> Result<()> {
         let mut deferred_ops = Vec::new();
         for op in ops {
@@ -1240,18 +1262,26 @@ impl Buffer {
     }
 
     fn flush_deferred_ops(&mut self) -> Result<()> {
-        self.deferred_replicas.clear();
-        let mut deferred_ops = Vec::new();
-        for op in self.deferred_ops.drain().iter().cloned() {
-            if self.can_apply_op(&op) {
-                self.apply_op(op)?;
-            } else {
-                self.deferred_replicas.insert(op.replica_id());
-                deferred_ops.push(op);
+        let mut deferred_ops = mem::take(&mut self.deferred_ops);
+        let result = loop {
+            self.deferred_replicas.clear();
+            let ops = deferred_ops.drain_ready(usize::MAX, |op| {
+                if self.can_apply_op(op) {
+                    true
+                } else {
+                    self.deferred_replicas.insert(op.replica_id());
+                    false
+                }
+            });
+            if ops.is_empty() {
+                break Ok(());
             }
-        }
-        self.deferred_ops.insert(deferred_ops);
-        Ok(())
+            if let Err(error) = ops.into_iter().try_for_each(|op| self.apply_op(op)) {
+                break Err(error);
+            }
+        };
+        self.deferred_ops = deferred_ops;
+        result
     }
 
     fn can_apply_op(&self, op: &Operation) -> bool {
@@ -1273,6 +1303,19 @@ impl Buffer {
         self.history.redo_stack.last()
     }
 
+    /// The transactions that can be undone, from the oldest to the most recent one.
+    pub fn undo_transactions(&self) -> impl DoubleEndedIterator<Item = &Transaction> {
+        self.history
+            .undo_stack
+            .iter()
+            .map(|entry| &entry.transaction)
+    }
+
+    /// Whether the given transaction is still on the undo or redo stack.
+    pub fn has_transaction(&self, transaction_id: TransactionId) -> bool {
+        self.history.transaction(transaction_id).is_some()
+    }
+
     pub fn start_transaction(&mut self) -> Option<TransactionId> {
         self.start_transaction_at(Instant::now())
     }
@@ -1332,7 +1375,10 @@ impl Buffer {
         self.undo_or_redo(transaction).log_err()
     }
 
-    pub fn undo_to_transaction(&mut self, transaction_id: TransactionId) -> Vec<Operation> {
+    pub fn undo_to_transaction(
+        &mut self,
+        transaction_id: TransactionId,
+    ) -> Vec<(TransactionId, Operation)> {
         let transactions = self
             .history
             .remove_from_undo_until(transaction_id)
@@ -1342,7 +1388,7 @@ impl Buffer {
 
         transactions
             .into_iter()
-            .map(|transaction| self.undo_or_redo(transaction).unwrap())
+            .map(|transaction| (transaction.id, self.undo_or_redo(transaction).unwrap()))
             .collect()
     }
 
@@ -1365,7 +1411,10 @@ impl Buffer {
         }
     }
 
-    pub fn redo_to_transaction(&mut self, transaction_id: TransactionId) -> Vec<Operation> {
+    pub fn redo_to_transaction(
+        &mut self,
+        transaction_id: TransactionId,
+    ) -> Vec<(TransactionId, Operation)> {
         let transactions = self
             .history
             .remove_from_redo(transaction_id)
@@ -1375,7 +1424,7 @@ impl Buffer {
 
         transactions
             .into_iter()
-            .map(|transaction| self.undo_or_redo(transaction).unwrap())
+            .map(|transaction| (transaction.id, self.undo_or_redo(transaction).unwrap()))
             .collect()
     }
 
@@ -1402,6 +1451,21 @@ impl Buffer {
         self.history.finalize_last_transaction();
     }
 
+    /// Pushes a transaction without any edits onto the undo stack, so that a change to the
+    /// buffer made outside of its text can be undone. It's never grouped with transactions
+    /// around it.
+    pub fn push_empty_transaction(&mut self, now: Instant) -> TransactionId {
+        self.history.finalize_last_transaction();
+        let transaction = Transaction {
+            id: self.lamport_clock.tick(),
+            edit_ids: Vec::new(),
+            start: self.version.clone(),
+        };
+        let transaction_id = transaction.id;
+        self.push_transaction(transaction, now);
+        transaction_id
+    }
+
     pub fn edited_ranges_for_transaction_id<D>(
         &self,
         transaction_id: TransactionId,
@@ -2034,6 +2098,12 @@ impl BufferSnapshot {
             .summary(range.end.to_offset(self))
     }
 
+    /// Returns the number of characters and words in the given range, see [`TextStats`].
+    pub fn text_stats_for_range<O: ToOffset>(&self, range: Range<O>) -> TextStats {
+        self.visible_text
+            .stats_in_range(range.start.to_offset(self)..range.end.to_offset(self))
+    }
+
     pub fn summaries_for_anchors<'a, D, A>(&'a self, anchors: A) -> impl 'a + Iterator<Item = D>
     where
         D: 'a + TextDimension,
@@ -2044,6 +2114,33 @@ impl BufferSnapshot {
             .map(|d| d.0)
     }
 
+    /// Resolves many anchors at once, returning their positions in the same order as the
+    /// given anchors. The anchors don't need to be sorted: they are ordered first so that
+    /// all of them can be resolved in a single pass over the buffer's fragments, which is
+    /// much cheaper than resolving each of them separately.
+    pub fn resolve_anchors<D>(&self, anchors: &[Anchor]) -> Vec<D>
+    where
+        D: TextDimension,
+    {
+        let mut order = (0..anchors.len()).collect::<Vec<_>>();
+        order.sort_by_cached_key(|ix| {
+            let anchor = &anchors[*ix];
+            (
+                self.fragment_id_for_anchor(anchor),
+                anchor.offset,
+                anchor.bias,
+            )
+        });
+
+        let mut summaries = self
+            .summaries_for_anchors_with_payload::<D, _, _>(
+                order.iter().map(|ix| (&anchors[*ix], *ix)),
+            )
+            .collect::<Vec<_>>();
+        summaries.sort_unstable_by_key(|(_, ix)| *ix);
+        summaries.into_iter().map(|(summary, _)| summary).collect()
+    }
+
     pub fn summaries_for_anchors_with_payload<'a, D, A, T>(
         &'a self,
         anchors: A,
@@ -2241,6 +2338,38 @@ impl BufferSnapshot {
         self.visible_text.clip_point_utf16(point, bias)
     }
 
+    /// Converts an offset to a point. Offsets inside of a character are clipped in the
+    /// direction of `bias`, and offsets past the end of the buffer are clamped to its end.
+    pub fn point_for_offset(&self, offset: usize, bias: Bias) -> Point {
+        self.offset_to_point(self.clip_offset(offset, bias))
+    }
+
+    /// Converts a point to an offset. Columns past the end of a line are clipped to the end of
+    /// that line, rows past the last one are clamped to the end of the buffer, and columns
+    /// inside of a character are clipped in the direction of `bias`.
+    pub fn offset_for_point(&self, point: Point, bias: Bias) -> usize {
+        self.point_to_offset(self.clip_point(point, bias))
+    }
+
+    /// Converts a point to an offset, or returns `None` if the point doesn't lie on a character
+    /// boundary within the buffer.
+    pub fn checked_offset_for_point(&self, point: Point) -> Option<usize> {
+        (self.clip_point(point, Bias::Left) == point).then(|| self.point_to_offset(point))
+    }
+
+    /// Converts a point to one whose column counts UTF-16 code units, as language servers
+    /// expect. The point is clipped first, in the same way as in [`Self::offset_for_point`].
+    pub fn point_utf16_for_point(&self, point: Point, bias: Bias) -> PointUtf16 {
+        self.point_to_point_utf16(self.clip_point(point, bias))
+    }
+
+    /// Converts a point whose column counts UTF-16 code units to one that counts bytes.
+    /// Columns in the middle of a surrogate pair are clipped in the direction of `bias`.
+    pub fn point_for_point_utf16(&self, point: Unclipped<PointUtf16>, bias: Bias) -> Point {
+        let point = self.clip_point_utf16(point, bias);
+        self.unclipped_point_utf16_to_point(Unclipped(point))
+    }
+
     pub fn edits_since<'a, D>(
         &'a self,
         since: &'a clock::Global,
@@ -2954,21 +3083,34 @@ impl LineEnding {
         }
     }
 
+    /// Detects the line ending used by most of the lines in the given text, defaulting
+    /// to the platform's line ending for text without any line breaks.
     pub fn detect(text: &str) -> Self {
-        let mut max_ix = cmp::min(text.len(), 1000);
-        while !text.is_char_boundary(max_ix) {
-            max_ix -= 1;
-        }
+        Self::detect_mixed(text).0
+    }
 
-        if let Some(ix) = text[..max_ix].find(['\n']) {
-            if ix > 0 && text.as_bytes()[ix - 1] == b'\r' {
-                Self::Windows
-            } else {
-                Self::Unix
-            }
+    /// Returns whether the given text uses more than one kind of line ending.
+    pub fn is_mixed(text: &str) -> bool {
+        Self::detect_mixed(text).1
+    }
+
+    /// Detects the line ending used by most of the lines in the given text along with
+    /// whether it uses more than one kind, in a single pass over the text.
+    pub fn detect_mixed(text: &str) -> (Self, bool) {
+        let counts = LineEndingCounts::new(text);
+        let line_ending = if counts.windows > counts.unix {
+            Self::Windows
+        } else if counts.unix > 0 {
+            Self::Unix
         } else {
             Self::default()
-        }
+        };
+        let is_mixed = [counts.unix, counts.windows, counts.other]
+            .iter()
+            .filter(|count| **count > 0)
+            .count()
+            > 1;
+        (line_ending, is_mixed)
     }
 
     pub fn normalize(text: &mut String) {
@@ -2985,3 +3127,33 @@ impl LineEnding {
         }
     }
 }
+
+/// The number of line breaks of each kind in a text.
+struct LineEndingCounts {
+    unix: usize,
+    windows: usize,
+    other: usize,
+}
+
+impl LineEndingCounts {
+    fn new(text: &str) -> Self {
+        let mut counts = Self {
+            unix: 0,
+            windows: 0,
+            other: 0,
+        };
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\n' => counts.unix += 1,
+                '\r' if chars.peek() == Some(&'\n') => {
+                    chars.next();
+                    counts.windows += 1;
+                }
+                '\r' | '\u{2028}' | '\u{2029}' => counts.other += 1,
+                _ => {}
+            }
+        }
+        counts
+    }
+}
@@ -101,6 +101,7 @@ pub(crate) fn one_dark() -> Theme {
                     0.2,
                 ),
                 editor_document_highlight_write_background: gpui::red(),
+                editor_word_highlight_background: hsla(207.8 / 360., 81. / 100., 66. / 100., 0.1),
 
                 terminal_background: bg,
                 // todo("Use one colors for terminal")
@@ -1,5 +1,8 @@
 use std::sync::Arc;
-use std::{fmt::Debug, path::Path};
+use std::{
+    fmt::Debug,
+    path::{Path, PathBuf},
+};
 
 use anyhow::{anyhow, Context, Result};
 use collections::HashMap;
@@ -36,6 +39,8 @@ impl Global for GlobalThemeRegistry {}
 
 struct ThemeRegistryState {
     themes: HashMap<SharedString, Arc<Theme>>,
+    /// The names of the themes loaded from each user theme file.
+    user_theme_names_by_path: HashMap<PathBuf, Vec<SharedString>>,
 }
 
 pub struct ThemeRegistry {
@@ -65,6 +70,7 @@ impl ThemeRegistry {
         let registry = Self {
             state: RwLock::new(ThemeRegistryState {
                 themes: HashMap::default(),
+                user_theme_names_by_path: HashMap::default(),
             }),
             assets,
         };
@@ -269,13 +275,44 @@ impl ThemeRegistry {
     }
 
     /// Loads the user theme from the specified path and adds it to the registry.
+    ///
+    /// The themes previously loaded from the same path are replaced.
     pub async fn load_user_theme(&self, theme_path: &Path, fs: Arc<dyn Fs>) -> Result<()> {
         let theme = Self::read_user_theme(theme_path, fs).await?;
 
+        let theme_names = theme
+            .themes
+            .iter()
+            .map(|theme| SharedString::from(theme.name.clone()))
+            .collect::<Vec<_>>();
+        self.unload_user_theme(theme_path);
         self.insert_user_theme_families([theme]);
+        self.state
+            .write()
+            .user_theme_names_by_path
+            .insert(theme_path.to_path_buf(), theme_names);
 
         Ok(())
     }
+
+    /// Removes the themes that were loaded from the specified path, such as when the file
+    /// was deleted.
+    pub fn unload_user_theme(&self, theme_path: &Path) {
+        let mut state = self.state.write();
+        let Some(theme_names) = state.user_theme_names_by_path.remove(theme_path) else {
+            return;
+        };
+        for theme_name in theme_names {
+            // Another file may define a theme with the same name.
+            let defined_elsewhere = state
+                .user_theme_names_by_path
+                .values()
+                .any(|names| names.contains(&theme_name));
+            if !defined_elsewhere {
+                state.themes.remove(&theme_name);
+            }
+        }
+    }
 }
 
 impl Default for ThemeRegistry {
@@ -283,3 +320,47 @@ impl Default for ThemeRegistry {
         Self::new(Box::new(()))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fs::FakeFs;
+    use gpui::TestAppContext;
+
+    fn theme_file(theme_name: &str) -> Vec<u8> {
+        serde_json::json!({
+            "name": "Test",
+            "author": "Zed",
+            "themes": [{ "name": theme_name, "appearance": "dark", "style": {} }]
+        })
+        .to_string()
+        .into_bytes()
+    }
+
+    #[gpui::test]
+    async fn test_reloading_and_unloading_user_themes(cx: &mut TestAppContext) {
+        let fs = FakeFs::new(cx.executor());
+        let registry = ThemeRegistry::default();
+        let theme_path = Path::new("/themes/test.json");
+
+        fs.insert_file(theme_path, theme_file("Test Dark")).await;
+        registry
+            .load_user_theme(theme_path, fs.clone())
+            .await
+            .unwrap();
+        assert!(registry.get("Test Dark").is_ok());
+
+        // Renaming the theme in its file replaces the old one.
+        fs.insert_file(theme_path, theme_file("Test Night")).await;
+        registry
+            .load_user_theme(theme_path, fs.clone())
+            .await
+            .unwrap();
+        assert!(registry.get("Test Dark").is_err());
+        assert!(registry.get("Test Night").is_ok());
+
+        registry.unload_user_theme(theme_path);
+        assert!(registry.get("Test Night").is_err());
+        assert!(registry.get("One Dark").is_ok());
+    }
+}
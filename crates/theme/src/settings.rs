@@ -412,13 +412,29 @@ impl settings::Settings for ThemeSettings {
             ui_density: defaults.ui_density.unwrap_or(UiDensity::Default),
         };
 
+        // A font that isn't installed would silently be drawn with a fallback font, so keep
+        // the previous family instead and say why.
+        let mut available_font_names = None;
+        let mut is_font_available = |family: &str, cx: &AppContext| {
+            let available = available_font_names
+                .get_or_insert_with(|| cx.text_system().all_font_names())
+                .iter()
+                .any(|name| name == family);
+            if !available {
+                log::warn!("font family {family:?} is not available, using the default one");
+            }
+            available
+        };
+
         for value in sources.user.into_iter().chain(sources.release_channel) {
             if let Some(value) = value.ui_density {
                 this.ui_density = value;
             }
 
             if let Some(value) = value.buffer_font_family.clone() {
-                this.buffer_font.family = value.into();
+                if is_font_available(&value, cx) {
+                    this.buffer_font.family = value.into();
+                }
             }
             if let Some(value) = value.buffer_font_features.clone() {
                 this.buffer_font.features = value;
@@ -429,7 +445,9 @@ impl settings::Settings for ThemeSettings {
             }
 
             if let Some(value) = value.ui_font_family.clone() {
-                this.ui_font.family = value.into();
+                if is_font_available(&value, cx) {
+                    this.ui_font.family = value.into();
+                }
             }
             if let Some(value) = value.ui_font_features.clone() {
                 this.ui_font.features = value;
@@ -522,3 +540,38 @@ fn merge<T: Copy>(target: &mut T, value: Option<T>) {
         *target = value;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use settings::SettingsStore;
+
+    #[gpui::test]
+    fn test_unavailable_font_family(cx: &mut AppContext) {
+        let store = SettingsStore::test(cx);
+        cx.set_global(store);
+        crate::init(crate::LoadThemes::JustBase, cx);
+        let default_family = ThemeSettings::get_global(cx).buffer_font.family.clone();
+
+        SettingsStore::update_global(cx, |store, cx| {
+            store
+                .set_user_settings(r#"{ "buffer_font_family": "Not A Real Font Family" }"#, cx)
+                .unwrap();
+        });
+        assert_eq!(
+            ThemeSettings::get_global(cx).buffer_font.family,
+            default_family
+        );
+
+        // The fonts that text falls back to are always available.
+        SettingsStore::update_global(cx, |store, cx| {
+            store
+                .set_user_settings(r#"{ "buffer_font_family": "Zed Mono" }"#, cx)
+                .unwrap();
+        });
+        assert_eq!(
+            ThemeSettings::get_global(cx).buffer_font.family.as_ref(),
+            "Zed Mono"
+        );
+    }
+}
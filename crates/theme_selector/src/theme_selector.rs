@@ -257,11 +257,7 @@ impl PickerDelegate for ThemeSelectorDelegate {
             .themes
             .iter()
             .enumerate()
-            .map(|(id, meta)| StringMatchCandidate {
-                id,
-                char_bag: meta.name.as_ref().into(),
-                string: meta.name.to_string(),
-            })
+            .map(|(id, meta)| StringMatchCandidate::new(id, meta.name.to_string()))
             .collect::<Vec<_>>();
 
         cx.spawn(|this, mut cx| async move {
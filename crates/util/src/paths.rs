@@ -64,6 +64,7 @@ lazy_static::lazy_static! {
     pub static ref SUPERMAVEN_DIR: PathBuf = SUPPORT_DIR.join("supermaven");
     pub static ref DEFAULT_PRETTIER_DIR: PathBuf = SUPPORT_DIR.join("prettier");
     pub static ref DB_DIR: PathBuf = SUPPORT_DIR.join("db");
+    pub static ref RECOVERY_DIR: PathBuf = SUPPORT_DIR.join("recovery");
     pub static ref CRASHES_DIR: Option<PathBuf> = cfg!(target_os = "macos")
         .then_some(HOME.join("Library/Logs/DiagnosticReports"));
     pub static ref CRASHES_RETIRED_DIR: Option<PathBuf> = CRASHES_DIR
@@ -74,6 +75,7 @@ lazy_static::lazy_static! {
     pub static ref KEYMAP: PathBuf = CONFIG_DIR.join("keymap.json");
     pub static ref TASKS: PathBuf = CONFIG_DIR.join("tasks.json");
     pub static ref LAST_USERNAME: PathBuf = CONFIG_DIR.join("last-username.txt");
+    pub static ref DICTIONARY: PathBuf = CONFIG_DIR.join("dictionary.txt");
     pub static ref LOG: PathBuf = LOGS_DIR.join("Zed.log");
     pub static ref OLD_LOG: PathBuf = LOGS_DIR.join("Zed.log.old");
     pub static ref LOCAL_SETTINGS_RELATIVE_PATH: &'static Path = Path::new(".zed/settings.json");
@@ -171,6 +173,17 @@ impl<T: AsRef<Path>> PathExt for T {
     }
 }
 
+/// Expands a leading tilde (`~`) in the given path into the user's home directory.
+///
+/// Paths that don't start with a `~` component, such as `~user/foo` or `foo/~`,
+/// are returned unchanged.
+pub fn expand_tilde(path: &Path) -> PathBuf {
+    match path.strip_prefix("~") {
+        Ok(relative_path) => HOME.join(relative_path),
+        Err(_) => path.to_path_buf(),
+    }
+}
+
 /// A delimiter to use in `path_query:row_number:column_number` strings parsing.
 pub const FILE_ROW_COLUMN_DELIMITER: char = ':';
 
@@ -396,6 +409,24 @@ impl PathMatcher {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_expand_tilde() {
+        assert_eq!(expand_tilde(Path::new("~")), HOME.as_path());
+        assert_eq!(
+            expand_tilde(Path::new("~/code/proj")),
+            HOME.join("code/proj")
+        );
+        assert_eq!(
+            expand_tilde(Path::new("/abs/~/path")),
+            Path::new("/abs/~/path")
+        );
+        assert_eq!(
+            expand_tilde(Path::new("~user/proj")),
+            Path::new("~user/proj")
+        );
+        assert_eq!(expand_tilde(Path::new("./proj")), Path::new("./proj"));
+    }
+
     type TestPath = PathLikeWithPosition<String>;
 
     fn parse_str(s: &str) -> TestPath {
@@ -0,0 +1,377 @@
+//! Timings of named spans of work, such as the phases of startup, to find out where the time goes.
+//!
+//! Nothing is recorded until the timings are enabled. Until then, starting a span only checks a
+//! flag, so spans can be left in code that runs often.
+
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    fmt::Write as _,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use collections::HashMap;
+use lazy_static::lazy_static;
+
+/// How many spans are recorded at most. The spans started after that aren't recorded.
+pub const MAX_SPANS: usize = 10_000;
+
+lazy_static! {
+    static ref GLOBAL: Timings = Timings::new();
+}
+
+static NEXT_TIMINGS_ID: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    /// The spans that were started with [`Timings::span`] on this thread and weren't dropped
+    /// yet, innermost last.
+    static SCOPES: RefCell<Vec<(usize, SpanId)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Returns the timings of the app.
+pub fn global() -> &'static Timings {
+    &GLOBAL
+}
+
+/// Starts recording the timings of the app.
+pub fn enable() {
+    GLOBAL.enable();
+}
+
+/// Starts a span of the app's timings that nests the spans started on this thread until it's
+/// dropped. See [`Timings::span`].
+pub fn span(name: impl Into<Cow<'static, str>>) -> Span<'static> {
+    GLOBAL.span(name)
+}
+
+/// Starts a span of the app's timings that can be held across awaits. See [`Timings::start`].
+pub fn start(name: impl Into<Cow<'static, str>>) -> Span<'static> {
+    GLOBAL.start(name)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SpanId(usize);
+
+/// A span that was recorded.
+#[derive(Clone, Debug)]
+pub struct SpanRecord {
+    pub id: SpanId,
+    pub parent: Option<SpanId>,
+    pub name: Cow<'static, str>,
+    /// The name of the thread the span was started on.
+    pub thread: String,
+    /// When the span started, since the timings were created.
+    pub start: Duration,
+    /// How long the span took, unless it's still running.
+    pub duration: Option<Duration>,
+}
+
+/// Records how long named spans of work take, and which spans they're part of.
+pub struct Timings {
+    id: usize,
+    enabled: AtomicBool,
+    origin: Instant,
+    spans: Mutex<Vec<SpanRecord>>,
+    /// How many spans weren't recorded because there were too many.
+    skipped: AtomicUsize,
+}
+
+impl Default for Timings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Timings {
+    pub fn new() -> Self {
+        Self {
+            id: NEXT_TIMINGS_ID.fetch_add(1, Ordering::Relaxed),
+            enabled: AtomicBool::new(false),
+            origin: Instant::now(),
+            spans: Mutex::new(Vec::new()),
+            skipped: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn enable(&self) {
+        self.enabled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Starts a span that's part of the innermost span started with this method on this thread.
+    /// Until it's dropped, it's the innermost span itself, so synchronous work can be nested by
+    /// holding the returned span for the duration of a scope.
+    pub fn span(&self, name: impl Into<Cow<'static, str>>) -> Span<'_> {
+        if !self.is_enabled() {
+            return Span::disabled();
+        }
+        let span = self.record(name.into(), None, true);
+        if let Some(id) = span.id() {
+            SCOPES.with(|scopes| scopes.borrow_mut().push((self.id, id)));
+        }
+        span
+    }
+
+    /// Starts a span that's part of the innermost span started with [`Timings::span`] on this
+    /// thread, without nesting any spans itself. Unlike [`Timings::span`], it can be held across
+    /// awaits, and nested spans can be started with [`Span::child`].
+    pub fn start(&self, name: impl Into<Cow<'static, str>>) -> Span<'_> {
+        if !self.is_enabled() {
+            return Span::disabled();
+        }
+        self.record(name.into(), None, false)
+    }
+
+    fn record(&self, name: Cow<'static, str>, parent: Option<SpanId>, scoped: bool) -> Span<'_> {
+        let start = self.origin.elapsed();
+        let thread = thread::current();
+        let thread = thread
+            .name()
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("{:?}", thread.id()));
+
+        let mut spans = self.spans.lock().unwrap();
+        if spans.len() >= MAX_SPANS {
+            self.skipped.fetch_add(1, Ordering::Relaxed);
+            return Span::disabled();
+        }
+        let parent = parent.or_else(|| {
+            // Spans can be dropped on another thread than the one they were started on, so the
+            // ones that ended are skipped rather than assumed to be gone.
+            SCOPES.with(|scopes| {
+                scopes
+                    .borrow()
+                    .iter()
+                    .rev()
+                    .filter(|(timings_id, _)| *timings_id == self.id)
+                    .map(|(_, id)| *id)
+                    .find(|id| spans[id.0].duration.is_none())
+            })
+        });
+        let id = SpanId(spans.len());
+        spans.push(SpanRecord {
+            id,
+            parent,
+            name,
+            thread,
+            start,
+            duration: None,
+        });
+        Span {
+            timings: Some(self),
+            id,
+            scoped,
+        }
+    }
+
+    fn end(&self, id: SpanId, scoped: bool) {
+        let end = self.origin.elapsed();
+        if let Some(span) = self.spans.lock().unwrap().get_mut(id.0) {
+            span.duration = Some(end.saturating_sub(span.start));
+        }
+        if scoped {
+            SCOPES.with(|scopes| scopes.borrow_mut().retain(|scope| *scope != (self.id, id)));
+        }
+    }
+
+    /// Returns the spans that were recorded, in the order they started.
+    pub fn spans(&self) -> Vec<SpanRecord> {
+        self.spans.lock().unwrap().clone()
+    }
+
+    /// Renders the tree of the spans that were recorded, with how long each took and the thread
+    /// it started on.
+    pub fn report(&self) -> String {
+        let spans = self.spans();
+        let mut children = HashMap::<Option<SpanId>, Vec<&SpanRecord>>::default();
+        for span in &spans {
+            children.entry(span.parent).or_default().push(span);
+        }
+
+        let mut lines = Vec::new();
+        let mut stack = children
+            .get(&None)
+            .map(|roots| roots.iter().rev().map(|span| (0, *span)).collect())
+            .unwrap_or_else(Vec::new);
+        while let Some((depth, span)) = stack.pop() {
+            let label = format!("{}{}", "  ".repeat(depth), span.name);
+            let duration = match span.duration {
+                Some(duration) => format!("{:.1}ms", duration.as_secs_f64() * 1000.),
+                None => "running".to_string(),
+            };
+            lines.push((label, duration, span.thread.as_str()));
+            if let Some(children) = children.get(&Some(span.id)) {
+                stack.extend(children.iter().rev().map(|child| (depth + 1, *child)));
+            }
+        }
+
+        let label_width = lines.iter().map(|line| line.0.len()).max().unwrap_or(0);
+        let duration_width = lines.iter().map(|line| line.1.len()).max().unwrap_or(0);
+        let mut report = String::new();
+        for (label, duration, thread) in lines {
+            writeln!(
+                report,
+                "{label:label_width$}  {duration:>duration_width$}  {thread}"
+            )
+            .unwrap();
+        }
+        let skipped = self.skipped.load(Ordering::Relaxed);
+        if skipped > 0 {
+            writeln!(report, "({skipped} more spans weren't recorded)").unwrap();
+        }
+        report
+    }
+}
+
+/// A span of work whose timing is recorded until it's dropped.
+#[must_use]
+pub struct Span<'a> {
+    /// The timings the span is recorded in, unless it isn't recorded.
+    timings: Option<&'a Timings>,
+    id: SpanId,
+    scoped: bool,
+}
+
+impl<'a> Span<'a> {
+    fn disabled() -> Self {
+        Self {
+            timings: None,
+            id: SpanId(0),
+            scoped: false,
+        }
+    }
+
+    /// Returns the id of the span, unless it isn't being recorded.
+    pub fn id(&self) -> Option<SpanId> {
+        self.timings.map(|_| self.id)
+    }
+
+    /// Starts a span that's part of this one. It doesn't nest any spans itself, so it can be
+    /// held across awaits and dropped on any thread.
+    pub fn child(&self, name: impl Into<Cow<'static, str>>) -> Span<'a> {
+        match self.timings {
+            Some(timings) => timings.record(name.into(), Some(self.id), false),
+            None => Span::disabled(),
+        }
+    }
+
+    /// Ends the span, which is the same as dropping it.
+    pub fn end(self) {}
+}
+
+impl Drop for Span<'_> {
+    fn drop(&mut self) {
+        if let Some(timings) = self.timings {
+            timings.end(self.id, self.scoped);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns each span's name, followed by the name of its parent if it has one.
+    fn tree(timings: &Timings) -> Vec<String> {
+        let spans = timings.spans();
+        spans
+            .iter()
+            .map(|span| match span.parent {
+                Some(parent) => format!("{} < {}", span.name, spans[parent.0].name),
+                None => span.name.to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_nested_spans() {
+        let timings = Timings::new();
+        timings.enable();
+        {
+            let _startup = timings.span("startup");
+            {
+                let _settings = timings.span("load settings");
+                timings.span("parse settings").end();
+            }
+            let restore = timings.start("restore workspace");
+            let window = restore.child("open window");
+            // A span that isn't scoped doesn't become the parent of the spans started after it.
+            let _themes = timings.span("load themes");
+            drop(window);
+            drop(restore);
+        }
+        timings.span("after startup").end();
+
+        assert_eq!(
+            tree(&timings),
+            [
+                "startup",
+                "load settings < startup",
+                "parse settings < load settings",
+                "restore workspace < startup",
+                "open window < restore workspace",
+                "load themes < startup",
+                "after startup",
+            ]
+        );
+        assert!(timings.spans().iter().all(|span| span.duration.is_some()));
+
+        let report = timings.report();
+        let lines = report.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 7, "{report}");
+        assert!(lines[0].starts_with("startup "), "{report}");
+        assert!(lines[2].starts_with("    parse settings "), "{report}");
+        assert!(lines[4].starts_with("    open window "), "{report}");
+        assert!(lines[6].starts_with("after startup "), "{report}");
+        let thread_name = thread::current().name().unwrap().to_string();
+        assert!(lines.iter().all(|line| line.ends_with(&thread_name)));
+    }
+
+    #[test]
+    fn test_span_ended_on_another_thread() {
+        let timings = Timings::new();
+        timings.enable();
+        let outer = timings.span("outer");
+        thread::scope(|scope| {
+            scope.spawn(|| drop(outer));
+        });
+        timings.span("next").end();
+        assert_eq!(tree(&timings), ["outer", "next"]);
+    }
+
+    #[test]
+    fn test_disabled_timings() {
+        let timings = Timings::new();
+        for _ in 0..1000 {
+            let span = timings.span("startup");
+            assert_eq!(span.id(), None);
+            span.child("child").end();
+        }
+        assert!(timings.spans().is_empty());
+        assert_eq!(timings.report(), "");
+        // A span that isn't recorded is a few words, and doesn't touch the thread's scopes.
+        assert!(std::mem::size_of::<Span>() <= 3 * std::mem::size_of::<usize>());
+        SCOPES.with(|scopes| assert!(scopes.borrow().is_empty()));
+    }
+
+    #[test]
+    fn test_span_limit() {
+        let timings = Timings::new();
+        timings.enable();
+        for _ in 0..MAX_SPANS + 2 {
+            timings.span("span").end();
+        }
+        assert_eq!(timings.spans().len(), MAX_SPANS);
+        assert!(timings
+            .report()
+            .ends_with("(2 more spans weren't recorded)\n"));
+    }
+}
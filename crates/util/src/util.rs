@@ -4,6 +4,7 @@ pub mod paths;
 pub mod serde;
 #[cfg(any(test, feature = "test-support"))]
 pub mod test;
+pub mod timings;
 
 use futures::Future;
 use lazy_static::lazy_static;
@@ -11,8 +12,8 @@ use rand::{seq::SliceRandom, Rng};
 use std::{
     borrow::Cow,
     cmp::{self, Ordering},
-    env,
-    ops::{AddAssign, Range, RangeInclusive},
+    env, fmt,
+    ops::{Range, RangeInclusive},
     panic::Location,
     pin::Pin,
     task::{Context, Poll},
@@ -214,9 +215,52 @@ pub fn truncate_lines_and_trailoff(s: &str, max_lines: usize) -> String {
     }
 }
 
-pub fn post_inc<T: From<u8> + AddAssign<T> + Copy>(value: &mut T) -> T {
+/// An integer that can be used as a counter by [`post_inc`] and its variants.
+pub trait Counter: Copy {
+    fn checked_inc(self) -> Option<Self>;
+    fn wrapping_inc(self) -> Self;
+}
+
+macro_rules! impl_counter {
+    ($($ty:ty),*) => {
+        $(
+            impl Counter for $ty {
+                fn checked_inc(self) -> Option<Self> {
+                    self.checked_add(1)
+                }
+
+                fn wrapping_inc(self) -> Self {
+                    self.wrapping_add(1)
+                }
+            }
+        )*
+    };
+}
+
+impl_counter!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+/// Increments the counter and returns its previous value.
+///
+/// Panics when the counter overflows, in release builds too, as reusing a value that was
+/// handed out before would alias ids. Use [`post_inc_checked`] or [`post_inc_wrapping`] when
+/// the counter may legitimately run out.
+pub fn post_inc<T: Counter + fmt::Debug>(value: &mut T) -> T {
+    post_inc_checked(value).unwrap_or_else(|| panic!("counter overflowed at {value:?}"))
+}
+
+/// Increments the counter and returns its previous value, or returns `None` and leaves the
+/// counter unchanged when it would overflow.
+pub fn post_inc_checked<T: Counter>(value: &mut T) -> Option<T> {
+    let prev = *value;
+    *value = prev.checked_inc()?;
+    Some(prev)
+}
+
+/// Increments the counter and returns its previous value, wrapping around on overflow. Only
+/// meant for counters whose values just need to be unique among the ones in use at a time.
+pub fn post_inc_wrapping<T: Counter>(value: &mut T) -> T {
     let prev = *value;
-    *value += T::from(1);
+    *value = prev.wrapping_inc();
     prev
 }
 
@@ -662,6 +706,48 @@ impl<'a> PartialOrd for NumericPrefixWithSuffix<'a> {
         Some(self.cmp(other))
     }
 }
+
+/// Compares strings in the order people expect for numbered names: runs of ASCII digits are
+/// compared by their numeric value, so `item2` comes before `item10`.
+pub fn compare_natural(a: &str, b: &str, case_insensitive: bool) -> Ordering {
+    let (mut a, mut b) = (a, b);
+    loop {
+        let (a_char, b_char) = match (a.chars().next(), b.chars().next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(a_char), Some(b_char)) => (a_char, b_char),
+        };
+
+        if a_char.is_ascii_digit() && b_char.is_ascii_digit() {
+            let (a_digits, a_rest) =
+                a.split_at(a.find(|c: char| !c.is_ascii_digit()).unwrap_or(a.len()));
+            let (b_digits, b_rest) =
+                b.split_at(b.find(|c: char| !c.is_ascii_digit()).unwrap_or(b.len()));
+            let a_digits = a_digits.trim_start_matches('0');
+            let b_digits = b_digits.trim_start_matches('0');
+            let ordering = a_digits
+                .len()
+                .cmp(&b_digits.len())
+                .then_with(|| a_digits.cmp(b_digits));
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+            (a, b) = (a_rest, b_rest);
+        } else {
+            let ordering = if case_insensitive {
+                a_char.to_lowercase().cmp(b_char.to_lowercase())
+            } else {
+                a_char.cmp(&b_char)
+            };
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+            (a, b) = (&a[a_char.len_utf8()..], &b[b_char.len_utf8()..]);
+        }
+    }
+}
+
 lazy_static! {
     static ref EMOJI_REGEX: regex::Regex = regex::Regex::new("(\\p{Emoji}|\u{200D})").unwrap();
 }
@@ -683,6 +769,28 @@ pub fn word_consists_of_emojis(s: &str) -> bool {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_post_inc() {
+        let mut counter = u8::MAX - 1;
+        assert_eq!(post_inc(&mut counter), u8::MAX - 1);
+        assert_eq!(counter, u8::MAX);
+
+        assert_eq!(post_inc_checked(&mut counter), None);
+        assert_eq!(counter, u8::MAX);
+
+        assert_eq!(post_inc_wrapping(&mut counter), u8::MAX);
+        assert_eq!(counter, 0);
+        assert_eq!(post_inc_checked(&mut counter), Some(0));
+        assert_eq!(counter, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "counter overflowed at 255")]
+    fn test_post_inc_overflow() {
+        let mut counter = u8::MAX;
+        post_inc(&mut counter);
+    }
+
     #[test]
     fn test_extend_sorted() {
         let mut vec = vec![];
@@ -719,6 +827,20 @@ mod tests {
         assert_eq!(truncate_and_trailoff("èèèèèè", 5), "èèèèè…");
     }
 
+    #[test]
+    fn test_compare_natural() {
+        let mut names = vec!["item10", "item2", "Item1", "item02b", "item", "10", "9"];
+        names.sort_by(|a, b| compare_natural(a, b, true));
+        assert_eq!(
+            names,
+            ["9", "10", "item", "Item1", "item2", "item02b", "item10"]
+        );
+
+        assert_eq!(compare_natural("B1", "a1", false), Ordering::Less);
+        assert_eq!(compare_natural("B1", "a1", true), Ordering::Greater);
+        assert_eq!(compare_natural("a007", "a7", false), Ordering::Equal);
+    }
+
     #[test]
     fn test_numeric_prefix_str_method() {
         let target = "1a";
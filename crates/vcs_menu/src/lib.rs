@@ -162,11 +162,7 @@ impl PickerDelegate for BranchListDelegate {
                 branches
                     .into_iter()
                     .enumerate()
-                    .map(|(ix, command)| StringMatchCandidate {
-                        id: ix,
-                        char_bag: command.name.chars().collect(),
-                        string: command.name.into(),
-                    })
+                    .map(|(ix, command)| StringMatchCandidate::new(ix, command.name.into()))
                     .collect::<Vec<StringMatchCandidate>>()
             });
             let Some(candidates) = candidates.log_err() else {
@@ -127,11 +127,7 @@ impl PickerDelegate for BaseKeymapSelectorDelegate {
         let background = cx.background_executor().clone();
         let candidates = BaseKeymap::names()
             .enumerate()
-            .map(|(id, name)| StringMatchCandidate {
-                id,
-                char_bag: name.into(),
-                string: name.into(),
-            })
+            .map(|(id, name)| StringMatchCandidate::new(id, name.into()))
             .collect::<Vec<_>>();
 
         cx.spawn(|this, mut cx| async move {
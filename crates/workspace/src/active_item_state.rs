@@ -0,0 +1,101 @@
+use crate::{item::ItemEvent, ItemHandle, SaveIntent, StatusItemView, Workspace};
+use gpui::{IntoElement, ParentElement, Render, Subscription, ViewContext, WeakView};
+use ui::{prelude::*, Tooltip};
+use util::ResultExt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ItemState {
+    Dirty,
+    Conflict,
+}
+
+/// Tells whether the active item has unsaved changes, or was changed on disk while it had
+/// some. Clicking it saves the item.
+pub struct ActiveItemState {
+    state: Option<ItemState>,
+    workspace: WeakView<Workspace>,
+    _observe_active_item: Option<Subscription>,
+}
+
+impl ActiveItemState {
+    pub fn new(workspace: &Workspace) -> Self {
+        Self {
+            state: None,
+            workspace: workspace.weak_handle(),
+            _observe_active_item: None,
+        }
+    }
+
+    fn update_state(&mut self, item: &dyn ItemHandle, cx: &mut ViewContext<Self>) {
+        let state = if item.has_conflict(cx) {
+            Some(ItemState::Conflict)
+        } else if item.is_dirty(cx) {
+            Some(ItemState::Dirty)
+        } else {
+            None
+        };
+
+        if state != self.state {
+            self.state = state;
+            cx.notify();
+        }
+    }
+}
+
+impl Render for ActiveItemState {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        div().when_some(self.state, |el, state| {
+            let (label, color, tooltip) = match state {
+                ItemState::Dirty => ("Modified", Color::Modified, "Save"),
+                ItemState::Conflict => (
+                    "Conflict",
+                    Color::Conflict,
+                    "The file changed on disk. Save to overwrite it.",
+                ),
+            };
+            el.child(
+                Button::new("active-item-state", label)
+                    .label_size(LabelSize::Small)
+                    .color(color)
+                    .on_click(cx.listener(|this, _, cx| {
+                        if let Some(workspace) = this.workspace.upgrade() {
+                            workspace.update(cx, |workspace, cx| {
+                                workspace
+                                    .save_active_item(SaveIntent::Save, cx)
+                                    .detach_and_log_err(cx);
+                            });
+                        }
+                    }))
+                    .tooltip(move |cx| Tooltip::text(tooltip, cx)),
+            )
+        })
+    }
+}
+
+impl StatusItemView for ActiveItemState {
+    fn set_active_pane_item(
+        &mut self,
+        active_pane_item: Option<&dyn ItemHandle>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        if let Some(item) = active_pane_item {
+            let this = cx.view().downgrade();
+            let observed_item = item.boxed_clone();
+            self._observe_active_item = Some(item.subscribe_to_item_events(
+                cx,
+                Box::new(move |event, cx| {
+                    if matches!(event, ItemEvent::UpdateTab) {
+                        this.update(cx, |this, cx| this.update_state(&*observed_item, cx))
+                            .log_err();
+                    }
+                }),
+            ));
+            self.update_state(item, cx);
+        } else {
+            self.state = None;
+            self._observe_active_item = None;
+        }
+
+        cx.notify();
+    }
+}
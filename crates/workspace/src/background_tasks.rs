@@ -0,0 +1,432 @@
+use std::{fmt, time::Duration};
+
+use gpui::{
+    percentage, AnchorCorner, Animation, AnimationExt, AppContext, EventEmitter, Model,
+    ModelContext, SharedString, Subscription, Transformation, View, ViewContext, WindowContext,
+};
+use ui::{popover_menu, prelude::*, ButtonLike, ContextMenu, Tooltip};
+
+use crate::{ItemHandle, StatusItemView, Workspace};
+
+/// Identifies a task registered with [`BackgroundTasks`], for as long as it runs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BackgroundTaskId(usize);
+
+/// What a task tells the workspace about itself when it starts running in the background.
+pub struct NewBackgroundTask {
+    title: SharedString,
+    cancel: Option<Box<dyn FnOnce(&mut AppContext)>>,
+    notify_on_finish: bool,
+}
+
+impl NewBackgroundTask {
+    pub fn new(title: impl Into<SharedString>) -> Self {
+        Self {
+            title: title.into(),
+            cancel: None,
+            notify_on_finish: false,
+        }
+    }
+
+    /// Lets the user cancel the task, which calls the given function.
+    pub fn on_cancel(mut self, cancel: impl FnOnce(&mut AppContext) + 'static) -> Self {
+        self.cancel = Some(Box::new(cancel));
+        self
+    }
+
+    /// Shows a notification once the task finishes or fails, though not when it's cancelled.
+    pub fn notify_on_finish(mut self) -> Self {
+        self.notify_on_finish = true;
+        self
+    }
+}
+
+/// A task that runs in the background, as the workspace shows it.
+pub struct BackgroundTask {
+    pub id: BackgroundTaskId,
+    pub title: SharedString,
+    /// How many units of work out of how many are done, for tasks that know.
+    pub progress: Option<(usize, usize)>,
+    cancel: Option<Box<dyn FnOnce(&mut AppContext)>>,
+    notify_on_finish: bool,
+}
+
+impl BackgroundTask {
+    pub fn can_cancel(&self) -> bool {
+        self.cancel.is_some()
+    }
+
+    fn label(&self) -> String {
+        match self.progress {
+            Some((current, total)) => format!("{} ({current}/{total})", self.title),
+            None => self.title.to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BackgroundTaskEvent {
+    /// A task finished, failing with the given error if there is one. Cancelled tasks don't
+    /// finish.
+    Finished {
+        title: SharedString,
+        error: Option<SharedString>,
+        notify: bool,
+    },
+}
+
+/// The tasks of a workspace that take a while without blocking anything, such as searches and
+/// scans, in the order they were registered in.
+///
+/// The methods that take a [`BackgroundTaskId`] do nothing once the task has ended, so a task
+/// that got cancelled can still report its progress or finish without checking first.
+#[derive(Default)]
+pub struct BackgroundTasks {
+    tasks: Vec<BackgroundTask>,
+    next_id: usize,
+}
+
+impl EventEmitter<BackgroundTaskEvent> for BackgroundTasks {}
+
+impl BackgroundTasks {
+    pub fn register(
+        &mut self,
+        task: NewBackgroundTask,
+        cx: &mut ModelContext<Self>,
+    ) -> BackgroundTaskId {
+        let id = BackgroundTaskId(self.next_id);
+        self.next_id += 1;
+        self.tasks.push(BackgroundTask {
+            id,
+            title: task.title,
+            progress: None,
+            cancel: task.cancel,
+            notify_on_finish: task.notify_on_finish,
+        });
+        cx.notify();
+        id
+    }
+
+    pub fn tasks(&self) -> &[BackgroundTask] {
+        &self.tasks
+    }
+
+    pub fn get(&self, id: BackgroundTaskId) -> Option<&BackgroundTask> {
+        self.tasks.iter().find(|task| task.id == id)
+    }
+
+    pub fn set_title(
+        &mut self,
+        id: BackgroundTaskId,
+        title: impl Into<SharedString>,
+        cx: &mut ModelContext<Self>,
+    ) {
+        if let Some(task) = self.tasks.iter_mut().find(|task| task.id == id) {
+            task.title = title.into();
+            cx.notify();
+        }
+    }
+
+    pub fn set_progress(
+        &mut self,
+        id: BackgroundTaskId,
+        current: usize,
+        total: usize,
+        cx: &mut ModelContext<Self>,
+    ) {
+        if let Some(task) = self.tasks.iter_mut().find(|task| task.id == id) {
+            task.progress = Some((current.min(total), total));
+            cx.notify();
+        }
+    }
+
+    pub fn finish(&mut self, id: BackgroundTaskId, cx: &mut ModelContext<Self>) {
+        self.end(id, None, cx);
+    }
+
+    pub fn fail(
+        &mut self,
+        id: BackgroundTaskId,
+        error: impl fmt::Display,
+        cx: &mut ModelContext<Self>,
+    ) {
+        self.end(id, Some(error.to_string().into()), cx);
+    }
+
+    /// Cancels a task, if it can be cancelled. Its cancel function gets called after the
+    /// registry is updated, so that it can end other tasks.
+    pub fn cancel(&mut self, id: BackgroundTaskId, cx: &mut ModelContext<Self>) {
+        let Some(ix) = self
+            .tasks
+            .iter()
+            .position(|task| task.id == id && task.can_cancel())
+        else {
+            return;
+        };
+        let task = self.tasks.remove(ix);
+        if let Some(cancel) = task.cancel {
+            cx.defer(cancel);
+        }
+        cx.notify();
+    }
+
+    /// Removes a task without cancelling it or emitting an event, for when whatever ran the task
+    /// went away.
+    pub fn remove(&mut self, id: BackgroundTaskId, cx: &mut ModelContext<Self>) {
+        let len = self.tasks.len();
+        self.tasks.retain(|task| task.id != id);
+        if self.tasks.len() != len {
+            cx.notify();
+        }
+    }
+
+    fn end(
+        &mut self,
+        id: BackgroundTaskId,
+        error: Option<SharedString>,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let Some(ix) = self.tasks.iter().position(|task| task.id == id) else {
+            return;
+        };
+        let task = self.tasks.remove(ix);
+        cx.emit(BackgroundTaskEvent::Finished {
+            title: task.title,
+            error,
+            notify: task.notify_on_finish,
+        });
+        cx.notify();
+    }
+}
+
+/// Shows in the status bar that tasks are running in the background, and lists them in a popover
+/// where they can be cancelled.
+pub struct BackgroundTaskIndicator {
+    background_tasks: Model<BackgroundTasks>,
+    _observe_background_tasks: Subscription,
+}
+
+impl BackgroundTaskIndicator {
+    pub fn new(workspace: &Workspace, cx: &mut ViewContext<Self>) -> Self {
+        let background_tasks = workspace.background_tasks().clone();
+        Self {
+            _observe_background_tasks: cx.observe(&background_tasks, |_, _, cx| cx.notify()),
+            background_tasks,
+        }
+    }
+
+    fn build_menu(
+        background_tasks: Model<BackgroundTasks>,
+        cx: &mut WindowContext,
+    ) -> View<ContextMenu> {
+        let tasks = background_tasks
+            .read(cx)
+            .tasks()
+            .iter()
+            .map(|task| (task.id, SharedString::from(task.label()), task.can_cancel()))
+            .collect::<Vec<_>>();
+        ContextMenu::build(cx, move |mut menu, _| {
+            menu = menu.header("Background Tasks");
+            for (id, label, can_cancel) in tasks {
+                let background_tasks = background_tasks.clone();
+                menu = menu.custom_entry(
+                    move |_| {
+                        h_flex()
+                            .w_full()
+                            .gap_4()
+                            .justify_between()
+                            .child(Label::new(label.clone()))
+                            .when(can_cancel, |row| {
+                                row.child(Label::new("Cancel").color(Color::Muted))
+                            })
+                            .into_any_element()
+                    },
+                    move |cx| {
+                        background_tasks.update(cx, |tasks, cx| tasks.cancel(id, cx));
+                    },
+                );
+            }
+            menu
+        })
+    }
+}
+
+impl Render for BackgroundTaskIndicator {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let tasks = self.background_tasks.read(cx).tasks();
+        let label = match tasks {
+            [] => return div(),
+            [task] => task.label(),
+            tasks => format!("{} tasks", tasks.len()),
+        };
+
+        let background_tasks = self.background_tasks.clone();
+        div().child(
+            popover_menu("background-tasks")
+                .menu(move |cx| Some(Self::build_menu(background_tasks.clone(), cx)))
+                .anchor(AnchorCorner::BottomLeft)
+                .trigger(
+                    ButtonLike::new("background-tasks-button")
+                        .child(
+                            h_flex()
+                                .gap_1()
+                                .child(
+                                    Icon::new(IconName::ArrowCircle)
+                                        .size(IconSize::Small)
+                                        .with_animation(
+                                            "background-tasks-spinner",
+                                            Animation::new(Duration::from_secs(2)).repeat(),
+                                            |icon, delta| {
+                                                icon.transform(Transformation::rotate(percentage(
+                                                    delta,
+                                                )))
+                                            },
+                                        ),
+                                )
+                                .child(Label::new(label).size(LabelSize::Small)),
+                        )
+                        .tooltip(|cx| Tooltip::text("Background Tasks", cx)),
+                ),
+        )
+    }
+}
+
+impl StatusItemView for BackgroundTaskIndicator {
+    fn set_active_pane_item(&mut self, _: Option<&dyn ItemHandle>, _: &mut ViewContext<Self>) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{channel::oneshot, future};
+    use gpui::TestAppContext;
+    use std::{
+        cell::{Cell, RefCell},
+        rc::Rc,
+    };
+
+    fn titles(tasks: &Model<BackgroundTasks>, cx: &TestAppContext) -> Vec<String> {
+        tasks.read_with(cx, |tasks, _| {
+            tasks.tasks().iter().map(|task| task.label()).collect()
+        })
+    }
+
+    #[gpui::test]
+    async fn test_concurrent_background_tasks(cx: &mut TestAppContext) {
+        let tasks = cx.new_model(|_| BackgroundTasks::default());
+        let events = Rc::new(RefCell::new(Vec::new()));
+        cx.update(|cx| {
+            let events = events.clone();
+            cx.subscribe(&tasks, move |_, event, _| {
+                events.borrow_mut().push(event.clone())
+            })
+            .detach();
+        });
+
+        // Tasks that register from several futures at once each get their own entry, and update
+        // it independently of the others.
+        let runs = (0..3)
+            .map(|ix| {
+                let tasks = tasks.downgrade();
+                cx.spawn(|mut cx| async move {
+                    let id = tasks.update(&mut cx, |tasks, cx| {
+                        let task = NewBackgroundTask::new(format!("Task {ix}"));
+                        let task = if ix == 2 {
+                            task.notify_on_finish()
+                        } else {
+                            task
+                        };
+                        tasks.register(task, cx)
+                    })?;
+                    for step in 1..=ix {
+                        cx.background_executor()
+                            .timer(Duration::from_millis(10))
+                            .await;
+                        tasks.update(&mut cx, |tasks, cx| tasks.set_progress(id, step, 2, cx))?;
+                    }
+                    anyhow::Ok(id)
+                })
+            })
+            .collect::<Vec<_>>();
+        cx.run_until_parked();
+        assert_eq!(titles(&tasks, cx), ["Task 0", "Task 1", "Task 2"]);
+
+        cx.executor().advance_clock(Duration::from_millis(10));
+        cx.run_until_parked();
+        assert_eq!(
+            titles(&tasks, cx),
+            ["Task 0", "Task 1 (1/2)", "Task 2 (1/2)"]
+        );
+
+        cx.executor().advance_clock(Duration::from_millis(10));
+        let ids = future::try_join_all(runs).await.unwrap();
+        assert_eq!(
+            titles(&tasks, cx),
+            ["Task 0", "Task 1 (1/2)", "Task 2 (2/2)"]
+        );
+
+        tasks.update(cx, |tasks, cx| {
+            tasks.finish(ids[2], cx);
+            tasks.fail(ids[0], "oops", cx);
+            // Tasks that ended already are ignored.
+            tasks.finish(ids[2], cx);
+            tasks.set_progress(ids[0], 1, 1, cx);
+        });
+        assert_eq!(titles(&tasks, cx), ["Task 1 (1/2)"]);
+        assert_eq!(
+            *events.borrow(),
+            [
+                BackgroundTaskEvent::Finished {
+                    title: "Task 2".into(),
+                    error: None,
+                    notify: true,
+                },
+                BackgroundTaskEvent::Finished {
+                    title: "Task 0".into(),
+                    error: Some("oops".into()),
+                    notify: false,
+                },
+            ]
+        );
+    }
+
+    #[gpui::test]
+    async fn test_cancel_background_task(cx: &mut TestAppContext) {
+        let tasks = cx.new_model(|_| BackgroundTasks::default());
+        let (cancel_tx, cancel_rx) = oneshot::channel::<()>();
+        let finished = Rc::new(Cell::new(false));
+
+        // Cancelling a task calls its cancel function, which stops the work the task does.
+        let id = tasks.update(cx, |tasks, cx| {
+            tasks.register(NewBackgroundTask::new("Cancellable"), cx);
+            let task = NewBackgroundTask::new("Cancellable").on_cancel(move |_| {
+                cancel_tx.send(()).ok();
+            });
+            tasks.register(task, cx)
+        });
+        let work = cx.spawn({
+            let tasks = tasks.downgrade();
+            let finished = finished.clone();
+            |mut cx| async move {
+                let timer = cx.background_executor().timer(Duration::from_secs(60));
+                if let future::Either::Left(_) = future::select(Box::pin(timer), cancel_rx).await {
+                    finished.set(true);
+                }
+                tasks.update(&mut cx, |tasks, cx| tasks.finish(id, cx)).ok();
+            }
+        });
+
+        let uncancellable = tasks.read_with(cx, |tasks, _| tasks.tasks()[0].id);
+        tasks.update(cx, |tasks, cx| {
+            tasks.cancel(uncancellable, cx);
+            tasks.cancel(id, cx);
+        });
+        work.await;
+        assert!(!finished.get());
+        assert_eq!(titles(&tasks, cx), ["Cancellable"]);
+        tasks.read_with(cx, |tasks, _| {
+            assert_eq!(tasks.tasks()[0].id, uncancellable);
+            assert!(tasks.get(id).is_none());
+        });
+    }
+}
@@ -136,6 +136,13 @@ pub struct TabContentParams {
     pub preview: bool,
 }
 
+/// A position in the text of an item, to move its cursor to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PointOrAnchor {
+    Point(language::Point),
+    Anchor(language::Anchor),
+}
+
 pub trait Item: FocusableView + EventEmitter<Self::Event> {
     type Event;
     fn tab_content(&self, _params: TabContentParams, _cx: &WindowContext) -> AnyElement {
@@ -145,15 +152,26 @@ pub trait Item: FocusableView + EventEmitter<Self::Event> {
 
     fn deactivated(&mut self, _: &mut ViewContext<Self>) {}
     fn workspace_deactivated(&mut self, _: &mut ViewContext<Self>) {}
+    /// Called when the item is closed and the user chose to throw away its unsaved changes.
+    fn discarded(&mut self, _: &mut ViewContext<Self>) {}
     fn navigate(&mut self, _: Box<dyn Any>, _: &mut ViewContext<Self>) -> bool {
         false
     }
+    /// Moves the cursor to the given position, clipped to the item's text, recording where it
+    /// was in the navigation history. Returns whether the item has a cursor at that position.
+    fn move_cursor_to(&mut self, _: PointOrAnchor, _: &mut ViewContext<Self>) -> bool {
+        false
+    }
     fn tab_tooltip_text(&self, _: &AppContext) -> Option<SharedString> {
         None
     }
     fn tab_description(&self, _: usize, _: &AppContext) -> Option<SharedString> {
         None
     }
+    /// The text of the tab's title, without any decorations, used to search for the item.
+    fn tab_content_text(&self, _: &AppContext) -> Option<SharedString> {
+        None
+    }
 
     fn telemetry_event_text(&self) -> Option<&'static str> {
         None
@@ -272,6 +290,7 @@ pub trait ItemHandle: 'static + Send {
     fn focus_handle(&self, cx: &WindowContext) -> FocusHandle;
     fn tab_tooltip_text(&self, cx: &AppContext) -> Option<SharedString>;
     fn tab_description(&self, detail: usize, cx: &AppContext) -> Option<SharedString>;
+    fn tab_content_text(&self, cx: &AppContext) -> Option<SharedString>;
     fn tab_content(&self, params: TabContentParams, cx: &WindowContext) -> AnyElement;
     fn telemetry_event_text(&self, cx: &WindowContext) -> Option<&'static str>;
     fn dragged_tab_content(&self, params: TabContentParams, cx: &WindowContext) -> AnyElement;
@@ -298,7 +317,9 @@ pub trait ItemHandle: 'static + Send {
     );
     fn deactivated(&self, cx: &mut WindowContext);
     fn workspace_deactivated(&self, cx: &mut WindowContext);
+    fn discarded(&self, cx: &mut WindowContext);
     fn navigate(&self, data: Box<dyn Any>, cx: &mut WindowContext) -> bool;
+    fn move_cursor_to(&self, position: PointOrAnchor, cx: &mut WindowContext) -> bool;
     fn item_id(&self) -> EntityId;
     fn to_any(&self) -> AnyView;
     fn is_dirty(&self, cx: &AppContext) -> bool;
@@ -376,6 +397,10 @@ impl<T: Item> ItemHandle for View<T> {
         self.read(cx).tab_description(detail, cx)
     }
 
+    fn tab_content_text(&self, cx: &AppContext) -> Option<SharedString> {
+        self.read(cx).tab_content_text(cx)
+    }
+
     fn tab_content(&self, params: TabContentParams, cx: &WindowContext) -> AnyElement {
         self.read(cx).tab_content(params, cx)
     }
@@ -572,7 +597,7 @@ impl<T: Item> ItemHandle for View<T> {
                                 let delay = Duration::from_millis(milliseconds);
                                 let item = item.clone();
                                 pending_autosave.fire_new(delay, cx, move |workspace, cx| {
-                                    Pane::autosave_item(&item, workspace.project().clone(), cx)
+                                    workspace.autosave_item(&item, cx)
                                 });
                             }
                             pane.update(cx, |pane, cx| pane.handle_item_edit(item.item_id(), cx));
@@ -586,8 +611,7 @@ impl<T: Item> ItemHandle for View<T> {
             cx.on_blur(&self.focus_handle(cx), move |workspace, cx| {
                 if WorkspaceSettings::get_global(cx).autosave == AutosaveSetting::OnFocusChange {
                     if let Some(item) = weak_item.upgrade() {
-                        Pane::autosave_item(&item, workspace.project.clone(), cx)
-                            .detach_and_log_err(cx);
+                        workspace.autosave_item(&item, cx).detach_and_log_err(cx);
                     }
                 }
             })
@@ -615,10 +639,18 @@ impl<T: Item> ItemHandle for View<T> {
         self.update(cx, |this, cx| this.workspace_deactivated(cx));
     }
 
+    fn discarded(&self, cx: &mut WindowContext) {
+        self.update(cx, |this, cx| this.discarded(cx));
+    }
+
     fn navigate(&self, data: Box<dyn Any>, cx: &mut WindowContext) -> bool {
         self.update(cx, |this, cx| this.navigate(data, cx))
     }
 
+    fn move_cursor_to(&self, position: PointOrAnchor, cx: &mut WindowContext) -> bool {
+        self.update(cx, |this, cx| this.move_cursor_to(position, cx))
+    }
+
     fn item_id(&self) -> EntityId {
         self.entity_id()
     }
@@ -891,6 +923,7 @@ pub mod test {
         pub is_dirty: bool,
         pub is_singleton: bool,
         pub has_conflict: bool,
+        pub save_error: Option<&'static str>,
         pub project_items: Vec<Model<TestProjectItem>>,
         pub nav_history: Option<ItemNavHistory>,
         pub tab_descriptions: Option<Vec<&'static str>>,
@@ -951,6 +984,7 @@ pub mod test {
                 reload_count: 0,
                 is_dirty: false,
                 has_conflict: false,
+                save_error: None,
                 project_items: Vec::new(),
                 is_singleton: true,
                 nav_history: None,
@@ -987,6 +1021,11 @@ pub mod test {
             self
         }
 
+        pub fn with_save_error(mut self, save_error: &'static str) -> Self {
+            self.save_error = Some(save_error);
+            self
+        }
+
         pub fn with_project_items(mut self, items: &[Model<TestProjectItem>]) -> Self {
             self.project_items.clear();
             self.project_items.extend(items.iter().cloned());
@@ -1095,6 +1134,7 @@ pub mod test {
                 is_dirty: self.is_dirty,
                 is_singleton: self.is_singleton,
                 has_conflict: self.has_conflict,
+                save_error: self.save_error,
                 project_items: self.project_items.clone(),
                 nav_history: None,
                 tab_descriptions: None,
@@ -1126,6 +1166,9 @@ pub mod test {
             _: Model<Project>,
             _: &mut ViewContext<Self>,
         ) -> Task<anyhow::Result<()>> {
+            if let Some(save_error) = self.save_error {
+                return Task::ready(Err(anyhow::anyhow!(save_error)));
+            }
             self.save_count += 1;
             self.is_dirty = false;
             Task::ready(Ok(()))
@@ -7,10 +7,17 @@ use gpui::{
 };
 use language::DiagnosticSeverity;
 
-use std::{any::TypeId, ops::DerefMut};
+use std::{any::TypeId, ops::DerefMut, time::Duration};
 use ui::{prelude::*, Tooltip};
 use util::ResultExt;
 
+/// How many notifications a workspace shows at most. Showing another one dismisses the
+/// oldest.
+pub const MAX_NOTIFICATIONS: usize = 8;
+
+/// How long a toast stays up when it's shown with [`Toast::autohide`].
+pub const TOAST_AUTOHIDE_DURATION: Duration = Duration::from_secs(5);
+
 pub fn init(cx: &mut AppContext) {
     cx.set_global(NotificationTracker::new());
 }
@@ -148,6 +155,9 @@ impl Workspace {
         })
         .detach();
         self.notifications.push((id, Box::new(notification)));
+        if self.notifications.len() > MAX_NOTIFICATIONS {
+            self.notifications.remove(0);
+        }
         cx.notify();
     }
 
@@ -172,19 +182,47 @@ impl Workspace {
         self.dismiss_notification_internal(id, cx)
     }
 
+    /// Shows the given toast, replacing any notification with the same id.
     pub fn show_toast(&mut self, toast: Toast, cx: &mut ViewContext<Self>) {
         self.dismiss_notification(&toast.id, cx);
-        self.show_notification(toast.id, cx, |cx| {
-            cx.new_view(|_cx| match toast.on_click.as_ref() {
-                Some((click_msg, on_click)) => {
-                    let on_click = on_click.clone();
+        let mut notification_entity_id = None;
+        self.show_notification(toast.id.clone(), cx, |cx| {
+            let notification = cx.new_view(|_cx| {
+                let notification =
                     simple_message_notification::MessageNotification::new(toast.msg.clone())
-                        .with_click_message(click_msg.clone())
-                        .on_click(move |cx| on_click(cx))
+                        .with_level(toast.level);
+                match toast.on_click.as_ref() {
+                    Some((click_msg, on_click)) => {
+                        let on_click = on_click.clone();
+                        notification
+                            .with_click_message(click_msg.clone())
+                            .on_click(move |cx| on_click(cx))
+                    }
+                    None => notification,
                 }
-                None => simple_message_notification::MessageNotification::new(toast.msg.clone()),
+            });
+            notification_entity_id = Some(notification.entity_id());
+            notification
+        });
+
+        if toast.autohide {
+            let toast_id = toast.id;
+            cx.spawn(|workspace, mut cx| async move {
+                cx.background_executor()
+                    .timer(TOAST_AUTOHIDE_DURATION)
+                    .await;
+                workspace.update(&mut cx, |workspace, cx| {
+                    // Leave alone a newer toast that replaced this one.
+                    let is_still_shown = workspace.notifications.iter().any(|(id, handle)| {
+                        *id == toast_id && Some(handle.id()) == notification_entity_id
+                    });
+                    if is_still_shown {
+                        workspace.dismiss_toast(&toast_id, cx);
+                    }
+                })
             })
-        })
+            .detach_and_log_err(cx);
+        }
     }
 
     pub fn dismiss_toast(&mut self, id: &NotificationId, cx: &mut ViewContext<Self>) {
@@ -346,8 +384,8 @@ impl EventEmitter<DismissEvent> for LanguageServerPrompt {}
 
 pub mod simple_message_notification {
     use gpui::{
-        div, DismissEvent, EventEmitter, InteractiveElement, ParentElement, Render, SharedString,
-        StatefulInteractiveElement, Styled, ViewContext,
+        div, DismissEvent, EventEmitter, InteractiveElement, ParentElement, PromptLevel, Render,
+        SharedString, StatefulInteractiveElement, Styled, ViewContext,
     };
     use std::sync::Arc;
     use ui::prelude::*;
@@ -355,6 +393,7 @@ pub mod simple_message_notification {
 
     pub struct MessageNotification {
         message: SharedString,
+        level: PromptLevel,
         on_click: Option<Arc<dyn Fn(&mut ViewContext<Self>)>>,
         click_message: Option<SharedString>,
         secondary_click_message: Option<SharedString>,
@@ -370,6 +409,7 @@ pub mod simple_message_notification {
         {
             Self {
                 message: message.into(),
+                level: PromptLevel::Info,
                 on_click: None,
                 click_message: None,
                 secondary_on_click: None,
@@ -377,6 +417,11 @@ pub mod simple_message_notification {
             }
         }
 
+        pub fn with_level(mut self, level: PromptLevel) -> Self {
+            self.level = level;
+            self
+        }
+
         pub fn with_click_message<S>(mut self, message: S) -> Self
         where
             S: Into<SharedString>,
@@ -422,7 +467,22 @@ pub mod simple_message_notification {
                 .child(
                     h_flex()
                         .justify_between()
-                        .child(div().max_w_80().child(Label::new(self.message.clone())))
+                        .child(
+                            h_flex()
+                                .gap_2()
+                                .children(match self.level {
+                                    PromptLevel::Info => None,
+                                    PromptLevel::Warning => Some(
+                                        Icon::new(IconName::ExclamationTriangle)
+                                            .color(Color::Warning),
+                                    ),
+                                    PromptLevel::Critical => Some(
+                                        Icon::new(IconName::ExclamationTriangle)
+                                            .color(Color::Error),
+                                    ),
+                                })
+                                .child(div().max_w_80().child(Label::new(self.message.clone()))),
+                        )
                         .child(
                             div()
                                 .id("cancel")
@@ -5,12 +5,11 @@ use crate::{
     },
     toolbar::Toolbar,
     workspace_settings::{AutosaveSetting, TabBarSettings, WorkspaceSettings},
-    CloseWindow, NewCenterTerminal, NewFile, NewSearch, OpenInTerminal, OpenTerminal, OpenVisible,
-    SplitDirection, ToggleZoom, Workspace,
+    CloseActivePane, CloseWindow, NewCenterTerminal, NewFile, NewSearch, OpenInTerminal,
+    OpenTerminal, OpenVisible, SplitDirection, ToggleZoom, Workspace,
 };
 use anyhow::Result;
 use collections::{BTreeSet, HashMap, HashSet, VecDeque};
-use futures::{stream::FuturesUnordered, StreamExt};
 use gpui::{
     actions, anchored, deferred, impl_actions, prelude::*, Action, AnchorCorner, AnyElement,
     AppContext, AsyncWindowContext, ClickEvent, DismissEvent, Div, DragMoveEvent, EntityId,
@@ -145,11 +144,14 @@ actions!(
         CloseItemsToTheRight,
         GoBack,
         GoForward,
+        MoveItemLeft,
+        MoveItemRight,
         ReopenClosedItem,
         SplitLeft,
         SplitUp,
         SplitRight,
         SplitDown,
+        TogglePinTab,
         TogglePreviewTab,
     ]
 );
@@ -221,6 +223,8 @@ pub struct Pane {
     was_focused: bool,
     active_item_index: usize,
     preview_item_id: Option<EntityId>,
+    /// The pinned items are always the first `pinned_tab_count` items.
+    pinned_tab_count: usize,
     last_focus_handle_by_item: HashMap<EntityId, WeakFocusHandle>,
     nav_history: NavHistory,
     toolbar: View<Toolbar>,
@@ -229,7 +233,7 @@ pub struct Pane {
     //     tab_context_menu: View<ContextMenu>,
     pub(crate) workspace: WeakView<Workspace>,
     project: Model<Project>,
-    drag_split_direction: Option<SplitDirection>,
+    pub(crate) drag_split_direction: Option<SplitDirection>,
     can_drop_predicate: Option<Arc<dyn Fn(&dyn Any, &mut WindowContext) -> bool>>,
     custom_drop_handle:
         Option<Arc<dyn Fn(&mut Pane, &dyn Any, &mut ViewContext<Pane>) -> ControlFlow<(), ()>>>,
@@ -332,6 +336,7 @@ impl Pane {
             zoomed: false,
             active_item_index: 0,
             preview_item_id: None,
+            pinned_tab_count: 0,
             last_focus_handle_by_item: Default::default(),
             nav_history: NavHistory(Arc::new(Mutex::new(NavHistoryState {
                 mode: NavigationMode::Normal,
@@ -389,6 +394,8 @@ impl Pane {
                                         .action("Split Left", SplitLeft.boxed_clone())
                                         .action("Split Up", SplitUp.boxed_clone())
                                         .action("Split Down", SplitDown.boxed_clone())
+                                        .separator()
+                                        .action("Close Pane", CloseActivePane.boxed_clone())
                                 });
                                 cx.subscribe(&menu, |pane, _, _: &DismissEvent, cx| {
                                     pane.focus(cx);
@@ -662,6 +669,44 @@ impl Pane {
         }
     }
 
+    pub fn is_item_pinned(&self, item_id: EntityId) -> bool {
+        self.items[..self.pinned_tab_count]
+            .iter()
+            .any(|item| item.item_id() == item_id)
+    }
+
+    /// Pins the first `count` items, and unpins the other ones.
+    pub fn set_pinned_count(&mut self, count: usize, cx: &mut ViewContext<Self>) {
+        self.pinned_tab_count = count.min(self.items.len());
+        cx.notify();
+    }
+
+    /// Pins the active item by moving it right after the other pinned items, or unpins it by
+    /// moving it right before the unpinned ones. Pinned items are never preview items.
+    pub fn toggle_pin_tab(&mut self, _: &TogglePinTab, cx: &mut ViewContext<Self>) {
+        if self.items.is_empty() {
+            return;
+        }
+
+        let ix = self.active_item_index;
+        let destination_index = if ix < self.pinned_tab_count {
+            self.pinned_tab_count -= 1;
+            self.pinned_tab_count
+        } else {
+            if self.is_active_preview_item(self.items[ix].item_id()) {
+                self.set_preview_item_id(None, cx);
+            }
+            self.pinned_tab_count += 1;
+            self.pinned_tab_count - 1
+        };
+
+        let item = self.items.remove(ix);
+        self.items.insert(destination_index, item);
+        self.active_item_index = destination_index;
+        self.tab_bar_scroll_handle.scroll_to_item(destination_index);
+        cx.notify();
+    }
+
     pub fn handle_item_edit(&mut self, item_id: EntityId, cx: &AppContext) {
         if let Some(preview_item_id) = self.preview_item_id {
             if preview_item_id == item_id {
@@ -772,6 +817,7 @@ impl Pane {
                 },
                 self.items.len(),
             )
+            .max(self.pinned_tab_count)
         };
 
         // Does the item already exist?
@@ -801,10 +847,12 @@ impl Pane {
 
             if existing_item_index != insertion_index {
                 let existing_item_is_active = existing_item_index == self.active_item_index;
+                let existing_item_is_pinned = existing_item_index < self.pinned_tab_count;
 
                 // If the caller didn't specify a destination and the added item is already
-                // the active one, don't move it
-                if existing_item_is_active && destination_index.is_none() {
+                // the active one, don't move it. Pinned items stay where they are.
+                if existing_item_is_pinned || existing_item_is_active && destination_index.is_none()
+                {
                     insertion_index = existing_item_index;
                 } else {
                     self.items.remove(existing_item_index);
@@ -966,6 +1014,33 @@ impl Pane {
         self.activate_item(index, activate_pane, activate_pane, cx);
     }
 
+    /// Moves the active item one tab to the left, it stays active.
+    pub fn move_item_left(&mut self, cx: &mut ViewContext<Self>) {
+        if let Some(index) = self.active_item_index.checked_sub(1) {
+            self.move_active_item_to(index, cx);
+        }
+    }
+
+    /// Moves the active item one tab to the right, it stays active.
+    pub fn move_item_right(&mut self, cx: &mut ViewContext<Self>) {
+        self.move_active_item_to(self.active_item_index + 1, cx);
+    }
+
+    fn move_active_item_to(&mut self, index: usize, cx: &mut ViewContext<Self>) {
+        if index >= self.items.len() || index == self.active_item_index {
+            return;
+        }
+        // Items don't move between the pinned and the unpinned ones.
+        if (index < self.pinned_tab_count) != (self.active_item_index < self.pinned_tab_count) {
+            return;
+        }
+
+        self.items.swap(self.active_item_index, index);
+        self.active_item_index = index;
+        self.tab_bar_scroll_handle.scroll_to_item(index);
+        cx.notify();
+    }
+
     pub fn close_active_item(
         &mut self,
         action: &CloseActiveItem,
@@ -1009,10 +1084,11 @@ impl Pane {
         }
 
         let active_item_id = self.items[self.active_item_index].item_id();
+        let pinned_item_ids = self.pinned_item_ids();
         Some(self.close_items(
             cx,
             action.save_intent.unwrap_or(SaveIntent::Close),
-            move |item_id| item_id != active_item_id,
+            move |item_id| item_id != active_item_id && !pinned_item_ids.contains(&item_id),
         ))
     }
 
@@ -1023,6 +1099,7 @@ impl Pane {
     ) -> Option<Task<Result<()>>> {
         let item_ids: Vec<_> = self
             .items()
+            .skip(self.pinned_tab_count)
             .filter(|item| !item.is_dirty(cx))
             .map(|item| item.item_id())
             .collect();
@@ -1050,6 +1127,7 @@ impl Pane {
     ) -> Task<Result<()>> {
         let item_ids: Vec<_> = self
             .items()
+            .skip(self.pinned_tab_count)
             .take_while(|item| item.item_id() != item_id)
             .map(|item| item.item_id())
             .collect();
@@ -1075,11 +1153,13 @@ impl Pane {
         item_id: EntityId,
         cx: &mut ViewContext<Self>,
     ) -> Task<Result<()>> {
+        let pinned_item_ids = self.pinned_item_ids();
         let item_ids: Vec<_> = self
             .items()
             .rev()
             .take_while(|item| item.item_id() != item_id)
             .map(|item| item.item_id())
+            .filter(|item_id| !pinned_item_ids.contains(item_id))
             .collect();
         self.close_items(cx, SaveIntent::Close, move |item_id| {
             item_ids.contains(&item_id)
@@ -1095,11 +1175,19 @@ impl Pane {
             return None;
         }
 
-        Some(
-            self.close_items(cx, action.save_intent.unwrap_or(SaveIntent::Close), |_| {
-                true
-            }),
-        )
+        let pinned_item_ids = self.pinned_item_ids();
+        Some(self.close_items(
+            cx,
+            action.save_intent.unwrap_or(SaveIntent::Close),
+            move |item_id| !pinned_item_ids.contains(&item_id),
+        ))
+    }
+
+    fn pinned_item_ids(&self) -> Vec<EntityId> {
+        self.items[..self.pinned_tab_count]
+            .iter()
+            .map(|item| item.item_id())
+            .collect()
     }
 
     pub(super) fn file_names_for_prompt(
@@ -1109,15 +1197,18 @@ impl Pane {
     ) -> (String, String) {
         /// Quantity of item paths displayed in prompt prior to cutoff..
         const FILE_NAMES_CUTOFF_POINT: usize = 10;
-        let mut file_names: Vec<_> = items
-            .filter_map(|item| {
-                item.project_path(cx).and_then(|project_path| {
-                    project_path
-                        .path
-                        .file_name()
-                        .and_then(|name| name.to_str().map(ToOwned::to_owned))
-                })
+        let file_name = |item: &Box<dyn ItemHandle>| {
+            item.project_path(cx).and_then(|project_path| {
+                project_path
+                    .path
+                    .file_name()
+                    .and_then(|name| name.to_str().map(ToOwned::to_owned))
             })
+        };
+        let items = items.collect::<Vec<_>>();
+        let mut file_names: Vec<_> = items
+            .iter()
+            .filter_map(|item| file_name(item))
             .take(FILE_NAMES_CUTOFF_POINT)
             .collect();
         let should_display_followup_text =
@@ -1130,6 +1221,21 @@ impl Pane {
                 file_names.push(format!(".. {} files not shown", not_shown_files));
             }
         }
+        // Saving everything must not overwrite files that were changed on disk
+        // without asking, so call them out; each of them gets its own prompt.
+        let conflicted_file_names = items
+            .iter()
+            .filter(|item| item.has_conflict(cx))
+            .filter_map(|item| file_name(item))
+            .collect::<Vec<_>>();
+        if !conflicted_file_names.is_empty() {
+            file_names.push(String::new());
+            file_names.push(
+                "These files changed on disk since they were opened, you will be asked about each of them:"
+                    .into(),
+            );
+            file_names.extend(conflicted_file_names);
+        }
         (
             format!(
                 "Do you want to save changes to the following {} files?",
@@ -1237,6 +1343,14 @@ impl Pane {
                     break;
                 }
 
+                // A conflicted item that was skipped keeps its unsaved changes open.
+                if should_save
+                    && save_intent == SaveIntent::SaveAll
+                    && cx.update(|cx| item.is_dirty(cx) && item.has_conflict(cx))?
+                {
+                    continue;
+                }
+
                 // Remove the item from the pane.
                 pane.update(&mut cx, |pane, cx| {
                     if let Some(item_ix) = pane
@@ -1287,6 +1401,9 @@ impl Pane {
         }
 
         let item = self.items.remove(item_index);
+        if item_index < self.pinned_tab_count {
+            self.pinned_tab_count -= 1;
+        }
 
         cx.emit(Event::RemoveItem {
             item_id: item.item_id(),
@@ -1353,6 +1470,7 @@ impl Pane {
                 "This file has changed on disk since you started editing it. Do you want to overwrite it?";
 
         if save_intent == SaveIntent::Skip {
+            cx.update(|cx| item.discarded(cx))?;
             return Ok(true);
         }
 
@@ -1383,21 +1501,41 @@ impl Pane {
         let should_format = save_intent != SaveIntent::SaveWithoutFormat;
 
         if has_conflict && can_save {
+            let mut answers = vec!["Overwrite", "Discard"];
+            if can_save_as {
+                answers.push("Keep Both");
+            }
+            // During a bulk save, a conflicted file can be left alone without
+            // aborting the saving of the others.
+            if save_intent == SaveIntent::SaveAll {
+                answers.push("Skip");
+            }
+            answers.push("Cancel");
             let answer = pane.update(cx, |pane, cx| {
                 pane.activate_item(item_ix, true, true, cx);
-                cx.prompt(
-                    PromptLevel::Warning,
-                    CONFLICT_MESSAGE,
-                    None,
-                    &["Overwrite", "Discard", "Cancel"],
-                )
+                cx.prompt(PromptLevel::Warning, CONFLICT_MESSAGE, None, &answers)
             })?;
-            match answer.await {
-                Ok(0) => {
+            match answer.await.ok().and_then(|ix| answers.get(ix).copied()) {
+                Some("Overwrite") => {
                     pane.update(cx, |_, cx| item.save(should_format, project, cx))?
                         .await?
                 }
-                Ok(1) => pane.update(cx, |_, cx| item.reload(project, cx))?.await?,
+                Some("Discard") => pane.update(cx, |_, cx| item.reload(project, cx))?.await?,
+                // Leave the file on disk as the other program wrote it and save
+                // our version of it next to it.
+                Some("Keep Both") => {
+                    let abs_path = pane.update(cx, |pane, cx| {
+                        pane.workspace
+                            .update(cx, |workspace, cx| workspace.prompt_for_new_path(cx))
+                    })??;
+                    if let Some(abs_path) = abs_path.await.ok().flatten() {
+                        pane.update(cx, |_, cx| item.save_as(project, abs_path, cx))?
+                            .await?;
+                    } else {
+                        return Ok(false);
+                    }
+                }
+                Some("Skip") => return Ok(true),
                 _ => return Ok(false),
             }
         } else if is_dirty && (can_save || can_save_as) {
@@ -1435,8 +1573,12 @@ impl Pane {
                         })?;
                         match answer {
                             Ok(0) => {}
-                            Ok(1) => return Ok(true), // Don't save this file
-                            _ => return Ok(false),    // Cancel
+                            Ok(1) => {
+                                // Don't save this file
+                                cx.update(|cx| item.discarded(cx))?;
+                                return Ok(true);
+                            }
+                            _ => return Ok(false), // Cancel
                         }
                     } else {
                         return Ok(false);
@@ -1563,6 +1705,7 @@ impl Pane {
             .preview_item_id
             .map(|id| id == item.item_id())
             .unwrap_or(false);
+        let is_pinned = ix < self.pinned_tab_count;
 
         let label = item.tab_content(
             TabContentParams {
@@ -1642,13 +1785,23 @@ impl Pane {
             }))
             .on_drop(cx.listener(move |this, paths, cx| {
                 this.drag_split_direction = None;
-                this.handle_external_paths_drop(paths, cx)
+                this.handle_external_paths_drop(paths, Some(ix), cx)
             }))
             .when_some(item.tab_tooltip_text(cx), |tab, text| {
                 tab.tooltip(move |cx| Tooltip::text(text.clone(), cx))
             })
             .start_slot::<Indicator>(indicator)
-            .end_slot(
+            .end_slot(if is_pinned {
+                IconButton::new("unpin tab", IconName::Pin)
+                    .shape(IconButtonShape::Square)
+                    .icon_color(Color::Muted)
+                    .size(ButtonSize::None)
+                    .icon_size(IconSize::XSmall)
+                    .on_click(cx.listener(move |pane, _, cx| {
+                        pane.activate_item(ix, true, true, cx);
+                        pane.toggle_pin_tab(&TogglePinTab, cx);
+                    }))
+            } else {
                 IconButton::new("close tab", IconName::Close)
                     .shape(IconButtonShape::Square)
                     .icon_color(Color::Muted)
@@ -1657,8 +1810,8 @@ impl Pane {
                     .on_click(cx.listener(move |pane, _, cx| {
                         pane.close_item_by_id(item_id, SaveIntent::Close, cx)
                             .detach_and_log_err(cx);
-                    })),
-            )
+                    }))
+            })
             .child(label);
 
         let single_entry_to_resolve = {
@@ -1688,8 +1841,11 @@ impl Pane {
                             "Close Others",
                             Some(Box::new(CloseInactiveItems { save_intent: None })),
                             cx.handler_for(&pane, move |pane, cx| {
-                                pane.close_items(cx, SaveIntent::Close, |id| id != item_id)
-                                    .detach_and_log_err(cx);
+                                let pinned_item_ids = pane.pinned_item_ids();
+                                pane.close_items(cx, SaveIntent::Close, move |id| {
+                                    id != item_id && !pinned_item_ids.contains(&id)
+                                })
+                                .detach_and_log_err(cx);
                             }),
                         )
                         .separator()
@@ -1729,6 +1885,15 @@ impl Pane {
                                     task.detach_and_log_err(cx)
                                 }
                             }),
+                        )
+                        .separator()
+                        .entry(
+                            if is_pinned { "Unpin Tab" } else { "Pin Tab" },
+                            Some(Box::new(TogglePinTab)),
+                            cx.handler_for(&pane, move |pane, cx| {
+                                pane.activate_item(ix, true, true, cx);
+                                pane.toggle_pin_tab(&TogglePinTab, cx);
+                            }),
                         );
 
                     if let Some(entry) = single_entry_to_resolve {
@@ -1857,7 +2022,7 @@ impl Pane {
                     }))
                     .on_drop(cx.listener(move |this, paths, cx| {
                         this.drag_split_direction = None;
-                        this.handle_external_paths_drop(paths, cx)
+                        this.handle_external_paths_drop(paths, Some(this.items.len()), cx)
                     }))
                     .on_click(cx.listener(move |this, event: &ClickEvent, cx| {
                         if event.up.click_count == 2 {
@@ -1996,9 +2161,12 @@ impl Pane {
             .log_err();
     }
 
-    fn handle_external_paths_drop(
+    /// Opens the dropped files in this pane, at `destination_index` when they were dropped
+    /// on the tab bar, and adds the dropped directories to the project once confirmed.
+    pub(crate) fn handle_external_paths_drop(
         &mut self,
         paths: &ExternalPaths,
+        destination_index: Option<usize>,
         cx: &mut ViewContext<'_, Self>,
     ) {
         if let Some(custom_drop_handle) = self.custom_drop_handle.clone() {
@@ -2031,40 +2199,67 @@ impl Pane {
             .update(cx, |workspace, cx| {
                 let fs = Arc::clone(workspace.project().read(cx).fs());
                 cx.spawn(|workspace, mut cx| async move {
-                    let mut is_file_checks = FuturesUnordered::new();
-                    for path in &paths {
-                        is_file_checks.push(fs.is_file(path))
+                    // Dropped items that aren't on the file system anymore are ignored.
+                    let mut files = Vec::new();
+                    let mut directories = Vec::new();
+                    for path in paths {
+                        match fs.metadata(&path).await {
+                            Ok(Some(metadata)) if metadata.is_dir => directories.push(path),
+                            Ok(Some(_)) => files.push(path),
+                            Ok(None) | Err(_) => log::info!("ignoring dropped path {path:?}"),
+                        }
                     }
-                    let mut has_files_to_open = false;
-                    while let Some(is_file) = is_file_checks.next().await {
-                        if is_file {
-                            has_files_to_open = true;
-                            break;
+
+                    if !directories.is_empty() {
+                        let message = match directories.as_slice() {
+                            [directory] => format!(
+                                "Add {} to the project?",
+                                directory.file_name().unwrap_or_default().to_string_lossy()
+                            ),
+                            _ => format!("Add {} folders to the project?", directories.len()),
+                        };
+                        let answer = workspace.update(&mut cx, |_, cx| {
+                            cx.prompt(PromptLevel::Info, &message, None, &["Add", "Cancel"])
+                        })?;
+                        if answer.await != Ok(0) {
+                            directories.clear();
                         }
                     }
-                    drop(is_file_checks);
-                    if !has_files_to_open {
+
+                    if files.is_empty() {
+                        if directories.is_empty() {
+                            return Ok(());
+                        }
                         split_direction = None;
                     }
 
-                    if let Some(open_task) = workspace
-                        .update(&mut cx, |workspace, cx| {
-                            if let Some(split_direction) = split_direction {
-                                to_pane = workspace.split_pane(to_pane, split_direction, cx);
+                    let open_task = workspace.update(&mut cx, |workspace, cx| {
+                        if let Some(split_direction) = split_direction {
+                            to_pane = workspace.split_pane(to_pane, split_direction, cx);
+                        }
+                        files.extend(directories);
+                        workspace.open_paths(
+                            files,
+                            OpenVisible::OnlyDirectories,
+                            Some(to_pane.downgrade()),
+                            cx,
+                        )
+                    })?;
+                    let opened_items = open_task.await;
+
+                    if let Some(destination_index) = destination_index {
+                        to_pane.update(&mut cx, |pane, cx| {
+                            let opened_items = opened_items.into_iter().flatten().flatten();
+                            for (offset, item) in opened_items.enumerate() {
+                                let destination_index = destination_index + offset;
+                                pane.add_item(item, false, false, Some(destination_index), cx);
                             }
-                            workspace.open_paths(
-                                paths,
-                                OpenVisible::OnlyDirectories,
-                                Some(to_pane.downgrade()),
-                                cx,
-                            )
-                        })
-                        .ok()
-                    {
-                        let _opened_items: Vec<_> = open_task.await;
+                        })?;
                     }
+
+                    anyhow::Ok(())
                 })
-                .detach();
+                .detach_and_log_err(cx);
             })
             .log_err();
     }
@@ -2121,6 +2316,13 @@ impl Render for Pane {
             .on_action(cx.listener(|pane: &mut Pane, _: &ActivateNextItem, cx| {
                 pane.activate_next_item(true, cx);
             }))
+            .on_action(cx.listener(|pane: &mut Pane, _: &MoveItemLeft, cx| {
+                pane.move_item_left(cx);
+            }))
+            .on_action(cx.listener(|pane: &mut Pane, _: &MoveItemRight, cx| {
+                pane.move_item_right(cx);
+            }))
+            .on_action(cx.listener(Pane::toggle_pin_tab))
             .when(PreviewTabsSettings::get_global(cx).enabled, |this| {
                 this.on_action(cx.listener(|pane: &mut Pane, _: &TogglePreviewTab, cx| {
                     if let Some(active_item_id) = pane.active_item().map(|i| i.item_id()) {
@@ -2244,7 +2446,7 @@ impl Render for Pane {
                                 )
                             }))
                             .on_drop(cx.listener(move |this, paths, cx| {
-                                this.handle_external_paths_drop(paths, cx)
+                                this.handle_external_paths_drop(paths, None, cx)
                             }))
                             .map(|div| {
                                 let size = DefiniteLength::Fraction(0.5);
@@ -2444,6 +2646,40 @@ impl NavHistory {
     pub fn path_for_item(&self, item_id: EntityId) -> Option<(ProjectPath, Option<PathBuf>)> {
         self.0.lock().paths_by_item.get(&item_id).cloned()
     }
+
+    /// When the most recently closed item of this history was closed, comparable across the
+    /// panes of a workspace.
+    pub fn last_closed_timestamp(&self) -> Option<usize> {
+        self.0
+            .lock()
+            .closed_stack
+            .back()
+            .map(|entry| entry.timestamp)
+    }
+
+    /// Adds the closed items of another history, e.g. one of a pane that got removed, so that
+    /// they can still be reopened.
+    pub fn merge_closed_items(&mut self, other: &NavHistory) {
+        if Arc::ptr_eq(&self.0, &other.0) {
+            return;
+        }
+
+        let mut other = other.0.lock();
+        let mut state = self.0.lock();
+        for entry in other.closed_stack.drain(..) {
+            if let Some(path) = other.paths_by_item.get(&entry.item.id()) {
+                state.paths_by_item.insert(entry.item.id(), path.clone());
+            }
+            state.closed_stack.push_back(entry);
+        }
+        state
+            .closed_stack
+            .make_contiguous()
+            .sort_by_key(|entry| entry.timestamp);
+        while state.closed_stack.len() > MAX_NAVIGATION_HISTORY_LEN {
+            state.closed_stack.pop_front();
+        }
+    }
 }
 
 impl NavHistoryState {
@@ -2970,6 +3206,144 @@ mod tests {
         assert_item_labels(&pane, [], cx);
     }
 
+    #[gpui::test]
+    async fn test_close_active_item_at_ends(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+
+        let project = Project::test(fs, None, cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project.clone(), cx));
+        let pane = workspace.update(cx, |workspace, _| workspace.active_pane().clone());
+
+        add_labeled_item(&pane, "A", false, cx);
+        add_labeled_item(&pane, "B", false, cx);
+        add_labeled_item(&pane, "C", false, cx);
+        assert_item_labels(&pane, ["A", "B", "C*"], cx);
+
+        pane.update(cx, |pane, cx| {
+            pane.close_active_item(&CloseActiveItem { save_intent: None }, cx)
+        })
+        .unwrap()
+        .await
+        .unwrap();
+        assert_item_labels(&pane, ["A", "B*"], cx);
+
+        pane.update(cx, |pane, cx| pane.activate_item(0, false, false, cx));
+        pane.update(cx, |pane, cx| {
+            pane.close_active_item(&CloseActiveItem { save_intent: None }, cx)
+        })
+        .unwrap()
+        .await
+        .unwrap();
+        assert_item_labels(&pane, ["B*"], cx);
+
+        pane.update(cx, |pane, cx| {
+            pane.close_active_item(&CloseActiveItem { save_intent: None }, cx)
+        })
+        .unwrap()
+        .await
+        .unwrap();
+        assert_item_labels(&pane, [], cx);
+    }
+
+    #[gpui::test]
+    async fn test_move_items(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+
+        let project = Project::test(fs, None, cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project.clone(), cx));
+        let pane = workspace.update(cx, |workspace, _| workspace.active_pane().clone());
+
+        set_labeled_items(&pane, ["A", "B*", "C"], cx);
+
+        pane.update(cx, |pane, cx| pane.move_item_left(cx));
+        assert_item_labels(&pane, ["B*", "A", "C"], cx);
+        pane.update(cx, |pane, cx| pane.move_item_left(cx));
+        assert_item_labels(&pane, ["B*", "A", "C"], cx);
+
+        pane.update(cx, |pane, cx| pane.move_item_right(cx));
+        assert_item_labels(&pane, ["A", "B*", "C"], cx);
+        pane.update(cx, |pane, cx| pane.move_item_right(cx));
+        assert_item_labels(&pane, ["A", "C", "B*"], cx);
+        pane.update(cx, |pane, cx| pane.move_item_right(cx));
+        assert_item_labels(&pane, ["A", "C", "B*"], cx);
+    }
+
+    #[gpui::test]
+    async fn test_pinned_tabs(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+
+        let project = Project::test(fs, None, cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project.clone(), cx));
+        let pane = workspace.update(cx, |workspace, _| workspace.active_pane().clone());
+
+        // Pinned items are moved after the other pinned ones.
+        set_labeled_items(&pane, ["A", "B", "C", "D*"], cx);
+        pane.update(cx, |pane, cx| {
+            pane.activate_item(2, false, false, cx);
+            pane.toggle_pin_tab(&TogglePinTab, cx);
+        });
+        assert_item_labels(&pane, ["C*", "A", "B", "D"], cx);
+        pane.update(cx, |pane, cx| {
+            pane.activate_item(3, false, false, cx);
+            pane.toggle_pin_tab(&TogglePinTab, cx);
+        });
+        assert_item_labels(&pane, ["C", "D*", "A", "B"], cx);
+
+        // Items move among the pinned or the unpinned ones, but not from one to the other.
+        pane.update(cx, |pane, cx| pane.move_item_left(cx));
+        assert_item_labels(&pane, ["D*", "C", "A", "B"], cx);
+        pane.update(cx, |pane, cx| {
+            pane.activate_item(2, false, false, cx);
+            pane.move_item_left(cx);
+        });
+        assert_item_labels(&pane, ["D", "C", "A*", "B"], cx);
+
+        // New items are added after the pinned ones.
+        add_labeled_item(&pane, "E", false, cx);
+        assert_item_labels(&pane, ["D", "C", "A", "E*", "B"], cx);
+        pane.update(cx, |pane, cx| pane.activate_item(0, false, false, cx));
+        add_labeled_item(&pane, "F", false, cx);
+        assert_item_labels(&pane, ["D", "C", "F*", "A", "E", "B"], cx);
+
+        // Closing all the items keeps the pinned ones.
+        pane.update(cx, |pane, cx| {
+            pane.close_all_items(&CloseAllItems { save_intent: None }, cx)
+        })
+        .unwrap()
+        .await
+        .unwrap();
+        assert_item_labels(&pane, ["D*", "C"], cx);
+
+        // Unpinned items are moved before the other unpinned ones, and then get closed.
+        pane.update(cx, |pane, cx| {
+            pane.activate_item(0, false, false, cx);
+            pane.toggle_pin_tab(&TogglePinTab, cx);
+        });
+        assert_item_labels(&pane, ["C", "D*"], cx);
+        pane.update(cx, |pane, cx| {
+            assert!(pane.is_item_pinned(pane.items[0].item_id()));
+            assert!(!pane.is_item_pinned(pane.items[1].item_id()));
+            pane.activate_item(0, false, false, cx);
+            pane.close_inactive_items(&CloseInactiveItems { save_intent: None }, cx)
+        })
+        .unwrap()
+        .await
+        .unwrap();
+        assert_item_labels(&pane, ["C*"], cx);
+
+        // Pinned items still get closed explicitly.
+        pane.update(cx, |pane, cx| {
+            pane.close_active_item(&CloseActiveItem { save_intent: None }, cx)
+        })
+        .unwrap()
+        .await
+        .unwrap();
+        assert_item_labels(&pane, [], cx);
+    }
+
     fn init_test(cx: &mut TestAppContext) {
         cx.update(|cx| {
             let settings_store = SettingsStore::test(cx);
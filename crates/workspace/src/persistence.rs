@@ -18,14 +18,43 @@ use uuid::Uuid;
 use crate::WorkspaceId;
 
 use model::{
-    GroupId, LocalPaths, PaneId, SerializedItem, SerializedPane, SerializedPaneGroup,
-    SerializedWorkspace,
+    retain_members, GroupId, LocalPaths, PaneId, SerializedItem, SerializedLanguageOverrides,
+    SerializedPane, SerializedPaneGroup, SerializedRecentPaths, SerializedWorkspace,
 };
 
 use self::model::{
     DockStructure, LocalPathsOrder, SerializedDevServerProject, SerializedWorkspaceLocation,
 };
 
+/// The version of the layout saved with each pane group, which is a JSON array with the flex
+/// of each member. Layouts saved before the version was, or that don't fit the members of
+/// their group, are restored with equal flexes. So are the ones saved by newer versions of Zed,
+/// whose layout can't be read.
+const PANE_GROUP_LAYOUT_VERSION: i64 = 1;
+
+fn deserialize_flexes(
+    flexes: Option<String>,
+    layout_version: Option<i64>,
+    member_count: usize,
+) -> Option<Vec<f32>> {
+    let flexes = flexes?;
+    if let Some(layout_version) =
+        layout_version.filter(|version| *version > PANE_GROUP_LAYOUT_VERSION)
+    {
+        log::info!("ignoring pane group layout of unknown version {layout_version}");
+        return None;
+    }
+
+    let flexes = serde_json::from_str::<Vec<f32>>(&flexes)
+        .context("deserializing pane group flexes")
+        .log_err()?;
+    if flexes.len() != member_count || flexes.iter().any(|flex| !flex.is_finite() || *flex <= 0.) {
+        log::warn!("ignoring invalid pane group flexes {flexes:?} for {member_count} members");
+        return None;
+    }
+    Some(flexes)
+}
+
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub(crate) struct SerializedAxis(pub(crate) gpui::Axis);
 impl sqlez::bindable::StaticColumnCount for SerializedAxis {}
@@ -192,6 +221,10 @@ define_connection! {
     //   display: Option<Uuid>, // Display id
     //   fullscreen: Option<bool>, // Is the window fullscreen?
     //   centered_layout: Option<bool>, // Is the Centered Layout mode activated?
+    //   read_only_paths: Option<Bincode<Vec<PathBuf>>>, // Worktree roots opened in read-only mode
+    //   recent_paths: Option<Bincode<Vec<PathBuf>>>, // Most recently opened files, most recent first
+    //   center_pane_zoomed: Option<bool>, // Is the active center pane zoomed?
+    //   language_overrides: Option<Bincode<Vec<(PathBuf, String)>>>, // Languages chosen for files
     // )
     //
     // pane_groups(
@@ -201,6 +234,7 @@ define_connection! {
     //   position: Optiopn<usize>, // None indicates that this is the root node
     //   axis: Option<Axis>, // 'Vertical', 'Horizontal'
     //   flexes: Option<Vec<f32>>, // A JSON array of floats
+    //   layout_version: Option<usize>, // The version of the layout that flexes was saved with
     // )
     //
     // panes(
@@ -223,6 +257,7 @@ define_connection! {
     //     position: usize, // Position of the item in the parent pane. This is equivalent to panes' position column
     //     active: bool, // Indicates if this item is the active one in the pane
     //     preview: bool // Indicates if this item is a preview item
+    //     pinned: bool // Indicates if this item is pinned to the start of the pane
     // )
     pub static ref DB: WorkspaceDb<()> =
     &[sql!(
@@ -366,6 +401,30 @@ define_connection! {
     sql!(
         ALTER TABLE workspaces ADD COLUMN local_paths_order BLOB;
     ),
+    // Add read-only worktree roots to workspace
+    sql!(
+        ALTER TABLE workspaces ADD COLUMN read_only_paths BLOB;
+    ),
+    // Add the most recently opened files to workspace
+    sql!(
+        ALTER TABLE workspaces ADD COLUMN recent_paths BLOB;
+    ),
+    // Add pinned field to items
+    sql!(
+        ALTER TABLE items ADD COLUMN pinned INTEGER; //bool
+    ),
+    // Add the zoom state of the center panes to workspace
+    sql!(
+        ALTER TABLE workspaces ADD COLUMN center_pane_zoomed INTEGER; //bool
+    ),
+    // Add the version of the layout of pane groups
+    sql!(
+        ALTER TABLE pane_groups ADD COLUMN layout_version INTEGER;
+    ),
+    // Add the languages chosen for files to workspace
+    sql!(
+        ALTER TABLE workspaces ADD COLUMN language_overrides BLOB;
+    ),
     ];
 }
 
@@ -390,6 +449,7 @@ impl WorkspaceDb {
             display,
             centered_layout,
             docks,
+            recent_paths,
         ): (
             WorkspaceId,
             Option<LocalPaths>,
@@ -399,6 +459,7 @@ impl WorkspaceDb {
             Option<Uuid>,
             Option<bool>,
             DockStructure,
+            Option<SerializedRecentPaths>,
         ) = self
             .select_row_bound(sql! {
                 SELECT
@@ -421,7 +482,8 @@ impl WorkspaceDb {
                     right_dock_zoom,
                     bottom_dock_visible,
                     bottom_dock_active_panel,
-                    bottom_dock_zoom
+                    bottom_dock_zoom,
+                    recent_paths
                 FROM workspaces
                 WHERE local_paths = ?
             })
@@ -465,6 +527,7 @@ impl WorkspaceDb {
             centered_layout: centered_layout.unwrap_or(false),
             display,
             docks,
+            recent_paths: recent_paths.unwrap_or_default(),
         })
     }
 
@@ -483,6 +546,7 @@ impl WorkspaceDb {
             display,
             centered_layout,
             docks,
+            recent_paths,
         ): (
             WorkspaceId,
             Option<LocalPaths>,
@@ -492,6 +556,7 @@ impl WorkspaceDb {
             Option<Uuid>,
             Option<bool>,
             DockStructure,
+            Option<SerializedRecentPaths>,
         ) = self
             .select_row_bound(sql! {
                 SELECT
@@ -514,7 +579,8 @@ impl WorkspaceDb {
                     right_dock_zoom,
                     bottom_dock_visible,
                     bottom_dock_active_panel,
-                    bottom_dock_zoom
+                    bottom_dock_zoom,
+                    recent_paths
                 FROM workspaces
                 WHERE dev_server_project_id = ?
             })
@@ -558,6 +624,7 @@ impl WorkspaceDb {
             centered_layout: centered_layout.unwrap_or(false),
             display,
             docks,
+            recent_paths: recent_paths.unwrap_or_default(),
         })
     }
 
@@ -671,6 +738,11 @@ impl WorkspaceDb {
                     }
                 }
 
+                conn.exec_bound(sql!(
+                    UPDATE workspaces SET recent_paths = ?2 WHERE workspace_id = ?1
+                ))?((workspace.id, &workspace.recent_paths))
+                .context("Updating recent paths")?;
+
                 // Save center pane group
                 Self::save_pane_group(conn, workspace.id, &workspace.center_group, None)
                     .context("save pane group in save workspace")?;
@@ -805,10 +877,22 @@ impl WorkspaceDb {
             }))
     }
 
+    /// Returns the panes and pane groups in a pane group, leaving out the ones that don't have
+    /// any items.
     fn get_pane_group(
         &self,
         workspace_id: WorkspaceId,
         group_id: Option<GroupId>,
+    ) -> Result<Vec<SerializedPaneGroup>> {
+        let mut members = self.get_pane_group_members(workspace_id, group_id)?;
+        members.retain(|member| !member.is_empty());
+        Ok(members)
+    }
+
+    fn get_pane_group_members(
+        &self,
+        workspace_id: WorkspaceId,
+        group_id: Option<GroupId>,
     ) -> Result<Vec<SerializedPaneGroup>> {
         type GroupKey = (Option<GroupId>, WorkspaceId);
         type GroupOrPane = (
@@ -817,9 +901,10 @@ impl WorkspaceDb {
             Option<PaneId>,
             Option<bool>,
             Option<String>,
+            Option<i64>,
         );
         self.select_bound::<GroupKey, GroupOrPane>(sql!(
-            SELECT group_id, axis, pane_id, active, flexes
+            SELECT group_id, axis, pane_id, active, flexes, layout_version
                 FROM (SELECT
                         group_id,
                         axis,
@@ -828,7 +913,8 @@ impl WorkspaceDb {
                         position,
                         parent_group_id,
                         workspace_id,
-                        flexes
+                        flexes,
+                        layout_version
                       FROM pane_groups
                     UNION
                       SELECT
@@ -839,6 +925,7 @@ impl WorkspaceDb {
                         position,
                         parent_group_id,
                         panes.workspace_id as workspace_id,
+                        NULL,
                         NULL
                       FROM center_panes
                       JOIN panes ON center_panes.pane_id = panes.pane_id)
@@ -846,15 +933,16 @@ impl WorkspaceDb {
                 ORDER BY position
         ))?((group_id, workspace_id))?
         .into_iter()
-        .map(|(group_id, axis, pane_id, active, flexes)| {
+        .map(|(group_id, axis, pane_id, active, flexes, version)| {
             if let Some((group_id, axis)) = group_id.zip(axis) {
-                let flexes = flexes
-                    .map(|flexes: String| serde_json::from_str::<Vec<f32>>(&flexes))
-                    .transpose()?;
+                let children = self.get_pane_group_members(workspace_id, Some(group_id))?;
+                let flexes = deserialize_flexes(flexes, version, children.len());
+                let (children, flexes) =
+                    retain_members(children, flexes, |member| !member.is_empty());
 
                 Ok(SerializedPaneGroup::Group {
                     axis,
-                    children: self.get_pane_group(workspace_id, Some(group_id))?,
+                    children,
                     flexes,
                 })
             } else if let Some((pane_id, active)) = pane_id.zip(active) {
@@ -866,12 +954,6 @@ impl WorkspaceDb {
                 bail!("Pane Group Child was neither a pane group or a pane");
             }
         })
-        // Filter out panes and pane groups which don't have any children or items
-        .filter(|pane_group| match pane_group {
-            Ok(SerializedPaneGroup::Group { children, .. }) => !children.is_empty(),
-            Ok(SerializedPaneGroup::Pane(pane)) => !pane.children.is_empty(),
-            _ => true,
-        })
         .collect::<Result<_>>()
     }
 
@@ -899,9 +981,10 @@ impl WorkspaceDb {
                         parent_group_id,
                         position,
                         axis,
-                        flexes
+                        flexes,
+                        layout_version
                     )
-                    VALUES (?, ?, ?, ?, ?)
+                    VALUES (?, ?, ?, ?, ?, ?)
                     RETURNING group_id
                 ))?((
                     workspace_id,
@@ -909,6 +992,7 @@ impl WorkspaceDb {
                     position,
                     *axis,
                     flex_string,
+                    PANE_GROUP_LAYOUT_VERSION,
                 ))?
                 .ok_or_else(|| anyhow!("Couldn't retrieve group_id from inserted pane_group"))?;
 
@@ -951,7 +1035,7 @@ impl WorkspaceDb {
 
     fn get_items(&self, pane_id: PaneId) -> Result<Vec<SerializedItem>> {
         self.select_bound(sql!(
-            SELECT kind, item_id, active, preview FROM items
+            SELECT kind, item_id, active, preview, pinned FROM items
             WHERE pane_id = ?
                 ORDER BY position
         ))?(pane_id)
@@ -964,7 +1048,7 @@ impl WorkspaceDb {
         items: &[SerializedItem],
     ) -> Result<()> {
         let mut insert = conn.exec_bound(sql!(
-            INSERT INTO items(workspace_id, pane_id, position, kind, item_id, active, preview) VALUES (?, ?, ?, ?, ?, ?, ?)
+            INSERT INTO items(workspace_id, pane_id, position, kind, item_id, active, preview, pinned) VALUES (?, ?, ?, ?, ?, ?, ?, ?)
         )).context("Preparing insertion")?;
         for (position, item) in items.iter().enumerate() {
             insert((workspace_id, pane_id, position, item))?;
@@ -1001,6 +1085,54 @@ impl WorkspaceDb {
             WHERE workspace_id = ?1
         }
     }
+
+    query! {
+        pub(crate) async fn set_read_only_paths(workspace_id: WorkspaceId, read_only_paths: LocalPaths) -> Result<()> {
+            UPDATE workspaces
+            SET read_only_paths = ?2
+            WHERE workspace_id = ?1
+        }
+    }
+
+    query! {
+        pub(crate) fn read_only_paths(workspace_id: WorkspaceId) -> Result<Option<LocalPaths>> {
+            SELECT read_only_paths
+            FROM workspaces
+            WHERE workspace_id = ?
+        }
+    }
+
+    query! {
+        pub(crate) async fn set_center_pane_zoomed(workspace_id: WorkspaceId, center_pane_zoomed: bool) -> Result<()> {
+            UPDATE workspaces
+            SET center_pane_zoomed = ?2
+            WHERE workspace_id = ?1
+        }
+    }
+
+    query! {
+        pub(crate) fn center_pane_zoomed(workspace_id: WorkspaceId) -> Result<Option<bool>> {
+            SELECT center_pane_zoomed
+            FROM workspaces
+            WHERE workspace_id = ?
+        }
+    }
+
+    query! {
+        pub(crate) async fn set_language_overrides(workspace_id: WorkspaceId, language_overrides: SerializedLanguageOverrides) -> Result<()> {
+            UPDATE workspaces
+            SET language_overrides = ?2
+            WHERE workspace_id = ?1
+        }
+    }
+
+    query! {
+        pub(crate) fn language_overrides(workspace_id: WorkspaceId) -> Result<Option<SerializedLanguageOverrides>> {
+            SELECT language_overrides
+            FROM workspaces
+            WHERE workspace_id = ?
+        }
+    }
 }
 
 #[cfg(test)]
@@ -1087,6 +1219,7 @@ mod tests {
             display: Default::default(),
             docks: Default::default(),
             centered_layout: false,
+            recent_paths: Default::default(),
         };
 
         let workspace_2 = SerializedWorkspace {
@@ -1097,6 +1230,7 @@ mod tests {
             display: Default::default(),
             docks: Default::default(),
             centered_layout: false,
+            recent_paths: Default::default(),
         };
 
         db.save_workspace(workspace_1.clone()).await;
@@ -1199,6 +1333,7 @@ mod tests {
             display: Default::default(),
             docks: Default::default(),
             centered_layout: false,
+            recent_paths: Default::default(),
         };
 
         db.save_workspace(workspace.clone()).await;
@@ -1231,6 +1366,7 @@ mod tests {
             display: Default::default(),
             docks: Default::default(),
             centered_layout: false,
+            recent_paths: Default::default(),
         };
 
         let mut workspace_2 = SerializedWorkspace {
@@ -1241,6 +1377,7 @@ mod tests {
             display: Default::default(),
             docks: Default::default(),
             centered_layout: false,
+            recent_paths: Default::default(),
         };
 
         db.save_workspace(workspace_1.clone()).await;
@@ -1281,6 +1418,7 @@ mod tests {
             display: Default::default(),
             docks: Default::default(),
             centered_layout: false,
+            recent_paths: Default::default(),
         };
 
         db.save_workspace(workspace_3.clone()).await;
@@ -1315,6 +1453,7 @@ mod tests {
             display: Default::default(),
             docks: Default::default(),
             centered_layout: false,
+            recent_paths: Default::default(),
         }
     }
 
@@ -1370,6 +1509,74 @@ mod tests {
         assert_eq!(workspace.center_group, new_workspace.center_group);
     }
 
+    #[gpui::test]
+    async fn test_split_flexes() {
+        env_logger::try_init().ok();
+
+        let db = WorkspaceDb(open_test_db("test_split_flexes").await);
+        let pane = |item_ids: &[u64]| {
+            SerializedPaneGroup::Pane(SerializedPane::new(
+                item_ids
+                    .iter()
+                    .map(|item_id| SerializedItem::new("Terminal", *item_id, true, false))
+                    .collect(),
+                false,
+            ))
+        };
+        let split = |flexes: Option<Vec<f32>>, children: Vec<SerializedPaneGroup>| {
+            SerializedPaneGroup::Group {
+                axis: SerializedAxis(Axis::Horizontal),
+                flexes,
+                children,
+            }
+        };
+
+        let center_group = split(Some(vec![0.5, 1.5]), vec![pane(&[1]), pane(&[2])]);
+        db.save_workspace(default_workspace(&["/tmp"], &center_group))
+            .await;
+        assert_eq!(
+            db.workspace_for_roots(&["/tmp"]).unwrap().center_group,
+            center_group
+        );
+
+        // Panes without items aren't restored, and give their space to the others.
+        db.save_workspace(default_workspace(
+            &["/tmp"],
+            &split(
+                Some(vec![0.25, 2., 0.75]),
+                vec![pane(&[1]), pane(&[]), pane(&[2])],
+            ),
+        ))
+        .await;
+        assert_eq!(
+            db.workspace_for_roots(&["/tmp"]).unwrap().center_group,
+            center_group
+        );
+
+        // Layouts that can't be read, or that were saved by a newer version, are restored
+        // with equal flexes.
+        db.write(|conn| {
+            conn.exec_bound(sql!(UPDATE pane_groups SET flexes = ?))
+                .unwrap()("[0.5, 1.5")
+            .unwrap()
+        })
+        .await;
+        assert_eq!(
+            db.workspace_for_roots(&["/tmp"]).unwrap().center_group,
+            split(None, vec![pane(&[1]), pane(&[2])])
+        );
+        db.write(|conn| {
+            conn.exec_bound(sql!(UPDATE pane_groups SET flexes = ?, layout_version = ?))
+                .unwrap()(("[0.5, 1.5]", 2))
+            .unwrap()
+        })
+        .await;
+        assert_eq!(
+            db.workspace_for_roots(&["/tmp"]).unwrap().center_group,
+            split(None, vec![pane(&[1]), pane(&[2])])
+        );
+    }
+
     #[gpui::test]
     async fn test_cleanup_panes() {
         env_logger::try_init().ok();
@@ -1440,4 +1647,54 @@ mod tests {
 
         assert_eq!(workspace.center_group, new_workspace.center_group);
     }
+
+    #[gpui::test]
+    async fn test_recent_paths() {
+        env_logger::try_init().ok();
+
+        let db = WorkspaceDb(open_test_db("test_recent_paths").await);
+
+        let mut workspace = default_workspace(&["/tmp"], &Default::default());
+        db.save_workspace(workspace.clone()).await;
+        assert_eq!(
+            db.workspace_for_roots(&["/tmp"]).unwrap().recent_paths,
+            SerializedRecentPaths::default()
+        );
+
+        // The recent paths are saved along with the rest of the workspace, in their order.
+        workspace.recent_paths =
+            SerializedRecentPaths::new(["/tmp/b.rs", "/tmp/a.rs", "/tmp/c.rs"]);
+        db.save_workspace(workspace.clone()).await;
+        assert_eq!(db.workspace_for_roots(&["/tmp"]).unwrap(), workspace);
+    }
+
+    #[gpui::test]
+    async fn test_language_overrides() {
+        env_logger::try_init().ok();
+
+        let db = WorkspaceDb(open_test_db("test_language_overrides").await);
+
+        let workspace = default_workspace(&["/tmp"], &Default::default());
+        db.save_workspace(workspace.clone()).await;
+        assert_eq!(db.language_overrides(workspace.id).unwrap(), None);
+
+        let language_overrides = SerializedLanguageOverrides::new([
+            ("/tmp/Justfile", "Shell Script"),
+            ("/tmp/a", "Rust"),
+        ]);
+        db.set_language_overrides(workspace.id, language_overrides.clone())
+            .await
+            .unwrap();
+        assert_eq!(
+            db.language_overrides(workspace.id).unwrap(),
+            Some(language_overrides.clone())
+        );
+
+        // Saving the rest of the workspace state keeps the language overrides.
+        db.save_workspace(workspace.clone()).await;
+        assert_eq!(
+            db.language_overrides(workspace.id).unwrap(),
+            Some(language_overrides)
+        );
+    }
 }
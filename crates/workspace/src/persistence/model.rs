@@ -58,6 +58,12 @@ impl Bind for &LocalPaths {
     }
 }
 
+impl Bind for LocalPaths {
+    fn bind(&self, statement: &Statement, start_index: i32) -> Result<i32> {
+        <&LocalPaths as Bind>::bind(&self, statement, start_index)
+    }
+}
+
 impl Column for LocalPaths {
     fn column(statement: &mut Statement, start_index: i32) -> Result<(Self, i32)> {
         let path_blob = statement.column_blob(start_index)?;
@@ -108,6 +114,98 @@ impl Column for LocalPathsOrder {
     }
 }
 
+/// Absolute paths of the files most recently opened in a workspace, most recent first.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct SerializedRecentPaths(Vec<PathBuf>);
+
+impl SerializedRecentPaths {
+    pub fn new<P: AsRef<Path>>(paths: impl IntoIterator<Item = P>) -> Self {
+        Self(
+            paths
+                .into_iter()
+                .map(|p| p.as_ref().to_path_buf())
+                .collect(),
+        )
+    }
+
+    pub fn paths(&self) -> &[PathBuf] {
+        self.0.as_slice()
+    }
+}
+
+impl StaticColumnCount for SerializedRecentPaths {}
+impl Bind for &SerializedRecentPaths {
+    fn bind(&self, statement: &Statement, start_index: i32) -> Result<i32> {
+        statement.bind(&bincode::serialize(&self.0)?, start_index)
+    }
+}
+
+impl Bind for SerializedRecentPaths {
+    fn bind(&self, statement: &Statement, start_index: i32) -> Result<i32> {
+        <&SerializedRecentPaths as Bind>::bind(&self, statement, start_index)
+    }
+}
+
+impl Column for SerializedRecentPaths {
+    fn column(statement: &mut Statement, start_index: i32) -> Result<(Self, i32)> {
+        let paths_blob = statement.column_blob(start_index)?;
+        let paths = if paths_blob.is_empty() {
+            Vec::new()
+        } else {
+            bincode::deserialize(paths_blob).context("deserializing workspace recent paths")?
+        };
+
+        Ok((Self(paths), start_index + 1))
+    }
+}
+
+/// The names of the languages chosen for files of a workspace, by the absolute paths of the
+/// files.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct SerializedLanguageOverrides(Vec<(PathBuf, String)>);
+
+impl SerializedLanguageOverrides {
+    pub fn new<P: AsRef<Path>, N: ToString>(overrides: impl IntoIterator<Item = (P, N)>) -> Self {
+        let mut overrides = overrides
+            .into_iter()
+            .map(|(path, name)| (path.as_ref().to_path_buf(), name.to_string()))
+            .collect::<Vec<_>>();
+        overrides.sort();
+        Self(overrides)
+    }
+
+    pub fn overrides(&self) -> &[(PathBuf, String)] {
+        self.0.as_slice()
+    }
+}
+
+impl StaticColumnCount for SerializedLanguageOverrides {}
+impl Bind for &SerializedLanguageOverrides {
+    fn bind(&self, statement: &Statement, start_index: i32) -> Result<i32> {
+        statement.bind(&bincode::serialize(&self.0)?, start_index)
+    }
+}
+
+impl Bind for SerializedLanguageOverrides {
+    fn bind(&self, statement: &Statement, start_index: i32) -> Result<i32> {
+        <&SerializedLanguageOverrides as Bind>::bind(&self, statement, start_index)
+    }
+}
+
+impl Column for SerializedLanguageOverrides {
+    fn column(statement: &mut Statement, start_index: i32) -> Result<(Self, i32)> {
+        let overrides_blob = statement.column_blob(start_index)?;
+        let overrides = if overrides_blob.is_empty() {
+            Vec::new()
+        } else {
+            bincode::deserialize(overrides_blob)
+                .context("deserializing workspace language overrides")?
+        };
+
+        Ok((Self(overrides), start_index + 1))
+    }
+}
+
 impl From<SerializedDevServerProject> for SerializedWorkspaceLocation {
     fn from(dev_server_project: SerializedDevServerProject) -> Self {
         Self::DevServer(dev_server_project)
@@ -154,6 +252,7 @@ pub(crate) struct SerializedWorkspace {
     pub(crate) centered_layout: bool,
     pub(crate) display: Option<Uuid>,
     pub(crate) docks: DockStructure,
+    pub(crate) recent_paths: SerializedRecentPaths,
 }
 
 #[derive(Debug, PartialEq, Clone, Default)]
@@ -238,7 +337,43 @@ impl Default for SerializedPaneGroup {
     }
 }
 
+/// Keeps the members of a pane group for which `keep` returns true, along with their flexes.
+/// The flexes that are kept are scaled to add up to the number of members that are kept again,
+/// and are dropped if they don't match the members.
+pub(crate) fn retain_members<T>(
+    members: Vec<T>,
+    flexes: Option<Vec<f32>>,
+    keep: impl Fn(&T) -> bool,
+) -> (Vec<T>, Option<Vec<f32>>) {
+    let flexes = flexes.filter(|flexes| flexes.len() == members.len());
+    let mut kept_members = Vec::new();
+    let mut kept_flexes = Vec::new();
+    for (ix, member) in members.into_iter().enumerate() {
+        if keep(&member) {
+            kept_members.push(member);
+            if let Some(flexes) = &flexes {
+                kept_flexes.push(flexes[ix]);
+            }
+        }
+    }
+
+    let total = kept_flexes.iter().sum::<f32>();
+    let flexes = flexes.filter(|_| total > 0.).map(|_| {
+        let scale = kept_flexes.len() as f32 / total;
+        kept_flexes.iter().map(|flex| flex * scale).collect()
+    });
+    (kept_members, flexes)
+}
+
 impl SerializedPaneGroup {
+    /// Whether the pane group has no items to restore.
+    pub(crate) fn is_empty(&self) -> bool {
+        match self {
+            SerializedPaneGroup::Group { children, .. } => children.is_empty(),
+            SerializedPaneGroup::Pane(pane) => pane.children.is_empty(),
+        }
+    }
+
     #[async_recursion(?Send)]
     pub(crate) async fn deserialize(
         self,
@@ -253,18 +388,27 @@ impl SerializedPaneGroup {
                 children,
                 flexes,
             } => {
+                let mut deserialized_children = Vec::new();
+                for child in children {
+                    deserialized_children.push(
+                        child
+                            .deserialize(project, workspace_id, workspace.clone(), cx)
+                            .await,
+                    );
+                }
+
+                // Children that couldn't be restored give their space to the others.
+                let (deserialized_children, flexes) =
+                    retain_members(deserialized_children, flexes, Option::is_some);
                 let mut current_active_pane = None;
                 let mut members = Vec::new();
                 let mut items = Vec::new();
-                for child in children {
-                    if let Some((new_member, active_pane, new_items)) = child
-                        .deserialize(project, workspace_id, workspace.clone(), cx)
-                        .await
-                    {
-                        members.push(new_member);
-                        items.extend(new_items);
-                        current_active_pane = current_active_pane.or(active_pane);
-                    }
+                for (new_member, active_pane, new_items) in
+                    deserialized_children.into_iter().flatten()
+                {
+                    members.push(new_member);
+                    items.extend(new_items);
+                    current_active_pane = current_active_pane.or(active_pane);
                 }
 
                 if members.is_empty() {
@@ -328,6 +472,7 @@ impl SerializedPane {
         let mut item_tasks = Vec::new();
         let mut active_item_index = None;
         let mut preview_item_index = None;
+        let mut pinned_items = Vec::new();
         for (index, item) in self.children.iter().enumerate() {
             let project = project.clone();
             item_tasks.push(pane.update(cx, |_, cx| {
@@ -346,10 +491,16 @@ impl SerializedPane {
             if item.preview {
                 preview_item_index = Some(index);
             }
+            pinned_items.push(item.pinned);
         }
 
         let mut items = Vec::new();
-        for item_handle in futures::future::join_all(item_tasks).await {
+        let mut pinned_count = 0;
+        for (item_handle, pinned) in futures::future::join_all(item_tasks)
+            .await
+            .into_iter()
+            .zip(pinned_items)
+        {
             let item_handle = item_handle.log_err();
             items.push(item_handle.clone());
 
@@ -357,9 +508,15 @@ impl SerializedPane {
                 pane.update(cx, |pane, cx| {
                     pane.add_item(item_handle.clone(), true, true, None, cx);
                 })?;
+                if pinned {
+                    pinned_count += 1;
+                }
             }
         }
 
+        // Pinned items are serialized first, so they are the first ones that got added.
+        pane.update(cx, |pane, cx| pane.set_pinned_count(pinned_count, cx))?;
+
         if let Some(active_item_index) = active_item_index {
             pane.update(cx, |pane, cx| {
                 pane.activate_item(active_item_index, false, false, cx);
@@ -388,6 +545,7 @@ pub struct SerializedItem {
     pub item_id: ItemId,
     pub active: bool,
     pub preview: bool,
+    pub pinned: bool,
 }
 
 impl SerializedItem {
@@ -397,6 +555,7 @@ impl SerializedItem {
             item_id,
             active,
             preview,
+            pinned: false,
         }
     }
 }
@@ -409,13 +568,14 @@ impl Default for SerializedItem {
             item_id: 100000,
             active: false,
             preview: false,
+            pinned: false,
         }
     }
 }
 
 impl StaticColumnCount for SerializedItem {
     fn column_count() -> usize {
-        4
+        5
     }
 }
 impl Bind for &SerializedItem {
@@ -423,7 +583,8 @@ impl Bind for &SerializedItem {
         let next_index = statement.bind(&self.kind, start_index)?;
         let next_index = statement.bind(&self.item_id, next_index)?;
         let next_index = statement.bind(&self.active, next_index)?;
-        statement.bind(&self.preview, next_index)
+        let next_index = statement.bind(&self.preview, next_index)?;
+        statement.bind(&self.pinned, next_index)
     }
 }
 
@@ -433,12 +594,14 @@ impl Column for SerializedItem {
         let (item_id, next_index) = ItemId::column(statement, next_index)?;
         let (active, next_index) = bool::column(statement, next_index)?;
         let (preview, next_index) = bool::column(statement, next_index)?;
+        let (pinned, next_index) = bool::column(statement, next_index)?;
         Ok((
             SerializedItem {
                 kind,
                 item_id,
                 active,
                 preview,
+                pinned,
             },
             next_index,
         ))
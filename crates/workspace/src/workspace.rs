@@ -1,3 +1,5 @@
+mod active_item_state;
+pub mod background_tasks;
 pub mod dock;
 pub mod item;
 mod modal_layer;
@@ -12,7 +14,9 @@ pub mod tasks;
 mod toolbar;
 mod workspace_settings;
 
+pub use active_item_state::ActiveItemState;
 use anyhow::{anyhow, Context as _, Result};
+use background_tasks::{BackgroundTaskEvent, BackgroundTasks, NewBackgroundTask};
 use call::{call_settings::CallSettings, ActiveCall};
 use client::{
     proto::{self, ErrorCode, PeerId},
@@ -29,16 +33,16 @@ use futures::{
 use gpui::{
     actions, canvas, impl_actions, point, relative, size, Action, AnyElement, AnyView, AnyWeakView,
     AppContext, AsyncAppContext, AsyncWindowContext, Bounds, DevicePixels, DragMoveEvent,
-    Entity as _, EntityId, EventEmitter, FocusHandle, FocusableView, Global, KeyContext, Keystroke,
-    ManagedView, Model, ModelContext, PathPromptOptions, Point, PromptLevel, Render, Size,
-    Subscription, Task, View, WeakView, WindowBounds, WindowHandle, WindowOptions,
+    Entity as _, EntityId, EventEmitter, ExternalPaths, FocusHandle, FocusableView, Global,
+    KeyContext, Keystroke, ManagedView, Model, ModelContext, PathPromptOptions, Point, PromptLevel,
+    Render, Size, Subscription, Task, View, WeakView, WindowBounds, WindowHandle, WindowOptions,
 };
 use item::{
-    FollowableItem, FollowableItemHandle, Item, ItemHandle, ItemSettings, PreviewTabsSettings,
-    ProjectItem,
+    FollowableItem, FollowableItemHandle, Item, ItemHandle, ItemSettings, PointOrAnchor,
+    PreviewTabsSettings, ProjectItem,
 };
 use itertools::Itertools;
-use language::{LanguageRegistry, Rope};
+use language::{Buffer, LanguageRegistry, Rope};
 use lazy_static::lazy_static;
 pub use modal_layer::*;
 use node_runtime::NodeRuntime;
@@ -69,6 +73,7 @@ use std::{
     collections::hash_map::DefaultHasher,
     env,
     hash::{Hash, Hasher},
+    iter,
     path::{Path, PathBuf},
     rc::Rc,
     sync::{atomic::AtomicUsize, Arc, Weak},
@@ -83,7 +88,7 @@ use ui::{
     ParentElement as _, Pixels, SharedString, Styled as _, ViewContext, VisualContext as _,
     WindowContext,
 };
-use util::{maybe, ResultExt};
+use util::{maybe, paths, timings, ResultExt};
 use uuid::Uuid;
 pub use workspace_settings::{
     AutosaveSetting, RestoreOnStartupBehaviour, TabBarSettings, WorkspaceSettings,
@@ -93,7 +98,13 @@ use crate::persistence::{
     model::{DockData, DockStructure, SerializedItem, SerializedPane, SerializedPaneGroup},
     SerializedAxis,
 };
-use crate::{notifications::NotificationId, persistence::model::LocalPathsOrder};
+use crate::{
+    notifications::NotificationId,
+    persistence::model::{LocalPathsOrder, SerializedLanguageOverrides, SerializedRecentPaths},
+};
+
+/// How many of the most recently opened files are remembered across sessions.
+const MAX_PERSISTED_RECENT_PATHS: usize = 50;
 
 lazy_static! {
     static ref ZED_WINDOW_SIZE: Option<Size<DevicePixels>> = env::var("ZED_WINDOW_SIZE")
@@ -124,6 +135,7 @@ actions!(
         ReloadActiveItem,
         ActivatePreviousPane,
         ActivateNextPane,
+        CloseActivePane,
         FollowNextCollaborator,
         NewTerminal,
         NewCenterTerminal,
@@ -135,6 +147,7 @@ actions!(
         ToggleRightDock,
         ToggleBottomDock,
         ToggleCenteredLayout,
+        ToggleReadOnlyWorktree,
         CloseAllDocks,
     ]
 );
@@ -208,6 +221,8 @@ impl_actions!(
 pub struct Toast {
     id: NotificationId,
     msg: Cow<'static, str>,
+    level: PromptLevel,
+    autohide: bool,
     on_click: Option<(Cow<'static, str>, Arc<dyn Fn(&mut WindowContext)>)>,
 }
 
@@ -216,6 +231,8 @@ impl Toast {
         Toast {
             id,
             msg: msg.into(),
+            level: PromptLevel::Info,
+            autohide: false,
             on_click: None,
         }
     }
@@ -228,12 +245,26 @@ impl Toast {
         self.on_click = Some((message.into(), Arc::new(on_click)));
         self
     }
+
+    /// Sets how severe the problem the toast reports is, which changes how it's rendered.
+    pub fn with_level(mut self, level: PromptLevel) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Dismisses the toast after [`notifications::TOAST_AUTOHIDE_DURATION`].
+    pub fn autohide(mut self) -> Self {
+        self.autohide = true;
+        self
+    }
 }
 
 impl PartialEq for Toast {
     fn eq(&self, other: &Self) -> bool {
         self.id == other.id
             && self.msg == other.msg
+            && self.level == other.level
+            && self.autohide == other.autohide
             && self.on_click.is_some() == other.on_click.is_some()
     }
 }
@@ -243,6 +274,8 @@ impl Clone for Toast {
         Toast {
             id: self.id.clone(),
             msg: self.msg.clone(),
+            level: self.level,
+            autohide: self.autohide,
             on_click: self.on_click.clone(),
         }
     }
@@ -256,6 +289,12 @@ pub struct OpenTerminal {
 #[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct WorkspaceId(i64);
 
+impl From<WorkspaceId> for i64 {
+    fn from(workspace_id: WorkspaceId) -> Self {
+        workspace_id.0
+    }
+}
+
 impl StaticColumnCount for WorkspaceId {}
 impl Bind for WorkspaceId {
     fn bind(&self, statement: &Statement, start_index: i32) -> Result<i32> {
@@ -574,6 +613,8 @@ pub struct Workspace {
     right_dock: View<Dock>,
     panes: Vec<View<Pane>>,
     panes_by_item: HashMap<EntityId, WeakView<Pane>>,
+    /// Items with an autosave in flight, and whether another one was requested meanwhile.
+    pending_autosaves: HashMap<EntityId, bool>,
     active_pane: View<Pane>,
     last_active_center_pane: Option<WeakView<Pane>>,
     last_active_view_id: Option<proto::ViewId>,
@@ -581,6 +622,9 @@ pub struct Workspace {
     modal_layer: View<ModalLayer>,
     titlebar_item: Option<AnyView>,
     notifications: Vec<(NotificationId, Box<dyn NotificationHandle>)>,
+    background_tasks: Model<BackgroundTasks>,
+    /// The local worktrees whose initial scan has been registered as a background task.
+    scanned_worktrees: HashSet<WorktreeId>,
     project: Model<Project>,
     follower_states: HashMap<View<Pane>, FollowerState>,
     last_leaders_by_pane: HashMap<WeakView<Pane>, PeerId>,
@@ -593,10 +637,12 @@ pub struct Workspace {
     _subscriptions: Vec<Subscription>,
     _apply_leader_updates: Task<Result<()>>,
     _observe_current_user: Task<Result<()>>,
+    _show_connection_status: Task<Result<()>>,
     _schedule_serialize: Option<Task<()>>,
     pane_history_timestamp: Arc<AtomicUsize>,
     bounds: Bounds<Pixels>,
     centered_layout: bool,
+    restored_recent_paths: Vec<PathBuf>,
     bounds_save_task_queued: Option<Task<()>>,
     on_prompt_for_new_path: Option<PromptForNewPath>,
     render_disconnected_overlay:
@@ -639,13 +685,27 @@ impl Workspace {
                     this.collaborator_left(*peer_id, cx);
                 }
 
-                project::Event::WorktreeRemoved(_) | project::Event::WorktreeAdded => {
+                project::Event::WorktreeRemoved(worktree_id) => {
+                    this.update_window_title(cx);
+                    this.serialize_workspace(cx);
+                    if this.project.read(cx).is_remote() {
+                        this.notify_sharing_stopped(Some(*worktree_id), cx);
+                    }
+                }
+
+                project::Event::WorktreeAdded => {
                     this.update_window_title(cx);
                     this.serialize_workspace(cx);
+                    this.track_worktree_scans(cx);
+                }
+
+                project::Event::LanguageOverridesChanged => {
+                    this.serialize_workspace(cx);
                 }
 
                 project::Event::DisconnectedFromHost => {
                     this.update_window_edited(cx);
+                    this.notify_sharing_stopped(None, cx);
                     let panes_to_unfollow: Vec<View<Pane>> =
                         this.follower_states.keys().map(|k| k.clone()).collect();
                     for pane in panes_to_unfollow {
@@ -728,6 +788,14 @@ impl Workspace {
             store.workspaces.insert(window_handle);
         });
 
+        let mut connection_status_for_toast = app_state.client.status();
+        let _show_connection_status = cx.spawn(|this, mut cx| async move {
+            while let Some(status) = connection_status_for_toast.recv().await {
+                this.update(&mut cx, |this, cx| this.show_connection_status(&status, cx))?;
+            }
+            anyhow::Ok(())
+        });
+
         let mut current_user = app_state.user_store.read(cx).watch_current_user();
         let mut connection_status = app_state.client.status();
         let _observe_current_user = cx.spawn(|this, mut cx| async move {
@@ -773,6 +841,7 @@ impl Workspace {
         });
 
         let modal_layer = cx.new_view(|_| ModalLayer::new());
+        let background_tasks = cx.new_model(|_| BackgroundTasks::default());
 
         let mut active_call = None;
         if let Some(call) = ActiveCall::try_global(cx) {
@@ -783,6 +852,7 @@ impl Workspace {
 
         let subscriptions = vec![
             cx.observe_window_activation(Self::on_window_activation_changed),
+            cx.subscribe(&background_tasks, Self::on_background_task_event),
             cx.observe_window_bounds(move |this, cx| {
                 if this.bounds_save_task_queued.is_some() {
                     return;
@@ -841,6 +911,7 @@ impl Workspace {
 
         cx.defer(|this, cx| {
             this.update_window_title(cx);
+            this.track_worktree_scans(cx);
         });
         Workspace {
             weak_self: weak_handle.clone(),
@@ -856,6 +927,8 @@ impl Workspace {
             modal_layer,
             titlebar_item: None,
             notifications: Default::default(),
+            background_tasks,
+            scanned_worktrees: Default::default(),
             left_dock,
             bottom_dock,
             right_dock,
@@ -868,6 +941,7 @@ impl Workspace {
             database_id: workspace_id,
             app_state,
             _observe_current_user,
+            _show_connection_status,
             _apply_leader_updates,
             _schedule_serialize: None,
             leader_updates_tx,
@@ -877,7 +951,9 @@ impl Workspace {
             // This data will be incorrect, but it will be overwritten by the time it needs to be used.
             bounds: Default::default(),
             centered_layout: false,
+            restored_recent_paths: Vec::new(),
             bounds_save_task_queued: None,
+            pending_autosaves: HashMap::default(),
             on_prompt_for_new_path: None,
             render_disconnected_overlay: None,
         }
@@ -903,6 +979,7 @@ impl Workspace {
             cx,
         );
 
+        let span = timings::start("open workspace");
         cx.spawn(|mut cx| async move {
             let serialized_workspace: Option<SerializedWorkspace> =
                 persistence::DB.workspace_for_roots(abs_paths.as_slice());
@@ -950,6 +1027,46 @@ impl Workspace {
                 }
             }
 
+            if let Some(read_only_paths) = serialized_workspace
+                .as_ref()
+                .and_then(|workspace| DB.read_only_paths(workspace.id).log_err().flatten())
+            {
+                project_handle.update(&mut cx, |project, cx| {
+                    let read_only_worktrees = project
+                        .worktrees()
+                        .filter(|worktree| {
+                            let abs_path = worktree.read(cx).abs_path();
+                            read_only_paths
+                                .paths()
+                                .iter()
+                                .any(|path| path.as_path() == abs_path.as_ref())
+                        })
+                        .map(|worktree| worktree.read(cx).id())
+                        .collect::<Vec<_>>();
+                    for worktree_id in read_only_worktrees {
+                        project.set_worktree_read_only(worktree_id, true, cx);
+                    }
+                })?;
+            }
+
+            if let Some(language_overrides) = serialized_workspace
+                .as_ref()
+                .and_then(|workspace| DB.language_overrides(workspace.id).log_err().flatten())
+            {
+                project_handle.update(&mut cx, |project, cx| {
+                    let language_overrides = language_overrides
+                        .overrides()
+                        .iter()
+                        .filter_map(|(abs_path, language_name)| {
+                            let project_path =
+                                project.project_path_for_absolute_path(abs_path, cx)?;
+                            Some((project_path, Arc::from(language_name.as_str())))
+                        })
+                        .collect::<Vec<_>>();
+                    project.restore_language_overrides(language_overrides, cx);
+                })?;
+            }
+
             let workspace_id = if let Some(serialized_workspace) = serialized_workspace.as_ref() {
                 serialized_workspace.id
             } else {
@@ -983,7 +1100,10 @@ impl Workspace {
                         });
 
                     if let Some((serialized_display, serialized_status)) = restorable_bounds {
-                        (Some(serialized_status.0), Some(serialized_display))
+                        let (window_bounds, display) = cx.update(|cx| {
+                            restorable_window_bounds(serialized_status.0, serialized_display, cx)
+                        })?;
+                        (Some(window_bounds), display)
                     } else {
                         (None, None)
                     }
@@ -996,6 +1116,11 @@ impl Workspace {
                     .as_ref()
                     .map(|w| w.centered_layout)
                     .unwrap_or(false);
+                let restored_recent_paths = serialized_workspace
+                    .as_ref()
+                    .map(|w| w.recent_paths.paths().to_vec())
+                    .unwrap_or_default();
+                let _window_span = span.child("create window");
                 cx.open_window(options, {
                     let app_state = app_state.clone();
                     let project_handle = project_handle.clone();
@@ -1004,6 +1129,7 @@ impl Workspace {
                             let mut workspace =
                                 Workspace::new(Some(workspace_id), project_handle, app_state, cx);
                             workspace.centered_layout = centered_layout;
+                            workspace.restored_recent_paths = restored_recent_paths;
                             workspace
                         })
                     }
@@ -1011,12 +1137,14 @@ impl Workspace {
             };
 
             notify_if_database_failed(window, &mut cx);
+            let items_span = span.child("open items");
             let opened_items = window
                 .update(&mut cx, |_workspace, cx| {
                     open_items(serialized_workspace, project_paths, app_state, cx)
                 })?
                 .await
                 .unwrap_or_default();
+            items_span.end();
 
             window
                 .update(&mut cx, |_, cx| cx.activate_window())
@@ -1073,6 +1201,69 @@ impl Workspace {
         &self.project
     }
 
+    /// The tasks that run in the background for this workspace, which the status bar shows.
+    pub fn background_tasks(&self) -> &Model<BackgroundTasks> {
+        &self.background_tasks
+    }
+
+    fn on_background_task_event(
+        &mut self,
+        _: Model<BackgroundTasks>,
+        event: &BackgroundTaskEvent,
+        cx: &mut ViewContext<Self>,
+    ) {
+        struct BackgroundTaskToast;
+
+        let BackgroundTaskEvent::Finished {
+            title,
+            error,
+            notify,
+        } = event;
+        if !notify {
+            return;
+        }
+        let id = NotificationId::unique::<BackgroundTaskToast>();
+        let toast = match error {
+            Some(error) => {
+                Toast::new(id, format!("{title} failed: {error}")).with_level(PromptLevel::Critical)
+            }
+            None => Toast::new(id, format!("{title} finished")).autohide(),
+        };
+        self.show_toast(toast, cx);
+    }
+
+    /// Registers the initial scan of each local worktree as a background task, until the scan
+    /// completes.
+    fn track_worktree_scans(&mut self, cx: &mut ViewContext<Self>) {
+        let worktrees = self
+            .project
+            .read(cx)
+            .visible_worktrees(cx)
+            .collect::<Vec<_>>();
+        for worktree in worktrees {
+            let worktree = worktree.read(cx);
+            let Some(local_worktree) = worktree.as_local() else {
+                continue;
+            };
+            if !self.scanned_worktrees.insert(worktree.id()) {
+                continue;
+            }
+            let scan_complete = local_worktree.scan_complete();
+            let title = format!("Scanning {}", worktree.root_name());
+            let task_id = self.background_tasks.update(cx, |tasks, cx| {
+                tasks.register(NewBackgroundTask::new(title), cx)
+            });
+            let background_tasks = self.background_tasks.downgrade();
+            cx.spawn(|_, mut cx| async move {
+                scan_complete.await;
+                background_tasks
+                    .update(&mut cx, |tasks, cx| tasks.finish(task_id, cx))
+                    .ok();
+            })
+            .detach();
+        }
+    }
+
     pub fn recent_navigation_history(
         &self,
         limit: Option<usize>,
@@ -1129,12 +1320,36 @@ impl Workspace {
             .collect()
     }
 
+    /// Absolute paths of the most recently opened files, most recent first, followed by
+    /// the ones restored from the previous session of this workspace.
+    pub fn recent_abs_paths(&self, limit: usize, cx: &AppContext) -> Vec<PathBuf> {
+        let mut seen = HashSet::default();
+        self.recent_navigation_history(Some(limit), cx)
+            .into_iter()
+            .filter_map(|(_, abs_path)| abs_path)
+            .chain(self.restored_recent_paths.iter().cloned())
+            .filter(|abs_path| seen.insert(abs_path.clone()))
+            .take(limit)
+            .collect()
+    }
+
+    /// Files that were recently opened in the previous session of this workspace.
+    pub fn restored_recent_paths(&self) -> &[PathBuf] {
+        &self.restored_recent_paths
+    }
+
+    #[cfg(any(test, feature = "test-support"))]
+    pub fn set_restored_recent_paths(&mut self, restored_recent_paths: Vec<PathBuf>) {
+        self.restored_recent_paths = restored_recent_paths;
+    }
+
     fn navigate_history(
         &mut self,
         pane: WeakView<Pane>,
         mode: NavigationMode,
         cx: &mut ViewContext<Workspace>,
     ) -> Task<Result<()>> {
+        let project = self.project.clone();
         let to_load = if let Some(pane) = pane.upgrade() {
             pane.update(cx, |pane, cx| {
                 pane.focus(cx);
@@ -1163,12 +1378,14 @@ impl Workspace {
                         }
                     }
                     // If the item is no longer present in this pane, then retrieve its
-                    // project path in order to reopen it.
-                    else {
-                        break pane
-                            .nav_history()
-                            .path_for_item(entry.item.id())
-                            .map(|(project_path, _)| (project_path, entry));
+                    // project path in order to reopen it. Entries whose file was deleted
+                    // since, or that can't be reopened, are skipped.
+                    else if let Some((project_path, _)) =
+                        pane.nav_history().path_for_item(entry.item.id())
+                    {
+                        if project.read(cx).entry_for_path(&project_path, cx).is_some() {
+                            break Some((project_path, entry));
+                        }
                     }
                 }
             })
@@ -1235,12 +1452,16 @@ impl Workspace {
         self.navigate_history(pane, NavigationMode::GoingForward, cx)
     }
 
+    /// Reopens the most recently closed item of the workspace, in the pane it was closed in,
+    /// or in the active pane if that pane was closed as well.
     pub fn reopen_closed_item(&mut self, cx: &mut ViewContext<Workspace>) -> Task<Result<()>> {
-        self.navigate_history(
-            self.active_pane().downgrade(),
-            NavigationMode::ReopeningClosedItem,
-            cx,
-        )
+        let pane = self
+            .panes
+            .iter()
+            .filter_map(|pane| Some((pane, pane.read(cx).nav_history().last_closed_timestamp()?)))
+            .max_by_key(|(_, timestamp)| *timestamp)
+            .map_or(self.active_pane(), |(pane, _)| pane);
+        self.navigate_history(pane.downgrade(), NavigationMode::ReopeningClosedItem, cx)
     }
 
     pub fn client(&self) -> &Arc<Client> {
@@ -1281,7 +1502,7 @@ impl Workspace {
                 .unwrap_or_else(|| Path::new("").into());
 
             let (tx, rx) = oneshot::channel();
-            let abs_path = cx.prompt_for_new_path(&start_abs_path);
+            let abs_path = cx.prompt_for_new_path(&start_abs_path, None);
             cx.spawn(|this, mut cx| async move {
                 let abs_path = abs_path.await?;
                 let project_path = abs_path.and_then(|abs_path| {
@@ -1535,6 +1756,8 @@ impl Workspace {
                     _ => {}
                 }
             }
+            // A file that fails to save doesn't keep the others from being saved.
+            let mut failures = Vec::new();
             for (pane, item) in dirty_items {
                 let (singleton, project_entry_ids) =
                     cx.update(|cx| (item.is_singleton(cx), item.project_entry_ids(cx)))?;
@@ -1542,7 +1765,7 @@ impl Workspace {
                     if let Some(ix) =
                         pane.update(&mut cx, |pane, _| pane.index_for_item(item.as_ref()))?
                     {
-                        if !Pane::save_item(
+                        match Pane::save_item(
                             project.clone(),
                             &pane,
                             ix,
@@ -1550,14 +1773,152 @@ impl Workspace {
                             save_intent,
                             &mut cx,
                         )
-                        .await?
+                        .await
                         {
-                            return Ok(false);
+                            Ok(true) => {}
+                            Ok(false) => return Ok(false),
+                            Err(error) => {
+                                let name = cx.update(|cx| {
+                                    item.project_path(cx).map_or("untitled".into(), |path| {
+                                        path.path.to_string_lossy().into_owned()
+                                    })
+                                })?;
+                                failures.push(format!("{name}: {error:#}"));
+                            }
                         }
                     }
                 }
             }
-            Ok(true)
+
+            if failures.is_empty() {
+                Ok(true)
+            } else {
+                workspace.update(&mut cx, |workspace, cx| {
+                    workspace.show_save_failures(failures, cx)
+                })?;
+                Ok(false)
+            }
+        })
+    }
+
+    fn show_save_failures(&mut self, failures: Vec<String>, cx: &mut ViewContext<Self>) {
+        struct SaveFailures;
+
+        let message = format!(
+            "Failed to save {} file{}:\n{}",
+            failures.len(),
+            if failures.len() == 1 { "" } else { "s" },
+            failures.join("\n")
+        );
+        self.show_notification(NotificationId::unique::<SaveFailures>(), cx, |cx| {
+            cx.new_view(|_| MessageNotification::new(message))
+        });
+    }
+
+    /// Lets the user know that the host stopped sharing the project, or the given worktree of it,
+    /// and offers to save the buffers that were edited and can't be saved to the host anymore.
+    fn notify_sharing_stopped(
+        &mut self,
+        worktree_id: Option<WorktreeId>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        struct SharingStopped;
+
+        let dirty_buffers = self
+            .project
+            .read(cx)
+            .opened_buffers()
+            .into_iter()
+            .filter(|buffer| {
+                let buffer = buffer.read(cx);
+                buffer.is_dirty()
+                    && worktree_id.map_or(true, |worktree_id| {
+                        project::File::from_dyn(buffer.file())
+                            .map_or(false, |file| file.worktree_id(cx) == worktree_id)
+                    })
+            })
+            .collect::<Vec<_>>();
+        let mut message = if worktree_id.is_some() {
+            "The host stopped sharing a folder of this project.".to_string()
+        } else {
+            "The host stopped sharing this project.".to_string()
+        };
+        if !dirty_buffers.is_empty() {
+            let (files, are) = if dirty_buffers.len() == 1 {
+                ("file", "is")
+            } else {
+                ("files", "are")
+            };
+            message.push_str(&format!(
+                " {} {files} with unsaved changes {are} read-only now.",
+                dirty_buffers.len(),
+            ));
+        }
+
+        let workspace = cx.view().downgrade();
+        self.show_notification(NotificationId::unique::<SharingStopped>(), cx, |cx| {
+            cx.new_view(|_| {
+                let notification = MessageNotification::new(message);
+                if dirty_buffers.is_empty() {
+                    return notification;
+                }
+                notification
+                    .with_click_message("Save Local Copies")
+                    .on_click(move |cx| {
+                        workspace
+                            .update(cx, |workspace, cx| {
+                                workspace
+                                    .save_local_copies(dirty_buffers.clone(), cx)
+                                    .detach_and_log_err(cx)
+                            })
+                            .ok();
+                    })
+            })
+        });
+    }
+
+    /// Saves the given buffers to paths the user picks, for buffers that can't be saved to where
+    /// they were opened from, such as the ones of a project whose host stopped sharing it.
+    pub fn save_local_copies(
+        &mut self,
+        buffers: Vec<Model<Buffer>>,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<Result<()>> {
+        let fs = self.app_state.fs.clone();
+        cx.spawn(|workspace, mut cx| async move {
+            // A copy that fails to save doesn't keep the others from being saved.
+            let mut failures = Vec::new();
+            for buffer in buffers {
+                let (text, line_ending, encoding, file_name) =
+                    buffer.update(&mut cx, |buffer, cx| {
+                        (
+                            buffer.as_rope().clone(),
+                            buffer.line_ending(),
+                            buffer.encoding(),
+                            buffer
+                                .file()
+                                .map(|file| file.file_name(cx).to_string_lossy().into_owned()),
+                        )
+                    })?;
+                let abs_path =
+                    cx.update(|cx| cx.prompt_for_new_path(&paths::HOME, file_name.as_deref()))?;
+                let Some(abs_path) = abs_path.await? else {
+                    continue;
+                };
+                if let Err(error) = fs
+                    .save_with_encoding(&abs_path, &text, line_ending, encoding)
+                    .await
+                {
+                    failures.push(format!("{}: {error:#}", abs_path.display()));
+                }
+            }
+
+            if !failures.is_empty() {
+                workspace.update(&mut cx, |workspace, cx| {
+                    workspace.show_save_failures(failures, cx)
+                })?;
+            }
+            Ok(())
         })
     }
 
@@ -1827,6 +2188,36 @@ impl Workspace {
         })
     }
 
+    /// Saves the active item like [`Workspace::save_active_item`], and tells the user when
+    /// saving to the host of a remote project fails.
+    fn save_active_item_and_notify_err(
+        &mut self,
+        save_intent: SaveIntent,
+        cx: &mut ViewContext<Self>,
+    ) {
+        struct RemoteSaveError;
+
+        let is_remote = self.project.read(cx).is_remote();
+        let save = self.save_active_item(save_intent, cx);
+        cx.spawn(|this, mut cx| async move {
+            let Err(err) = save.await else {
+                return Ok(());
+            };
+            if is_remote {
+                this.update(&mut cx, |this, cx| {
+                    let toast = Toast::new(
+                        NotificationId::unique::<RemoteSaveError>(),
+                        format!("Failed to save to the host: {err}"),
+                    )
+                    .with_level(PromptLevel::Critical);
+                    this.show_toast(toast, cx)
+                })?;
+            }
+            Err(err)
+        })
+        .detach_and_log_err(cx);
+    }
+
     pub fn close_inactive_items_and_panes(
         &mut self,
         action: &CloseInactiveTabsAndPanes,
@@ -1851,6 +2242,25 @@ impl Workspace {
         }
     }
 
+    /// Closes the active pane's items, pinned ones included, and then the pane itself, unless
+    /// it's the last one. The pane is kept if any of its items couldn't be closed.
+    pub fn close_active_pane(&mut self, _: &CloseActivePane, cx: &mut ViewContext<Self>) {
+        let pane = self.active_pane.clone();
+        let close_items = pane.update(cx, |pane, cx| {
+            pane.close_items(cx, SaveIntent::Close, |_| true)
+        });
+        cx.spawn(|workspace, mut cx| async move {
+            close_items.await?;
+            workspace.update(&mut cx, |workspace, cx| {
+                // Closing the last item already removed the pane, this removes empty panes.
+                if pane.read(cx).items_len() == 0 && workspace.panes.contains(&pane) {
+                    workspace.remove_pane(pane, cx);
+                }
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
     fn close_all_internal(
         &mut self,
         retain_active_pane: bool,
@@ -2181,6 +2591,72 @@ impl Workspace {
         })
     }
 
+    /// Opens the file at the given absolute path, or activates an item it's open in already, and
+    /// moves the item's cursor to the given position. Files outside of the project's worktrees get
+    /// opened in invisible worktrees of their own.
+    pub fn open_path_at(
+        &mut self,
+        abs_path: PathBuf,
+        position: PointOrAnchor,
+        focus: bool,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<Result<Box<dyn ItemHandle>>> {
+        let fs = self.app_state.fs.clone();
+        let project = self.project.clone();
+        cx.spawn(|this, mut cx| async move {
+            match fs.metadata(&abs_path).await? {
+                Some(metadata) if metadata.is_dir => {
+                    return Err(anyhow!("{abs_path:?} is a directory"));
+                }
+                Some(_) => {}
+                None => return Err(anyhow!("{abs_path:?} does not exist")),
+            }
+
+            let (_, project_path) = this
+                .update(&mut cx, |_, cx| {
+                    Workspace::project_path_for_path(project, &abs_path, false, cx)
+                })?
+                .await?;
+            let existing_item = this.update(&mut cx, |this, cx| {
+                this.activate_item_for_path(&project_path, focus, cx)
+            })?;
+            let item = match existing_item {
+                Some(item) => item,
+                None => {
+                    this.update(&mut cx, |this, cx| {
+                        this.open_path(project_path, None, focus, cx)
+                    })?
+                    .await?
+                }
+            };
+            this.update(&mut cx, |_, cx| item.move_cursor_to(position, cx))?;
+            Ok(item)
+        })
+    }
+
+    /// Activates an item of the file at the given path, preferring the ones in the active pane.
+    fn activate_item_for_path(
+        &mut self,
+        project_path: &ProjectPath,
+        focus: bool,
+        cx: &mut WindowContext,
+    ) -> Option<Box<dyn ItemHandle>> {
+        let panes = iter::once(&self.active_pane)
+            .chain(self.panes.iter().filter(|pane| **pane != self.active_pane));
+        for pane in panes {
+            let pane_ref = pane.read(cx);
+            let Some(ix) = pane_ref.items().position(|item| {
+                item.is_singleton(cx) && item.project_path(cx).as_ref() == Some(project_path)
+            }) else {
+                continue;
+            };
+            let item = pane_ref.item_for_index(ix)?.boxed_clone();
+            pane.update(cx, |pane, cx| pane.activate_item(ix, true, focus, cx));
+            return Some(item);
+        }
+        None
+    }
+
     pub fn split_abs_path(
         &mut self,
         abs_path: PathBuf,
@@ -2287,7 +2763,26 @@ impl Workspace {
         else {
             return Task::ready(Err(anyhow!("cannot open file {:?}", path.path)));
         };
-        open_project_item
+        if !project.read(cx).is_remote() {
+            return open_project_item;
+        }
+
+        // Files of remote projects get transferred from the host, which can take a while.
+        let title = format!("Opening {}", path.path.display());
+        let task_id = self.background_tasks.update(cx, |tasks, cx| {
+            tasks.register(NewBackgroundTask::new(title), cx)
+        });
+        let background_tasks = self.background_tasks.downgrade();
+        cx.spawn(|mut cx| async move {
+            let result = open_project_item.await;
+            background_tasks
+                .update(&mut cx, |tasks, cx| match &result {
+                    Ok(_) => tasks.finish(task_id, cx),
+                    Err(error) => tasks.fail(task_id, error, cx),
+                })
+                .ok();
+            result
+        })
     }
 
     pub fn open_project_item<T>(
@@ -2530,6 +3025,18 @@ impl Workspace {
             self.last_active_center_pane = Some(pane.downgrade());
         }
 
+        // Unless configured otherwise, focusing another center pane unzooms the zoomed one.
+        // Otherwise the zoom moves to the newly focused pane.
+        if !WorkspaceSettings::get_global(cx).unzoom_on_focus_change
+            && self.panes.contains(&pane)
+            && self
+                .panes
+                .iter()
+                .any(|other| other != &pane && other.read(cx).is_zoomed())
+        {
+            pane.update(cx, |pane, cx| pane.set_zoomed(true, cx));
+        }
+
         self.dismiss_zoomed_items_to_reveal(None, cx);
         if pane.read(cx).is_zoomed() {
             self.zoomed = Some(pane.downgrade().into());
@@ -2621,6 +3128,7 @@ impl Workspace {
         split_direction: SplitDirection,
         cx: &mut ViewContext<Self>,
     ) -> View<Pane> {
+        self.unzoom_center_pane(cx);
         let new_pane = self.add_pane(cx);
         self.center
             .split(&pane_to_split, &new_pane, split_direction)
@@ -2637,6 +3145,7 @@ impl Workspace {
     ) -> Option<View<Pane>> {
         let item = pane.read(cx).active_item()?;
         let maybe_pane_handle = if let Some(clone) = item.clone_on_split(self.database_id(), cx) {
+            self.unzoom_center_pane(cx);
             let new_pane = self.add_pane(cx);
             new_pane.update(cx, |pane, cx| pane.add_item(clone, true, true, None, cx));
             self.center.split(&pane, &new_pane, direction).unwrap();
@@ -2726,9 +3235,35 @@ impl Workspace {
         });
     }
 
+    /// Unzooms the zoomed center pane, if any, so that changes to the center's layout show.
+    fn unzoom_center_pane(&mut self, cx: &mut ViewContext<Self>) {
+        for pane in &self.panes {
+            if pane.read(cx).is_zoomed() {
+                pane.update(cx, |pane, cx| pane.set_zoomed(false, cx));
+            }
+        }
+        if self.zoomed.is_some() && self.zoomed_position.is_none() {
+            self.zoomed = None;
+            cx.emit(Event::ZoomChanged);
+        }
+    }
+
     fn remove_pane(&mut self, pane: View<Pane>, cx: &mut ViewContext<Self>) {
         if self.center.remove(&pane).unwrap() {
+            if self.zoomed == Some(pane.downgrade().into()) {
+                self.zoomed = None;
+                cx.emit(Event::ZoomChanged);
+            }
             self.force_remove_pane(&pane, cx);
+            // The pane that got focused instead takes over the items closed in this one.
+            let closed_items = pane.read(cx).nav_history().clone();
+            if let Some(focused_pane) = self.panes.last() {
+                focused_pane.update(cx, |focused_pane, _| {
+                    focused_pane
+                        .nav_history_mut()
+                        .merge_closed_items(&closed_items)
+                });
+            }
             self.unfollow(&pane, cx);
             self.last_leaders_by_pane.remove(&pane.downgrade());
             for removed_item in pane.read(cx).items() {
@@ -2763,16 +3298,46 @@ impl Workspace {
     }
 
     fn collaborator_left(&mut self, peer_id: PeerId, cx: &mut ViewContext<Self>) {
+        let login = self.active_call().and_then(|call| {
+            let room = call.read(cx).room()?.read(cx);
+            let participant = room.remote_participant_for_peer_id(peer_id)?;
+            Some(participant.user.github_login.clone())
+        });
+        self.leader_left(peer_id, login, cx);
+    }
+
+    /// Stops following a collaborator who left the project or the call, telling the user about
+    /// it.
+    fn leader_left(
+        &mut self,
+        leader_id: PeerId,
+        login: Option<String>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        struct LeaderLeft;
+
+        let mut was_following = false;
         self.follower_states.retain(|_, state| {
-            if state.leader_id == peer_id {
+            if state.leader_id == leader_id {
                 for item in state.items_by_leader_view_id.values() {
                     item.set_leader_peer_id(None, cx);
                 }
+                was_following = true;
                 false
             } else {
                 true
             }
         });
+        if was_following {
+            let message = match login {
+                Some(login) => format!("Stopped following {login}, who left"),
+                None => "Stopped following a collaborator who left".to_string(),
+            };
+            self.show_toast(
+                Toast::new(NotificationId::unique::<LeaderLeft>(), message),
+                cx,
+            );
+        }
         cx.notify();
     }
 
@@ -3005,6 +3570,26 @@ impl Workspace {
         cx.set_window_title(&title);
     }
 
+    /// Tells the user when the connection to the collaboration server is lost, and lets them
+    /// retry right away instead of waiting for the next reconnection attempt.
+    fn show_connection_status(&mut self, status: &Status, cx: &mut ViewContext<Self>) {
+        struct ConnectionStatusToast;
+
+        let id = NotificationId::unique::<ConnectionStatusToast>();
+        match status {
+            Status::ConnectionLost => {
+                let client = self.app_state.client.clone();
+                let toast = Toast::new(id, "Collaboration server disconnected — retrying")
+                    .with_level(PromptLevel::Warning)
+                    .on_click("Retry Now", move |cx| client.reconnect(&cx.to_async()));
+                self.show_toast(toast, cx);
+            }
+            // The toast stays up while reconnecting, unless the user dismissed it.
+            Status::Reconnecting | Status::ReconnectionError { .. } | Status::Reauthenticating => {}
+            _ => self.dismiss_toast(&id, cx),
+        }
+    }
+
     fn update_window_edited(&mut self, cx: &mut WindowContext) {
         let is_edited = !self.project.read(cx).is_disconnected()
             && self
@@ -3486,6 +4071,7 @@ impl Workspace {
                     .detach();
             }
         } else {
+            let mut items_to_autosave = Vec::new();
             for pane in &self.panes {
                 pane.update(cx, |pane, cx| {
                     if let Some(item) = pane.active_item() {
@@ -3495,16 +4081,57 @@ impl Workspace {
                         WorkspaceSettings::get_global(cx).autosave,
                         AutosaveSetting::OnWindowChange | AutosaveSetting::OnFocusChange
                     ) {
-                        for item in pane.items() {
-                            Pane::autosave_item(item.as_ref(), self.project.clone(), cx)
-                                .detach_and_log_err(cx);
-                        }
+                        items_to_autosave.extend(pane.items().map(|item| item.boxed_clone()));
                     }
                 });
             }
+            for item in items_to_autosave {
+                self.autosave_item(item.as_ref(), cx).detach_and_log_err(cx);
+            }
         }
     }
 
+    /// Autosaves the given item, unless an autosave of it is already in flight. In that case
+    /// the item is autosaved once more after it finishes, so that a burst of focus changes
+    /// doesn't queue up a write for each of them.
+    pub(crate) fn autosave_item(
+        &mut self,
+        item: &dyn ItemHandle,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<Result<()>> {
+        let item_id = item.item_id();
+        if let Some(requested_again) = self.pending_autosaves.get_mut(&item_id) {
+            *requested_again = true;
+            return Task::ready(Ok(()));
+        }
+
+        self.pending_autosaves.insert(item_id, false);
+        let mut save = Pane::autosave_item(item, self.project.clone(), cx);
+        let item = item.boxed_clone();
+        cx.spawn(|this, mut cx| async move {
+            loop {
+                let result = save.await;
+                let save_again = this.update(&mut cx, |this, _| {
+                    let save_again =
+                        result.is_ok() && this.pending_autosaves.get(&item_id) == Some(&true);
+                    if save_again {
+                        this.pending_autosaves.insert(item_id, false);
+                    } else {
+                        this.pending_autosaves.remove(&item_id);
+                    }
+                    save_again
+                })?;
+                result?;
+                if !save_again {
+                    return Ok(());
+                }
+                save = this.update(&mut cx, |this, cx| {
+                    Pane::autosave_item(item.as_ref(), this.project.clone(), cx)
+                })?;
+            }
+        })
+    }
+
     fn active_call(&self) -> Option<&Model<ActiveCall>> {
         self.active_call.as_ref().map(|(call, _)| call)
     }
@@ -3520,6 +4147,12 @@ impl Workspace {
             | call::room::Event::RemoteVideoTracksChanged { participant_id } => {
                 self.leader_updated(*participant_id, cx);
             }
+            call::room::Event::ParticipantLeft {
+                participant_id,
+                user,
+            } => {
+                self.leader_left(*participant_id, Some(user.github_login.clone()), cx);
+            }
             _ => {}
         }
     }
@@ -3600,6 +4233,7 @@ impl Workspace {
                                 item_id: item_handle.item_id().as_u64(),
                                 active: Some(item_handle.item_id()) == active_item_id,
                                 preview: pane.is_active_preview_item(item_handle.item_id()),
+                                pinned: pane.is_item_pinned(item_handle.item_id()),
                             })
                         })
                         .collect::<Vec<_>>(),
@@ -3730,14 +4364,37 @@ impl Workspace {
                 display: Default::default(),
                 docks,
                 centered_layout: self.centered_layout,
+                recent_paths: SerializedRecentPaths::new(
+                    self.recent_abs_paths(MAX_PERSISTED_RECENT_PATHS, cx),
+                ),
             };
-            return cx.spawn(|_| persistence::DB.save_workspace(serialized_workspace));
-        }
-        Task::ready(())
-    }
-
-    pub(crate) fn load_workspace(
-        serialized_workspace: SerializedWorkspace,
+            let center_pane_zoomed = self.panes.iter().any(|pane| pane.read(cx).is_zoomed());
+            let project = self.project.read(cx);
+            let language_overrides =
+                project
+                    .language_overrides()
+                    .iter()
+                    .filter_map(|(project_path, language_name)| {
+                        Some((project.absolute_path(project_path, cx)?, language_name))
+                    });
+            let language_overrides = SerializedLanguageOverrides::new(language_overrides);
+            return cx.spawn(|_| async move {
+                persistence::DB.save_workspace(serialized_workspace).await;
+                persistence::DB
+                    .set_center_pane_zoomed(database_id, center_pane_zoomed)
+                    .await
+                    .log_err();
+                persistence::DB
+                    .set_language_overrides(database_id, language_overrides)
+                    .await
+                    .log_err();
+            });
+        }
+        Task::ready(())
+    }
+
+    pub(crate) fn load_workspace(
+        serialized_workspace: SerializedWorkspace,
         paths_to_open: Vec<Option<ProjectPath>>,
         cx: &mut ViewContext<Workspace>,
     ) -> Task<Result<Vec<Option<Box<dyn ItemHandle>>>>> {
@@ -3796,6 +4453,19 @@ impl Workspace {
                     } else {
                         workspace.active_pane = workspace.center.first_pane().clone();
                     }
+
+                    let center_pane_zoomed = persistence::DB
+                        .center_pane_zoomed(serialized_workspace.id)
+                        .log_err()
+                        .flatten()
+                        .unwrap_or(false);
+                    if center_pane_zoomed {
+                        let active_pane = workspace.active_pane.clone();
+                        active_pane.update(cx, |pane, cx| pane.set_zoomed(true, cx));
+                        workspace.zoomed = Some(active_pane.downgrade().into());
+                        workspace.zoomed_position = None;
+                        cx.emit(Event::ZoomChanged);
+                    }
                 }
 
                 let docks = serialized_workspace.docks;
@@ -3840,19 +4510,16 @@ impl Workspace {
                 workspace.unfollow(&pane, cx);
             }))
             .on_action(cx.listener(|workspace, action: &Save, cx| {
-                workspace
-                    .save_active_item(action.save_intent.unwrap_or(SaveIntent::Save), cx)
-                    .detach_and_log_err(cx);
+                workspace.save_active_item_and_notify_err(
+                    action.save_intent.unwrap_or(SaveIntent::Save),
+                    cx,
+                );
             }))
             .on_action(cx.listener(|workspace, _: &SaveWithoutFormat, cx| {
-                workspace
-                    .save_active_item(SaveIntent::SaveWithoutFormat, cx)
-                    .detach_and_log_err(cx);
+                workspace.save_active_item_and_notify_err(SaveIntent::SaveWithoutFormat, cx);
             }))
             .on_action(cx.listener(|workspace, _: &SaveAs, cx| {
-                workspace
-                    .save_active_item(SaveIntent::SaveAs, cx)
-                    .detach_and_log_err(cx);
+                workspace.save_active_item_and_notify_err(SaveIntent::SaveAs, cx);
             }))
             .on_action(cx.listener(|workspace, _: &ActivatePreviousPane, cx| {
                 workspace.activate_previous_pane(cx)
@@ -3895,6 +4562,7 @@ impl Workspace {
                 }),
             )
             .on_action(cx.listener(Workspace::toggle_centered_layout))
+            .on_action(cx.listener(Workspace::toggle_read_only_worktree))
     }
 
     #[cfg(any(test, feature = "test-support"))]
@@ -3939,6 +4607,7 @@ impl Workspace {
         let mut div = div
             .on_action(cx.listener(Self::close_inactive_items_and_panes))
             .on_action(cx.listener(Self::close_all_items_and_panes))
+            .on_action(cx.listener(Self::close_active_pane))
             .on_action(cx.listener(Self::add_folder_to_project))
             .on_action(cx.listener(Self::save_all))
             .on_action(cx.listener(Self::open));
@@ -3974,6 +4643,44 @@ impl Workspace {
         cx.notify();
     }
 
+    /// Toggles read-only mode for the worktree containing the active item.
+    pub fn toggle_read_only_worktree(
+        &mut self,
+        _: &ToggleReadOnlyWorktree,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let Some(worktree_id) = self
+            .active_item(cx)
+            .and_then(|item| item.project_path(cx))
+            .map(|project_path| project_path.worktree_id)
+        else {
+            return;
+        };
+        self.project.update(cx, |project, cx| {
+            let read_only = !project
+                .worktree_for_id(worktree_id, cx)
+                .map_or(false, |worktree| worktree.read(cx).is_marked_read_only());
+            project.set_worktree_read_only(worktree_id, read_only, cx);
+        });
+        if let Some(database_id) = self.database_id() {
+            let read_only_paths = LocalPaths::new(
+                self.project
+                    .read(cx)
+                    .worktrees()
+                    .filter_map(|worktree| {
+                        let worktree = worktree.read(cx);
+                        (worktree.is_local() && worktree.is_marked_read_only())
+                            .then(|| worktree.abs_path())
+                    })
+                    .collect::<Vec<_>>(),
+            );
+            cx.background_executor()
+                .spawn(DB.set_read_only_paths(database_id, read_only_paths))
+                .detach_and_log_err(cx);
+        }
+        cx.notify();
+    }
+
     fn adjust_padding(padding: Option<f32>) -> f32 {
         padding
             .unwrap_or(Self::DEFAULT_PADDING)
@@ -3990,6 +4697,62 @@ fn window_bounds_env_override() -> Option<Bounds<DevicePixels>> {
         })
 }
 
+/// Fits the bounds a window is restored with onto the displays that are connected now, as
+/// the display it was on may have been unplugged or had its resolution changed since. Windows
+/// go back to the display they were on when it's still connected, and to the primary display
+/// otherwise.
+fn restorable_window_bounds(
+    window_bounds: WindowBounds,
+    display: Uuid,
+    cx: &AppContext,
+) -> (WindowBounds, Option<Uuid>) {
+    let Some(target_display) = cx
+        .displays()
+        .into_iter()
+        .find(|candidate| candidate.uuid().ok() == Some(display))
+        .or_else(|| cx.primary_display())
+    else {
+        return (window_bounds, Some(display));
+    };
+
+    let display_size = target_display.bounds().size;
+    let window_bounds = match window_bounds {
+        WindowBounds::Windowed(bounds) => {
+            WindowBounds::Windowed(fit_window_bounds(bounds, display_size))
+        }
+        WindowBounds::Maximized(bounds) => {
+            WindowBounds::Maximized(fit_window_bounds(bounds, display_size))
+        }
+        WindowBounds::Fullscreen(bounds) => {
+            WindowBounds::Fullscreen(fit_window_bounds(bounds, display_size))
+        }
+    };
+    (window_bounds, target_display.uuid().ok())
+}
+
+/// Moves and shrinks window bounds, which are relative to the display the window is on, so that
+/// the window fits on a display of the given size.
+fn fit_window_bounds(
+    bounds: Bounds<DevicePixels>,
+    display_size: Size<DevicePixels>,
+) -> Bounds<DevicePixels> {
+    let size = size(
+        bounds.size.width.min(display_size.width),
+        bounds.size.height.min(display_size.height),
+    );
+    let origin = point(
+        bounds
+            .origin
+            .x
+            .clamp(DevicePixels::from(0), display_size.width - size.width),
+        bounds
+            .origin
+            .y
+            .clamp(DevicePixels::from(0), display_size.height - size.height),
+    );
+    Bounds { origin, size }
+}
+
 fn open_items(
     serialized_workspace: Option<SerializedWorkspace>,
     mut project_paths_to_open: Vec<(PathBuf, Option<ProjectPath>)>,
@@ -4165,6 +4928,14 @@ impl Render for Workspace {
             .items_start()
             .text_color(colors.text)
             .bg(colors.background)
+            // Paths dropped outside of the panes, like on the title bar or a dock, open in the
+            // active pane.
+            .on_drop(cx.listener(|workspace, paths: &ExternalPaths, cx| {
+                workspace.active_pane.update(cx, |pane, cx| {
+                    pane.drag_split_direction = None;
+                    pane.handle_external_paths_drop(paths, None, cx)
+                })
+            }))
             .children(self.titlebar_item.clone())
             .child(
                 div()
@@ -5241,6 +6012,32 @@ mod tests {
         item3.update(cx, |item, _| assert_eq!(item.tab_detail.get(), Some(3)));
     }
 
+    #[gpui::test]
+    fn test_fit_window_bounds() {
+        let bounds = |x: i32, y: i32, width: i32, height: i32| -> Bounds<DevicePixels> {
+            Bounds {
+                origin: point(x.into(), y.into()),
+                size: size(width.into(), height.into()),
+            }
+        };
+        let display_size: Size<DevicePixels> = size(1440.into(), 900.into());
+
+        // Windows that fit are left alone.
+        assert_eq!(
+            fit_window_bounds(bounds(100, 50, 800, 600), display_size),
+            bounds(100, 50, 800, 600)
+        );
+        // Windows that were on a larger display, or further to its right, are moved back.
+        assert_eq!(
+            fit_window_bounds(bounds(2000, 1200, 800, 600), display_size),
+            bounds(640, 300, 800, 600)
+        );
+        assert_eq!(
+            fit_window_bounds(bounds(-300, 100, 2560, 1440), display_size),
+            bounds(0, 0, 1440, 900)
+        );
+    }
+
     #[gpui::test]
     async fn test_tracking_active_path(cx: &mut TestAppContext) {
         init_test(cx);
@@ -5374,6 +6171,52 @@ mod tests {
         assert!(!task.await.unwrap());
     }
 
+    #[gpui::test]
+    async fn test_save_all_with_failures(cx: &mut TestAppContext) {
+        init_test(cx);
+
+        let fs = FakeFs::new(cx.executor());
+        let project = Project::test(fs, None, cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project, cx));
+
+        let item1 = cx.new_view(|cx| {
+            TestItem::new(cx)
+                .with_dirty(true)
+                .with_save_error("permission denied")
+                .with_project_items(&[TestProjectItem::new(1, "1.txt", cx)])
+        });
+        let item2 = cx.new_view(|cx| {
+            TestItem::new(cx)
+                .with_dirty(true)
+                .with_project_items(&[TestProjectItem::new(2, "2.txt", cx)])
+        });
+        workspace.update(cx, |workspace, cx| {
+            workspace.add_item_to_active_pane(Box::new(item1.clone()), None, cx);
+            let pane =
+                workspace.split_pane(workspace.active_pane().clone(), SplitDirection::Right, cx);
+            pane.update(cx, |pane, cx| {
+                pane.add_item(Box::new(item2.clone()), true, true, None, cx)
+            });
+        });
+
+        // The other files still get saved, and the failures are reported.
+        let saved = workspace.update(cx, |workspace, cx| {
+            workspace.save_all_internal(SaveIntent::SaveAll, cx)
+        });
+        assert!(!saved.await.unwrap());
+        item1.update(cx, |item, _| {
+            assert_eq!(item.save_count, 0);
+            assert!(item.is_dirty);
+        });
+        item2.update(cx, |item, _| {
+            assert_eq!(item.save_count, 1);
+            assert!(!item.is_dirty);
+        });
+        workspace.update(cx, |workspace, _| {
+            assert_eq!(workspace.notification_ids().len(), 1);
+        });
+    }
+
     #[gpui::test]
     async fn test_close_pane_items(cx: &mut TestAppContext) {
         init_test(cx);
@@ -5480,6 +6323,138 @@ mod tests {
         });
     }
 
+    #[gpui::test]
+    async fn test_keeping_both_versions_of_a_conflicted_item(cx: &mut TestAppContext) {
+        init_test(cx);
+
+        let fs = FakeFs::new(cx.executor());
+        let project = Project::test(fs, None, cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project, cx));
+
+        let item = cx.new_view(|cx| {
+            TestItem::new(cx)
+                .with_dirty(true)
+                .with_conflict(true)
+                .with_project_items(&[TestProjectItem::new(1, "1.txt", cx)])
+        });
+        let pane = workspace.update(cx, |workspace, cx| {
+            workspace.add_item_to_active_pane(Box::new(item.clone()), None, cx);
+            workspace.active_pane().clone()
+        });
+
+        let close_items = pane.update(cx, |pane, cx| {
+            pane.close_items(cx, SaveIntent::Close, move |_| true)
+        });
+        cx.executor().run_until_parked();
+
+        // Choose "Keep Both", which saves the item to a new path instead of
+        // overwriting the file that was changed on disk.
+        assert!(cx.has_pending_prompt());
+        cx.simulate_prompt_answer(2);
+        cx.executor().run_until_parked();
+        cx.simulate_new_path_selection(|_| Some(Default::default()));
+        close_items.await.unwrap();
+
+        pane.update(cx, |pane, cx| {
+            assert_eq!(item.read(cx).save_count, 0);
+            assert_eq!(item.read(cx).save_as_count, 1);
+            assert_eq!(item.read(cx).reload_count, 0);
+            assert_eq!(pane.items_len(), 0);
+        });
+    }
+
+    #[gpui::test]
+    async fn test_conflicted_items_that_cannot_be_saved_as(cx: &mut TestAppContext) {
+        init_test(cx);
+
+        let fs = FakeFs::new(cx.executor());
+        let project = Project::test(fs, None, cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project, cx));
+
+        let item = cx.new_view(|cx| {
+            TestItem::new(cx)
+                .with_dirty(true)
+                .with_conflict(true)
+                .with_singleton(false)
+                .with_project_items(&[TestProjectItem::new(1, "1.txt", cx)])
+        });
+        let pane = workspace.update(cx, |workspace, cx| {
+            workspace.add_item_to_active_pane(Box::new(item.clone()), None, cx);
+            workspace.active_pane().clone()
+        });
+
+        let close_items = pane.update(cx, |pane, cx| {
+            pane.close_items(cx, SaveIntent::Close, move |_| true)
+        });
+        cx.executor().run_until_parked();
+
+        // Without "Keep Both", the third answer is "Cancel".
+        assert!(cx.has_pending_prompt());
+        cx.simulate_prompt_answer(2);
+        cx.executor().run_until_parked();
+        assert!(!cx.has_pending_prompt());
+        close_items.await.unwrap();
+
+        pane.update(cx, |pane, cx| {
+            assert_eq!(item.read(cx).save_count, 0);
+            assert_eq!(item.read(cx).save_as_count, 0);
+            assert_eq!(item.read(cx).reload_count, 0);
+            assert_eq!(pane.items_len(), 1);
+        });
+    }
+
+    #[gpui::test]
+    async fn test_skipping_conflicted_items_when_saving_all(cx: &mut TestAppContext) {
+        init_test(cx);
+
+        let fs = FakeFs::new(cx.executor());
+        let project = Project::test(fs, None, cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project, cx));
+
+        let conflicted_item = cx.new_view(|cx| {
+            TestItem::new(cx)
+                .with_dirty(true)
+                .with_conflict(true)
+                .with_project_items(&[TestProjectItem::new(1, "1.txt", cx)])
+        });
+        let dirty_item = cx.new_view(|cx| {
+            TestItem::new(cx)
+                .with_dirty(true)
+                .with_project_items(&[TestProjectItem::new(2, "2.txt", cx)])
+        });
+        let pane = workspace.update(cx, |workspace, cx| {
+            workspace.add_item_to_active_pane(Box::new(conflicted_item.clone()), None, cx);
+            workspace.add_item_to_active_pane(Box::new(dirty_item.clone()), None, cx);
+            workspace.active_pane().clone()
+        });
+
+        let close_items = pane.update(cx, |pane, cx| {
+            pane.close_items(cx, SaveIntent::Close, move |_| true)
+        });
+        cx.executor().run_until_parked();
+
+        // Choose "Save all", then "Skip" for the file that was changed on disk.
+        assert!(cx.has_pending_prompt());
+        cx.simulate_prompt_answer(0);
+        cx.executor().run_until_parked();
+        assert!(cx.has_pending_prompt());
+        cx.simulate_prompt_answer(3);
+        cx.executor().run_until_parked();
+        close_items.await.unwrap();
+
+        pane.update(cx, |pane, cx| {
+            assert_eq!(conflicted_item.read(cx).save_count, 0);
+            assert_eq!(conflicted_item.read(cx).reload_count, 0);
+            assert!(conflicted_item.read(cx).is_dirty);
+            assert_eq!(dirty_item.read(cx).save_count, 1);
+            assert_eq!(pane.items_len(), 1);
+            assert_eq!(
+                pane.active_item().unwrap().item_id(),
+                conflicted_item.item_id()
+            );
+        });
+    }
+
     #[gpui::test]
     async fn test_prompting_to_save_only_on_last_item_for_entry(cx: &mut TestAppContext) {
         init_test(cx);
@@ -5669,6 +6644,30 @@ mod tests {
         cx.executor().advance_clock(Duration::from_millis(250));
         item.update(cx, |item, _| assert_eq!(item.save_count, 4));
 
+        // Autosaves requested while one is in flight are coalesced into a single one.
+        item.update(cx, |item, cx| {
+            SettingsStore::update_global(cx, |settings, cx| {
+                settings.update_user_settings::<WorkspaceSettings>(cx, |settings| {
+                    settings.autosave = Some(AutosaveSetting::OnFocusChange);
+                })
+            });
+            item.is_dirty = true;
+        });
+        cx.deactivate_window();
+        item.update(cx, |item, _| {
+            assert_eq!(item.save_count, 5);
+            item.is_dirty = true;
+        });
+        cx.update(|cx| cx.activate_window());
+        cx.deactivate_window();
+        cx.update(|cx| cx.activate_window());
+        cx.deactivate_window();
+        item.update(cx, |item, _| assert_eq!(item.save_count, 5));
+        cx.executor().run_until_parked();
+        item.update(cx, |item, _| assert_eq!(item.save_count, 6));
+        cx.update(|cx| cx.activate_window());
+        cx.executor().run_until_parked();
+
         // Autosave on focus change, ensuring closing the tab counts as such.
         item.update(cx, |item, cx| {
             SettingsStore::update_global(cx, |settings, cx| {
@@ -5685,7 +6684,7 @@ mod tests {
         .await
         .unwrap();
         assert!(!cx.has_pending_prompt());
-        item.update(cx, |item, _| assert_eq!(item.save_count, 5));
+        item.update(cx, |item, _| assert_eq!(item.save_count, 7));
 
         // Add the item again, ensuring autosave is prevented if the underlying file has been deleted.
         workspace.update(cx, |workspace, cx| {
@@ -5699,7 +6698,7 @@ mod tests {
             cx.blur();
         });
         cx.run_until_parked();
-        item.update(cx, |item, _| assert_eq!(item.save_count, 5));
+        item.update(cx, |item, _| assert_eq!(item.save_count, 7));
 
         // Ensure autosave is prevented for deleted files also when closing the buffer.
         let _close_items = pane.update(cx, |pane, cx| {
@@ -5707,7 +6706,75 @@ mod tests {
         });
         cx.run_until_parked();
         assert!(cx.has_pending_prompt());
-        item.update(cx, |item, _| assert_eq!(item.save_count, 5));
+        item.update(cx, |item, _| assert_eq!(item.save_count, 7));
+    }
+
+    #[gpui::test]
+    async fn test_close_active_pane(cx: &mut TestAppContext) {
+        init_test(cx);
+
+        let fs = FakeFs::new(cx.executor());
+        let project = Project::test(fs, None, cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project, cx));
+
+        let item1 = cx.new_view(|cx| {
+            TestItem::new(cx).with_project_items(&[TestProjectItem::new(1, "1.txt", cx)])
+        });
+        let item2 = cx.new_view(|cx| {
+            TestItem::new(cx)
+                .with_dirty(true)
+                .with_project_items(&[TestProjectItem::new(2, "2.txt", cx)])
+        });
+        let pane1 = workspace.update(cx, |workspace, cx| {
+            workspace.add_item_to_active_pane(Box::new(item1.clone()), None, cx);
+            workspace.active_pane().clone()
+        });
+
+        // An empty pane is closed right away, and the pane it was split from gets focused.
+        workspace.update(cx, |workspace, cx| {
+            workspace.split_pane(pane1.clone(), SplitDirection::Right, cx);
+            assert_eq!(workspace.panes().len(), 2);
+            workspace.close_active_pane(&CloseActivePane, cx);
+        });
+        cx.executor().run_until_parked();
+        workspace.update(cx, |workspace, _| {
+            assert_eq!(workspace.panes(), &[pane1.clone()]);
+            assert_eq!(workspace.active_pane(), &pane1);
+        });
+
+        // A pane is kept when closing one of its items is cancelled.
+        let pane2 = workspace.update(cx, |workspace, cx| {
+            let pane2 = workspace.split_pane(pane1.clone(), SplitDirection::Down, cx);
+            workspace.add_item_to_active_pane(Box::new(item2.clone()), None, cx);
+            workspace.close_active_pane(&CloseActivePane, cx);
+            pane2
+        });
+        cx.executor().run_until_parked();
+        assert!(cx.has_pending_prompt());
+        cx.simulate_prompt_answer(2);
+        cx.executor().run_until_parked();
+        workspace.update(cx, |workspace, cx| {
+            assert_eq!(workspace.panes(), &[pane1.clone(), pane2.clone()]);
+            assert_eq!(pane2.read(cx).items_len(), 1);
+            workspace.close_active_pane(&CloseActivePane, cx);
+        });
+        cx.executor().run_until_parked();
+        cx.simulate_prompt_answer(1);
+        cx.executor().run_until_parked();
+        workspace.update(cx, |workspace, _| {
+            assert_eq!(workspace.panes(), &[pane1.clone()]);
+            assert_eq!(workspace.active_pane(), &pane1);
+        });
+
+        // The last pane only gets its items closed.
+        workspace.update(cx, |workspace, cx| {
+            workspace.close_active_pane(&CloseActivePane, cx);
+        });
+        cx.executor().run_until_parked();
+        workspace.update(cx, |workspace, cx| {
+            assert_eq!(workspace.panes(), &[pane1.clone()]);
+            assert_eq!(pane1.read(cx).items_len(), 0);
+        });
     }
 
     #[gpui::test]
@@ -5764,6 +6831,90 @@ mod tests {
         });
     }
 
+    #[gpui::test]
+    async fn test_toasts(cx: &mut gpui::TestAppContext) {
+        struct ToastA;
+        struct ToastB;
+
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+        let project = Project::test(fs, [], cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project, cx));
+        let id_a = NotificationId::unique::<ToastA>();
+        let id_b = NotificationId::unique::<ToastB>();
+
+        // A toast replaces the notification with the same id.
+        workspace.update(cx, |workspace, cx| {
+            workspace.show_toast(Toast::new(id_a.clone(), "one"), cx);
+            workspace.show_toast(Toast::new(id_a.clone(), "two"), cx);
+            workspace.show_toast(Toast::new(id_b.clone(), "three").autohide(), cx);
+            assert_eq!(workspace.notification_ids(), [id_a.clone(), id_b.clone()]);
+        });
+
+        // Only toasts that autohide are dismissed once their time is up.
+        cx.executor()
+            .advance_clock(notifications::TOAST_AUTOHIDE_DURATION / 2);
+        workspace.update(cx, |workspace, cx| {
+            workspace.show_toast(Toast::new(id_a.clone(), "four").autohide(), cx);
+        });
+        cx.executor()
+            .advance_clock(notifications::TOAST_AUTOHIDE_DURATION / 2);
+        workspace.update(cx, |workspace, _| {
+            assert_eq!(workspace.notification_ids(), [id_a.clone()]);
+        });
+
+        // The timer of a replaced toast doesn't dismiss the toast that replaced it.
+        workspace.update(cx, |workspace, cx| {
+            workspace.show_toast(Toast::new(id_a.clone(), "five"), cx);
+        });
+        cx.executor()
+            .advance_clock(notifications::TOAST_AUTOHIDE_DURATION);
+        workspace.update(cx, |workspace, _| {
+            assert_eq!(workspace.notification_ids(), [id_a.clone()]);
+        });
+    }
+
+    #[gpui::test]
+    async fn test_notifications_are_bounded(cx: &mut gpui::TestAppContext) {
+        struct NumberedToast;
+
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+        let project = Project::test(fs, [], cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project, cx));
+
+        let ids = (0..notifications::MAX_NOTIFICATIONS + 2)
+            .map(NotificationId::identified::<NumberedToast>)
+            .collect::<Vec<_>>();
+        workspace.update(cx, |workspace, cx| {
+            for id in &ids {
+                workspace.show_toast(Toast::new(id.clone(), "message"), cx);
+            }
+
+            // The oldest notifications make room for the newer ones.
+            assert_eq!(workspace.notification_ids(), &ids[2..]);
+        });
+    }
+
+    #[gpui::test]
+    async fn test_connection_status_toast(cx: &mut gpui::TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+        let project = Project::test(fs, [], cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project, cx));
+
+        workspace.update(cx, |workspace, cx| {
+            workspace.show_connection_status(&Status::ConnectionLost, cx);
+            assert_eq!(workspace.notification_ids().len(), 1);
+
+            // The toast stays up while reconnecting, and goes away once that's over.
+            workspace.show_connection_status(&Status::Reconnecting, cx);
+            assert_eq!(workspace.notification_ids().len(), 1);
+            workspace.show_connection_status(&Status::SignedOut, cx);
+            assert!(workspace.notification_ids().is_empty());
+        });
+    }
+
     #[gpui::test]
     async fn test_toggle_docks_and_panels(cx: &mut gpui::TestAppContext) {
         init_test(cx);
@@ -5911,6 +7062,86 @@ mod tests {
         });
     }
 
+    #[gpui::test]
+    async fn test_zoomed_center_pane(cx: &mut gpui::TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+
+        let project = Project::test(fs, [], cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project, cx));
+        let pane_1 = workspace.update(cx, |workspace, cx| {
+            let item = cx.new_view(|cx| TestItem::new(cx));
+            workspace.add_item_to_active_pane(Box::new(item), None, cx);
+            workspace.active_pane().clone()
+        });
+        let pane_2 = workspace.update(cx, |workspace, cx| {
+            let pane_2 = workspace.split_pane(pane_1.clone(), SplitDirection::Right, cx);
+            let item = cx.new_view(|cx| TestItem::new(cx));
+            workspace.add_item(pane_2.clone(), Box::new(item), None, cx);
+            pane_2
+        });
+        cx.run_until_parked();
+
+        // Focusing another pane unzooms the zoomed one.
+        cx.focus_view(&pane_2);
+        cx.run_until_parked();
+        pane_2.update(cx, |pane, cx| pane.toggle_zoom(&Default::default(), cx));
+        workspace.update(cx, |workspace, cx| {
+            assert!(pane_2.read(cx).is_zoomed());
+            assert_eq!(workspace.zoomed, Some(pane_2.downgrade().into()));
+            workspace.activate_previous_pane(cx);
+        });
+        cx.run_until_parked();
+        workspace.update(cx, |workspace, cx| {
+            assert_eq!(workspace.active_pane(), &pane_1);
+            assert!(!pane_1.read(cx).is_zoomed());
+            assert!(!pane_2.read(cx).is_zoomed());
+            assert!(workspace.zoomed.is_none());
+        });
+
+        // Unless configured otherwise, in which case the zoom follows the focus.
+        cx.update(|cx| {
+            SettingsStore::update_global(cx, |settings, cx| {
+                settings.update_user_settings::<WorkspaceSettings>(cx, |settings| {
+                    settings.unzoom_on_focus_change = Some(false);
+                })
+            });
+        });
+        pane_1.update(cx, |pane, cx| pane.toggle_zoom(&Default::default(), cx));
+        workspace.update(cx, |workspace, cx| workspace.activate_next_pane(cx));
+        cx.run_until_parked();
+        workspace.update(cx, |workspace, cx| {
+            assert_eq!(workspace.active_pane(), &pane_2);
+            assert!(!pane_1.read(cx).is_zoomed());
+            assert!(pane_2.read(cx).is_zoomed());
+            assert_eq!(workspace.zoomed, Some(pane_2.downgrade().into()));
+        });
+
+        // Splitting a zoomed pane unzooms it, and the split layout is kept.
+        let pane_3 = workspace.update(cx, |workspace, cx| {
+            workspace.split_pane(pane_2.clone(), SplitDirection::Down, cx)
+        });
+        workspace.update(cx, |workspace, cx| {
+            assert!(!pane_2.read(cx).is_zoomed());
+            assert!(workspace.zoomed.is_none());
+            assert_eq!(workspace.center.panes(), [&pane_1, &pane_2, &pane_3]);
+        });
+
+        // Closing a zoomed pane unzooms the workspace.
+        cx.focus_view(&pane_2);
+        cx.run_until_parked();
+        pane_2.update(cx, |pane, cx| pane.toggle_zoom(&Default::default(), cx));
+        workspace.update(cx, |workspace, cx| {
+            assert_eq!(workspace.zoomed, Some(pane_2.downgrade().into()));
+            workspace.close_active_pane(&CloseActivePane, cx);
+        });
+        cx.run_until_parked();
+        workspace.update(cx, |workspace, _| {
+            assert_eq!(workspace.center.panes(), [&pane_1, &pane_3]);
+            assert!(workspace.zoomed.is_none());
+        });
+    }
+
     struct TestModal(FocusHandle);
 
     impl TestModal {
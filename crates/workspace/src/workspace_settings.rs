@@ -14,6 +14,7 @@ pub struct WorkspaceSettings {
     pub restore_on_startup: RestoreOnStartupBehaviour,
     pub drop_target_size: f32,
     pub when_closing_with_no_tabs: CloseWindowWhenNoItems,
+    pub unzoom_on_focus_change: bool,
 }
 
 #[derive(Copy, Clone, Default, Serialize, Deserialize, JsonSchema)]
@@ -83,6 +84,11 @@ pub struct WorkspaceSettingsContent {
     ///
     /// Default: auto ("on" on macOS, "off" otherwise)
     pub when_closing_with_no_tabs: Option<CloseWindowWhenNoItems>,
+    /// Whether a zoomed pane gets unzoomed when another pane is focused.
+    /// When disabled, the newly focused pane gets zoomed instead.
+    ///
+    /// Default: true
+    pub unzoom_on_focus_change: Option<bool>,
 }
 
 #[derive(Deserialize)]
@@ -0,0 +1,111 @@
+use std::{path::Path, sync::Arc};
+
+use collections::HashSet;
+use fuzzy::PreparedPath;
+use sum_tree::{SumTree, TreeMap, TreeMapCursor};
+
+use crate::{Entry, PathChange, PathKey};
+
+/// The paths of a worktree's entries, prepared for fuzzy matching ahead of time, so that
+/// matching them against a query doesn't fold every path again. It's kept up to date from the
+/// changes to the entries, which only prepares the paths that were added.
+///
+/// Ignored entries are left out, since they're often many and are only matched when asked
+/// for, in which case their paths are folded while matching them.
+///
+/// The prepared form of a path only depends on the path, so an index that lags behind the
+/// entries is never wrong, merely missing some of the paths.
+#[derive(Clone, Debug, Default)]
+pub struct MatchCandidateIndex {
+    paths: TreeMap<Arc<Path>, Arc<PreparedPath>>,
+}
+
+impl MatchCandidateIndex {
+    /// Prepares the paths of the given entries, which have to be in the worktree's order.
+    pub(crate) fn from_entries<'a>(entries: impl IntoIterator<Item = &'a Entry>) -> Self {
+        Self {
+            paths: TreeMap::from_ordered_entries(
+                entries
+                    .into_iter()
+                    .filter(|entry| !entry.is_ignored)
+                    .map(|entry| (entry.path.clone(), Arc::new(PreparedPath::new(&entry.path)))),
+            ),
+        }
+    }
+
+    /// Applies a batch of changes to the entries, which have to be applied to `entries_by_path`
+    /// already. A path that the same batch both removes and adds, such as the path of an entry
+    /// that got replaced, stays in the index, unless its new entry is ignored.
+    pub(crate) fn apply_changes<'a>(
+        &mut self,
+        changes: impl IntoIterator<Item = (&'a Arc<Path>, PathChange)>,
+        entries_by_path: &SumTree<Entry>,
+    ) {
+        let mut added_paths = HashSet::default();
+        let mut removed_paths = Vec::new();
+        for (path, change) in changes {
+            if change == PathChange::Removed {
+                removed_paths.push(path);
+            } else {
+                added_paths.insert(path);
+            }
+        }
+
+        for path in removed_paths {
+            if !added_paths.contains(&path) {
+                self.paths.remove(path);
+            }
+        }
+
+        // An entry that becomes ignored is reported as updated.
+        added_paths.retain(|path| {
+            let is_ignored = entries_by_path
+                .get(&PathKey((*path).clone()), &())
+                .map_or(true, |entry| entry.is_ignored);
+            if is_ignored {
+                self.paths.remove(path);
+            }
+            !is_ignored
+        });
+
+        let mut new_paths = added_paths
+            .into_iter()
+            .filter(|path| self.paths.get(path).is_none())
+            .map(|path| (path.clone(), Arc::new(PreparedPath::new(path))))
+            .collect::<Vec<_>>();
+        if !new_paths.is_empty() {
+            new_paths.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+            self.paths
+                .insert_tree(TreeMap::from_ordered_entries(new_paths));
+        }
+    }
+
+    pub fn get(&self, path: &Arc<Path>) -> Option<&PreparedPath> {
+        self.paths.get(path).map(AsRef::as_ref)
+    }
+
+    /// Returns a cursor for looking up the prepared paths of entries that are visited in the
+    /// worktree's order, which is cheaper than looking up each of them from scratch.
+    pub fn cursor(&self) -> MatchCandidateCursor<'_> {
+        MatchCandidateCursor(self.paths.cursor())
+    }
+
+    #[cfg(test)]
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&Arc<Path>, &PreparedPath)> {
+        self.paths
+            .iter()
+            .map(|(path, prepared)| (path, prepared.as_ref()))
+    }
+}
+
+/// Looks up prepared paths in the order of the worktree's entries, see
+/// [`MatchCandidateIndex::cursor`].
+pub struct MatchCandidateCursor<'a>(TreeMapCursor<'a, Arc<Path>, Arc<PreparedPath>>);
+
+impl<'a> MatchCandidateCursor<'a> {
+    /// Returns the prepared form of the given path, which can't come before the paths looked
+    /// up before it.
+    pub fn seek_forward(&mut self, path: &Arc<Path>) -> Option<&'a PreparedPath> {
+        self.0.seek_forward(path).map(AsRef::as_ref)
+    }
+}
@@ -1,3 +1,4 @@
+mod candidate_index;
 mod ignore;
 mod worktree_settings;
 #[cfg(test)]
@@ -7,15 +8,15 @@ use ::ignore::gitignore::{Gitignore, GitignoreBuilder};
 use anyhow::{anyhow, Context as _, Result};
 use clock::ReplicaId;
 use collections::{HashMap, HashSet, VecDeque};
-use fs::{copy_recursive, Fs, RemoveOptions, Watcher};
+use fs::{copy_recursive, Fs, RemoveOptions, SaveProgress, Watcher};
 use futures::{
     channel::{
         mpsc::{self, UnboundedSender},
         oneshot,
     },
-    future::BoxFuture,
+    future::{self, BoxFuture},
     select_biased,
-    stream::select,
+    stream::{self, select},
     task::Poll,
     FutureExt as _, Stream, StreamExt,
 };
@@ -27,7 +28,7 @@ use git::{
 };
 use gpui::{
     AppContext, AsyncAppContext, BackgroundExecutor, Context, EventEmitter, Model, ModelContext,
-    Task,
+    Task, TaskPriority,
 };
 use ignore::IgnoreStack;
 use parking_lot::Mutex;
@@ -36,7 +37,10 @@ use postage::{
     prelude::{Sink as _, Stream as _},
     watch,
 };
-use rpc::proto::{self, EnvelopedMessage as _, RequestMessage};
+use rpc::{
+    proto::{self, EnvelopedMessage as _, RequestMessage},
+    subscribe_with_snapshot, ErrorCode, ErrorCodeExt as _, SnapshotUpdate,
+};
 use settings::{Settings, SettingsLocation, SettingsStore};
 use smol::channel::{self, Sender};
 use std::{
@@ -57,8 +61,10 @@ use std::{
     time::{Duration, Instant, SystemTime},
 };
 use sum_tree::{Bias, Edit, SeekTarget, SumTree, TreeMap, TreeSet};
-use text::{LineEnding, Rope};
-use util::{paths::HOME, ResultExt};
+use text::{Encoding, LineEnding, Rope};
+use util::{paths::HOME, timings, ResultExt};
+
+pub use candidate_index::{MatchCandidateCursor, MatchCandidateIndex};
 pub use worktree_settings::WorktreeSettings;
 
 #[cfg(feature = "test-support")]
@@ -97,19 +103,30 @@ pub enum CreatedEntry {
 pub struct LoadedFile {
     pub file: Arc<File>,
     pub text: String,
+    /// The encoding the file's contents were decoded from.
+    pub encoding: Encoding,
     pub diff_base: Option<String>,
+    /// Whether the file's permissions don't allow writing to it.
+    pub is_read_only: bool,
 }
 
 pub struct LocalWorktree {
     snapshot: LocalSnapshot,
+    /// The root path as it was provided when opening the worktree, e.g. `~/code/proj`.
+    display_path: Arc<Path>,
+    /// The root path with all symlinks resolved, used to detect when the same
+    /// directory is opened through different paths.
+    canonical_abs_path: Arc<Path>,
     scan_requests_tx: channel::Sender<ScanRequest>,
-    path_prefixes_to_scan_tx: channel::Sender<Arc<Path>>,
+    path_prefixes_to_scan_tx: channel::Sender<PathPrefixScanRequest>,
     is_scanning: (watch::Sender<bool>, watch::Receiver<bool>),
     _background_scanner_tasks: Vec<Task<()>>,
     update_observer: Option<UpdateObservationState>,
     fs: Arc<dyn Fs>,
     fs_case_sensitive: bool,
     visible: bool,
+    /// Whether the user marked the worktree read-only.
+    read_only: bool,
     next_entry_id: Arc<AtomicUsize>,
     settings: WorktreeSettings,
     share_private_files: bool,
@@ -120,6 +137,11 @@ struct ScanRequest {
     done: barrier::Sender,
 }
 
+struct PathPrefixScanRequest {
+    path: Arc<Path>,
+    done: barrier::Sender,
+}
+
 pub struct RemoteWorktree {
     snapshot: Snapshot,
     background_snapshot: Arc<Mutex<Snapshot>>,
@@ -132,6 +154,10 @@ pub struct RemoteWorktree {
     snapshot_subscriptions: VecDeque<(usize, oneshot::Sender<()>)>,
     replica_id: ReplicaId,
     visible: bool,
+    /// Whether the user marked the worktree read-only.
+    read_only: bool,
+    /// Whether the role of the user in the project keeps them from writing to the worktree.
+    read_only_by_role: bool,
     disconnected: bool,
 }
 
@@ -152,6 +178,7 @@ pub struct Snapshot {
     entries_by_path: SumTree<Entry>,
     entries_by_id: SumTree<PathEntry>,
     repository_entries: TreeMap<RepositoryWorkDirectory, RepositoryEntry>,
+    candidate_index: MatchCandidateIndex,
 
     /// A number that increases every time the worktree begins scanning
     /// a set of paths from the filesystem. This scanning could be caused
@@ -362,6 +389,21 @@ pub enum Event {
 
 static EMPTY_PATH: &str = "";
 
+/// Expands a leading `~` and resolves `.` and `..` components, making relative
+/// paths absolute against the current directory. Symlinks are left untouched.
+pub fn absolutize_root_path(path: &Path) -> PathBuf {
+    let path = util::paths::expand_tilde(path);
+    let path = if path.is_relative() {
+        match std::env::current_dir() {
+            Ok(current_dir) => current_dir.join(path),
+            Err(_) => path,
+        }
+    } else {
+        path
+    };
+    fs::normalize_path(&path)
+}
+
 impl EventEmitter<Event> for Worktree {}
 
 impl Worktree {
@@ -372,7 +414,12 @@ impl Worktree {
         next_entry_id: Arc<AtomicUsize>,
         cx: &mut AsyncAppContext,
     ) -> Result<Model<Self>> {
-        let abs_path = path.into();
+        let display_path = path.into();
+        let abs_path: Arc<Path> = absolutize_root_path(&display_path).into();
+        let canonical_abs_path: Arc<Path> = match fs.canonicalize(&abs_path).await {
+            Ok(canonical_abs_path) => canonical_abs_path.into(),
+            Err(_) => abs_path.clone(),
+        };
         let metadata = fs
             .metadata(&abs_path)
             .await
@@ -417,7 +464,7 @@ impl Worktree {
             };
 
             if let Some(metadata) = metadata {
-                snapshot.insert_entry(
+                let root_entry = snapshot.insert_entry(
                     Entry::new(
                         Arc::from(Path::new("")),
                         &metadata,
@@ -427,11 +474,19 @@ impl Worktree {
                     ),
                     fs.as_ref(),
                 );
+                // The background scanner only reports the entries that change after this one.
+                let snapshot = &mut snapshot.snapshot;
+                snapshot.candidate_index.apply_changes(
+                    [(&root_entry.path, PathChange::Added)],
+                    &snapshot.entries_by_path,
+                );
             }
 
             let (scan_requests_tx, scan_requests_rx) = channel::unbounded();
             let (path_prefixes_to_scan_tx, path_prefixes_to_scan_rx) = channel::unbounded();
             let mut worktree = LocalWorktree {
+                display_path,
+                canonical_abs_path,
                 share_private_files: false,
                 next_entry_id,
                 snapshot,
@@ -443,6 +498,7 @@ impl Worktree {
                 fs,
                 fs_case_sensitive,
                 visible,
+                read_only: false,
                 settings,
             };
             worktree.start_background_scanner(scan_requests_rx, path_prefixes_to_scan_rx, cx);
@@ -464,9 +520,10 @@ impl Worktree {
                 Arc::from(PathBuf::from(worktree.abs_path)),
             );
 
-            let (updates_tx, mut updates_rx) = mpsc::unbounded();
+            let (updates_tx, updates_rx) = mpsc::unbounded();
             let background_snapshot = Arc::new(Mutex::new(snapshot.clone()));
             let (mut snapshot_updated_tx, mut snapshot_updated_rx) = watch::channel();
+            let (refresh_tx, mut refresh_rx) = mpsc::unbounded::<()>();
             let update_observer = Arc::new(Mutex::new(None));
 
             let worktree = RemoteWorktree {
@@ -479,12 +536,41 @@ impl Worktree {
                 updates_tx: Some(updates_tx),
                 snapshot_subscriptions: Default::default(),
                 visible: worktree.visible,
+                read_only: false,
+                read_only_by_role: false,
                 disconnected: false,
             };
 
             cx.background_executor()
                 .spawn(async move {
-                    while let Some(update) = updates_rx.next().await {
+                    let mut updates = match subscribe_with_snapshot(updates_rx).await {
+                        Ok((snapshot, updates)) => {
+                            stream::once(future::ready(SnapshotUpdate::Resync(snapshot)))
+                                .chain(updates)
+                        }
+                        Err(error) => {
+                            log::error!("error receiving worktree updates: {error:#}");
+                            return;
+                        }
+                    };
+                    while let Some(update) = updates.next().await {
+                        let (update, replaces_entries) = match update {
+                            SnapshotUpdate::Resync(update) => (update, true),
+                            SnapshotUpdate::Delta(update) => (update, false),
+                        };
+                        // Updates come in the order of the host's scans, so one for an earlier scan
+                        // than the last one means that the entries no longer follow the host's.
+                        let scan_id = background_snapshot.lock().scan_id;
+                        if !replaces_entries && (update.scan_id as usize) < scan_id {
+                            log::error!(
+                                "received worktree update for scan {} after scan {}",
+                                update.scan_id,
+                                scan_id
+                            );
+                            refresh_tx.unbounded_send(()).ok();
+                            continue;
+                        }
+
                         let call = update_observer
                             .lock()
                             .as_mut()
@@ -492,8 +578,15 @@ impl Worktree {
                         if let Some(call) = call {
                             call.await;
                         }
-                        if let Err(error) = background_snapshot.lock().apply_remote_update(update) {
-                            log::error!("error applying worktree update: {}", error);
+                        {
+                            let mut snapshot = background_snapshot.lock();
+                            if replaces_entries {
+                                snapshot.clear_entries();
+                            }
+                            if let Err(error) = snapshot.apply_remote_update(update) {
+                                log::error!("error applying worktree update: {}", error);
+                                refresh_tx.unbounded_send(()).ok();
+                            }
                         }
                         snapshot_updated_tx.send(()).await.ok();
                     }
@@ -521,6 +614,23 @@ impl Worktree {
             })
             .detach();
 
+            cx.spawn(|this, mut cx| async move {
+                while refresh_rx.next().await.is_some() {
+                    // One refresh makes up for all of the updates that went wrong before it.
+                    while let Ok(Some(())) = refresh_rx.try_next() {}
+                    let request = this.update(&mut cx, |this, _| {
+                        let this = this.as_remote().unwrap();
+                        this.rpc_request(proto::RefreshWorktree {
+                            project_id: this.project_id,
+                            worktree_id: this.id().to_proto(),
+                        })
+                    })?;
+                    request.await.log_err();
+                }
+                anyhow::Ok(())
+            })
+            .detach();
+
             Worktree::Remote(worktree)
         })
     }
@@ -602,6 +712,53 @@ impl Worktree {
         }
     }
 
+    /// Whether this worktree was opened for inspection only. Buffers opened from a
+    /// read-only worktree are read-only, and its entries can't be created, renamed,
+    /// copied, deleted or written to.
+    pub fn is_read_only(&self) -> bool {
+        match self {
+            Worktree::Local(worktree) => worktree.read_only,
+            Worktree::Remote(worktree) => worktree.read_only || worktree.read_only_by_role,
+        }
+    }
+
+    /// Whether this worktree was marked read-only, rather than being read-only because of the
+    /// role of the user in the project.
+    pub fn is_marked_read_only(&self) -> bool {
+        match self {
+            Worktree::Local(worktree) => worktree.read_only,
+            Worktree::Remote(worktree) => worktree.read_only,
+        }
+    }
+
+    /// Marks this worktree read-only, or writable again unless the role of the user in the
+    /// project keeps it read-only.
+    pub fn set_read_only(&mut self, read_only: bool, cx: &mut ModelContext<Self>) {
+        let current = match self {
+            Worktree::Local(worktree) => &mut worktree.read_only,
+            Worktree::Remote(worktree) => &mut worktree.read_only,
+        };
+        if *current != read_only {
+            *current = read_only;
+            cx.notify();
+        }
+    }
+
+    /// Makes this remote worktree read-only while the role of the user in the project doesn't
+    /// allow writing to it, independently of whether it was marked read-only.
+    pub fn set_read_only_by_role(&mut self, read_only: bool, cx: &mut ModelContext<Self>) {
+        if let Worktree::Remote(worktree) = self {
+            if worktree.read_only_by_role != read_only {
+                worktree.read_only_by_role = read_only;
+                cx.notify();
+            }
+        }
+    }
+
+    fn read_only_error(&self) -> anyhow::Error {
+        anyhow!("worktree {:?} is read-only", self.root_name())
+    }
+
     pub fn replica_id(&self) -> ReplicaId {
         match self {
             Worktree::Local(_) => 0,
@@ -677,10 +834,37 @@ impl Worktree {
         path: &Path,
         text: Rope,
         line_ending: LineEnding,
+        encoding: Encoding,
+        cx: &mut ModelContext<Worktree>,
+    ) -> Task<Result<Arc<File>>> {
+        self.write_file_with_progress(
+            path,
+            text,
+            line_ending,
+            encoding,
+            SaveProgress::default(),
+            cx,
+        )
+    }
+
+    /// Writes the file like `write_file`, reporting how much of it has been written to
+    /// `progress`, through which the write can also be cancelled until the file is replaced.
+    pub fn write_file_with_progress(
+        &self,
+        path: &Path,
+        text: Rope,
+        line_ending: LineEnding,
+        encoding: Encoding,
+        progress: SaveProgress,
         cx: &mut ModelContext<Worktree>,
     ) -> Task<Result<Arc<File>>> {
+        if self.is_read_only() {
+            return Task::ready(Err(self.read_only_error()));
+        }
         match self {
-            Worktree::Local(this) => this.write_file(path, text, line_ending, cx),
+            Worktree::Local(this) => {
+                this.write_file(path, text, line_ending, encoding, progress, cx)
+            }
             Worktree::Remote(_) => {
                 Task::ready(Err(anyhow!("remote worktree can't yet write files")))
             }
@@ -693,6 +877,9 @@ impl Worktree {
         is_directory: bool,
         cx: &mut ModelContext<Worktree>,
     ) -> Task<Result<CreatedEntry>> {
+        if self.is_read_only() {
+            return Task::ready(Err(self.read_only_error()));
+        }
         let path = path.into();
         let worktree_id = self.id();
         match self {
@@ -738,6 +925,9 @@ impl Worktree {
         trash: bool,
         cx: &mut ModelContext<Worktree>,
     ) -> Option<Task<Result<()>>> {
+        if self.is_read_only() {
+            return Some(Task::ready(Err(self.read_only_error())));
+        }
         match self {
             Worktree::Local(this) => this.delete_entry(entry_id, trash, cx),
             Worktree::Remote(this) => {
@@ -767,6 +957,9 @@ impl Worktree {
         new_path: impl Into<Arc<Path>>,
         cx: &mut ModelContext<Self>,
     ) -> Task<Result<CreatedEntry>> {
+        if self.is_read_only() {
+            return Task::ready(Err(self.read_only_error()));
+        }
         let new_path = new_path.into();
         match self {
             Worktree::Local(this) => this.rename_entry(entry_id, new_path, cx),
@@ -809,6 +1002,9 @@ impl Worktree {
         new_path: impl Into<Arc<Path>>,
         cx: &mut ModelContext<Self>,
     ) -> Task<Result<Option<Entry>>> {
+        if self.is_read_only() {
+            return Task::ready(Err(self.read_only_error()));
+        }
         let new_path = new_path.into();
         match self {
             Worktree::Local(this) => this.copy_entry(entry_id, new_path, cx),
@@ -968,6 +1164,14 @@ impl Worktree {
 }
 
 impl LocalWorktree {
+    pub fn display_path(&self) -> &Arc<Path> {
+        &self.display_path
+    }
+
+    pub fn canonical_abs_path(&self) -> &Arc<Path> {
+        &self.canonical_abs_path
+    }
+
     pub fn contains_abs_path(&self, path: &Path) -> bool {
         path.starts_with(&self.abs_path)
     }
@@ -987,7 +1191,7 @@ impl LocalWorktree {
     fn start_background_scanner(
         &mut self,
         scan_requests_rx: channel::Receiver<ScanRequest>,
-        path_prefixes_to_scan_rx: channel::Receiver<Arc<Path>>,
+        path_prefixes_to_scan_rx: channel::Receiver<PathPrefixScanRequest>,
         cx: &mut ModelContext<Worktree>,
     ) {
         let snapshot = self.snapshot();
@@ -996,15 +1200,12 @@ impl LocalWorktree {
         let fs = self.fs.clone();
         let settings = self.settings.clone();
         let (scan_states_tx, mut scan_states_rx) = mpsc::unbounded();
+        // Scanning runs at the default priority, as the user waits on it for the project's
+        // files to show up, and for changes on disk to be picked up.
         let background_scanner = cx.background_executor().spawn({
-            let abs_path = &snapshot.abs_path;
-            let abs_path = if cfg!(target_os = "windows") {
-                abs_path
-                    .canonicalize()
-                    .unwrap_or_else(|_| abs_path.to_path_buf())
-            } else {
-                abs_path.to_path_buf()
-            };
+            // FS events are reported for canonical paths, e.g. under `/private/tmp` for
+            // a worktree opened as `/tmp/proj` on macOS.
+            let abs_path = self.canonical_abs_path.to_path_buf();
             let background = cx.background_executor().clone();
             async move {
                 let (events, watcher) = fs.watch(&abs_path, FS_WATCH_LATENCY).await;
@@ -1227,16 +1428,28 @@ impl LocalWorktree {
         let fs = self.fs.clone();
         let entry = self.refresh_entry(path.clone(), None, cx);
         let is_private = self.is_path_private(path.as_ref());
+        let fallback_encoding = self.settings.fallback_encoding;
 
         cx.spawn(|this, mut cx| async move {
             let abs_path = abs_path?;
-            let text = fs.load(&abs_path).await?;
+            let bytes = fs.load_bytes(&abs_path).await?;
+            let metadata = fs
+                .metadata(&abs_path)
+                .await
+                .with_context(|| format!("Loading metadata for {abs_path:?}"))?
+                .with_context(|| format!("File {abs_path:?} got removed during loading"))?;
+            let (text, encoding) = cx
+                .background_executor()
+                .spawn(async move { Encoding::detect(&bytes, fallback_encoding) })
+                .await
+                .with_context(|| format!("Loading {abs_path:?}"))?;
             let mut index_task = None;
             let snapshot = this.update(&mut cx, |this, _| this.as_local().unwrap().snapshot())?;
             if let Some(repo) = snapshot.repository_for_path(&path) {
                 if let Some(repo_path) = repo.relativize(&snapshot, &path).log_err() {
                     if let Some(git_repo) = snapshot.git_repositories.get(&*repo.work_directory) {
                         let git_repo = git_repo.repo_ptr.clone();
+                        let executor = cx.background_executor().clone();
                         index_task = Some(cx.background_executor().spawn({
                             let fs = fs.clone();
                             let abs_path = abs_path.clone();
@@ -1252,7 +1465,12 @@ impl LocalWorktree {
                                 if metadata.is_dir || metadata.is_symlink {
                                     None
                                 } else {
-                                    git_repo.load_index_text(&repo_path)
+                                    // Reading the index blocks on git.
+                                    executor
+                                        .spawn_blocking(move || {
+                                            git_repo.load_index_text(&repo_path)
+                                        })
+                                        .await
                                 }
                             }
                         }));
@@ -1271,32 +1489,23 @@ impl LocalWorktree {
                 .ok_or_else(|| anyhow!("worktree was dropped"))?;
             let file = match entry.await? {
                 Some(entry) => File::for_entry(entry, worktree),
-                None => {
-                    let metadata = fs
-                        .metadata(&abs_path)
-                        .await
-                        .with_context(|| {
-                            format!("Loading metadata for excluded file {abs_path:?}")
-                        })?
-                        .with_context(|| {
-                            format!("Excluded file {abs_path:?} got removed during loading")
-                        })?;
-                    Arc::new(File {
-                        entry_id: None,
-                        worktree,
-                        path,
-                        mtime: Some(metadata.mtime),
-                        is_local: true,
-                        is_deleted: false,
-                        is_private,
-                    })
-                }
+                None => Arc::new(File {
+                    entry_id: None,
+                    worktree,
+                    path,
+                    mtime: Some(metadata.mtime),
+                    is_local: true,
+                    is_deleted: false,
+                    is_private,
+                }),
             };
 
             Ok(LoadedFile {
                 file,
                 text,
+                encoding,
                 diff_base,
+                is_read_only: metadata.is_read_only,
             })
         })
     }
@@ -1383,6 +1592,8 @@ impl LocalWorktree {
         path: impl Into<Arc<Path>>,
         text: Rope,
         line_ending: LineEnding,
+        encoding: Encoding,
+        progress: SaveProgress,
         cx: &mut ModelContext<Worktree>,
     ) -> Task<Result<Arc<File>>> {
         let path = path.into();
@@ -1395,7 +1606,10 @@ impl LocalWorktree {
         let write = cx.background_executor().spawn({
             let fs = fs.clone();
             let abs_path = abs_path.clone();
-            async move { fs.save(&abs_path, &text, line_ending).await }
+            async move {
+                fs.save_with_progress(&abs_path, &text, line_ending, encoding, &progress)
+                    .await
+            }
         });
 
         cx.spawn(move |this, mut cx| async move {
@@ -1505,6 +1719,7 @@ impl LocalWorktree {
         let abs_path = abs_new_path.clone();
         let fs = self.fs.clone();
         let case_sensitive = self.fs_case_sensitive;
+        let target_path = new_path.clone();
         let rename = cx.background_executor().spawn(async move {
             let abs_old_path = abs_old_path?;
             let abs_new_path = abs_new_path;
@@ -1517,6 +1732,17 @@ impl LocalWorktree {
             let overwrite = !case_sensitive
                 && abs_old_path != abs_new_path
                 && abs_old_path_lower == abs_new_path_lower;
+            // Entries don't get renamed over others, with an error that tells guests requesting the
+            // rename why.
+            if !overwrite
+                && abs_old_path != abs_new_path
+                && fs.metadata(&abs_new_path).await?.is_some()
+            {
+                return Err(ErrorCode::EntryAlreadyExists
+                    .message(format!("{target_path:?} already exists"))
+                    .with_tag("path", &target_path.to_string_lossy())
+                    .into());
+            }
 
             fs.rename(
                 &abs_old_path,
@@ -1603,8 +1829,35 @@ impl LocalWorktree {
         rx
     }
 
-    pub fn add_path_prefix_to_scan(&self, path_prefix: Arc<Path>) {
-        self.path_prefixes_to_scan_tx.try_send(path_prefix).ok();
+    /// Keeps everything under the given path loaded, even when it's ignored. The returned
+    /// barrier resolves once it has been scanned.
+    pub fn add_path_prefix_to_scan(&self, path_prefix: Arc<Path>) -> barrier::Receiver {
+        let (tx, rx) = barrier::channel();
+        self.path_prefixes_to_scan_tx
+            .try_send(PathPrefixScanRequest {
+                path: path_prefix,
+                done: tx,
+            })
+            .ok();
+        rx
+    }
+
+    /// Loads the ignored directories that haven't been scanned, along with everything in
+    /// them, and keeps them loaded from then on, so that their files can be searched.
+    pub fn load_ignored_dirs(&self) -> impl Future<Output = ()> {
+        let barriers = self
+            .snapshot
+            .entries(true)
+            .filter(|entry| {
+                entry.kind == EntryKind::UnloadedDir && entry.is_ignored && !entry.is_external
+            })
+            .map(|entry| self.add_path_prefix_to_scan(entry.path.clone()))
+            .collect::<Vec<_>>();
+        async move {
+            for mut barrier in barriers {
+                barrier.next().await;
+            }
+        }
     }
 
     fn refresh_entry(
@@ -1806,6 +2059,7 @@ impl Snapshot {
             entries_by_path: Default::default(),
             entries_by_id: Default::default(),
             repository_entries: Default::default(),
+            candidate_index: Default::default(),
             scan_id: 1,
             completed_scan_id: 0,
         }
@@ -1815,6 +2069,11 @@ impl Snapshot {
         self.id
     }
 
+    /// The paths of the entries, prepared for fuzzy matching.
+    pub fn candidate_index(&self) -> &MatchCandidateIndex {
+        &self.candidate_index
+    }
+
     pub fn abs_path(&self) -> &Arc<Path> {
         &self.abs_path
     }
@@ -1848,15 +2107,24 @@ impl Snapshot {
             },
             &(),
         );
+        let mut index_changes = Vec::new();
         if let Some(old_entry) = old_entry {
-            self.entries_by_path.remove(&PathKey(old_entry.path), &());
+            self.entries_by_path
+                .remove(&PathKey(old_entry.path.clone()), &());
+            index_changes.push((old_entry.path, PathChange::Removed));
         }
         self.entries_by_path.insert_or_replace(entry.clone(), &());
+        index_changes.push((entry.path.clone(), PathChange::Added));
+        self.candidate_index.apply_changes(
+            index_changes.iter().map(|(path, change)| (path, *change)),
+            &self.entries_by_path,
+        );
         Ok(entry)
     }
 
     fn delete_entry(&mut self, entry_id: ProjectEntryId) -> Option<Arc<Path>> {
         let removed_entry = self.entries_by_id.remove(&entry_id, &())?;
+        let mut removed_paths = Vec::new();
         self.entries_by_path = {
             let mut cursor = self.entries_by_path.cursor::<TraversalProgress>();
             let mut new_entries_by_path =
@@ -1864,6 +2132,7 @@ impl Snapshot {
             while let Some(entry) = cursor.item() {
                 if entry.path.starts_with(&removed_entry.path) {
                     self.entries_by_id.remove(&entry.id, &());
+                    removed_paths.push(entry.path.clone());
                     cursor.next(&());
                 } else {
                     break;
@@ -1872,6 +2141,10 @@ impl Snapshot {
             new_entries_by_path.append(cursor.suffix(&()), &());
             new_entries_by_path
         };
+        self.candidate_index.apply_changes(
+            removed_paths.iter().map(|path| (path, PathChange::Removed)),
+            &self.entries_by_path,
+        );
 
         Some(removed_entry.path)
     }
@@ -1884,39 +2157,105 @@ impl Snapshot {
             .and_then(|entry| entry.git_status)
     }
 
-    pub(crate) fn apply_remote_update(&mut self, mut update: proto::UpdateWorktree) -> Result<()> {
-        let mut entries_by_path_edits = Vec::new();
-        let mut entries_by_id_edits = Vec::new();
+    fn clear_entries(&mut self) {
+        self.entries_by_path = Default::default();
+        self.entries_by_id = Default::default();
+        self.repository_entries = Default::default();
+        self.candidate_index = Default::default();
+    }
 
-        for entry_id in update.removed_entries {
-            let entry_id = ProjectEntryId::from_proto(entry_id);
-            entries_by_id_edits.push(Edit::Remove(entry_id));
-            if let Some(entry) = self.entry_for_id(entry_id) {
-                entries_by_path_edits.push(Edit::Remove(PathKey(entry.path.clone())));
-            }
+    /// Checks that the candidate index holds exactly the paths of the entries that aren't
+    /// ignored, prepared the same way they would be from scratch.
+    #[cfg(test)]
+    pub(crate) fn check_candidate_index(&self) {
+        use pretty_assertions::assert_eq;
+
+        assert_eq!(
+            self.candidate_index
+                .iter()
+                .map(|(path, _)| path)
+                .collect::<Vec<_>>(),
+            self.entries_by_path
+                .cursor::<()>()
+                .filter(|entry| !entry.is_ignored)
+                .map(|entry| &entry.path)
+                .collect::<Vec<_>>(),
+            "the candidate index and the entries are inconsistent"
+        );
+        for (path, prepared) in self.candidate_index.iter() {
+            assert_eq!(*prepared, fuzzy::PreparedPath::new(path), "{path:?}");
         }
+    }
+
+    pub(crate) fn apply_remote_update(&mut self, mut update: proto::UpdateWorktree) -> Result<()> {
+        if self.entries_by_path.is_empty() && update.removed_entries.is_empty() {
+            // The first update of a worktree contains all of its entries, which can be many,
+            // so build the trees from them at once instead of inserting them one by one.
+            let mut entries = update
+                .updated_entries
+                .drain(..)
+                .map(|entry| Entry::try_from((&self.root_char_bag, entry)))
+                .collect::<Result<Vec<_>>>()?;
+            entries.sort_unstable_by(|a, b| a.path.cmp(&b.path));
+            entries.dedup_by(|a, b| a.path == b.path);
+            let mut path_entries = entries
+                .iter()
+                .map(|entry| PathEntry {
+                    id: entry.id,
+                    path: entry.path.clone(),
+                    is_ignored: entry.is_ignored,
+                    scan_id: 0,
+                })
+                .collect::<Vec<_>>();
+            path_entries.sort_unstable_by_key(|entry| entry.id);
+            path_entries.dedup_by_key(|entry| entry.id);
 
-        for entry in update.updated_entries {
-            let entry = Entry::try_from((&self.root_char_bag, entry))?;
-            if let Some(PathEntry { path, .. }) = self.entries_by_id.get(&entry.id, &()) {
-                entries_by_path_edits.push(Edit::Remove(PathKey(path.clone())));
+            self.candidate_index = MatchCandidateIndex::from_entries(&entries);
+            self.entries_by_path = SumTree::from_sorted_items_par(entries, &());
+            self.entries_by_id = SumTree::from_sorted_items_par(path_entries, &());
+        } else {
+            let mut entries_by_path_edits = Vec::new();
+            let mut entries_by_id_edits = Vec::new();
+            let mut index_changes = Vec::new();
+
+            for entry_id in update.removed_entries {
+                let entry_id = ProjectEntryId::from_proto(entry_id);
+                entries_by_id_edits.push(Edit::Remove(entry_id));
+                if let Some(entry) = self.entry_for_id(entry_id) {
+                    entries_by_path_edits.push(Edit::Remove(PathKey(entry.path.clone())));
+                    index_changes.push((entry.path.clone(), PathChange::Removed));
+                }
             }
-            if let Some(old_entry) = self.entries_by_path.get(&PathKey(entry.path.clone()), &()) {
-                if old_entry.id != entry.id {
-                    entries_by_id_edits.push(Edit::Remove(old_entry.id));
+
+            for entry in update.updated_entries {
+                let entry = Entry::try_from((&self.root_char_bag, entry))?;
+                if let Some(PathEntry { path, .. }) = self.entries_by_id.get(&entry.id, &()) {
+                    entries_by_path_edits.push(Edit::Remove(PathKey(path.clone())));
+                    index_changes.push((path.clone(), PathChange::Removed));
                 }
+                if let Some(old_entry) = self.entries_by_path.get(&PathKey(entry.path.clone()), &())
+                {
+                    if old_entry.id != entry.id {
+                        entries_by_id_edits.push(Edit::Remove(old_entry.id));
+                    }
+                }
+                entries_by_id_edits.push(Edit::Insert(PathEntry {
+                    id: entry.id,
+                    path: entry.path.clone(),
+                    is_ignored: entry.is_ignored,
+                    scan_id: 0,
+                }));
+                index_changes.push((entry.path.clone(), PathChange::Added));
+                entries_by_path_edits.push(Edit::Insert(entry));
             }
-            entries_by_id_edits.push(Edit::Insert(PathEntry {
-                id: entry.id,
-                path: entry.path.clone(),
-                is_ignored: entry.is_ignored,
-                scan_id: 0,
-            }));
-            entries_by_path_edits.push(Edit::Insert(entry));
-        }
 
-        self.entries_by_path.edit(entries_by_path_edits, &());
-        self.entries_by_id.edit(entries_by_id_edits, &());
+            self.entries_by_path.edit(entries_by_path_edits, &());
+            self.entries_by_id.edit(entries_by_id_edits, &());
+            self.candidate_index.apply_changes(
+                index_changes.iter().map(|(path, change)| (path, *change)),
+                &self.entries_by_path,
+            );
+        }
 
         update.removed_repositories.sort_unstable();
         self.repository_entries.retain(|_, entry| {
@@ -2276,6 +2615,7 @@ impl LocalSnapshot {
             is_last_update: self.completed_scan_id == self.scan_id,
             updated_repositories,
             removed_repositories,
+            is_snapshot: false,
         }
     }
 
@@ -2305,6 +2645,7 @@ impl LocalSnapshot {
             is_last_update: self.completed_scan_id == self.scan_id,
             updated_repositories,
             removed_repositories: Vec::new(),
+            is_snapshot: true,
         }
     }
 
@@ -2667,12 +3008,12 @@ impl BackgroundScannerState {
             Some(parent_dir) => {
                 // Guard against repositories inside the repository metadata
                 if parent_dir.iter().any(|component| component == *DOT_GIT) {
-                    log::info!(
+                    log::debug!(
                         "not building git repository for nested `.git` directory, `.git` path in the worktree: {dot_git_path:?}"
                     );
                     return None;
                 };
-                log::info!(
+                log::debug!(
                     "building git repository, `.git` path in the worktree: {dot_git_path:?}"
                 );
 
@@ -2681,7 +3022,7 @@ impl BackgroundScannerState {
             None => {
                 // `dot_git_path.parent().is_none()` means `.git` directory is the opened worktree itself,
                 // no files inside that directory are tracked by git, so no need to build the repo around it
-                log::info!(
+                log::debug!(
                     "not building git repository for the worktree itself, `.git` path in the worktree: {dot_git_path:?}"
                 );
                 return None;
@@ -2739,7 +3080,10 @@ async fn build_gitignore(abs_path: &Path, fs: &dyn Fs) -> Result<Gitignore> {
     let parent = abs_path.parent().unwrap_or_else(|| Path::new("/"));
     let mut builder = GitignoreBuilder::new(parent);
     for line in contents.lines() {
-        builder.add_line(Some(abs_path.into()), line)?;
+        // Like git, skip invalid patterns rather than disregarding the whole file.
+        if let Err(error) = builder.add_line(Some(abs_path.into()), line) {
+            log::warn!("skipping invalid pattern in {abs_path:?}: {error}");
+        }
     }
     Ok(builder.build()?)
 }
@@ -2933,6 +3277,14 @@ impl language::LocalFile for File {
         cx.background_executor()
             .spawn(async move { fs.load(&abs_path?).await })
     }
+
+    fn load_bytes(&self, cx: &AppContext) -> Task<Result<Vec<u8>>> {
+        let worktree = self.worktree.read(cx).as_local().unwrap();
+        let abs_path = worktree.absolutize(&self.path);
+        let fs = worktree.fs.clone();
+        cx.background_executor()
+            .spawn(async move { fs.load_bytes(&abs_path?).await })
+    }
 }
 
 impl File {
@@ -3262,7 +3614,7 @@ struct BackgroundScanner {
     status_updates_tx: UnboundedSender<ScanState>,
     executor: BackgroundExecutor,
     scan_requests_rx: channel::Receiver<ScanRequest>,
-    path_prefixes_to_scan_rx: channel::Receiver<Arc<Path>>,
+    path_prefixes_to_scan_rx: channel::Receiver<PathPrefixScanRequest>,
     next_entry_id: Arc<AtomicUsize>,
     phase: BackgroundScannerPhase,
     watcher: Arc<dyn Watcher>,
@@ -3285,6 +3637,8 @@ impl BackgroundScanner {
         // the git repository in an ancestor directory. Find any gitignore files
         // in ancestor directories.
         let root_abs_path = self.state.lock().snapshot.abs_path.clone();
+        let scan_span = timings::start(format!("scan {}", root_abs_path.display()));
+        let ancestors_span = scan_span.child("find ancestor repositories");
         for (index, ancestor) in root_abs_path.ancestors().enumerate() {
             if index != 0 {
                 if let Ok(ignore) =
@@ -3324,6 +3678,7 @@ impl BackgroundScanner {
                 break;
             }
         }
+        ancestors_span.end();
 
         let (scan_job_tx, scan_job_rx) = channel::unbounded();
         {
@@ -3337,13 +3692,17 @@ impl BackgroundScanner {
                     root_entry.is_ignored = true;
                     state.insert_entry(root_entry.clone(), self.fs.as_ref());
                 }
-                state.enqueue_scan_dir(root_abs_path, &root_entry, &scan_job_tx);
+                state.enqueue_scan_dir(root_abs_path.clone(), &root_entry, &scan_job_tx);
             }
         };
 
         // Perform an initial scan of the directory.
         drop(scan_job_tx);
+        let t0 = Instant::now();
+        let dirs_span = scan_span.child("scan directories");
         self.scan_dirs(true, scan_job_rx).await;
+        dirs_span.end();
+        log::debug!("initial scan of {root_abs_path:?} took {:?}", t0.elapsed());
         {
             let mut state = self.state.lock();
             state.snapshot.completed_scan_id = state.snapshot.scan_id;
@@ -3359,8 +3718,10 @@ impl BackgroundScanner {
             while let Poll::Ready(Some(more_paths)) = futures::poll!(fs_events_rx.next()) {
                 paths.extend(more_paths);
             }
+            let _events_span = scan_span.child("process events received during scan");
             self.process_events(paths).await;
         }
+        scan_span.end();
 
         // Continue processing events until the worktree is dropped.
         self.phase = BackgroundScannerPhase::Events;
@@ -3376,8 +3737,10 @@ impl BackgroundScanner {
                     }
                 }
 
-                path_prefix = self.path_prefixes_to_scan_rx.recv().fuse() => {
-                    let Ok(path_prefix) = path_prefix else { break };
+                request = self.path_prefixes_to_scan_rx.recv().fuse() => {
+                    let Ok(PathPrefixScanRequest { path: path_prefix, done }) = request else {
+                        break
+                    };
                     log::trace!("adding path prefix {:?}", path_prefix);
 
                     let did_scan = self.forcibly_load_paths(&[path_prefix.clone()]).await;
@@ -3393,6 +3756,7 @@ impl BackgroundScanner {
                             self.process_events(vec![abs_path]).await;
                         }
                     }
+                    drop(done);
                 }
 
                 paths = fs_events_rx.next().fuse() => {
@@ -3663,10 +4027,17 @@ impl BackgroundScanner {
             return true;
         }
 
-        let new_snapshot = state.snapshot.clone();
-        let old_snapshot = mem::replace(&mut state.prev_snapshot, new_snapshot.snapshot.clone());
-        let changes = self.build_change_set(&old_snapshot, &new_snapshot, &state.changed_paths);
+        let state = &mut *state;
+        let changes =
+            self.build_change_set(&state.prev_snapshot, &state.snapshot, &state.changed_paths);
         state.changed_paths.clear();
+        let snapshot = &mut state.snapshot.snapshot;
+        snapshot.candidate_index.apply_changes(
+            changes.iter().map(|(path, _, change)| (path, *change)),
+            &snapshot.entries_by_path,
+        );
+        let new_snapshot = state.snapshot.clone();
+        state.prev_snapshot = new_snapshot.snapshot.clone();
 
         self.status_updates_tx
             .unbounded_send(ScanState::Updated {
@@ -4092,7 +4463,7 @@ impl BackgroundScanner {
         drop(ignore_queue_tx);
 
         self.executor
-            .scoped(|scope| {
+            .scoped_with_priority(TaskPriority::Low, |scope| {
                 for _ in 0..self.executor.num_cpus() {
                     scope.spawn(async {
                         loop {
@@ -4294,7 +4665,7 @@ impl BackgroundScanner {
 
         let (mut updates_done_tx, mut updates_done_rx) = barrier::channel();
         self.executor
-            .scoped(|scope| {
+            .scoped_with_priority(TaskPriority::Low, |scope| {
                 scope.spawn(async {
                     for repo_update in repo_updates {
                         self.update_git_statuses(repo_update);
@@ -4988,8 +5359,15 @@ impl ProjectEntryId {
     pub const MAX: Self = Self(usize::MAX);
     pub const MIN: Self = Self(usize::MIN);
 
+    /// Allocates the next id of the given counter. Panics rather than reusing ids once the
+    /// counter runs out, and never allocates [`ProjectEntryId::MAX`].
     pub fn new(counter: &AtomicUsize) -> Self {
-        Self(counter.fetch_add(1, SeqCst))
+        let id = counter
+            .fetch_update(SeqCst, SeqCst, |id| {
+                id.checked_add(1).filter(|next| *next < Self::MAX.0)
+            })
+            .unwrap_or_else(|id| panic!("project entry ids overflowed at {id}"));
+        Self(id)
     }
 
     pub fn from_proto(id: u64) -> Self {
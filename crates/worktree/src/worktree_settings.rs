@@ -4,12 +4,14 @@ use gpui::AppContext;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use settings::{Settings, SettingsSources};
+use text::Encoding;
 use util::paths::PathMatcher;
 
 #[derive(Clone, PartialEq, Eq)]
 pub struct WorktreeSettings {
     pub file_scan_exclusions: Arc<[PathMatcher]>,
     pub private_files: Arc<[PathMatcher]>,
+    pub fallback_encoding: Encoding,
 }
 
 impl WorktreeSettings {
@@ -50,6 +52,12 @@ pub struct WorktreeSettingsContent {
     /// Treat the files matching these globs as `.env` files.
     /// Default: [ "**/.env*" ]
     pub private_files: Option<Vec<String>>,
+
+    /// The encoding to read files with when they have no byte order mark and aren't
+    /// valid UTF-8.
+    ///
+    /// Default: "windows-1252"
+    pub fallback_encoding: Option<String>,
 }
 
 impl Settings for WorktreeSettings {
@@ -66,9 +74,17 @@ impl Settings for WorktreeSettings {
         let mut private_files = result.private_files.unwrap_or_default();
         file_scan_exclusions.sort();
         private_files.sort();
+        let fallback_encoding = match result.fallback_encoding {
+            Some(name) => Encoding::from_name(&name).unwrap_or_else(|| {
+                log::error!("Unknown encoding {name:?} in `fallback_encoding` project settings");
+                Encoding::Windows1252
+            }),
+            None => Encoding::Windows1252,
+        };
         Ok(Self {
             file_scan_exclusions: path_matchers(&file_scan_exclusions, "file_scan_exclusions"),
             private_files: path_matchers(&private_files, "private_files"),
+            fallback_encoding,
         })
     }
 }
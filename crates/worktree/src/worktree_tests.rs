@@ -1,6 +1,6 @@
 use crate::{
-    worktree_settings::WorktreeSettings, Entry, EntryKind, Event, PathChange, Snapshot, Worktree,
-    WorktreeModelHandle,
+    worktree_settings::WorktreeSettings, Entry, EntryKind, Event, PathChange, ProjectEntryId,
+    Snapshot, Worktree, WorktreeModelHandle,
 };
 use anyhow::Result;
 use fs::{FakeFs, Fs, RealFs, RemoveOptions};
@@ -12,7 +12,16 @@ use pretty_assertions::assert_eq;
 use rand::prelude::*;
 use serde_json::json;
 use settings::{Settings, SettingsStore};
-use std::{env, fmt::Write, mem, path::Path, sync::Arc};
+use std::{
+    env,
+    fmt::Write,
+    mem, panic,
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering::SeqCst},
+        Arc,
+    },
+};
 use util::{test::temp_tree, ResultExt};
 
 #[gpui::test]
@@ -667,6 +676,117 @@ async fn test_dirs_no_longer_ignored(cx: &mut TestAppContext) {
     assert_eq!(read_dir_count_3 - read_dir_count_2, 2);
 }
 
+#[gpui::test]
+async fn test_gitignore_semantics(cx: &mut TestAppContext) {
+    const ROOT_GITIGNORE: &str = concat!(
+        "# Comments and blank lines are skipped\n",
+        "\n",
+        "*.log\n",
+        "!important.log\n",
+        "/build\n",
+        "docs/generated/\n",
+        "**/cache\n",
+        "tmp/\n",
+        "!tmp/keep.txt\n",
+        "node_modules\n",
+        "foo/**/bar.txt\n",
+        "\\#literal\n",
+        "unclosed[\n",
+    );
+
+    init_test(cx);
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings::<WorktreeSettings>(cx, |project_settings| {
+                project_settings.file_scan_exclusions = Some(Vec::new());
+            });
+        });
+    });
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            ".git": {},
+            ".gitignore": ROOT_GITIGNORE,
+            "#literal": "",
+            "important.log": "",
+            "error.log": "",
+            "generated": "",
+            "build": { "out.o": "" },
+            "cache": { "data": "" },
+            "deep": { "a": { "b": { "cache": { "data": "" } } } },
+            "docs": {
+                "generated": { "api.md": "" },
+                "guide.md": "",
+            },
+            "foo": {
+                "a": { "b": { "bar.txt": "" } },
+                "bar.txt": "",
+                "baz.txt": "",
+            },
+            "lib": { "tmp": "" },
+            "node_modules": { "pkg": { "index.js": "" } },
+            "src": {
+                "build": { "main.rs": "" },
+                "cache": { "data": "" },
+            },
+            "tmp": {
+                "keep.txt": "",
+                "other.txt": "",
+            },
+            "packages": {
+                "app": {
+                    ".gitignore": "/dist\n*.tmp\n!keep.tmp\ngenerated/\n",
+                    "a.tmp": "",
+                    "keep.tmp": "",
+                    "dist": { "bundle.js": "" },
+                    "generated": { "types.rs": "" },
+                    "node_modules": { "index.js": "" },
+                    "src": {
+                        ".gitignore": "!debug.log\n",
+                        "debug.log": "",
+                        "error.log": "",
+                        "main.rs": "",
+                        "dist": { "main.rs": "" },
+                    },
+                },
+            },
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        "/root".as_ref(),
+        true,
+        fs.clone(),
+        Default::default(),
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    // The expected statuses were produced by running `git check-ignore` on the same tree.
+    let expected = include_str!("../test_data/check_ignore.txt");
+    tree.read_with(cx, |tree, _| {
+        let snapshot = tree.as_local().unwrap().snapshot();
+        for line in expected.lines().filter(|line| !line.starts_with('#')) {
+            let (status, path) = line.split_once(' ').unwrap();
+            let is_dir = path.ends_with('/');
+            let path = Path::new(path.trim_end_matches('/'));
+            let abs_path = Path::new("/root").join(path);
+            let is_ignored = snapshot
+                .ignore_stack_for_abs_path(&abs_path, is_dir)
+                .is_abs_path_ignored(&abs_path, is_dir);
+            assert_eq!(is_ignored, status == "ignored", "status of {path:?}");
+            if let Some(entry) = snapshot.entry_for_path(path) {
+                assert_eq!(entry.is_ignored, is_ignored, "entry for {path:?}");
+            }
+        }
+    });
+}
+
 #[gpui::test(iterations = 10)]
 async fn test_rescan_with_gitignore(cx: &mut TestAppContext) {
     init_test(cx);
@@ -850,6 +970,7 @@ async fn test_write_file(cx: &mut TestAppContext) {
             Path::new("tracked-dir/file.txt"),
             "hello".into(),
             Default::default(),
+            Default::default(),
             cx,
         )
     })
@@ -860,6 +981,7 @@ async fn test_write_file(cx: &mut TestAppContext) {
             Path::new("ignored-dir/file.txt"),
             "world".into(),
             Default::default(),
+            Default::default(),
             cx,
         )
     })
@@ -874,6 +996,86 @@ async fn test_write_file(cx: &mut TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_read_only_worktree(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            "a.txt": "a",
+            "dir": {
+                "b.txt": "b",
+            },
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        "/root".as_ref(),
+        true,
+        fs,
+        Default::default(),
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    tree.update(cx, |tree, cx| tree.set_read_only(true, cx));
+    let entry_id = tree.read_with(cx, |tree, _| {
+        assert!(tree.is_read_only());
+        tree.entry_for_path("a.txt").unwrap().id
+    });
+
+    let create = tree.update(cx, |tree, cx| {
+        tree.create_entry(Path::new("c.txt"), false, cx)
+    });
+    assert!(create.await.is_err());
+    let rename = tree.update(cx, |tree, cx| {
+        tree.rename_entry(entry_id, Path::new("d.txt"), cx)
+    });
+    assert!(rename.await.is_err());
+    let copy = tree.update(cx, |tree, cx| {
+        tree.copy_entry(entry_id, Path::new("e.txt"), cx)
+    });
+    assert!(copy.await.is_err());
+    let delete = tree
+        .update(cx, |tree, cx| tree.delete_entry(entry_id, false, cx))
+        .unwrap();
+    assert!(delete.await.is_err());
+    let write = tree.update(cx, |tree, cx| {
+        tree.write_file(
+            Path::new("a.txt"),
+            "x".into(),
+            Default::default(),
+            Default::default(),
+            cx,
+        )
+    });
+    assert!(write.await.is_err());
+
+    cx.executor().run_until_parked();
+    tree.read_with(cx, |tree, _| {
+        assert!(tree.entry_for_path("a.txt").is_some());
+        assert!(tree.entry_for_path("c.txt").is_none());
+        assert!(tree.entry_for_path("d.txt").is_none());
+        assert!(tree.entry_for_path("e.txt").is_none());
+    });
+
+    tree.update(cx, |tree, cx| tree.set_read_only(false, cx));
+    tree.update(cx, |tree, cx| {
+        tree.create_entry(Path::new("c.txt"), false, cx)
+    })
+    .await
+    .unwrap();
+    cx.executor().run_until_parked();
+    tree.read_with(cx, |tree, _| {
+        assert!(tree.entry_for_path("c.txt").is_some());
+    });
+}
+
 #[gpui::test]
 async fn test_file_scan_exclusions(cx: &mut TestAppContext) {
     init_test(cx);
@@ -1379,7 +1581,9 @@ async fn test_random_worktree_operations_during_initial_scan(
             .await
             .log_err();
         worktree.read_with(cx, |tree, _| {
-            tree.as_local().unwrap().snapshot().check_invariants(true)
+            let snapshot = tree.as_local().unwrap().snapshot();
+            snapshot.check_invariants(true);
+            snapshot.check_candidate_index();
         });
 
         if rng.gen_bool(0.6) {
@@ -1397,6 +1601,7 @@ async fn test_random_worktree_operations_during_initial_scan(
         let tree = tree.as_local().unwrap();
         let snapshot = tree.snapshot();
         snapshot.check_invariants(true);
+        snapshot.check_candidate_index();
         snapshot
     });
 
@@ -1415,6 +1620,7 @@ async fn test_random_worktree_operations_during_initial_scan(
             final_snapshot.entries(true).collect::<Vec<_>>(),
             "wrong updates after snapshot {i}: {snapshot:#?} {updates:#?}",
         );
+        updated_snapshot.check_candidate_index();
     }
 }
 
@@ -1502,6 +1708,7 @@ async fn test_random_worktree_changes(cx: &mut TestAppContext, mut rng: StdRng)
 
     let snapshot = worktree.read_with(cx, |tree, _| tree.as_local().unwrap().snapshot());
     snapshot.check_invariants(true);
+    snapshot.check_candidate_index();
     let expanded_paths = snapshot
         .expanded_entries()
         .map(|e| e.path.clone())
@@ -1554,6 +1761,7 @@ async fn test_random_worktree_changes(cx: &mut TestAppContext, mut rng: StdRng)
                 .collect::<Vec<_>>(),
             "wrong updates after snapshot {i}: {updates:#?}",
         );
+        prev_snapshot.check_candidate_index();
     }
 
     fn ignore_pending_dir(entry: &Entry) -> Entry {
@@ -1658,8 +1866,13 @@ fn randomly_mutate_worktree(
                 })
             } else {
                 log::info!("overwriting file {:?} ({})", entry.path, entry.id.0);
-                let task =
-                    worktree.write_file(entry.path.clone(), "".into(), Default::default(), cx);
+                let task = worktree.write_file(
+                    entry.path.clone(),
+                    "".into(),
+                    Default::default(),
+                    Default::default(),
+                    cx,
+                );
                 cx.background_executor().spawn(async move {
                     task.await?;
                     Ok(())
@@ -2426,6 +2639,17 @@ async fn test_propagate_git_statuses(cx: &mut TestAppContext) {
     }
 }
 
+#[test]
+fn test_entry_ids_near_overflow() {
+    let counter = AtomicUsize::new(usize::MAX - 2);
+    assert_eq!(ProjectEntryId::new(&counter).to_usize(), usize::MAX - 2);
+
+    // The last id is reserved for `ProjectEntryId::MAX`, and ids are never reused.
+    assert!(panic::catch_unwind(|| ProjectEntryId::new(&counter)).is_err());
+    assert!(panic::catch_unwind(|| ProjectEntryId::new(&counter)).is_err());
+    assert_eq!(counter.load(SeqCst), usize::MAX - 1);
+}
+
 #[track_caller]
 fn git_init(path: &Path) -> git2::Repository {
     git2::Repository::init(path).expect("Failed to initialize git repository")
@@ -0,0 +1,218 @@
+//! Logs to stderr and to the log file, which is rotated once it grows too large.
+//!
+//! Which messages are logged is controlled by the `log_level` setting, or by the `ZED_LOG`
+//! environment variable when it's set. Both use the `env_logger` syntax, so that a module
+//! can be made more verbose with e.g. `info,client=debug`.
+
+use anyhow::Result;
+use env_logger::filter::{Builder as FilterBuilder, Filter};
+use gpui::AppContext;
+use parking_lot::{Mutex, RwLock};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use settings::{Settings, SettingsSources, SettingsStore};
+use std::{
+    env,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+use util::paths;
+
+const DEFAULT_LOG_LEVEL: &str = "info";
+const KIB: u64 = 1024;
+const MIB: u64 = 1024 * KIB;
+const MAX_LOG_BYTES: u64 = MIB;
+/// How many rotated log files are kept next to the current one.
+const MAX_OLD_LOGS: usize = 4;
+
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+
+#[derive(Deserialize)]
+pub struct LogSettings {
+    pub log_level: String,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct LogSettingsContent {
+    /// Which messages are logged, e.g. "info" or "warn,client=debug".
+    ///
+    /// Default: "info"
+    pub log_level: Option<String>,
+}
+
+impl Settings for LogSettings {
+    const KEY: Option<&'static str> = None;
+
+    type FileContent = LogSettingsContent;
+
+    fn load(sources: SettingsSources<Self::FileContent>, _: &mut AppContext) -> Result<Self> {
+        sources.json_merge()
+    }
+}
+
+/// Starts logging. Until the settings are loaded, the level comes from the environment.
+pub fn init() {
+    let (log_file, log_file_error) = match LogFile::open(paths::LOG.clone(), MAX_LOG_BYTES) {
+        Ok(log_file) => (Some(log_file), None),
+        Err(err) => (None, Some(err)),
+    };
+    let logger = LOGGER.get_or_init(|| Logger {
+        filter: RwLock::new(LogFilter::new(
+            env_log_level().as_deref().unwrap_or(DEFAULT_LOG_LEVEL),
+        )),
+        file: Mutex::new(log_file),
+    });
+    if log::set_logger(logger).is_ok() {
+        log::set_max_level(logger.filter.read().filter.filter());
+    }
+
+    if let Some(err) = log_file_error {
+        log::error!("could not open log file, logging to stderr only: {err}");
+    }
+}
+
+/// Registers the `log_level` setting and applies it whenever it changes.
+pub fn init_settings(cx: &mut AppContext) {
+    LogSettings::register(cx);
+    apply_log_level_setting(cx);
+    cx.observe_global::<SettingsStore>(apply_log_level_setting)
+        .detach();
+}
+
+fn apply_log_level_setting(cx: &mut AppContext) {
+    // The environment variable is meant to override the settings while debugging.
+    if env_log_level().is_some() {
+        return;
+    }
+    if let Some(logger) = LOGGER.get() {
+        logger.set_log_level(&LogSettings::get_global(cx).log_level);
+    }
+}
+
+fn env_log_level() -> Option<String> {
+    env::var("ZED_LOG")
+        .or_else(|_| env::var("RUST_LOG"))
+        .ok()
+        .filter(|level| !level.trim().is_empty())
+}
+
+struct LogFilter {
+    spec: String,
+    filter: Filter,
+}
+
+impl LogFilter {
+    fn new(spec: &str) -> Self {
+        Self {
+            spec: spec.to_string(),
+            filter: FilterBuilder::new().parse(spec).build(),
+        }
+    }
+}
+
+struct Logger {
+    filter: RwLock<LogFilter>,
+    file: Mutex<Option<LogFile>>,
+}
+
+impl Logger {
+    fn set_log_level(&self, spec: &str) {
+        let mut filter = self.filter.write();
+        if filter.spec != spec {
+            *filter = LogFilter::new(spec);
+            log::set_max_level(filter.filter.filter());
+        }
+    }
+}
+
+impl log::Log for Logger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.filter.read().filter.enabled(metadata)
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.filter.read().filter.matches(record) {
+            return;
+        }
+
+        let mut line = format!(
+            "{} {:<5}",
+            chrono::Local::now().format("%Y-%m-%dT%H:%M:%S%:z"),
+            record.level()
+        );
+        if let Some(module_path) = record.module_path() {
+            line.push(' ');
+            line.push_str(module_path);
+        }
+        line.push_str(&format!("] {}\n", record.args()));
+
+        io::stderr().write_all(line.as_bytes()).ok();
+        if let Some(file) = self.file.lock().as_mut() {
+            file.write(line.as_bytes());
+        }
+    }
+
+    fn flush(&self) {
+        io::stderr().flush().ok();
+        if let Some(file) = self.file.lock().as_mut() {
+            file.file.flush().ok();
+        }
+    }
+}
+
+struct LogFile {
+    path: PathBuf,
+    file: File,
+    len: u64,
+    max_len: u64,
+}
+
+impl LogFile {
+    fn open(path: PathBuf, max_len: u64) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let len = file.metadata()?.len();
+        Ok(Self {
+            path,
+            file,
+            len,
+            max_len,
+        })
+    }
+
+    fn write(&mut self, line: &[u8]) {
+        if self.len > 0 && self.len + line.len() as u64 > self.max_len {
+            if let Err(err) = self.rotate() {
+                eprintln!("could not rotate log file {:?}: {err}", self.path);
+            }
+        }
+        if self.file.write_all(line).is_ok() {
+            self.len += line.len() as u64;
+        }
+    }
+
+    /// Moves the current log file to `<name>.old`, the previous `<name>.old` to `<name>.old.2`
+    /// and so on, dropping the oldest one.
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        for index in (1..MAX_OLD_LOGS).rev() {
+            let from = old_log_path(&self.path, index);
+            if from.exists() {
+                fs::rename(from, old_log_path(&self.path, index + 1))?;
+            }
+        }
+        fs::rename(&self.path, old_log_path(&self.path, 1))?;
+        *self = Self::open(self.path.clone(), self.max_len)?;
+        Ok(())
+    }
+}
+
+fn old_log_path(path: &Path, index: usize) -> PathBuf {
+    let mut old_path = path.as_os_str().to_owned();
+    old_path.push(".old");
+    if index > 1 {
+        old_path.push(format!(".{index}"));
+    }
+    PathBuf::from(old_path)
+}
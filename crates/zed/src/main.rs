@@ -3,6 +3,7 @@
 // Disable command line from opening on release mode
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod logger;
 mod reliability;
 mod zed;
 
@@ -13,7 +14,6 @@ use client::{parse_zed_link, Client, DevServerToken, UserStore};
 use collab_ui::channel_view::ChannelView;
 use db::kvp::KEY_VALUE_STORE;
 use editor::Editor;
-use env_logger::Builder;
 use fs::RealFs;
 use futures::{future, StreamExt};
 use git::GitHostingProviderRegistry;
@@ -22,31 +22,23 @@ use gpui::{
 };
 use image_viewer;
 use language::LanguageRegistry;
-use log::LevelFilter;
 
 use assets::Assets;
 use node_runtime::RealNodeRuntime;
 use parking_lot::Mutex;
 use release_channel::{AppCommitSha, AppVersion};
 use settings::{handle_settings_file_changes, watch_config_file, Settings, SettingsStore};
-use simplelog::ConfigBuilder;
 use smol::process::Command;
-use std::{
-    env,
-    fs::OpenOptions,
-    io::{IsTerminal, Write},
-    path::Path,
-    process,
-    sync::Arc,
-};
+use std::{env, io::IsTerminal, path::Path, process, sync::Arc};
 use theme::{ActiveTheme, SystemAppearance, ThemeRegistry, ThemeSettings};
-use util::{maybe, parse_env_output, paths, with_clone, ResultExt, TryFutureExt};
+use util::{maybe, parse_env_output, paths, timings, with_clone, ResultExt, TryFutureExt};
 use uuid::Uuid;
 use welcome::{show_welcome_view, BaseKeymap, FIRST_OPEN};
 use workspace::{AppState, WorkspaceSettings, WorkspaceStore};
 use zed::{
     app_menus, build_window_options, handle_cli_connection, handle_keymap_file_changes,
-    initialize_workspace, open_paths_with_positions, OpenListener, OpenRequest,
+    handle_settings_changed, initialize_workspace, open_paths_with_positions, OpenListener,
+    OpenRequest,
 };
 
 use crate::zed::inline_completion_registry;
@@ -165,15 +157,21 @@ fn init_ui(app_state: Arc<AppState>, cx: &mut AppContext) -> Result<()> {
         }
     };
 
+    let _span = timings::span("init ui");
     SystemAppearance::init(cx);
     load_embedded_fonts(cx);
 
-    theme::init(theme::LoadThemes::All(Box::new(Assets)), cx);
+    {
+        let _span = timings::span("load themes");
+        theme::init(theme::LoadThemes::All(Box::new(Assets)), cx);
+    }
     app_state.languages.set_theme(cx.theme().clone());
     command_palette::init(cx);
+    spell_check::init(app_state.fs.clone(), cx);
     editor::init(cx);
     image_viewer::init(cx);
     diagnostics::init(cx);
+    diff_view::init(cx);
 
     audio::init(Assets, cx);
     workspace::init(app_state.clone(), cx);
@@ -183,6 +181,7 @@ fn init_ui(app_state: Arc<AppState>, cx: &mut AppContext) -> Result<()> {
     file_finder::init(cx);
     tab_switcher::init(cx);
     outline::init(cx);
+    marks::init(cx);
     project_symbols::init(cx);
     project_panel::init(Assets, cx);
     outline_panel::init(Assets, cx);
@@ -193,7 +192,11 @@ fn init_ui(app_state: Arc<AppState>, cx: &mut AppContext) -> Result<()> {
     terminal_view::init(cx);
 
     journal::init(app_state.clone(), cx);
+    key_bindings::init(cx);
     language_selector::init(cx);
+    line_ending_selector::init(cx);
+    indentation_selector::init(cx);
+    encoding_selector::init(cx);
     theme_selector::init(cx);
     language_tools::init(cx);
     call::init(app_state.client.clone(), app_state.user_store.clone(), cx);
@@ -268,6 +271,10 @@ fn init_ui(app_state: Arc<AppState>, cx: &mut AppContext) -> Result<()> {
     cx.set_menus(app_menus());
     initialize_workspace(app_state.clone(), cx);
 
+    if let Ok(socket_path) = env::var("ZED_AUTOMATION_SOCKET") {
+        automation::serve(socket_path.into(), cx);
+    }
+
     cx.activate(true);
 
     cx.spawn(|cx| async move { authenticate(app_state.client.clone(), &cx).await })
@@ -277,6 +284,8 @@ fn init_ui(app_state: Arc<AppState>, cx: &mut AppContext) -> Result<()> {
 }
 
 fn main() {
+    timings::enable();
+    let startup_span = timings::span("startup");
     menu::init();
     zed_actions::init();
 
@@ -285,7 +294,7 @@ fn main() {
         return;
     }
 
-    init_logger();
+    logger::init();
 
     log::info!("========== starting zed ==========");
     let app = App::new().with_assets(Assets);
@@ -302,19 +311,15 @@ fn main() {
 
     let (open_listener, mut open_rx) = OpenListener::new();
 
-    #[cfg(target_os = "linux")]
-    {
-        if env::var("ZED_STATELESS").is_err() {
-            if crate::zed::listen_for_cli_connections(open_listener.clone()).is_err() {
-                println!("zed is already running");
-                return;
-            }
-        }
-    }
-    #[cfg(not(target_os = "linux"))]
+    let args = Args::parse();
     {
         use zed::only_instance::*;
-        if ensure_only_instance() != IsOnlyInstance::Yes {
+        let urls = args
+            .paths_or_urls
+            .iter()
+            .map(|arg| forwarded_url_arg(arg))
+            .collect::<Vec<_>>();
+        if ensure_only_instance(open_listener.clone(), &urls) != IsOnlyInstance::Yes {
             println!("zed is already running");
             return;
         }
@@ -383,10 +388,20 @@ fn main() {
         git_hosting_providers::init(cx);
 
         OpenListener::set_global(cx, open_listener.clone());
+        cx.on_app_quit(|_| {
+            zed::only_instance::release_instance_lock();
+            async {}
+        })
+        .detach();
 
+        cx.set_global(StartupSpan(startup_span));
         settings::init(cx);
-        handle_settings_file_changes(user_settings_file_rx, cx);
-        handle_keymap_file_changes(user_keymap_file_rx, cx);
+        logger::init_settings(cx);
+        {
+            let _span = timings::span("load settings");
+            handle_settings_file_changes(user_settings_file_rx, cx, handle_settings_changed);
+            handle_keymap_file_changes(user_keymap_file_rx, cx);
+        }
 
         client::init_settings(cx);
         let client = Client::production(cx);
@@ -432,7 +447,6 @@ fn main() {
 
         reliability::init(client.http_client(), installation_id, cx);
 
-        let args = Args::parse();
         let urls: Vec<_> = args
             .paths_or_urls
             .iter()
@@ -462,6 +476,7 @@ fn main() {
                             cx.update(|cx| cx.quit()).log_err();
                         } else {
                             log::info!("connected!");
+                            cx.update(StartupSpan::finish).log_err();
                         }
                     })
                     .detach();
@@ -473,6 +488,7 @@ fn main() {
                             if let Err(e) = restore_or_create_workspace(app_state, &mut cx).await {
                                 fail_to_open_window_async(e, &mut cx)
                             }
+                            cx.update(StartupSpan::finish).log_err();
                         }
                     })
                     .detach();
@@ -484,7 +500,14 @@ fn main() {
         cx.spawn(move |cx| async move {
             while let Some(urls) = open_rx.next().await {
                 cx.update(|cx| {
-                    if let Some(request) = OpenRequest::parse(urls, cx).log_err() {
+                    // Zed was launched again without anything to open.
+                    if urls.is_empty() {
+                        cx.spawn({
+                            let app_state = app_state.clone();
+                            |cx| workspace::get_any_active_workspace(app_state, cx)
+                        })
+                        .detach_and_log_err(cx);
+                    } else if let Some(request) = OpenRequest::parse(urls, cx).log_err() {
                         handle_open_request(request, app_state.clone(), cx);
                     }
                 })
@@ -573,6 +596,7 @@ fn handle_open_request(request: OpenRequest, app_state: Arc<AppState>, cx: &mut
             if let Err(err) = result {
                 fail_to_open_window_async(err, &mut cx);
             }
+            cx.update(StartupSpan::finish).log_err();
         })
         .detach()
     } else if let Some(task) = task {
@@ -580,8 +604,30 @@ fn handle_open_request(request: OpenRequest, app_state: Arc<AppState>, cx: &mut
             if let Err(err) = task.await {
                 fail_to_open_window_async(err, &mut cx);
             }
+            cx.update(StartupSpan::finish).log_err();
         })
         .detach();
+    } else {
+        StartupSpan::finish(cx);
+    }
+}
+
+/// The span of startup, which ends once the first workspace is open.
+struct StartupSpan(timings::Span<'static>);
+
+impl Global for StartupSpan {}
+
+impl StartupSpan {
+    /// Ends startup, unless it ended already, logging the timings of startup if the
+    /// `ZED_STARTUP_TIMINGS` environment variable is set.
+    fn finish(cx: &mut AppContext) {
+        if !cx.has_global::<Self>() {
+            return;
+        }
+        cx.remove_global::<Self>().0.end();
+        if env::var("ZED_STARTUP_TIMINGS").map_or(false, |value| value == "1" || value == "true") {
+            log::info!("startup timings:\n{}", timings::global().report());
+        }
     }
 }
 
@@ -628,6 +674,7 @@ async fn restore_or_create_workspace(
     app_state: Arc<AppState>,
     cx: &mut AsyncAppContext,
 ) -> Result<()> {
+    let _span = timings::start("restore workspace");
     let restore_behaviour = cx.update(|cx| WorkspaceSettings::get(None, cx).restore_on_startup)?;
     let location = match restore_behaviour {
         workspace::RestoreOnStartupBehaviour::LastWorkspace => {
@@ -673,74 +720,15 @@ fn init_paths() -> anyhow::Result<()> {
         std::fs::create_dir_all(path)
             .map_err(|e| anyhow!("Could not create directory {:?}: {}", path, e))?;
     }
-    Ok(())
-}
-
-fn init_logger() {
-    if stdout_is_a_pty() {
-        init_stdout_logger();
-    } else {
-        let level = LevelFilter::Info;
-
-        // Prevent log file from becoming too large.
-        const KIB: u64 = 1024;
-        const MIB: u64 = 1024 * KIB;
-        const MAX_LOG_BYTES: u64 = MIB;
-        if std::fs::metadata(&*paths::LOG).map_or(false, |metadata| metadata.len() > MAX_LOG_BYTES)
-        {
-            let _ = std::fs::rename(&*paths::LOG, &*paths::OLD_LOG);
-        }
-
-        match OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&*paths::LOG)
-        {
-            Ok(log_file) => {
-                let config = ConfigBuilder::new()
-                    .set_time_format_str("%Y-%m-%dT%T%:z")
-                    .set_time_to_local(true)
-                    .build();
-
-                simplelog::WriteLogger::init(level, config, log_file)
-                    .expect("could not initialize logger");
-            }
-            Err(err) => {
-                init_stdout_logger();
-                log::error!(
-                    "could not open log file, defaulting to stdout logging: {}",
-                    err
-                );
-            }
-        }
+    // Start users off with a settings file that explains where to find the defaults.
+    if !util::paths::SETTINGS.exists() {
+        std::fs::write(
+            &*util::paths::SETTINGS,
+            settings::initial_user_settings_content().as_bytes(),
+        )
+        .log_err();
     }
-}
-
-fn init_stdout_logger() {
-    Builder::new()
-        .parse_default_env()
-        .format(|buf, record| {
-            use env_logger::fmt::Color;
-
-            let subtle = buf
-                .style()
-                .set_color(Color::Black)
-                .set_intense(true)
-                .clone();
-            write!(buf, "{}", subtle.value("["))?;
-            write!(
-                buf,
-                "{} ",
-                chrono::Local::now().format("%Y-%m-%dT%H:%M:%S%:z")
-            )?;
-            write!(buf, "{:<5}", buf.default_styled_level(record.level()))?;
-            if let Some(path) = record.module_path() {
-                write!(buf, " {}", path)?;
-            }
-            write!(buf, "{}", subtle.value("]"))?;
-            writeln!(buf, " {}", record.args())
-        })
-        .init();
+    Ok(())
 }
 
 #[cfg(unix)]
@@ -879,6 +867,15 @@ fn parse_url_arg(arg: &str, cx: &AppContext) -> Result<String> {
     }
 }
 
+/// Like `parse_url_arg`, but for handing the argument over to an already running instance,
+/// which is the one to tell whether it is a valid URL.
+fn forwarded_url_arg(arg: &str) -> String {
+    match std::fs::canonicalize(Path::new(arg)) {
+        Ok(path) => format!("file://{}", path.to_string_lossy()),
+        Err(_) => arg.to_string(),
+    }
+}
+
 fn load_embedded_fonts(cx: &AppContext) {
     let asset_source = cx.asset_source();
     let font_paths = asset_source.list("fonts").unwrap();
@@ -905,9 +902,11 @@ fn load_embedded_fonts(cx: &AppContext) {
 
 /// Spawns a background task to load the user themes from the themes directory.
 fn load_user_themes_in_background(fs: Arc<dyn fs::Fs>, cx: &mut AppContext) {
+    let span = timings::start("load user themes");
     cx.spawn({
         let fs = fs.clone();
         |cx| async move {
+            let _span = span;
             if let Some(theme_registry) =
                 cx.update(|cx| ThemeRegistry::global(cx).clone()).log_err()
             {
@@ -947,19 +946,26 @@ fn watch_themes(fs: Arc<dyn fs::Fs>, cx: &mut AppContext) {
 
         while let Some(paths) = events.next().await {
             for path in paths {
+                let Some(theme_registry) =
+                    cx.update(|cx| ThemeRegistry::global(cx).clone()).log_err()
+                else {
+                    continue;
+                };
                 if fs.metadata(&path).await.ok().flatten().is_some() {
-                    if let Some(theme_registry) =
-                        cx.update(|cx| ThemeRegistry::global(cx).clone()).log_err()
+                    if let Some(()) = theme_registry
+                        .load_user_theme(&path, fs.clone())
+                        .await
+                        .log_err()
                     {
-                        if let Some(()) = theme_registry
-                            .load_user_theme(&path, fs.clone())
-                            .await
-                            .log_err()
-                        {
-                            cx.update(|cx| ThemeSettings::reload_current_theme(cx))
-                                .log_err();
-                        }
+                        cx.update(|cx| ThemeSettings::reload_current_theme(cx))
+                            .log_err();
                     }
+                } else {
+                    // Stop offering the themes of a deleted file. If one of them was in use,
+                    // the default theme takes its place.
+                    theme_registry.unload_user_theme(&path);
+                    cx.update(|cx| ThemeSettings::reload_current_theme(cx))
+                        .log_err();
                 }
             }
         }
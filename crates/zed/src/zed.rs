@@ -1,6 +1,5 @@
 mod app_menus;
 pub mod inline_completion_registry;
-#[cfg(not(target_os = "linux"))]
 pub(crate) mod only_instance;
 mod open_listener;
 
@@ -10,8 +9,9 @@ use client::ZED_URL_SCHEME;
 use collections::VecDeque;
 use editor::{scroll::Autoscroll, Editor, MultiBuffer};
 use gpui::{
-    actions, point, px, AppContext, AsyncAppContext, Context, FocusableView, MenuItem, PromptLevel,
-    ReadGlobal, TitlebarOptions, View, ViewContext, VisualContext, WindowKind, WindowOptions,
+    actions, point, px, Action, AppContext, AsyncAppContext, Context, FocusableView, KeyBinding,
+    MenuItem, PromptLevel, ReadGlobal, SharedString, TitlebarOptions, View, ViewContext,
+    VisualContext, WindowKind, WindowOptions,
 };
 pub use open_listener::*;
 
@@ -38,7 +38,7 @@ use terminal_view::terminal_panel::{self, TerminalPanel};
 use util::{
     asset_str,
     paths::{self, LOCAL_SETTINGS_RELATIVE_PATH, LOCAL_TASKS_RELATIVE_PATH},
-    ResultExt,
+    timings, ResultExt,
 };
 use uuid::Uuid;
 use vim::VimModeSetting;
@@ -48,7 +48,7 @@ use workspace::{
     open_new, AppState, NewFile, NewWindow, OpenLog, Toast, Workspace, WorkspaceSettings,
 };
 use workspace::{notifications::DetachAndPromptErr, Pane};
-use zed_actions::{OpenBrowser, OpenSettings, OpenZedUrl, Quit};
+use zed_actions::{OpenBrowser, OpenSettings, OpenZedUrl, Quit, ShowKeyBindings};
 
 actions!(
     zed,
@@ -71,6 +71,7 @@ actions!(
         ResetBufferFontSize,
         ResetDatabase,
         ShowAll,
+        ShowStartupTimings,
         ToggleFullScreen,
         Zoom,
     ]
@@ -141,19 +142,38 @@ pub fn initialize_workspace(app_state: Arc<AppState>, cx: &mut AppContext) {
             activity_indicator::ActivityIndicator::new(workspace, app_state.languages.clone(), cx);
         let active_buffer_language =
             cx.new_view(|_| language_selector::ActiveBufferLanguage::new(workspace));
+        let active_buffer_line_ending =
+            cx.new_view(|_| line_ending_selector::ActiveBufferLineEnding::new(workspace));
+        let active_buffer_indentation =
+            cx.new_view(|_| indentation_selector::ActiveBufferIndentation::new(workspace));
+        let active_buffer_encoding =
+            cx.new_view(|_| encoding_selector::ActiveBufferEncoding::new(workspace));
+        let active_item_state = cx.new_view(|_| workspace::ActiveItemState::new(workspace));
+        let background_task_indicator = cx.new_view(|cx| {
+            workspace::background_tasks::BackgroundTaskIndicator::new(workspace, cx)
+        });
         let vim_mode_indicator = cx.new_view(|cx| vim::ModeIndicator::new(cx));
         let cursor_position =
             cx.new_view(|_| go_to_line::cursor_position::CursorPosition::new(workspace));
         workspace.status_bar().update(cx, |status_bar, cx| {
             status_bar.add_left_item(diagnostic_summary, cx);
             status_bar.add_left_item(activity_indicator, cx);
+            status_bar.add_left_item(background_task_indicator, cx);
             status_bar.add_right_item(inline_completion_button, cx);
+            status_bar.add_right_item(active_item_state, cx);
+            status_bar.add_right_item(active_buffer_encoding, cx);
+            status_bar.add_right_item(active_buffer_indentation, cx);
+            status_bar.add_right_item(active_buffer_line_ending, cx);
             status_bar.add_right_item(active_buffer_language, cx);
             status_bar.add_right_item(vim_mode_indicator, cx);
             status_bar.add_right_item(cursor_position, cx);
         });
 
         auto_update::notify_of_any_new_update(cx);
+        // The settings get loaded before there are any workspaces to show their problems in.
+        if let Some(message) = settings_problems_message(cx) {
+            show_settings_notification(workspace, Some(message), cx);
+        }
 
         let handle = cx.view().downgrade();
         cx.on_window_should_close(move |cx| {
@@ -308,6 +328,9 @@ pub fn initialize_workspace(app_state: Arc<AppState>, cx: &mut AppContext) {
             .register_action(|workspace, _: &OpenLog, cx| {
                 open_log_file(workspace, cx);
             })
+            .register_action(|workspace, _: &ShowStartupTimings, cx| {
+                open_startup_timings(workspace, cx);
+            })
             .register_action(|workspace, _: &OpenLicenses, cx| {
                 open_bundled_file(
                     workspace,
@@ -651,10 +674,17 @@ pub fn handle_keymap_file_changes(
                 _ = base_keymap_rx.next() => {}
                 user_keymap_content = user_keymap_file_rx.next() => {
                     if let Some(user_keymap_content) = user_keymap_content {
-                        if let Some(keymap_content) = KeymapFile::parse(&user_keymap_content).log_err() {
-                            user_keymap = keymap_content;
-                        } else {
-                            continue
+                        match KeymapFile::parse(&user_keymap_content) {
+                            Ok(keymap_content) => {
+                                user_keymap = keymap_content.with_source(
+                                    &paths::KEYMAP.to_string_lossy(),
+                                    &user_keymap_content,
+                                )
+                            }
+                            Err(error) => {
+                                cx.update(|cx| show_keymap_error(&error, cx)).ok();
+                                continue;
+                            }
                         }
                     }
                 }
@@ -668,11 +698,221 @@ pub fn handle_keymap_file_changes(
 fn reload_keymaps(cx: &mut AppContext, keymap_content: &KeymapFile) {
     cx.clear_key_bindings();
     load_default_keymap(cx);
-    keymap_content.clone().add_to_cx(cx).log_err();
+    match keymap_content.clone().add_to_cx(cx) {
+        Ok(()) => dismiss_keymap_error(cx),
+        Err(error) => show_keymap_error(&error, cx),
+    }
+    show_keymap_conflicts(keymap_conflicts_message(cx), cx);
     cx.set_menus(app_menus());
     cx.set_dock_menu(vec![MenuItem::action("New Window", workspace::NewWindow)])
 }
 
+struct KeymapError;
+
+/// Tells every open workspace about the problems with the user keymap, which are otherwise
+/// only visible in the log.
+fn show_keymap_error(error: &anyhow::Error, cx: &mut AppContext) {
+    log::error!("{error:?}");
+    let message = SharedString::from(format!("Error in your keymap: {error:#}"));
+    for window in cx.windows() {
+        let Some(workspace) = window.downcast::<Workspace>() else {
+            continue;
+        };
+        workspace
+            .update(cx, |workspace, cx| {
+                workspace.show_notification(NotificationId::unique::<KeymapError>(), cx, |cx| {
+                    cx.new_view(|_| {
+                        MessageNotification::new(message.clone())
+                            .with_click_message("Open Keymap")
+                            .on_click(|cx| cx.dispatch_action(OpenKeymap.boxed_clone()))
+                    })
+                });
+            })
+            .log_err();
+    }
+}
+
+fn dismiss_keymap_error(cx: &mut AppContext) {
+    for window in cx.windows() {
+        let Some(workspace) = window.downcast::<Workspace>() else {
+            continue;
+        };
+        workspace
+            .update(cx, |workspace, cx| {
+                workspace.dismiss_notification(&NotificationId::unique::<KeymapError>(), cx)
+            })
+            .log_err();
+    }
+}
+
+struct KeymapConflicts;
+
+/// The most conflicts with the user keymap that get listed in their notification.
+const MAX_SHOWN_KEYMAP_CONFLICTS: usize = 5;
+
+/// Lists the bindings of the user keymap that conflict with other bindings, or that other
+/// bindings conflict with, if there are any.
+fn keymap_conflicts_message(cx: &AppContext) -> Option<SharedString> {
+    let user_keymap = paths::KEYMAP.to_string_lossy();
+    let from_user_keymap = |binding: &KeyBinding| {
+        binding
+            .source()
+            .map_or(false, |source| source.starts_with(user_keymap.as_ref()))
+    };
+    let conflicts = cx
+        .key_binding_conflicts()
+        .into_iter()
+        .filter(|conflict| {
+            from_user_keymap(&conflict.binding) || from_user_keymap(&conflict.shadowed)
+        })
+        .collect::<Vec<_>>();
+    if conflicts.is_empty() {
+        return None;
+    }
+
+    let describe = |binding: &KeyBinding| {
+        let source = binding
+            .source()
+            .map_or("an unknown source", |source| source.as_ref());
+        match binding.context_predicate() {
+            Some(predicate) => format!("{} ({source}, in {predicate})", binding.action().name()),
+            None => format!("{} ({source})", binding.action().name()),
+        }
+    };
+    let conflicts = conflicts
+        .iter()
+        .map(|conflict| {
+            let keystrokes = conflict
+                .binding
+                .keystrokes()
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(" ");
+            format!(
+                "{keystrokes}: {} takes precedence over {}",
+                describe(&conflict.binding),
+                describe(&conflict.shadowed)
+            )
+        })
+        .collect::<Vec<_>>();
+    for conflict in &conflicts {
+        log::warn!("conflicting key bindings, {conflict}");
+    }
+
+    let mut message = String::from("Some of your key bindings conflict with others:");
+    for conflict in conflicts.iter().take(MAX_SHOWN_KEYMAP_CONFLICTS) {
+        message.push_str("\n• ");
+        message.push_str(conflict);
+    }
+    if conflicts.len() > MAX_SHOWN_KEYMAP_CONFLICTS {
+        message.push_str(&format!(
+            "\n…and {} more",
+            conflicts.len() - MAX_SHOWN_KEYMAP_CONFLICTS
+        ));
+    }
+    Some(message.into())
+}
+
+/// Warns every open workspace about the conflicts with the user keymap, or dismisses that
+/// warning once there are none.
+fn show_keymap_conflicts(message: Option<SharedString>, cx: &mut AppContext) {
+    for window in cx.windows() {
+        let Some(workspace) = window.downcast::<Workspace>() else {
+            continue;
+        };
+        workspace
+            .update(cx, |workspace, cx| {
+                let id = NotificationId::unique::<KeymapConflicts>();
+                if let Some(message) = message.clone() {
+                    workspace.show_notification(id, cx, |cx| {
+                        cx.new_view(|_| {
+                            MessageNotification::new(message)
+                                .with_click_message("Show Key Bindings")
+                                .on_click(|cx| cx.dispatch_action(ShowKeyBindings.boxed_clone()))
+                        })
+                    });
+                } else {
+                    workspace.dismiss_notification(&id, cx);
+                }
+            })
+            .log_err();
+    }
+}
+
+struct SettingsError;
+
+/// The most problems with the user settings that get listed in their notification.
+const MAX_SHOWN_SETTINGS_PROBLEMS: usize = 5;
+
+/// Shows the reason the user settings file couldn't be applied in every open workspace, or the
+/// problems in it that kept some of the settings from applying. Dismisses that notification once
+/// the file is fine again.
+pub fn handle_settings_changed(error: Option<anyhow::Error>, cx: &mut AppContext) {
+    let message = match error {
+        Some(error) => {
+            log::error!("{error:?}");
+            Some(SharedString::from(format!(
+                "Error in your settings, keeping the previous ones: {error:#}"
+            )))
+        }
+        None => settings_problems_message(cx),
+    };
+    for window in cx.windows() {
+        let Some(workspace) = window.downcast::<Workspace>() else {
+            continue;
+        };
+        workspace
+            .update(cx, |workspace, cx| {
+                show_settings_notification(workspace, message.clone(), cx)
+            })
+            .log_err();
+    }
+}
+
+fn show_settings_notification(
+    workspace: &mut Workspace,
+    message: Option<SharedString>,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let id = NotificationId::unique::<SettingsError>();
+    if let Some(message) = message {
+        workspace.show_notification(id, cx, |cx| {
+            cx.new_view(|_| {
+                MessageNotification::new(message)
+                    .with_click_message("Open Settings")
+                    .on_click(|cx| cx.dispatch_action(OpenSettings.boxed_clone()))
+            })
+        });
+    } else {
+        workspace.dismiss_notification(&id, cx);
+    }
+}
+
+/// Lists the problems with the user settings, if there are any.
+fn settings_problems_message(cx: &AppContext) -> Option<SharedString> {
+    let problems = SettingsStore::global(cx).user_settings_problems(cx);
+    if problems.is_empty() {
+        return None;
+    }
+    for problem in &problems {
+        log::warn!("{problem} in the user settings");
+    }
+
+    let mut message = String::from("Some of your settings are ignored:");
+    for problem in problems.iter().take(MAX_SHOWN_SETTINGS_PROBLEMS) {
+        message.push_str("\n• ");
+        message.push_str(&problem.to_string());
+    }
+    if problems.len() > MAX_SHOWN_SETTINGS_PROBLEMS {
+        message.push_str(&format!(
+            "\n…and {} more",
+            problems.len() - MAX_SHOWN_SETTINGS_PROBLEMS
+        ));
+    }
+    Some(message.into())
+}
+
 pub fn load_default_keymap(cx: &mut AppContext) {
     let base_keymap = *BaseKeymap::get_global(cx);
     if base_keymap == BaseKeymap::None {
@@ -840,6 +1080,31 @@ fn open_telemetry_log_file(workspace: &mut Workspace, cx: &mut ViewContext<Works
     }).detach();
 }
 
+fn open_startup_timings(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) {
+    let mut report = timings::global().report();
+    if report.is_empty() {
+        report = "No timings were recorded.\n".to_string();
+    }
+    workspace
+        .with_local_workspace(cx, move |workspace, cx| {
+            let project = workspace.project().clone();
+            let buffer = project.update(cx, |project, cx| {
+                project.create_local_buffer(&report, None, cx)
+            });
+            let buffer = cx.new_model(|cx| {
+                MultiBuffer::singleton(buffer, cx).with_title("Startup Timings".into())
+            });
+            workspace.add_item_to_active_pane(
+                Box::new(
+                    cx.new_view(|cx| Editor::for_multibuffer(buffer, Some(project), true, cx)),
+                ),
+                None,
+                cx,
+            );
+        })
+        .detach_and_log_err(cx);
+}
+
 fn open_bundled_file(
     workspace: &mut Workspace,
     text: Cow<'static, str>,
@@ -2033,6 +2298,59 @@ mod tests {
         })
     }
 
+    #[gpui::test]
+    async fn test_untitled_editor_titles(cx: &mut TestAppContext) {
+        let app_state = init_test(cx);
+        app_state.fs.create_dir(Path::new("/root")).await.unwrap();
+
+        let project = Project::test(app_state.fs.clone(), ["/root".as_ref()], cx).await;
+        let window = cx.add_window(|cx| Workspace::test_new(project, cx));
+        let active_editor = |cx: &mut TestAppContext| {
+            window
+                .read_with(cx, |workspace, cx| {
+                    workspace
+                        .active_item(cx)
+                        .unwrap()
+                        .downcast::<Editor>()
+                        .unwrap()
+                })
+                .unwrap()
+        };
+
+        let mut editors = Vec::new();
+        for _ in 0..3 {
+            cx.dispatch_action(window.into(), NewFile);
+            editors.push(active_editor(cx));
+        }
+        cx.read(|cx| {
+            assert_eq!(
+                editors
+                    .iter()
+                    .map(|editor| editor.read(cx).title(cx).to_string())
+                    .collect::<Vec<_>>(),
+                ["untitled", "untitled-2", "untitled-3"]
+            );
+        });
+
+        // The numbers of closed editors get reused.
+        let pane = window
+            .read_with(cx, |workspace, _| workspace.active_pane().clone())
+            .unwrap();
+        let editor2_id = editors[1].entity_id();
+        window
+            .update(cx, |_, cx| {
+                pane.update(cx, |pane, cx| {
+                    pane.close_item_by_id(editor2_id, SaveIntent::Skip, cx)
+                })
+            })
+            .unwrap()
+            .await
+            .unwrap();
+        cx.dispatch_action(window.into(), NewFile);
+        let editor = active_editor(cx);
+        cx.read(|cx| assert_eq!(editor.read(cx).title(cx), "untitled-2"));
+    }
+
     #[gpui::test]
     async fn test_setting_language_when_saving_as_single_file_worktree(cx: &mut TestAppContext) {
         let app_state = init_test(cx);
@@ -2389,7 +2707,8 @@ mod tests {
             (file3.clone(), DisplayPoint::new(DisplayRow(0), 0), 0.)
         );
 
-        // Go back to an item that has been closed and removed from disk
+        // Go back past an item that has been closed and removed from disk, ensuring it gets
+        // skipped rather than re-opened.
         workspace
             .update(cx, |_, cx| {
                 pane.update(cx, |pane, cx| {
@@ -2415,7 +2734,7 @@ mod tests {
             .unwrap();
         assert_eq!(
             active_location(&workspace, cx),
-            (file2.clone(), DisplayPoint::new(DisplayRow(0), 0), 0.)
+            (file1.clone(), DisplayPoint::new(DisplayRow(10), 0), 0.)
         );
         workspace
             .update(cx, |w, cx| w.go_forward(w.active_pane().downgrade(), cx))
@@ -2755,6 +3074,101 @@ mod tests {
         }
     }
 
+    #[gpui::test]
+    async fn test_reopening_closed_items_across_panes(cx: &mut TestAppContext) {
+        let app_state = init_test(cx);
+        app_state
+            .fs
+            .as_fake()
+            .insert_tree(
+                "/root",
+                json!({
+                    "a": {
+                        "file1": "",
+                        "file2": "",
+                        "file3": "",
+                    },
+                }),
+            )
+            .await;
+
+        let project = Project::test(app_state.fs.clone(), ["/root".as_ref()], cx).await;
+        let workspace = cx.add_window(|cx| Workspace::test_new(project, cx));
+        let pane1 = workspace
+            .read_with(cx, |workspace, _| workspace.active_pane().clone())
+            .unwrap();
+        let pane2 = workspace
+            .update(cx, |workspace, cx| {
+                workspace.split_pane(pane1.clone(), SplitDirection::Right, cx)
+            })
+            .unwrap();
+
+        let entries = cx.update(|cx| workspace.root(cx).unwrap().file_project_paths(cx));
+        let file1 = entries[0].clone();
+        let file2 = entries[1].clone();
+        let file3 = entries[2].clone();
+
+        let open = |file: &ProjectPath, pane: &View<Pane>, cx: &mut TestAppContext| {
+            workspace
+                .update(cx, |workspace, cx| {
+                    workspace.open_path(file.clone(), Some(pane.downgrade()), true, cx)
+                })
+                .unwrap()
+        };
+        open(&file1, &pane1, cx).await.unwrap();
+        let file3_item_id = open(&file3, &pane1, cx).await.unwrap().item_id();
+        let file2_item_id = open(&file2, &pane2, cx).await.unwrap().item_id();
+
+        // An item is reopened in the pane it was closed in, even if another pane is active.
+        workspace
+            .update(cx, |_, cx| {
+                pane1.update(cx, |pane, cx| {
+                    pane.close_item_by_id(file3_item_id, SaveIntent::Close, cx)
+                })
+            })
+            .unwrap()
+            .await
+            .unwrap();
+        workspace
+            .update(cx, |_, cx| pane2.update(cx, |pane, cx| pane.focus(cx)))
+            .unwrap();
+        workspace
+            .update(cx, Workspace::reopen_closed_item)
+            .unwrap()
+            .await
+            .unwrap();
+        cx.read(|cx| {
+            let pane = pane1.read(cx);
+            assert_eq!(pane.active_item().unwrap().project_path(cx), Some(file3));
+        });
+
+        // The items closed in a pane that got closed as well are reopened in another pane.
+        workspace
+            .update(cx, |_, cx| {
+                pane2.update(cx, |pane, cx| {
+                    pane.close_item_by_id(file2_item_id, SaveIntent::Close, cx)
+                })
+            })
+            .unwrap()
+            .await
+            .unwrap();
+        workspace
+            .read_with(cx, |workspace, _| {
+                assert_eq!(workspace.panes(), &[pane1.clone()])
+            })
+            .unwrap();
+        workspace
+            .update(cx, Workspace::reopen_closed_item)
+            .unwrap()
+            .await
+            .unwrap();
+        cx.read(|cx| {
+            let pane = pane1.read(cx);
+            assert_eq!(pane.active_item().unwrap().project_path(cx), Some(file2));
+            assert_eq!(pane.items_len(), 3);
+        });
+    }
+
     fn init_keymap_test(cx: &mut TestAppContext) -> Arc<AppState> {
         cx.update(|cx| {
             let app_state = AppState::test(cx);
@@ -2827,7 +3241,7 @@ mod tests {
                 app_state.fs.clone(),
                 PathBuf::from("/keymap.json"),
             );
-            handle_settings_file_changes(settings_rx, cx);
+            handle_settings_file_changes(settings_rx, cx, |_, _| {});
             handle_keymap_file_changes(keymap_rx, cx);
         });
         workspace
@@ -2967,7 +3381,7 @@ mod tests {
                 PathBuf::from("/keymap.json"),
             );
 
-            handle_settings_file_changes(settings_rx, cx);
+            handle_settings_file_changes(settings_rx, cx, |_, _| {});
             handle_keymap_file_changes(keymap_rx, cx);
         });
 
@@ -1,17 +1,25 @@
+use std::{fs, path::PathBuf, process, time::Duration};
+#[cfg(not(unix))]
 use std::{
-    io::{Read, Write},
-    net::{Ipv4Addr, SocketAddr, SocketAddrV4, TcpListener, TcpStream},
+    io::{BufRead, BufReader, Read, Write},
+    net::{Ipv4Addr, Shutdown, SocketAddr, SocketAddrV4, TcpListener, TcpStream},
     thread,
-    time::Duration,
 };
 
-use release_channel::ReleaseChannel;
+use release_channel::{ReleaseChannel, RELEASE_CHANNEL_NAME};
+use util::{paths, ResultExt};
 
+use super::OpenListener;
+
+#[cfg(not(unix))]
 const LOCALHOST: Ipv4Addr = Ipv4Addr::new(127, 0, 0, 1);
+#[cfg(not(unix))]
 const CONNECT_TIMEOUT: Duration = Duration::from_millis(10);
 const RECEIVE_TIMEOUT: Duration = Duration::from_millis(35);
+#[cfg(not(unix))]
 const SEND_TIMEOUT: Duration = Duration::from_millis(20);
 
+#[cfg(not(unix))]
 fn address() -> SocketAddr {
     let port = match *release_channel::RELEASE_CHANNEL {
         ReleaseChannel::Dev => 43737,
@@ -23,6 +31,7 @@ fn address() -> SocketAddr {
     SocketAddr::V4(SocketAddrV4::new(LOCALHOST, port))
 }
 
+#[cfg(not(unix))]
 fn instance_handshake() -> &'static str {
     match *release_channel::RELEASE_CHANNEL {
         ReleaseChannel::Dev => "Zed Editor Dev Instance Running",
@@ -38,31 +47,160 @@ pub enum IsOnlyInstance {
     No,
 }
 
-pub fn ensure_only_instance() -> IsOnlyInstance {
-    if *db::ZED_STATELESS || *release_channel::RELEASE_CHANNEL == ReleaseChannel::Dev {
+/// Makes sure that only one Zed per user and release channel is running.
+///
+/// If another instance already runs, `urls` are forwarded to it, so that it opens them
+/// (or raises one of its windows when there are none) and this process can exit. Otherwise,
+/// this process takes the instance lock and starts listening for such forwarded requests,
+/// until [`release_instance_lock`] is called when it quits.
+///
+/// Dev builds can run side by side, so they only listen when nothing else does.
+pub fn ensure_only_instance(opener: OpenListener, urls: &[String]) -> IsOnlyInstance {
+    if *db::ZED_STATELESS {
+        return IsOnlyInstance::Yes;
+    }
+    if *release_channel::RELEASE_CHANNEL == ReleaseChannel::Dev {
+        // On Linux, that's how the CLI reaches the instance.
+        listen_for_forwarded_requests(opener);
         return IsOnlyInstance::Yes;
     }
 
-    if check_got_handshake() {
-        return IsOnlyInstance::No;
+    if let Some(pid) = running_instance_pid() {
+        if forward_to_running_instance(urls) {
+            return IsOnlyInstance::No;
+        }
+        // The process holding the lock doesn't answer, so it is most likely an unrelated
+        // process that reused the PID of a crashed instance.
+        log::warn!("Instance {pid} holding the lock did not respond, reclaiming the lock");
+    }
+
+    if !listen_for_forwarded_requests(opener) {
+        // Another instance started right before us and got to listen first.
+        if forward_to_running_instance(urls) {
+            return IsOnlyInstance::No;
+        }
+        // Avoid failing to start when some other application by chance already has
+        // a claim on the socket. This is sub-par as any other instance that gets launched
+        // will be unable to communicate with this instance and will duplicate
+        log::warn!("Failed to reach the instance listening for requests, continuing without it");
+    }
+
+    if let Err(err) = fs::write(lock_path(), process::id().to_string()) {
+        log::warn!("Error writing the instance lock: {err}");
+    }
+
+    IsOnlyInstance::Yes
+}
+
+/// Removes the instance lock and stops listening for forwarded requests, if this process
+/// holds them, so that the next launch doesn't have to find out that they're stale.
+pub fn release_instance_lock() {
+    #[cfg(unix)]
+    super::stop_listening_for_cli_connections();
+    #[cfg(not(unix))]
+    if let Some(token) = INSTANCE_TOKEN.get() {
+        if fs::read_to_string(token_path()).ok().as_ref() == Some(token) {
+            fs::remove_file(token_path()).log_err();
+        }
+    }
+
+    let pid = fs::read_to_string(lock_path()).ok();
+    if pid.as_deref().map(str::trim) == Some(process::id().to_string().as_str()) {
+        fs::remove_file(lock_path()).log_err();
+    }
+}
+
+fn lock_path() -> PathBuf {
+    paths::SUPPORT_DIR.join(format!("zed-{}.pid", *RELEASE_CHANNEL_NAME))
+}
+
+/// Returns the PID stored in the instance lock, unless the lock is stale.
+fn running_instance_pid() -> Option<u32> {
+    let pid = fs::read_to_string(lock_path())
+        .ok()?
+        .trim()
+        .parse::<u32>()
+        .ok()?;
+    if pid != process::id() && is_process_running(pid) {
+        Some(pid)
+    } else {
+        log::info!("Reclaiming the stale instance lock of process {pid}");
+        None
+    }
+}
+
+#[cfg(unix)]
+fn is_process_running(pid: u32) -> bool {
+    // Sending no signal only checks whether the process exists. A process owned by
+    // another user still exists, but it can't be a Zed that shares our lock file.
+    unsafe { libc::kill(pid as libc::pid_t, 0) == 0 }
+}
+
+#[cfg(not(unix))]
+fn is_process_running(_pid: u32) -> bool {
+    // Whether the process is still around is found out when forwarding the request to it.
+    true
+}
+
+/// The CLI socket lives in the user's support directory and only the user can write to
+/// it, so requests can't come from anyone else.
+#[cfg(unix)]
+fn listen_for_forwarded_requests(opener: OpenListener) -> bool {
+    super::listen_for_cli_connections(opener).is_ok()
+}
+
+#[cfg(unix)]
+fn forward_to_running_instance(urls: &[String]) -> bool {
+    use std::os::unix::net::UnixDatagram;
+
+    let forward = || -> std::io::Result<()> {
+        let socket = UnixDatagram::unbound()?;
+        socket.set_write_timeout(Some(RECEIVE_TIMEOUT))?;
+        socket.connect(super::cli_socket_path())?;
+        if urls.is_empty() {
+            socket.send(&[])?;
+        }
+        for url in urls {
+            socket.send(url.as_bytes())?;
+        }
+        Ok(())
+    };
+
+    match forward() {
+        Ok(()) => true,
+        Err(err) => {
+            log::warn!("Error forwarding open request to the running instance: {err}");
+            false
+        }
     }
+}
+
+/// The secret that connecting instances have to send before their requests are accepted,
+/// as any local user can connect to the port. It's stored where only the user can read it.
+#[cfg(not(unix))]
+static INSTANCE_TOKEN: std::sync::OnceLock<String> = std::sync::OnceLock::new();
+
+#[cfg(not(unix))]
+fn token_path() -> PathBuf {
+    paths::SUPPORT_DIR.join(format!("zed-{}.token", *RELEASE_CHANNEL_NAME))
+}
 
+#[cfg(not(unix))]
+fn listen_for_forwarded_requests(opener: OpenListener) -> bool {
     let listener = match TcpListener::bind(address()) {
         Ok(listener) => listener,
-
         Err(err) => {
             log::warn!("Error binding to single instance port: {err}");
-            if check_got_handshake() {
-                return IsOnlyInstance::No;
-            }
-
-            // Avoid failing to start when some other application by chance already has
-            // a claim on the port. This is sub-par as any other instance that gets launched
-            // will be unable to communicate with this instance and will duplicate
-            log::warn!("Backup handshake request failed, continuing without handshake");
-            return IsOnlyInstance::Yes;
+            return false;
         }
     };
+    let token = INSTANCE_TOKEN
+        .get_or_init(|| uuid::Uuid::new_v4().to_string())
+        .clone();
+    if let Err(err) = fs::write(token_path(), &token) {
+        log::warn!("Error writing the instance token: {err}");
+        return false;
+    }
 
     thread::spawn(move || {
         for stream in listener.incoming() {
@@ -73,33 +211,64 @@ pub fn ensure_only_instance() -> IsOnlyInstance {
 
             _ = stream.set_nodelay(true);
             _ = stream.set_read_timeout(Some(SEND_TIMEOUT));
-            _ = stream.write_all(instance_handshake().as_bytes());
+            if stream.write_all(instance_handshake().as_bytes()).is_err() {
+                continue;
+            }
+
+            // The connecting instance sends the token and then its URLs, one per line,
+            // and closes the stream.
+            let mut lines = BufReader::new(stream).lines().map_while(Result::ok);
+            if lines.next().as_deref() != Some(token.as_str()) {
+                log::warn!("Ignoring an open request without the instance token");
+                continue;
+            }
+            let urls = lines.filter(|line| !line.is_empty()).collect::<Vec<_>>();
+            opener.open_urls(urls);
         }
     });
 
-    IsOnlyInstance::Yes
+    true
 }
 
-fn check_got_handshake() -> bool {
-    match TcpStream::connect_timeout(&address(), CONNECT_TIMEOUT) {
-        Ok(mut stream) => {
-            let mut buf = vec![0u8; instance_handshake().len()];
+#[cfg(not(unix))]
+fn forward_to_running_instance(urls: &[String]) -> bool {
+    let Ok(token) = fs::read_to_string(token_path()) else {
+        return false;
+    };
+    let Some(mut stream) = connect_with_handshake() else {
+        return false;
+    };
 
-            stream.set_read_timeout(Some(RECEIVE_TIMEOUT)).unwrap();
-            if let Err(err) = stream.read_exact(&mut buf) {
-                log::warn!("Connected to single instance port but failed to read: {err}");
-                return false;
-            }
+    let mut message = token;
+    for url in urls {
+        message.push('\n');
+        message.push_str(url);
+    }
+    message.push('\n');
+    if let Err(err) = stream.write_all(message.as_bytes()) {
+        log::warn!("Error forwarding open request to the running instance: {err}");
+        return false;
+    }
+    _ = stream.shutdown(Shutdown::Write);
+    true
+}
 
-            if buf == instance_handshake().as_bytes() {
-                log::info!("Got instance handshake");
-                return true;
-            }
+#[cfg(not(unix))]
+fn connect_with_handshake() -> Option<TcpStream> {
+    let mut stream = TcpStream::connect_timeout(&address(), CONNECT_TIMEOUT).ok()?;
+    let mut buf = vec![0u8; instance_handshake().len()];
 
-            log::warn!("Got wrong instance handshake value");
-            false
-        }
+    stream.set_read_timeout(Some(RECEIVE_TIMEOUT)).ok()?;
+    if let Err(err) = stream.read_exact(&mut buf) {
+        log::warn!("Connected to single instance port but failed to read: {err}");
+        return None;
+    }
 
-        Err(_) => false,
+    if buf == instance_handshake().as_bytes() {
+        log::info!("Got instance handshake");
+        return Some(stream);
     }
+
+    log::warn!("Got wrong instance handshake value");
+    None
 }
@@ -4,22 +4,22 @@ use cli::{ipc::IpcSender, CliRequest, CliResponse};
 use client::parse_zed_link;
 use collections::HashMap;
 use db::kvp::KEY_VALUE_STORE;
-use editor::scroll::Autoscroll;
 use editor::Editor;
 use futures::channel::mpsc::{UnboundedReceiver, UnboundedSender};
 use futures::channel::{mpsc, oneshot};
 use futures::{FutureExt, SinkExt, StreamExt};
 use gpui::{AppContext, AsyncAppContext, Global, WindowHandle};
-use language::{Bias, Point};
+use language::Point;
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 use std::{process, thread};
 use util::paths::PathLikeWithPosition;
 use util::ResultExt;
 use welcome::{show_welcome_view, FIRST_OPEN};
-use workspace::item::ItemHandle;
+use workspace::item::{ItemHandle, PointOrAnchor};
 use workspace::{AppState, Workspace};
 
 use crate::{init_headless, init_ui};
@@ -109,29 +109,57 @@ impl OpenListener {
     }
 }
 
-#[cfg(target_os = "linux")]
-pub fn listen_for_cli_connections(opener: OpenListener) -> Result<()> {
+#[cfg(unix)]
+pub(crate) fn cli_socket_path() -> PathBuf {
     use release_channel::RELEASE_CHANNEL_NAME;
-    use std::os::unix::net::UnixDatagram;
     use util::paths;
 
-    let sock_path = paths::SUPPORT_DIR.join(format!("zed-{}.sock", *RELEASE_CHANNEL_NAME));
+    paths::SUPPORT_DIR.join(format!("zed-{}.sock", *RELEASE_CHANNEL_NAME))
+}
+
+/// Whether this process bound the CLI socket, and so has to remove it when it quits.
+#[cfg(unix)]
+static LISTENING_FOR_CLI_CONNECTIONS: AtomicBool = AtomicBool::new(false);
+
+#[cfg(unix)]
+pub fn listen_for_cli_connections(opener: OpenListener) -> Result<()> {
+    use std::os::unix::{fs::FileTypeExt, fs::PermissionsExt, net::UnixDatagram};
+
+    let sock_path = cli_socket_path();
     // remove the socket if the process listening on it has died
     if let Err(e) = UnixDatagram::unbound()?.connect(&sock_path) {
-        if e.kind() == std::io::ErrorKind::ConnectionRefused {
+        let is_socket = std::fs::symlink_metadata(&sock_path)
+            .map_or(false, |metadata| metadata.file_type().is_socket());
+        if e.kind() == std::io::ErrorKind::ConnectionRefused && is_socket {
             std::fs::remove_file(&sock_path)?;
         }
     }
     let listener = UnixDatagram::bind(&sock_path)?;
+    LISTENING_FOR_CLI_CONNECTIONS.store(true, Ordering::SeqCst);
+    // Only the user running Zed gets to send it requests.
+    std::fs::set_permissions(&sock_path, std::fs::Permissions::from_mode(0o600))?;
     thread::spawn(move || {
         let mut buf = [0u8; 1024];
         while let Ok(len) = listener.recv(&mut buf) {
-            opener.open_urls(vec![String::from_utf8_lossy(&buf[..len]).to_string()]);
+            // An empty message asks to raise an existing window.
+            if len == 0 {
+                opener.open_urls(Vec::new());
+            } else {
+                opener.open_urls(vec![String::from_utf8_lossy(&buf[..len]).to_string()]);
+            }
         }
     });
     Ok(())
 }
 
+/// Removes the CLI socket, if this process is the one listening on it.
+#[cfg(unix)]
+pub fn stop_listening_for_cli_connections() {
+    if LISTENING_FOR_CLI_CONNECTIONS.swap(false, Ordering::SeqCst) {
+        std::fs::remove_file(cli_socket_path()).log_err();
+    }
+}
+
 fn connect_to_cli(
     server_name: &str,
 ) -> Result<(mpsc::Receiver<CliRequest>, IpcSender<CliResponse>)> {
@@ -198,19 +226,11 @@ pub async fn open_paths_with_positions(
         let Some(point) = caret_positions.remove(path) else {
             continue;
         };
-        if let Some(active_editor) = item.downcast::<Editor>() {
-            workspace
-                .update(cx, |_, cx| {
-                    active_editor.update(cx, |editor, cx| {
-                        let snapshot = editor.snapshot(cx).display_snapshot;
-                        let point = snapshot.buffer_snapshot.clip_point(point, Bias::Left);
-                        editor.change_selections(Some(Autoscroll::center()), cx, |s| {
-                            s.select_ranges([point..point])
-                        });
-                    });
-                })
-                .log_err();
-        }
+        workspace
+            .update(cx, |_, cx| {
+                item.move_cursor_to(PointOrAnchor::Point(point), cx)
+            })
+            .log_err();
     }
 
     Ok((workspace, items))
@@ -323,22 +343,50 @@ pub async fn handle_cli_connection(
                     {
                         Ok((workspace, items)) => {
                             let mut item_release_futures = Vec::new();
+                            let mut dirty_items = Vec::new();
+                            let mut item_subscriptions = Vec::new();
 
                             for (item, path) in items.into_iter().zip(&paths) {
                                 match item {
                                     Some(Ok(item)) => {
-                                        cx.update(|cx| {
-                                            let released = oneshot::channel();
-                                            item.on_release(
-                                                cx,
-                                                Box::new(move |_| {
-                                                    let _ = released.0.send(());
-                                                }),
-                                            )
-                                            .detach();
-                                            item_release_futures.push(released.1);
-                                        })
-                                        .log_err();
+                                        workspace
+                                            .update(&mut cx, |_, cx| {
+                                                let released = oneshot::channel();
+                                                item.on_release(
+                                                    cx,
+                                                    Box::new(move |_| {
+                                                        let _ = released.0.send(());
+                                                    }),
+                                                )
+                                                .detach();
+                                                item_release_futures.push(released.1);
+
+                                                // Remember whether the item has unsaved changes,
+                                                // to tell if they were discarded once it's closed.
+                                                let dirty =
+                                                    Arc::new(AtomicBool::new(item.is_dirty(cx)));
+                                                let weak_item = item.downgrade_item();
+                                                item_subscriptions.push(
+                                                    item.subscribe_to_item_events(
+                                                        cx,
+                                                        Box::new({
+                                                            let dirty = dirty.clone();
+                                                            move |_, cx| {
+                                                                if let Some(item) =
+                                                                    weak_item.upgrade()
+                                                                {
+                                                                    dirty.store(
+                                                                        item.is_dirty(cx),
+                                                                        Ordering::SeqCst,
+                                                                    );
+                                                                }
+                                                            }
+                                                        }),
+                                                    ),
+                                                );
+                                                dirty_items.push(dirty);
+                                            })
+                                            .log_err();
                                     }
                                     Some(Err(err)) => {
                                         responses
@@ -387,6 +435,19 @@ pub async fn handle_cli_connection(
                                         }
                                     }
                                 }
+
+                                // Like other editors, exit with an error when the files were
+                                // closed with unsaved changes, so that e.g. git aborts a commit
+                                // whose message was never saved.
+                                drop(item_subscriptions);
+                                if dirty_items.iter().any(|dirty| dirty.load(Ordering::SeqCst)) {
+                                    responses
+                                        .send(CliResponse::Stderr {
+                                            message: "closed with unsaved changes".into(),
+                                        })
+                                        .log_err();
+                                    errored = true;
+                                }
                             }
                         }
                         Err(error) => {